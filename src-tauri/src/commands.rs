@@ -6,58 +6,165 @@ use uuid::Uuid;
 use semver::Version;
 
 use crate::device::{DeviceManager, Device, ProfileConfig, ProfileManager};
+use crate::device::macros::MacroDefinition;
 use crate::serial::protocol::{DeviceStatus, AxisConfig, ButtonConfig};
-use crate::serial::StorageInfo;
+use crate::serial::{ConnectionSettings, StorageInfo};
 use crate::hid::ButtonStates;
 use crate::update::{UpdateService, VersionCheckResult};
 use crate::config::binary::{BinaryConfig, UIAxisConfig, UIButtonConfig};
 use crate::serial::unified::types::{CommandSpec, ResponseMatcher, SerialCommand};
+use crate::serial::unified::CommandPriority;
+use crate::error::{AppError, ErrorCode, ResultExt};
 
 /// Discover available JoyCore devices
 #[tauri::command]
 pub async fn discover_devices(
     device_manager: State<'_, Arc<DeviceManager>>,
-) -> Result<Vec<Device>, String> {
+) -> Result<Vec<Device>, AppError> {
     device_manager
         .discover_devices()
         .await
-        .map_err(|e| format!("Failed to discover devices: {}", e))
+        .context("Failed to discover devices")
 }
 
 /// Get all known devices
 #[tauri::command]
 pub async fn get_devices(
     device_manager: State<'_, Arc<DeviceManager>>,
-) -> Result<Vec<Device>, String> {
+) -> Result<Vec<Device>, AppError> {
     Ok(device_manager.get_devices().await)
 }
 
+/// Device list with the sequence number of the last device_added/updated/removed event it
+/// includes; the frontend reloads it when the event sequence has a gap
+#[tauri::command]
+pub async fn get_device_list_snapshot(
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<crate::device::list_events::DeviceListSnapshot, AppError> {
+    Ok(device_manager.get_device_list_snapshot().await)
+}
+
 // Legacy cleanup_disconnected_devices command removed: discovery events are authoritative
 
 /// Explicit force discover command (alias to discover for clarity in new event model)
 #[tauri::command]
 pub async fn force_discover_devices(
     device_manager: State<'_, Arc<DeviceManager>>,
-) -> Result<Vec<Device>, String> {
+) -> Result<Vec<Device>, AppError> {
     // Perform a short burst of discovery attempts to catch freshly attached devices that
     // appear a fraction of a second after user action (no continuous polling reintroduced).
-    let baseline = device_manager.get_devices().await;
+    let baseline: Vec<Device> = device_manager.get_devices().await.into_iter().filter(|d| d.present).collect();
     let mut attempts = 0;
-    let mut last = baseline.clone();
     while attempts < 3 {
         attempts += 1;
         match device_manager.discover_devices().await {
             Ok(list) => {
                 // If device count changed or any new port appears, break early
                 let changed = list.len() != baseline.len() || list.iter().any(|d| !baseline.iter().any(|b| b.id == d.id));
-                last = list;
                 if changed { break; }
             }
-            Err(e) => return Err(format!("Failed to force discover devices: {}", e)),
+            Err(e) => return Err(AppError::from(e).with_context("Failed to force discover devices")),
         }
         if attempts < 3 { tokio::time::sleep(std::time::Duration::from_millis(180)).await; }
     }
-    Ok(last)
+    // Present devices plus the known ones that are unplugged, like get_device_list_snapshot
+    Ok(device_manager.get_devices().await)
+}
+
+/// List serial ports quarantined after repeated IDENTIFY failures
+#[tauri::command]
+pub async fn get_quarantined_ports(
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<Vec<crate::device::quarantine::QuarantinedPort>, AppError> {
+    Ok(device_manager.get_quarantined_ports().await)
+}
+
+/// Every device seen so far (including unplugged ones), most recently seen first
+#[tauri::command]
+pub async fn get_known_devices(
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<Vec<crate::device::known_devices::KnownDevice>, AppError> {
+    Ok(device_manager.get_known_devices().await)
+}
+
+/// Remove a device from the known-device registry; its config backups are kept
+#[tauri::command]
+pub async fn forget_known_device(
+    serial_number: String,
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<bool, AppError> {
+    Ok(device_manager.forget_known_device(&serial_number).await)
+}
+
+/// User-chosen name of the device with this serial number
+#[tauri::command]
+pub async fn get_device_alias(
+    serial_number: String,
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<Option<String>, AppError> {
+    Ok(device_manager.get_device_alias(&serial_number).await)
+}
+
+/// Serial connection overrides (baud rate, timeouts) of a known device
+#[tauri::command]
+pub async fn get_device_connection_settings(
+    serial_number: String,
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<ConnectionSettings, AppError> {
+    device_manager.get_device_connection_settings(&serial_number).await
+        .context("Failed to get connection settings")
+}
+
+/// Store connection overrides for a known device; they apply from its next connection
+#[tauri::command]
+pub async fn set_device_connection_settings(
+    serial_number: String,
+    settings: ConnectionSettings,
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<ConnectionSettings, AppError> {
+    device_manager.set_device_connection_settings(&serial_number, settings).await
+        .context("Failed to set connection settings")
+}
+
+/// Connection overrides stored for a port; defaults when it has none
+#[tauri::command]
+pub async fn get_port_connection_settings(
+    port_name: String,
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<ConnectionSettings, AppError> {
+    Ok(device_manager.get_port_connection_settings(&port_name).await)
+}
+
+/// Store connection overrides for the device on a port that is not known yet (e.g. one that only
+/// answers discovery at another baud rate); they apply to the next discovery probe
+#[tauri::command]
+pub async fn set_port_connection_settings(
+    port_name: String,
+    settings: ConnectionSettings,
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<ConnectionSettings, AppError> {
+    device_manager.set_port_connection_settings(&port_name, settings).await
+        .context("Failed to set port connection settings")
+}
+
+/// Name the device with this serial number; an empty or missing alias removes the name
+#[tauri::command]
+pub async fn set_device_alias(
+    serial_number: String,
+    alias: Option<String>,
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<Option<String>, AppError> {
+    device_manager.set_device_alias(&serial_number, alias.as_deref()).await
+        .context("Failed to set device alias")
+}
+
+/// Release a quarantined port (or all ports when none is given) so discovery probes it again
+#[tauri::command]
+pub async fn clear_port_quarantine(
+    port_name: Option<String>,
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<usize, AppError> {
+    Ok(device_manager.clear_port_quarantine(port_name.as_deref()).await)
 }
 
 /// Connect to a specific device
@@ -65,32 +172,32 @@ pub async fn force_discover_devices(
 pub async fn connect_device(
     device_id: String,
     device_manager: State<'_, Arc<DeviceManager>>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let uuid = Uuid::parse_str(&device_id)
-        .map_err(|e| format!("Invalid device ID: {}", e))?;
+        .context("Invalid device ID")?;
     
     device_manager
         .connect_device(&uuid)
         .await
-        .map_err(|e| format!("Failed to connect to device: {}", e))
+        .context("Failed to connect to device")
 }
 
 /// Disconnect from the currently connected device
 #[tauri::command]
 pub async fn disconnect_device(
     device_manager: State<'_, Arc<DeviceManager>>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     device_manager
         .disconnect_device()
         .await
-        .map_err(|e| format!("Failed to disconnect device: {}", e))
+        .context("Failed to disconnect device")
 }
 
 /// Get the currently connected device
 #[tauri::command]
 pub async fn get_connected_device(
     device_manager: State<'_, Arc<DeviceManager>>,
-) -> Result<Option<Device>, String> {
+) -> Result<Option<Device>, AppError> {
     if let Some(device_id) = device_manager.get_connected_device_id().await {
         Ok(device_manager.get_device(&device_id).await)
     } else {
@@ -102,7 +209,7 @@ pub async fn get_connected_device(
 #[tauri::command]
 pub async fn get_device_status(
     device_manager: State<'_, Arc<DeviceManager>>,
-) -> Result<Option<DeviceStatus>, String> {
+) -> Result<Option<DeviceStatus>, AppError> {
     if let Some(device_id) = device_manager.get_connected_device_id().await {
         if let Some(device) = device_manager.get_device(&device_id).await {
             Ok(device.device_status)
@@ -119,11 +226,22 @@ pub async fn get_device_status(
 pub async fn read_axis_config(
     axis_id: u8,
     device_manager: State<'_, Arc<DeviceManager>>,
-) -> Result<AxisConfig, String> {
+) -> Result<AxisConfig, AppError> {
     device_manager
         .read_axis_config(axis_id)
         .await
-        .map_err(|e| format!("Failed to read axis config: {}", e))
+        .context("Failed to read axis config")
+}
+
+/// Read every axis configuration of the connected device in one call
+#[tauri::command]
+pub async fn read_all_axis_configs(
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<Vec<AxisConfig>, AppError> {
+    device_manager
+        .read_all_axis_configs()
+        .await
+        .context("Failed to read axis configs")
 }
 
 /// Write axis configuration to connected device
@@ -131,11 +249,11 @@ pub async fn read_axis_config(
 pub async fn write_axis_config(
     config: AxisConfig,
     device_manager: State<'_, Arc<DeviceManager>>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     device_manager
         .write_axis_config(&config)
         .await
-        .map_err(|e| format!("Failed to write axis config: {}", e))
+        .context("Failed to write axis config")
 }
 
 /// Read button configuration from connected device
@@ -143,11 +261,22 @@ pub async fn write_axis_config(
 pub async fn read_button_config(
     button_id: u8,
     device_manager: State<'_, Arc<DeviceManager>>,
-) -> Result<ButtonConfig, String> {
+) -> Result<ButtonConfig, AppError> {
     device_manager
         .read_button_config(button_id)
         .await
-        .map_err(|e| format!("Failed to read button config: {}", e))
+        .context("Failed to read button config")
+}
+
+/// Read every button configuration of the connected device in one call
+#[tauri::command]
+pub async fn read_all_button_configs(
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<Vec<ButtonConfig>, AppError> {
+    device_manager
+        .read_all_button_configs()
+        .await
+        .context("Failed to read button configs")
 }
 
 /// Write button configuration to connected device
@@ -155,40 +284,40 @@ pub async fn read_button_config(
 pub async fn write_button_config(
     config: ButtonConfig,
     device_manager: State<'_, Arc<DeviceManager>>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     device_manager
         .write_button_config(&config)
         .await
-        .map_err(|e| format!("Failed to write button config: {}", e))
+        .context("Failed to write button config")
 }
 
 /// Save configuration to connected device
 #[tauri::command]
 pub async fn save_device_config(
     device_manager: State<'_, Arc<DeviceManager>>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     device_manager
         .save_device_config()
         .await
-        .map_err(|e| format!("Failed to save device config: {}", e))
+        .context("Failed to save device config")
 }
 
 /// Load configuration from connected device
 #[tauri::command]
 pub async fn load_device_config(
     device_manager: State<'_, Arc<DeviceManager>>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     device_manager
         .load_device_config()
         .await
-        .map_err(|e| format!("Failed to load device config: {}", e))
+        .context("Failed to load device config")
 }
 
 /// Get all profiles
 #[tauri::command]
 pub async fn get_profiles(
     device_manager: State<'_, Arc<DeviceManager>>,
-) -> Result<ProfileManager, String> {
+) -> Result<ProfileManager, AppError> {
     Ok(device_manager.get_profile_manager().await)
 }
 
@@ -197,13 +326,13 @@ pub async fn get_profiles(
 pub async fn create_profile(
     profile: ProfileConfig,
     device_manager: State<'_, Arc<DeviceManager>>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     device_manager
         .update_profile_manager(|pm| {
             pm.add_profile(profile);
         })
         .await
-        .map_err(|e| format!("Failed to create profile: {}", e))
+        .context("Failed to create profile")
 }
 
 /// Update an existing profile
@@ -211,7 +340,7 @@ pub async fn create_profile(
 pub async fn update_profile(
     profile: ProfileConfig,
     device_manager: State<'_, Arc<DeviceManager>>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     device_manager
         .update_profile_manager(|pm| {
             if let Some(existing_profile) = pm.get_profile_mut(&profile.id) {
@@ -219,7 +348,7 @@ pub async fn update_profile(
             }
         })
         .await
-        .map_err(|e| format!("Failed to update profile: {}", e))
+        .context("Failed to update profile")
 }
 
 /// Delete a profile
@@ -227,14 +356,14 @@ pub async fn update_profile(
 pub async fn delete_profile(
     profile_id: String,
     device_manager: State<'_, Arc<DeviceManager>>,
-) -> Result<bool, String> {
+) -> Result<bool, AppError> {
     let mut removed = false;
     device_manager
         .update_profile_manager(|pm| {
             removed = pm.remove_profile(&profile_id);
         })
         .await
-        .map_err(|e| format!("Failed to delete profile: {}", e))?;
+        .context("Failed to delete profile")?;
     
     Ok(removed)
 }
@@ -244,91 +373,469 @@ pub async fn delete_profile(
 pub async fn set_active_profile(
     profile_id: String,
     device_manager: State<'_, Arc<DeviceManager>>,
-) -> Result<bool, String> {
+) -> Result<bool, AppError> {
     let mut success = false;
     device_manager
         .update_profile_manager(|pm| {
             success = pm.set_active_profile(&profile_id);
         })
         .await
-        .map_err(|e| format!("Failed to set active profile: {}", e))?;
+        .context("Failed to set active profile")?;
     
     Ok(success)
 }
 
+/// Write a profile's axis and button settings to the connected device (verified before SAVE_CONFIG)
+/// and make it the active profile
+#[tauri::command]
+pub async fn apply_profile_to_device(
+    profile_id: String,
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<crate::config::ProfileApplyResult, AppError> {
+    let profile = device_manager.get_profile_manager().await
+        .get_profile(&profile_id)
+        .cloned()
+        .ok_or_else(|| AppError::new(ErrorCode::NotFound, format!("Profile {} not found", profile_id)))?;
+    let raw_data = device_manager
+        .read_config_binary()
+        .await
+        .context("Failed to read config binary")?;
+    let current = BinaryConfig::from_bytes(&raw_data)
+        .map_err(AppError::invalid_configuration)
+        .context("Failed to parse config binary")?;
+    let mut config = current.clone();
+    let warnings = config.apply_profile(&profile)
+        .map_err(AppError::invalid_argument)
+        .context("Profile cannot be applied to this device")?;
+    let diff = crate::config::diff_configs(&current, &config);
+    if !diff.identical {
+        let data = config.to_bytes()
+            .map_err(AppError::invalid_configuration)
+            .context("Failed to serialize config")?;
+        device_manager
+            .write_config_binary(&data)
+            .await
+            .context("Failed to write config binary")?;
+    }
+    device_manager
+        .update_profile_manager(|pm| {
+            pm.set_active_profile(&profile_id);
+        })
+        .await
+        .context("Failed to set active profile")?;
+    Ok(crate::config::ProfileApplyResult { profile_id, diff, warnings })
+}
+
+/// Snapshot the connected device's configuration into a new profile
+#[tauri::command]
+pub async fn create_profile_from_device(
+    name: Option<String>,
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<ProfileConfig, AppError> {
+    let raw_data = device_manager
+        .read_config_binary()
+        .await
+        .context("Failed to read config binary")?;
+    let config = BinaryConfig::from_bytes(&raw_data)
+        .map_err(AppError::invalid_configuration)
+        .context("Failed to parse config binary")?;
+    let device = match device_manager.get_connected_device_id().await {
+        Some(id) => device_manager.get_device(&id).await,
+        None => None,
+    };
+    let description = match device.and_then(|d| d.serial_number) {
+        Some(serial) => format!("Captured from device {}", serial),
+        None => "Captured from device".to_string(),
+    };
+    let profile = config.to_profile(name.as_deref().unwrap_or("Device Snapshot"), &description);
+    let created = profile.clone();
+    device_manager
+        .update_profile_manager(|pm| {
+            pm.add_profile(profile);
+        })
+        .await
+        .context("Failed to create profile")?;
+    Ok(created)
+}
+
+// Macro commands
+
+/// Get all macro definitions
+#[tauri::command]
+pub async fn get_macros(
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<Vec<MacroDefinition>, AppError> {
+    Ok(device_manager.get_profile_manager().await.macros)
+}
+
+/// Problems that would prevent saving the macro (empty when valid)
+#[tauri::command]
+pub async fn validate_macro(
+    definition: MacroDefinition,
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<Vec<String>, AppError> {
+    Ok(definition.validate(&device_manager.get_profile_manager().await.macros))
+}
+
+/// Create a macro; a new id and timestamps are assigned
+#[tauri::command]
+pub async fn create_macro(
+    mut definition: MacroDefinition,
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<MacroDefinition, AppError> {
+    let now = chrono::Utc::now();
+    definition.id = Uuid::new_v4().to_string();
+    definition.created_at = now;
+    definition.modified_at = now;
+    save_macro(definition, false, &device_manager).await
+}
+
+/// Replace an existing macro
+#[tauri::command]
+pub async fn update_macro(
+    mut definition: MacroDefinition,
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<MacroDefinition, AppError> {
+    definition.modified_at = chrono::Utc::now();
+    save_macro(definition, true, &device_manager).await
+}
+
+async fn save_macro(definition: MacroDefinition, must_exist: bool, device_manager: &DeviceManager) -> Result<MacroDefinition, AppError> {
+    let mut outcome = Ok(());
+    device_manager
+        .update_profile_manager(|pm| {
+            if must_exist && pm.get_macro(&definition.id).is_none() {
+                outcome = Err(AppError::new(ErrorCode::NotFound, format!("Macro {} not found", definition.id)));
+                return;
+            }
+            let issues = definition.validate(&pm.macros);
+            if !issues.is_empty() {
+                outcome = Err(AppError::invalid_argument(issues.join("; ")));
+                return;
+            }
+            pm.upsert_macro(definition.clone());
+        })
+        .await
+        .context("Failed to save macro")?;
+    outcome.map(|_| definition)
+}
+
+/// Delete a macro
+#[tauri::command]
+pub async fn delete_macro(
+    macro_id: String,
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<bool, AppError> {
+    let mut removed = false;
+    device_manager
+        .update_profile_manager(|pm| {
+            removed = pm.remove_macro(&macro_id);
+        })
+        .await
+        .context("Failed to delete macro")?;
+    Ok(removed)
+}
+
 // Firmware update commands
 
-/// Check for firmware updates
+/// Check for firmware updates on the selected release channel
 #[tauri::command]
 pub async fn check_firmware_updates(
     current_version: String,
     repo_owner: String,
     repo_name: String,
-) -> Result<VersionCheckResult, String> {
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<VersionCheckResult, AppError> {
     let version = Version::parse(&current_version)
-        .map_err(|e| format!("Invalid current version: {}", e))?;
+        .context("Invalid current version")?;
+    let channel = device_manager.get_app_settings().await.firmware_update.channel;
     
-    let update_service = UpdateService::new(repo_owner, repo_name);
-    update_service
-        .check_for_updates(version)
+    let hardware_revision = device_manager.get_device_hardware_revision().await;
+    let update_service = update_service(&device_manager, repo_owner, repo_name).await?;
+    let result = update_service
+        .check_for_updates(version, channel, hardware_revision.as_deref())
         .await
-        .map_err(|e| format!("Failed to check for updates: {}", e))
+        .context("Failed to check for updates")?;
+    // A manual check restarts the background check interval
+    device_manager.record_update_check(chrono::Utc::now()).await;
+    Ok(result)
+}
+
+/// Update service using the proxy and GitHub token from the app settings
+async fn update_service(device_manager: &DeviceManager, repo_owner: String, repo_name: String) -> Result<UpdateService, AppError> {
+    let options = device_manager.get_app_settings().await.firmware_update.client_options();
+    UpdateService::with_options(repo_owner, repo_name, &options)
+        .context("Invalid update network settings")
+}
+
+/// Store the GitHub token used by update checks (None removes it); returns whether one is stored.
+/// The token is kept out of the app settings and never sent back to the frontend.
+#[tauri::command]
+pub async fn set_github_token(
+    token: Option<String>,
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<bool, AppError> {
+    Ok(device_manager.set_github_token(token).await)
 }
 
-/// Download firmware update
+/// Whether a GitHub token is stored for update checks
+#[tauri::command]
+pub async fn has_github_token(
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<bool, AppError> {
+    Ok(device_manager.has_github_token().await)
+}
+
+/// Select the firmware release channel (stable / beta / nightly) used by update checks
+#[tauri::command]
+pub async fn set_update_channel(
+    channel: crate::update::UpdateChannel,
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<crate::device::AppSettings, AppError> {
+    let mut settings = device_manager.get_app_settings().await;
+    settings.firmware_update.channel = channel;
+    Ok(device_manager.set_app_settings(settings).await)
+}
+
+/// Download a release returned by `check_firmware_updates`; verified against the release
+/// checksum when one is available. Rebuilt from a cached earlier image when the release has a
+/// delta patch for it (see `update::delta`). Emits `download_progress`, `download_resumed` when an earlier
+/// partial download is continued, and `download_cancelled` after `cancel_firmware_download`.
 #[tauri::command]
 pub async fn download_firmware_update(
-    download_url: String,
-    version: String,
-    changelog: String,
-    published_at: String,
-    size_bytes: u64,
+    release: crate::update::models::FirmwareRelease,
     output_dir: String,
     app_handle: tauri::AppHandle,
-) -> Result<String, String> {
-    use crate::update::models::FirmwareRelease;
-    
-    let version_parsed = Version::parse(&version)
-        .map_err(|e| format!("Invalid version: {}", e))?;
-    
-    let published_at_parsed = chrono::DateTime::parse_from_rfc3339(&published_at)
-        .map_err(|e| format!("Invalid date: {}", e))?
-        .with_timezone(&chrono::Utc);
-    
-    let release = FirmwareRelease {
-        version: version_parsed.clone(),
-        download_url,
-        changelog,
-        published_at: published_at_parsed,
-        size_bytes,
-        sha256_hash: None,
-    };
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<String, AppError> {
+    let version_parsed = release.version.clone();
     
     let output_path = PathBuf::from(&output_dir).join(format!("firmware-{}.uf2", version_parsed));
-    let update_service = UpdateService::new("gingerskull".to_string(), "JoyCore-FW".to_string());
+    let update_service = update_service(&device_manager, "gingerskull".to_string(), "JoyCore-FW".to_string()).await?;
     
-    update_service
-        .download_firmware(&release, &output_path, |progress| {
-            // Emit progress events to frontend
+    let version_key = version_parsed.to_string();
+    let cancel = crate::update::active_downloads().register(&version_key);
+    // A delta patch against a cached image saves most of the download; anything short of a
+    // verified image falls back to the full download
+    let rebuilt = match firmware_cache(&app_handle) {
+        Ok(cache) => match update_service.download_firmware_delta(&release, &cache, &output_path, &cancel, |progress| {
             let _ = app_handle.emit("download_progress", &progress);
-        })
-        .await
-        .map_err(|e| format!("Failed to download firmware: {}", e))?;
+        }).await {
+            Ok(rebuilt) => Ok(rebuilt),
+            Err(crate::update::UpdateError::Cancelled) => Err(crate::update::UpdateError::Cancelled),
+            Err(e) => {
+                log::warn!("Delta update of firmware {} failed, downloading the full image: {}", version_parsed, e);
+                Ok(false)
+            }
+        },
+        Err(_) => Ok(false),
+    };
+    let result = match rebuilt {
+        Ok(true) => Ok(()),
+        Ok(false) => update_service
+            .download_firmware(&release, &output_path, &cancel, |progress| {
+                // Emit progress events to frontend
+                let _ = app_handle.emit("download_progress", &progress);
+            }, |resumed| {
+                let _ = app_handle.emit("download_resumed", &resumed);
+            })
+            .await,
+        Err(e) => Err(e),
+    };
+    crate::update::active_downloads().finish(&version_key, &cancel);
+    if let Err(crate::update::UpdateError::Cancelled) = &result {
+        let downloaded_bytes = std::fs::metadata(crate::update::download::partial_path(&output_path))
+            .map(|m| m.len())
+            .unwrap_or(0);
+        let _ = app_handle.emit("download_cancelled", &crate::update::DownloadCancelled {
+            version: version_parsed.clone(),
+            downloaded_bytes,
+        });
+    }
+    result.context("Failed to download firmware")?;
+    
+    // Keep a copy for rollbacks; the download itself succeeded either way
+    match firmware_cache(&app_handle) {
+        Ok(cache) => {
+            if let Err(e) = cache.store(&version_parsed, &output_path) {
+                log::warn!("Failed to cache firmware {}: {}", version_parsed, e);
+            }
+        }
+        Err(e) => log::warn!("Firmware not cached: {}", e),
+    }
     
     Ok(output_path.to_string_lossy().to_string())
 }
 
-/// Get all available firmware versions
+/// Cancel a running firmware download, or all of them when `version` is omitted. The partial file
+/// is kept, so downloading the same release again resumes it. Returns the cancelled versions.
+#[tauri::command]
+pub async fn cancel_firmware_download(
+    version: Option<String>,
+) -> Result<Vec<String>, AppError> {
+    let version = match version {
+        Some(v) => Some(Version::parse(&v).context("Invalid version")?.to_string()),
+        None => None,
+    };
+    let cancelled = crate::update::active_downloads().cancel(version.as_deref());
+    log::info!("Cancelled firmware downloads: {:?}", cancelled);
+    Ok(cancelled)
+}
+
+fn firmware_cache(app_handle: &tauri::AppHandle) -> Result<crate::update::FirmwareCache, AppError> {
+    use tauri::Manager;
+    app_handle
+        .path()
+        .app_data_dir()
+        .map(|dir| crate::update::FirmwareCache::in_dir(&dir))
+        .context("No app data directory")
+}
+
+/// Milliseconds since the connected device booted
+#[tauri::command]
+pub async fn get_device_uptime(
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<u64, AppError> {
+    device_manager
+        .get_device_uptime()
+        .await
+        .context("Failed to read device uptime")
+}
+
+/// Restart the connected device; it goes Disconnected and is reconnected when auto-reconnect is on
+#[tauri::command]
+pub async fn reboot_device(
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<(), AppError> {
+    device_manager
+        .reboot_device()
+        .await
+        .context("Failed to reboot device")
+}
+
+/// Reboot a device (default: the connected one) into the UF2 bootloader for flashing
+#[tauri::command]
+pub async fn enter_bootloader(
+    device_id: Option<String>,
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<(), AppError> {
+    let uuid = match device_id {
+        Some(id) => Uuid::parse_str(&id).context("Invalid device ID")?,
+        None => device_manager
+            .get_connected_device_id()
+            .await
+            .ok_or(crate::device::DeviceError::NotConnected)
+            .context("Failed to enter bootloader")?,
+    };
+    device_manager
+        .reboot_to_bootloader(&uuid)
+        .await
+        .context("Failed to enter bootloader")
+}
+
+/// Flash a UF2 firmware image. With a device id the device is rebooted into the bootloader first;
+/// without one the board must already be in BOOTSEL mode. Emits `flash_started`, `flash_progress`
+/// and `flash_complete`. When signature verification is enabled and a public key is available,
+/// images without a valid `<image>.sig` are refused unless `allow_unsigned` is set.
+#[tauri::command]
+pub async fn flash_firmware(
+    device_id: Option<String>,
+    file_path: String,
+    allow_unsigned: Option<bool>,
+    app_handle: tauri::AppHandle,
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<String, AppError> {
+    flash_image(device_id, PathBuf::from(&file_path), allow_unsigned.unwrap_or(false), &app_handle, &device_manager).await
+}
+
+/// Shared flashing pipeline of `flash_firmware` and `rollback_firmware`
+async fn flash_image(
+    device_id: Option<String>,
+    path: PathBuf,
+    allow_unsigned: bool,
+    app_handle: &tauri::AppHandle,
+    device_manager: &DeviceManager,
+) -> Result<String, AppError> {
+    use crate::flasher::{FlashComplete, FlashStarted};
+
+    let uuid = match &device_id {
+        Some(id) => Some(Uuid::parse_str(id).context("Invalid device ID")?),
+        None => None,
+    };
+    let image = crate::flasher::validate_uf2(&path)
+        .context("Failed to flash firmware")?;
+    let settings = device_manager.get_app_settings().await.firmware_update;
+    if let Some(public_key) = settings.flash_verification_key() {
+        let verification = crate::update::signature::verify_image(&path, None, Some(&public_key))
+            .context("Failed to verify firmware signature")?;
+        crate::update::signature::ensure_flashable(&verification, allow_unsigned)
+            .context("Refusing to flash firmware")?;
+    }
+    let started = std::time::Instant::now();
+    let _ = app_handle.emit("flash_started", &FlashStarted { device_id, image_path: path.clone(), size_bytes: image.size_bytes });
+
+    let result = device_manager
+        .flash_firmware(uuid.as_ref(), &path, crate::flasher::UF2_DRIVE_TIMEOUT_MS, |progress| {
+            let _ = app_handle.emit("flash_progress", &progress);
+        })
+        .await;
+
+    let complete = FlashComplete {
+        success: result.is_ok(),
+        error: result.as_ref().err().map(|e| e.to_string()),
+        drive_path: result.as_ref().ok().cloned(),
+        duration_ms: started.elapsed().as_millis() as u64,
+    };
+    let _ = app_handle.emit("flash_complete", &complete);
+
+    result
+        .map(|drive| drive.to_string_lossy().to_string())
+        .context("Failed to flash firmware")
+}
+
+/// List firmware images kept from earlier downloads, newest version first
+#[tauri::command]
+pub async fn list_cached_firmware(
+    app_handle: tauri::AppHandle,
+) -> Result<Vec<crate::update::CachedFirmware>, AppError> {
+    firmware_cache(&app_handle)?
+        .list()
+        .context("Failed to list cached firmware")
+}
+
+/// Flash a cached firmware version, e.g. to revert a bad update. Same events and signature
+/// check as `flash_firmware`.
+#[tauri::command]
+pub async fn rollback_firmware(
+    version: String,
+    device_id: Option<String>,
+    allow_unsigned: Option<bool>,
+    app_handle: tauri::AppHandle,
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<String, AppError> {
+    let version = Version::parse(&version)
+        .context("Invalid version")?;
+    let cached = firmware_cache(&app_handle)?
+        .get(&version)
+        .context("Failed to read firmware cache")?
+        .ok_or_else(|| AppError::new(ErrorCode::NotFound, format!("Firmware {} is not in the local cache", version)))?;
+    log::info!("Rolling back to cached firmware {}", version);
+    flash_image(device_id, cached.path, allow_unsigned.unwrap_or(false), &app_handle, &device_manager).await
+}
+
+/// Get all firmware versions built for the connected board's hardware revision
 #[tauri::command]
 pub async fn get_available_firmware_versions(
     repo_owner: String,
     repo_name: String,
-) -> Result<Vec<crate::update::models::FirmwareRelease>, String> {
-    let update_service = UpdateService::new(repo_owner, repo_name);
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<Vec<crate::update::models::FirmwareRelease>, AppError> {
+    let hardware_revision = device_manager.get_device_hardware_revision().await;
+    let update_service = update_service(&device_manager, repo_owner, repo_name).await?;
     update_service
-        .get_available_versions()
+        .get_available_versions(hardware_revision.as_deref())
         .await
-        .map_err(|e| format!("Failed to get available versions: {}", e))
+        .context("Failed to get available versions")
 }
 
 /// Verify downloaded firmware integrity
@@ -336,14 +843,31 @@ pub async fn get_available_firmware_versions(
 pub async fn verify_firmware(
     file_path: String,
     expected_hash: Option<String>,
-) -> Result<bool, String> {
+) -> Result<bool, AppError> {
     let path = PathBuf::from(&file_path);
     let update_service = UpdateService::new("".to_string(), "".to_string());
     
     update_service
         .verify_firmware(&path, expected_hash.as_deref())
         .await
-        .map_err(|e| format!("Failed to verify firmware: {}", e))
+        .context("Failed to verify firmware")
+}
+
+/// Check a firmware image against its minisign signature (default `<image>.sig`) using the
+/// configured or bundled public key
+#[tauri::command]
+pub async fn verify_firmware_signature(
+    file_path: String,
+    signature_path: Option<String>,
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<crate::update::signature::SignatureVerification, AppError> {
+    let public_key = device_manager.get_app_settings().await.firmware_update.effective_signature_key();
+    crate::update::signature::verify_image(
+        &PathBuf::from(&file_path),
+        signature_path.as_deref().map(std::path::Path::new),
+        public_key.as_deref(),
+    )
+    .context("Failed to verify firmware signature")
 }
 
 // Binary configuration file commands
@@ -352,11 +876,11 @@ pub async fn verify_firmware(
 #[tauri::command]
 pub async fn read_device_config_raw(
     device_manager: State<'_, Arc<DeviceManager>>,
-) -> Result<Vec<u8>, String> {
+) -> Result<Vec<u8>, AppError> {
     device_manager
         .read_config_binary()
         .await
-        .map_err(|e| format!("Failed to read config binary: {}", e))
+        .context("Failed to read config binary")
 }
 
 /// Write raw device configuration binary
@@ -364,112 +888,302 @@ pub async fn read_device_config_raw(
 pub async fn write_device_config_raw(
     data: Vec<u8>,
     device_manager: State<'_, Arc<DeviceManager>>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     device_manager
         .write_config_binary(&data)
         .await
-        .map_err(|e| format!("Failed to write config binary: {}", e))
+        .context("Failed to write config binary")
 }
 
-/// Delete device configuration file
+/// Run a config from device RAM without storing it (firmware TEST_CONFIG)
 #[tauri::command]
-pub async fn delete_device_config(
+pub async fn preview_device_config(
+    data: Vec<u8>,
     device_manager: State<'_, Arc<DeviceManager>>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     device_manager
-        .delete_config_file()
+        .preview_config_binary(&data)
         .await
-        .map_err(|e| format!("Failed to delete config file: {}", e))
+        .context("Failed to preview config")
 }
 
-/// Reset device to factory defaults
+/// The config preview running on the device, if any
 #[tauri::command]
-pub async fn reset_device_to_defaults(
+pub async fn get_config_preview(
     device_manager: State<'_, Arc<DeviceManager>>,
-) -> Result<(), String> {
-    device_manager
-        .reset_device_to_defaults()
-        .await
-        .map_err(|e| format!("Failed to reset device: {}", e))
+) -> Result<Option<crate::device::config_preview::ConfigPreview>, AppError> {
+    Ok(device_manager.get_config_preview().await)
 }
 
-/// Format device storage (deletes all files)
+/// Store the previewed config on the device
 #[tauri::command]
-pub async fn format_device_storage(
+pub async fn commit_preview_config(
     device_manager: State<'_, Arc<DeviceManager>>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     device_manager
-        .format_device_storage()
+        .commit_preview_config()
         .await
-        .map_err(|e| format!("Failed to format storage: {}", e))
+        .context("Failed to commit config preview")
 }
 
-/// Get device storage information
+/// Drop the previewed config and go back to the stored one
 #[tauri::command]
-pub async fn get_device_storage_info(
+pub async fn discard_preview_config(
     device_manager: State<'_, Arc<DeviceManager>>,
-) -> Result<StorageInfo, String> {
+) -> Result<(), AppError> {
     device_manager
-        .get_device_storage_info()
+        .discard_preview_config()
         .await
-        .map_err(|e| format!("Failed to get storage info: {}", e))
+        .context("Failed to discard config preview")
 }
 
-/// List files on device storage
+/// Export the device configuration as human-readable JSON
 #[tauri::command]
-pub async fn list_device_files(
+pub async fn export_device_config_json(
     device_manager: State<'_, Arc<DeviceManager>>,
-) -> Result<Vec<String>, String> {
-    device_manager
-        .list_device_files()
+) -> Result<String, AppError> {
+    let raw_data = device_manager
+        .read_config_binary()
         .await
-        .map_err(|e| format!("Failed to list files: {}", e))
+        .context("Failed to read config binary")?;
+    let config = BinaryConfig::from_bytes(&raw_data)
+        .map_err(AppError::invalid_configuration)
+        .context("Failed to parse config binary")?;
+    config.to_json().map_err(AppError::invalid_configuration)
 }
 
-/// Read any file from device storage
+/// JSON Schema of the document `export_device_config_json` produces, for the config layout
+/// stored on the connected device
 #[tauri::command]
-pub async fn read_device_file(
-    filename: String,
+pub async fn get_config_schema(
     device_manager: State<'_, Arc<DeviceManager>>,
-) -> Result<Vec<u8>, String> {
-    device_manager
-        .read_device_file(&filename)
+) -> Result<serde_json::Value, AppError> {
+    let raw_data = device_manager
+        .read_config_binary()
         .await
-        .map_err(|e| format!("Failed to read file: {}", e))
+        .context("Failed to read config binary")?;
+    let config_version = raw_data
+        .get(..6)
+        .filter(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) == crate::config::binary::CONFIG_MAGIC)
+        .map(|b| u16::from_le_bytes([b[4], b[5]]))
+        .ok_or_else(|| AppError::invalid_configuration("Device config has no valid header"))?;
+    let firmware_version = device_manager.get_device_firmware_version().await;
+    crate::config::config_schema(config_version, firmware_version.as_deref())
+        .map_err(AppError::invalid_configuration)
 }
 
-/// Write any file to device storage
+/// Export the button/axis layout for another mapper (see `config::export`) and write it to `path`
 #[tauri::command]
-pub async fn write_device_file(
-    filename: String,
-    data: Vec<u8>,
+pub async fn export_mapping(
+    format: crate::config::MappingExportFormat,
+    path: String,
     device_manager: State<'_, Arc<DeviceManager>>,
-) -> Result<(), String> {
-    device_manager
-        .write_device_file(&filename, &data)
+) -> Result<(), AppError> {
+    let raw_data = device_manager
+        .read_config_binary()
         .await
-        .map_err(|e| format!("Failed to write file: {}", e))
+        .context("Failed to read config binary")?;
+    let config = BinaryConfig::from_bytes(&raw_data)
+        .map_err(AppError::invalid_configuration)
+        .context("Failed to parse config binary")?;
+    let mapping = device_manager.hid_button_mapping().await;
+    let contents = config
+        .export_mapping(format, mapping.as_deref())
+        .map_err(AppError::internal)?;
+    std::fs::write(&path, contents).context("Failed to write mapping export")
 }
 
-/// Delete any file from device storage
+/// Import a JSON configuration (see `config::json` for the schema) and write it to the device
 #[tauri::command]
-pub async fn delete_device_file(
-    filename: String,
+pub async fn import_device_config_json(
+    json: String,
     device_manager: State<'_, Arc<DeviceManager>>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
+    let config = BinaryConfig::from_json(&json)
+        .map_err(AppError::invalid_configuration)
+        .context("Failed to import config JSON")?;
+    let data = config.to_bytes()
+        .map_err(AppError::invalid_configuration)
+        .context("Failed to serialize config")?;
     device_manager
-        .delete_device_file(&filename)
+        .write_config_binary(&data)
         .await
-        .map_err(|e| format!("Failed to delete file: {}", e))
+        .context("Failed to write config binary")
 }
 
-// Parsed configuration commands
-
-/// Test device file listing
+/// List archived copies of `/config.bin`, newest first; `serial` limits the list to one device
 #[tauri::command]
-pub async fn test_list_device_files(
+pub async fn list_config_backups(
+    serial: Option<String>,
     device_manager: State<'_, Arc<DeviceManager>>,
-) -> Result<Vec<String>, String> {
+) -> Result<Vec<crate::device::config_backups::ConfigBackupInfo>, AppError> {
+    device_manager
+        .list_config_backups(serial.as_deref())
+        .await
+        .context("Failed to list config backups")
+}
+
+/// Write an archived config back to the connected device
+#[tauri::command]
+pub async fn restore_config_backup(
+    id: String,
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<(), AppError> {
+    device_manager
+        .restore_config_backup(&id)
+        .await
+        .context("Failed to restore config backup")
+}
+
+/// Difference from the device config to the firmware's `/config.bak`, i.e. what restoring it would change
+#[tauri::command]
+pub async fn compare_with_device_backup(
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<crate::config::ConfigDiff, AppError> {
+    device_manager
+        .compare_with_device_backup()
+        .await
+        .context("Failed to compare with device backup")
+}
+
+/// Write the firmware's `/config.bak` back as the device config; returns what changed
+#[tauri::command]
+pub async fn restore_from_device_backup(
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<crate::config::ConfigDiff, AppError> {
+    device_manager
+        .restore_from_device_backup()
+        .await
+        .context("Failed to restore device backup")
+}
+
+/// Export an archived config as human-readable JSON; works without the device connected
+#[tauri::command]
+pub async fn export_config_backup_json(
+    id: String,
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<String, AppError> {
+    let raw_data = device_manager
+        .read_config_backup(&id)
+        .await
+        .context("Failed to read config backup")?;
+    let config = BinaryConfig::from_bytes(&raw_data)
+        .map_err(AppError::invalid_configuration)
+        .context("Failed to parse config backup")?;
+    config.to_json().map_err(AppError::invalid_configuration)
+}
+
+/// Delete an archived config
+#[tauri::command]
+pub async fn delete_config_backup(
+    id: String,
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<(), AppError> {
+    device_manager
+        .delete_config_backup(&id)
+        .await
+        .context("Failed to delete config backup")
+}
+
+/// Delete device configuration file
+#[tauri::command]
+pub async fn delete_device_config(
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<(), AppError> {
+    device_manager
+        .delete_config_file()
+        .await
+        .context("Failed to delete config file")
+}
+
+/// Reset device to factory defaults
+#[tauri::command]
+pub async fn reset_device_to_defaults(
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<(), AppError> {
+    device_manager
+        .reset_device_to_defaults()
+        .await
+        .context("Failed to reset device")
+}
+
+/// Format device storage (deletes all files)
+#[tauri::command]
+pub async fn format_device_storage(
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<(), AppError> {
+    device_manager
+        .format_device_storage()
+        .await
+        .context("Failed to format storage")
+}
+
+/// Get device storage information
+#[tauri::command]
+pub async fn get_device_storage_info(
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<StorageInfo, AppError> {
+    device_manager
+        .get_device_storage_info()
+        .await
+        .context("Failed to get storage info")
+}
+
+/// List files on device storage
+#[tauri::command]
+pub async fn list_device_files(
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<Vec<String>, AppError> {
+    device_manager
+        .list_device_files()
+        .await
+        .context("Failed to list files")
+}
+
+/// Read any file from device storage
+#[tauri::command]
+pub async fn read_device_file(
+    filename: String,
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<Vec<u8>, AppError> {
+    device_manager
+        .read_device_file(&filename)
+        .await
+        .context("Failed to read file")
+}
+
+/// Write any file to device storage
+#[tauri::command]
+pub async fn write_device_file(
+    filename: String,
+    data: Vec<u8>,
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<(), AppError> {
+    device_manager
+        .write_device_file(&filename, &data)
+        .await
+        .context("Failed to write file")
+}
+
+/// Delete any file from device storage
+#[tauri::command]
+pub async fn delete_device_file(
+    filename: String,
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<(), AppError> {
+    device_manager
+        .delete_device_file(&filename)
+        .await
+        .context("Failed to delete file")
+}
+
+// Parsed configuration commands
+
+/// Test device file listing
+#[tauri::command]
+pub async fn test_list_device_files(
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<Vec<String>, AppError> {
     log::info!("Testing LIST_FILES command");
     
     let files = device_manager
@@ -477,7 +1191,7 @@ pub async fn test_list_device_files(
         .await
         .map_err(|e| {
             log::error!("Failed to list device files: {}", e);
-            format!("Failed to list device files: {}", e)
+            AppError::from(e).with_context("Failed to list device files")
         })?;
 
     log::info!("Found {} files: {:?}", files.len(), files);
@@ -488,7 +1202,7 @@ pub async fn test_list_device_files(
 #[tauri::command]
 pub async fn read_parsed_device_config(
     device_manager: State<'_, Arc<DeviceManager>>,
-) -> Result<(Vec<UIAxisConfig>, Vec<UIButtonConfig>), String> {
+) -> Result<(Vec<UIAxisConfig>, Vec<UIButtonConfig>), AppError> {
     
     // Read raw binary configuration
     let raw_data = device_manager
@@ -496,14 +1210,14 @@ pub async fn read_parsed_device_config(
         .await
         .map_err(|e| {
             log::error!("Failed to read config binary: {}", e);
-            format!("Failed to read config binary: {}", e)
+            AppError::from(e).with_context("Failed to read config binary")
         })?;
 
     // Parse binary data
     let config = BinaryConfig::from_bytes(&raw_data)
         .map_err(|e| {
             log::error!("Failed to parse config binary: {}", e);
-            format!("Failed to parse config binary: {}", e)
+            AppError::invalid_configuration(e).with_context("Failed to parse config binary")
         })?;
 
     // Convert to UI format
@@ -513,11 +1227,309 @@ pub async fn read_parsed_device_config(
     Ok((axes, buttons))
 }
 
+/// Read the rotary encoders (ENC_A/ENC_B logical input pairs) from the device configuration
+#[tauri::command]
+pub async fn read_device_encoder_configs(
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<Vec<crate::config::UIEncoderConfig>, AppError> {
+    let raw_data = device_manager
+        .read_config_binary()
+        .await
+        .context("Failed to read config binary")?;
+    let config = BinaryConfig::from_bytes(&raw_data)
+        .map_err(AppError::invalid_configuration)
+        .context("Failed to parse config binary")?;
+    Ok(config.to_encoder_configs())
+}
+
+/// Update encoder buttons, latch mode and direction, then write the configuration back
+#[tauri::command]
+pub async fn update_device_encoder_configs(
+    encoders: Vec<crate::config::UIEncoderConfig>,
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<Vec<crate::config::UIEncoderConfig>, AppError> {
+    device_manager
+        .update_encoder_configs(&encoders)
+        .await
+        .context("Failed to update encoder configuration")
+}
+
+/// Update axis range, deadzone, curve and inversion, then write the configuration back
+#[tauri::command]
+pub async fn update_device_axis_configs(
+    axes: Vec<UIAxisConfig>,
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<Vec<UIAxisConfig>, AppError> {
+    device_manager
+        .update_axis_configs(&axes)
+        .await
+        .context("Failed to update axis configuration")
+}
+
+/// Replace a single axis of the device config; returns what changed
+#[tauri::command]
+pub async fn update_axis_in_config(
+    axis: crate::config::json::AxisJson,
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<crate::config::ConfigDiff, AppError> {
+    device_manager
+        .update_axis_in_config(&axis)
+        .await
+        .context("Failed to update axis")
+}
+
+/// Replace the logical input at `index` of the device config (or append at the end); returns what changed
+#[tauri::command]
+pub async fn update_logical_input_in_config(
+    index: usize,
+    input: crate::config::json::LogicalInputJson,
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<crate::config::ConfigDiff, AppError> {
+    device_manager
+        .update_logical_input_in_config(index, &input)
+        .await
+        .context("Failed to update logical input")
+}
+
+/// Reassign joystick button IDs as `(from, to)` moves, resolving occupied targets by `conflict`
+/// (default swap); returns the resulting layout
+#[tauri::command]
+pub async fn remap_logical_buttons(
+    mapping: Vec<(u8, u8)>,
+    conflict: Option<crate::config::RemapConflict>,
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<crate::config::RemapSummary, AppError> {
+    device_manager
+        .remap_logical_buttons(&mapping, conflict.unwrap_or_default())
+        .await
+        .context("Failed to remap buttons")
+}
+
+/// Shift layer of the connected device and whether firmware or the virtual bridge applies it
+#[tauri::command]
+pub async fn get_shift_layer(
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<crate::config::shift_layer::ShiftLayerState, AppError> {
+    device_manager
+        .get_shift_layer()
+        .await
+        .context("Failed to read shift layer")
+}
+
+/// Set (or with `null` remove) the shift layer: modifier button plus base -> shifted assignments
+#[tauri::command]
+pub async fn set_shift_layer(
+    layer: Option<crate::config::shift_layer::ShiftLayer>,
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<crate::config::shift_layer::ShiftLayerState, AppError> {
+    device_manager
+        .set_shift_layer(layer)
+        .await
+        .context("Failed to set shift layer")
+}
+
+/// Read the hat switches (four direction inputs each) from the device configuration
+#[tauri::command]
+pub async fn get_hat_configs(
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<Vec<crate::config::UIHatConfig>, AppError> {
+    device_manager
+        .get_hat_configs()
+        .await
+        .context("Failed to read hats")
+}
+
+/// Replace all hat switches; buttons wired to a hat's direction sources become part of the hat
+#[tauri::command]
+pub async fn set_hat_configs(
+    hats: Vec<crate::config::UIHatConfig>,
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<Vec<crate::config::UIHatConfig>, AppError> {
+    device_manager
+        .set_hat_configs(&hats)
+        .await
+        .context("Failed to update hats")
+}
+
+/// Starter configurations for common hardware layouts
+#[tauri::command]
+pub async fn list_config_templates() -> Result<Vec<crate::config::ConfigTemplateInfo>, AppError> {
+    Ok(crate::config::templates::list_config_templates())
+}
+
+/// Replace the device config with a template, keeping the device's USB descriptor; returns what changed
+#[tauri::command]
+pub async fn apply_config_template(
+    id: String,
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<crate::config::ConfigDiff, AppError> {
+    device_manager
+        .apply_config_template(&id)
+        .await
+        .context("Failed to apply config template")
+}
+
+/// Models of the firmware's axis curve presets as editable control points (see `config::curve`)
+#[tauri::command]
+pub async fn get_axis_curve_presets() -> Result<Vec<crate::config::CurvePreset>, AppError> {
+    Ok(crate::config::curve::curve_presets())
+}
+
+/// Sample a custom axis curve for plotting and name the preset the firmware would store it as
+#[tauri::command]
+pub async fn preview_axis_curve(
+    curve: crate::config::AxisCurve,
+    samples: Option<usize>,
+) -> Result<crate::config::AxisCurvePreview, AppError> {
+    curve.validate()
+        .map_err(AppError::invalid_argument)
+        .context("Invalid axis curve")?;
+    Ok(crate::config::AxisCurvePreview {
+        points: curve.preview(samples.unwrap_or(crate::config::curve::DEFAULT_PREVIEW_SAMPLES)),
+        preset: curve.nearest_preset(),
+    })
+}
+
+/// Store a custom curve on an axis as its nearest firmware preset, then write the configuration
+/// back. The presets are modeled, so the UI shows the `preview_axis_curve` match first and passes
+/// the preset the user accepted as `confirmed_preset`; nothing is written for any other preset.
+#[tauri::command]
+pub async fn apply_axis_curve(
+    axis_id: u8,
+    curve: crate::config::AxisCurve,
+    confirmed_preset: String,
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<crate::config::PresetMatch, AppError> {
+    let preset = device_manager
+        .set_axis_curve(axis_id, &curve, &confirmed_preset)
+        .await
+        .context("Failed to apply axis curve")?;
+    if !preset.close_match {
+        log::info!("Axis {} curve stored as {} (max deviation {:.3} from the modeled preset)", axis_id, preset.curve, preset.max_error);
+    }
+    Ok(preset)
+}
+
+/// Check a configuration binary for pin conflicts and impossible combinations before writing it
+#[tauri::command]
+pub async fn validate_config_binary(
+    data: Vec<u8>,
+) -> Result<Vec<crate::config::ConfigDiagnostic>, AppError> {
+    let config = BinaryConfig::from_bytes(&data)
+        .map_err(AppError::invalid_configuration)
+        .context("Failed to parse config binary")?;
+    Ok(crate::config::validate_config(&config))
+}
+
+/// Dry run of a config before applying it: the device checks it with VALIDATE_CONFIG when its
+/// firmware supports it, otherwise the host-side checks run. Nothing is written to flash.
+#[tauri::command]
+pub async fn validate_config_on_device(
+    data: Vec<u8>,
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<crate::config::ConfigValidationReport, AppError> {
+    device_manager
+        .validate_config_on_device(&data)
+        .await
+        .context("Failed to validate config")
+}
+
+/// Report which validations a config binary fails and what is recoverable from it; with `repair`,
+/// also return the recovered config re-serialized with a fresh size and CRC
+#[tauri::command]
+pub async fn diagnose_config_binary(
+    data: Vec<u8>,
+    repair: Option<bool>,
+) -> Result<crate::config::ConfigBinaryDiagnosis, AppError> {
+    Ok(crate::config::diagnose_config_binary(&data, repair.unwrap_or(false)))
+}
+
+/// Field-level difference from config `a` to config `b`, e.g. to review a profile apply or restore
+#[tauri::command]
+pub async fn diff_device_configs(
+    a: Vec<u8>,
+    b: Vec<u8>,
+) -> Result<crate::config::ConfigDiff, AppError> {
+    let old = BinaryConfig::from_bytes(&a)
+        .map_err(AppError::invalid_configuration)
+        .context("Failed to parse first config")?;
+    let new = BinaryConfig::from_bytes(&b)
+        .map_err(AppError::invalid_configuration)
+        .context("Failed to parse second config")?;
+    Ok(crate::config::diff_configs(&old, &new))
+}
+
+/// Check the configuration currently stored on the device
+#[tauri::command]
+pub async fn validate_device_config(
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<Vec<crate::config::ConfigDiagnostic>, AppError> {
+    let raw_data = device_manager
+        .read_config_binary()
+        .await
+        .context("Failed to read config binary")?;
+    validate_config_binary(raw_data).await
+}
+
+/// Read the button matrix (row/column pins and cell button assignments) from the device configuration
+#[tauri::command]
+pub async fn read_matrix_config(
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<crate::config::MatrixConfig, AppError> {
+    let raw_data = device_manager
+        .read_config_binary()
+        .await
+        .context("Failed to read config binary")?;
+    let config = BinaryConfig::from_bytes(&raw_data)
+        .map_err(AppError::invalid_configuration)
+        .context("Failed to parse config binary")?;
+    Ok(config.to_matrix_config())
+}
+
+/// Replace the button matrix in the device configuration and write it back
+#[tauri::command]
+pub async fn write_matrix_config(
+    matrix: crate::config::MatrixConfig,
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<crate::config::MatrixConfig, AppError> {
+    device_manager
+        .update_matrix_config(&matrix)
+        .await
+        .context("Failed to update matrix configuration")
+}
+
+/// Read the USB descriptor (VID/PID, manufacturer and product name) from the device configuration
+#[tauri::command]
+pub async fn read_usb_descriptor(
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<crate::config::UsbDescriptorConfig, AppError> {
+    let raw_data = device_manager
+        .read_config_binary()
+        .await
+        .context("Failed to read config binary")?;
+    let config = BinaryConfig::from_bytes(&raw_data)
+        .map_err(AppError::invalid_configuration)
+        .context("Failed to parse config binary")?;
+    Ok(config.to_usb_descriptor())
+}
+
+/// Change the USB descriptor and write the configuration back; takes effect after the device re-enumerates
+#[tauri::command]
+pub async fn write_usb_descriptor(
+    descriptor: crate::config::UsbDescriptorConfig,
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<crate::config::UsbDescriptorConfig, AppError> {
+    device_manager
+        .update_usb_descriptor(&descriptor)
+        .await
+        .context("Failed to update USB descriptor")
+}
+
 /// Read device pin assignments from configuration
 #[tauri::command]
 pub async fn read_device_pin_assignments(
     device_manager: State<'_, Arc<DeviceManager>>,
-) -> Result<std::collections::HashMap<u8, String>, String> {
+) -> Result<std::collections::HashMap<u8, String>, AppError> {
     
     // Read raw binary configuration
     let raw_data = device_manager
@@ -525,14 +1537,14 @@ pub async fn read_device_pin_assignments(
         .await
         .map_err(|e| {
             log::error!("Failed to read config binary for pin assignments: {}", e);
-            format!("Failed to read config binary: {}", e)
+            AppError::from(e).with_context("Failed to read config binary")
         })?;
 
     // Parse binary data
     let config = BinaryConfig::from_bytes(&raw_data)
         .map_err(|e| {
             log::error!("Failed to parse config binary for pin assignments: {}", e);
-            format!("Failed to parse config binary: {}", e)
+            AppError::invalid_configuration(e).with_context("Failed to parse config binary")
         })?;
 
     // Extract pin assignments
@@ -547,7 +1559,7 @@ pub async fn read_device_pin_assignments(
 #[tauri::command]
 pub async fn read_parsed_device_config_with_pins(
     device_manager: State<'_, Arc<DeviceManager>>,
-) -> Result<(Vec<UIAxisConfig>, Vec<UIButtonConfig>, std::collections::HashMap<u8, String>), String> {
+) -> Result<(Vec<UIAxisConfig>, Vec<UIButtonConfig>, std::collections::HashMap<u8, String>), AppError> {
     
     // Read raw binary configuration once
     let raw_data = device_manager
@@ -555,14 +1567,14 @@ pub async fn read_parsed_device_config_with_pins(
         .await
         .map_err(|e| {
             log::error!("Failed to read config binary: {}", e);
-            format!("Failed to read config binary: {}", e)
+            AppError::from(e).with_context("Failed to read config binary")
         })?;
 
     // Parse binary data once
     let config = BinaryConfig::from_bytes(&raw_data)
         .map_err(|e| {
             log::error!("Failed to parse config binary: {}", e);
-            format!("Failed to parse config binary: {}", e)
+            AppError::invalid_configuration(e).with_context("Failed to parse config binary")
         })?;
 
     // Convert to UI format
@@ -580,13 +1592,13 @@ pub async fn read_parsed_device_config_with_pins(
 #[tauri::command]
 pub async fn read_button_states(
     device_manager: State<'_, Arc<DeviceManager>>,
-) -> Result<ButtonStates, String> {
+) -> Result<ButtonStates, AppError> {
     log::debug!("read_button_states command called");
     device_manager
         .read_button_states()
         .await
         .map_err(|e| {
-            let error = format!("Failed to read button states: {}", e);
+            let error = AppError::from(e).with_context("Failed to read button states");
             log::error!("{}", error);
             error
         })
@@ -596,7 +1608,7 @@ pub async fn read_button_states(
 #[tauri::command]
 pub async fn debug_hid_mapping(
     device_manager: State<'_, Arc<DeviceManager>>,
-) -> Result<Option<(usize, u64)>, String> {
+) -> Result<Option<(usize, u64)>, AppError> {
     Ok(device_manager.hid_debug_mapping().await)
 }
 
@@ -604,7 +1616,7 @@ pub async fn debug_hid_mapping(
 #[tauri::command]
 pub async fn debug_full_hid_report(
     device_manager: State<'_, Arc<DeviceManager>>,
-) -> Result<Option<(usize, String)>, String> {
+) -> Result<Option<(usize, String)>, AppError> {
     Ok(device_manager.hid_full_report().await)
 }
 
@@ -612,7 +1624,7 @@ pub async fn debug_full_hid_report(
 #[tauri::command]
 pub async fn hid_mapping_details(
     device_manager: State<'_, Arc<DeviceManager>>,
-) -> Result<Option<serde_json::Value>, String> {
+) -> Result<Option<serde_json::Value>, AppError> {
     Ok(device_manager.hid_mapping_details().await)
 }
 
@@ -620,16 +1632,63 @@ pub async fn hid_mapping_details(
 #[tauri::command]
 pub async fn hid_button_bit_diagnostics(
     device_manager: State<'_, Arc<DeviceManager>>,
-) -> Result<Option<serde_json::Value>, String> {
+) -> Result<Option<serde_json::Value>, AppError> {
     // There is no direct existing method; access via hid reader through mapping details path
     Ok(device_manager.hid_button_bit_diagnostics().await)
 }
 
-// Raw hardware state commands
-
+/// Dropped-report and stall counters derived from the HID frame counter
+#[tauri::command]
+pub async fn get_hid_link_stats(
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<crate::hid::HidLinkStats, AppError> {
+    Ok(device_manager.hid_link_stats().await)
+}
+
+/// HID controls (identify, test mode) the connected firmware accepts
+#[tauri::command]
+pub async fn get_hid_control_support(
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<crate::hid::HidControlSupport, AppError> {
+    Ok(device_manager.hid_control_support().await)
+}
+
+/// Write a firmware-defined HID feature report
+#[tauri::command]
+pub async fn send_hid_feature_report(
+    device_manager: State<'_, Arc<DeviceManager>>,
+    report_id: u8,
+    data: Vec<u8>,
+) -> Result<(), AppError> {
+    device_manager.send_hid_feature_report(report_id, &data).await
+        .context("Failed to send HID feature report")
+}
+
+/// Blink the device status LED
+#[tauri::command]
+pub async fn identify_device(
+    device_manager: State<'_, Arc<DeviceManager>>,
+    seconds: Option<u8>,
+) -> Result<(), AppError> {
+    device_manager.hid_identify(seconds.unwrap_or(5)).await
+        .context("Failed to identify device")
+}
+
+/// Enter or leave the firmware input test mode
+#[tauri::command]
+pub async fn set_device_test_mode(
+    device_manager: State<'_, Arc<DeviceManager>>,
+    enabled: bool,
+) -> Result<(), AppError> {
+    device_manager.hid_set_test_mode(enabled).await
+        .context("Failed to change device test mode")
+}
+
+// Raw hardware state commands
+
 /// Get the current raw state display mode
 #[tauri::command]
-pub async fn get_raw_state_display_mode() -> Result<String, String> {
+pub async fn get_raw_state_display_mode() -> Result<String, AppError> {
     Ok(crate::raw_state::get_display_mode_string())
 }
 
@@ -637,77 +1696,142 @@ pub async fn get_raw_state_display_mode() -> Result<String, String> {
 #[tauri::command]
 pub async fn set_raw_state_display_mode(
     device_manager: State<'_, Arc<DeviceManager>>,
-    app_handle: tauri::AppHandle,
     mode: String,
-) -> Result<(), String> {
-    // Parse desired mode
+) -> Result<(), AppError> {
     let new_mode = crate::raw_state::DisplayMode::from_str(&mode)
-        .ok_or_else(|| format!("Invalid display mode: {}", mode))?;
-    let current = crate::raw_state::get_display_mode();
-    if current == new_mode { return Ok(()); }
-
-    log::info!("Changing display mode from {:?} to {:?}", current, new_mode);
-    crate::raw_state::set_display_mode(new_mode);
-
-    // Handle transitions
-    // If entering a mode that includes Raw and we have a connected device, ensure monitoring started
-    if matches!(new_mode, crate::raw_state::DisplayMode::Raw | crate::raw_state::DisplayMode::Both) {
-        if let Some(_id) = device_manager.get_connected_device_id().await { // device connected
-            if let Err(e) = device_manager.start_raw_state_monitoring(app_handle.clone()).await {
-                log::warn!("Failed to start raw monitoring after mode change: {}", e);
-            }
-        }
-    } else {
-        // Leaving raw (to HID) -> stop monitoring if active
-        if let Err(e) = device_manager.stop_raw_state_monitoring().await { log::debug!("Stop raw monitoring (mode change) error: {}", e); }
-    }
-
-    // If entering a mode that includes HID, ensure HID connected
-    if matches!(new_mode, crate::raw_state::DisplayMode::HID | crate::raw_state::DisplayMode::Both) {
-        if let Some(_id) = device_manager.get_connected_device_id().await {
-            if let Err(e) = device_manager.connect_hid().await { log::warn!("Failed to connect HID after mode change: {}", e); }
-        }
-    } else {
-        // Leaving HID portion -> disconnect if active
-        if let Err(e) = device_manager.disconnect_hid().await { log::debug!("Disconnect HID (mode change) error: {}", e); }
-    }
+        .ok_or_else(|| AppError::invalid_argument(format!("Invalid display mode: {}", mode)))?;
+    device_manager.set_display_mode(new_mode).await;
     Ok(())
 }
 
+/// Current display mode (persisted in the app settings)
+#[tauri::command]
+pub async fn get_display_mode() -> Result<crate::raw_state::DisplayMode, AppError> {
+    Ok(crate::raw_state::get_display_mode())
+}
+
+/// Switch between HID, raw and combined display for the connected device and remember the choice
+#[tauri::command]
+pub async fn set_display_mode(
+    mode: crate::raw_state::DisplayMode,
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<crate::raw_state::DisplayMode, AppError> {
+    Ok(device_manager.set_display_mode(mode).await)
+}
+
+/// Get the raw monitor event coalescing interval in milliseconds (0 = no coalescing)
+#[tauri::command]
+pub async fn get_raw_monitor_coalesce_interval() -> Result<u64, AppError> {
+    Ok(crate::raw_state::get_monitor_coalesce_ms())
+}
+
+/// Set the raw monitor event coalescing interval; takes effect immediately. Returns the applied (clamped) value.
+#[tauri::command]
+pub async fn set_raw_monitor_coalesce_interval(
+    interval_ms: u64,
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<u64, AppError> {
+    let mut settings = device_manager.get_app_settings().await;
+    settings.raw_monitor.batch_ms = interval_ms;
+    settings.raw_monitor = settings.raw_monitor.clamped();
+    Ok(device_manager.set_app_settings(settings).await.raw_monitor.batch_ms)
+}
+
+/// Tune raw monitor event pressure; omitted values are kept. Persisted in the app settings.
+#[tauri::command]
+pub async fn configure_raw_monitoring(
+    max_event_rate_hz: Option<u32>,
+    batch_ms: Option<u64>,
+    debug: Option<bool>,
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<crate::raw_state::RawMonitorSettings, AppError> {
+    let mut settings = device_manager.get_app_settings().await;
+    let raw_monitor = &mut settings.raw_monitor;
+    raw_monitor.max_event_rate_hz = max_event_rate_hz.unwrap_or(raw_monitor.max_event_rate_hz);
+    raw_monitor.batch_ms = batch_ms.unwrap_or(raw_monitor.batch_ms);
+    raw_monitor.debug = debug.unwrap_or(raw_monitor.debug);
+    settings.raw_monitor = settings.raw_monitor.clamped();
+    Ok(device_manager.set_app_settings(settings).await.raw_monitor)
+}
+
 /// Read current GPIO states from connected device
 #[tauri::command]
 pub async fn read_raw_gpio_states(
     device_manager: State<'_, Arc<DeviceManager>>,
-) -> Result<crate::raw_state::RawGpioStates, String> {
+) -> Result<crate::raw_state::RawGpioStates, AppError> {
     device_manager.read_raw_gpio_states().await
-        .map_err(|e| format!("Failed to read GPIO states: {}", e))
+        .context("Failed to read GPIO states")
 }
 
 /// Read current matrix states from connected device
 #[tauri::command]
 pub async fn read_raw_matrix_state(
     device_manager: State<'_, Arc<DeviceManager>>,
-) -> Result<crate::raw_state::MatrixState, String> {
+) -> Result<crate::raw_state::MatrixState, AppError> {
     device_manager.read_raw_matrix_state().await
-        .map_err(|e| format!("Failed to read matrix states: {}", e))
+        .context("Failed to read matrix states")
 }
 
 /// Read current shift register states from connected device
 #[tauri::command]
 pub async fn read_raw_shift_reg_state(
     device_manager: State<'_, Arc<DeviceManager>>,
-) -> Result<Vec<crate::raw_state::ShiftRegisterState>, String> {
+) -> Result<Vec<crate::raw_state::ShiftRegisterState>, AppError> {
     device_manager.read_raw_shift_reg_state().await
-        .map_err(|e| format!("Failed to read shift register states: {}", e))
+        .context("Failed to read shift register states")
 }
 
 /// Read all raw hardware states from connected device
 #[tauri::command]
 pub async fn read_all_raw_states(
     device_manager: State<'_, Arc<DeviceManager>>,
-) -> Result<crate::raw_state::RawHardwareState, String> {
+) -> Result<crate::raw_state::RawHardwareState, AppError> {
     device_manager.read_all_raw_states().await
-        .map_err(|e| format!("Failed to read all raw states: {}", e))
+        .context("Failed to read all raw states")
+}
+
+/// Latest raw state kept by the unified reader, without a serial round trip
+#[tauri::command]
+pub async fn get_raw_state_snapshot(
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<Option<crate::raw_state::RawStateSnapshotInfo>, AppError> {
+    Ok(device_manager.raw_state_snapshot().await)
+}
+
+/// Recent raw transitions since `since_us` (firmware time), for timing waveforms
+#[tauri::command]
+pub async fn get_raw_state_history(
+    since_us: Option<u64>,
+) -> Result<crate::raw_state::RawStateHistory, AppError> {
+    Ok(crate::raw_state::history::get_history().query(since_us))
+}
+
+/// Per-pin, matrix cell and shift register bit activation counts since app start or the last
+/// reset, from the raw monitor stream
+#[tauri::command]
+pub async fn get_input_activity_stats() -> Result<crate::raw_state::InputActivityStats, AppError> {
+    Ok(crate::raw_state::activity::get_activity().stats())
+}
+
+#[tauri::command]
+pub async fn reset_input_activity_stats() -> Result<(), AppError> {
+    crate::raw_state::activity::get_activity().reset();
+    Ok(())
+}
+
+/// Receive raw-* events of `kinds` (all kinds when empty); only subscribed kinds are emitted.
+/// Returns the token for `unsubscribe_raw_events`.
+#[tauri::command]
+pub async fn subscribe_raw_events(
+    kinds: Vec<crate::raw_state::RawEventKind>,
+) -> Result<u64, AppError> {
+    Ok(crate::raw_state::subscriptions::get_subscriptions().subscribe(&kinds))
+}
+
+/// Release a raw event subscription; false when the token is unknown
+#[tauri::command]
+pub async fn unsubscribe_raw_events(token: u64) -> Result<bool, AppError> {
+    Ok(crate::raw_state::subscriptions::get_subscriptions().unsubscribe(token))
 }
 
 /// Start raw state monitoring for connected device
@@ -715,25 +1839,88 @@ pub async fn read_all_raw_states(
 pub async fn start_raw_state_monitoring(
     device_manager: State<'_, Arc<DeviceManager>>,
     app_handle: tauri::AppHandle,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     device_manager.start_raw_state_monitoring(app_handle).await
-        .map_err(|e| format!("Failed to start monitoring: {}", e))
+        .context("Failed to start monitoring")
 }
 
 /// Stop raw state monitoring for connected device
 #[tauri::command]
 pub async fn stop_raw_state_monitoring(
     device_manager: State<'_, Arc<DeviceManager>>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     device_manager.stop_raw_state_monitoring().await
-        .map_err(|e| format!("Failed to stop monitoring: {}", e))
+        .context("Failed to stop monitoring")
+}
+
+/// Overflow counters for every bounded buffer, keyed by buffer name
+#[tauri::command]
+pub async fn get_buffer_overflow_stats() -> Result<std::collections::HashMap<String, crate::util::OverflowStats>, AppError> {
+    Ok(crate::util::bounded::overflow_snapshot())
+}
+
+/// Describe the firmware serial protocol known to the app.
+/// With `probe` set, read-only commands are sent once per connection to detect support.
+#[tauri::command]
+pub async fn describe_protocol(
+    probe: Option<bool>,
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<crate::serial::catalog::ProtocolDescription, AppError> {
+    Ok(device_manager.describe_protocol(probe.unwrap_or(false)).await)
+}
+
+/// Feature matrix negotiated with the connected firmware (None when not connected)
+#[tauri::command]
+pub async fn get_firmware_capabilities(
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<Option<crate::serial::capabilities::FirmwareCapabilities>, AppError> {
+    Ok(device_manager.get_firmware_capabilities().await)
+}
+
+/// Device clock model of the connected device (None when not connected or the firmware has no `TIME`)
+#[tauri::command]
+pub async fn get_device_clock_sync(
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<Option<crate::device::clock_sync::ClockModel>, AppError> {
+    Ok(device_manager.get_clock_model())
+}
+
+/// Enable or disable the protocol trace; while enabled it is also written to the app log directory
+#[tauri::command]
+pub async fn set_protocol_trace_enabled(
+    enabled: bool,
+    app_handle: tauri::AppHandle,
+) -> Result<crate::serial::unified::ProtocolTrace, AppError> {
+    use tauri::Manager;
+    let tracer = crate::serial::unified::get_protocol_tracer();
+    if enabled {
+        let dir = app_handle.path().app_log_dir().context("No app log directory")?;
+        tracer.enable(Some(&dir)).context("Failed to open protocol trace file")?;
+    } else {
+        tracer.disable();
+    }
+    Ok(tracer.snapshot(Some(0)))
+}
+
+/// Recorded protocol trace; `limit` returns only the newest entries
+#[tauri::command]
+pub async fn get_protocol_trace(
+    limit: Option<usize>,
+) -> Result<crate::serial::unified::ProtocolTrace, AppError> {
+    Ok(crate::serial::unified::get_protocol_tracer().snapshot(limit))
+}
+
+#[tauri::command]
+pub async fn clear_protocol_trace() -> Result<(), AppError> {
+    crate::serial::unified::get_protocol_tracer().clear()
+        .context("Failed to clear protocol trace")
 }
 
 // Unified serial
 #[tauri::command]
 pub async fn unified_get_snapshot(
     device_manager: State<'_, Arc<DeviceManager>>,
-) -> Result<Option<crate::serial::unified::types::RawStateSnapshot>, String> {
+) -> Result<Option<crate::serial::unified::types::RawStateSnapshot>, AppError> {
     if let Some(handle) = device_manager.get_unified_serial_handle().await {
         let snap = handle.snapshot_receiver().borrow().clone();
         return Ok(Some((*snap).clone()));
@@ -744,9 +1931,9 @@ pub async fn unified_get_snapshot(
 #[tauri::command]
 pub async fn unified_get_metrics(
     device_manager: State<'_, Arc<DeviceManager>>,
-) -> Result<Option<crate::serial::unified::types::MetricsSnapshot>, String> {
+) -> Result<Option<crate::serial::unified::types::MetricsSnapshot>, AppError> {
     if let Some(handle) = device_manager.get_unified_serial_handle().await {
-        let m = handle.metrics_receiver().borrow().clone();
+        let m = handle.metrics_snapshot();
         return Ok(Some(m));
     }
     Ok(None)
@@ -755,16 +1942,569 @@ pub async fn unified_get_metrics(
 #[tauri::command]
 pub async fn unified_status(
     device_manager: State<'_, Arc<DeviceManager>>,
-) -> Result<Option<Vec<String>>, String> {
+) -> Result<Option<Vec<String>>, AppError> {
     if let Some(handle) = device_manager.get_unified_serial_handle().await {
     let spec = CommandSpec { name: "STATUS", matcher: ResponseMatcher::UntilPrefix("OK"), timeout: std::time::Duration::from_millis(500), test_min_duration_ms: None };
         let (tx, rx) = tokio::sync::oneshot::channel();
-        handle.cmd_tx.send(SerialCommand::Write { cmd: "STATUS".to_string(), spec, responder: tx }).await.map_err(|e| format!("Send failed: {}", e))?;
+        handle.cmd_tx.send(SerialCommand::Write { cmd: "STATUS".to_string(), frame: None, spec, priority: CommandPriority::Diagnostics, responder: tx }).await.map_err(|e| AppError::new(ErrorCode::NotConnected, e.to_string()).with_context("Send failed"))?;
         match rx.await {
             Ok(Ok(resp)) => return Ok(Some(resp.lines)),
-            Ok(Err(e)) => return Err(format!("STATUS error: {}", e)),
-            Err(e) => return Err(format!("Channel error: {}", e)),
+            Ok(Err(e)) => return Err(AppError::from(e).with_context("STATUS error")),
+            Err(e) => return Err(AppError::internal(e.to_string()).with_context("Channel error")),
         }
     }
     Ok(None)
-}
\ No newline at end of file
+}
+
+// Application settings
+#[tauri::command]
+pub async fn get_app_settings(
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<crate::device::AppSettings, AppError> {
+    Ok(device_manager.get_app_settings().await)
+}
+
+#[tauri::command]
+pub async fn set_app_settings(
+    settings: crate::device::AppSettings,
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<crate::device::AppSettings, AppError> {
+    if settings.update_rate_ms == 0 {
+        return Err(AppError::invalid_argument("update_rate_ms must be greater than zero"));
+    }
+    Ok(device_manager.set_app_settings(settings).await)
+}
+
+// Developer mode (firmware iteration harness)
+#[tauri::command]
+pub async fn get_dev_mode_settings(
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<crate::device::dev_mode::DevModeSettings, AppError> {
+    Ok(device_manager.get_dev_mode_settings().await)
+}
+
+/// Store developer mode settings; returns them with long timeouts clamped
+#[tauri::command]
+pub async fn set_dev_mode_settings(
+    settings: crate::device::dev_mode::DevModeSettings,
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<crate::device::dev_mode::DevModeSettings, AppError> {
+    device_manager.set_dev_mode_settings(settings).await
+        .context("Invalid developer mode settings")
+}
+
+#[tauri::command]
+pub async fn get_last_flashed_firmware(
+    device_id: String,
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<Option<crate::device::dev_mode::LastFlashedFirmware>, AppError> {
+    let uuid = Uuid::parse_str(&device_id)
+        .context("Invalid device ID")?;
+    device_manager.get_last_flashed_firmware(&uuid).await
+        .context("Failed to get last flashed firmware")
+}
+
+/// Flash a local UF2 build (developer mode); it is remembered for `reflash_last_firmware`
+#[tauri::command]
+pub async fn dev_flash_firmware(
+    device_id: String,
+    file_path: String,
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<crate::device::dev_mode::DevFlashReport, AppError> {
+    let uuid = Uuid::parse_str(&device_id)
+        .context("Invalid device ID")?;
+    device_manager.dev_flash_firmware(&uuid, PathBuf::from(file_path)).await
+        .context("Failed to flash firmware")
+}
+
+/// Re-flash the last local UF2 used for this device, reconnect and run the smoke test
+#[tauri::command]
+pub async fn reflash_last_firmware(
+    device_id: String,
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<crate::device::dev_mode::DevFlashReport, AppError> {
+    let uuid = Uuid::parse_str(&device_id)
+        .context("Invalid device ID")?;
+    device_manager.reflash_last_firmware(&uuid).await
+        .context("Failed to reflash firmware")
+}
+
+/// One-click health check of the connected device, sampling the monitor stream for `monitor_secs`
+#[tauri::command]
+pub async fn run_device_self_test(
+    device_manager: State<'_, Arc<DeviceManager>>,
+    monitor_secs: Option<u64>,
+) -> Result<crate::device::self_test::SelfTestReport, AppError> {
+    device_manager.run_device_self_test(monitor_secs.unwrap_or(crate::device::self_test::DEFAULT_MONITOR_SAMPLE_SECS)).await
+        .context("Failed to run device self-test")
+}
+
+/// Run the configured smoke-test command sequence against the connected device (developer mode only)
+#[tauri::command]
+pub async fn run_smoke_test(
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<crate::device::dev_mode::SmokeTestReport, AppError> {
+    let steps = device_manager.get_dev_mode_settings().await.smoke_test;
+    device_manager.run_smoke_test(&steps).await
+        .context("Failed to run smoke test")
+}
+
+/// Send an arbitrary command line from the developer console (developer mode only)
+#[tauri::command]
+pub async fn send_custom_serial_command(
+    cmd: String,
+    timeout_ms: Option<u64>,
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<crate::device::dev_mode::ConsoleResponse, AppError> {
+    device_manager.send_custom_serial_command(&cmd, timeout_ms).await
+        .context("Console command failed")
+}
+
+// Input recording
+
+fn recordings_dir(app_handle: &tauri::AppHandle) -> Result<PathBuf, AppError> {
+    use tauri::Manager;
+    app_handle
+        .path()
+        .app_data_dir()
+        .map(|dir| dir.join(crate::recording::RECORDINGS_DIR_NAME))
+        .context("No app data directory")
+}
+
+/// Start capturing HID button/axis and raw GPIO/matrix/shift events to a JSONL file
+#[tauri::command]
+pub async fn start_input_recording(
+    label: Option<String>,
+    app_handle: tauri::AppHandle,
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<crate::recording::RecordingInfo, AppError> {
+    let dir = recordings_dir(&app_handle)?;
+    let device = match device_manager.get_connected_device_id().await {
+        Some(id) => device_manager.get_device(&id).await,
+        None => None,
+    };
+    let device_key = device.as_ref().map(crate::device::dev_mode::device_key);
+    let firmware_version = device
+        .and_then(|d| d.device_status)
+        .map(|s| s.firmware_version);
+    crate::recording::get_recorder()
+        .start(&dir, label, device_key, firmware_version)
+        .context("Failed to start recording")
+}
+
+/// Stop the active input recording
+#[tauri::command]
+pub async fn stop_input_recording() -> Result<crate::recording::RecordingInfo, AppError> {
+    crate::recording::get_recorder()
+        .stop()
+        .context("Failed to stop recording")
+}
+
+/// List saved input recordings, newest first
+#[tauri::command]
+pub async fn list_recordings(
+    app_handle: tauri::AppHandle,
+) -> Result<Vec<crate::recording::RecordingInfo>, AppError> {
+    let dir = recordings_dir(&app_handle)?;
+    crate::recording::list_recordings(&dir)
+        .context("Failed to list recordings")
+}
+
+/// Resolve a recording argument: bare file names live in the recordings directory
+fn resolve_recording_path(app_handle: &tauri::AppHandle, recording: &str) -> Result<PathBuf, AppError> {
+    let candidate = PathBuf::from(recording);
+    if candidate.components().count() > 1 || candidate.is_absolute() {
+        return Ok(candidate);
+    }
+    Ok(recordings_dir(app_handle)?.join(candidate))
+}
+
+/// Replay a saved recording, re-emitting its events at recorded timing scaled by `speed` (default 1.0)
+#[tauri::command]
+pub async fn start_input_replay(
+    recording: String,
+    speed: Option<f64>,
+    app_handle: tauri::AppHandle,
+) -> Result<crate::recording::ReplayStatus, AppError> {
+    let path = resolve_recording_path(&app_handle, &recording)?;
+    crate::recording::get_replay_engine()
+        .start(app_handle, &path, speed.unwrap_or(1.0))
+        .await
+        .context("Failed to start replay")
+}
+
+/// Stop the running replay, if any
+#[tauri::command]
+pub async fn stop_input_replay() -> Result<crate::recording::ReplayStatus, AppError> {
+    Ok(crate::recording::get_replay_engine().stop().await)
+}
+
+/// Current replay progress
+#[tauri::command]
+pub async fn get_replay_status() -> Result<crate::recording::ReplayStatus, AppError> {
+    Ok(crate::recording::get_replay_engine().status())
+}
+
+// Virtual joystick bridge
+
+/// Mirror the device's HID input into a virtual joystick (vJoy / uinput), applying the optional
+/// remaps in `options`. Replaces a running bridge.
+#[tauri::command]
+pub async fn start_virtual_bridge(
+    options: Option<crate::interop::VirtualBridgeOptions>,
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<crate::interop::VirtualBridgeStatus, AppError> {
+    let pressed = device_manager
+        .read_button_states()
+        .await
+        .map(|states| states.get_pressed_buttons())
+        .unwrap_or_default();
+    crate::interop::get_virtual_bridge()
+        .start(options.unwrap_or_default(), &pressed)
+        .context("Failed to start virtual joystick bridge")
+}
+
+/// Stop the virtual joystick bridge and remove the virtual device
+#[tauri::command]
+pub async fn stop_virtual_bridge() -> Result<crate::interop::VirtualBridgeStatus, AppError> {
+    Ok(crate::interop::get_virtual_bridge().stop())
+}
+
+/// Current bridge state and update counters
+#[tauri::command]
+pub async fn get_virtual_bridge_status() -> Result<crate::interop::VirtualBridgeStatus, AppError> {
+    Ok(crate::interop::get_virtual_bridge().status())
+}
+
+// Input diagnostics
+
+/// Start measuring button bounce over a test window (see `diagnostics::chatter`), discarding the
+/// previous results
+#[tauri::command]
+pub async fn start_chatter_test(
+    options: Option<crate::diagnostics::ChatterOptions>,
+) -> Result<crate::diagnostics::ChatterReport, AppError> {
+    crate::diagnostics::get_chatter_analyzer()
+        .start(options.unwrap_or_default())
+        .context("Failed to start chatter test")
+}
+
+/// End the chatter test window early and return the final report
+#[tauri::command]
+pub async fn stop_chatter_test() -> Result<crate::diagnostics::ChatterReport, AppError> {
+    crate::diagnostics::get_chatter_analyzer()
+        .stop()
+        .context("Failed to stop chatter test")
+}
+
+/// Per-button bounce counts of the running or last chatter test
+#[tauri::command]
+pub async fn get_chatter_report() -> Result<crate::diagnostics::ChatterReport, AppError> {
+    crate::diagnostics::get_chatter_analyzer()
+        .report()
+        .context("Failed to get chatter report")
+}
+
+/// Cross-check raw hardware state against HID buttons using the device config and emit
+/// `mapping_anomaly` events for disagreements. Needs the "both" display mode.
+#[tauri::command]
+pub async fn start_ghost_detector(
+    options: Option<crate::diagnostics::GhostDetectorOptions>,
+    app_handle: tauri::AppHandle,
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<crate::diagnostics::GhostDetectorStatus, AppError> {
+    if crate::raw_state::get_display_mode() != crate::raw_state::DisplayMode::Both {
+        return Err(crate::diagnostics::DiagnosticsError::Unavailable(
+            "The ghost button detector needs the \"both\" display mode".into(),
+        ))
+        .context("Failed to start ghost button detector");
+    }
+    let raw_data = device_manager
+        .read_config_binary()
+        .await
+        .context("Failed to read config binary")?;
+    let config = BinaryConfig::from_bytes(&raw_data)
+        .map_err(AppError::invalid_configuration)
+        .context("Failed to parse config binary")?;
+    let pressed = device_manager
+        .read_button_states()
+        .await
+        .map(|states| states.get_pressed_buttons())
+        .unwrap_or_default();
+    let inputs = crate::diagnostics::WatchedInput::from_config(&config);
+    crate::diagnostics::start_ghost_detector(inputs, options.unwrap_or_default(), &pressed, move |anomaly| {
+        let _ = app_handle.emit("mapping_anomaly", anomaly);
+    })
+    .context("Failed to start ghost button detector")
+}
+
+/// Stop the ghost button detector; its anomalies stay available in the status
+#[tauri::command]
+pub async fn stop_ghost_detector() -> Result<crate::diagnostics::GhostDetectorStatus, AppError> {
+    Ok(crate::diagnostics::get_ghost_detector().stop())
+}
+
+/// Watched button count and recent anomalies of the ghost button detector
+#[tauri::command]
+pub async fn get_ghost_detector_status() -> Result<crate::diagnostics::GhostDetectorStatus, AppError> {
+    Ok(crate::diagnostics::get_ghost_detector().status())
+}
+
+/// Decode encoder `encoder_id` from the raw monitor stream to report its direction, detents and
+/// whether its configured latch mode matches where the knob rests. Needs raw monitoring.
+#[tauri::command]
+pub async fn start_encoder_test(
+    encoder_id: u8,
+    options: Option<crate::diagnostics::EncoderTestOptions>,
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<crate::diagnostics::EncoderTestReport, AppError> {
+    if !device_manager.is_raw_state_monitoring().await {
+        return Err(crate::diagnostics::DiagnosticsError::Unavailable(
+            "The encoder test needs raw state monitoring".into(),
+        ))
+        .context("Failed to start encoder test");
+    }
+    let raw_data = device_manager
+        .read_config_binary()
+        .await
+        .context("Failed to read config binary")?;
+    let config = BinaryConfig::from_bytes(&raw_data)
+        .map_err(AppError::invalid_configuration)
+        .context("Failed to parse config binary")?;
+    let target = crate::diagnostics::EncoderTarget::from_config(&config, encoder_id)
+        .context("Failed to start encoder test")?;
+    crate::diagnostics::get_encoder_tester()
+        .start(target, options.unwrap_or_default())
+        .context("Failed to start encoder test")
+}
+
+/// Stop the encoder test and return its final report
+#[tauri::command]
+pub async fn stop_encoder_test() -> Result<crate::diagnostics::EncoderTestReport, AppError> {
+    crate::diagnostics::get_encoder_tester()
+        .stop()
+        .context("Failed to stop encoder test")
+}
+
+/// Direction, detent counts and latch mode suggestion of the running or last encoder test
+#[tauri::command]
+pub async fn get_encoder_test_report() -> Result<crate::diagnostics::EncoderTestReport, AppError> {
+    crate::diagnostics::get_encoder_tester()
+        .report()
+        .context("Failed to get encoder test report")
+}
+
+/// Measure raw monitor latency and HID frame loss for `duration_secs` (default 10, max 120).
+/// Raw monitoring and the HID reader must be active for their parts of the report.
+#[tauri::command]
+pub async fn run_latency_benchmark(
+    duration_secs: Option<u64>,
+) -> Result<crate::diagnostics::LatencyReport, AppError> {
+    use crate::diagnostics::latency::{DEFAULT_BENCHMARK_SECS, MAX_BENCHMARK_SECS};
+    let secs = duration_secs.unwrap_or(DEFAULT_BENCHMARK_SECS);
+    if secs == 0 || secs > MAX_BENCHMARK_SECS {
+        return Err(AppError::invalid_argument(format!("duration_secs must be 1-{}", MAX_BENCHMARK_SECS)));
+    }
+    let benchmark = crate::diagnostics::get_latency_benchmark();
+    benchmark.begin().context("Failed to start latency benchmark")?;
+    let duration = std::time::Duration::from_secs(secs);
+    tokio::time::sleep(duration).await;
+    Ok(benchmark.finish(duration))
+}
+
+/// Guided axis filter tuning: samples axis `axis_id` over HID at rest for `rest_ms`, then during a
+/// slow sweep for `sweep_ms`, and recommends `filter_level`, `ewma_alpha` and `deadband` with
+/// traces simulated by an approximate filter model. Emits `axis_tuning_phase` when each phase starts.
+#[tauri::command]
+pub async fn tune_axis_filter(
+    axis_id: u8,
+    rest_ms: Option<u64>,
+    sweep_ms: Option<u64>,
+    app_handle: tauri::AppHandle,
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<crate::diagnostics::AxisTuningReport, AppError> {
+    use crate::diagnostics::axis_tuning::{AXIS_TUNING_PHASE_EVENT, DEFAULT_REST_MS, DEFAULT_SWEEP_MS, MAX_PHASE_MS};
+    let rest_ms = rest_ms.unwrap_or(DEFAULT_REST_MS);
+    let sweep_ms = sweep_ms.unwrap_or(DEFAULT_SWEEP_MS);
+    if !(100..=MAX_PHASE_MS).contains(&rest_ms) || !(100..=MAX_PHASE_MS).contains(&sweep_ms) {
+        return Err(AppError::invalid_argument(format!("rest_ms and sweep_ms must be 100-{}", MAX_PHASE_MS)));
+    }
+    let raw_data = device_manager
+        .read_config_binary()
+        .await
+        .context("Failed to read config binary")?;
+    let config = BinaryConfig::from_bytes(&raw_data)
+        .map_err(AppError::invalid_configuration)
+        .context("Failed to parse config binary")?;
+    let axis = config.stored_config.axes.get(axis_id as usize).copied()
+        .filter(|axis| axis.enabled != 0)
+        .ok_or_else(|| AppError::invalid_argument(format!("Axis {} is not enabled on the device", axis_id)))?;
+    let current = crate::diagnostics::AxisFilterSettings {
+        filter_level: axis.filter_level,
+        ewma_alpha: axis.ewma_alpha,
+        deadband: axis.deadband,
+    };
+
+    let sampler = crate::diagnostics::get_axis_sampler();
+    sampler.begin(axis_id).context("Failed to start axis tuning")?;
+    let _ = app_handle.emit(AXIS_TUNING_PHASE_EVENT, serde_json::json!({ "axis_id": axis_id, "phase": "rest", "duration_ms": rest_ms }));
+    tokio::time::sleep(std::time::Duration::from_millis(rest_ms)).await;
+    let rest = sampler.take();
+    let _ = app_handle.emit(AXIS_TUNING_PHASE_EVENT, serde_json::json!({ "axis_id": axis_id, "phase": "sweep", "duration_ms": sweep_ms }));
+    tokio::time::sleep(std::time::Duration::from_millis(sweep_ms)).await;
+    let sweep = sampler.finish();
+    crate::diagnostics::axis_tuning::analyze(axis_id, current, &rest, &sweep)
+        .context("Failed to analyze axis samples")
+}
+
+// Telemetry server
+
+/// Start the localhost WebSocket telemetry server on `port` (default: the configured port),
+/// replacing a running one. Browser clients need an origin from the telemetry settings.
+#[tauri::command]
+pub async fn start_telemetry_server(
+    port: Option<u16>,
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<crate::telemetry::TelemetryStatus, AppError> {
+    let settings = device_manager.get_app_settings().await.telemetry;
+    crate::telemetry::get_telemetry_server()
+        .start(port.unwrap_or(settings.port), &settings.allowed_origins)
+        .await
+        .context("Failed to start telemetry server")
+}
+
+/// Stop the telemetry server and disconnect its clients
+#[tauri::command]
+pub async fn stop_telemetry_server() -> Result<crate::telemetry::TelemetryStatus, AppError> {
+    Ok(crate::telemetry::get_telemetry_server().stop())
+}
+
+/// Listening address, client count and event counter of the telemetry server
+#[tauri::command]
+pub async fn get_telemetry_status() -> Result<crate::telemetry::TelemetryStatus, AppError> {
+    Ok(crate::telemetry::get_telemetry_server().status())
+}
+
+/// Zip recent logs, the device config (binary and JSON), devices with firmware version and port,
+/// HID mapping, serial metrics and system info into one archive for bug reports
+#[tauri::command]
+pub async fn export_support_bundle(
+    path: String,
+    device_manager: State<'_, Arc<DeviceManager>>,
+    app_handle: tauri::AppHandle,
+) -> Result<crate::support::SupportBundleInfo, AppError> {
+    use tauri::Manager;
+    let mut bundle = crate::support::SupportBundle::new();
+    bundle.add_json("system.json", &crate::support::system_info());
+    bundle.add_json("devices.json", &device_manager.get_devices().await);
+
+    match device_manager.read_config_binary().await {
+        Ok(raw_data) => {
+            match BinaryConfig::from_bytes(&raw_data).and_then(|config| config.to_json()) {
+                Ok(json) => bundle.add("config.json", json),
+                Err(e) => bundle.note(format!("config.json: {}", e)),
+            }
+            bundle.add("config.bin", raw_data);
+        }
+        Err(e) => bundle.note(format!("config.bin: {}", e)),
+    }
+
+    bundle.add_json("hid_mapping.json", &serde_json::json!({
+        "details": device_manager.hid_mapping_details().await,
+        "button_mapping": device_manager.hid_button_mapping().await,
+        "link_stats": device_manager.hid_link_stats().await,
+    }));
+    match device_manager.get_unified_serial_handle().await {
+        Some(handle) => {
+            let metrics = handle.metrics_snapshot();
+            bundle.add_json("serial_metrics.json", &metrics);
+        }
+        None => bundle.note("serial_metrics.json: no serial connection"),
+    }
+    bundle.add_json("buffer_overflows.json", &crate::util::bounded::overflow_snapshot());
+    bundle.add_json("protocol_trace.json", &crate::serial::unified::get_protocol_tracer().snapshot(None));
+
+    match serde_json::to_value(device_manager.get_app_settings().await) {
+        Ok(mut settings) => {
+            crate::support::redact_keys(&mut settings, &["github_token", "proxy_url"]);
+            bundle.add_json("settings.json", &settings);
+        }
+        Err(e) => bundle.note(format!("settings.json: {}", e)),
+    }
+
+    match app_handle.path().app_log_dir() {
+        Ok(dir) => bundle.add_log_dir(&dir, crate::support::MAX_LOG_FILE_BYTES),
+        Err(e) => bundle.note(format!("logs: {}", e)),
+    }
+
+    let info = bundle.write(std::path::Path::new(&path)).context("Failed to write support bundle")?;
+    log::info!("Support bundle written to {} ({} entries, {} bytes)", info.path, info.entries.len(), info.bytes);
+    Ok(info)
+}
+
+/// Look for missing udev rules, device node and group permissions (Linux) or Input Monitoring
+/// consent (macOS) behind generic serial/HID open errors
+#[tauri::command]
+pub async fn check_system_permissions(
+    app_handle: tauri::AppHandle,
+) -> Result<crate::support::permissions::PermissionReport, AppError> {
+    use tauri::Manager;
+    let rules_dir = app_handle.path().app_config_dir().map_err(|e| AppError::internal(e.to_string()))?;
+    let report = tauri::async_runtime::spawn_blocking(move || crate::support::permissions::check(&rules_dir)).await
+        .map_err(|e| AppError::internal(format!("Permission check failed: {}", e)))?;
+    if !report.ok {
+        log::warn!("Permission check found {} issues: {:?}", report.issues.len(), report.issues.iter().map(|i| i.kind).collect::<Vec<_>>());
+    }
+    Ok(report)
+}
+
+/// Write the JoyCore udev rules file into `directory` (default: app config directory) and
+/// return the commands that install it
+#[tauri::command]
+pub async fn write_udev_rules(
+    directory: Option<String>,
+    app_handle: tauri::AppHandle,
+) -> Result<crate::support::permissions::UdevRulesFile, AppError> {
+    use tauri::Manager;
+    let dir = match directory {
+        Some(dir) => PathBuf::from(dir),
+        None => app_handle.path().app_config_dir().map_err(|e| AppError::internal(e.to_string()))?,
+    };
+    let file = crate::support::permissions::write_udev_rules(&dir).context("Failed to write udev rules")?;
+    log::info!("udev rules written to {}", file.path);
+    Ok(file)
+}
+
+/// Change the log level at runtime and persist it in the app settings
+#[tauri::command]
+pub async fn set_log_level(
+    level: String,
+    device_manager: State<'_, Arc<DeviceManager>>,
+) -> Result<String, AppError> {
+    let filter = crate::logging::parse_level(&level).map_err(AppError::invalid_argument)?;
+    let mut settings = device_manager.get_app_settings().await;
+    settings.log_level = filter.to_string().to_ascii_lowercase();
+    crate::logging::set_level(filter);
+    Ok(device_manager.set_app_settings(settings).await.log_level)
+}
+
+/// Newest backend log lines for the in-app log viewer, oldest first (default 200)
+#[tauri::command]
+pub async fn get_recent_logs(lines: Option<usize>) -> Result<Vec<crate::logging::LogLine>, AppError> {
+    Ok(crate::logging::recent_logs(lines.unwrap_or(200)))
+}
+
+/// Level filter and rate limit of the `log-line` event
+#[tauri::command]
+pub async fn get_log_stream_options() -> Result<crate::logging::LogStreamOptions, AppError> {
+    Ok(crate::logging::get_log_stream().options())
+}
+
+#[tauri::command]
+pub async fn set_log_stream_options(
+    options: crate::logging::LogStreamOptions,
+) -> Result<crate::logging::LogStreamOptions, AppError> {
+    let stream = crate::logging::get_log_stream();
+    stream.configure(&options).map_err(AppError::invalid_argument)?;
+    Ok(stream.options())
+}
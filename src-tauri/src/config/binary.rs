@@ -1,11 +1,41 @@
 use serde::{Deserialize, Serialize};
+use crate::util::crc::crc32_update_byte;
+use super::json::{parse_code, CURVE_NAMES};
 
 // Constants from firmware
-const CONFIG_MAGIC: u32 = 0x4A4F5943; // "JOYC"
-const CONFIG_VERSION: u16 = 7; // Current config version from firmware
+pub(crate) const CONFIG_MAGIC: u32 = 0x4A4F5943; // "JOYC"
+pub(crate) const CONFIG_VERSION: u16 = 7; // Current config version from firmware
 const STORED_AXIS_CONFIG_SIZE: usize = 15;
-const MAX_PIN_MAP_COUNT: u8 = 32;
-const MAX_LOGICAL_INPUT_COUNT: u8 = 64;
+pub(crate) const MAX_PIN_MAP_COUNT: u8 = 32;
+pub(crate) const MAX_LOGICAL_INPUT_COUNT: u8 = 64;
+
+// Firmware enums: PinType, InputType and ButtonBehavior (see `json` for their names)
+pub(crate) const PIN_TYPE_UNUSED: u8 = 0;
+pub(crate) const PIN_TYPE_BTN: u8 = 1;
+pub(crate) const PIN_TYPE_BTN_ROW: u8 = 2;
+pub(crate) const PIN_TYPE_BTN_COL: u8 = 3;
+pub(crate) const PIN_TYPE_SHIFTREG_PL: u8 = 4;
+pub(crate) const PIN_TYPE_SHIFTREG_CLK: u8 = 5;
+pub(crate) const PIN_TYPE_SHIFTREG_QH: u8 = 6;
+pub(crate) const INPUT_TYPE_PIN: u8 = 0;
+pub(crate) const INPUT_TYPE_MATRIX: u8 = 1;
+pub(crate) const INPUT_TYPE_SHIFTREG: u8 = 2;
+pub(crate) const BEHAVIOR_NORMAL: u8 = 0;
+pub(crate) const BEHAVIOR_MOMENTARY: u8 = 1;
+pub(crate) const BEHAVIOR_ENC_A: u8 = 2;
+pub(crate) const BEHAVIOR_ENC_B: u8 = 3;
+pub(crate) const BEHAVIOR_SHIFT: u8 = 4;
+pub(crate) const BEHAVIOR_HAT_UP: u8 = 5;
+pub(crate) const BEHAVIOR_HAT_RIGHT: u8 = 6;
+pub(crate) const BEHAVIOR_HAT_DOWN: u8 = 7;
+pub(crate) const BEHAVIOR_HAT_LEFT: u8 = 8;
+/// Highest RP2040 GPIO
+pub(crate) const RP2040_MAX_GPIO: u8 = 29;
+/// App-owned axis flags live in `StoredAxisConfig::reserved[0]`; the firmware leaves reserved
+/// bytes alone, so they survive a device round trip
+pub(crate) const AXIS_FLAG_INVERTED: u8 = 0x01;
+/// `StoredLogicalInput::reserved[0]` flag of inputs on the shift layer (see `config::shift_layer`)
+pub(crate) const INPUT_FLAG_SHIFTED: u8 = 0x01;
 
 #[cfg(test)]
 fn calculate_crc32(data: &[u8]) -> u32 { let mut checksum: u32 = 0xFFFFFFFF; for &byte in data { checksum = crc32_update_byte(checksum, byte); } !checksum }
@@ -104,6 +134,18 @@ impl Default for StoredAxisConfig {
 // Ensure the size matches firmware expectations
 const _: () = assert!(std::mem::size_of::<StoredAxisConfig>() == STORED_AXIS_CONFIG_SIZE);
 
+impl StoredAxisConfig {
+    pub fn is_inverted(&self) -> bool {
+        self.reserved[0] & AXIS_FLAG_INVERTED != 0
+    }
+
+    pub fn set_inverted(&mut self, inverted: bool) {
+        let mut reserved = self.reserved;
+        if inverted { reserved[0] |= AXIS_FLAG_INVERTED } else { reserved[0] &= !AXIS_FLAG_INVERTED }
+        self.reserved = reserved;
+    }
+}
+
 #[repr(C, packed)]
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct StoredPinMapEntry {
@@ -124,6 +166,18 @@ pub struct StoredLogicalInput {
     pub data: [u8; 2], // Changed from [u8; 4] to match firmware
 }
 
+impl StoredLogicalInput {
+    pub fn is_shifted(&self) -> bool {
+        self.reserved[0] & INPUT_FLAG_SHIFTED != 0
+    }
+
+    pub fn set_shifted(&mut self, shifted: bool) {
+        let mut reserved = self.reserved;
+        if shifted { reserved[0] |= INPUT_FLAG_SHIFTED } else { reserved[0] &= !INPUT_FLAG_SHIFTED }
+        self.reserved = reserved;
+    }
+}
+
 #[repr(C, packed)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StoredConfig {
@@ -302,6 +356,31 @@ impl BinaryConfig {
         })
     }
 
+    /// Best-effort parse for damaged data: header, size and checksum are not checked, counts
+    /// above the maximum are clamped and sections cut short by the data are truncated (the counts
+    /// are adjusted to what was read). Returns notes on everything that had to be tolerated.
+    pub fn from_bytes_lenient(data: &[u8]) -> Result<(Self, Vec<String>), String> {
+        let fixed_size = std::mem::size_of::<StoredConfig>();
+        if data.len() < fixed_size {
+            return Err(format!("Data too small for StoredConfig: {} of {} bytes", data.len(), fixed_size));
+        }
+        let mut stored_config = unsafe {
+            std::ptr::read_unaligned(data.as_ptr() as *const StoredConfig)
+        };
+        let mut notes = Vec::new();
+
+        let mut offset = fixed_size;
+        let (pin_map_entries, read) = read_entries::<StoredPinMapEntry>(data, &mut offset, stored_config.pin_map_count, MAX_PIN_MAP_COUNT, "pin map entries", &mut notes);
+        stored_config.pin_map_count = read;
+        let (logical_inputs, read) = read_entries::<StoredLogicalInput>(data, &mut offset, stored_config.logical_input_count, MAX_LOGICAL_INPUT_COUNT, "logical inputs", &mut notes);
+        stored_config.logical_input_count = read;
+        if offset < data.len() {
+            notes.push(format!("{} trailing bytes ignored", data.len() - offset));
+        }
+
+        Ok((Self { stored_config, pin_map_entries, logical_inputs }, notes))
+    }
+
     /// Convert to UI-compatible axis configurations
     pub fn to_axis_configs(&self) -> Vec<UIAxisConfig> {
         let mut configs = Vec::new();
@@ -325,7 +404,7 @@ impl BinaryConfig {
                     center_value: ((stored_axis.min_value as u32 + stored_axis.max_value as u32) / 2) as i32,
                     deadzone: stored_axis.deadband as u32,
                     curve: curve_name.to_string(),
-                    inverted: false, // Not stored in binary format
+                    inverted: stored_axis.is_inverted(),
                 });
             }
         }
@@ -333,12 +412,38 @@ impl BinaryConfig {
         configs
     }
 
+    /// Apply edited axes (matched by `id`): range, deadzone, curve and inversion. The center is
+    /// derived from the range and not stored. Errors leave the config untouched.
+    pub fn apply_axis_configs(&mut self, axes: &[UIAxisConfig]) -> Result<(), String> {
+        let mut stored = self.stored_config.axes;
+        let count = stored.len();
+        for axis in axes {
+            let target = stored.get_mut(axis.id as usize)
+                .ok_or_else(|| format!("Axis {} does not exist (the device has {} axes)", axis.id, count))?;
+            if target.enabled == 0 {
+                return Err(format!("Axis {} is not enabled on the device", axis.id));
+            }
+            let (min, max) = match (u16::try_from(axis.min_value), u16::try_from(axis.max_value)) {
+                (Ok(min), Ok(max)) if min < max => (min, max),
+                _ => return Err(format!("Axis {} range {}..{} must be ascending within 0..{}", axis.id, axis.min_value, axis.max_value, u16::MAX)),
+            };
+            target.min_value = min;
+            target.max_value = max;
+            target.deadband = u16::try_from(axis.deadzone)
+                .map_err(|_| format!("Axis {} deadzone {} exceeds {}", axis.id, axis.deadzone, u16::MAX))?;
+            target.curve = parse_code(CURVE_NAMES, &axis.curve, "curve")?;
+            target.set_inverted(axis.inverted);
+        }
+        self.stored_config.axes = stored;
+        Ok(())
+    }
+
     /// Convert pin maps and logical inputs to UI button configurations
     pub fn to_button_configs(&self) -> Vec<UIButtonConfig> {
         let mut configs = Vec::new();
         
-        // Extract buttons from logical inputs
-        for logical_input in self.logical_inputs.iter() {
+        // Extract buttons from logical inputs; hat directions carry a hat number, not a button
+        for logical_input in self.logical_inputs.iter().filter(|l| !l.is_hat()) {
             // Map firmware behavior values to function names
             // From firmware: enum ButtonBehavior { NORMAL=0, MOMENTARY=1, ENC_A=2, ENC_B=3 };
             let function_name = match logical_input.behavior {
@@ -495,6 +600,30 @@ pub struct UIButtonConfig {
     pub enabled: bool,
 }
 
+/// Read up to `count` (clamped to `max`) records of `T` at `offset`, stopping at the end of `data`
+fn read_entries<T: Copy>(data: &[u8], offset: &mut usize, count: u8, max: u8, what: &str, notes: &mut Vec<String>) -> (Vec<T>, u8) {
+    let size = std::mem::size_of::<T>();
+    if count > max {
+        notes.push(format!("{} count {} exceeds maximum {}; reading {}", what, count, max, max));
+    }
+    let wanted = count.min(max);
+    let mut entries = Vec::new();
+    while entries.len() < wanted as usize && *offset + size <= data.len() {
+        entries.push(unsafe { std::ptr::read_unaligned(data[*offset..].as_ptr() as *const T) });
+        *offset += size;
+    }
+    if entries.len() < wanted as usize {
+        notes.push(format!("Only {} of {} {} present", entries.len(), wanted, what));
+    }
+    let read = entries.len() as u8;
+    (entries, read)
+}
+
+/// Checksum the firmware would compute for a config binary (header checksum field excluded)
+pub(crate) fn firmware_checksum(data: &[u8]) -> u32 {
+    calculate_firmware_crc32(data)
+}
+
 /// Calculate CRC32 checksum using firmware-specific algorithm and coverage order
 /// Coverage order: ConfigHeader (skip checksum field) + rest of StoredConfig + variable data
 fn calculate_firmware_crc32(data: &[u8]) -> u32 {
@@ -518,19 +647,6 @@ fn calculate_firmware_crc32(data: &[u8]) -> u32 {
     !checksum // Final bitwise NOT
 }
 
-/// Update CRC32 checksum with a single byte using firmware algorithm
-fn crc32_update_byte(mut checksum: u32, byte: u8) -> u32 {
-    checksum ^= byte as u32;
-    for _ in 0..8 {
-        if checksum & 1 != 0 {
-            checksum = (checksum >> 1) ^ 0xEDB88320;
-        } else {
-            checksum = checksum >> 1;
-        }
-    }
-    checksum
-}
-
 /// Calculate CRC32 checksum matching firmware implementation exactly
 /// Uses firmware-specific algorithm with polynomial 0xEDB88320
 
@@ -615,4 +731,31 @@ mod tests {
         assert_eq!(config.logical_inputs.len(), parsed.logical_inputs.len());
     }
 
+    #[test]
+    fn persists_axis_range_and_inversion() {
+        let mut config = BinaryConfig::new();
+        config.stored_config.axes[1].enabled = 1;
+        let mut axes = config.to_axis_configs();
+        axes[0].min_value = 100;
+        axes[0].max_value = 4000;
+        axes[0].inverted = true;
+        axes[0].curve = "curve3".into();
+        config.apply_axis_configs(&axes).unwrap();
+
+        let parsed = BinaryConfig::from_bytes(&config.to_bytes().unwrap()).unwrap();
+        let axis = &parsed.to_axis_configs()[0];
+        assert_eq!((axis.id, axis.min_value, axis.max_value, axis.center_value), (1, 100, 4000, 2050));
+        assert!(axis.inverted);
+        assert_eq!(axis.curve, "curve3");
+
+        axes[0].inverted = false;
+        axes[0].max_value = 50;
+        assert!(config.apply_axis_configs(&axes).unwrap_err().contains("ascending"));
+        assert!(config.stored_config.axes[1].is_inverted(), "failed apply leaves the config untouched");
+        axes[0].max_value = 4000;
+        config.apply_axis_configs(&axes).unwrap();
+        let reserved = config.stored_config.axes[1].reserved;
+        assert_eq!(reserved, [0; 3]);
+    }
+
 }
\ No newline at end of file
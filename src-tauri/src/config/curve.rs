@@ -0,0 +1,212 @@
+//! User-defined axis response curves.
+//!
+//! An [`AxisCurve`] maps the normalized axis position (0 = min, 1 = max) to the normalized
+//! output through straight segments between control points. The firmware only stores one of its
+//! preset codes (`curve` in [`StoredAxisConfig`](super::StoredAxisConfig)), so a custom curve is
+//! written as the preset closest to it and [`PresetMatch`] tells how far off that is.
+//!
+//! The firmware does not publish the shape of its presets, so they are *modeled* here as
+//! center-softening power curves of increasing strength: the output moves by `|c|^k` around the
+//! center, with `c` the distance from center and `k` 1 (linear), 1.5, 2 and 3 for `curve1` to
+//! `curve3`. Only `linear` is known to be right. Preset previews, the chosen preset and its
+//! `max_error` are all relative to this model and are estimates of what the device does, so a
+//! preset is only stored once the user has confirmed the match from the preview.
+use serde::{Deserialize, Serialize};
+
+use super::binary::BinaryConfig;
+use super::json::CURVE_NAMES;
+
+pub const MAX_CURVE_POINTS: usize = 32;
+pub const DEFAULT_PREVIEW_SAMPLES: usize = 101;
+pub const MAX_PREVIEW_SAMPLES: usize = 1024;
+/// Modeled exponent of each preset, by curve code (assumed, see module docs)
+const PRESET_EXPONENTS: [f64; 4] = [1.0, 1.5, 2.0, 3.0];
+/// Control points used to sample a preset
+const PRESET_POINTS: usize = 25;
+/// Largest deviation from a modeled preset that still counts as a close match
+const CLOSE_MATCH_TOLERANCE: f64 = 0.005;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CurvePoint {
+    pub x: f64,
+    pub y: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AxisCurve {
+    /// Ascending `x`, from 0 to 1; `y` within 0..=1
+    pub points: Vec<CurvePoint>,
+}
+
+/// Firmware preset a curve is stored as, chosen against the modeled preset shapes
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PresetMatch {
+    pub curve: String,
+    pub code: u8,
+    /// Largest output difference between the curve and the modeled preset (0..=1)
+    pub max_error: f64,
+    /// The modeled preset is within 0.5 % of the curve everywhere; the device's actual preset
+    /// may still differ from the model
+    pub close_match: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AxisCurvePreview {
+    pub points: Vec<CurvePoint>,
+    pub preset: PresetMatch,
+}
+
+/// A firmware preset as editable control points
+#[derive(Debug, Clone, Serialize)]
+pub struct CurvePreset {
+    pub name: String,
+    pub code: u8,
+    pub curve: AxisCurve,
+}
+
+impl AxisCurve {
+    pub fn linear() -> Self {
+        Self { points: vec![CurvePoint { x: 0.0, y: 0.0 }, CurvePoint { x: 1.0, y: 1.0 }] }
+    }
+
+    /// Control points of the model of firmware preset `code`
+    pub fn preset(code: u8) -> Option<Self> {
+        let exponent = *PRESET_EXPONENTS.get(code as usize)?;
+        if code == 0 {
+            return Some(Self::linear());
+        }
+        let points = (0..PRESET_POINTS).map(|i| {
+            let x = i as f64 / (PRESET_POINTS - 1) as f64;
+            CurvePoint { x, y: preset_value(exponent, x) }
+        }).collect();
+        Some(Self { points })
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        let points = &self.points;
+        if points.len() < 2 || points.len() > MAX_CURVE_POINTS {
+            return Err(format!("A curve needs 2-{} points, got {}", MAX_CURVE_POINTS, points.len()));
+        }
+        if let Some(p) = points.iter().find(|p| !(0.0..=1.0).contains(&p.x) || !(0.0..=1.0).contains(&p.y)) {
+            return Err(format!("Curve point ({}, {}) is outside 0..1", p.x, p.y));
+        }
+        if points[0].x != 0.0 || points[points.len() - 1].x != 1.0 {
+            return Err("A curve must start at x = 0 and end at x = 1".to_string());
+        }
+        if let Some(pair) = points.windows(2).find(|pair| pair[1].x <= pair[0].x) {
+            return Err(format!("Curve points must have ascending x ({} follows {})", pair[1].x, pair[0].x));
+        }
+        Ok(())
+    }
+
+    /// Output for axis position `x` (clamped to 0..=1); the curve must be valid
+    pub fn evaluate(&self, x: f64) -> f64 {
+        let x = x.clamp(0.0, 1.0);
+        let i = self.points.partition_point(|p| p.x < x);
+        match (i.checked_sub(1).and_then(|j| self.points.get(j)), self.points.get(i)) {
+            (Some(a), Some(b)) => a.y + (b.y - a.y) * (x - a.x) / (b.x - a.x),
+            (_, Some(p)) | (Some(p), None) => p.y,
+            (None, None) => x,
+        }
+    }
+
+    /// `samples` evenly spaced points of the curve for plotting
+    pub fn preview(&self, samples: usize) -> Vec<CurvePoint> {
+        let samples = samples.clamp(2, MAX_PREVIEW_SAMPLES);
+        (0..samples).map(|i| {
+            let x = i as f64 / (samples - 1) as f64;
+            CurvePoint { x, y: self.evaluate(x) }
+        }).collect()
+    }
+
+    /// The modeled firmware preset closest to this curve (smallest mean squared difference)
+    pub fn nearest_preset(&self) -> PresetMatch {
+        let xs: Vec<f64> = (0..DEFAULT_PREVIEW_SAMPLES).map(|i| i as f64 / (DEFAULT_PREVIEW_SAMPLES - 1) as f64).collect();
+        let (code, _, max_error) = PRESET_EXPONENTS.iter().enumerate().map(|(code, &exponent)| {
+            let errors = xs.iter().map(|&x| (self.evaluate(x) - preset_value(exponent, x)).abs());
+            let (sum_sq, max) = errors.fold((0.0, 0.0f64), |(sum, max), e| (sum + e * e, max.max(e)));
+            (code, sum_sq, max)
+        }).min_by(|a, b| a.1.total_cmp(&b.1)).expect("presets exist");
+        PresetMatch { curve: CURVE_NAMES[code].to_string(), code: code as u8, max_error, close_match: max_error <= CLOSE_MATCH_TOLERANCE }
+    }
+}
+
+fn preset_value(exponent: f64, x: f64) -> f64 {
+    let c = 2.0 * x - 1.0;
+    0.5 + 0.5 * c.signum() * c.abs().powf(exponent)
+}
+
+/// The model of every firmware preset, in code order
+pub fn curve_presets() -> Vec<CurvePreset> {
+    CURVE_NAMES.iter().enumerate().filter_map(|(code, name)| {
+        Some(CurvePreset { name: name.to_string(), code: code as u8, curve: AxisCurve::preset(code as u8)? })
+    }).collect()
+}
+
+impl BinaryConfig {
+    /// Store `curve` on axis `axis` as its nearest firmware preset. `confirmed_preset` is the
+    /// preset the user accepted from the preview; the curve is not stored as any other.
+    pub fn set_axis_curve(&mut self, axis: u8, curve: &AxisCurve, confirmed_preset: &str) -> Result<PresetMatch, String> {
+        curve.validate()?;
+        let count = self.stored_config.axes.len();
+        let stored = self.stored_config.axes.get_mut(axis as usize)
+            .ok_or_else(|| format!("Axis {} does not exist (the device has {} axes)", axis, count))?;
+        if stored.enabled == 0 {
+            return Err(format!("Axis {} is not enabled on the device", axis));
+        }
+        let preset = curve.nearest_preset();
+        if preset.curve != confirmed_preset {
+            return Err(format!("The curve matches preset {}, not the confirmed {}; preview it again", preset.curve, confirmed_preset));
+        }
+        stored.curve = preset.code;
+        Ok(preset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn curve(points: &[(f64, f64)]) -> AxisCurve {
+        AxisCurve { points: points.iter().map(|&(x, y)| CurvePoint { x, y }).collect() }
+    }
+
+    #[test]
+    fn evaluates_and_validates_points() {
+        let c = curve(&[(0.0, 0.0), (0.5, 0.2), (1.0, 1.0)]);
+        c.validate().unwrap();
+        assert!((c.evaluate(0.25) - 0.1).abs() < 1e-9);
+        assert!((c.evaluate(0.75) - 0.6).abs() < 1e-9);
+        assert_eq!((c.evaluate(-1.0), c.evaluate(2.0)), (0.0, 1.0));
+        let preview = c.preview(5);
+        assert_eq!(preview.len(), 5);
+        assert_eq!(preview[2], CurvePoint { x: 0.5, y: 0.2 });
+
+        assert!(curve(&[(0.0, 0.0)]).validate().is_err());
+        assert!(curve(&[(0.0, 0.0), (0.6, 0.5), (0.6, 0.7), (1.0, 1.0)]).validate().unwrap_err().contains("ascending"));
+        assert!(curve(&[(0.1, 0.0), (1.0, 1.0)]).validate().is_err());
+        assert!(curve(&[(0.0, 0.0), (1.0, 1.5)]).validate().is_err());
+    }
+
+    #[test]
+    fn degrades_to_nearest_preset() {
+        for preset in curve_presets() {
+            let found = preset.curve.nearest_preset();
+            assert_eq!(found.curve, preset.name);
+            assert!(found.close_match, "{} max error {}", preset.name, found.max_error);
+        }
+        let soft_center = curve(&[(0.0, 0.0), (0.2, 0.32), (0.4, 0.48), (0.6, 0.52), (0.8, 0.68), (1.0, 1.0)]);
+        let found = soft_center.nearest_preset();
+        assert_eq!(found.curve, "curve2");
+        assert!(!found.close_match);
+
+        let mut config = BinaryConfig::new();
+        config.stored_config.axes[1].enabled = 1;
+        assert!(config.set_axis_curve(1, &soft_center, "curve1").is_err(), "only the confirmed preset is stored");
+        assert_eq!(config.stored_config.axes[1].curve, 0);
+        assert_eq!(config.set_axis_curve(1, &soft_center, "curve2").unwrap().code, 2);
+        let stored = config.stored_config.axes[1].curve;
+        assert_eq!(stored, 2);
+        assert!(config.set_axis_curve(0, &soft_center, "curve2").is_err(), "disabled axis");
+    }
+}
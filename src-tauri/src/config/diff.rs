@@ -0,0 +1,155 @@
+//! Field-level difference between two [`BinaryConfig`]s.
+//!
+//! Both sides are compared in their JSON form (see [`super::json`]), so enumerated values show up
+//! by name. Axes are matched by index, pin map entries by pin name and logical inputs by source
+//! (input type plus `data`, i.e. the physical pin, matrix cell or shift register bit). A matched
+//! entry whose other fields differ is reported as changed; unmatched entries as added or removed.
+use serde::Serialize;
+use serde_json::Value;
+
+use super::binary::BinaryConfig;
+use super::json::{LogicalInputJson, PinMapJson};
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldChange {
+    pub field: String,
+    pub old: Value,
+    pub new: Value,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AxisDiff {
+    pub index: u8,
+    pub changes: Vec<FieldChange>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EntryDiff {
+    /// Pin name, or the logical input source (e.g. `matrix[1,2]`)
+    pub key: String,
+    pub changes: Vec<FieldChange>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ListDiff<T> {
+    pub added: Vec<T>,
+    pub removed: Vec<T>,
+    pub changed: Vec<EntryDiff>,
+}
+
+impl<T> ListDiff<T> {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigDiff {
+    /// True when no field below differs
+    pub identical: bool,
+    /// USB descriptor fields (vid, pid, manufacturer, product)
+    pub usb: Vec<FieldChange>,
+    /// Top-level scalars such as `shift_reg_count`
+    pub general: Vec<FieldChange>,
+    pub axes: Vec<AxisDiff>,
+    pub pins: ListDiff<PinMapJson>,
+    pub inputs: ListDiff<LogicalInputJson>,
+}
+
+/// Differing fields of two serializable records, skipping `ignore`
+fn field_changes<T: Serialize>(old: &T, new: &T, ignore: &[&str]) -> Vec<FieldChange> {
+    let (Ok(Value::Object(old)), Ok(Value::Object(new))) = (serde_json::to_value(old), serde_json::to_value(new)) else {
+        return Vec::new();
+    };
+    old.iter()
+        .filter(|(field, _)| !ignore.contains(&field.as_str()))
+        .filter_map(|(field, value)| {
+            let other = new.get(field).cloned().unwrap_or(Value::Null);
+            (*value != other).then(|| FieldChange { field: field.clone(), old: value.clone(), new: other })
+        })
+        .collect()
+}
+
+/// Match entries by key (first unmatched entry with an equal key) and classify them
+fn diff_list<T: Serialize + Clone>(old: &[T], new: &[T], key: impl Fn(&T) -> String, key_fields: &[&str]) -> ListDiff<T> {
+    let mut matched = vec![false; new.len()];
+    let mut diff = ListDiff { added: Vec::new(), removed: Vec::new(), changed: Vec::new() };
+    for entry in old {
+        let entry_key = key(entry);
+        match new.iter().enumerate().position(|(i, n)| !matched[i] && key(n) == entry_key) {
+            Some(i) => {
+                matched[i] = true;
+                let changes = field_changes(entry, &new[i], key_fields);
+                if !changes.is_empty() {
+                    diff.changed.push(EntryDiff { key: entry_key, changes });
+                }
+            }
+            None => diff.removed.push(entry.clone()),
+        }
+    }
+    diff.added = new.iter().zip(&matched).filter(|(_, m)| !**m).map(|(n, _)| n.clone()).collect();
+    diff
+}
+
+fn input_key(input: &LogicalInputJson) -> String {
+    format!("{}[{},{}]", input.input_type, input.data[0], input.data[1])
+}
+
+/// Everything that changes when going from `old` to `new`
+pub fn diff_configs(old: &BinaryConfig, new: &BinaryConfig) -> ConfigDiff {
+    let (old, new) = (old.to_config_json(), new.to_config_json());
+    let usb = field_changes(&old.usb, &new.usb, &[]);
+    let mut general = Vec::new();
+    if old.shift_reg_count != new.shift_reg_count {
+        general.push(FieldChange { field: "shift_reg_count".to_string(), old: old.shift_reg_count.into(), new: new.shift_reg_count.into() });
+    }
+    let axes: Vec<AxisDiff> = old.axes.iter().zip(&new.axes)
+        .map(|(a, b)| AxisDiff { index: a.index, changes: field_changes(a, b, &["index"]) })
+        .filter(|d| !d.changes.is_empty())
+        .collect();
+    let pins = diff_list(&old.pins, &new.pins, |p| p.name.clone(), &["name"]);
+    let inputs = diff_list(&old.inputs, &new.inputs, input_key, &["input_type", "data"]);
+    ConfigDiff {
+        identical: usb.is_empty() && general.is_empty() && axes.is_empty() && pins.is_empty() && inputs.is_empty(),
+        usb,
+        general,
+        axes,
+        pins,
+        inputs,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{StoredLogicalInput, StoredPinMapEntry};
+
+    fn input(input_type: u8, button: u8, data: [u8; 2]) -> StoredLogicalInput {
+        StoredLogicalInput { input_type, behavior: 0, joy_button_id: button, reverse: 0, encoder_latch_mode: 0, reserved: [0; 3], data }
+    }
+
+    #[test]
+    fn reports_field_level_changes() {
+        let mut old = BinaryConfig::new();
+        old.pin_map_entries.push(StoredPinMapEntry { name: *b"2\0\0\0\0\0\0\0", pin_type: 1, reserved: 0 });
+        old.logical_inputs = vec![input(0, 0, [2, 0]), input(1, 5, [0, 1])];
+        assert!(diff_configs(&old, &old.clone()).identical);
+
+        let mut new = old.clone();
+        new.stored_config.usb_descriptor.pid = 0xA030;
+        new.stored_config.axes[2].curve = 1;
+        new.pin_map_entries[0].pin_type = 2;
+        new.logical_inputs[0].joy_button_id = 9;
+        new.logical_inputs[1] = input(2, 6, [0, 3]);
+
+        let diff = diff_configs(&old, &new);
+        assert!(!diff.identical);
+        assert_eq!(diff.usb, vec![FieldChange { field: "pid".into(), old: "0xA02F".into(), new: "0xA030".into() }]);
+        assert_eq!(diff.axes.len(), 1);
+        assert_eq!((diff.axes[0].index, diff.axes[0].changes[0].new.clone()), (2, Value::from("curve1")));
+        assert_eq!(diff.pins.changed[0].changes[0].field, "pin_type");
+        assert_eq!(diff.inputs.changed, vec![EntryDiff { key: "pin[2,0]".into(), changes: vec![FieldChange { field: "joy_button_id".into(), old: 0.into(), new: 9.into() }] }]);
+        assert_eq!(diff.inputs.removed.len(), 1);
+        assert_eq!(diff.inputs.added[0].input_type, "shift_reg");
+    }
+}
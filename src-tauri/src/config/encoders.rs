@@ -0,0 +1,190 @@
+//! Rotary encoders in [`BinaryConfig`].
+//!
+//! The firmware has no encoder record: an encoder is a pair of logical inputs, one with
+//! behavior `ENC_A` (2) and one with `ENC_B` (3). Each `ENC_A` is paired with the next unpaired
+//! `ENC_B` of the same input type, which is how the firmware builds its encoder list. The A input
+//! carries the clockwise joystick button, the B input the counter-clockwise one. Latch mode and
+//! the `reverse` flag are written to both inputs of a pair.
+use serde::{Deserialize, Serialize};
+
+use super::binary::{BinaryConfig, BEHAVIOR_ENC_A, BEHAVIOR_ENC_B};
+
+/// Firmware encoder latch modes (detent positions per step)
+const LATCH_MODES: &[(u8, &str)] = &[(1, "four3"), (2, "four0"), (3, "two03")];
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UIEncoderConfig {
+    /// Position of the pair among the config's encoders
+    pub id: u8,
+    pub name: String,
+    /// Source of both channels: "pin", "matrix" or "shift_reg" (read-only)
+    pub input_type: String,
+    /// Source addresses of the A and B channels (read-only)
+    pub a_data: [u8; 2],
+    pub b_data: [u8; 2],
+    pub cw_button: u8,
+    pub ccw_button: u8,
+    /// four3 / four0 / two03, or a numeric code the app has no name for
+    pub latch_mode: String,
+    /// Swap the rotation direction
+    pub reversed: bool,
+}
+
+pub(crate) fn latch_mode_name(code: u8) -> String {
+    LATCH_MODES.iter().find(|(c, _)| *c == code).map(|(_, n)| n.to_string()).unwrap_or_else(|| code.to_string())
+}
+
+fn parse_latch_mode(value: &str) -> Result<u8, String> {
+    if let Some((code, _)) = LATCH_MODES.iter().find(|(_, n)| n.eq_ignore_ascii_case(value.trim())) {
+        return Ok(*code);
+    }
+    value.trim().parse::<u8>()
+        .map_err(|_| format!("Unknown encoder latch mode '{}' (expected four3, four0, two03 or a numeric code)", value))
+}
+
+fn input_type_name(code: u8) -> String {
+    match code {
+        0 => "pin".to_string(),
+        1 => "matrix".to_string(),
+        2 => "shift_reg".to_string(),
+        other => other.to_string(),
+    }
+}
+
+pub(crate) fn source_label(input_type: u8, data: [u8; 2]) -> String {
+    match input_type {
+        0 => format!("Pin {}", data[0]),
+        1 => format!("Matrix[{},{}]", data[0], data[1]),
+        2 => format!("ShiftReg[{}].bit{}", data[0], data[1]),
+        _ => format!("{:?}", data),
+    }
+}
+
+impl BinaryConfig {
+    /// Indices into `logical_inputs` of each (A, B) encoder pair, in config order
+    pub fn encoder_pairs(&self) -> Vec<(usize, usize)> {
+        let mut used = vec![false; self.logical_inputs.len()];
+        let mut pairs = Vec::new();
+        for (a, input) in self.logical_inputs.iter().enumerate() {
+            if input.behavior != BEHAVIOR_ENC_A {
+                continue;
+            }
+            let partner = self.logical_inputs.iter().enumerate().skip(a + 1)
+                .find(|(b, other)| !used[*b] && other.behavior == BEHAVIOR_ENC_B && other.input_type == input.input_type)
+                .map(|(b, _)| b);
+            match partner {
+                Some(b) => {
+                    used[b] = true;
+                    pairs.push((a, b));
+                }
+                None => log::warn!("Encoder A channel at logical input {} has no matching B channel", a),
+            }
+        }
+        pairs
+    }
+
+    pub fn to_encoder_configs(&self) -> Vec<UIEncoderConfig> {
+        self.encoder_pairs().into_iter().enumerate().map(|(id, (a, b))| {
+            let a_input = &self.logical_inputs[a];
+            let b_input = &self.logical_inputs[b];
+            UIEncoderConfig {
+                id: id as u8,
+                name: format!("Encoder {} ({} / {})", id + 1, source_label(a_input.input_type, a_input.data), source_label(b_input.input_type, b_input.data)),
+                input_type: input_type_name(a_input.input_type),
+                a_data: a_input.data,
+                b_data: b_input.data,
+                cw_button: a_input.joy_button_id,
+                ccw_button: b_input.joy_button_id,
+                latch_mode: latch_mode_name(a_input.encoder_latch_mode),
+                reversed: a_input.reverse != 0,
+            }
+        }).collect()
+    }
+
+    /// Apply edited encoders (matched by `id`). Only buttons, latch mode and direction change.
+    pub fn apply_encoder_configs(&mut self, encoders: &[UIEncoderConfig]) -> Result<(), String> {
+        let pairs = self.encoder_pairs();
+        let mut updates = Vec::with_capacity(encoders.len());
+        for encoder in encoders {
+            let &(a, b) = pairs.get(encoder.id as usize)
+                .ok_or_else(|| format!("Encoder {} does not exist in the device config", encoder.id))?;
+            if encoder.cw_button == encoder.ccw_button {
+                return Err(format!("Encoder {} uses button {} for both directions", encoder.id, encoder.cw_button));
+            }
+            updates.push((a, b, encoder, parse_latch_mode(&encoder.latch_mode)?));
+        }
+
+        let mut inputs = self.logical_inputs.clone();
+        for (a, b, encoder, latch) in &updates {
+            let a_input = &mut inputs[*a];
+            a_input.joy_button_id = encoder.cw_button;
+            a_input.encoder_latch_mode = *latch;
+            a_input.reverse = u8::from(encoder.reversed);
+            let b_input = &mut inputs[*b];
+            b_input.joy_button_id = encoder.ccw_button;
+            b_input.encoder_latch_mode = *latch;
+            b_input.reverse = u8::from(encoder.reversed);
+        }
+
+        // Every joystick button may only be driven by one logical input
+        let mut owners = std::collections::HashMap::new();
+        for (i, input) in inputs.iter().enumerate().filter(|(_, l)| !l.is_hat()) {
+            if let Some(prev) = owners.insert(input.joy_button_id, i) {
+                return Err(format!("Joystick button {} is assigned to logical inputs {} and {}", input.joy_button_id, prev, i));
+            }
+        }
+        self.logical_inputs = inputs;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::StoredLogicalInput;
+
+    fn input(behavior: u8, button: u8, data: [u8; 2]) -> StoredLogicalInput {
+        StoredLogicalInput { input_type: 0, behavior, joy_button_id: button, reverse: 0, encoder_latch_mode: 1, reserved: [0; 3], data }
+    }
+
+    fn config() -> BinaryConfig {
+        let mut config = BinaryConfig::new();
+        config.logical_inputs = vec![
+            input(0, 0, [2, 0]),
+            input(BEHAVIOR_ENC_A, 1, [3, 0]),
+            input(BEHAVIOR_ENC_A, 3, [5, 0]),
+            input(BEHAVIOR_ENC_B, 2, [4, 0]),
+            input(BEHAVIOR_ENC_B, 4, [6, 0]),
+        ];
+        config.stored_config.logical_input_count = 5;
+        config
+    }
+
+    #[test]
+    fn pairs_encoder_channels_in_order() {
+        let encoders = config().to_encoder_configs();
+        assert_eq!(encoders.len(), 2);
+        assert_eq!((encoders[0].a_data, encoders[0].b_data), ([3, 0], [4, 0]));
+        assert_eq!((encoders[1].cw_button, encoders[1].ccw_button), (3, 4));
+        assert_eq!(encoders[0].latch_mode, "four3");
+    }
+
+    #[test]
+    fn applies_edits_and_rejects_button_conflicts() {
+        let mut config = config();
+        let mut encoders = config.to_encoder_configs();
+        encoders[0].cw_button = 10;
+        encoders[0].latch_mode = "two03".into();
+        encoders[0].reversed = true;
+        config.apply_encoder_configs(&encoders[..1]).unwrap();
+        assert_eq!(config.logical_inputs[1].joy_button_id, 10);
+        assert_eq!(config.logical_inputs[3].encoder_latch_mode, 3);
+        assert_eq!(config.logical_inputs[3].reverse, 1);
+        let bytes = config.to_bytes().unwrap();
+        assert_eq!(BinaryConfig::from_bytes(&bytes).unwrap().to_encoder_configs()[0], config.to_encoder_configs()[0]);
+
+        encoders[1].cw_button = 0;
+        assert!(config.apply_encoder_configs(&encoders[1..]).unwrap_err().contains("Joystick button 0"));
+        assert_eq!(config.logical_inputs[2].joy_button_id, 3, "failed apply leaves the config untouched");
+    }
+}
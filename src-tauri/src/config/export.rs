@@ -0,0 +1,251 @@
+//! Export of the button/axis layout to other input mappers.
+//!
+//! Buttons are numbered the way the host sees them: the HID mapping (report bit -> logical
+//! button) is inverted so a logical input lands on the 1-based button of the report bit that
+//! carries it. Without a mapping the layout is sequential.
+//!
+//! Formats:
+//! - `gremlin`: Joystick Gremlin profile (version 13) with one device and a "Default" mode whose
+//!   buttons and axes carry descriptions. Gremlin matches devices by DirectInput instance GUID,
+//!   which cannot be derived here; the product GUID is written instead, so the device may need to
+//!   be reassigned once after import.
+//! - `json`: [`MappingExport`], a tool-neutral list of buttons and axes.
+
+use serde::{Deserialize, Serialize};
+
+use super::binary::{BinaryConfig, BEHAVIOR_MOMENTARY};
+use super::encoders::source_label;
+use super::json::{code_name, BEHAVIOR_NAMES};
+use super::usb::decode_usb_string;
+
+pub const MAPPING_EXPORT_SCHEMA: &str = "joycore-mapping";
+pub const MAPPING_EXPORT_SCHEMA_VERSION: u32 = 1;
+const GREMLIN_PROFILE_VERSION: u32 = 13;
+
+/// HID usage names of the eight firmware axes, in report order
+const AXIS_NAMES: [&str; 8] = ["X", "Y", "Z", "Rx", "Ry", "Rz", "Slider", "Dial"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MappingExportFormat {
+    Gremlin,
+    Json,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MappingExport {
+    pub schema: String,
+    pub schema_version: u32,
+    pub device: ExportedDevice,
+    pub buttons: Vec<ExportedButton>,
+    pub axes: Vec<ExportedAxis>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportedDevice {
+    pub name: String,
+    pub manufacturer: String,
+    pub vid: String,
+    pub pid: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportedButton {
+    /// Button as seen by the host (1-based, like DirectInput and Gremlin)
+    pub button: u16,
+    /// `joy_button_id` in the device config
+    pub logical_id: u8,
+    /// Physical source, e.g. "Pin 4" or "Matrix[1,2]"
+    pub source: String,
+    pub behavior: String,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportedAxis {
+    /// Axis as seen by the host (1-based)
+    pub axis: u8,
+    pub usage: String,
+    pub pin: u8,
+    pub name: String,
+}
+
+impl BinaryConfig {
+    /// Build the tool-neutral layout. `hid_mapping[bit]` is the logical button carried by report
+    /// bit `bit`, as read from the HID mapping feature report.
+    pub fn to_mapping_export(&self, hid_mapping: Option<&[u8]>) -> MappingExport {
+        let usb = self.stored_config.usb_descriptor;
+        let (vid, pid) = (usb.vid, usb.pid);
+
+        let mut names: Vec<Option<String>> = vec![None; self.logical_inputs.len()];
+        for (n, (a, b)) in self.encoder_pairs().into_iter().enumerate() {
+            names[a] = Some(format!("Encoder {} CW", n + 1));
+            names[b] = Some(format!("Encoder {} CCW", n + 1));
+        }
+
+        let mut buttons: Vec<ExportedButton> = self.logical_inputs.iter().zip(names).filter(|(input, _)| !input.is_hat()).map(|(input, name)| {
+            let source = source_label(input.input_type, input.data);
+            let name = match name {
+                Some(name) => format!("{} ({})", name, source),
+                None if input.behavior == BEHAVIOR_MOMENTARY => format!("{} (momentary)", source),
+                None => source.clone(),
+            };
+            ExportedButton {
+                button: host_button(hid_mapping, input.joy_button_id),
+                logical_id: input.joy_button_id,
+                source,
+                behavior: code_name(BEHAVIOR_NAMES, input.behavior),
+                name,
+            }
+        }).collect();
+        buttons.sort_by_key(|b| b.button);
+
+        let axes = self.stored_config.axes.iter().enumerate()
+            .filter(|(_, axis)| axis.enabled != 0)
+            .map(|(i, axis)| ExportedAxis {
+                axis: i as u8 + 1,
+                usage: AXIS_NAMES[i].to_string(),
+                pin: axis.pin,
+                name: format!("{} (pin {})", AXIS_NAMES[i], axis.pin),
+            })
+            .collect();
+
+        MappingExport {
+            schema: MAPPING_EXPORT_SCHEMA.to_string(),
+            schema_version: MAPPING_EXPORT_SCHEMA_VERSION,
+            device: ExportedDevice {
+                name: decode_usb_string(&usb.product),
+                manufacturer: decode_usb_string(&usb.manufacturer),
+                vid: format!("0x{:04X}", vid),
+                pid: format!("0x{:04X}", pid),
+            },
+            buttons,
+            axes,
+        }
+    }
+
+    /// Render the layout in `format`
+    pub fn export_mapping(&self, format: MappingExportFormat, hid_mapping: Option<&[u8]>) -> Result<String, String> {
+        let export = self.to_mapping_export(hid_mapping);
+        match format {
+            MappingExportFormat::Json => serde_json::to_string_pretty(&export)
+                .map_err(|e| format!("Failed to serialize mapping JSON: {}", e)),
+            MappingExportFormat::Gremlin => {
+                let usb = self.stored_config.usb_descriptor;
+                let (vid, pid) = (usb.vid, usb.pid);
+                Ok(to_gremlin_xml(&export, vid, pid))
+            }
+        }
+    }
+}
+
+/// 1-based host button of a logical button; sequential when the mapping does not list it
+fn host_button(hid_mapping: Option<&[u8]>, logical_id: u8) -> u16 {
+    hid_mapping
+        .and_then(|mapping| mapping.iter().position(|&id| id == logical_id))
+        .map_or(logical_id as u16, |bit| bit as u16)
+        + 1
+}
+
+/// DirectInput product GUID, `{PPPPVVVV-0000-0000-0000-504944564944}` ("PIDVID")
+fn directinput_product_guid(vid: u16, pid: u16) -> String {
+    format!("{{{:04X}{:04X}-0000-0000-0000-504944564944}}", pid, vid)
+}
+
+fn xml_escape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn to_gremlin_xml(export: &MappingExport, vid: u16, pid: u16) -> String {
+    let name = xml_escape(&export.device.name);
+    let mut xml = String::new();
+    xml.push_str("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+    xml.push_str(&format!("<profile version=\"{}\">\n", GREMLIN_PROFILE_VERSION));
+    xml.push_str("    <devices>\n");
+    xml.push_str(&format!(
+        "        <device device-guid=\"{}\" label=\"{}\" name=\"{}\" type=\"joystick\">\n",
+        directinput_product_guid(vid, pid), name, name
+    ));
+    xml.push_str("            <mode name=\"Default\">\n");
+    for axis in &export.axes {
+        xml.push_str(&format!("                <axis description=\"{}\" id=\"{}\"/>\n", xml_escape(&axis.name), axis.axis));
+    }
+    for button in &export.buttons {
+        xml.push_str(&format!("                <button description=\"{}\" id=\"{}\"/>\n", xml_escape(&button.name), button.button));
+    }
+    xml.push_str("            </mode>\n");
+    xml.push_str("        </device>\n");
+    xml.push_str("    </devices>\n");
+    xml.push_str("    <vjoy-devices/>\n");
+    xml.push_str("    <merge-axes/>\n");
+    xml.push_str("    <plugins/>\n");
+    xml.push_str("</profile>\n");
+    xml
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{StoredAxisConfig, StoredLogicalInput};
+    use crate::config::usb::encode_usb_string;
+
+    fn input(input_type: u8, behavior: u8, button: u8, data: [u8; 2]) -> StoredLogicalInput {
+        StoredLogicalInput { input_type, behavior, joy_button_id: button, reverse: 0, encoder_latch_mode: 0, reserved: [0; 3], data }
+    }
+
+    fn config() -> BinaryConfig {
+        let mut config = BinaryConfig::new();
+        config.stored_config.usb_descriptor.product = encode_usb_string("Stick & Throttle", "Product").unwrap();
+        let mut axes = config.stored_config.axes;
+        axes[1] = StoredAxisConfig { enabled: 1, pin: 27, ..StoredAxisConfig::default() };
+        config.stored_config.axes = axes;
+        config.logical_inputs = vec![
+            input(0, 0, 0, [2, 0]),
+            input(1, 1, 1, [1, 2]),
+            input(0, 2, 2, [3, 0]),
+            input(0, 3, 3, [4, 0]),
+        ];
+        config.stored_config.logical_input_count = 4;
+        config
+    }
+
+    #[test]
+    fn numbers_buttons_through_the_hid_mapping() {
+        let sequential = config().to_mapping_export(None);
+        assert_eq!(sequential.buttons.iter().map(|b| b.button).collect::<Vec<_>>(), vec![1, 2, 3, 4]);
+        assert_eq!(sequential.buttons[1].name, "Matrix[1,2] (momentary)");
+        assert_eq!(sequential.buttons[2].name, "Encoder 1 CW (Pin 3)");
+
+        let mapped = config().to_mapping_export(Some(&[3, 2, 1, 0]));
+        let first = &mapped.buttons[0];
+        assert_eq!((first.button, first.logical_id, first.source.as_str()), (1, 3, "Pin 4"));
+        let axes: Vec<_> = mapped.axes.iter().map(|a| (a.axis, a.usage.as_str(), a.pin)).collect();
+        assert_eq!(axes, vec![(2, "Y", 27)], "only enabled axes are exported");
+    }
+
+    #[test]
+    fn writes_gremlin_profile() {
+        let xml = config().export_mapping(MappingExportFormat::Gremlin, None).unwrap();
+        assert!(xml.contains("<profile version=\"13\">"));
+        assert!(xml.contains("device-guid=\"{A02F2E8A-0000-0000-0000-504944564944}\""));
+        assert!(xml.contains("name=\"Stick &amp; Throttle\""));
+        assert!(xml.contains("<button description=\"Pin 2\" id=\"1\"/>"));
+        assert!(xml.contains("<axis description=\"Y (pin 27)\" id=\"2\"/>"));
+
+        let json = config().export_mapping(MappingExportFormat::Json, None).unwrap();
+        let parsed: MappingExport = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.schema, MAPPING_EXPORT_SCHEMA);
+        assert_eq!(parsed.buttons.len(), 4);
+    }
+}
@@ -0,0 +1,238 @@
+//! Hat switches (POV) in [`BinaryConfig`].
+//!
+//! Like encoders, a hat has no record of its own: it is four logical inputs with the behaviors
+//! `hat_up` (5), `hat_right` (6), `hat_down` (7) and `hat_left` (8), read from direct pins or
+//! matrix cells. On these inputs `joy_button_id` holds the hat number instead of a button and
+//! `encoder_latch_mode` the mode: 0 reports eight directions (two adjacent inputs give the
+//! diagonal), 1 only four. Firmware reporting `HATS` sends each hat in the input report (see
+//! `hid::hat`).
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+
+use super::binary::{
+    BinaryConfig, StoredLogicalInput, BEHAVIOR_HAT_DOWN, BEHAVIOR_HAT_LEFT, BEHAVIOR_HAT_RIGHT, BEHAVIOR_HAT_UP,
+    INPUT_TYPE_MATRIX, INPUT_TYPE_PIN, MAX_LOGICAL_INPUT_COUNT,
+};
+use super::encoders::source_label;
+use super::json::{parse_code, INPUT_TYPE_NAMES};
+
+/// Behaviors of the up, right, down and left inputs
+const HAT_BEHAVIORS: [u8; 4] = [BEHAVIOR_HAT_UP, BEHAVIOR_HAT_RIGHT, BEHAVIOR_HAT_DOWN, BEHAVIOR_HAT_LEFT];
+const DIRECTION_NAMES: [&str; 4] = ["up", "right", "down", "left"];
+pub const MAX_HATS: u8 = 4;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HatMode {
+    #[default]
+    EightWay,
+    FourWay,
+}
+
+impl HatMode {
+    fn code(self) -> u8 {
+        match self {
+            HatMode::EightWay => 0,
+            HatMode::FourWay => 1,
+        }
+    }
+
+    fn from_code(code: u8) -> Self {
+        if code == 1 { HatMode::FourWay } else { HatMode::EightWay }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UIHatConfig {
+    /// Hat number in the HID report
+    pub id: u8,
+    /// Generated from the sources (read-only)
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub mode: HatMode,
+    /// Source of all four directions: "pin" or "matrix"
+    pub input_type: String,
+    /// Source addresses of the directions (pin: [gpio, 0]; matrix: [row, col])
+    pub up: [u8; 2],
+    pub right: [u8; 2],
+    pub down: [u8; 2],
+    pub left: [u8; 2],
+}
+
+impl UIHatConfig {
+    fn sources(&self) -> [[u8; 2]; 4] {
+        [self.up, self.right, self.down, self.left]
+    }
+}
+
+impl StoredLogicalInput {
+    /// Direction of a hat input (0 = up, clockwise); None for buttons and encoder channels
+    pub fn hat_direction(&self) -> Option<usize> {
+        HAT_BEHAVIORS.iter().position(|&behavior| behavior == self.behavior)
+    }
+
+    pub fn is_hat(&self) -> bool {
+        self.hat_direction().is_some()
+    }
+}
+
+impl BinaryConfig {
+    /// Indices into `logical_inputs` of the up, right, down and left input of each hat number.
+    /// A direction without an input, or with several, is None.
+    pub fn hat_inputs(&self) -> Vec<(u8, [Option<usize>; 4])> {
+        let mut hats: Vec<(u8, [Option<usize>; 4], [usize; 4])> = Vec::new();
+        for (index, input) in self.logical_inputs.iter().enumerate() {
+            let Some(direction) = input.hat_direction() else { continue };
+            let position = match hats.iter().position(|(id, _, _)| *id == input.joy_button_id) {
+                Some(position) => position,
+                None => {
+                    hats.push((input.joy_button_id, [None; 4], [0; 4]));
+                    hats.len() - 1
+                }
+            };
+            let (_, inputs, counts) = &mut hats[position];
+            inputs[direction] = Some(index);
+            counts[direction] += 1;
+        }
+        hats.sort_by_key(|(id, _, _)| *id);
+        hats.into_iter()
+            .map(|(id, mut inputs, counts)| {
+                for (input, count) in inputs.iter_mut().zip(counts) {
+                    if count > 1 {
+                        *input = None;
+                    }
+                }
+                (id, inputs)
+            })
+            .collect()
+    }
+
+    pub fn to_hat_configs(&self) -> Vec<UIHatConfig> {
+        self.hat_inputs().into_iter().filter_map(|(id, inputs)| {
+            let [Some(up), Some(right), Some(down), Some(left)] = inputs else {
+                log::warn!("Hat {} does not have exactly one input per direction", id);
+                return None;
+            };
+            let [up, right, down, left] = [up, right, down, left].map(|i| &self.logical_inputs[i]);
+            Some(UIHatConfig {
+                id,
+                name: format!("Hat {} ({})", id + 1, [up, right, down, left].map(|l| source_label(l.input_type, l.data)).join(" / ")),
+                mode: HatMode::from_code(up.encoder_latch_mode),
+                input_type: INPUT_TYPE_NAMES.get(up.input_type as usize).map_or_else(|| up.input_type.to_string(), |n| n.to_string()),
+                up: up.data,
+                right: right.data,
+                down: down.data,
+                left: left.data,
+            })
+        }).collect()
+    }
+
+    /// Replace all hats with `hats`. Logical inputs already reading one of the new direction
+    /// sources (buttons wired to the hat's contacts) are removed; hats missing from the list are
+    /// deleted.
+    pub fn set_hat_configs(&mut self, hats: &[UIHatConfig]) -> Result<(), String> {
+        let mut ids = HashSet::new();
+        let mut sources = HashSet::new();
+        let mut new_inputs = Vec::with_capacity(hats.len() * 4);
+        for hat in hats {
+            if hat.id >= MAX_HATS {
+                return Err(format!("Hat {} is out of range (at most {} hats)", hat.id, MAX_HATS));
+            }
+            if !ids.insert(hat.id) {
+                return Err(format!("Hat {} is configured more than once", hat.id));
+            }
+            let input_type = parse_code(INPUT_TYPE_NAMES, &hat.input_type, "input_type")?;
+            if !matches!(input_type, INPUT_TYPE_PIN | INPUT_TYPE_MATRIX) {
+                return Err(format!("Hat {} must read direct pins or matrix cells", hat.id));
+            }
+            for (direction, data) in hat.sources().into_iter().enumerate() {
+                if !sources.insert((input_type, data)) {
+                    return Err(format!(
+                        "{} is used by more than one hat direction (hat {} {})",
+                        source_label(input_type, data), hat.id, DIRECTION_NAMES[direction]
+                    ));
+                }
+                new_inputs.push(StoredLogicalInput {
+                    input_type,
+                    behavior: HAT_BEHAVIORS[direction],
+                    joy_button_id: hat.id,
+                    reverse: 0,
+                    encoder_latch_mode: hat.mode.code(),
+                    reserved: [0; 3],
+                    data,
+                });
+            }
+        }
+
+        let mut inputs: Vec<StoredLogicalInput> = self.logical_inputs.iter()
+            .filter(|l| !l.is_hat() && !sources.contains(&(l.input_type, l.data)))
+            .copied()
+            .collect();
+        inputs.extend(new_inputs);
+        if inputs.len() > MAX_LOGICAL_INPUT_COUNT as usize {
+            return Err(format!("The hats need {} logical inputs (at most {})", inputs.len(), MAX_LOGICAL_INPUT_COUNT));
+        }
+        self.stored_config.logical_input_count = inputs.len() as u8;
+        self.logical_inputs = inputs;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn button(button: u8, data: [u8; 2]) -> StoredLogicalInput {
+        StoredLogicalInput { input_type: INPUT_TYPE_MATRIX, behavior: 0, joy_button_id: button, reverse: 0, encoder_latch_mode: 0, reserved: [0; 3], data }
+    }
+
+    fn hat(id: u8, row: u8) -> UIHatConfig {
+        UIHatConfig {
+            id,
+            name: String::new(),
+            mode: HatMode::FourWay,
+            input_type: "matrix".to_string(),
+            up: [row, 0],
+            right: [row, 1],
+            down: [row, 2],
+            left: [row, 3],
+        }
+    }
+
+    #[test]
+    fn converts_buttons_into_a_hat() {
+        let mut config = BinaryConfig::new();
+        config.logical_inputs = vec![button(0, [0, 0]), button(1, [0, 1]), button(2, [1, 0])];
+        config.set_hat_configs(&[hat(0, 0)]).unwrap();
+        assert_eq!(config.logical_inputs.len(), 5, "buttons on the hat contacts are replaced");
+        assert_eq!(config.logical_inputs[0].joy_button_id, 2);
+
+        let parsed = BinaryConfig::from_bytes(&config.to_bytes().unwrap()).unwrap();
+        let hats = parsed.to_hat_configs();
+        assert_eq!(hats.len(), 1);
+        assert_eq!((hats[0].mode, hats[0].left), (HatMode::FourWay, [0, 3]));
+        assert!(hats[0].name.contains("Matrix[0,0]"));
+
+        config.set_hat_configs(&[]).unwrap();
+        assert!(config.to_hat_configs().is_empty());
+        assert_eq!(config.logical_inputs.len(), 1);
+    }
+
+    #[test]
+    fn rejects_invalid_hats() {
+        let mut config = BinaryConfig::new();
+        assert!(config.set_hat_configs(&[hat(0, 0), hat(0, 1)]).unwrap_err().contains("more than once"));
+        assert!(config.set_hat_configs(&[hat(MAX_HATS, 0)]).unwrap_err().contains("out of range"));
+        assert!(config.set_hat_configs(&[hat(0, 0), hat(1, 0)]).unwrap_err().contains("Matrix[0,0]"));
+        let shift_reg = UIHatConfig { input_type: "shift_reg".to_string(), ..hat(0, 0) };
+        assert!(config.set_hat_configs(&[shift_reg]).is_err());
+
+        // A direction wired twice is not a usable hat
+        config.set_hat_configs(&[hat(0, 0)]).unwrap();
+        config.logical_inputs.push(config.logical_inputs[0]);
+        assert_eq!(config.hat_inputs()[0].1[0], None);
+        assert!(config.to_hat_configs().is_empty());
+    }
+}
@@ -0,0 +1,412 @@
+//! Human-readable JSON form of [`BinaryConfig`] for backup, hand-editing and sharing.
+//!
+//! Schema (`schema_version` 1):
+//!
+//! ```json
+//! {
+//!   "schema": "joycore-config",
+//!   "schema_version": 1,
+//!   "config_version": 7,
+//!   "usb": { "vid": "0x2E8A", "pid": "0xA02F", "manufacturer": "JoyCore", "product": "JoyCore HOTAS" },
+//!   "shift_reg_count": 0,
+//!   "axes": [
+//!     { "index": 0, "enabled": true, "pin": 26, "min_value": 0, "max_value": 1023,
+//!       "filter_level": 2, "ewma_alpha": 6554, "deadband": 0, "curve": "linear", "inverted": false }
+//!   ],
+//!   "pins": [ { "name": "2", "pin_type": "BTN" } ],
+//!   "inputs": [
+//!     { "input_type": "pin", "behavior": "normal", "joy_button_id": 0,
+//!       "reverse": false, "encoder_latch_mode": 0, "data": [2, 0] }
+//!   ]
+//! }
+//! ```
+//!
+//! Enumerated fields use names (`curve`: linear/curve1/curve2/curve3, `pin_type`: UNUSED/BTN/
+//! BTN_ROW/BTN_COL/SHIFTREG_PL/SHIFTREG_CLK/SHIFTREG_QH, `input_type`: pin/matrix/shift_reg,
+//! `behavior`: normal/momentary/encoder_a/encoder_b/shift/hat_up/hat_right/hat_down/hat_left).
+//! Values without a name are written as their decimal code and are accepted back the same way.
+//! USB strings are the Latin-1 characters the firmware shows (see [`super::usb`]), so any stored
+//! bytes round-trip. Axes missing from `axes` keep firmware defaults. Reserved bytes are not exported and are
+//! zeroed on import, except for the axis `inverted` and input `shifted` flags kept in them.
+
+use serde::{Deserialize, Serialize};
+
+use super::binary::{
+    BinaryConfig, StoredAxisConfig, StoredLogicalInput, StoredPinMapEntry, AXIS_FLAG_INVERTED,
+    CONFIG_VERSION, MAX_LOGICAL_INPUT_COUNT,
+};
+use super::usb::{decode_usb_string, encode_usb_string};
+
+pub const CONFIG_JSON_SCHEMA: &str = "joycore-config";
+pub const CONFIG_JSON_SCHEMA_VERSION: u32 = 1;
+
+pub(crate) const AXIS_COUNT: usize = 8;
+pub(crate) const USB_STRING_MAX_BYTES: usize = 31; // 32 byte field, null terminated
+pub(crate) const PIN_NAME_MAX_BYTES: usize = 8;
+
+pub(crate) const CURVE_NAMES: &[&str] = &["linear", "curve1", "curve2", "curve3"];
+pub(crate) const PIN_TYPE_NAMES: &[&str] = &["UNUSED", "BTN", "BTN_ROW", "BTN_COL", "SHIFTREG_PL", "SHIFTREG_CLK", "SHIFTREG_QH"];
+pub(crate) const INPUT_TYPE_NAMES: &[&str] = &["pin", "matrix", "shift_reg"];
+pub(crate) const BEHAVIOR_NAMES: &[&str] = &[
+    "normal", "momentary", "encoder_a", "encoder_b", "shift", "hat_up", "hat_right", "hat_down", "hat_left",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigJson {
+    pub schema: String,
+    pub schema_version: u32,
+    /// Firmware config layout version the document was exported from
+    pub config_version: u16,
+    pub usb: UsbDescriptorJson,
+    #[serde(default)]
+    pub shift_reg_count: u8,
+    #[serde(default)]
+    pub axes: Vec<AxisJson>,
+    #[serde(default)]
+    pub pins: Vec<PinMapJson>,
+    #[serde(default)]
+    pub inputs: Vec<LogicalInputJson>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsbDescriptorJson {
+    /// Hex ("0x2E8A") or decimal
+    pub vid: String,
+    pub pid: String,
+    #[serde(default)]
+    pub manufacturer: String,
+    #[serde(default)]
+    pub product: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AxisJson {
+    pub index: u8,
+    pub enabled: bool,
+    pub pin: u8,
+    pub min_value: u16,
+    pub max_value: u16,
+    pub filter_level: u8,
+    pub ewma_alpha: u16,
+    pub deadband: u16,
+    pub curve: String,
+    #[serde(default)]
+    pub inverted: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PinMapJson {
+    pub name: String,
+    pub pin_type: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogicalInputJson {
+    pub input_type: String,
+    pub behavior: String,
+    pub joy_button_id: u8,
+    #[serde(default)]
+    pub reverse: bool,
+    #[serde(default)]
+    pub encoder_latch_mode: u8,
+    pub data: [u8; 2],
+    /// Reports only while the shift modifier is held
+    #[serde(default)]
+    pub shifted: bool,
+}
+
+pub(crate) fn code_name(names: &[&str], code: u8) -> String {
+    names.get(code as usize).map(|n| n.to_string()).unwrap_or_else(|| code.to_string())
+}
+
+pub(crate) fn parse_code(names: &[&str], value: &str, field: &str) -> Result<u8, String> {
+    if let Some(pos) = names.iter().position(|n| n.eq_ignore_ascii_case(value.trim())) {
+        return Ok(pos as u8);
+    }
+    value.trim().parse::<u8>()
+        .map_err(|_| format!("Unknown {} '{}' (expected one of {:?} or a numeric code)", field, value, names))
+}
+
+fn parse_u16(value: &str, field: &str) -> Result<u16, String> {
+    let v = value.trim();
+    let parsed = match v.strip_prefix("0x").or_else(|| v.strip_prefix("0X")) {
+        Some(hex) => u16::from_str_radix(hex, 16),
+        None => v.parse::<u16>(),
+    };
+    parsed.map_err(|_| format!("Invalid {} '{}'", field, value))
+}
+
+pub(crate) fn bytes_to_string(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).to_string()
+}
+
+pub(crate) fn string_to_bytes<const N: usize>(value: &str, max: usize, field: &str) -> Result<[u8; N], String> {
+    let bytes = value.as_bytes();
+    if bytes.len() > max {
+        return Err(format!("{} '{}' is {} bytes (maximum {})", field, value, bytes.len(), max));
+    }
+    let mut out = [0u8; N];
+    out[..bytes.len()].copy_from_slice(bytes);
+    Ok(out)
+}
+
+impl AxisJson {
+    fn to_stored(&self) -> Result<StoredAxisConfig, String> {
+        if self.min_value > self.max_value {
+            return Err(format!("Axis {} min_value {} exceeds max_value {}", self.index, self.min_value, self.max_value));
+        }
+        let mut axis = StoredAxisConfig {
+            enabled: u8::from(self.enabled),
+            pin: self.pin,
+            min_value: self.min_value,
+            max_value: self.max_value,
+            filter_level: self.filter_level,
+            ewma_alpha: self.ewma_alpha,
+            deadband: self.deadband,
+            curve: parse_code(CURVE_NAMES, &self.curve, "curve")?,
+            reserved: [0; 3],
+        };
+        axis.set_inverted(self.inverted);
+        Ok(axis)
+    }
+}
+
+impl LogicalInputJson {
+    fn to_stored(&self) -> Result<StoredLogicalInput, String> {
+        let mut input = StoredLogicalInput {
+            input_type: parse_code(INPUT_TYPE_NAMES, &self.input_type, "input_type")?,
+            behavior: parse_code(BEHAVIOR_NAMES, &self.behavior, "behavior")?,
+            joy_button_id: self.joy_button_id,
+            reverse: u8::from(self.reverse),
+            encoder_latch_mode: self.encoder_latch_mode,
+            reserved: [0; 3],
+            data: self.data,
+        };
+        input.set_shifted(self.shifted);
+        Ok(input)
+    }
+}
+
+impl BinaryConfig {
+    /// Replace the axis at `axis.index`. The app-owned reserved bits other than inversion are kept.
+    pub fn set_axis(&mut self, axis: &AxisJson) -> Result<(), String> {
+        let mut axes = self.stored_config.axes;
+        let target = axes.get_mut(axis.index as usize)
+            .ok_or_else(|| format!("Axis index {} out of range (0-{})", axis.index, AXIS_COUNT - 1))?;
+        let mut stored = axis.to_stored()?;
+        let mut reserved = target.reserved;
+        reserved[0] = (reserved[0] & !AXIS_FLAG_INVERTED) | (stored.reserved[0] & AXIS_FLAG_INVERTED);
+        stored.reserved = reserved;
+        *target = stored;
+        self.stored_config.axes = axes;
+        Ok(())
+    }
+
+    /// Replace the logical input at `index` (keeping its reserved bytes other than the shift
+    /// flag); `index` equal to the current count appends one
+    pub fn set_logical_input(&mut self, index: usize, input: &LogicalInputJson) -> Result<(), String> {
+        let mut stored = input.to_stored()?;
+        match index.cmp(&self.logical_inputs.len()) {
+            std::cmp::Ordering::Less => {
+                stored.reserved = self.logical_inputs[index].reserved;
+                stored.set_shifted(input.shifted);
+                self.logical_inputs[index] = stored;
+            }
+            std::cmp::Ordering::Equal if index < MAX_LOGICAL_INPUT_COUNT as usize => {
+                self.logical_inputs.push(stored);
+                self.stored_config.logical_input_count = self.logical_inputs.len() as u8;
+            }
+            _ => return Err(format!(
+                "Logical input index {} out of range (the config has {}, at most {})",
+                index, self.logical_inputs.len(), MAX_LOGICAL_INPUT_COUNT,
+            )),
+        }
+        Ok(())
+    }
+
+    /// Convert to the documented JSON schema
+    pub fn to_config_json(&self) -> ConfigJson {
+        let stored = &self.stored_config;
+        let usb = stored.usb_descriptor;
+        let (vid, pid) = (usb.vid, usb.pid);
+        let axes = stored.axes.iter().enumerate().map(|(i, a)| {
+            let a = *a;
+            AxisJson {
+                index: i as u8,
+                enabled: a.enabled != 0,
+                pin: a.pin,
+                min_value: a.min_value,
+                max_value: a.max_value,
+                filter_level: a.filter_level,
+                ewma_alpha: a.ewma_alpha,
+                deadband: a.deadband,
+                curve: code_name(CURVE_NAMES, a.curve),
+                inverted: a.is_inverted(),
+            }
+        }).collect();
+
+        ConfigJson {
+            schema: CONFIG_JSON_SCHEMA.to_string(),
+            schema_version: CONFIG_JSON_SCHEMA_VERSION,
+            config_version: stored.header.version,
+            usb: UsbDescriptorJson {
+                vid: format!("0x{:04X}", vid),
+                pid: format!("0x{:04X}", pid),
+                manufacturer: decode_usb_string(&usb.manufacturer),
+                product: decode_usb_string(&usb.product),
+            },
+            shift_reg_count: stored.shift_reg_count,
+            axes,
+            pins: self.pin_map_entries.iter().map(|p| PinMapJson {
+                name: bytes_to_string(&p.name),
+                pin_type: code_name(PIN_TYPE_NAMES, p.pin_type),
+            }).collect(),
+            inputs: self.logical_inputs.iter().map(|l| LogicalInputJson {
+                input_type: code_name(INPUT_TYPE_NAMES, l.input_type),
+                behavior: code_name(BEHAVIOR_NAMES, l.behavior),
+                joy_button_id: l.joy_button_id,
+                reverse: l.reverse != 0,
+                encoder_latch_mode: l.encoder_latch_mode,
+                data: l.data,
+                shifted: l.is_shifted(),
+            }).collect(),
+        }
+    }
+
+    /// Build a configuration from the JSON schema, validating names, lengths and counts
+    pub fn from_config_json(doc: &ConfigJson) -> Result<Self, String> {
+        if doc.schema != CONFIG_JSON_SCHEMA {
+            return Err(format!("Not a JoyCore config document (schema '{}')", doc.schema));
+        }
+        if doc.schema_version != CONFIG_JSON_SCHEMA_VERSION {
+            return Err(format!("Unsupported schema version {} (expected {})", doc.schema_version, CONFIG_JSON_SCHEMA_VERSION));
+        }
+        if doc.config_version != CONFIG_VERSION {
+            return Err(format!("Config version {} does not match supported version {}", doc.config_version, CONFIG_VERSION));
+        }
+
+        let mut config = BinaryConfig::new();
+        let stored = &mut config.stored_config;
+        stored.usb_descriptor.vid = parse_u16(&doc.usb.vid, "VID")?;
+        stored.usb_descriptor.pid = parse_u16(&doc.usb.pid, "PID")?;
+        stored.usb_descriptor.manufacturer = encode_usb_string(&doc.usb.manufacturer, "Manufacturer")?;
+        stored.usb_descriptor.product = encode_usb_string(&doc.usb.product, "Product")?;
+        stored.shift_reg_count = doc.shift_reg_count;
+
+        let mut seen = [false; AXIS_COUNT];
+        for axis in &doc.axes {
+            let idx = axis.index as usize;
+            if idx >= AXIS_COUNT {
+                return Err(format!("Axis index {} out of range (0-{})", axis.index, AXIS_COUNT - 1));
+            }
+            if std::mem::replace(&mut seen[idx], true) {
+                return Err(format!("Axis {} listed more than once", axis.index));
+            }
+            stored.axes[idx] = axis.to_stored()?;
+        }
+
+        for pin in &doc.pins {
+            config.pin_map_entries.push(StoredPinMapEntry {
+                name: string_to_bytes(&pin.name, PIN_NAME_MAX_BYTES, "Pin name")?,
+                pin_type: parse_code(PIN_TYPE_NAMES, &pin.pin_type, "pin_type")?,
+                reserved: 0,
+            });
+        }
+
+        for input in &doc.inputs {
+            config.logical_inputs.push(input.to_stored()?);
+        }
+
+        let pin_count = u8::try_from(config.pin_map_entries.len()).map_err(|_| "Too many pin map entries".to_string())?;
+        let input_count = u8::try_from(config.logical_inputs.len()).map_err(|_| "Too many logical inputs".to_string())?;
+        config.stored_config.pin_map_count = pin_count;
+        config.stored_config.logical_input_count = input_count;
+        config.stored_config.validate_counts()?;
+        Ok(config)
+    }
+
+    /// Serialize to pretty-printed JSON (see module docs for the schema)
+    pub fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string_pretty(&self.to_config_json())
+            .map_err(|e| format!("Failed to serialize config JSON: {}", e))
+    }
+
+    /// Parse and validate a JSON document produced by [`BinaryConfig::to_json`] (or hand-edited)
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let doc: ConfigJson = serde_json::from_str(json)
+            .map_err(|e| format!("Invalid config JSON: {}", e))?;
+        Self::from_config_json(&doc)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_roundtrip_preserves_binary() {
+        let mut config = BinaryConfig::new();
+        config.stored_config.axes[0].enabled = 1;
+        config.stored_config.axes[0].pin = 26;
+        config.stored_config.axes[0].curve = 2;
+        config.stored_config.usb_descriptor.product[..6].copy_from_slice(b"HOTAS1");
+        config.pin_map_entries.push(StoredPinMapEntry { name: *b"12\0\0\0\0\0\0", pin_type: 2, reserved: 0 });
+        config.logical_inputs.push(StoredLogicalInput {
+            input_type: 1, behavior: 9, joy_button_id: 4, reverse: 1,
+            encoder_latch_mode: 0, reserved: [0; 3], data: [1, 2],
+        });
+        config.stored_config.pin_map_count = 1;
+        config.stored_config.logical_input_count = 1;
+
+        let json = config.to_json().unwrap();
+        assert!(json.contains("\"curve2\"") && json.contains("\"BTN_ROW\"") && json.contains("\"behavior\": \"9\""));
+        let parsed = BinaryConfig::from_json(&json).unwrap();
+        assert_eq!(parsed.to_bytes().unwrap(), config.to_bytes().unwrap());
+    }
+
+    #[test]
+    fn rejects_bad_documents() {
+        let json = BinaryConfig::new().to_json().unwrap();
+        let mut doc: ConfigJson = serde_json::from_str(&json).unwrap();
+        doc.axes[3].curve = "s_curve".to_string();
+        assert!(BinaryConfig::from_config_json(&doc).is_err());
+
+        let mut doc: ConfigJson = serde_json::from_str(&json).unwrap();
+        doc.pins.push(PinMapJson { name: "too_long_name".to_string(), pin_type: "BTN".to_string() });
+        assert!(BinaryConfig::from_config_json(&doc).is_err());
+
+        let mut doc: ConfigJson = serde_json::from_str(&json).unwrap();
+        doc.schema_version = 99;
+        assert!(BinaryConfig::from_config_json(&doc).is_err());
+    }
+
+    #[test]
+    fn sets_single_axis_and_logical_input() {
+        let mut config = BinaryConfig::new();
+        let mut reserved = config.stored_config.axes[2].reserved;
+        reserved[0] = 0x80;
+        config.stored_config.axes[2].reserved = reserved;
+        let mut axis = config.to_config_json().axes[2].clone();
+        axis.enabled = true;
+        axis.curve = "curve3".to_string();
+        axis.inverted = true;
+        config.set_axis(&axis).unwrap();
+        let stored = config.stored_config.axes[2];
+        assert_eq!((stored.enabled, stored.curve, stored.reserved[0]), (1, 3, 0x80 | AXIS_FLAG_INVERTED));
+        axis.index = 8;
+        assert!(config.set_axis(&axis).is_err());
+
+        let input = LogicalInputJson {
+            input_type: "pin".to_string(), behavior: "normal".to_string(), joy_button_id: 0,
+            reverse: false, encoder_latch_mode: 0, data: [2, 0], shifted: false,
+        };
+        config.set_logical_input(0, &input).unwrap();
+        config.set_logical_input(0, &LogicalInputJson { joy_button_id: 5, ..input.clone() }).unwrap();
+        assert_eq!((config.logical_inputs.len(), config.stored_config.logical_input_count), (1, 1));
+        assert_eq!(config.logical_inputs[0].joy_button_id, 5);
+        assert!(config.set_logical_input(2, &input).is_err(), "gaps are not allowed");
+        assert!(config.set_logical_input(1, &LogicalInputJson { behavior: "toggle".to_string(), ..input }).is_err());
+        assert!(BinaryConfig::from_bytes(&config.to_bytes().unwrap()).is_ok());
+    }
+}
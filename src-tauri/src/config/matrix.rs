@@ -0,0 +1,219 @@
+//! Button matrix in [`BinaryConfig`].
+//!
+//! Row and column pins are `BTN_ROW` / `BTN_COL` pin map entries; a pin's row (column) index is
+//! its position among the row (column) entries. Each wired cell is an `INPUT_MATRIX` logical
+//! input with `data = [row, col]`. Writing a matrix replaces all of these while leaving other
+//! pin map entries and logical inputs in place.
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use super::binary::{
+    BinaryConfig, StoredLogicalInput, StoredPinMapEntry, INPUT_TYPE_MATRIX, INPUT_TYPE_PIN, MAX_LOGICAL_INPUT_COUNT,
+    MAX_PIN_MAP_COUNT, PIN_TYPE_BTN_COL, PIN_TYPE_BTN_ROW, PIN_TYPE_UNUSED, RP2040_MAX_GPIO,
+};
+use super::json::{bytes_to_string, code_name, parse_code, string_to_bytes, BEHAVIOR_NAMES, PIN_NAME_MAX_BYTES};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MatrixCell {
+    pub row: u8,
+    pub col: u8,
+    pub joy_button_id: u8,
+    /// normal / momentary / encoder_a / encoder_b
+    pub behavior: String,
+    #[serde(default)]
+    pub reverse: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MatrixConfig {
+    /// GPIO of each row, by row index
+    pub row_pins: Vec<u8>,
+    /// GPIO of each column, by column index
+    pub col_pins: Vec<u8>,
+    pub cells: Vec<MatrixCell>,
+}
+
+impl BinaryConfig {
+    pub fn to_matrix_config(&self) -> MatrixConfig {
+        let pins_of = |pin_type: u8| -> Vec<u8> {
+            self.pin_map_entries.iter()
+                .filter(|p| p.pin_type == pin_type)
+                .filter_map(|p| bytes_to_string(&p.name).trim().parse::<u8>().ok())
+                .collect()
+        };
+        MatrixConfig {
+            row_pins: pins_of(PIN_TYPE_BTN_ROW),
+            col_pins: pins_of(PIN_TYPE_BTN_COL),
+            cells: self.logical_inputs.iter()
+                .filter(|l| l.input_type == INPUT_TYPE_MATRIX)
+                .map(|l| MatrixCell {
+                    row: l.data[0],
+                    col: l.data[1],
+                    joy_button_id: l.joy_button_id,
+                    behavior: code_name(BEHAVIOR_NAMES, l.behavior),
+                    reverse: l.reverse != 0,
+                })
+                .collect(),
+        }
+    }
+
+    /// GPIOs claimed by anything other than the matrix, with a description of the use
+    fn non_matrix_pin_uses(&self) -> HashMap<u8, String> {
+        let mut uses = HashMap::new();
+        for (i, axis) in self.stored_config.axes.iter().enumerate() {
+            if axis.enabled != 0 {
+                uses.insert(axis.pin, format!("axis {}", i + 1));
+            }
+        }
+        for entry in &self.pin_map_entries {
+            if matches!(entry.pin_type, PIN_TYPE_UNUSED | PIN_TYPE_BTN_ROW | PIN_TYPE_BTN_COL) {
+                continue;
+            }
+            if let Ok(pin) = bytes_to_string(&entry.name).trim().parse::<u8>() {
+                uses.insert(pin, format!("pin map entry of type {}", entry.pin_type));
+            }
+        }
+        for input in &self.logical_inputs {
+            if input.input_type == INPUT_TYPE_PIN {
+                uses.entry(input.data[0]).or_insert_with(|| format!("button {}", input.joy_button_id));
+            }
+        }
+        uses
+    }
+
+    /// Replace the matrix pins and cells, validating counts and conflicts with other pin uses
+    pub fn apply_matrix_config(&mut self, matrix: &MatrixConfig) -> Result<(), String> {
+        if matrix.row_pins.is_empty() != matrix.col_pins.is_empty() {
+            return Err("A matrix needs both row and column pins".to_string());
+        }
+        let other_uses = self.non_matrix_pin_uses();
+        let mut matrix_pins = HashSet::new();
+        for (kind, pins) in [("Row", &matrix.row_pins), ("Column", &matrix.col_pins)] {
+            for &pin in pins.iter() {
+                if pin > RP2040_MAX_GPIO {
+                    return Err(format!("{} pin GPIO {} is out of range (0-{})", kind, pin, RP2040_MAX_GPIO));
+                }
+                if !matrix_pins.insert(pin) {
+                    return Err(format!("GPIO {} is used more than once in the matrix", pin));
+                }
+                if let Some(owner) = other_uses.get(&pin) {
+                    return Err(format!("{} pin GPIO {} is already used by {}", kind, pin, owner));
+                }
+            }
+        }
+
+        let mut cells_seen = HashSet::new();
+        let mut cell_inputs = Vec::with_capacity(matrix.cells.len());
+        for cell in &matrix.cells {
+            if cell.row as usize >= matrix.row_pins.len() || cell.col as usize >= matrix.col_pins.len() {
+                return Err(format!("Cell [{},{}] is outside the {}x{} matrix", cell.row, cell.col, matrix.row_pins.len(), matrix.col_pins.len()));
+            }
+            if !cells_seen.insert((cell.row, cell.col)) {
+                return Err(format!("Cell [{},{}] is assigned more than once", cell.row, cell.col));
+            }
+            cell_inputs.push(StoredLogicalInput {
+                input_type: INPUT_TYPE_MATRIX,
+                behavior: parse_code(BEHAVIOR_NAMES, &cell.behavior, "behavior")?,
+                joy_button_id: cell.joy_button_id,
+                reverse: u8::from(cell.reverse),
+                encoder_latch_mode: 0,
+                reserved: [0; 3],
+                data: [cell.row, cell.col],
+            });
+        }
+
+        let mut pin_map_entries: Vec<StoredPinMapEntry> = self.pin_map_entries.iter()
+            .filter(|p| !matches!(p.pin_type, PIN_TYPE_BTN_ROW | PIN_TYPE_BTN_COL))
+            .copied()
+            .collect();
+        for (pin_type, pins) in [(PIN_TYPE_BTN_ROW, &matrix.row_pins), (PIN_TYPE_BTN_COL, &matrix.col_pins)] {
+            for pin in pins.iter() {
+                pin_map_entries.push(StoredPinMapEntry {
+                    name: string_to_bytes(&pin.to_string(), PIN_NAME_MAX_BYTES, "Pin name")?,
+                    pin_type,
+                    reserved: 0,
+                });
+            }
+        }
+        if pin_map_entries.len() > MAX_PIN_MAP_COUNT as usize {
+            return Err(format!("Pin map would have {} entries (maximum {})", pin_map_entries.len(), MAX_PIN_MAP_COUNT));
+        }
+
+        let mut logical_inputs: Vec<StoredLogicalInput> = self.logical_inputs.iter()
+            .filter(|l| l.input_type != INPUT_TYPE_MATRIX)
+            .copied()
+            .collect();
+        let mut buttons: HashMap<u8, String> = logical_inputs.iter()
+            .map(|l| (l.joy_button_id, format!("a logical input of type {}", l.input_type)))
+            .collect();
+        for cell in &matrix.cells {
+            if let Some(owner) = buttons.insert(cell.joy_button_id, format!("matrix cell [{},{}]", cell.row, cell.col)) {
+                return Err(format!("Joystick button {} of cell [{},{}] is already used by {}", cell.joy_button_id, cell.row, cell.col, owner));
+            }
+        }
+        logical_inputs.extend(cell_inputs);
+        if logical_inputs.len() > MAX_LOGICAL_INPUT_COUNT as usize {
+            return Err(format!("Config would have {} logical inputs (maximum {})", logical_inputs.len(), MAX_LOGICAL_INPUT_COUNT));
+        }
+
+        self.stored_config.pin_map_count = pin_map_entries.len() as u8;
+        self.stored_config.logical_input_count = logical_inputs.len() as u8;
+        self.pin_map_entries = pin_map_entries;
+        self.logical_inputs = logical_inputs;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cell(row: u8, col: u8, button: u8) -> MatrixCell {
+        MatrixCell { row, col, joy_button_id: button, behavior: "normal".into(), reverse: false }
+    }
+
+    fn base_config() -> BinaryConfig {
+        let mut config = BinaryConfig::new();
+        config.pin_map_entries.push(StoredPinMapEntry { name: string_to_bytes("2", PIN_NAME_MAX_BYTES, "Pin name").unwrap(), pin_type: 1, reserved: 0 });
+        config.logical_inputs.push(StoredLogicalInput { input_type: INPUT_TYPE_PIN, behavior: 0, joy_button_id: 0, reverse: 0, encoder_latch_mode: 0, reserved: [0; 3], data: [2, 0] });
+        config.stored_config.pin_map_count = 1;
+        config.stored_config.logical_input_count = 1;
+        config
+    }
+
+    #[test]
+    fn writes_and_reads_back_matrix() {
+        let mut config = base_config();
+        let matrix = MatrixConfig { row_pins: vec![4, 5], col_pins: vec![6, 7], cells: vec![cell(0, 0, 1), cell(1, 1, 2)] };
+        config.apply_matrix_config(&matrix).unwrap();
+        let parsed = BinaryConfig::from_bytes(&config.to_bytes().unwrap()).unwrap();
+        assert_eq!(parsed.to_matrix_config(), matrix);
+        assert_eq!(parsed.pin_map_entries.len(), 5);
+
+        // Rewriting replaces the previous matrix rather than appending to it
+        let smaller = MatrixConfig { row_pins: vec![4], col_pins: vec![6], cells: vec![cell(0, 0, 1)] };
+        config.apply_matrix_config(&smaller).unwrap();
+        assert_eq!(config.to_matrix_config(), smaller);
+        assert_eq!(config.logical_inputs.len(), 2);
+    }
+
+    #[test]
+    fn rejects_conflicts() {
+        let mut config = base_config();
+        let pin_clash = MatrixConfig { row_pins: vec![2], col_pins: vec![6], cells: vec![] };
+        assert!(config.apply_matrix_config(&pin_clash).unwrap_err().contains("GPIO 2 is already used"));
+
+        let button_clash = MatrixConfig { row_pins: vec![4], col_pins: vec![6], cells: vec![cell(0, 0, 0)] };
+        assert!(config.apply_matrix_config(&button_clash).unwrap_err().contains("Joystick button 0"));
+
+        let outside = MatrixConfig { row_pins: vec![4], col_pins: vec![6], cells: vec![cell(1, 0, 5)] };
+        assert!(config.apply_matrix_config(&outside).is_err());
+
+        for _ in 0..28 {
+            config.pin_map_entries.push(StoredPinMapEntry { name: [0; 8], pin_type: PIN_TYPE_UNUSED, reserved: 0 });
+        }
+        let too_many = MatrixConfig { row_pins: vec![4, 5], col_pins: vec![6, 7], cells: vec![] };
+        assert!(config.apply_matrix_config(&too_many).unwrap_err().contains("maximum 32"));
+    }
+}
@@ -1,6 +1,35 @@
 pub mod binary;
+pub mod curve;
+pub mod diff;
+pub mod encoders;
+pub mod export;
+pub mod hats;
+pub mod json;
+pub mod matrix;
+pub mod profile;
+pub mod remap;
+pub mod repair;
+pub mod schema;
+pub mod shift_layer;
+pub mod templates;
+pub mod usb;
+pub mod validate;
 
 pub use binary::{
     BinaryConfig, ConfigHeader, StoredConfig, StoredAxisConfig,
     StoredPinMapEntry, StoredLogicalInput, StoredUSBDescriptor,
-};
\ No newline at end of file
+};
+pub use curve::{AxisCurve, AxisCurvePreview, CurvePoint, CurvePreset, PresetMatch};
+pub use diff::{diff_configs, ConfigDiff};
+pub use encoders::UIEncoderConfig;
+pub use export::{MappingExport, MappingExportFormat};
+pub use hats::{HatMode, UIHatConfig};
+pub use json::ConfigJson;
+pub use matrix::{MatrixCell, MatrixConfig};
+pub use profile::ProfileApplyResult;
+pub use remap::{RemapConflict, RemapSummary};
+pub use repair::{diagnose_config_binary, ConfigBinaryDiagnosis};
+pub use schema::config_schema;
+pub use templates::ConfigTemplateInfo;
+pub use usb::UsbDescriptorConfig;
+pub use validate::{validate_config, ConfigDiagnostic, ConfigValidationReport, ValidationSource};
@@ -0,0 +1,169 @@
+//! Translation between app-side profiles ([`ProfileConfig`]) and [`BinaryConfig`].
+//!
+//! A profile tunes what the device already has wired: axes are matched by index and buttons by
+//! joystick button id. Axis min/max are device calibration values (as in `to_axis_configs`);
+//! negative or inverted ranges, such as the full `i16` range of default profiles, keep the
+//! device calibration. Fields the config format cannot store (axis center, button functions
+//! other than normal/momentary, disabled buttons) are skipped with a warning.
+use serde::Serialize;
+
+use super::binary::{BinaryConfig, BEHAVIOR_MOMENTARY, BEHAVIOR_NORMAL};
+use super::diff::ConfigDiff;
+use super::json::{parse_code, BEHAVIOR_NAMES, CURVE_NAMES};
+use crate::serial::protocol::{AxisConfig, ButtonConfig, ProfileConfig};
+
+/// Outcome of writing a profile to the device
+#[derive(Debug, Clone, Serialize)]
+pub struct ProfileApplyResult {
+    pub profile_id: String,
+    /// What changed in the device configuration
+    pub diff: ConfigDiff,
+    /// Profile settings that could not be applied
+    pub warnings: Vec<String>,
+}
+
+impl BinaryConfig {
+    /// Apply a profile's axis and button settings. Returns warnings for skipped settings;
+    /// errors leave the config untouched.
+    pub fn apply_profile(&mut self, profile: &ProfileConfig) -> Result<Vec<String>, String> {
+        let mut warnings = Vec::new();
+        let mut axes = self.stored_config.axes;
+        for axis in &profile.axes {
+            let stored = axes.get_mut(axis.id as usize)
+                .ok_or_else(|| format!("Axis {} does not exist (the device has {} axes)", axis.id, self.stored_config.axes.len()))?;
+            if stored.enabled == 0 {
+                warnings.push(format!("Axis {} is not enabled on the device, skipped", axis.id));
+                continue;
+            }
+            stored.curve = parse_code(CURVE_NAMES, &axis.curve, "curve")?;
+            stored.deadband = axis.deadzone;
+            if axis.min_value >= 0 && axis.min_value < axis.max_value {
+                stored.min_value = axis.min_value as u16;
+                stored.max_value = axis.max_value as u16;
+            } else {
+                warnings.push(format!("Axis {} range {}..{} is not a device calibration range, kept the device range", axis.id, axis.min_value, axis.max_value));
+            }
+            stored.set_inverted(axis.inverted);
+        }
+
+        let mut inputs = self.logical_inputs.clone();
+        for button in &profile.buttons {
+            let wired: Vec<usize> = inputs.iter().enumerate()
+                .filter(|(_, l)| !l.is_hat() && l.joy_button_id == button.id)
+                .map(|(i, _)| i)
+                .collect();
+            if wired.is_empty() {
+                warnings.push(format!("Button {} is not wired in the device config, skipped", button.id));
+                continue;
+            }
+            if !button.enabled {
+                warnings.push(format!("Button {} cannot be disabled in the device config", button.id));
+            }
+            let behavior = match parse_code(BEHAVIOR_NAMES, &button.function, "button function") {
+                Ok(behavior) => behavior,
+                Err(_) => {
+                    warnings.push(format!("Button {} function '{}' is not supported by the device config", button.id, button.function));
+                    continue;
+                }
+            };
+            if !matches!(behavior, BEHAVIOR_NORMAL | BEHAVIOR_MOMENTARY) {
+                // Encoder channels come from the wiring; a profile may only repeat them
+                if wired.iter().any(|&i| inputs[i].behavior != behavior) {
+                    return Err(format!("Button {} cannot be given function '{}' by a profile", button.id, button.function));
+                }
+                continue;
+            }
+            for i in wired {
+                if matches!(inputs[i].behavior, BEHAVIOR_NORMAL | BEHAVIOR_MOMENTARY) {
+                    inputs[i].behavior = behavior;
+                } else {
+                    warnings.push(format!("Button {} is an encoder channel, function left unchanged", button.id));
+                }
+            }
+        }
+
+        self.stored_config.axes = axes;
+        self.logical_inputs = inputs;
+        Ok(warnings)
+    }
+
+    /// Snapshot the device's axes and buttons as a new profile
+    pub fn to_profile(&self, name: &str, description: &str) -> ProfileConfig {
+        let now = chrono::Utc::now();
+        ProfileConfig {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: name.to_string(),
+            description: description.to_string(),
+            axes: self.to_axis_configs().into_iter().map(|a| AxisConfig {
+                id: a.id,
+                name: a.name,
+                min_value: a.min_value.clamp(i16::MIN as i32, i16::MAX as i32) as i16,
+                max_value: a.max_value.clamp(i16::MIN as i32, i16::MAX as i32) as i16,
+                center_value: a.center_value.clamp(i16::MIN as i32, i16::MAX as i32) as i16,
+                deadzone: a.deadzone.min(u16::MAX as u32) as u16,
+                curve: a.curve,
+                inverted: a.inverted,
+            }).collect(),
+            buttons: self.to_button_configs().into_iter().map(|b| ButtonConfig {
+                id: b.id,
+                name: b.name,
+                function: b.function,
+                enabled: b.enabled,
+            }).collect(),
+            created_at: now,
+            modified_at: now,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::StoredLogicalInput;
+
+    fn device_config() -> BinaryConfig {
+        let mut config = BinaryConfig::new();
+        config.stored_config.axes[0].enabled = 1;
+        config.stored_config.axes[0].pin = 26;
+        config.logical_inputs = vec![
+            StoredLogicalInput { input_type: 0, behavior: 0, joy_button_id: 0, reverse: 0, encoder_latch_mode: 0, reserved: [0; 3], data: [2, 0] },
+            StoredLogicalInput { input_type: 0, behavior: 2, joy_button_id: 1, reverse: 0, encoder_latch_mode: 1, reserved: [0; 3], data: [3, 0] },
+        ];
+        config.stored_config.logical_input_count = 2;
+        config
+    }
+
+    #[test]
+    fn round_trips_device_snapshot_through_profile() {
+        let config = device_config();
+        let mut profile = config.to_profile("Snapshot", "");
+        assert_eq!((profile.axes.len(), profile.buttons.len()), (1, 2));
+
+        profile.axes[0].curve = "curve2".into();
+        profile.axes[0].deadzone = 12;
+        profile.buttons[0].function = "momentary".into();
+        let mut applied = config.clone();
+        assert!(applied.apply_profile(&profile).unwrap().is_empty());
+        let axis = applied.stored_config.axes[0];
+        let (curve, deadband, max_value) = (axis.curve, axis.deadband, axis.max_value);
+        assert_eq!((curve, deadband, max_value), (2, 12, 1023));
+        assert_eq!(applied.logical_inputs[0].behavior, BEHAVIOR_MOMENTARY);
+    }
+
+    #[test]
+    fn warns_about_settings_the_config_cannot_store() {
+        let mut config = device_config();
+        let mut profile = config.to_profile("Defaults", "");
+        profile.axes[0].min_value = -32768;
+        profile.axes[0].inverted = true;
+        profile.buttons[0].function = "toggle".into();
+        profile.buttons.push(ButtonConfig { id: 9, name: "Unwired".into(), function: "normal".into(), enabled: true });
+        assert_eq!(config.apply_profile(&profile).unwrap().len(), 3);
+        let min_value = config.stored_config.axes[0].min_value;
+        assert_eq!(min_value, 0);
+        assert!(config.stored_config.axes[0].is_inverted());
+
+        profile.buttons[0].function = "encoder_a".into();
+        assert!(config.apply_profile(&profile).is_err());
+    }
+}
@@ -0,0 +1,154 @@
+//! Reassignment of joystick button IDs (drag-and-drop in the button view).
+//!
+//! Each `(from, to)` move gives every logical input driving button `from` the ID `to`, applied in
+//! list order. When `to` is already driven by another input the conflict mode decides what
+//! happens to it: [`RemapConflict::Swap`] gives it `from`, [`RemapConflict::Shift`] moves every
+//! button between the two slots one step towards `from`, like inserting into a list. Moves onto
+//! a free button never disturb other inputs.
+use serde::{Deserialize, Serialize};
+
+use super::binary::BinaryConfig;
+use super::encoders::source_label;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RemapConflict {
+    #[default]
+    Swap,
+    Shift,
+}
+
+/// One logical input whose button changed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ButtonReassignment {
+    pub input_index: usize,
+    /// Physical source, e.g. "Pin 4" or "Matrix[0,2]"
+    pub source: String,
+    pub from: u8,
+    pub to: u8,
+}
+
+/// A joystick button and the logical inputs driving it after the remap
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ButtonSlot {
+    pub joy_button_id: u8,
+    pub inputs: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RemapSummary {
+    pub reassigned: Vec<ButtonReassignment>,
+    /// Used buttons in ID order
+    pub layout: Vec<ButtonSlot>,
+    pub button_count: u8,
+    /// Buttons below `button_count` that no input drives
+    pub free_buttons: Vec<u8>,
+}
+
+impl BinaryConfig {
+    /// Apply `mapping` to the logical inputs; nothing changes when a move is invalid.
+    /// `button_count` is the number of buttons the HID report carries.
+    pub fn remap_logical_buttons(&mut self, mapping: &[(u8, u8)], conflict: RemapConflict, button_count: u8) -> Result<RemapSummary, String> {
+        // Hat directions carry a hat number instead of a button
+        let buttons: Vec<usize> = (0..self.logical_inputs.len()).filter(|&i| !self.logical_inputs[i].is_hat()).collect();
+        let original: Vec<u8> = buttons.iter().map(|&i| self.logical_inputs[i].joy_button_id).collect();
+        let mut ids = original.clone();
+        for &(from, to) in mapping {
+            if let Some(id) = [from, to].into_iter().find(|&id| id >= button_count) {
+                return Err(format!("Joystick button {} is out of range (the device has {} buttons)", id, button_count));
+            }
+            if !ids.contains(&from) {
+                return Err(format!("No input drives joystick button {}", from));
+            }
+            if from == to {
+                continue;
+            }
+            let occupied = ids.contains(&to);
+            for id in ids.iter_mut() {
+                *id = match (*id, conflict) {
+                    (id, _) if id == from => to,
+                    _ if !occupied => *id,
+                    (id, RemapConflict::Swap) if id == to => from,
+                    (id, RemapConflict::Shift) if from < to && id > from && id <= to => id - 1,
+                    (id, RemapConflict::Shift) if from > to && id >= to && id < from => id + 1,
+                    (id, _) => id,
+                };
+            }
+        }
+
+        let mut reassigned = Vec::new();
+        for (&index, (&old, &new)) in buttons.iter().zip(original.iter().zip(&ids)) {
+            if old != new {
+                let input = &mut self.logical_inputs[index];
+                input.joy_button_id = new;
+                reassigned.push(ButtonReassignment { input_index: index, source: source_label(input.input_type, input.data), from: old, to: new });
+            }
+        }
+        Ok(self.button_layout(button_count, reassigned))
+    }
+
+    fn button_layout(&self, button_count: u8, reassigned: Vec<ButtonReassignment>) -> RemapSummary {
+        let mut layout: Vec<ButtonSlot> = Vec::new();
+        let mut by_id: Vec<(u8, usize)> = self.logical_inputs.iter().enumerate()
+            .filter(|(_, l)| !l.is_hat())
+            .map(|(i, l)| (l.joy_button_id, i))
+            .collect();
+        by_id.sort_unstable();
+        for (id, index) in by_id {
+            match layout.last_mut() {
+                Some(slot) if slot.joy_button_id == id => slot.inputs.push(index),
+                _ => layout.push(ButtonSlot { joy_button_id: id, inputs: vec![index] }),
+            }
+        }
+        let free_buttons = (0..button_count).filter(|id| !layout.iter().any(|s| s.joy_button_id == *id)).collect();
+        RemapSummary { reassigned, layout, button_count, free_buttons }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::StoredLogicalInput;
+
+    fn config(buttons: &[u8]) -> BinaryConfig {
+        let mut config = BinaryConfig::new();
+        config.logical_inputs = buttons.iter().enumerate()
+            .map(|(i, &b)| StoredLogicalInput { input_type: 0, behavior: 0, joy_button_id: b, reverse: 0, encoder_latch_mode: 0, reserved: [0; 3], data: [i as u8 + 2, 0] })
+            .collect();
+        config.stored_config.logical_input_count = buttons.len() as u8;
+        config
+    }
+
+    fn ids(config: &BinaryConfig) -> Vec<u8> {
+        config.logical_inputs.iter().map(|l| l.joy_button_id).collect()
+    }
+
+    #[test]
+    fn swaps_shifts_and_moves() {
+        let mut swapped = config(&[0, 1, 2, 3]);
+        let summary = swapped.remap_logical_buttons(&[(0, 2)], RemapConflict::Swap, 8).unwrap();
+        assert_eq!(ids(&swapped), vec![2, 1, 0, 3]);
+        assert_eq!(summary.reassigned.len(), 2);
+        assert_eq!(summary.reassigned[0].source, "Pin 2");
+
+        let mut shifted = config(&[0, 1, 2, 3]);
+        shifted.remap_logical_buttons(&[(0, 2)], RemapConflict::Shift, 8).unwrap();
+        assert_eq!(ids(&shifted), vec![2, 0, 1, 3]);
+        shifted.remap_logical_buttons(&[(3, 0)], RemapConflict::Shift, 8).unwrap();
+        assert_eq!(ids(&shifted), vec![3, 1, 2, 0]);
+
+        let mut moved = config(&[0, 1, 2]);
+        let summary = moved.remap_logical_buttons(&[(1, 5)], RemapConflict::Shift, 8).unwrap();
+        assert_eq!(ids(&moved), vec![0, 5, 2]);
+        assert_eq!(summary.free_buttons, vec![1, 3, 4, 6, 7]);
+        assert_eq!(summary.layout.iter().map(|s| s.joy_button_id).collect::<Vec<_>>(), vec![0, 2, 5]);
+    }
+
+    #[test]
+    fn rejects_invalid_moves_without_changes() {
+        let mut config = config(&[0, 1]);
+        assert!(config.remap_logical_buttons(&[(0, 1), (1, 8)], RemapConflict::Swap, 8).unwrap_err().contains("out of range"));
+        assert!(config.remap_logical_buttons(&[(4, 1)], RemapConflict::Swap, 8).unwrap_err().contains("No input"));
+        assert_eq!(ids(&config), vec![0, 1]);
+    }
+}
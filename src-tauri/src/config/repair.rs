@@ -0,0 +1,176 @@
+//! Diagnosis and repair of config binaries that fail to parse.
+//!
+//! [`BinaryConfig::from_bytes`] stops at the first failed validation with a one-line error.
+//! [`diagnose_config_binary`] runs every check it can and reports each one, then parses the data
+//! leniently (checksum and size ignored, truncated sections cut short) to show what is still
+//! recoverable. On request it re-serializes the recovered config with a correct size and CRC.
+//! Repair is refused when magic or version are wrong: such data is not a config of this layout.
+use serde::Serialize;
+
+use super::binary::{firmware_checksum, BinaryConfig, ConfigHeader, StoredConfig, CONFIG_MAGIC, CONFIG_VERSION};
+use super::json::ConfigJson;
+use super::validate::{validate_config, ConfigDiagnostic};
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ConfigCheck {
+    /// `length`, `magic`, `version`, `counts`, `size` or `checksum`
+    pub name: &'static str,
+    pub passed: bool,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigBinaryDiagnosis {
+    /// The data parses as is
+    pub valid: bool,
+    pub length: usize,
+    /// Validations in the order the parser runs them; checks that need more data than present are left out
+    pub checks: Vec<ConfigCheck>,
+    /// What the lenient parse had to tolerate
+    pub notes: Vec<String>,
+    /// Fields recovered by the lenient parse
+    pub recovered: Option<ConfigJson>,
+    /// Consistency problems of the recovered config
+    pub diagnostics: Vec<ConfigDiagnostic>,
+    /// Recovered config re-serialized with a fresh size and CRC (only for invalid data, on request)
+    pub repaired: Option<Vec<u8>>,
+    /// Why a requested repair was not produced
+    pub repair_error: Option<String>,
+}
+
+fn check(name: &'static str, passed: bool, detail: String) -> ConfigCheck {
+    ConfigCheck { name, passed, detail }
+}
+
+/// Every validation of `data` that can be run
+fn run_checks(data: &[u8]) -> Vec<ConfigCheck> {
+    let fixed_size = std::mem::size_of::<StoredConfig>();
+    let mut checks = vec![check(
+        "length",
+        data.len() >= fixed_size,
+        format!("{} bytes, fixed part needs {}", data.len(), fixed_size),
+    )];
+    if data.len() < std::mem::size_of::<ConfigHeader>() {
+        return checks;
+    }
+    let header = unsafe { std::ptr::read_unaligned(data.as_ptr() as *const ConfigHeader) };
+    let (magic, version, size, checksum) = (header.magic, header.version, header.size, header.checksum);
+    checks.push(check("magic", magic == CONFIG_MAGIC, format!("0x{:08X}", magic)));
+    checks.push(check("version", version == CONFIG_VERSION, format!("{} (expected {})", version, CONFIG_VERSION)));
+    if data.len() < fixed_size {
+        return checks;
+    }
+    let stored = unsafe { std::ptr::read_unaligned(data.as_ptr() as *const StoredConfig) };
+    let (pin_maps, inputs) = (stored.pin_map_count, stored.logical_input_count);
+    checks.push(match stored.validate_counts() {
+        Ok(()) => check("counts", true, format!("{} pin map entries, {} logical inputs", pin_maps, inputs)),
+        Err(e) => check("counts", false, e),
+    });
+    checks.push(check("size", size as usize == data.len(), format!("header says {}, got {}", size, data.len())));
+    let calculated = firmware_checksum(data);
+    checks.push(check(
+        "checksum",
+        calculated == checksum,
+        format!("header 0x{:08X}, calculated 0x{:08X}", checksum, calculated),
+    ));
+    checks
+}
+
+/// Diagnose `data`; with `repair`, also produce a repaired binary for invalid but recoverable data
+pub fn diagnose_config_binary(data: &[u8], repair: bool) -> ConfigBinaryDiagnosis {
+    let checks = run_checks(data);
+    let valid = BinaryConfig::from_bytes(data).is_ok();
+    let mut diagnosis = ConfigBinaryDiagnosis {
+        valid,
+        length: data.len(),
+        checks,
+        notes: Vec::new(),
+        recovered: None,
+        diagnostics: Vec::new(),
+        repaired: None,
+        repair_error: None,
+    };
+
+    let config = match BinaryConfig::from_bytes_lenient(data) {
+        Ok((config, notes)) => {
+            diagnosis.notes = notes;
+            diagnosis.recovered = Some(config.to_config_json());
+            diagnosis.diagnostics = validate_config(&config);
+            Some(config)
+        }
+        Err(e) => {
+            diagnosis.notes.push(e);
+            None
+        }
+    };
+
+    if repair && !valid {
+        diagnosis.repair_error = match (config, diagnosis.checks.iter().find(|c| !c.passed && matches!(c.name, "length" | "magic" | "version"))) {
+            (_, Some(failed)) => Some(format!("Cannot repair: {} check failed ({})", failed.name, failed.detail)),
+            (None, None) => Some("Cannot repair: nothing recoverable".to_string()),
+            (Some(config), None) => match config.to_bytes().and_then(|bytes| BinaryConfig::from_bytes(&bytes).map(|_| bytes)) {
+                Ok(bytes) => {
+                    diagnosis.repaired = Some(bytes);
+                    None
+                }
+                Err(e) => Some(format!("Repaired config does not parse: {}", e)),
+            },
+        };
+    }
+    diagnosis
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::binary::StoredLogicalInput;
+
+    fn sample() -> Vec<u8> {
+        let mut config = BinaryConfig::new();
+        config.logical_inputs = vec![
+            StoredLogicalInput { input_type: 0, behavior: 0, joy_button_id: 0, reverse: 0, encoder_latch_mode: 0, reserved: [0; 3], data: [2, 0] },
+            StoredLogicalInput { input_type: 0, behavior: 0, joy_button_id: 1, reverse: 0, encoder_latch_mode: 0, reserved: [0; 3], data: [3, 0] },
+        ];
+        config.stored_config.logical_input_count = 2;
+        config.to_bytes().unwrap()
+    }
+
+    fn failed(diagnosis: &ConfigBinaryDiagnosis) -> Vec<&str> {
+        diagnosis.checks.iter().filter(|c| !c.passed).map(|c| c.name).collect()
+    }
+
+    #[test]
+    fn repairs_a_bad_checksum() {
+        let mut data = sample();
+        assert!(diagnose_config_binary(&data, true).valid);
+        let last = data.len() - 1;
+        data[last] ^= 0x01; // a flag bit of the last logical input
+
+        let diagnosis = diagnose_config_binary(&data, true);
+        assert!(!diagnosis.valid);
+        assert_eq!(failed(&diagnosis), vec!["checksum"]);
+        assert_eq!(diagnosis.recovered.as_ref().unwrap().inputs.len(), 2);
+        let repaired = diagnosis.repaired.expect("repaired");
+        assert!(BinaryConfig::from_bytes(&repaired).is_ok());
+        assert_eq!(repaired[12..], data[12..], "only the checksum changes");
+    }
+
+    #[test]
+    fn recovers_truncated_data_and_refuses_foreign_data() {
+        let data = sample();
+        let truncated = &data[..data.len() - 4];
+        let diagnosis = diagnose_config_binary(truncated, true);
+        assert_eq!(failed(&diagnosis), vec!["size", "checksum"]);
+        assert_eq!(diagnosis.recovered.unwrap().inputs.len(), 1);
+        assert!(diagnosis.notes.iter().any(|n| n.contains("Only 1 of 2 logical inputs")));
+        let repaired = BinaryConfig::from_bytes(&diagnosis.repaired.unwrap()).unwrap();
+        assert_eq!(repaired.logical_inputs.len(), 1);
+
+        let mut foreign = data.clone();
+        foreign[0] = 0;
+        let diagnosis = diagnose_config_binary(&foreign, true);
+        assert!(diagnosis.repaired.is_none());
+        assert!(diagnosis.repair_error.unwrap().contains("magic"));
+        assert_eq!(diagnose_config_binary(&data[..8], false).checks.len(), 1);
+    }
+}
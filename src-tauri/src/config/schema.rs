@@ -0,0 +1,173 @@
+//! JSON Schema of the [`ConfigJson`](super::ConfigJson) document.
+//!
+//! Generated from the same constants the binary layout and the JSON conversion use, so external
+//! scripts and the frontend form generator can validate edits before
+//! [`BinaryConfig::from_config_json`](super::BinaryConfig::from_config_json) sees them. Only the
+//! layout version this app edits has a schema; other versions are rejected.
+use serde_json::{json, Value};
+
+use super::binary::{CONFIG_VERSION, MAX_LOGICAL_INPUT_COUNT, MAX_PIN_MAP_COUNT, RP2040_MAX_GPIO};
+use super::json::{
+    AXIS_COUNT, BEHAVIOR_NAMES, CONFIG_JSON_SCHEMA, CONFIG_JSON_SCHEMA_VERSION, CURVE_NAMES,
+    INPUT_TYPE_NAMES, PIN_NAME_MAX_BYTES, PIN_TYPE_NAMES, USB_STRING_MAX_BYTES,
+};
+
+pub const JSON_SCHEMA_DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// Enumerated field: one of `names` (any case) or a decimal code
+fn code_field(names: &[&str], description: &str) -> Value {
+    json!({
+        "description": description,
+        "anyOf": [
+            { "type": "string", "enum": names },
+            { "type": "string", "pattern": "^[0-9]{1,3}$" }
+        ]
+    })
+}
+
+fn uint(max: u64, description: &str) -> Value {
+    json!({ "type": "integer", "minimum": 0, "maximum": max, "description": description })
+}
+
+/// Schema for documents of config layout `config_version`; `firmware_version` only labels it
+pub fn config_schema(config_version: u16, firmware_version: Option<&str>) -> Result<Value, String> {
+    if config_version != CONFIG_VERSION {
+        return Err(format!(
+            "No schema for config version {} (this app supports version {})",
+            config_version, CONFIG_VERSION
+        ));
+    }
+    let u16_code = json!({
+        "type": "string",
+        "pattern": "^(0[xX][0-9a-fA-F]{1,4}|[0-9]{1,5})$",
+        "description": "Hex (\"0x2E8A\") or decimal"
+    });
+    let usb_string = json!({
+        "type": "string",
+        "maxLength": USB_STRING_MAX_BYTES,
+        "pattern": "^([!-~\\u00A1-\\u00FF]([ -~\\u00A0-\\u00FF]*[!-~\\u00A1-\\u00FF])?)?$",
+        "description": "Printable Latin-1 without leading or trailing spaces; the device shows one character per stored byte"
+    });
+
+    Ok(json!({
+        "$schema": JSON_SCHEMA_DIALECT,
+        "$id": format!("urn:joycore:config:{}:{}", CONFIG_JSON_SCHEMA_VERSION, config_version),
+        "title": "JoyCore configuration",
+        "description": match firmware_version {
+            Some(version) => format!("Config layout version {} (firmware {})", config_version, version),
+            None => format!("Config layout version {}", config_version),
+        },
+        "type": "object",
+        "required": ["schema", "schema_version", "config_version", "usb"],
+        "properties": {
+            "schema": { "const": CONFIG_JSON_SCHEMA },
+            "schema_version": { "const": CONFIG_JSON_SCHEMA_VERSION },
+            "config_version": { "const": config_version },
+            "usb": {
+                "type": "object",
+                "required": ["vid", "pid"],
+                "properties": {
+                    "vid": u16_code.clone(),
+                    "pid": u16_code,
+                    "manufacturer": usb_string.clone(),
+                    "product": usb_string
+                },
+                "additionalProperties": false
+            },
+            "shift_reg_count": uint(u8::MAX as u64, "Number of chained 74HC165 shift registers"),
+            "axes": {
+                "type": "array",
+                "maxItems": AXIS_COUNT,
+                "description": "Axes missing from the list keep firmware defaults; each index at most once",
+                "items": {
+                    "type": "object",
+                    "required": ["index", "enabled", "pin", "min_value", "max_value", "filter_level", "ewma_alpha", "deadband", "curve"],
+                    "properties": {
+                        "index": uint(AXIS_COUNT as u64 - 1, "Axis slot (X, Y, Z, RX, RY, RZ, S1, S2)"),
+                        "enabled": { "type": "boolean" },
+                        "pin": uint(u8::MAX as u64, "Analog GPIO"),
+                        "min_value": uint(u16::MAX as u64, "Raw reading mapped to the axis minimum; at most max_value"),
+                        "max_value": uint(u16::MAX as u64, "Raw reading mapped to the axis maximum"),
+                        "filter_level": uint(u8::MAX as u64, "Firmware filter preset"),
+                        "ewma_alpha": uint(u16::MAX as u64, "Smoothing factor in 1/65536 units"),
+                        "deadband": uint(u16::MAX as u64, "Raw change ignored around the last value"),
+                        "curve": code_field(CURVE_NAMES, "Response curve"),
+                        "inverted": { "type": "boolean", "default": false }
+                    },
+                    "additionalProperties": false
+                }
+            },
+            "pins": {
+                "type": "array",
+                "maxItems": MAX_PIN_MAP_COUNT,
+                "items": {
+                    "type": "object",
+                    "required": ["name", "pin_type"],
+                    "properties": {
+                        "name": {
+                            "type": "string",
+                            "maxLength": PIN_NAME_MAX_BYTES,
+                            "description": format!("GPIO number (0-{}) as text", RP2040_MAX_GPIO)
+                        },
+                        "pin_type": code_field(PIN_TYPE_NAMES, "Role of the pin")
+                    },
+                    "additionalProperties": false
+                }
+            },
+            "inputs": {
+                "type": "array",
+                "maxItems": MAX_LOGICAL_INPUT_COUNT,
+                "items": {
+                    "type": "object",
+                    "required": ["input_type", "behavior", "joy_button_id", "data"],
+                    "properties": {
+                        "input_type": code_field(INPUT_TYPE_NAMES, "Source of the input"),
+                        "behavior": code_field(BEHAVIOR_NAMES, "Button behavior"),
+                        "joy_button_id": uint(MAX_LOGICAL_INPUT_COUNT as u64 - 1, "HID button the input drives; the hat number for hat directions"),
+                        "reverse": { "type": "boolean", "default": false },
+                        "shifted": { "type": "boolean", "default": false, "description": "Reports only while the shift modifier is held" },
+                        "encoder_latch_mode": uint(u8::MAX as u64, "Latch mode of encoder inputs; 1 makes hat directions 4-way"),
+                        "data": {
+                            "type": "array",
+                            "items": { "type": "integer", "minimum": 0, "maximum": u8::MAX },
+                            "minItems": 2,
+                            "maxItems": 2,
+                            "description": "pin: [gpio, 0]; matrix: [row, col]; shift_reg: [register, bit]"
+                        }
+                    },
+                    "additionalProperties": false
+                }
+            }
+        },
+        "additionalProperties": false
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::BinaryConfig;
+
+    #[test]
+    fn describes_exported_documents() {
+        let schema = config_schema(CONFIG_VERSION, Some("1.4.0")).unwrap();
+        assert_eq!(schema["properties"]["config_version"]["const"], CONFIG_VERSION);
+        assert_eq!(schema["properties"]["inputs"]["maxItems"], MAX_LOGICAL_INPUT_COUNT);
+        assert!(schema["description"].as_str().unwrap().contains("1.4.0"));
+        assert!(config_schema(CONFIG_VERSION + 1, None).is_err());
+
+        // Every field an export writes is described, and every required field is exported
+        let doc = serde_json::to_value(BinaryConfig::new().to_config_json()).unwrap();
+        let props = &schema["properties"];
+        for key in doc.as_object().unwrap().keys() {
+            assert!(props.get(key).is_some(), "{} missing from schema", key);
+        }
+        let axis_schema = &props["axes"]["items"];
+        for (key, _) in doc["axes"][0].as_object().unwrap() {
+            assert!(axis_schema["properties"].get(key).is_some(), "axis field {} missing from schema", key);
+        }
+        for key in axis_schema["required"].as_array().unwrap() {
+            assert!(doc["axes"][0].get(key.as_str().unwrap()).is_some());
+        }
+    }
+}
@@ -0,0 +1,207 @@
+//! Shift layer ("pinky shift"): while a modifier input is held, layered inputs report a second
+//! joystick button instead of their base one, doubling the usable buttons.
+//!
+//! Firmware reporting `SHIFT_LAYERS` stores the layer in the config. The modifier is a logical
+//! input with behavior `shift` (4), which reports no button of its own. Each layered assignment
+//! is a copy of its base input (same source) with the shifted button and the `shifted` flag in
+//! its first reserved byte; while the modifier is held the firmware reports the copies in place
+//! of their base inputs. Older firmware has no such representation, so the virtual joystick
+//! bridge emulates the layer from the same [`ShiftLayer`], keyed by joystick buttons.
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+
+use super::binary::{BinaryConfig, StoredLogicalInput, BEHAVIOR_MOMENTARY, BEHAVIOR_SHIFT, MAX_LOGICAL_INPUT_COUNT};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LayeredButton {
+    pub base_button: u8,
+    /// Button reported instead of `base_button` while the modifier is held
+    pub shifted_button: u8,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShiftLayer {
+    /// Joystick button of the modifier input
+    pub modifier_button: u8,
+    #[serde(default)]
+    pub assignments: Vec<LayeredButton>,
+}
+
+impl ShiftLayer {
+    pub fn shifted_button(&self, base_button: u8) -> Option<u8> {
+        self.assignments.iter().find(|a| a.base_button == base_button).map(|a| a.shifted_button)
+    }
+}
+
+/// Where the layer is applied
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShiftLayerMode {
+    /// Stored in the config and run by the firmware
+    Firmware,
+    /// Emulated by the virtual joystick bridge while it runs
+    Bridge,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ShiftLayerState {
+    pub mode: ShiftLayerMode,
+    pub layer: Option<ShiftLayer>,
+    pub button_count: u8,
+    /// Buttons no base-layer input drives, available as shifted buttons
+    pub free_buttons: Vec<u8>,
+}
+
+fn can_be_layered(input: &StoredLogicalInput) -> bool {
+    input.behavior <= BEHAVIOR_MOMENTARY
+}
+
+impl BinaryConfig {
+    /// Button inputs outside the shift layer, with their index
+    fn base_inputs(&self) -> impl Iterator<Item = (usize, &StoredLogicalInput)> {
+        self.logical_inputs.iter().enumerate().filter(|(_, l)| !l.is_shifted() && !l.is_hat())
+    }
+
+    /// Layer stored in the firmware representation
+    pub fn shift_layer(&self) -> Option<ShiftLayer> {
+        let modifier = self.base_inputs().find(|(_, l)| l.behavior == BEHAVIOR_SHIFT)?.1;
+        let assignments = self.logical_inputs.iter()
+            .filter(|l| l.is_shifted())
+            .filter_map(|layered| {
+                let (_, base) = self.base_inputs().find(|(_, b)| b.input_type == layered.input_type && b.data == layered.data)?;
+                Some(LayeredButton { base_button: base.joy_button_id, shifted_button: layered.joy_button_id })
+            })
+            .collect();
+        Some(ShiftLayer { modifier_button: modifier.joy_button_id, assignments })
+    }
+
+    /// Buttons below `button_count` free for shifted assignments
+    pub fn free_base_buttons(&self, button_count: u8) -> Vec<u8> {
+        let used: HashSet<u8> = self.base_inputs().map(|(_, l)| l.joy_button_id).collect();
+        (0..button_count).filter(|b| !used.contains(b)).collect()
+    }
+
+    pub fn check_shift_layer(&self, layer: &ShiftLayer, button_count: u8) -> Result<(), String> {
+        let drivers = |button: u8| self.base_inputs().filter(move |(_, l)| l.joy_button_id == button).map(|(_, l)| l);
+        let modifier = layer.modifier_button;
+        if drivers(modifier).next().is_none() {
+            return Err(format!("No input drives modifier button {}", modifier));
+        }
+        if drivers(modifier).any(|l| !can_be_layered(l) && l.behavior != BEHAVIOR_SHIFT) {
+            return Err(format!("Button {} is an encoder channel and cannot be the modifier", modifier));
+        }
+        let mut bases = HashSet::new();
+        let mut targets = HashSet::new();
+        for LayeredButton { base_button, shifted_button } in &layer.assignments {
+            let (base, shifted) = (*base_button, *shifted_button);
+            if base == modifier || shifted == modifier {
+                return Err(format!("Modifier button {} cannot be part of an assignment", modifier));
+            }
+            if drivers(base).next().is_none() {
+                return Err(format!("No input drives button {}", base));
+            }
+            if drivers(base).any(|l| !can_be_layered(l)) {
+                return Err(format!("Button {} is an encoder channel and cannot be layered", base));
+            }
+            if shifted >= button_count {
+                return Err(format!("Shifted button {} is out of range (the device has {} buttons)", shifted, button_count));
+            }
+            if drivers(shifted).next().is_some() {
+                return Err(format!("Shifted button {} is already used on the base layer", shifted));
+            }
+            if !bases.insert(base) {
+                return Err(format!("Button {} is layered more than once", base));
+            }
+            if !targets.insert(shifted) {
+                return Err(format!("Shifted button {} is assigned more than once", shifted));
+            }
+        }
+        Ok(())
+    }
+
+    /// Replace the stored layer (None removes it); call [`check_shift_layer`](Self::check_shift_layer) first
+    pub fn set_shift_layer(&mut self, layer: Option<&ShiftLayer>) -> Result<(), String> {
+        let mut inputs: Vec<StoredLogicalInput> = self.logical_inputs.iter().filter(|l| !l.is_shifted()).copied().collect();
+        for input in inputs.iter_mut().filter(|l| l.behavior == BEHAVIOR_SHIFT) {
+            input.behavior = 0;
+        }
+        if let Some(layer) = layer {
+            for input in inputs.iter_mut().filter(|l| !l.is_hat() && l.joy_button_id == layer.modifier_button) {
+                input.behavior = BEHAVIOR_SHIFT;
+            }
+            let mut layered = Vec::new();
+            for assignment in &layer.assignments {
+                for base in inputs.iter().filter(|l| !l.is_hat() && l.joy_button_id == assignment.base_button) {
+                    let mut copy = *base;
+                    copy.joy_button_id = assignment.shifted_button;
+                    copy.set_shifted(true);
+                    layered.push(copy);
+                }
+            }
+            inputs.extend(layered);
+        }
+        if inputs.len() > MAX_LOGICAL_INPUT_COUNT as usize {
+            return Err(format!(
+                "The shift layer needs {} logical inputs (at most {})", inputs.len(), MAX_LOGICAL_INPUT_COUNT
+            ));
+        }
+        self.stored_config.logical_input_count = inputs.len() as u8;
+        self.logical_inputs = inputs;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(buttons: &[(u8, u8)]) -> BinaryConfig {
+        let mut config = BinaryConfig::new();
+        config.logical_inputs = buttons.iter().enumerate()
+            .map(|(i, &(button, behavior))| StoredLogicalInput { input_type: 0, behavior, joy_button_id: button, reverse: 0, encoder_latch_mode: 0, reserved: [0; 3], data: [i as u8 + 2, 0] })
+            .collect();
+        config.stored_config.logical_input_count = buttons.len() as u8;
+        config
+    }
+
+    fn layer() -> ShiftLayer {
+        ShiftLayer {
+            modifier_button: 0,
+            assignments: vec![LayeredButton { base_button: 1, shifted_button: 10 }, LayeredButton { base_button: 2, shifted_button: 11 }],
+        }
+    }
+
+    #[test]
+    fn stores_and_reads_back_the_layer() {
+        let mut config = config(&[(0, 0), (1, 0), (2, 1)]);
+        config.check_shift_layer(&layer(), 32).unwrap();
+        config.set_shift_layer(Some(&layer())).unwrap();
+        assert_eq!(config.logical_inputs.len(), 5);
+        assert_eq!(config.logical_inputs[0].behavior, BEHAVIOR_SHIFT);
+        assert_eq!(config.logical_inputs[3].data, config.logical_inputs[1].data);
+
+        let bytes = config.to_bytes().unwrap();
+        let parsed = BinaryConfig::from_bytes(&bytes).unwrap();
+        assert_eq!(parsed.shift_layer(), Some(layer()));
+        assert!(!parsed.free_base_buttons(12).contains(&1));
+        assert!(parsed.free_base_buttons(12).contains(&10));
+
+        config.set_shift_layer(None).unwrap();
+        assert_eq!(config.logical_inputs.len(), 3);
+        assert_eq!(config.shift_layer(), None);
+    }
+
+    #[test]
+    fn rejects_conflicting_layers() {
+        let config = config(&[(0, 0), (1, 0), (2, 0), (3, 2), (4, 3)]);
+        let mut bad = layer();
+        bad.assignments[1].shifted_button = 1;
+        assert!(config.check_shift_layer(&bad, 32).unwrap_err().contains("base layer"));
+        bad.assignments[1].shifted_button = 10;
+        assert!(config.check_shift_layer(&bad, 32).unwrap_err().contains("more than once"));
+        assert!(config.check_shift_layer(&ShiftLayer { modifier_button: 3, assignments: vec![] }, 32).is_err());
+        assert!(config.check_shift_layer(&ShiftLayer { modifier_button: 9, assignments: vec![] }, 32).is_err());
+        assert!(config.check_shift_layer(&layer(), 11).unwrap_err().contains("out of range"));
+    }
+}
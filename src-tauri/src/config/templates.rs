@@ -0,0 +1,226 @@
+//! Starter configurations for common hardware layouts.
+//!
+//! Each template builds a complete [`BinaryConfig`] in the layout this app writes
+//! (`CONFIG_VERSION`), wired to fixed GPIOs that are noted in its description. The RP2040 has four
+//! ADC inputs (GPIO 26-29), so no template has more than four axes. Applying a template replaces
+//! the whole device config except the USB descriptor.
+use serde::Serialize;
+
+use super::binary::{
+    BinaryConfig, StoredLogicalInput, StoredPinMapEntry, BEHAVIOR_ENC_A, BEHAVIOR_ENC_B, BEHAVIOR_NORMAL,
+    INPUT_TYPE_MATRIX, INPUT_TYPE_PIN, INPUT_TYPE_SHIFTREG,
+    PIN_TYPE_BTN, PIN_TYPE_BTN_COL, PIN_TYPE_BTN_ROW, PIN_TYPE_SHIFTREG_CLK, PIN_TYPE_SHIFTREG_PL, PIN_TYPE_SHIFTREG_QH,
+};
+use super::json::{string_to_bytes, PIN_NAME_MAX_BYTES};
+use super::usb::encode_usb_string;
+
+/// `four3` latch mode, the usual one for detented encoders
+const LATCH_FOUR3: u8 = 1;
+
+pub struct ConfigTemplate {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub description: &'static str,
+    build: fn() -> BinaryConfig,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ConfigTemplateInfo {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub axes: usize,
+    pub buttons: usize,
+    pub encoders: usize,
+    /// Config layout version the template is written in
+    pub config_version: u16,
+}
+
+pub const CONFIG_TEMPLATES: &[ConfigTemplate] = &[
+    ConfigTemplate {
+        id: "button-box-32",
+        name: "32-button box with 4 shift registers",
+        description: "Four daisy-chained 74HC165 shift registers (load GPIO 3, clock GPIO 4, data GPIO 5) reading buttons 1-32",
+        build: button_box_32,
+    },
+    ConfigTemplate {
+        id: "dual-gimbal",
+        name: "Dual gimbal (4 axes, 8 buttons)",
+        description: "Two 2-axis gimbals on the four ADC pins (GPIO 26-29) and eight direct buttons on GPIO 6-13",
+        build: dual_gimbal,
+    },
+    ConfigTemplate {
+        id: "panel-matrix-25",
+        name: "25-button panel (5x5 matrix)",
+        description: "Button matrix with rows on GPIO 2-6 and columns on GPIO 7-11",
+        build: panel_matrix_25,
+    },
+    ConfigTemplate {
+        id: "encoder-panel",
+        name: "Encoder panel (4 encoders, 8 buttons)",
+        description: "Four rotary encoders on GPIO 2-9 (A/B pairs, buttons 1-8) and eight direct buttons on GPIO 10-17",
+        build: encoder_panel,
+    },
+];
+
+/// Accumulates a template config; counts are set by [`Self::build`]
+struct TemplateBuilder {
+    config: BinaryConfig,
+}
+
+impl TemplateBuilder {
+    fn new(product: &str) -> Self {
+        let mut config = BinaryConfig::new();
+        let usb = &mut config.stored_config.usb_descriptor;
+        usb.manufacturer = encode_usb_string("JoyCore", "Manufacturer").unwrap_or_default();
+        usb.product = encode_usb_string(product, "Product").unwrap_or_default();
+        Self { config }
+    }
+
+    fn axis(mut self, index: usize, pin: u8) -> Self {
+        let mut axes = self.config.stored_config.axes;
+        axes[index].enabled = 1;
+        axes[index].pin = pin;
+        self.config.stored_config.axes = axes;
+        self
+    }
+
+    fn pin(mut self, gpio: u8, pin_type: u8) -> Self {
+        let name = string_to_bytes(&gpio.to_string(), PIN_NAME_MAX_BYTES, "Pin name").unwrap_or_default();
+        self.config.pin_map_entries.push(StoredPinMapEntry { name, pin_type, reserved: 0 });
+        self
+    }
+
+    fn input(mut self, input_type: u8, behavior: u8, joy_button_id: u8, data: [u8; 2]) -> Self {
+        let encoder_latch_mode = if behavior == BEHAVIOR_NORMAL { 0 } else { LATCH_FOUR3 };
+        self.config.logical_inputs.push(StoredLogicalInput {
+            input_type, behavior, joy_button_id, reverse: 0, encoder_latch_mode, reserved: [0; 3], data,
+        });
+        self
+    }
+
+    /// Direct buttons `first_button..` on consecutive GPIOs from `first_gpio`
+    fn buttons(mut self, first_gpio: u8, first_button: u8, count: u8) -> Self {
+        for i in 0..count {
+            self = self.pin(first_gpio + i, PIN_TYPE_BTN).input(INPUT_TYPE_PIN, BEHAVIOR_NORMAL, first_button + i, [first_gpio + i, 0]);
+        }
+        self
+    }
+
+    fn build(mut self) -> BinaryConfig {
+        self.config.stored_config.pin_map_count = self.config.pin_map_entries.len() as u8;
+        self.config.stored_config.logical_input_count = self.config.logical_inputs.len() as u8;
+        self.config
+    }
+}
+
+fn button_box_32() -> BinaryConfig {
+    let mut builder = TemplateBuilder::new("JoyCore Button Box")
+        .pin(3, PIN_TYPE_SHIFTREG_PL)
+        .pin(4, PIN_TYPE_SHIFTREG_CLK)
+        .pin(5, PIN_TYPE_SHIFTREG_QH);
+    builder.config.stored_config.shift_reg_count = 4;
+    for button in 0..32u8 {
+        builder = builder.input(INPUT_TYPE_SHIFTREG, BEHAVIOR_NORMAL, button, [button / 8, button % 8]);
+    }
+    builder.build()
+}
+
+fn dual_gimbal() -> BinaryConfig {
+    TemplateBuilder::new("JoyCore Dual Gimbal")
+        .axis(0, 26)
+        .axis(1, 27)
+        .axis(2, 28)
+        .axis(3, 29)
+        .buttons(6, 0, 8)
+        .build()
+}
+
+fn panel_matrix_25() -> BinaryConfig {
+    let mut builder = TemplateBuilder::new("JoyCore Panel");
+    for row in 0..5u8 {
+        builder = builder.pin(2 + row, PIN_TYPE_BTN_ROW);
+    }
+    for col in 0..5u8 {
+        builder = builder.pin(7 + col, PIN_TYPE_BTN_COL);
+    }
+    for row in 0..5u8 {
+        for col in 0..5u8 {
+            builder = builder.input(INPUT_TYPE_MATRIX, BEHAVIOR_NORMAL, row * 5 + col, [row, col]);
+        }
+    }
+    builder.build()
+}
+
+fn encoder_panel() -> BinaryConfig {
+    let mut builder = TemplateBuilder::new("JoyCore Encoder Panel");
+    for encoder in 0..4u8 {
+        let (a, b) = (2 + encoder * 2, 3 + encoder * 2);
+        builder = builder
+            .pin(a, PIN_TYPE_BTN)
+            .pin(b, PIN_TYPE_BTN)
+            .input(INPUT_TYPE_PIN, BEHAVIOR_ENC_A, encoder * 2, [a, 0])
+            .input(INPUT_TYPE_PIN, BEHAVIOR_ENC_B, encoder * 2 + 1, [b, 0]);
+    }
+    builder.buttons(10, 8, 8).build()
+}
+
+fn find(id: &str) -> Result<&'static ConfigTemplate, String> {
+    CONFIG_TEMPLATES.iter().find(|t| t.id == id)
+        .ok_or_else(|| format!("Unknown config template '{}'", id))
+}
+
+/// The shipped templates with a summary of what each sets up
+pub fn list_config_templates() -> Vec<ConfigTemplateInfo> {
+    CONFIG_TEMPLATES.iter().map(|template| {
+        let config = (template.build)();
+        let encoders = config.encoder_pairs().len();
+        ConfigTemplateInfo {
+            id: template.id.to_string(),
+            name: template.name.to_string(),
+            description: template.description.to_string(),
+            axes: config.stored_config.axes.iter().filter(|a| a.enabled != 0).count(),
+            buttons: config.logical_inputs.len() - encoders * 2,
+            encoders,
+            config_version: config.stored_config.header.version,
+        }
+    }).collect()
+}
+
+/// Build the config of template `id`
+pub fn build_config_template(id: &str) -> Result<BinaryConfig, String> {
+    Ok((find(id)?.build)())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::validate::{has_errors, validate_config};
+
+    #[test]
+    fn templates_are_valid_configs() {
+        for template in CONFIG_TEMPLATES {
+            let config = build_config_template(template.id).unwrap();
+            let diagnostics = validate_config(&config);
+            assert!(diagnostics.is_empty(), "{}: {:?}", template.id, diagnostics);
+            assert!(!has_errors(&diagnostics));
+            let bytes = config.to_bytes().unwrap();
+            assert!(BinaryConfig::from_bytes(&bytes).is_ok(), "{} does not round-trip", template.id);
+        }
+        assert!(build_config_template("hotas-9000").is_err());
+    }
+
+    #[test]
+    fn summarizes_templates() {
+        let list = list_config_templates();
+        let summary = |id: &str| {
+            let t = list.iter().find(|t| t.id == id).unwrap();
+            (t.axes, t.buttons, t.encoders)
+        };
+        assert_eq!(summary("button-box-32"), (0, 32, 0));
+        assert_eq!(summary("dual-gimbal"), (4, 8, 0));
+        assert_eq!(summary("panel-matrix-25"), (0, 25, 0));
+        assert_eq!(summary("encoder-panel"), (0, 8, 4));
+        assert!(list.iter().all(|t| t.config_version == crate::config::binary::CONFIG_VERSION));
+    }
+}
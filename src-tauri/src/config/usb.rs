@@ -0,0 +1,160 @@
+//! USB device descriptor (VID/PID and the manufacturer / product strings) in [`BinaryConfig`].
+//!
+//! The firmware widens each stored byte to one UTF-16 code unit when it builds the USB string
+//! descriptors, so a stored byte is the Latin-1 character U+0000..U+00FF with the same value.
+//! Strings are encoded and decoded that way rather than as UTF-8: the names shown here and in the
+//! JSON export are the ones the OS and games show, every stored byte round-trips, and characters
+//! outside Latin-1 (or strings over 31 characters) are rejected instead of mangled or cut.
+//!
+//! The app finds devices by the JoyCore VID/PID (`hid::JOYCORE_VID` / `JOYCORE_PID`), so the IDs
+//! can only be kept as they are or set back to those; any other change would make the device
+//! disappear from the app after it re-enumerates.
+use serde::{Deserialize, Serialize};
+
+use super::binary::BinaryConfig;
+use super::json::USB_STRING_MAX_BYTES;
+use crate::hid::{JOYCORE_PID, JOYCORE_VID};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UsbDescriptorConfig {
+    pub vid: u16,
+    pub pid: u16,
+    pub manufacturer: String,
+    pub product: String,
+}
+
+/// Stored USB string up to its NUL terminator, one Latin-1 character per byte
+pub(crate) fn decode_usb_string(bytes: &[u8]) -> String {
+    bytes.iter().take_while(|&&b| b != 0).map(|&b| char::from(b)).collect()
+}
+
+/// Latin-1 bytes of `value` for a stored USB string field; never truncates
+pub(crate) fn encode_usb_string(value: &str, field: &str) -> Result<[u8; USB_STRING_MAX_BYTES + 1], String> {
+    let mut out = [0u8; USB_STRING_MAX_BYTES + 1];
+    let count = value.chars().count();
+    if count > USB_STRING_MAX_BYTES {
+        return Err(format!("{} '{}' is {} characters (maximum {})", field, value, count, USB_STRING_MAX_BYTES));
+    }
+    for (slot, c) in out.iter_mut().zip(value.chars()) {
+        *slot = match u8::try_from(c) {
+            Ok(b) if b != 0 => b,
+            _ => return Err(format!("{} contains '{}'; the device can only show Latin-1 characters (U+0001..U+00FF)", field, c.escape_default())),
+        };
+    }
+    Ok(out)
+}
+
+fn is_printable(c: char) -> bool {
+    (' '..='~').contains(&c) || ('\u{A0}'..='\u{FF}').contains(&c)
+}
+
+fn check_string(value: &str, field: &str) -> Result<(), String> {
+    if let Some(c) = value.chars().find(|&c| !is_printable(c)) {
+        return Err(format!("{} contains '{}'; only printable Latin-1 characters are supported", field, c.escape_default()));
+    }
+    if value.trim() != value {
+        return Err(format!("{} must not start or end with spaces", field));
+    }
+    let count = value.chars().count();
+    if count > USB_STRING_MAX_BYTES {
+        return Err(format!("{} is {} characters (maximum {})", field, count, USB_STRING_MAX_BYTES));
+    }
+    Ok(())
+}
+
+impl BinaryConfig {
+    pub fn to_usb_descriptor(&self) -> UsbDescriptorConfig {
+        let usb = self.stored_config.usb_descriptor;
+        UsbDescriptorConfig {
+            vid: usb.vid,
+            pid: usb.pid,
+            manufacturer: decode_usb_string(&usb.manufacturer),
+            product: decode_usb_string(&usb.product),
+        }
+    }
+
+    /// Replace the USB descriptor after checking IDs, string length and encoding
+    pub fn apply_usb_descriptor(&mut self, descriptor: &UsbDescriptorConfig) -> Result<(), String> {
+        if descriptor.vid == 0 || descriptor.pid == 0 {
+            return Err("VID and PID must be non-zero".to_string());
+        }
+        let usb = &self.stored_config.usb_descriptor;
+        let unchanged = (descriptor.vid, descriptor.pid) == (usb.vid, usb.pid);
+        if !unchanged && (descriptor.vid, descriptor.pid) != (JOYCORE_VID, JOYCORE_PID) {
+            return Err(format!(
+                "Changing VID/PID to {:04X}:{:04X} is not supported: JoyCore-X only detects devices with {:04X}:{:04X}, so the device would no longer be found",
+                descriptor.vid, descriptor.pid, JOYCORE_VID, JOYCORE_PID
+            ));
+        }
+        check_string(&descriptor.manufacturer, "Manufacturer")?;
+        check_string(&descriptor.product, "Product")?;
+        if descriptor.product.is_empty() {
+            return Err("Product name must not be empty".to_string());
+        }
+        let usb = &mut self.stored_config.usb_descriptor;
+        usb.manufacturer = encode_usb_string(&descriptor.manufacturer, "Manufacturer")?;
+        usb.product = encode_usb_string(&descriptor.product, "Product")?;
+        usb.vid = descriptor.vid;
+        usb.pid = descriptor.pid;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn descriptor(product: &str) -> UsbDescriptorConfig {
+        UsbDescriptorConfig { vid: JOYCORE_VID, pid: JOYCORE_PID, manufacturer: "JoyCore".into(), product: product.into() }
+    }
+
+    #[test]
+    fn applies_and_validates_descriptor() {
+        let mut config = BinaryConfig::new();
+        config.apply_usb_descriptor(&descriptor("JoyCore Throttle")).unwrap();
+        let parsed = BinaryConfig::from_bytes(&config.to_bytes().unwrap()).unwrap();
+        assert_eq!(parsed.to_usb_descriptor(), descriptor("JoyCore Throttle"));
+
+        assert!(config.apply_usb_descriptor(&descriptor("Stick\u{263A}")).unwrap_err().contains("printable Latin-1"));
+        assert!(config.apply_usb_descriptor(&descriptor("Stick\tL")).is_err());
+        assert!(config.apply_usb_descriptor(&descriptor(&"X".repeat(32))).unwrap_err().contains("maximum 31"));
+        assert!(config.apply_usb_descriptor(&descriptor(&"\u{00C4}".repeat(32))).unwrap_err().contains("maximum 31"));
+        assert!(config.apply_usb_descriptor(&descriptor("")).is_err());
+        assert!(config.apply_usb_descriptor(&UsbDescriptorConfig { pid: 0, ..descriptor("Stick") }).is_err());
+        assert_eq!(config.to_usb_descriptor().product, "JoyCore Throttle", "rejected edits leave the descriptor untouched");
+    }
+
+    #[test]
+    fn rejects_ids_the_app_cannot_detect() {
+        let mut config = BinaryConfig::new();
+        config.apply_usb_descriptor(&descriptor("Stick")).unwrap();
+        let moved = UsbDescriptorConfig { pid: 0xA030, ..descriptor("Stick") };
+        assert!(config.apply_usb_descriptor(&moved).unwrap_err().contains("no longer be found"));
+
+        // IDs already stored on the device may be kept, and set back to the JoyCore ones
+        config.stored_config.usb_descriptor.pid = 0xA030;
+        config.apply_usb_descriptor(&UsbDescriptorConfig { product: "Renamed".into(), ..moved }).unwrap();
+        config.apply_usb_descriptor(&descriptor("Stick")).unwrap();
+        assert_eq!(config.to_usb_descriptor().pid, JOYCORE_PID);
+    }
+
+    #[test]
+    fn latin1_strings_round_trip() {
+        let name = format!("Schubkraft \u{00DC}ber {}", "\u{00E9}".repeat(16));
+        assert_eq!(name.chars().count(), USB_STRING_MAX_BYTES);
+        let mut config = BinaryConfig::new();
+        config.apply_usb_descriptor(&descriptor(&name)).unwrap();
+        assert_eq!(config.stored_config.usb_descriptor.product[11], 0xDC, "one byte per character, as the firmware widens it");
+        assert_eq!(config.stored_config.usb_descriptor.product[31], 0);
+
+        let parsed = BinaryConfig::from_json(&config.to_json().unwrap()).unwrap();
+        assert_eq!(parsed.to_usb_descriptor(), descriptor(&name));
+        assert_eq!(parsed.to_bytes().unwrap(), config.to_bytes().unwrap());
+
+        // Bytes written by other tools come back unchanged, control characters included
+        let raw = [b'A', 0x01, 0xFF, 0x80];
+        assert_eq!(encode_usb_string(&decode_usb_string(&raw), "Product").unwrap()[..4], raw);
+        assert!(encode_usb_string("\u{65E5}\u{672C}", "Product").unwrap_err().contains("Latin-1"));
+        assert!(encode_usb_string("A\0B", "Product").is_err());
+    }
+}
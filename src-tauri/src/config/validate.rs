@@ -0,0 +1,428 @@
+//! Consistency checks for a [`BinaryConfig`] before it is written to the device.
+//!
+//! [`validate_config`] never fails; it returns every problem found so the UI can list them.
+//! Errors describe configurations the firmware cannot run correctly (a GPIO driven by two
+//! roles, pins the RP2040 does not have, half-configured matrices or shift registers);
+//! warnings flag things that are probably mistakes but still load.
+//!
+//! [`ConfigValidationReport`] is the result of a dry run: the firmware's own findings when it
+//! supports VALIDATE_CONFIG, otherwise these checks plus the features the firmware lacks.
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use super::binary::{
+    BinaryConfig, BEHAVIOR_ENC_A, BEHAVIOR_ENC_B, INPUT_TYPE_MATRIX, INPUT_TYPE_PIN, INPUT_TYPE_SHIFTREG,
+    PIN_TYPE_BTN, PIN_TYPE_BTN_COL, PIN_TYPE_BTN_ROW, PIN_TYPE_SHIFTREG_CLK, PIN_TYPE_SHIFTREG_PL,
+    PIN_TYPE_SHIFTREG_QH, PIN_TYPE_UNUSED, RP2040_MAX_GPIO,
+};
+use super::json::bytes_to_string;
+use crate::serial::capabilities::FirmwareCapabilities;
+use crate::serial::file_transfer::ValidationFinding;
+
+/// RP2040 GPIOs wired to the ADC
+const ADC_PINS: std::ops::RangeInclusive<u8> = 26..=29;
+/// Inputs per 74HC165
+const SHIFT_REG_BITS: u8 = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum DiagnosticCode {
+    PinConflict,
+    DuplicatePin,
+    PinOutOfRange,
+    AxisPinNotAnalog,
+    InvalidPinName,
+    IncompleteMatrix,
+    MatrixCellOutOfRange,
+    IncompleteShiftRegister,
+    ShiftRegisterInputOutOfRange,
+    UnmappedButtonPin,
+    DuplicateButton,
+    UnpairedEncoder,
+    IncompleteHat,
+    CountMismatch,
+    /// The binary does not parse
+    InvalidBinary,
+    /// The config uses a feature the connected firmware does not have
+    UnsupportedFeature,
+    /// Firmware finding without a host-side equivalent
+    FirmwareCheck,
+}
+
+impl DiagnosticCode {
+    /// Host code for a firmware code such as `PIN_CONFLICT`
+    fn from_firmware(code: &str) -> Option<Self> {
+        serde_json::from_value(serde_json::Value::String(code.to_string())).ok()
+    }
+}
+
+/// Who checked the config in a dry run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ValidationSource {
+    Firmware,
+    Host,
+}
+
+/// Result of `validate_config_on_device`; nothing is written to flash
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigValidationReport {
+    pub source: ValidationSource,
+    /// No errors; warnings alone still allow applying the config
+    pub valid: bool,
+    pub diagnostics: Vec<ConfigDiagnostic>,
+}
+
+impl ConfigValidationReport {
+    pub fn new(source: ValidationSource, diagnostics: Vec<ConfigDiagnostic>) -> Self {
+        Self { source, valid: !has_errors(&diagnostics), diagnostics }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PinRole {
+    Axis,
+    Button,
+    MatrixRow,
+    MatrixCol,
+    ShiftRegLoad,
+    ShiftRegClock,
+    ShiftRegData,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigDiagnostic {
+    pub severity: Severity,
+    pub code: DiagnosticCode,
+    pub message: String,
+    /// GPIOs involved, if any
+    #[serde(default)]
+    pub pins: Vec<u8>,
+    /// Index into the logical inputs, if the problem is tied to one
+    #[serde(default)]
+    pub logical_input: Option<usize>,
+}
+
+impl ConfigDiagnostic {
+    fn new(severity: Severity, code: DiagnosticCode, message: String) -> Self {
+        Self { severity, code, message, pins: Vec::new(), logical_input: None }
+    }
+
+    fn error(code: DiagnosticCode, message: String) -> Self {
+        Self::new(Severity::Error, code, message)
+    }
+
+    fn warning(code: DiagnosticCode, message: String) -> Self {
+        Self::new(Severity::Warning, code, message)
+    }
+
+    fn pin(mut self, pin: u8) -> Self {
+        self.pins.push(pin);
+        self
+    }
+
+    fn input(mut self, index: usize) -> Self {
+        self.logical_input = Some(index);
+        self
+    }
+}
+
+pub fn has_errors(diagnostics: &[ConfigDiagnostic]) -> bool {
+    diagnostics.iter().any(|d| d.severity == Severity::Error)
+}
+
+fn pin_role(pin_type: u8) -> Option<PinRole> {
+    match pin_type {
+        PIN_TYPE_BTN => Some(PinRole::Button),
+        PIN_TYPE_BTN_ROW => Some(PinRole::MatrixRow),
+        PIN_TYPE_BTN_COL => Some(PinRole::MatrixCol),
+        PIN_TYPE_SHIFTREG_PL => Some(PinRole::ShiftRegLoad),
+        PIN_TYPE_SHIFTREG_CLK => Some(PinRole::ShiftRegClock),
+        PIN_TYPE_SHIFTREG_QH => Some(PinRole::ShiftRegData),
+        _ => None,
+    }
+}
+
+/// Diagnostics for the firmware's VALIDATE_CONFIG findings
+pub fn firmware_diagnostics(findings: &[ValidationFinding]) -> Vec<ConfigDiagnostic> {
+    findings.iter()
+        .map(|f| {
+            let code = DiagnosticCode::from_firmware(&f.code).unwrap_or(DiagnosticCode::FirmwareCheck);
+            let message = if code == DiagnosticCode::FirmwareCheck { format!("{} ({})", f.message, f.code) } else { f.message.clone() };
+            let severity = if f.error { Severity::Error } else { Severity::Warning };
+            ConfigDiagnostic::new(severity, code, message)
+        })
+        .collect()
+}
+
+/// Errors for config features the firmware reports it does not support
+pub fn unsupported_features(config: &BinaryConfig, caps: &FirmwareCapabilities) -> Vec<ConfigDiagnostic> {
+    let mut out = Vec::new();
+    if !caps.hats && !config.hat_inputs().is_empty() {
+        out.push(ConfigDiagnostic::error(DiagnosticCode::UnsupportedFeature, "The config has hat switches, which this firmware does not support".to_string()));
+    }
+    if !caps.shift_layers {
+        for (i, _) in config.logical_inputs.iter().enumerate().filter(|(_, l)| l.is_shifted()) {
+            out.push(ConfigDiagnostic::error(DiagnosticCode::UnsupportedFeature, format!(
+                "Logical input {} is on the shift layer, which this firmware does not support", i
+            )).input(i));
+        }
+    }
+    out
+}
+
+/// Host-side dry run of a config binary: parse it, run every check and, with `caps`, flag the
+/// features the firmware lacks
+pub fn validate_config_binary(data: &[u8], caps: Option<&FirmwareCapabilities>) -> ConfigValidationReport {
+    let diagnostics = match BinaryConfig::from_bytes(data) {
+        Ok(config) => {
+            let mut diagnostics = validate_config(&config);
+            if let Some(caps) = caps {
+                diagnostics.extend(unsupported_features(&config, caps));
+            }
+            diagnostics
+        }
+        Err(e) => vec![ConfigDiagnostic::error(DiagnosticCode::InvalidBinary, format!("Config binary does not parse: {}", e))],
+    };
+    ConfigValidationReport::new(ValidationSource::Host, diagnostics)
+}
+
+/// Run every check over the configuration
+pub fn validate_config(config: &BinaryConfig) -> Vec<ConfigDiagnostic> {
+    let mut out = Vec::new();
+    let stored = &config.stored_config;
+
+    let (pin_map_count, input_count) = (stored.pin_map_count, stored.logical_input_count);
+    if pin_map_count as usize != config.pin_map_entries.len() || input_count as usize != config.logical_inputs.len() {
+        out.push(ConfigDiagnostic::error(DiagnosticCode::CountMismatch, format!(
+            "Header counts ({} pins, {} inputs) do not match the entries ({} pins, {} inputs)",
+            pin_map_count, input_count, config.pin_map_entries.len(), config.logical_inputs.len()
+        )));
+    }
+
+    // GPIO -> every role claiming it
+    let mut uses: BTreeMap<u8, Vec<(PinRole, String)>> = BTreeMap::new();
+    for (i, axis) in stored.axes.iter().enumerate() {
+        if axis.enabled == 0 {
+            continue;
+        }
+        let pin = axis.pin;
+        if pin > RP2040_MAX_GPIO {
+            out.push(ConfigDiagnostic::error(DiagnosticCode::PinOutOfRange, format!("Axis {} uses GPIO {}, which does not exist on the RP2040", i + 1, pin)).pin(pin));
+            continue;
+        }
+        if !ADC_PINS.contains(&pin) {
+            out.push(ConfigDiagnostic::error(DiagnosticCode::AxisPinNotAnalog, format!("Axis {} uses GPIO {}, which is not an ADC pin (26-29)", i + 1, pin)).pin(pin));
+        }
+        uses.entry(pin).or_default().push((PinRole::Axis, format!("axis {}", i + 1)));
+    }
+
+    let mut role_pins: HashMap<PinRole, Vec<u8>> = HashMap::new();
+    for entry in &config.pin_map_entries {
+        if entry.pin_type == PIN_TYPE_UNUSED {
+            continue;
+        }
+        let name = bytes_to_string(&entry.name);
+        let Ok(pin) = name.trim().parse::<u8>() else {
+            out.push(ConfigDiagnostic::warning(DiagnosticCode::InvalidPinName, format!("Pin map entry '{}' is not a GPIO number and is ignored", name)));
+            continue;
+        };
+        if pin > RP2040_MAX_GPIO {
+            out.push(ConfigDiagnostic::error(DiagnosticCode::PinOutOfRange, format!("Pin map entry uses GPIO {}, which does not exist on the RP2040", pin)).pin(pin));
+            continue;
+        }
+        let Some(role) = pin_role(entry.pin_type) else {
+            out.push(ConfigDiagnostic::warning(DiagnosticCode::InvalidPinName, format!("GPIO {} has unknown pin type {}", pin, entry.pin_type)).pin(pin));
+            continue;
+        };
+        role_pins.entry(role).or_default().push(pin);
+        uses.entry(pin).or_default().push((role, format!("{:?}", role)));
+    }
+
+    let button_pins: HashSet<u8> = role_pins.get(&PinRole::Button).cloned().unwrap_or_default().into_iter().collect();
+    for (i, input) in config.logical_inputs.iter().enumerate() {
+        if input.input_type != INPUT_TYPE_PIN {
+            continue;
+        }
+        let pin = input.data[0];
+        if pin > RP2040_MAX_GPIO {
+            out.push(ConfigDiagnostic::error(DiagnosticCode::PinOutOfRange, format!("Button {} reads GPIO {}, which does not exist on the RP2040", input.joy_button_id, pin)).pin(pin).input(i));
+        } else if !button_pins.contains(&pin) {
+            out.push(ConfigDiagnostic::warning(DiagnosticCode::UnmappedButtonPin, format!("Button {} reads GPIO {}, which has no BTN pin map entry", input.joy_button_id, pin)).pin(pin).input(i));
+            uses.entry(pin).or_default().push((PinRole::Button, format!("button {}", input.joy_button_id)));
+        }
+    }
+
+    for (pin, claims) in &uses {
+        let roles: HashSet<PinRole> = claims.iter().map(|(r, _)| *r).collect();
+        if roles.len() > 1 {
+            let mut names: Vec<&str> = claims.iter().map(|(_, n)| n.as_str()).collect();
+            names.dedup();
+            out.push(ConfigDiagnostic::error(DiagnosticCode::PinConflict, format!("GPIO {} is assigned to several roles: {}", pin, names.join(", "))).pin(*pin));
+        } else if claims.len() > 1 {
+            out.push(ConfigDiagnostic::warning(DiagnosticCode::DuplicatePin, format!("GPIO {} is listed {} times as {:?}", pin, claims.len(), claims[0].0)).pin(*pin));
+        }
+    }
+
+    // Matrix
+    let rows = role_pins.get(&PinRole::MatrixRow).map_or(0, Vec::len);
+    let cols = role_pins.get(&PinRole::MatrixCol).map_or(0, Vec::len);
+    if (rows == 0) != (cols == 0) {
+        out.push(ConfigDiagnostic::error(DiagnosticCode::IncompleteMatrix, format!("Matrix has {} row pin(s) and {} column pin(s); both are required", rows, cols)));
+    }
+    for (i, input) in config.logical_inputs.iter().enumerate() {
+        if input.input_type == INPUT_TYPE_MATRIX && (input.data[0] as usize >= rows || input.data[1] as usize >= cols) {
+            out.push(ConfigDiagnostic::error(DiagnosticCode::MatrixCellOutOfRange, format!(
+                "Button {} uses matrix cell [{},{}] outside the {}x{} matrix", input.joy_button_id, input.data[0], input.data[1], rows, cols
+            )).input(i));
+        }
+    }
+
+    // Shift registers
+    let shift_reg_count = stored.shift_reg_count;
+    let shift_roles = [PinRole::ShiftRegLoad, PinRole::ShiftRegClock, PinRole::ShiftRegData];
+    let missing: Vec<String> = shift_roles.iter().filter(|r| !role_pins.contains_key(r)).map(|r| format!("{:?}", r)).collect();
+    if shift_reg_count > 0 && !missing.is_empty() {
+        out.push(ConfigDiagnostic::error(DiagnosticCode::IncompleteShiftRegister, format!("{} shift register(s) configured but missing pins: {}", shift_reg_count, missing.join(", "))));
+    } else if shift_reg_count == 0 && missing.len() < shift_roles.len() {
+        out.push(ConfigDiagnostic::warning(DiagnosticCode::IncompleteShiftRegister, "Shift register pins are mapped but the shift register count is 0".to_string()));
+    }
+    for (i, input) in config.logical_inputs.iter().enumerate() {
+        if input.input_type == INPUT_TYPE_SHIFTREG && (input.data[0] >= shift_reg_count || input.data[1] >= SHIFT_REG_BITS) {
+            out.push(ConfigDiagnostic::error(DiagnosticCode::ShiftRegisterInputOutOfRange, format!(
+                "Button {} reads shift register {} bit {}, but only {} register(s) of {} bits are configured",
+                input.joy_button_id, input.data[0], input.data[1], shift_reg_count, SHIFT_REG_BITS
+            )).input(i));
+        }
+    }
+
+    // Buttons, encoders and hats
+    let mut owners: HashMap<u8, usize> = HashMap::new();
+    for (i, input) in config.logical_inputs.iter().enumerate().filter(|(_, l)| !l.is_hat()) {
+        if let Some(prev) = owners.insert(input.joy_button_id, i) {
+            out.push(ConfigDiagnostic::warning(DiagnosticCode::DuplicateButton, format!(
+                "Joystick button {} is driven by logical inputs {} and {}", input.joy_button_id, prev, i
+            )).input(i));
+        }
+    }
+    let paired: HashSet<usize> = config.encoder_pairs().into_iter().flat_map(|(a, b)| [a, b]).collect();
+    for (i, input) in config.logical_inputs.iter().enumerate() {
+        if matches!(input.behavior, BEHAVIOR_ENC_A | BEHAVIOR_ENC_B) && !paired.contains(&i) {
+            let channel = if input.behavior == BEHAVIOR_ENC_A { "A" } else { "B" };
+            out.push(ConfigDiagnostic::error(DiagnosticCode::UnpairedEncoder, format!(
+                "Encoder channel {} on button {} has no matching channel", channel, input.joy_button_id
+            )).input(i));
+        }
+    }
+    for (id, inputs) in config.hat_inputs() {
+        if inputs.iter().any(Option::is_none) {
+            out.push(ConfigDiagnostic::error(DiagnosticCode::IncompleteHat, format!(
+                "Hat {} needs exactly one input for each of up, right, down and left", id
+            )));
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{StoredLogicalInput, StoredPinMapEntry};
+
+    fn pin_entry(pin: u8, pin_type: u8) -> StoredPinMapEntry {
+        let mut name = [0u8; 8];
+        let s = pin.to_string();
+        name[..s.len()].copy_from_slice(s.as_bytes());
+        StoredPinMapEntry { name, pin_type, reserved: 0 }
+    }
+
+    fn input(input_type: u8, behavior: u8, button: u8, data: [u8; 2]) -> StoredLogicalInput {
+        StoredLogicalInput { input_type, behavior, joy_button_id: button, reverse: 0, encoder_latch_mode: 0, reserved: [0; 3], data }
+    }
+
+    fn finish(mut config: BinaryConfig) -> BinaryConfig {
+        config.stored_config.pin_map_count = config.pin_map_entries.len() as u8;
+        config.stored_config.logical_input_count = config.logical_inputs.len() as u8;
+        config
+    }
+
+    fn codes(config: &BinaryConfig) -> Vec<DiagnosticCode> {
+        validate_config(config).into_iter().map(|d| d.code).collect()
+    }
+
+    #[test]
+    fn clean_config_has_no_diagnostics() {
+        let mut config = BinaryConfig::new();
+        config.stored_config.axes[0].enabled = 1;
+        config.stored_config.axes[0].pin = 26;
+        config.pin_map_entries = vec![pin_entry(2, PIN_TYPE_BTN), pin_entry(4, PIN_TYPE_BTN_ROW), pin_entry(5, PIN_TYPE_BTN_COL)];
+        config.logical_inputs = vec![input(INPUT_TYPE_PIN, 0, 0, [2, 0]), input(INPUT_TYPE_MATRIX, 0, 1, [0, 0])];
+        let diagnostics = validate_config(&finish(config));
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+    }
+
+    #[test]
+    fn detects_conflicts_and_impossible_combinations() {
+        let mut config = BinaryConfig::new();
+        config.stored_config.axes[0].enabled = 1;
+        config.stored_config.axes[0].pin = 5;
+        config.stored_config.shift_reg_count = 1;
+        config.pin_map_entries = vec![pin_entry(5, PIN_TYPE_SHIFTREG_CLK), pin_entry(31, PIN_TYPE_BTN), pin_entry(4, PIN_TYPE_BTN_ROW)];
+        config.logical_inputs = vec![
+            input(INPUT_TYPE_SHIFTREG, 0, 0, [1, 0]),
+            input(INPUT_TYPE_PIN, BEHAVIOR_ENC_A, 0, [7, 0]),
+            input(INPUT_TYPE_MATRIX, 5, 0, [0, 0]),
+        ];
+        let config = finish(config);
+        let found = codes(&config);
+        for expected in [
+            DiagnosticCode::AxisPinNotAnalog,
+            DiagnosticCode::PinConflict,
+            DiagnosticCode::PinOutOfRange,
+            DiagnosticCode::IncompleteMatrix,
+            DiagnosticCode::IncompleteShiftRegister,
+            DiagnosticCode::ShiftRegisterInputOutOfRange,
+            DiagnosticCode::UnmappedButtonPin,
+            DiagnosticCode::DuplicateButton,
+            DiagnosticCode::UnpairedEncoder,
+            DiagnosticCode::IncompleteHat,
+        ] {
+            assert!(found.contains(&expected), "missing {:?} in {:?}", expected, found);
+        }
+        assert!(has_errors(&validate_config(&config)));
+    }
+
+    #[test]
+    fn dry_run_reports() {
+        let report = validate_config_binary(b"not a config", None);
+        assert!(!report.valid);
+        assert_eq!(report.diagnostics[0].code, DiagnosticCode::InvalidBinary);
+
+        let findings = [
+            ValidationFinding { error: true, code: "PIN_CONFLICT".into(), message: "GPIO 4".into() },
+            ValidationFinding { error: false, code: "FLASH_WEAR".into(), message: "Config rewritten often".into() },
+        ];
+        let report = ConfigValidationReport::new(ValidationSource::Firmware, firmware_diagnostics(&findings));
+        assert!(!report.valid);
+        assert_eq!(report.diagnostics[0].code, DiagnosticCode::PinConflict);
+        assert_eq!((report.diagnostics[1].code, report.diagnostics[1].severity), (DiagnosticCode::FirmwareCheck, Severity::Warning));
+
+        let mut config = BinaryConfig::new();
+        let mut shifted = input(INPUT_TYPE_MATRIX, 0, 0, [0, 0]);
+        shifted.set_shifted(true);
+        config.logical_inputs = vec![shifted];
+        let found = unsupported_features(&finish(config), &FirmwareCapabilities::legacy("1.0.0"));
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].logical_input, Some(0));
+    }
+}
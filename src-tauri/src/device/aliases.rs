@@ -0,0 +1,88 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use crate::util::persist::{load_json, save_json};
+
+pub const ALIASES_FILE_NAME: &str = "device_aliases.json";
+pub const MAX_ALIAS_LENGTH: usize = 64;
+
+/// User-chosen device names, keyed by USB serial number and saved to disk on every change
+#[derive(Debug, Default)]
+pub struct DeviceAliases {
+    path: Option<PathBuf>,
+    aliases: BTreeMap<String, String>,
+}
+
+impl DeviceAliases {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load persisted aliases; a missing or unreadable file yields no aliases
+    pub fn load(path: PathBuf) -> Self {
+        let aliases = load_json(&path, "device aliases");
+        Self { path: Some(path), aliases }
+    }
+
+    pub fn get(&self, serial_number: &str) -> Option<&str> {
+        self.aliases.get(serial_number).map(String::as_str)
+    }
+
+    /// Name the device with `serial_number`; a blank or missing alias removes it.
+    /// Returns the stored (trimmed) alias.
+    pub fn set(&mut self, serial_number: &str, alias: Option<&str>) -> Result<Option<String>, String> {
+        if serial_number.trim().is_empty() {
+            return Err("A device alias needs a serial number".to_string());
+        }
+        let alias = alias.map(str::trim).filter(|a| !a.is_empty());
+        if let Some(a) = alias {
+            if a.chars().count() > MAX_ALIAS_LENGTH {
+                return Err(format!("Device alias is longer than {} characters", MAX_ALIAS_LENGTH));
+            }
+            if a.chars().any(char::is_control) {
+                return Err("Device alias contains control characters".to_string());
+            }
+        }
+        let changed = match alias {
+            Some(a) => self.aliases.insert(serial_number.to_string(), a.to_string()).as_deref() != Some(a),
+            None => self.aliases.remove(serial_number).is_some(),
+        };
+        if changed { self.save(); }
+        Ok(alias.map(str::to_string))
+    }
+
+    fn save(&self) {
+        if let Some(path) = &self.path {
+            save_json(path, &self.aliases, "device aliases");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sets_trims_and_clears_aliases() {
+        let mut aliases = DeviceAliases::new();
+        assert_eq!(aliases.set("E661", Some("  Throttle ")).unwrap().as_deref(), Some("Throttle"));
+        assert_eq!(aliases.get("E661"), Some("Throttle"));
+        assert_eq!(aliases.set("E661", Some("   ")).unwrap(), None);
+        assert_eq!(aliases.get("E661"), None);
+        assert!(aliases.set("", Some("Stick")).is_err());
+        assert!(aliases.set("E661", Some(&"x".repeat(MAX_ALIAS_LENGTH + 1))).is_err());
+        assert!(aliases.set("E661", Some("a\nb")).is_err());
+    }
+
+    #[test]
+    fn persists_aliases() {
+        let path = std::env::temp_dir().join(format!("joycore_aliases_{}.json", uuid::Uuid::new_v4()));
+        let mut aliases = DeviceAliases::load(path.clone());
+        aliases.set("E661", Some("Left stick")).unwrap();
+        aliases.set("A002", Some("Pedals")).unwrap();
+        aliases.set("A002", None).unwrap();
+        let reloaded = DeviceAliases::load(path.clone());
+        assert_eq!(reloaded.get("E661"), Some("Left stick"));
+        assert_eq!(reloaded.get("A002"), None);
+        let _ = std::fs::remove_file(path);
+    }
+}
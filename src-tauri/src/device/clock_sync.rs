@@ -0,0 +1,219 @@
+//! Mapping between the firmware clock and host time.
+//!
+//! Firmware timestamps are microseconds since boot. On connect the manager sends a burst of
+//! `TIME` queries and records, for each, when it was sent, when the reply arrived and the device
+//! time in the reply; the device time is taken to correspond to the midpoint of that round trip.
+//! The sample with the shortest round trip anchors the offset, and once samples span
+//! [`MIN_SKEW_SPAN`] (the heartbeat adds one every [`CLOCK_RESYNC_INTERVAL`]) a least-squares fit
+//! over the good samples estimates the crystal's drift. The fitted model is published globally so
+//! the raw monitor and the HID reader can convert between the two clocks.
+//!
+//! `UPTIME` reports the same clock in milliseconds; firmware without it is asked for `TIME`.
+use std::sync::RwLock;
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+
+use crate::serial::unified::types::{CommandSpec, ResponseMatcher};
+
+pub const TIME_COMMAND: &str = "TIME";
+const TIME_RESPONSE_PREFIX: &str = "TIME:";
+/// Round trips measured in the connect handshake
+pub const CLOCK_SYNC_SAMPLES: usize = 8;
+pub const CLOCK_RESYNC_INTERVAL: Duration = Duration::from_secs(60);
+/// Samples must span this much device time before drift is estimated
+pub const MIN_SKEW_SPAN: Duration = Duration::from_secs(10);
+/// Samples kept for the fit
+const MAX_SAMPLES: usize = 64;
+/// Drift beyond this is treated as a bad fit; crystals are within a few tens of ppm
+const MAX_SKEW_PPM: f64 = 500.0;
+
+pub fn time_spec() -> CommandSpec {
+    CommandSpec { name: TIME_COMMAND, timeout: Duration::from_millis(300), matcher: ResponseMatcher::Custom(time_reply_complete), test_min_duration_ms: None }
+}
+
+fn time_reply_complete(lines: &[String]) -> bool {
+    lines.iter().any(|l| l.starts_with(TIME_RESPONSE_PREFIX) || l.starts_with("ERROR"))
+}
+
+/// Device time in a `TIME:<micros>` reply
+pub fn parse_time_response(lines: &[String]) -> Option<u64> {
+    lines.iter().find_map(|l| l.trim().strip_prefix(TIME_RESPONSE_PREFIX)?.trim().parse().ok())
+}
+
+pub const UPTIME_COMMAND: &str = "UPTIME";
+const UPTIME_RESPONSE_PREFIX: &str = "UPTIME:";
+
+pub fn uptime_spec() -> CommandSpec {
+    CommandSpec { name: UPTIME_COMMAND, timeout: Duration::from_millis(300), matcher: ResponseMatcher::Custom(uptime_reply_complete), test_min_duration_ms: None }
+}
+
+fn uptime_reply_complete(lines: &[String]) -> bool {
+    lines.iter().any(|l| l.starts_with(UPTIME_RESPONSE_PREFIX) || l.starts_with("ERROR"))
+}
+
+/// Milliseconds in an `UPTIME:<millis>` reply
+pub fn parse_uptime_response(lines: &[String]) -> Option<u64> {
+    lines.iter().find_map(|l| l.trim().strip_prefix(UPTIME_RESPONSE_PREFIX)?.trim().trim_end_matches("ms").parse().ok())
+}
+
+/// One `TIME` round trip
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClockSample {
+    /// Host time (µs since the Unix epoch) halfway through the round trip
+    pub host_us: i64,
+    pub device_us: u64,
+    pub rtt_us: i64,
+}
+
+impl ClockSample {
+    pub fn new(sent: DateTime<Utc>, received: DateTime<Utc>, device_us: u64) -> Self {
+        let (sent, received) = (sent.timestamp_micros(), received.timestamp_micros());
+        let rtt_us = (received - sent).max(0);
+        Self { host_us: sent + rtt_us / 2, device_us, rtt_us }
+    }
+}
+
+/// Fitted relation between device and host time
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct ClockModel {
+    /// Anchor: device time and the host time (µs since the Unix epoch) it corresponds to
+    pub device_ref_us: u64,
+    pub host_ref_us: i64,
+    /// Device clock drift relative to the host in parts per million (0 until estimated)
+    pub skew_ppm: f64,
+    /// Half the round trip of the anchor sample: how far off the offset can be
+    pub uncertainty_us: i64,
+    pub samples: usize,
+}
+
+impl ClockModel {
+    /// Host time at which the device clock read `device_us`
+    pub fn to_host_time(&self, device_us: u64) -> DateTime<Utc> {
+        let elapsed = device_us as f64 - self.device_ref_us as f64;
+        let host_us = self.host_ref_us + (elapsed * (1.0 + self.skew_ppm / 1e6)).round() as i64;
+        DateTime::from_timestamp_micros(host_us).unwrap_or_else(Utc::now)
+    }
+
+    /// Device clock reading at host time `at`; `None` before the device booted
+    pub fn to_device_time(&self, at: DateTime<Utc>) -> Option<u64> {
+        let elapsed = (at.timestamp_micros() - self.host_ref_us) as f64 / (1.0 + self.skew_ppm / 1e6);
+        let device_us = self.device_ref_us as f64 + elapsed.round();
+        (device_us >= 0.0).then_some(device_us as u64)
+    }
+}
+
+/// Samples of the current connection
+#[derive(Debug, Default)]
+pub struct ClockEstimator {
+    samples: Vec<ClockSample>,
+}
+
+impl ClockEstimator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, sample: ClockSample) {
+        // The device rebooted: earlier samples describe another boot
+        if self.samples.last().is_some_and(|last| sample.device_us < last.device_us) {
+            self.samples.clear();
+        }
+        if self.samples.len() == MAX_SAMPLES {
+            self.samples.remove(0);
+        }
+        self.samples.push(sample);
+    }
+
+    pub fn fit(&self) -> Option<ClockModel> {
+        let best = self.samples.iter().min_by_key(|s| s.rtt_us)?;
+        // Samples delayed much longer than the best one only add noise
+        let good: Vec<&ClockSample> = self.samples.iter().filter(|s| s.rtt_us <= best.rtt_us * 2 + 1000).collect();
+        let skew_ppm = Self::fit_skew(&good).unwrap_or(0.0);
+        Some(ClockModel {
+            device_ref_us: best.device_us,
+            host_ref_us: best.host_us,
+            skew_ppm,
+            uncertainty_us: best.rtt_us / 2,
+            samples: self.samples.len(),
+        })
+    }
+
+    /// Least-squares slope of host time over device time, as drift in ppm
+    fn fit_skew(samples: &[&ClockSample]) -> Option<f64> {
+        let first = samples.iter().map(|s| s.device_us).min()?;
+        let last = samples.iter().map(|s| s.device_us).max()?;
+        if last - first < MIN_SKEW_SPAN.as_micros() as u64 {
+            return None;
+        }
+        // Relative to the first sample so the sums stay well within f64 precision
+        let origin = samples[0];
+        let points: Vec<(f64, f64)> = samples.iter()
+            .map(|s| ((s.device_us as f64 - origin.device_us as f64), (s.host_us - origin.host_us) as f64))
+            .collect();
+        let n = points.len() as f64;
+        let mean_x = points.iter().map(|p| p.0).sum::<f64>() / n;
+        let mean_y = points.iter().map(|p| p.1).sum::<f64>() / n;
+        let sxx: f64 = points.iter().map(|p| (p.0 - mean_x).powi(2)).sum();
+        let sxy: f64 = points.iter().map(|p| (p.0 - mean_x) * (p.1 - mean_y)).sum();
+        let skew_ppm = (sxy / sxx - 1.0) * 1e6;
+        (skew_ppm.abs() <= MAX_SKEW_PPM).then_some(skew_ppm)
+    }
+}
+
+static CLOCK: Lazy<RwLock<Option<ClockModel>>> = Lazy::new(|| RwLock::new(None));
+
+/// Clock model of the connected device, if it answered `TIME`
+pub fn current() -> Option<ClockModel> {
+    *CLOCK.read().unwrap()
+}
+
+pub fn publish(model: Option<ClockModel>) {
+    *CLOCK.write().unwrap() = model;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(us: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp_micros(1_800_000_000_000_000 + us).unwrap()
+    }
+
+    #[test]
+    fn anchors_on_the_fastest_round_trip() {
+        let mut estimator = ClockEstimator::new();
+        // Device booted 5 s before host time `at(0)`
+        estimator.add(ClockSample::new(at(0), at(4_000), 5_003_000));
+        estimator.add(ClockSample::new(at(10_000), at(10_800), 5_010_400));
+        let model = estimator.fit().unwrap();
+        assert_eq!((model.device_ref_us, model.uncertainty_us, model.skew_ppm), (5_010_400, 400, 0.0));
+        assert_eq!(model.to_host_time(5_020_400), at(20_400));
+        assert_eq!(model.to_device_time(at(20_400)), Some(5_020_400));
+        assert_eq!(model.to_device_time(at(-6_000_000)), None, "before boot");
+        assert_eq!(parse_time_response(&["TIME:5010400".to_string()]), Some(5_010_400));
+        assert_eq!(parse_time_response(&["ERROR:Unknown command: TIME".to_string()]), None);
+        assert_eq!(parse_uptime_response(&["UPTIME:81234".to_string()]), Some(81_234));
+        assert_eq!(parse_uptime_response(&["ERROR:Unknown command: UPTIME".to_string()]), None);
+    }
+
+    #[test]
+    fn estimates_drift_over_a_long_span() {
+        let mut estimator = ClockEstimator::new();
+        // Device clock runs 50 ppm slow: host time advances 1.00005 µs per device µs
+        for i in 0..6i64 {
+            let device_us = 1_000_000 + i * 10_000_000;
+            let host_us = i * 10_000_500;
+            estimator.add(ClockSample::new(at(host_us - 300), at(host_us + 300), device_us as u64));
+        }
+        let model = estimator.fit().unwrap();
+        assert!((model.skew_ppm - 50.0).abs() < 0.5, "skew {}", model.skew_ppm);
+        let host = model.to_host_time(101_000_000);
+        assert!((host - at(100_005_000)).num_microseconds().unwrap().abs() < 50);
+
+        // A reboot starts over
+        estimator.add(ClockSample::new(at(70_000_000), at(70_000_600), 2_000));
+        assert_eq!(estimator.fit().unwrap().samples, 1);
+    }
+}
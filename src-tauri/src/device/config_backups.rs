@@ -0,0 +1,208 @@
+//! Timestamped copies of `/config.bin` taken whenever the app reads or writes it.
+//!
+//! Backups live in `config_backups/` under the app data directory, one file per copy named
+//! `config_<serial>_<UTC timestamp>_<read|write>.bin`; the file name is the backup id. A copy
+//! identical to the device's newest backup is not stored again, and only the newest
+//! [`MAX_BACKUPS_PER_DEVICE`] copies per device are kept.
+//!
+//! Separately, the firmware saves the config it replaces as [`DEVICE_BACKUP_FILE`] on the device
+//! before every write; the device manager compares against and restores from that copy.
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, NaiveDateTime, SubsecRound, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::util::crc::crc32;
+
+/// Subdirectory of the app data directory holding config backups
+pub const CONFIG_BACKUPS_DIR_NAME: &str = "config_backups";
+pub const MAX_BACKUPS_PER_DEVICE: usize = 50;
+/// Previous config kept by the firmware on the device storage
+pub const DEVICE_BACKUP_FILE: &str = "/config.bak";
+/// Serial used in file names when the device reports none
+pub const UNKNOWN_SERIAL: &str = "unknown";
+const TIMESTAMP_FORMAT: &str = "%Y%m%dT%H%M%S%3fZ";
+const BACKUP_EXTENSION: &str = "bin";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackupReason {
+    /// Copy of the config as read from the device
+    Read,
+    /// Copy of the config after it was written to the device
+    Write,
+}
+
+impl BackupReason {
+    fn as_str(self) -> &'static str {
+        match self {
+            BackupReason::Read => "read",
+            BackupReason::Write => "write",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "read" => Some(BackupReason::Read),
+            "write" => Some(BackupReason::Write),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigBackupInfo {
+    /// File name of the backup, used to restore or delete it
+    pub id: String,
+    pub device_serial: String,
+    pub reason: BackupReason,
+    pub created_at: DateTime<Utc>,
+    pub size_bytes: u64,
+    pub crc32: u32,
+}
+
+/// Keep file names portable: anything but ASCII alphanumerics and '-' becomes '-'
+fn sanitize_serial(serial: &str) -> String {
+    let clean: String = serial.trim().chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '-' })
+        .collect();
+    if clean.is_empty() { UNKNOWN_SERIAL.to_string() } else { clean }
+}
+
+/// Serial, timestamp and reason encoded in a backup file name
+fn parse_file_name(name: &str) -> Option<(String, DateTime<Utc>, BackupReason)> {
+    let stem = name.strip_prefix("config_")?.strip_suffix(&format!(".{}", BACKUP_EXTENSION))?;
+    let mut parts = stem.rsplitn(3, '_');
+    let reason = BackupReason::parse(parts.next()?)?;
+    let timestamp = NaiveDateTime::parse_from_str(parts.next()?, TIMESTAMP_FORMAT).ok()?.and_utc();
+    let serial = parts.next()?.to_string();
+    Some((serial, timestamp, reason))
+}
+
+#[derive(Debug, Default)]
+pub struct ConfigBackupStore {
+    dir: Option<PathBuf>,
+}
+
+impl ConfigBackupStore {
+    /// Store without a directory; every operation fails until one is known
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Store located in the given app data directory
+    pub fn in_dir(dir: &Path) -> Self {
+        Self { dir: Some(dir.join(CONFIG_BACKUPS_DIR_NAME)) }
+    }
+
+    fn dir(&self) -> std::io::Result<&Path> {
+        self.dir.as_deref()
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "No config backup directory"))
+    }
+
+    /// Resolve a backup id to its path, rejecting anything that is not a backup file name
+    fn path_for(&self, id: &str) -> std::io::Result<PathBuf> {
+        if id.contains(['/', '\\']) || parse_file_name(id).is_none() {
+            return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("Invalid backup id '{}'", id)));
+        }
+        Ok(self.dir()?.join(id))
+    }
+
+    /// Archive a copy of `data`. Returns `None` when it matches the device's newest backup.
+    pub fn archive(&self, serial: Option<&str>, reason: BackupReason, data: &[u8]) -> std::io::Result<Option<ConfigBackupInfo>> {
+        let dir = self.dir()?;
+        let serial = sanitize_serial(serial.unwrap_or(UNKNOWN_SERIAL));
+        let existing = self.list(Some(&serial))?;
+        if let Some(newest) = existing.first() {
+            if newest.crc32 == crc32(data) && std::fs::read(dir.join(&newest.id))? == data {
+                return Ok(None);
+            }
+        }
+
+        std::fs::create_dir_all(dir)?;
+        // Millisecond precision, as stored in the file name
+        let created_at = Utc::now().trunc_subsecs(3);
+        let id = format!("config_{}_{}_{}.{}", serial, created_at.format(TIMESTAMP_FORMAT), reason.as_str(), BACKUP_EXTENSION);
+        std::fs::write(dir.join(&id), data)?;
+        log::info!("Archived config backup {} ({} bytes)", id, data.len());
+
+        for old in existing.iter().skip(MAX_BACKUPS_PER_DEVICE - 1) {
+            if let Err(e) = std::fs::remove_file(dir.join(&old.id)) {
+                log::warn!("Failed to prune config backup {}: {}", old.id, e);
+            }
+        }
+        Ok(Some(ConfigBackupInfo {
+            id,
+            device_serial: serial,
+            reason,
+            created_at,
+            size_bytes: data.len() as u64,
+            crc32: crc32(data),
+        }))
+    }
+
+    /// Backups, newest first, optionally only those of one device serial
+    pub fn list(&self, serial: Option<&str>) -> std::io::Result<Vec<ConfigBackupInfo>> {
+        let wanted = serial.map(sanitize_serial);
+        let entries = match std::fs::read_dir(self.dir()?) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        let mut list = Vec::new();
+        for entry in entries.flatten() {
+            let id = entry.file_name().to_string_lossy().to_string();
+            let Some((device_serial, created_at, reason)) = parse_file_name(&id) else { continue };
+            if wanted.as_ref().is_some_and(|w| *w != device_serial) {
+                continue;
+            }
+            let data = match std::fs::read(entry.path()) {
+                Ok(data) => data,
+                Err(e) => {
+                    log::warn!("Unreadable config backup {}: {}", id, e);
+                    continue;
+                }
+            };
+            list.push(ConfigBackupInfo { id, device_serial, reason, created_at, size_bytes: data.len() as u64, crc32: crc32(&data) });
+        }
+        list.sort_by(|a, b| b.created_at.cmp(&a.created_at).then_with(|| b.id.cmp(&a.id)));
+        Ok(list)
+    }
+
+    pub fn read(&self, id: &str) -> std::io::Result<Vec<u8>> {
+        std::fs::read(self.path_for(id)?)
+    }
+
+    pub fn delete(&self, id: &str) -> std::io::Result<()> {
+        std::fs::remove_file(self.path_for(id)?)?;
+        log::info!("Deleted config backup {}", id);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn archives_lists_and_deletes_backups() {
+        let dir = crate::util::test_util::TempDir::new("backups");
+        let store = ConfigBackupStore::in_dir(dir.path());
+        let first = store.archive(Some("JC_0001"), BackupReason::Read, &[1, 2, 3]).unwrap().unwrap();
+        assert_eq!(first.device_serial, "JC-0001");
+        assert!(store.archive(Some("JC_0001"), BackupReason::Read, &[1, 2, 3]).unwrap().is_none(), "unchanged config is not archived twice");
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        let second = store.archive(Some("JC_0001"), BackupReason::Write, &[4, 5]).unwrap().unwrap();
+        store.archive(None, BackupReason::Read, &[9]).unwrap();
+
+        let listed = store.list(Some("JC_0001")).unwrap();
+        assert_eq!(listed.iter().map(|b| b.id.as_str()).collect::<Vec<_>>(), vec![second.id.as_str(), first.id.as_str()]);
+        assert_eq!(listed[0].reason, BackupReason::Write);
+        assert_eq!(store.list(None).unwrap().len(), 3);
+        assert_eq!(store.read(&first.id).unwrap(), vec![1, 2, 3]);
+
+        assert!(store.read("../profiles.json").is_err());
+        store.delete(&first.id).unwrap();
+        assert_eq!(store.list(Some("JC-0001")).unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,26 @@
+//! Config previews: an edited config running from device RAM without being stored.
+//!
+//! A preview is uploaded with TEST_CONFIG and ends with `commit_preview_config` (stored through
+//! the normal write transaction), `discard_preview_config` (TEST_CONFIG_REVERT), a regular config
+//! write, or a disconnect, which reverts it while the device is still reachable. Every change is
+//! announced with [`CONFIG_PREVIEW_EVENT`], carrying the active preview or `null`.
+use serde::Serialize;
+use uuid::Uuid;
+
+pub const CONFIG_PREVIEW_EVENT: &str = "config_preview_changed";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigPreview {
+    pub device_id: Uuid,
+    pub started_at: chrono::DateTime<chrono::Utc>,
+    pub size_bytes: usize,
+    /// Validated config bytes running on the device
+    #[serde(skip)]
+    pub data: Vec<u8>,
+}
+
+impl ConfigPreview {
+    pub fn new(device_id: Uuid, data: Vec<u8>) -> Self {
+        Self { device_id, started_at: chrono::Utc::now(), size_bytes: data.len(), data }
+    }
+}
@@ -0,0 +1,231 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use crate::util::persist::{load_json, save_json};
+
+pub const DEV_MODE_FILE_NAME: &str = "dev_mode.json";
+
+/// One command of the post-flash smoke test
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SmokeTestStep {
+    pub command: String,
+    /// Substring the response must contain (None = any response passes)
+    pub expect: Option<String>,
+    pub timeout_ms: u64,
+}
+
+/// Developer mode settings (firmware A/B iteration harness)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DevModeSettings {
+    pub enabled: bool,
+    /// Reconnect to the device automatically once the new firmware enumerates
+    pub auto_reconnect: bool,
+    pub reconnect_timeout_ms: u64,
+    pub uf2_drive_timeout_ms: u64,
+    pub smoke_test: Vec<SmokeTestStep>,
+}
+
+impl Default for DevModeSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            auto_reconnect: true,
+            reconnect_timeout_ms: 15000,
+            uf2_drive_timeout_ms: 15000,
+            smoke_test: vec![
+                SmokeTestStep { command: "IDENTIFY".to_string(), expect: Some("JOYCORE_ID".to_string()), timeout_ms: 500 },
+                SmokeTestStep { command: "STATUS".to_string(), expect: Some("Config Status".to_string()), timeout_ms: 1200 },
+            ],
+        }
+    }
+}
+
+impl DevModeSettings {
+    /// Refuse zero timeouts and clamp longer ones to their maximum
+    pub fn validated(mut self) -> Result<Self, String> {
+        self.reconnect_timeout_ms = clamp_timeout("Reconnect timeout", self.reconnect_timeout_ms, DEV_MAX_WAIT_TIMEOUT_MS)?;
+        self.uf2_drive_timeout_ms = clamp_timeout("UF2 drive timeout", self.uf2_drive_timeout_ms, DEV_MAX_WAIT_TIMEOUT_MS)?;
+        for step in &mut self.smoke_test {
+            step.timeout_ms = clamp_timeout(&format!("Timeout of smoke test step {}", step.command), step.timeout_ms, CONSOLE_MAX_TIMEOUT_MS)?;
+        }
+        Ok(self)
+    }
+}
+
+/// Upper bound of the reconnect and UF2 drive waits after a flash
+pub const DEV_MAX_WAIT_TIMEOUT_MS: u64 = 120_000;
+
+fn clamp_timeout(name: &str, timeout_ms: u64, max_ms: u64) -> Result<u64, String> {
+    if timeout_ms == 0 {
+        return Err(format!("{} must be greater than 0 ms", name));
+    }
+    Ok(timeout_ms.min(max_ms))
+}
+
+/// Bounds of the developer console's per-command timeout
+pub const CONSOLE_DEFAULT_TIMEOUT_MS: u64 = 1000;
+pub const CONSOLE_MAX_TIMEOUT_MS: u64 = 30_000;
+/// A console command is complete once the device stays quiet this long after a line
+pub const CONSOLE_QUIET_MS: u64 = 150;
+
+/// Reply to a developer console command
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConsoleResponse {
+    pub command: String,
+    pub lines: Vec<String>,
+    /// The device sent nothing before the timeout
+    pub timed_out: bool,
+    pub duration_ms: u64,
+}
+
+/// Reject console input that would be sent as more than one command line
+pub fn validate_console_command(command: &str) -> Result<&str, String> {
+    let trimmed = command.trim();
+    if trimmed.is_empty() {
+        return Err("Command is empty".to_string());
+    }
+    if trimmed.chars().any(|c| c == '\n' || c == '\r' || c == '\0') {
+        return Err("Command must be a single line".to_string());
+    }
+    Ok(trimmed)
+}
+
+/// Last local UF2 flashed to a device
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LastFlashedFirmware {
+    pub device_key: String,
+    pub image_path: PathBuf,
+    pub flashed_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SmokeTestStepResult {
+    pub command: String,
+    pub passed: bool,
+    pub response: Vec<String>,
+    pub error: Option<String>,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SmokeTestReport {
+    pub passed: bool,
+    pub steps: Vec<SmokeTestStepResult>,
+}
+
+/// Outcome of a developer flash cycle
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DevFlashReport {
+    pub device_key: String,
+    pub image_path: PathBuf,
+    pub drive_path: PathBuf,
+    pub reconnected: bool,
+    /// Device id after re-enumeration (ids are reassigned when the port reappears)
+    pub device_id: Option<String>,
+    pub smoke_test: Option<SmokeTestReport>,
+    pub passed: bool,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct DevModeFile {
+    #[serde(default)]
+    settings: DevModeSettings,
+    #[serde(default)]
+    last_flashed: HashMap<String, LastFlashedFirmware>,
+}
+
+/// Persisted developer mode state: settings plus last flashed image per device
+#[derive(Debug, Default)]
+pub struct DevModeStore {
+    path: Option<PathBuf>,
+    data: DevModeFile,
+}
+
+impl DevModeStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load from disk; a missing or corrupt file yields defaults
+    pub fn load(path: PathBuf) -> Self {
+        let data = load_json(&path, "developer mode");
+        Self { path: Some(path), data }
+    }
+
+    pub fn settings(&self) -> &DevModeSettings {
+        &self.data.settings
+    }
+
+    pub fn set_settings(&mut self, settings: DevModeSettings) {
+        self.data.settings = settings;
+        self.save();
+    }
+
+    pub fn last_flashed(&self, device_key: &str) -> Option<&LastFlashedFirmware> {
+        self.data.last_flashed.get(device_key)
+    }
+
+    pub fn record_flash(&mut self, device_key: &str, image_path: PathBuf) {
+        self.data.last_flashed.insert(device_key.to_string(), LastFlashedFirmware {
+            device_key: device_key.to_string(),
+            image_path,
+            flashed_at: Utc::now(),
+        });
+        self.save();
+    }
+
+    fn save(&self) {
+        if let Some(path) = &self.path {
+            save_json(path, &self.data, "developer mode");
+        }
+    }
+}
+
+/// Stable key identifying a physical device across re-enumeration (USB serial, else port)
+pub fn device_key(device: &super::Device) -> String {
+    match &device.serial_number {
+        Some(sn) if !sn.is_empty() => format!("sn:{}", sn),
+        _ => format!("port:{}", device.port_name),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn remembers_last_flashed_image_across_reload() {
+        let path = std::env::temp_dir().join(format!("joycore_devmode_{}.json", uuid::Uuid::new_v4()));
+        let mut store = DevModeStore::load(path.clone());
+        store.record_flash("sn:E660", PathBuf::from("/builds/a.uf2"));
+        store.record_flash("sn:E660", PathBuf::from("/builds/b.uf2"));
+        let reloaded = DevModeStore::load(path.clone());
+        assert_eq!(reloaded.last_flashed("sn:E660").unwrap().image_path, PathBuf::from("/builds/b.uf2"));
+        assert!(!reloaded.settings().enabled);
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn rejects_zero_and_clamps_long_timeouts() {
+        let mut settings = DevModeSettings { reconnect_timeout_ms: 10_000_000, ..DevModeSettings::default() };
+        settings.smoke_test[0].timeout_ms = u64::MAX;
+        let settings = settings.validated().unwrap();
+        assert_eq!(settings.reconnect_timeout_ms, DEV_MAX_WAIT_TIMEOUT_MS);
+        assert_eq!(settings.smoke_test[0].timeout_ms, CONSOLE_MAX_TIMEOUT_MS);
+        assert_eq!(settings.smoke_test[1].timeout_ms, 1200);
+
+        assert!(DevModeSettings { uf2_drive_timeout_ms: 0, ..DevModeSettings::default() }.validated().is_err());
+        let mut settings = DevModeSettings::default();
+        settings.smoke_test[1].timeout_ms = 0;
+        assert!(settings.validated().is_err());
+    }
+
+    #[test]
+    fn console_accepts_single_line_commands_only() {
+        assert_eq!(validate_console_command("  STATUS \n"), Ok("STATUS"));
+        assert!(validate_console_command("   ").is_err());
+        assert!(validate_console_command("STATUS\nSAVE_CONFIG").is_err());
+    }
+}
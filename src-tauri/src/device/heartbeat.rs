@@ -0,0 +1,101 @@
+//! Connection watchdog for the serial link.
+//!
+//! Every [`HEARTBEAT_INTERVAL`] the manager checks whether the unified reader has read any line
+//! since the previous tick. Monitor streams and regular commands count as proof of life; only an
+//! idle link is probed with `STATUS`. [`HEARTBEAT_MAX_MISSES`] consecutive failed probes mark the
+//! link dead.
+use std::time::Duration;
+
+use crate::serial::unified::types::{CommandResponse, CommandSpec, ResponseMatcher};
+use crate::serial::SerialError;
+
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
+pub const HEARTBEAT_TIMEOUT: Duration = Duration::from_millis(1500);
+pub const HEARTBEAT_MAX_MISSES: u32 = 2;
+/// Connection error reported when the watchdog gives up on the link
+pub const HEARTBEAT_TIMEOUT_ERROR: &str = "heartbeat timeout";
+
+pub fn heartbeat_spec() -> CommandSpec {
+    CommandSpec { name: "STATUS", timeout: HEARTBEAT_TIMEOUT, matcher: ResponseMatcher::Contains("Config Status"), test_min_duration_ms: None }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProbeOutcome {
+    Alive,
+    /// Another command held the link; says nothing about its health
+    Busy,
+    Missed,
+}
+
+impl ProbeOutcome {
+    pub fn from_result(result: &Result<CommandResponse, SerialError>) -> Self {
+        match result {
+            Ok(_) => ProbeOutcome::Alive,
+            Err(SerialError::ProtocolError(msg)) if msg.contains("in flight") => ProbeOutcome::Busy,
+            Err(_) => ProbeOutcome::Missed,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct HeartbeatState {
+    last_lines_read: u64,
+    misses: u32,
+}
+
+impl HeartbeatState {
+    /// True when nothing was read since the last tick, so the link has to be probed
+    pub fn needs_probe(&mut self, lines_read: u64) -> bool {
+        if lines_read != self.last_lines_read {
+            self.last_lines_read = lines_read;
+            self.misses = 0;
+            return false;
+        }
+        true
+    }
+
+    /// Record a probe result; returns true once the link should be treated as dead
+    pub fn record(&mut self, outcome: ProbeOutcome, lines_read: u64) -> bool {
+        self.last_lines_read = lines_read;
+        match outcome {
+            ProbeOutcome::Alive => self.misses = 0,
+            ProbeOutcome::Busy => {}
+            ProbeOutcome::Missed => self.misses += 1,
+        }
+        self.misses >= HEARTBEAT_MAX_MISSES
+    }
+
+    pub fn misses(&self) -> u32 {
+        self.misses
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn activity_skips_probe_and_clears_misses() {
+        let mut state = HeartbeatState::default();
+        assert!(state.needs_probe(0));
+        assert!(!state.record(ProbeOutcome::Missed, 0));
+        assert_eq!(state.misses(), 1);
+
+        // Lines arrived (e.g. monitor stream) -> no probe, misses reset
+        assert!(!state.needs_probe(12));
+        assert_eq!(state.misses(), 0);
+        assert!(state.needs_probe(12));
+    }
+
+    #[test]
+    fn consecutive_misses_declare_link_dead() {
+        let mut state = HeartbeatState::default();
+        assert!(!state.record(ProbeOutcome::Missed, 0));
+        assert!(!state.record(ProbeOutcome::Busy, 0));
+        assert!(state.record(ProbeOutcome::Missed, 0));
+
+        let busy = Err(SerialError::ProtocolError("Another command in flight".into()));
+        assert_eq!(ProbeOutcome::from_result(&busy), ProbeOutcome::Busy);
+        assert_eq!(ProbeOutcome::from_result(&Err(SerialError::Timeout)), ProbeOutcome::Missed);
+    }
+}
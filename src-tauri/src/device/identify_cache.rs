@@ -0,0 +1,85 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use crate::serial::SerialDeviceInfo;
+use super::port_filter::port_key;
+
+/// How long an IDENTIFY answer is reused before the port is probed again
+pub const IDENTIFY_CACHE_TTL: Duration = Duration::from_secs(30);
+
+/// Recent IDENTIFY answers by port, so repeated discovery passes do not reopen every port.
+/// Only clean answers are kept (a JoyCore device or a port that is not one); a port that is
+/// added or removed is forgotten, since a different device may sit behind it now.
+#[derive(Debug)]
+pub struct IdentifyCache {
+    ttl: Duration,
+    entries: HashMap<String, (Instant, Option<SerialDeviceInfo>)>,
+}
+
+impl IdentifyCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self { ttl, entries: HashMap::new() }
+    }
+
+    /// The answer for `port_name` if it is younger than the TTL at `now`
+    pub fn get(&self, port_name: &str, now: Instant) -> Option<Option<SerialDeviceInfo>> {
+        self.entries.get(&port_key(port_name))
+            .filter(|(at, _)| now.saturating_duration_since(*at) < self.ttl)
+            .map(|(_, info)| info.clone())
+    }
+
+    pub fn insert(&mut self, port_name: &str, info: Option<SerialDeviceInfo>, now: Instant) {
+        self.entries.retain(|_, (at, _)| now.saturating_duration_since(*at) < self.ttl);
+        self.entries.insert(port_key(port_name), (now, info));
+    }
+
+    /// Forget `port_name` (any spelling the port monitors use)
+    pub fn invalidate(&mut self, port_name: &str) {
+        self.entries.remove(&port_key(port_name));
+    }
+}
+
+impl Default for IdentifyCache {
+    fn default() -> Self {
+        Self::new(IDENTIFY_CACHE_TTL)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(port_name: &str) -> SerialDeviceInfo {
+        SerialDeviceInfo {
+            port_name: port_name.to_string(),
+            vid: 0,
+            pid: 0,
+            serial_number: None,
+            manufacturer: None,
+            product: None,
+            firmware_version: Some("1.2.0".to_string()),
+            device_signature: None,
+            hardware_revision: None,
+        }
+    }
+
+    #[test]
+    fn reuses_answers_within_ttl() {
+        let start = Instant::now();
+        let mut cache = IdentifyCache::new(Duration::from_secs(5));
+        cache.insert("/dev/ttyACM0", Some(info("/dev/ttyACM0")), start);
+        cache.insert("/dev/ttyUSB0", None, start);
+        assert!(cache.get("/dev/ttyACM0", start + Duration::from_secs(4)).unwrap().is_some());
+        assert_eq!(cache.get("/dev/ttyUSB0", start).map(|i| i.is_none()), Some(true), "non-JoyCore answers are cached too");
+        assert!(cache.get("/dev/ttyACM0", start + Duration::from_secs(5)).is_none(), "expired");
+        assert!(cache.get("/dev/ttyACM1", start).is_none());
+    }
+
+    #[test]
+    fn invalidates_by_monitor_port_name() {
+        let start = Instant::now();
+        let mut cache = IdentifyCache::default();
+        cache.insert("/dev/ttyACM0", Some(info("/dev/ttyACM0")), start);
+        cache.invalidate("ttyACM0");
+        assert!(cache.get("/dev/ttyACM0", start).is_none());
+    }
+}
@@ -0,0 +1,337 @@
+//! Every device the app has seen, by USB serial number.
+//!
+//! The registry survives restarts so a stick that is not plugged in still shows up in the device
+//! list (with `present: false`) and its backups and last known firmware stay reachable. A
+//! device keeps the id it was first seen with, so its entry is the same whether it is present
+//! or not. Devices that report no serial number cannot be told apart and are not recorded.
+//! Each entry also carries the device's serial connection overrides (see `serial::connection`).
+//! Overrides can also be set for a port (by [`port_key`]) before any device on it is known, e.g.
+//! when the device only answers discovery at another baud rate; the first device recorded on
+//! that port takes them over.
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::PathBuf;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use crate::serial::ConnectionSettings;
+use crate::util::persist::{load_json, save_json};
+use super::config_backups::ConfigBackupInfo;
+use super::port_filter::port_key;
+use super::{ConnectionState, Device};
+
+pub const KNOWN_DEVICES_FILE_NAME: &str = "known_devices.json";
+/// Connection overrides of ports without a known device, next to the registry file
+pub const PORT_CONNECTION_SETTINGS_FILE_NAME: &str = "port_connection_settings.json";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KnownDevice {
+    pub id: Uuid,
+    pub serial_number: String,
+    pub manufacturer: Option<String>,
+    pub product: Option<String>,
+    /// Port the device was last seen on
+    pub last_port: String,
+    pub last_firmware_version: Option<String>,
+    /// Id of the newest config backup (see `config_backups`)
+    pub last_backup_id: Option<String>,
+    pub last_backup_at: Option<DateTime<Utc>>,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "ConnectionSettings::is_default")]
+    pub connection: ConnectionSettings,
+}
+
+impl KnownDevice {
+    /// Device list entry for the device while it is not plugged in
+    pub fn offline_device(&self) -> Device {
+        Device {
+            id: self.id,
+            port_name: self.last_port.clone(),
+            serial_number: Some(self.serial_number.clone()),
+            manufacturer: self.manufacturer.clone(),
+            product: self.product.clone(),
+            alias: None,
+            present: false,
+            connection_state: ConnectionState::Disconnected,
+            device_status: None,
+            capabilities: None,
+            last_seen: self.last_seen,
+        }
+    }
+}
+
+/// Persisted registry of known devices, saved to disk on every change
+#[derive(Debug, Default)]
+pub struct KnownDeviceRegistry {
+    path: Option<PathBuf>,
+    devices: BTreeMap<String, KnownDevice>,
+    /// Connection overrides by port key, for devices not known yet
+    port_settings: BTreeMap<String, ConnectionSettings>,
+}
+
+impl KnownDeviceRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load a persisted registry; a missing or unreadable file yields an empty registry
+    pub fn load(path: PathBuf) -> Self {
+        let list: Vec<KnownDevice> = load_json(&path, "known devices");
+        let devices = list.into_iter().map(|d| (d.serial_number.clone(), d)).collect();
+        let port_settings = load_json(&path.with_file_name(PORT_CONNECTION_SETTINGS_FILE_NAME), "port connection settings");
+        Self { path: Some(path), devices, port_settings }
+    }
+
+    pub fn get(&self, serial_number: &str) -> Option<&KnownDevice> {
+        self.devices.get(serial_number)
+    }
+
+    /// Id the device with `serial_number` was first seen with
+    pub fn id_of(&self, serial_number: Option<&str>) -> Option<Uuid> {
+        self.devices.get(serial_number?).map(|d| d.id)
+    }
+
+    /// Note that `device` is present, adding it on first sight. The file is only rewritten when
+    /// the device is new or a stored field other than `last_seen` changed, since discovery calls
+    /// this on every pass; `last_seen` is saved along with the next such change.
+    pub fn record_seen(&mut self, device: &Device) {
+        let Some(serial) = device.serial_number.as_deref().filter(|s| !s.trim().is_empty()) else { return };
+        let firmware = device.device_status.as_ref().map(|s| s.firmware_version.clone());
+        let before = self.devices.get(serial).cloned();
+        let port_settings = &mut self.port_settings;
+        let known = self.devices.entry(serial.to_string()).or_insert_with(|| {
+            log::info!("Recording new known device {}", serial);
+            let connection = port_settings.remove(&port_key(&device.port_name)).unwrap_or_default();
+            KnownDevice {
+                id: device.id,
+                serial_number: serial.to_string(),
+                manufacturer: None,
+                product: None,
+                last_port: String::new(),
+                last_firmware_version: None,
+                last_backup_id: None,
+                last_backup_at: None,
+                first_seen: device.last_seen,
+                last_seen: device.last_seen,
+                connection,
+            }
+        });
+        known.manufacturer = device.manufacturer.clone().or(known.manufacturer.take());
+        known.product = device.product.clone().or(known.product.take());
+        known.last_port = device.port_name.clone();
+        known.last_firmware_version = firmware.or(known.last_firmware_version.take());
+        known.last_seen = device.last_seen.max(known.last_seen);
+        let changed = before.map_or(true, |before| KnownDevice { last_seen: known.last_seen, ..before } != *known);
+        if changed {
+            self.save();
+        }
+    }
+
+    /// Note a config backup taken from the device with `serial_number`
+    pub fn record_backup(&mut self, serial_number: &str, backup: &ConfigBackupInfo) {
+        if let Some(known) = self.devices.get_mut(serial_number) {
+            known.last_backup_id = Some(backup.id.clone());
+            known.last_backup_at = Some(backup.created_at);
+            self.save();
+        }
+    }
+
+    /// Connection settings of the device with `serial_number`, else the overrides of `port_name`
+    /// or of the device last seen on it; defaults for unknown devices
+    pub fn connection_settings(&self, serial_number: Option<&str>, port_name: &str) -> ConnectionSettings {
+        if let Some(known) = serial_number.and_then(|s| self.devices.get(s)) {
+            return known.connection;
+        }
+        self.port_connection_settings(port_name)
+            .or_else(|| self.devices.values().filter(|d| d.last_port == port_name).max_by_key(|d| d.last_seen).map(|d| d.connection))
+            .unwrap_or_default()
+    }
+
+    /// Overrides stored for `port_name` while no device on it is known
+    pub fn port_connection_settings(&self, port_name: &str) -> Option<ConnectionSettings> {
+        self.port_settings.get(&port_key(port_name)).copied()
+    }
+
+    /// Settings to probe each port with: those of the device last seen there, unless the port
+    /// has overrides of its own
+    pub fn probe_settings(&self) -> HashMap<String, ConnectionSettings> {
+        let mut by_port: HashMap<String, ConnectionSettings> = HashMap::new();
+        let mut devices: Vec<&KnownDevice> = self.devices.values().collect();
+        devices.sort_by_key(|d| d.last_seen);
+        for device in devices {
+            by_port.insert(port_key(&device.last_port), device.connection);
+        }
+        by_port.extend(self.port_settings.iter().map(|(key, settings)| (key.clone(), *settings)));
+        by_port
+    }
+
+    /// Replace the connection settings of a known device; false when the device is not known
+    pub fn set_connection_settings(&mut self, serial_number: &str, settings: ConnectionSettings) -> bool {
+        let Some(known) = self.devices.get_mut(serial_number) else { return false };
+        known.connection = settings;
+        self.save();
+        true
+    }
+
+    /// Store overrides for the port `port_name` until a device on it is recorded; default
+    /// settings remove them
+    pub fn set_port_connection_settings(&mut self, port_name: &str, settings: ConnectionSettings) {
+        let key = port_key(port_name);
+        if settings.is_default() {
+            self.port_settings.remove(&key);
+        } else {
+            self.port_settings.insert(key, settings);
+        }
+        self.save_port_settings();
+    }
+
+    /// Known devices, most recently seen first
+    pub fn list(&self) -> Vec<KnownDevice> {
+        let mut list: Vec<KnownDevice> = self.devices.values().cloned().collect();
+        list.sort_by_key(|d| std::cmp::Reverse(d.last_seen));
+        list
+    }
+
+    /// Device list entries for known devices whose serial is not among `present`
+    pub fn offline_devices(&self, present: &HashSet<&str>) -> Vec<Device> {
+        self.devices.values()
+            .filter(|d| !present.contains(d.serial_number.as_str()))
+            .map(KnownDevice::offline_device)
+            .collect()
+    }
+
+    /// Drop a device from the registry; its backups are kept
+    pub fn forget(&mut self, serial_number: &str) -> bool {
+        let removed = self.devices.remove(serial_number).is_some();
+        if removed { self.save(); }
+        removed
+    }
+
+    fn save(&self) {
+        if let Some(path) = &self.path {
+            save_json(path, &self.list(), "known devices");
+        }
+        self.save_port_settings();
+    }
+
+    fn save_port_settings(&self) {
+        if let Some(path) = &self.path {
+            save_json(&path.with_file_name(PORT_CONNECTION_SETTINGS_FILE_NAME), &self.port_settings, "port connection settings");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn device(serial: Option<&str>, port: &str) -> Device {
+        let mut device = Device::new(port.to_string());
+        device.serial_number = serial.map(str::to_string);
+        device.product = Some("JoyCore".to_string());
+        device
+    }
+
+    #[test]
+    fn records_devices_and_lists_offline_ones() {
+        let mut registry = KnownDeviceRegistry::new();
+        let stick = device(Some("E661"), "COM3");
+        registry.record_seen(&stick);
+        registry.record_seen(&device(None, "COM4"));
+        registry.record_seen(&device(Some("A002"), "COM5"));
+        assert_eq!(registry.list().len(), 2, "devices without a serial are not recorded");
+
+        // Seen again on another port with a new session id: the first id is kept
+        registry.record_seen(&device(Some("E661"), "COM8"));
+        let known = registry.get("E661").unwrap();
+        assert_eq!((known.id, known.last_port.as_str(), known.product.as_deref()), (stick.id, "COM8", Some("JoyCore")));
+        assert_eq!(registry.id_of(Some("E661")), Some(stick.id));
+
+        let offline = registry.offline_devices(&HashSet::from(["A002"]));
+        assert_eq!(offline.len(), 1);
+        assert_eq!((offline[0].id, offline[0].present), (stick.id, false));
+        assert!(registry.forget("E661"));
+        assert!(registry.offline_devices(&HashSet::new()).iter().all(|d| d.serial_number.as_deref() == Some("A002")));
+    }
+
+    #[test]
+    fn persists_firmware_and_last_backup() {
+        let path = std::env::temp_dir().join(format!("joycore_known_devices_{}.json", uuid::Uuid::new_v4()));
+        let mut registry = KnownDeviceRegistry::load(path.clone());
+        let mut stick = device(Some("E661"), "COM3");
+        stick.device_status = Some(crate::serial::protocol::DeviceStatus {
+            firmware_version: "1.4.0".to_string(),
+            device_name: "JoyCore".to_string(),
+            axes_count: 8,
+            buttons_count: 32,
+            connected: true,
+            ..Default::default()
+        });
+        registry.record_seen(&stick);
+        let backup = ConfigBackupInfo {
+            id: "config_E661_20260101T000000000Z_read.bin".to_string(),
+            device_serial: "E661".to_string(),
+            reason: super::super::config_backups::BackupReason::Read,
+            created_at: Utc::now(),
+            size_bytes: 1024,
+            crc32: 0,
+        };
+        registry.record_backup("E661", &backup);
+        // A later sighting without a status keeps the last firmware
+        registry.record_seen(&device(Some("E661"), "COM3"));
+
+        let settings = ConnectionSettings { baud_rate: Some(921_600), ..Default::default() };
+        assert!(registry.set_connection_settings("E661", settings));
+        assert!(!registry.set_connection_settings("0000", settings));
+
+        let reloaded = KnownDeviceRegistry::load(path.clone());
+        let known = reloaded.get("E661").unwrap();
+        assert_eq!(known.last_firmware_version.as_deref(), Some("1.4.0"));
+        assert_eq!(known.last_backup_id.as_deref(), Some(backup.id.as_str()));
+        assert_eq!(reloaded.connection_settings(Some("E661"), "COM9"), settings);
+        assert_eq!(reloaded.connection_settings(None, "COM3"), settings, "matched by last port");
+        assert!(reloaded.connection_settings(None, "COM9").is_default());
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn rewrites_file_only_when_a_stored_field_changes() {
+        let dir = crate::util::test_util::TempDir::new("known_devices_writes");
+        let path = dir.join(KNOWN_DEVICES_FILE_NAME);
+        let mut registry = KnownDeviceRegistry::load(path.clone());
+        let mut stick = device(Some("E661"), "COM3");
+        registry.record_seen(&stick);
+        assert!(path.exists(), "new device");
+
+        std::fs::remove_file(&path).unwrap();
+        stick.last_seen = stick.last_seen + chrono::Duration::seconds(5);
+        registry.record_seen(&stick);
+        assert!(!path.exists(), "only last_seen changed");
+        assert_eq!(registry.get("E661").unwrap().last_seen, stick.last_seen);
+
+        stick.port_name = "COM4".to_string();
+        registry.record_seen(&stick);
+        assert_eq!(KnownDeviceRegistry::load(path).get("E661").unwrap().last_port, "COM4");
+    }
+
+    #[test]
+    fn port_overrides_apply_until_a_device_is_recorded() {
+        let dir = crate::util::test_util::TempDir::new("port_settings");
+        let path = dir.join("known_devices.json");
+        let mut registry = KnownDeviceRegistry::load(path.clone());
+        let settings = ConnectionSettings { baud_rate: Some(921_600), ..Default::default() };
+        registry.set_port_connection_settings("/dev/ttyACM1", settings);
+
+        let reloaded = KnownDeviceRegistry::load(path.clone());
+        assert_eq!(reloaded.connection_settings(None, "ttyACM1"), settings);
+        assert_eq!(reloaded.probe_settings().get("ttyacm1"), Some(&settings));
+
+        let mut registry = reloaded;
+        registry.record_seen(&device(Some("E662"), "/dev/ttyACM1"));
+        assert_eq!(registry.get("E662").unwrap().connection, settings, "the first device on the port takes the overrides");
+        assert_eq!(registry.port_connection_settings("/dev/ttyACM1"), None);
+        registry.set_port_connection_settings("COM4", settings);
+        registry.set_port_connection_settings("COM4", ConnectionSettings::default());
+        assert_eq!(KnownDeviceRegistry::load(path).port_connection_settings("COM4"), None);
+    }
+}
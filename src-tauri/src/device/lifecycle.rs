@@ -0,0 +1,93 @@
+//! App exit coordination.
+//!
+//! Long-running background tasks (update scheduler, HID supervisor) register here so shutdown
+//! can abort them. [`DeviceManager::shutdown`](super::DeviceManager::shutdown) runs once from
+//! Tauri's exit event: it stops the port monitor and these tasks, stops raw monitoring (which
+//! sends STOP_RAW_MONITOR), closes the serial port, joins the HID thread and waits for a config
+//! backup being written, all within [`SHUTDOWN_DEADLINE`] so a wedged port cannot keep the
+//! process alive.
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use tokio::task::JoinHandle;
+
+pub const SHUTDOWN_DEADLINE: Duration = Duration::from_secs(5);
+
+#[derive(Default)]
+pub struct Lifecycle {
+    shutting_down: AtomicBool,
+    tasks: Mutex<Vec<(&'static str, JoinHandle<()>)>>,
+}
+
+impl Lifecycle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_shutting_down(&self) -> bool {
+        self.shutting_down.load(Ordering::SeqCst)
+    }
+
+    /// Mark the start of shutdown; false when it already started
+    pub fn begin_shutdown(&self) -> bool {
+        !self.shutting_down.swap(true, Ordering::SeqCst)
+    }
+
+    /// Keep `handle` to abort it on shutdown. Aborted right away when shutdown already started.
+    pub fn track(&self, name: &'static str, handle: JoinHandle<()>) {
+        if self.is_shutting_down() {
+            handle.abort();
+            return;
+        }
+        let mut tasks = self.tasks.lock().unwrap_or_else(|e| e.into_inner());
+        tasks.retain(|(_, task)| !task.is_finished());
+        tasks.push((name, handle));
+    }
+
+    /// Abort every tracked task still running and wait for them to end
+    pub async fn abort_tasks(&self) -> usize {
+        let tasks = std::mem::take(&mut *self.tasks.lock().unwrap_or_else(|e| e.into_inner()));
+        let mut aborted = 0;
+        for (name, task) in tasks {
+            if task.is_finished() {
+                continue;
+            }
+            task.abort();
+            let _ = task.await;
+            log::debug!("Stopped background task {}", name);
+            aborted += 1;
+        }
+        aborted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn aborts_tracked_tasks_once() {
+        let lifecycle = Lifecycle::new();
+        lifecycle.track("forever", tokio::spawn(std::future::pending()));
+        let done = tokio::spawn(async {});
+        while !done.is_finished() {
+            tokio::task::yield_now().await;
+        }
+        lifecycle.track("done", done);
+
+        assert!(lifecycle.begin_shutdown());
+        assert!(!lifecycle.begin_shutdown());
+        assert_eq!(lifecycle.abort_tasks().await, 1);
+
+        let late = tokio::spawn(std::future::pending::<()>());
+        let probe = late.abort_handle();
+        lifecycle.track("late", late);
+        let cancelled = tokio::time::timeout(Duration::from_secs(1), async {
+            while !probe.is_finished() {
+                tokio::task::yield_now().await;
+            }
+        });
+        assert!(cancelled.await.is_ok(), "tasks tracked after shutdown started are aborted");
+    }
+}
@@ -0,0 +1,142 @@
+//! Incremental device list events.
+//!
+//! Rather than re-sending the whole list after every state change, the manager diffs each list
+//! against the last one it published and emits `device_added`, `device_updated` (with the full
+//! device) and `device_removed` (with its id). Every event carries the next sequence number.
+//! `get_device_list_snapshot` returns the list together with the sequence number it is current
+//! as of; a frontend that sees a gap in the numbers has missed an event and reloads the snapshot.
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::Device;
+
+pub const DEVICE_ADDED_EVENT: &str = "device_added";
+pub const DEVICE_UPDATED_EVENT: &str = "device_updated";
+pub const DEVICE_REMOVED_EVENT: &str = "device_removed";
+
+/// Result of `get_device_list_snapshot`; the next event has `seq + 1`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceListSnapshot {
+    pub seq: u64,
+    pub devices: Vec<Device>,
+}
+
+/// Payload of `device_added` and `device_updated`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceChangedEvent {
+    pub seq: u64,
+    pub device: Device,
+}
+
+/// Payload of `device_removed`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceRemovedEvent {
+    pub seq: u64,
+    pub id: Uuid,
+}
+
+#[derive(Debug, Clone)]
+pub enum DeviceListDelta {
+    Added(DeviceChangedEvent),
+    Updated(DeviceChangedEvent),
+    Removed(DeviceRemovedEvent),
+}
+
+impl DeviceListDelta {
+    pub fn seq(&self) -> u64 {
+        match self {
+            DeviceListDelta::Added(e) | DeviceListDelta::Updated(e) => e.seq,
+            DeviceListDelta::Removed(e) => e.seq,
+        }
+    }
+
+    pub fn event_name(&self) -> &'static str {
+        match self {
+            DeviceListDelta::Added(_) => DEVICE_ADDED_EVENT,
+            DeviceListDelta::Updated(_) => DEVICE_UPDATED_EVENT,
+            DeviceListDelta::Removed(_) => DEVICE_REMOVED_EVENT,
+        }
+    }
+}
+
+/// Last published device list and the sequence number of the last event
+#[derive(Debug, Default)]
+pub struct DeviceListTracker {
+    seq: u64,
+    /// Serialized form of each published device, compared to detect updates
+    published: HashMap<Uuid, serde_json::Value>,
+}
+
+impl DeviceListTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn seq(&self) -> u64 {
+        self.seq
+    }
+
+    /// Record `devices` as published and return the events that take the previous list to it:
+    /// removals first, then additions and updates in list order
+    pub fn update(&mut self, devices: &[Device]) -> Vec<DeviceListDelta> {
+        let current: Vec<(Uuid, serde_json::Value)> = devices.iter()
+            .map(|d| (d.id, serde_json::to_value(d).unwrap_or_default()))
+            .collect();
+        let mut deltas = Vec::new();
+
+        let mut removed: Vec<Uuid> = self.published.keys()
+            .filter(|id| !current.iter().any(|(c, _)| c == *id))
+            .copied()
+            .collect();
+        removed.sort();
+        for id in removed {
+            self.published.remove(&id);
+            self.seq += 1;
+            deltas.push(DeviceListDelta::Removed(DeviceRemovedEvent { seq: self.seq, id }));
+        }
+
+        for (device, (id, value)) in devices.iter().zip(current) {
+            let previous = self.published.insert(id, value.clone());
+            if previous.as_ref() == Some(&value) {
+                continue;
+            }
+            self.seq += 1;
+            let event = DeviceChangedEvent { seq: self.seq, device: device.clone() };
+            deltas.push(if previous.is_some() { DeviceListDelta::Updated(event) } else { DeviceListDelta::Added(event) });
+        }
+        deltas
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::device::ConnectionState;
+
+    #[test]
+    fn emits_sequenced_deltas() {
+        let mut tracker = DeviceListTracker::new();
+        let a = Device::new("COM3".to_string());
+        let b = Device::new("COM4".to_string());
+
+        let deltas = tracker.update(&[a.clone(), b.clone()]);
+        assert_eq!(deltas.iter().map(|d| (d.event_name(), d.seq())).collect::<Vec<_>>(),
+            vec![(DEVICE_ADDED_EVENT, 1), (DEVICE_ADDED_EVENT, 2)]);
+        assert!(tracker.update(&[a.clone(), b.clone()]).is_empty(), "an unchanged list emits nothing");
+
+        let mut connected = a.clone();
+        connected.connection_state = ConnectionState::Connected;
+        let deltas = tracker.update(&[connected, b.clone()]);
+        assert!(matches!(&deltas[..], [DeviceListDelta::Updated(e)] if e.seq == 3 && e.device.id == a.id));
+
+        let deltas = tracker.update(&[b.clone()]);
+        assert!(matches!(&deltas[..], [DeviceListDelta::Removed(e)] if e.seq == 4 && e.id == a.id));
+        assert_eq!(tracker.seq(), 4);
+
+        let deltas = tracker.update(&[a.clone()]);
+        assert_eq!(deltas.iter().map(|d| (d.event_name(), d.seq())).collect::<Vec<_>>(),
+            vec![(DEVICE_REMOVED_EVENT, 5), (DEVICE_ADDED_EVENT, 6)]);
+    }
+}
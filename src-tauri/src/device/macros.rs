@@ -0,0 +1,191 @@
+//! Button macros: key sequences with delays, bound to a joystick button.
+//!
+//! Definitions are stored with the profiles (`profiles.json`). Config format v7 has no macro
+//! table and no MACRO button behavior, so macros only exist app-side; there is deliberately no
+//! device encoding until the firmware defines one.
+use serde::{Deserialize, Serialize};
+
+pub const MAX_MACRO_STEPS: usize = 32;
+pub const MAX_MACRO_DELAY_MS: u32 = 10_000;
+pub const MAX_TAP_HOLD_MS: u32 = 2_550;
+/// Joystick buttons addressable by a macro trigger
+pub const MAX_TRIGGER_BUTTON: u8 = 127;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MacroMode {
+    /// Run the sequence once per button press
+    Once,
+    /// Repeat the sequence while the button is held
+    WhileHeld,
+    /// First press starts repeating, second press stops
+    Toggle,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MacroStep {
+    KeyPress { key: String },
+    KeyRelease { key: String },
+    KeyTap { key: String, #[serde(default)] hold_ms: u32 },
+    Delay { ms: u32 },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MacroDefinition {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub mode: MacroMode,
+    /// Joystick button (logical input `joy_button_id`) that fires the macro
+    #[serde(default)]
+    pub trigger_button: Option<u8>,
+    pub steps: Vec<MacroStep>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub modified_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// HID keyboard usage ID for a key name (case-insensitive)
+pub fn key_usage(name: &str) -> Option<u8> {
+    let upper = name.trim().to_ascii_uppercase();
+    let bytes = upper.as_bytes();
+    if bytes.len() == 1 {
+        return match bytes[0] {
+            c @ b'A'..=b'Z' => Some(0x04 + (c - b'A')),
+            b'0' => Some(0x27),
+            c @ b'1'..=b'9' => Some(0x1E + (c - b'1')),
+            _ => None,
+        };
+    }
+    if let Some(n) = upper.strip_prefix('F').and_then(|n| n.parse::<u8>().ok()) {
+        return match n {
+            1..=12 => Some(0x3A + n - 1),
+            13..=24 => Some(0x68 + n - 13),
+            _ => None,
+        };
+    }
+    Some(match upper.as_str() {
+        "ENTER" => 0x28,
+        "ESC" | "ESCAPE" => 0x29,
+        "BACKSPACE" => 0x2A,
+        "TAB" => 0x2B,
+        "SPACE" => 0x2C,
+        "RIGHT" => 0x4F,
+        "LEFT" => 0x50,
+        "DOWN" => 0x51,
+        "UP" => 0x52,
+        "LCTRL" => 0xE0,
+        "LSHIFT" => 0xE1,
+        "LALT" => 0xE2,
+        "LGUI" => 0xE3,
+        "RCTRL" => 0xE4,
+        "RSHIFT" => 0xE5,
+        "RALT" => 0xE6,
+        "RGUI" => 0xE7,
+        _ => return None,
+    })
+}
+
+impl MacroDefinition {
+    /// Problems that prevent saving this macro; `others` are the remaining stored macros
+    pub fn validate(&self, others: &[MacroDefinition]) -> Vec<String> {
+        let mut issues = Vec::new();
+        if self.name.trim().is_empty() {
+            issues.push("Macro name must not be empty".to_string());
+        }
+        if self.steps.is_empty() {
+            issues.push("Macro needs at least one step".to_string());
+        }
+        if self.steps.len() > MAX_MACRO_STEPS {
+            issues.push(format!("Macro has {} steps (maximum {})", self.steps.len(), MAX_MACRO_STEPS));
+        }
+        if let Some(button) = self.trigger_button {
+            if button > MAX_TRIGGER_BUTTON {
+                issues.push(format!("Trigger button {} is out of range (0-{})", button, MAX_TRIGGER_BUTTON));
+            }
+            if let Some(other) = others.iter().find(|m| m.id != self.id && m.trigger_button == Some(button)) {
+                issues.push(format!("Button {} already triggers macro '{}'", button, other.name));
+            }
+        }
+
+        let mut held: Vec<&str> = Vec::new();
+        for (i, step) in self.steps.iter().enumerate() {
+            let n = i + 1;
+            match step {
+                MacroStep::KeyPress { key } | MacroStep::KeyRelease { key } | MacroStep::KeyTap { key, .. } if key_usage(key).is_none() => {
+                    issues.push(format!("Step {}: unknown key '{}'", n, key));
+                }
+                MacroStep::KeyPress { key } => {
+                    if held.iter().any(|k| k.eq_ignore_ascii_case(key)) {
+                        issues.push(format!("Step {}: '{}' is already pressed", n, key));
+                    } else {
+                        held.push(key);
+                    }
+                }
+                MacroStep::KeyRelease { key } => match held.iter().position(|k| k.eq_ignore_ascii_case(key)) {
+                    Some(pos) => { held.remove(pos); }
+                    None => issues.push(format!("Step {}: '{}' is released without being pressed", n, key)),
+                },
+                MacroStep::KeyTap { hold_ms, .. } => {
+                    if *hold_ms > MAX_TAP_HOLD_MS {
+                        issues.push(format!("Step {}: tap hold {} ms exceeds {} ms", n, hold_ms, MAX_TAP_HOLD_MS));
+                    }
+                }
+                MacroStep::Delay { ms } => {
+                    if *ms == 0 || *ms > MAX_MACRO_DELAY_MS {
+                        issues.push(format!("Step {}: delay must be 1-{} ms", n, MAX_MACRO_DELAY_MS));
+                    }
+                }
+            }
+        }
+        if !held.is_empty() {
+            issues.push(format!("Keys still pressed at the end of the macro: {}", held.join(", ")));
+        }
+        issues
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn macro_with(steps: Vec<MacroStep>, trigger: Option<u8>) -> MacroDefinition {
+        let now = chrono::Utc::now();
+        MacroDefinition {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: "Test".into(),
+            description: String::new(),
+            mode: MacroMode::Once,
+            trigger_button: trigger,
+            steps,
+            created_at: now,
+            modified_at: now,
+        }
+    }
+
+    #[test]
+    fn validates_keys_balance_and_trigger_conflicts() {
+        let good = macro_with(vec![
+            MacroStep::KeyPress { key: "lctrl".into() },
+            MacroStep::KeyTap { key: "C".into(), hold_ms: 30 },
+            MacroStep::KeyRelease { key: "LCTRL".into() },
+        ], Some(3));
+        assert!(good.validate(&[]).is_empty());
+
+        let bad = macro_with(vec![
+            MacroStep::KeyPress { key: "LSHIFT".into() },
+            MacroStep::KeyTap { key: "NOPE".into(), hold_ms: 0 },
+            MacroStep::Delay { ms: 0 },
+        ], Some(3));
+        let issues = bad.validate(std::slice::from_ref(&good));
+        assert_eq!(issues.len(), 4, "{:?}", issues);
+    }
+
+    #[test]
+    fn resolves_key_usages() {
+        assert_eq!(key_usage("a"), Some(0x04));
+        assert_eq!(key_usage("F13"), Some(0x68));
+        assert_eq!(key_usage("NOPE"), None);
+    }
+}
@@ -4,15 +4,40 @@ use std::sync::atomic::{AtomicBool, Ordering};
 use tokio::sync::{Mutex, RwLock};
 use uuid::Uuid;
 use semver::Version;
-use tauri::{AppHandle, Emitter};
+use tauri::{AppHandle, Emitter, Manager};
 
-use crate::serial::{SerialInterface, ConfigProtocol, StorageInfo};
+use crate::serial::{SerialInterface, ConfigProtocol, ConnectionSettings, StorageInfo};
+use crate::serial::capabilities::{Capability, CapabilitySource, FirmwareCapabilities};
 use crate::serial::unified::reader::UnifiedSerialHandle;
+use crate::serial::unified::CommandPriority;
 use crate::update::{UpdateService, VersionCheckResult};
+use crate::update::scheduler::{self, FirmwareUpdateAvailable, UpdateSchedule};
 use crate::config::BinaryConfig;
+use crate::config::validate::{self, ConfigValidationReport, ValidationSource};
 use crate::hid::{HidReader, ButtonStates};
-use super::{Device, ConnectionState, ProfileManager, DeviceError, Result, FirmwareUpdateSettings};
-use super::port_monitor::{create_port_monitor, PortMonitor, PortEvent};
+use super::{Device, ConnectionState, ProfileManager, ProfileStore, DeviceError, Result, FirmwareUpdateSettings, AppSettings};
+use super::identify_cache::IdentifyCache;
+use super::lifecycle::{Lifecycle, SHUTDOWN_DEADLINE};
+use super::port_filter::{self, PortFilter};
+use super::port_monitor::{create_port_monitor, PollingPortMonitor, PortMonitor, PortEvent};
+use super::aliases::{DeviceAliases, ALIASES_FILE_NAME};
+use super::known_devices::{KnownDevice, KnownDeviceRegistry, KNOWN_DEVICES_FILE_NAME};
+use super::list_events::{DeviceListDelta, DeviceListSnapshot, DeviceListTracker};
+use super::quarantine::{PortQuarantine, QuarantinedPort, QUARANTINE_FILE_NAME};
+use super::settings::{SettingsStore, SETTINGS_FILE_NAME};
+use super::config_backups::{BackupReason, ConfigBackupInfo, ConfigBackupStore, DEVICE_BACKUP_FILE};
+use super::clock_sync::{self, ClockEstimator, ClockModel, ClockSample, CLOCK_RESYNC_INTERVAL, CLOCK_SYNC_SAMPLES};
+use super::heartbeat::{HeartbeatState, ProbeOutcome, heartbeat_spec, HEARTBEAT_INTERVAL, HEARTBEAT_TIMEOUT_ERROR};
+use super::setup_check::{self, DeviceSetupRequired, SetupIssue};
+use super::config_preview::{ConfigPreview, CONFIG_PREVIEW_EVENT};
+use crate::config::shift_layer::{ShiftLayer, ShiftLayerMode, ShiftLayerState};
+use super::self_test::{self, CheckStatus, SelfTestCheck, SelfTestReport};
+use super::dev_mode::{ConsoleResponse, DevModeStore, DevModeSettings, DevFlashReport, LastFlashedFirmware, SmokeTestReport, SmokeTestStep, SmokeTestStepResult, DEV_MODE_FILE_NAME};
+
+/// Serial command restarting the firmware
+const REBOOT_COMMAND: &str = "REBOOT";
+/// How long the device gets to drop off its port after REBOOT
+const REBOOT_CONFIRM_MS: u64 = 2000;
 
 /// Central device management system
 /// Handles device discovery, connection management, and configuration
@@ -21,6 +46,8 @@ pub struct DeviceManager {
     devices: Arc<RwLock<HashMap<Uuid, Device>>>,
     connected_device: Arc<Mutex<Option<(Uuid, ConfigProtocol)>>>,
     profile_manager: Arc<Mutex<ProfileManager>>,
+    /// Disk persistence for profiles (set once the app data directory is known)
+    profile_store: Arc<Mutex<Option<ProfileStore>>>,
     hid_reader: Arc<Mutex<HidReader>>,
     app_handle: Arc<Mutex<Option<AppHandle>>>,
     raw_monitoring_active: Arc<AtomicBool>,
@@ -32,6 +59,34 @@ pub struct DeviceManager {
     port_monitor: Arc<Mutex<Option<Box<dyn PortMonitor>>>>,
     /// Handle for port monitor task
     port_monitor_handle: Arc<Mutex<Option<tokio::task::JoinHandle<()>>>>,
+    /// Probed firmware command support for the current connection (command name -> supported)
+    protocol_support: Arc<Mutex<HashMap<String, bool>>>,
+    /// Ports excluded from discovery after repeated IDENTIFY failures (persisted once app handle is set)
+    port_quarantine: Arc<Mutex<PortQuarantine>>,
+    /// Developer mode settings and last flashed UF2 per device (persisted once app handle is set)
+    dev_mode: Arc<Mutex<DevModeStore>>,
+    /// Application settings (persisted in the app config directory once app handle is set)
+    settings: Arc<Mutex<SettingsStore>>,
+    /// Key (`port_name:serial_number`) of a device that dropped while connected and should be reconnected
+    reconnect_target: Arc<Mutex<Option<String>>>,
+    /// Keep-alive task watching the connected serial link
+    heartbeat_handle: Arc<Mutex<Option<tokio::task::JoinHandle<()>>>>,
+    /// Archive of every config read from or written to the device (app data directory)
+    config_backups: Arc<Mutex<ConfigBackupStore>>,
+    /// User-chosen device names by serial number (persisted once app handle is set)
+    aliases: Arc<Mutex<DeviceAliases>>,
+    /// Every device seen so far, listed while unplugged (persisted once app handle is set)
+    known_devices: Arc<Mutex<KnownDeviceRegistry>>,
+    /// Recent IDENTIFY answers by port, reused by discovery and connect
+    identify_cache: Arc<Mutex<IdentifyCache>>,
+    /// Config running from device RAM via TEST_CONFIG, not yet stored
+    config_preview: Arc<Mutex<Option<ConfigPreview>>>,
+    /// Device list last published through delta events, with its sequence number
+    device_list_events: Arc<Mutex<DeviceListTracker>>,
+    /// Per-device lock held for the whole read-modify-write of `edit_device_config`
+    config_edit_locks: Arc<Mutex<HashMap<Uuid, Arc<Mutex<()>>>>>,
+    /// Shutdown state and background tasks stopped on app exit
+    lifecycle: Arc<Lifecycle>,
 }
 
 impl DeviceManager {
@@ -50,12 +105,14 @@ impl DeviceManager {
     // 2. Connection events are standardized: { id, state, error? } with state in
     //    [Connected, Connecting, Disconnected, Error]. All emissions flow through
     //    update_device_connection_state to avoid duplicate or out-of-order UI updates.
-    // 3. Device list snapshots are emitted after each connection state change and discovery to
-    //    keep frontend authoritative without needing intervals.
+    // 3. Device list changes are emitted as sequenced device_added/updated/removed deltas after
+    //    each connection state change and discovery; the frontend starts from
+    //    get_device_list_snapshot and reloads it when it sees a gap in the sequence.
         Self {
             devices: Arc::new(RwLock::new(HashMap::new())),
             connected_device: Arc::new(Mutex::new(None)),
             profile_manager: Arc::new(Mutex::new(ProfileManager::new())),
+            profile_store: Arc::new(Mutex::new(None)),
             hid_reader: Arc::new(Mutex::new(hid_reader)),
             app_handle: Arc::new(Mutex::new(None)),
             raw_monitoring_active: Arc::new(AtomicBool::new(false)),
@@ -64,6 +121,20 @@ impl DeviceManager {
             initial_discovery_started: Arc::new(AtomicBool::new(false)),
             port_monitor: Arc::new(Mutex::new(None)),
             port_monitor_handle: Arc::new(Mutex::new(None)),
+            protocol_support: Arc::new(Mutex::new(HashMap::new())),
+            port_quarantine: Arc::new(Mutex::new(PortQuarantine::new())),
+            dev_mode: Arc::new(Mutex::new(DevModeStore::new())),
+            settings: Arc::new(Mutex::new(SettingsStore::new())),
+            reconnect_target: Arc::new(Mutex::new(None)),
+            heartbeat_handle: Arc::new(Mutex::new(None)),
+            config_backups: Arc::new(Mutex::new(ConfigBackupStore::new())),
+            aliases: Arc::new(Mutex::new(DeviceAliases::new())),
+            known_devices: Arc::new(Mutex::new(KnownDeviceRegistry::new())),
+            identify_cache: Arc::new(Mutex::new(IdentifyCache::default())),
+            config_preview: Arc::new(Mutex::new(None)),
+            device_list_events: Arc::new(Mutex::new(DeviceListTracker::new())),
+            config_edit_locks: Arc::new(Mutex::new(HashMap::new())),
+            lifecycle: Arc::new(Lifecycle::new()),
         }
     }
 
@@ -73,6 +144,9 @@ impl DeviceManager {
         use std::time::Duration;
         // Check if display mode allows HID
         if !matches!(crate::raw_state::get_display_mode(), crate::raw_state::DisplayMode::HID | crate::raw_state::DisplayMode::Both) { return Ok(None); }
+        if let Some(caps) = self.get_firmware_capabilities().await {
+            if !caps.hid_mapping { return Ok(None); }
+        }
         // Quick check if mapping already present
         {
             let hid_reader = self.hid_reader.lock().await;
@@ -87,8 +161,8 @@ impl DeviceManager {
         if !mapping_resp.starts_with("HID_MAPPING_INFO:") { return Ok(None); }
         // Parse key=value pairs after prefix
         let data_part = mapping_resp.splitn(2, ':').nth(1).unwrap_or("");
-        let mut proto_ver: u8 = 0; let mut report_id: u8 = 0; let mut btn_cnt: u16 = 0; let mut axis_cnt: u16 = 0; let mut btn_off: u8 = 0; let mut bit_order: u8 = 0; let mut crc: u16 = 0; let mut fc_off: Option<u8> = None;
-        for kv in data_part.split(',') { if let Some((k,v)) = kv.split_once('=') { match k { "ver"=> proto_ver = v.parse().unwrap_or(0), "rid"=> report_id = v.parse().unwrap_or(0), "btn"=> btn_cnt = v.parse().unwrap_or(0), "axis"=> axis_cnt = v.parse().unwrap_or(0), "btn_offset"=> btn_off = v.parse().unwrap_or(0), "bit_order"=> bit_order = v.parse().unwrap_or(0), "crc"=> { crc = u16::from_str_radix(v.trim_start_matches("0x"),16).unwrap_or(0); }, "fc_offset"=> fc_off = Some(v.parse().unwrap_or(0)), _=>{} } } }
+        let mut proto_ver: u8 = 0; let mut report_id: u8 = 0; let mut btn_cnt: u16 = 0; let mut axis_cnt: u16 = 0; let mut btn_off: u8 = 0; let mut bit_order: u8 = 0; let mut crc: u16 = 0; let mut fc_off: Option<u8> = None; let mut hat_cnt: u8 = 0;
+        for kv in data_part.split(',') { if let Some((k,v)) = kv.split_once('=') { match k { "ver"=> proto_ver = v.parse().unwrap_or(0), "rid"=> report_id = v.parse().unwrap_or(0), "btn"=> btn_cnt = v.parse().unwrap_or(0), "axis"=> axis_cnt = v.parse().unwrap_or(0), "btn_offset"=> btn_off = v.parse().unwrap_or(0), "bit_order"=> bit_order = v.parse().unwrap_or(0), "crc"=> { crc = u16::from_str_radix(v.trim_start_matches("0x"),16).unwrap_or(0); }, "fc_offset"=> fc_off = Some(v.parse().unwrap_or(0)), "hats"=> hat_cnt = v.parse().unwrap_or(0), _=>{} } } }
         if btn_cnt == 0 { return Ok(None); }
         // Always attempt to fetch explicit mapping table; fall back to identity if SEQUENTIAL or unavailable
         let mut mapping: Vec<u8> = (0..btn_cnt.min(128) as u8).collect(); // identity by default
@@ -127,6 +201,7 @@ impl DeviceManager {
                 button_bit_order: bit_order,
                 mapping_crc: crc,
                 frame_counter_offset: fc_off,
+                hat_count: hat_cnt,
             };
             hid_reader.apply_external_mapping(ext_info, mapping, false)
         };
@@ -139,7 +214,13 @@ impl DeviceManager {
         
         if let Err(e) = monitor.start().await {
             log::error!("Failed to start port monitor: {}", e);
-            return;
+            // Keep discovery alive without native notifications
+            log::warn!("Falling back to polling for port changes");
+            monitor = Box::new(PollingPortMonitor::new());
+            if let Err(e) = monitor.start().await {
+                log::error!("Failed to start polling port monitor: {}", e);
+                return;
+            }
         }
         
         if let Some(mut rx) = monitor.get_receiver() {
@@ -150,11 +231,27 @@ impl DeviceManager {
                 while let Some(event) = rx.recv().await {
                     log::info!("Port event received: {:?}", event);
                     
+                    // A different device may be behind the port now
+                    match &event {
+                        PortEvent::PortAdded(port_name) | PortEvent::PortRemoved(port_name) => {
+                            mgr.identify_cache.lock().await.invalidate(port_name);
+                        }
+                    }
+                    if let PortEvent::PortAdded(port_name) = &event {
+                        if !mgr.should_probe_added_port(port_name).await {
+                            log::debug!("Ignoring added port {} (not a JoyCore USB id or ignored)", port_name);
+                            continue;
+                        }
+                    }
                     match event {
                         PortEvent::PortAdded(_) | PortEvent::PortRemoved(_) => {
                             // Trigger device discovery on any port change
-                            if let Err(e) = mgr.discover_devices().await {
-                                log::error!("Failed to discover devices after port event: {}", e);
+                            match mgr.discover_devices().await {
+                                Ok(devices) => {
+                                    mgr.maybe_auto_reconnect(&devices).await;
+                                    mgr.maybe_auto_connect().await;
+                                }
+                                Err(e) => log::error!("Failed to discover devices after port event: {}", e),
                             }
                         }
                     }
@@ -169,6 +266,34 @@ impl DeviceManager {
         *self.port_monitor.lock().await = Some(monitor);
     }
     
+    /// IDENTIFY `port_name`, answering from `cache` while its answer is fresh. Blocking: the cache
+    /// is only locked around lookups, never while the port is probed.
+    fn identify_cached(cache: &Mutex<IdentifyCache>, port_name: &str, settings: &ConnectionSettings) -> crate::serial::Result<Option<crate::serial::SerialDeviceInfo>> {
+        if let Some(info) = cache.blocking_lock().get(port_name, std::time::Instant::now()) {
+            log::debug!("Using cached IDENTIFY answer for {}", port_name);
+            return Ok(info);
+        }
+        let result = SerialInterface::identify_device_with(port_name, settings);
+        if let Ok(info) = &result {
+            cache.blocking_lock().insert(port_name, info.clone(), std::time::Instant::now());
+        }
+        result
+    }
+
+    /// Whether a port the monitor reported as added may be probed, judged by its USB metadata
+    async fn should_probe_added_port(&self, port_name: &str) -> bool {
+        let filter = PortFilter::new(&self.get_app_settings().await.discovery);
+        if filter.is_ignored(port_name) {
+            return false;
+        }
+        let key = port_filter::port_key(port_name);
+        match serialport::available_ports() {
+            // Unknown to the enumeration (yet): let discovery decide
+            Ok(ports) => ports.iter().find(|p| port_filter::port_key(&p.port_name) == key).map(|p| filter.should_probe(p)).unwrap_or(true),
+            Err(_) => true,
+        }
+    }
+
     /// Stop the port monitor
     async fn stop_port_monitor(&self) {
         // Stop the event loop
@@ -232,10 +357,39 @@ impl DeviceManager {
     pub async fn set_app_handle(&self, handle: AppHandle) {
         let hid_reader = self.hid_reader.lock().await;
         hid_reader.set_app_handle(handle.clone());
+        self.spawn_hid_supervisor(hid_reader.connection_events());
+        drop(hid_reader);
         
         let mut app_handle_guard = self.app_handle.lock().await;
         *app_handle_guard = Some(handle.clone());
         drop(app_handle_guard); // Release the lock before calling start_raw_state_monitoring
+
+        // Load persisted state from the app data directory
+        match handle.path().app_data_dir() {
+            Ok(dir) => {
+                let quarantine = PortQuarantine::load(dir.join(QUARANTINE_FILE_NAME));
+                let count = quarantine.list().len();
+                *self.port_quarantine.lock().await = quarantine;
+                if count > 0 { log::info!("Loaded {} quarantined port(s)", count); }
+                *self.dev_mode.lock().await = DevModeStore::load(dir.join(DEV_MODE_FILE_NAME));
+                let store = ProfileStore::in_dir(&dir);
+                *self.profile_manager.lock().await = store.load();
+                *self.profile_store.lock().await = Some(store);
+                *self.config_backups.lock().await = ConfigBackupStore::in_dir(&dir);
+                *self.aliases.lock().await = DeviceAliases::load(dir.join(ALIASES_FILE_NAME));
+                *self.known_devices.lock().await = KnownDeviceRegistry::load(dir.join(KNOWN_DEVICES_FILE_NAME));
+            }
+            Err(e) => log::warn!("No app data directory, persisted state will not be saved: {}", e),
+        }
+        match handle.path().app_config_dir() {
+            Ok(dir) => {
+                let store = SettingsStore::load(dir.join(SETTINGS_FILE_NAME));
+                Self::apply_settings(store.settings());
+                crate::telemetry::apply_settings(&store.settings().telemetry).await;
+                *self.settings.lock().await = store;
+            }
+            Err(e) => log::warn!("No app config directory, settings will not be saved: {}", e),
+        }
         
     // If we're in Raw mode or Both and have a connected device, start raw monitoring now
     if matches!(crate::raw_state::get_display_mode(), crate::raw_state::DisplayMode::Raw | crate::raw_state::DisplayMode::Both) {
@@ -250,19 +404,59 @@ impl DeviceManager {
         // Start port monitor for event-driven device discovery
         if !self.initial_discovery_started.swap(true, Ordering::SeqCst) {
             self.start_port_monitor().await;
+            self.spawn_update_scheduler();
+            if self.get_app_settings().await.auto_connect {
+                let mgr = self.clone();
+                tokio::spawn(async move {
+                    match mgr.discover_devices().await {
+                        Ok(_) => mgr.maybe_auto_connect().await,
+                        Err(e) => log::warn!("Startup discovery for auto-connect failed: {}", e),
+                    }
+                });
+            }
         }
     }
 
     /// Discover available JoyCore devices
     pub async fn discover_devices(&self) -> Result<Vec<Device>> {
-        let serial_devices = SerialInterface::discover_devices().map_err(DeviceError::SerialError)?;
+        let quarantined: std::collections::HashSet<String> = self.port_quarantine.lock().await
+            .list().into_iter().map(|p| p.port_name).collect();
+        let filter = PortFilter::new(&self.get_app_settings().await.discovery);
+        // Ports this app has open (or is opening) are never probed; they stay present while enumerated
+        let busy_ports: std::collections::HashSet<String> = self.devices.read().await.values()
+            .filter(|d| matches!(d.connection_state, ConnectionState::Connected | ConnectionState::Connecting))
+            .map(|d| d.port_name.clone())
+            .collect();
+        let cache = self.identify_cache.clone();
+        // Known devices are probed with their connection settings on their last port
+        let port_settings: HashMap<String, ConnectionSettings> = self.known_devices.lock().await.probe_settings();
+        let report = SerialInterface::discover_devices_concurrent(
+            |port| busy_ports.contains(&port.port_name) || quarantined.contains(&port.port_name) || !filter.should_probe(port),
+            move |port_name| {
+                let settings = port_settings.get(&port_filter::port_key(port_name)).copied().unwrap_or_default();
+                Self::identify_cached(&cache, port_name, &settings)
+            },
+            std::time::Duration::from_millis(crate::serial::interface::DISCOVERY_DEADLINE_MS),
+        ).await.map_err(DeviceError::SerialError)?;
+        {
+            let mut quarantine = self.port_quarantine.lock().await;
+            for port in &report.healthy_ports { quarantine.record_success(port); }
+            for (port, error) in &report.failures { quarantine.record_failure(port, error); }
+        }
+        let serial_devices = report.devices;
+        let skipped_ports = report.skipped_ports;
+        // A device whose probe ran out of time this pass is still plugged in, just slow to answer
+        let timed_out_ports = report.timed_out_ports;
+        let connected_id = self.get_connected_device_id().await;
+        let reconnect_key = self.reconnect_target.lock().await.clone();
+        let mut lost = None;
         let mut devices_guard = self.devices.write().await;
         let mut key_map = self.key_to_id.lock().await;
         let mut seen_keys = std::collections::HashSet::new();
         let mut result = Vec::new();
 
         for info in serial_devices {
-            let key = format!("{}:{}", info.port_name, info.serial_number.clone().unwrap_or_default());
+            let key = Self::device_key(&info.port_name, info.serial_number.as_deref());
             seen_keys.insert(key.clone());
             if let Some(id) = key_map.get(&key).cloned() {
                 if let Some(existing) = devices_guard.get_mut(&id) {
@@ -282,43 +476,157 @@ impl DeviceManager {
                     result.push(existing.clone());
                 }
             } else {
-                let device = Device::from_serial_info(&info);
+                let mut device = Device::from_serial_info(&info);
+                // A known device keeps its id unless that id is already taken by a present device
+                if let Some(id) = self.known_devices.lock().await.id_of(info.serial_number.as_deref()) {
+                    if !devices_guard.contains_key(&id) { device.id = id; }
+                }
                 let id = device.id;
                 key_map.insert(key, id);
                 devices_guard.insert(id, device.clone());
                 result.push(device);
             }
         }
-        // Remove stale keys (disconnected devices) that vanished
+        for device in devices_guard.values() {
+            let unprobed = (busy_ports.contains(&device.port_name) && skipped_ports.contains(&device.port_name))
+                || timed_out_ports.contains(&device.port_name);
+            if unprobed {
+                seen_keys.insert(Self::device_key(&device.port_name, device.serial_number.as_deref()));
+                result.push(device.clone());
+            }
+        }
+        // Remove stale keys (disconnected devices) that vanished. The connected device and one
+        // awaiting reconnect keep their entry (and id) so a replug resumes the same device.
         let to_remove: Vec<Uuid> = key_map.iter()
-            .filter_map(|(k, id)| if !seen_keys.contains(k) { Some(*id) } else { None })
+            .filter(|(k, _)| !seen_keys.contains(*k))
+            .filter_map(|(k, id)| {
+                if Some(*id) == connected_id {
+                    lost = Some((*id, k.clone()));
+                    None
+                } else if reconnect_key.as_ref() == Some(k) {
+                    None
+                } else {
+                    Some(*id)
+                }
+            })
             .collect();
         for id in to_remove {
             key_map.retain(|_, v| *v != id);
             if let Some(mut d) = devices_guard.remove(&id) { d.update_connection_state(ConnectionState::Disconnected); }
         }
+        drop(key_map);
         drop(devices_guard);
-        self.emit_device_list().await;
-        Ok(result)
+        {
+            let mut known = self.known_devices.lock().await;
+            for device in &result { known.record_seen(device); }
+        }
+        match lost {
+            Some((id, key)) => self.handle_connection_lost(id, key, "Device disconnected unexpectedly").await,
+            None => self.emit_device_list().await,
+        }
+        let aliases = self.aliases.lock().await;
+        Ok(result.iter().map(|d| Self::with_alias(d, &aliases)).collect())
     }
 
     /// Clean up devices that are no longer present (separate from discovery)
     // legacy cleanup_disconnected_devices removed: event-driven discovery now authoritative
 
-    /// Get all known devices
+    /// Get all present devices, followed by known devices that are not plugged in (`present: false`)
     pub async fn get_devices(&self) -> Vec<Device> {
         let devices_guard = self.devices.read().await;
-        devices_guard.values().cloned().collect()
+        let aliases = self.aliases.lock().await;
+        let present: std::collections::HashSet<&str> = devices_guard.values()
+            .filter_map(|d| d.serial_number.as_deref()).collect();
+        let offline = self.known_devices.lock().await.offline_devices(&present);
+        devices_guard.values().chain(offline.iter()).map(|d| Self::with_alias(d, &aliases)).collect()
     }
 
     /// Get a specific device by ID
     pub async fn get_device(&self, device_id: &Uuid) -> Option<Device> {
         let devices_guard = self.devices.read().await;
-        devices_guard.get(device_id).cloned()
+        let aliases = self.aliases.lock().await;
+        devices_guard.get(device_id).map(|d| Self::with_alias(d, &aliases))
+    }
+
+    fn with_alias(device: &Device, aliases: &DeviceAliases) -> Device {
+        let mut device = device.clone();
+        device.alias = device.serial_number.as_deref().and_then(|s| aliases.get(s)).map(str::to_string);
+        device
+    }
+
+    /// Every device seen so far, most recently seen first
+    pub async fn get_known_devices(&self) -> Vec<KnownDevice> {
+        self.known_devices.lock().await.list()
+    }
+
+    /// Remove a device from the known-device registry; returns false when it was not known
+    pub async fn forget_known_device(&self, serial_number: &str) -> bool {
+        let removed = self.known_devices.lock().await.forget(serial_number);
+        if removed { self.emit_device_list().await; }
+        removed
+    }
+
+    /// Serial connection overrides of a known device
+    pub async fn get_device_connection_settings(&self, serial_number: &str) -> Result<ConnectionSettings> {
+        self.known_devices.lock().await.get(serial_number).map(|d| d.connection).ok_or(DeviceError::NotFound)
+    }
+
+    /// Store connection overrides for a known device. They apply to the next discovery probe and
+    /// connection; the open connection keeps its settings.
+    pub async fn set_device_connection_settings(&self, serial_number: &str, settings: ConnectionSettings) -> Result<ConnectionSettings> {
+        settings.validate().map_err(DeviceError::InvalidConfiguration)?;
+        let port = {
+            let mut known = self.known_devices.lock().await;
+            if !known.set_connection_settings(serial_number, settings) {
+                return Err(DeviceError::NotFound);
+            }
+            known.get(serial_number).map(|d| d.last_port.clone()).unwrap_or_default()
+        };
+        // The cached answer was probed with the old settings
+        self.identify_cache.lock().await.invalidate(&port);
+        log::info!("Connection settings for {}: {:?}", serial_number, settings);
+        Ok(settings)
+    }
+
+    /// Connection overrides stored for a port; defaults when it has none
+    pub async fn get_port_connection_settings(&self, port_name: &str) -> ConnectionSettings {
+        self.known_devices.lock().await.port_connection_settings(port_name).unwrap_or_default()
+    }
+
+    /// Store connection overrides for a port (compared by `port_filter::port_key`), for a device
+    /// not known yet, e.g. one that only answers discovery at another baud rate. Default settings
+    /// remove them.
+    pub async fn set_port_connection_settings(&self, port_name: &str, settings: ConnectionSettings) -> Result<ConnectionSettings> {
+        settings.validate().map_err(DeviceError::InvalidConfiguration)?;
+        if port_filter::port_key(port_name).is_empty() {
+            return Err(DeviceError::InvalidConfiguration("Port name is empty".to_string()));
+        }
+        self.known_devices.lock().await.set_port_connection_settings(port_name, settings);
+        self.identify_cache.lock().await.invalidate(port_name);
+        log::info!("Connection settings for port {}: {:?}", port_name, settings);
+        Ok(settings)
+    }
+
+    pub async fn get_device_alias(&self, serial_number: &str) -> Option<String> {
+        self.aliases.lock().await.get(serial_number).map(str::to_string)
+    }
+
+    /// Name (or with `None`, unname) the device with `serial_number` and refresh the device list
+    pub async fn set_device_alias(&self, serial_number: &str, alias: Option<&str>) -> Result<Option<String>> {
+        let alias = self.aliases.lock().await.set(serial_number, alias)
+            .map_err(DeviceError::InvalidConfiguration)?;
+        self.emit_device_list().await;
+        Ok(alias)
     }
 
     /// Connect to a device
     pub async fn connect_device(&self, device_id: &Uuid) -> Result<()> {
+        // Discovery tasks still running at exit must not reopen the port
+        if self.lifecycle.is_shutting_down() {
+            return Err(DeviceError::ProtocolError("Application is shutting down".to_string()));
+        }
+        // An explicit connect supersedes any pending reconnect
+        self.reconnect_target.lock().await.take();
         // Check if another device is already connected
         {
             let connected_guard = self.connected_device.lock().await;
@@ -337,12 +645,14 @@ impl DeviceManager {
         // Update device state to connecting
         self.update_device_connection_state(device_id, ConnectionState::Connecting).await;
 
-        // Get the device info from discovery for proper connection
-        let serial_devices = SerialInterface::discover_devices()
-            .map_err(DeviceError::SerialError)?;
-        let device_info = serial_devices.iter()
-            .find(|info| info.port_name == device.port_name)
-            .cloned();
+        let settings = self.known_devices.lock().await.connection_settings(device.serial_number.as_deref(), &device.port_name);
+        // Identify only the target port (a fresh discovery answer is reused); other ports are left alone
+        let device_info = {
+            let cache = self.identify_cache.clone();
+            let port_name = device.port_name.clone();
+            tokio::task::spawn_blocking(move || Self::identify_cached(&cache, &port_name, &settings))
+                .await.ok().and_then(|r| r.ok()).flatten()
+        };
         
         // Attempt connection
         let mut serial_interface = SerialInterface::new();
@@ -350,11 +660,11 @@ impl DeviceManager {
         let connection_result = match device_info {
             Some(info) => {
                 log::info!("Using discovered device info with firmware version: {:?}", info.firmware_version);
-                serial_interface.connect_with_info(info)
+                serial_interface.connect_with_info(info, &settings)
             }
             None => {
                 log::warn!("No device info found for {}, using basic connection", device.port_name);
-                serial_interface.connect(&device.port_name)
+                serial_interface.connect(&device.port_name, &settings)
             }
         };
         
@@ -365,13 +675,21 @@ impl DeviceManager {
                 // Wrap interface and build unified reader/handle
                 let iface_arc = std::sync::Arc::new(tokio::sync::Mutex::new(serial_interface));
                 let builder = crate::serial::unified::UnifiedSerialBuilder { interface: iface_arc.clone(), event_capacity: 256, command_capacity: 64 };
-                let handle = builder.build();
+                let handle = builder.build().with_timeout_factor(settings.command_timeout_factor());
                 let mut protocol = ConfigProtocol::new(handle.clone(), iface_arc.clone());
+                if let Some(app) = self.app_handle.lock().await.clone() {
+                    protocol.set_progress_sink(Some(std::sync::Arc::new(move |progress: crate::serial::file_transfer::ConfigTransferProgress| {
+                        let _ = app.emit(crate::serial::file_transfer::CONFIG_TRANSFER_PROGRESS_EVENT, &progress);
+                    })));
+                }
                 
                 // Initialize protocol
                 match protocol.init().await {
                     Ok(()) => {
                         log::info!("Protocol initialization successful, getting device status");
+                        if let Some(capabilities) = protocol.capabilities().cloned() {
+                            self.apply_capabilities(device_id, capabilities).await;
+                        }
                         // Get device status
                         match protocol.get_device_status().await {
                             Ok(status) => {
@@ -385,6 +703,7 @@ impl DeviceManager {
                                     *connected_guard = Some((*device_id, protocol));
                                 }
                                 { let mut map = self.unified_handles.lock().await; map.insert(*device_id, handle.clone()); }
+                                crate::support::crash::record_serial_metrics(Some(handle.metrics_receiver()));
                                 // Now emit connected state
                                 log::debug!("Emitting Connected state after protocol stored");
                                 self.update_device_connection_state(device_id, ConnectionState::Connected).await;
@@ -410,6 +729,12 @@ impl DeviceManager {
                                         log::info!("Raw monitoring mode active - will start when app handle is available");
                                     }
                                 }
+                                let clock = Self::sync_device_clock(&handle).await;
+                                let heartbeat = self.spawn_heartbeat(*device_id, handle.clone(), clock);
+                                if let Some(previous) = self.heartbeat_handle.lock().await.replace(heartbeat) {
+                                    previous.abort();
+                                }
+                                self.spawn_setup_check(*device_id, handle.clone());
                                 log::info!("Successfully connected to device: {}", device.port_name);
                                 Ok(())
                             }
@@ -440,16 +765,27 @@ impl DeviceManager {
 
     /// Disconnect from the currently connected device
     pub async fn disconnect_device(&self) -> Result<()> {
+        // A manual disconnect cancels any pending reconnect
+        self.reconnect_target.lock().await.take();
+        let device_id = self.teardown_connection().await.ok_or(DeviceError::NotConnected)?;
+
+        // Emit disconnected state
+        self.update_device_connection_state(&device_id, ConnectionState::Disconnected).await;
+        log::info!("Disconnected from device {}", device_id);
+        Ok(())
+    }
+
+    /// Stop monitoring and close serial + HID for the connected device without emitting a state change
+    async fn teardown_connection(&self) -> Option<Uuid> {
+        if let Some(heartbeat) = self.heartbeat_handle.lock().await.take() {
+            heartbeat.abort();
+        }
+        clock_sync::publish(None);
         // First capture whether a device is connected (without taking ownership yet)
-        let device_id_opt = {
+        let device_id = {
             let connected_guard = self.connected_device.lock().await;
             connected_guard.as_ref().map(|(id, _)| *id)
-        };
-
-        let device_id = match device_id_opt {
-            Some(id) => id,
-            None => return Err(DeviceError::NotConnected),
-        };
+        }?;
 
         // Stop any active monitoring BEFORE tearing down protocol to avoid deadlocks on connected_device
         match crate::raw_state::get_display_mode() {
@@ -464,6 +800,19 @@ impl DeviceManager {
             },
         }
 
+        // A previewed config must not outlive the session that applied it
+        if self.config_preview.lock().await.take().is_some() {
+            let mut connected_guard = self.connected_device.lock().await;
+            if let Some((_, protocol)) = connected_guard.as_mut() {
+                match protocol.revert_test_config().await {
+                    Ok(()) => log::info!("Reverted config preview on disconnect of device {}", device_id),
+                    Err(e) => log::warn!("Could not revert config preview on disconnect (lost on the next device reset): {}", e),
+                }
+            }
+            drop(connected_guard);
+            self.emit_config_preview(None).await;
+        }
+
         // Now take ownership of the protocol and clear connected_device
         let protocol_opt = {
             let mut connected_guard = self.connected_device.lock().await;
@@ -481,17 +830,133 @@ impl DeviceManager {
             let mut handles = self.unified_handles.lock().await;
             handles.remove(&device_id);
         }
+        self.config_edit_locks.lock().await.remove(&device_id);
+        crate::support::crash::record_serial_metrics(None);
+        self.protocol_support.lock().await.clear();
 
         // Now handle HID monitoring stop (after protocol disconnect so underlying interface closed)
     if matches!(crate::raw_state::get_display_mode(), crate::raw_state::DisplayMode::HID | crate::raw_state::DisplayMode::Both) {
             let _ = self.disconnect_hid().await; // Ignore errors (non-fatal)
             log::info!("Disconnected HID monitoring");
         }
+        Some(device_id)
+    }
 
-        // Emit disconnected state
-        self.update_device_connection_state(&device_id, ConnectionState::Disconnected).await;
-        log::info!("Disconnected from device {}", device_id);
-        Ok(())
+    /// The connected device vanished from discovery: drop the dead connection and remember its key
+    async fn handle_connection_lost(&self, device_id: Uuid, key: String, reason: &str) {
+        log::warn!("Lost connection to device {} ({}): {}", device_id, key, reason);
+        self.teardown_connection().await;
+        if self.get_app_settings().await.auto_reconnect {
+            *self.reconnect_target.lock().await = Some(key);
+        }
+        self.update_device_connection_state(&device_id, ConnectionState::Error(reason.to_string())).await;
+    }
+
+    /// Spawn the keep-alive for a freshly connected device
+    fn spawn_heartbeat(&self, device_id: Uuid, handle: UnifiedSerialHandle, mut clock: Option<ClockEstimator>) -> tokio::task::JoinHandle<()> {
+        let mgr = self.clone();
+        tokio::spawn(async move {
+            let metrics = handle.metrics_receiver();
+            let mut state = HeartbeatState::default();
+            let mut last_clock_sample = std::time::Instant::now();
+            loop {
+                tokio::time::sleep(HEARTBEAT_INTERVAL).await;
+                if mgr.get_connected_device_id().await != Some(device_id) {
+                    break;
+                }
+                // Keep refining the clock model; drift only shows over minutes
+                if let Some(estimator) = clock.as_mut().filter(|_| last_clock_sample.elapsed() >= CLOCK_RESYNC_INTERVAL) {
+                    last_clock_sample = std::time::Instant::now();
+                    if let Some(sample) = Self::sample_device_clock(&handle).await {
+                        estimator.add(sample);
+                        clock_sync::publish(estimator.fit());
+                    }
+                }
+                if !state.needs_probe(metrics.borrow().lines_read) {
+                    continue;
+                }
+                let result = handle.send_command_with_priority("STATUS".to_string(), heartbeat_spec(), CommandPriority::Background).await;
+                let outcome = ProbeOutcome::from_result(&result);
+                if outcome == ProbeOutcome::Missed {
+                    log::warn!("Heartbeat probe failed for device {}: {:?}", device_id, result.err());
+                }
+                if state.record(outcome, metrics.borrow().lines_read) {
+                    // Recover on a separate task: tearing down the connection aborts this one
+                    let mgr = mgr.clone();
+                    tokio::spawn(async move { mgr.recover_from_heartbeat_timeout(device_id).await });
+                    break;
+                }
+            }
+        })
+    }
+
+    /// One `TIME` round trip; `None` if the firmware did not answer it
+    async fn sample_device_clock(handle: &UnifiedSerialHandle) -> Option<ClockSample> {
+        let sent = chrono::Utc::now();
+        let response = handle.send_command_with_priority(clock_sync::TIME_COMMAND.to_string(), clock_sync::time_spec(), CommandPriority::Background).await.ok()?;
+        let received = chrono::Utc::now();
+        clock_sync::parse_time_response(&response.lines).map(|device_us| ClockSample::new(sent, received, device_us))
+    }
+
+    /// Clock handshake on connect: publish a model of the device clock for event timestamps.
+    /// `None` (and no model) when the firmware does not support `TIME`.
+    async fn sync_device_clock(handle: &UnifiedSerialHandle) -> Option<ClockEstimator> {
+        let mut estimator = ClockEstimator::new();
+        for _ in 0..CLOCK_SYNC_SAMPLES {
+            match Self::sample_device_clock(handle).await {
+                Some(sample) => estimator.add(sample),
+                None => break,
+            }
+        }
+        let model = estimator.fit();
+        clock_sync::publish(model);
+        match model {
+            Some(model) => {
+                log::info!("Device clock synchronized ({} samples, ±{}µs)", model.samples, model.uncertainty_us);
+                Some(estimator)
+            }
+            None => {
+                log::info!("Firmware does not answer TIME; event timestamps stay device-relative");
+                None
+            }
+        }
+    }
+
+    /// Clock model of the connected device
+    pub fn get_clock_model(&self) -> Option<ClockModel> {
+        clock_sync::current()
+    }
+
+    /// The link stopped answering: mark it failed and go through the reconnect path
+    async fn recover_from_heartbeat_timeout(&self, device_id: Uuid) {
+        let Some(device) = self.get_device(&device_id).await else { return };
+        let key = Self::device_key(&device.port_name, device.serial_number.as_deref());
+        self.handle_connection_lost(device_id, key, HEARTBEAT_TIMEOUT_ERROR).await;
+        match self.discover_devices().await {
+            Ok(devices) => self.maybe_auto_reconnect(&devices).await,
+            Err(e) => log::warn!("Discovery after heartbeat timeout failed: {}", e),
+        }
+    }
+
+    /// Reconnect to a device that dropped unexpectedly once discovery reports its key again
+    pub async fn maybe_auto_reconnect(&self, discovered: &[Device]) {
+        let target = self.reconnect_target.lock().await.clone();
+        let Some(key) = target else { return };
+        if self.get_connected_device_id().await.is_some() {
+            self.reconnect_target.lock().await.take();
+            return;
+        }
+        let Some(device) = discovered.iter().find(|d| Self::device_key(&d.port_name, d.serial_number.as_deref()) == key) else { return };
+        self.reconnect_target.lock().await.take();
+        log::info!("Device {} reappeared, reconnecting", key);
+        if let Err(e) = self.connect_device(&device.id).await {
+            log::warn!("Auto-reconnect to {} failed: {}", key, e);
+        }
+    }
+
+    /// Identity used to match a device across discoveries
+    fn device_key(port_name: &str, serial_number: Option<&str>) -> String {
+        format!("{}:{}", port_name, serial_number.unwrap_or_default())
     }
 
     /// Get the currently connected device ID
@@ -525,6 +990,16 @@ impl DeviceManager {
         }).await
     }
 
+    /// Read every axis of the connected device in one pipelined batch
+    pub async fn read_all_axis_configs(&self) -> Result<Vec<crate::serial::protocol::AxisConfig>> {
+        self.execute_with_protocol(|protocol| {
+            Box::pin(async move {
+                protocol.read_all_axis_configs().await
+                    .map_err(DeviceError::SerialError)
+            })
+        }).await
+    }
+
     /// Write axis configuration to connected device
     pub async fn write_axis_config(&self, config: &crate::serial::protocol::AxisConfig) -> Result<()> {
         let config_clone = config.clone();
@@ -546,6 +1021,16 @@ impl DeviceManager {
         }).await
     }
 
+    /// Read every button of the connected device in one pipelined batch
+    pub async fn read_all_button_configs(&self) -> Result<Vec<crate::serial::protocol::ButtonConfig>> {
+        self.execute_with_protocol(|protocol| {
+            Box::pin(async move {
+                protocol.read_all_button_configs().await
+                    .map_err(DeviceError::SerialError)
+            })
+        }).await
+    }
+
     /// Write button configuration to connected device
     pub async fn write_button_config(&self, config: &crate::serial::protocol::ButtonConfig) -> Result<()> {
         let config_clone = config.clone();
@@ -590,6 +1075,10 @@ impl DeviceManager {
     {
         let mut profile_guard = self.profile_manager.lock().await;
         f(&mut profile_guard);
+        // Autosave every mutation so profiles survive restarts
+        if let Some(store) = &*self.profile_store.lock().await {
+            store.save(&profile_guard)?;
+        }
         Ok(())
     }
 
@@ -605,9 +1094,10 @@ impl DeviceManager {
         let mut devices_guard = self.devices.write().await;
         if let Some(device) = devices_guard.get_mut(device_id) {
             device.update_connection_state(state);
+            crate::support::crash::record_device(Some(device.clone()));
         }
         drop(devices_guard);
-        // Emit updated device list snapshot FIRST so frontend has current device object before connection event
+        // Emit device list deltas FIRST so frontend has current device object before connection event
         self.emit_device_list().await; // internal logging added there
         // Then emit standardized connection event payload
         if let Some(app) = &*self.app_handle.lock().await {
@@ -633,21 +1123,48 @@ impl DeviceManager {
                 sanitized.firmware_version = cleaned;
             }
             device.update_device_status(sanitized);
+            self.known_devices.lock().await.record_seen(device);
         }
         drop(devices_guard);
         self.emit_device_list().await;
     }
 
+    /// Emit the device_added/updated/removed events since the last published list
     pub async fn emit_device_list(&self) {
-        if let Some(app) = &*self.app_handle.lock().await {
-            let list = self.get_devices().await;
-            let count = list.len();
-            match app.emit("device_list_updated", &list) {
-                Ok(_) => log::info!("Emitted device_list_updated ({} devices)", count),
-                Err(e) => log::warn!("Failed to emit device_list_updated: {}", e),
+        let Some(app) = self.app_handle.lock().await.clone() else {
+            log::debug!("Skipped device list events (app_handle not yet set)");
+            return;
+        };
+        // The tracker lock keeps diffing and emission in sequence order across callers
+        let mut tracker = self.device_list_events.lock().await;
+        let list = self.get_devices().await;
+        Self::emit_device_deltas(&app, tracker.update(&list));
+    }
+
+    /// Current device list with the sequence number of the last delta event it includes
+    pub async fn get_device_list_snapshot(&self) -> DeviceListSnapshot {
+        let app = self.app_handle.lock().await.clone();
+        let mut tracker = self.device_list_events.lock().await;
+        let devices = self.get_devices().await;
+        // Publish pending changes first so the snapshot and the event stream agree
+        let deltas = tracker.update(&devices);
+        if let Some(app) = app {
+            Self::emit_device_deltas(&app, deltas);
+        }
+        DeviceListSnapshot { seq: tracker.seq(), devices }
+    }
+
+    fn emit_device_deltas(app: &AppHandle, deltas: Vec<DeviceListDelta>) {
+        for delta in deltas {
+            let (name, seq) = (delta.event_name(), delta.seq());
+            let emitted = match &delta {
+                DeviceListDelta::Added(e) | DeviceListDelta::Updated(e) => app.emit(name, e),
+                DeviceListDelta::Removed(e) => app.emit(name, e),
+            };
+            match emitted {
+                Ok(_) => log::info!("Emitted {} (seq {})", name, seq),
+                Err(e) => log::warn!("Failed to emit {} (seq {}): {}", name, seq, e),
             }
-        } else {
-            log::debug!("Skipped device_list_updated emission (app_handle not yet set)");
         }
     }
 
@@ -667,13 +1184,14 @@ impl DeviceManager {
                     let current_version = Version::parse(&device_status.firmware_version)
                         .map_err(|e| DeviceError::UpdateError(format!("Invalid firmware version: {}", e)))?;
                     
-                    let update_service = UpdateService::new(
+                    let update_service = UpdateService::with_options(
                         update_settings.repo_owner.clone(),
                         update_settings.repo_name.clone(),
-                    );
+                        &update_settings.client_options(),
+                    ).map_err(|e| DeviceError::UpdateError(format!("Invalid update network settings: {}", e)))?;
                     
                     let result = update_service
-                        .check_for_updates(current_version)
+                        .check_for_updates(current_version, update_settings.channel, device_status.hardware_revision.as_deref())
                         .await
                         .map_err(|e| DeviceError::UpdateError(format!("Update check failed: {}", e)))?;
                     
@@ -685,6 +1203,79 @@ impl DeviceManager {
         Ok(None)
     }
 
+    /// Background update checks on the configured channel (see `crate::update::scheduler`)
+    fn spawn_update_scheduler(&self) {
+        let mgr = self.clone();
+        let task = tokio::spawn(async move {
+            let mut schedule = UpdateSchedule::default();
+            tokio::time::sleep(scheduler::STARTUP_DELAY).await;
+            loop {
+                mgr.run_scheduled_update_check(&mut schedule).await;
+                tokio::time::sleep(scheduler::SCHEDULER_TICK).await;
+            }
+        });
+        self.lifecycle.track("update scheduler", task);
+    }
+
+    /// Check for updates when due and a device with a known firmware version is connected
+    async fn run_scheduled_update_check(&self, schedule: &mut UpdateSchedule) {
+        let settings = self.get_app_settings().await.firmware_update;
+        let now = chrono::Utc::now();
+        if !schedule.is_due(settings.auto_check, settings.check_interval_hours, settings.last_check, now) {
+            return;
+        }
+        let Some(device_id) = self.get_connected_device_id().await else { return };
+        let Some(status) = self.get_device(&device_id).await.and_then(|d| d.device_status) else { return };
+        let Ok(current_version) = Version::parse(&status.firmware_version) else {
+            log::debug!("Scheduled update check skipped: firmware version '{}' is not semver", status.firmware_version);
+            return;
+        };
+        let service = match UpdateService::with_options(settings.repo_owner.clone(), settings.repo_name.clone(), &settings.client_options()) {
+            Ok(service) => service,
+            Err(e) => {
+                log::warn!("Scheduled update check skipped: {}", e);
+                schedule.failed(&e, now);
+                return;
+            }
+        };
+        match service.check_for_updates(current_version.clone(), settings.channel, status.hardware_revision.as_deref()).await {
+            Ok(result) => {
+                schedule.succeeded();
+                self.record_update_check(now).await;
+                let Some(release) = result.release_info.filter(|_| result.update_available) else { return };
+                if !schedule.should_notify(&release.version) {
+                    return;
+                }
+                log::info!("Firmware {} is available (installed {})", release.version, current_version);
+                if let Some(app) = &*self.app_handle.lock().await {
+                    let payload = FirmwareUpdateAvailable { current_version, release };
+                    if let Err(e) = app.emit(scheduler::FIRMWARE_UPDATE_AVAILABLE_EVENT, &payload) {
+                        log::warn!("Failed to emit {}: {}", scheduler::FIRMWARE_UPDATE_AVAILABLE_EVENT, e);
+                    }
+                }
+            }
+            Err(e) => {
+                log::warn!("Scheduled update check failed: {}", e);
+                schedule.failed(&e, now);
+            }
+        }
+    }
+
+    /// Persist the time of the last successful update check
+    pub async fn record_update_check(&self, at: chrono::DateTime<chrono::Utc>) {
+        // Read and write under one lock so a concurrent settings change is not reverted
+        let mut store = self.settings.lock().await;
+        let mut settings = store.settings().clone();
+        settings.firmware_update.last_check = Some(at);
+        store.set_settings(settings);
+    }
+
+    /// Hardware revision reported by the connected device
+    pub async fn get_device_hardware_revision(&self) -> Option<String> {
+        let device_id = self.get_connected_device_id().await?;
+        self.devices.read().await.get(&device_id)?.device_status.as_ref()?.hardware_revision.clone()
+    }
+
     /// Get current firmware version of connected device
     pub async fn get_device_firmware_version(&self) -> Option<String> {
         let connected_guard = self.connected_device.lock().await;
@@ -705,19 +1296,26 @@ impl DeviceManager {
 
     /// Read raw binary configuration from device
     pub async fn read_config_binary(&self) -> Result<Vec<u8>> {
+        let (device_id, data) = self.read_device_file("/config.bin").await?;
+        self.archive_config(&device_id, BackupReason::Read, &data).await;
+        Ok(data)
+    }
+
+    /// Read a file from the device storage with raw monitoring paused
+    async fn read_device_file(&self, path: &str) -> Result<(Uuid, Vec<u8>)> {
         // Temporarily pause monitoring to prevent data contamination
         let was_monitoring = self.is_raw_state_monitoring().await;
         if was_monitoring {
-            log::info!("Temporarily stopping monitoring for config read");
+            log::info!("Temporarily stopping monitoring to read {}", path);
             let _ = self.stop_raw_state_monitoring().await;
         }
         
         let mut connected_guard = self.connected_device.lock().await;
         
-        let result = if let Some((_, protocol)) = connected_guard.as_mut() {
-            let data = protocol.read_file("/config.bin").await
-                .map_err(DeviceError::SerialError)?;
-            Ok(data)
+        let result = if let Some((device_id, protocol)) = connected_guard.as_mut() {
+            protocol.read_file(path).await
+                .map(|data| (*device_id, data))
+                .map_err(DeviceError::SerialError)
         } else {
             Err(DeviceError::NotConnected)
         };
@@ -728,7 +1326,7 @@ impl DeviceManager {
         // Restart monitoring if it was running
         if was_monitoring {
             if let Some(app_handle) = self.app_handle.lock().await.as_ref() {
-                log::info!("Restarting monitoring after config read");
+                log::info!("Restarting monitoring after reading {}", path);
                 let _ = self.start_raw_state_monitoring(app_handle.clone()).await;
             }
         }
@@ -755,18 +1353,27 @@ impl DeviceManager {
         
         let mut connected_guard = self.connected_device.lock().await;
         
-        let result = if let Some((_, protocol)) = connected_guard.as_mut() {
-            // The firmware automatically creates a backup before writing
-            protocol.write_raw_file("/config.bin", &validated_data).await
-                .map_err(DeviceError::SerialError)?;
-            log::info!("Successfully wrote binary configuration to device");
-            Ok(())
+        let result = if let Some((device_id, protocol)) = connected_guard.as_mut() {
+            // Written, read back and compared before SAVE_CONFIG; rolled back on mismatch
+            let write = protocol.write_config_transaction("/config.bin", &validated_data).await;
+            self.record_file_command_support(protocol).await;
+            write.map(|_| {
+                log::info!("Successfully wrote binary configuration to device");
+                *device_id
+            }).map_err(DeviceError::SerialError)
         } else {
             Err(DeviceError::NotConnected)
         };
         
         // Drop the lock before restarting monitoring
         drop(connected_guard);
+        if let Ok(device_id) = &result {
+            self.archive_config(device_id, BackupReason::Write, &validated_data).await;
+            // SAVE_CONFIG replaced whatever ran from RAM
+            if self.config_preview.lock().await.take().is_some() {
+                self.emit_config_preview(None).await;
+            }
+        }
         
         // Restart monitoring if it was running
         if was_monitoring {
@@ -776,68 +1383,445 @@ impl DeviceManager {
             }
         }
         
-        result
+        result.map(|_| ())
     }
 
-    /// Delete configuration file (forces regeneration on next boot)
-    pub async fn delete_config_file(&self) -> Result<()> {
-        let mut connected_guard = self.connected_device.lock().await;
-        
-        if let Some((_, protocol)) = connected_guard.as_mut() {
-            protocol.delete_file("/config.bin").await
-                .map_err(DeviceError::SerialError)?;
-            log::warn!("Configuration file deleted - will regenerate on next boot");
-            Ok(())
-        } else {
-            Err(DeviceError::NotConnected)
+    /// Run `data` from device RAM without storing it, replacing any earlier preview
+    pub async fn preview_config_binary(&self, data: &[u8]) -> Result<()> {
+        let config = BinaryConfig::from_bytes(data)
+            .map_err(|e| DeviceError::ProtocolError(format!("Invalid config data: {}", e)))?;
+        let validated_data = config.to_bytes()
+            .map_err(|e| DeviceError::ProtocolError(format!("Failed to serialize config: {}", e)))?;
+
+        let was_monitoring = self.is_raw_state_monitoring().await;
+        if was_monitoring {
+            let _ = self.stop_raw_state_monitoring().await;
+        }
+        let result = {
+            let mut connected_guard = self.connected_device.lock().await;
+            match connected_guard.as_mut() {
+                Some((device_id, protocol)) => protocol.test_config(&validated_data).await
+                    .map(|_| *device_id)
+                    .map_err(DeviceError::SerialError),
+                None => Err(DeviceError::NotConnected),
+            }
+        };
+        if was_monitoring {
+            if let Some(app_handle) = self.app_handle.lock().await.as_ref() {
+                let _ = self.start_raw_state_monitoring(app_handle.clone()).await;
+            }
         }
+
+        let preview = ConfigPreview::new(result?, validated_data);
+        *self.config_preview.lock().await = Some(preview.clone());
+        self.emit_config_preview(Some(&preview)).await;
+        Ok(())
     }
 
-    /// Reset device to factory defaults
-    pub async fn reset_device_to_defaults(&self) -> Result<()> {
-        let mut connected_guard = self.connected_device.lock().await;
-        
-        if let Some((_, protocol)) = connected_guard.as_mut() {
-            protocol.reset_to_defaults().await
-                .map_err(DeviceError::SerialError)?;
-            log::info!("Device reset to factory defaults");
-            Ok(())
-        } else {
-            Err(DeviceError::NotConnected)
+    /// Dry run of `data`: the firmware checks it with VALIDATE_CONFIG when supported, otherwise
+    /// (or without a device) the host-side checks run. Nothing is applied or stored.
+    pub async fn validate_config_on_device(&self, data: &[u8]) -> Result<ConfigValidationReport> {
+        let caps = self.get_firmware_capabilities().await;
+        if !caps.as_ref().is_some_and(|c| c.validate_config) {
+            return Ok(validate::validate_config_binary(data, caps.as_ref()));
         }
-    }
 
-    /// Format device storage (nuclear option - deletes all files)
-    pub async fn format_device_storage(&self) -> Result<()> {
-        let mut connected_guard = self.connected_device.lock().await;
-        
-        if let Some((_, protocol)) = connected_guard.as_mut() {
-            protocol.format_storage().await
-                .map_err(DeviceError::SerialError)?;
-            log::warn!("Device storage formatted - all files deleted");
-            Ok(())
-        } else {
-            Err(DeviceError::NotConnected)
+        let was_monitoring = self.is_raw_state_monitoring().await;
+        if was_monitoring {
+            let _ = self.stop_raw_state_monitoring().await;
+        }
+        let result = {
+            let mut connected_guard = self.connected_device.lock().await;
+            match connected_guard.as_mut() {
+                Some((_, protocol)) => protocol.validate_config(data).await.map_err(DeviceError::SerialError),
+                None => Err(DeviceError::NotConnected),
+            }
+        };
+        if was_monitoring {
+            if let Some(app_handle) = self.app_handle.lock().await.as_ref() {
+                let _ = self.start_raw_state_monitoring(app_handle.clone()).await;
+            }
         }
-    }
 
-    /// Get device storage information
-    pub async fn get_device_storage_info(&self) -> Result<StorageInfo> {
-        let mut connected_guard = self.connected_device.lock().await;
-        
-        if let Some((_, protocol)) = connected_guard.as_mut() {
-            let info = protocol.get_storage_details().await
-                .map_err(DeviceError::SerialError)?;
-            Ok(info)
-        } else {
-            Err(DeviceError::NotConnected)
+        match result {
+            Ok(findings) => Ok(ConfigValidationReport::new(ValidationSource::Firmware, validate::firmware_diagnostics(&findings))),
+            // Disconnected or capability gone since the check above
+            Err(DeviceError::NotConnected | DeviceError::SerialError(crate::serial::SerialError::Unsupported(_))) => {
+                Ok(validate::validate_config_binary(data, caps.as_ref()))
+            }
+            Err(e) => Err(e),
         }
     }
 
-    /// List files on device storage
-    pub async fn list_device_files(&self) -> Result<Vec<String>> {
-        let mut connected_guard = self.connected_device.lock().await;
-        
+    pub async fn get_config_preview(&self) -> Option<ConfigPreview> {
+        self.config_preview.lock().await.clone()
+    }
+
+    /// Store the previewed config through the verified write transaction
+    pub async fn commit_preview_config(&self) -> Result<()> {
+        let data = self.config_preview.lock().await.as_ref().map(|p| p.data.clone())
+            .ok_or_else(|| DeviceError::InvalidConfiguration("No config preview is active".to_string()))?;
+        // Clears the preview once SAVE_CONFIG succeeded; a failed write leaves it to discard
+        self.write_config_binary(&data).await
+    }
+
+    /// Drop the preview; the device reloads its stored config
+    pub async fn discard_preview_config(&self) -> Result<()> {
+        if self.config_preview.lock().await.is_none() {
+            return Err(DeviceError::InvalidConfiguration("No config preview is active".to_string()));
+        }
+        self.execute_with_protocol(|protocol| {
+            Box::pin(async move { protocol.revert_test_config().await.map_err(DeviceError::SerialError) })
+        }).await?;
+        self.config_preview.lock().await.take();
+        self.emit_config_preview(None).await;
+        Ok(())
+    }
+
+    async fn emit_config_preview(&self, preview: Option<&ConfigPreview>) {
+        if let Some(app) = &*self.app_handle.lock().await {
+            if let Err(e) = app.emit(CONFIG_PREVIEW_EVENT, &preview) {
+                log::warn!("Failed to emit {}: {}", CONFIG_PREVIEW_EVENT, e);
+            }
+        }
+    }
+
+    /// Read-modify-write of the device config. `edit` changes one part; the write is refused if it
+    /// introduces validation errors and skipped if nothing changed. Edits of the same device are
+    /// serialized so concurrent ones cannot overwrite each other. Emits `device_config_changed`
+    /// with the diff after a write.
+    async fn edit_device_config<F>(&self, edit: F) -> Result<crate::config::ConfigDiff>
+    where
+        F: FnOnce(&mut BinaryConfig) -> std::result::Result<(), String>,
+    {
+        let device_id = self.get_connected_device_id().await.ok_or(DeviceError::NotConnected)?;
+        let edit_lock = self.config_edit_locks.lock().await.entry(device_id).or_default().clone();
+        let _editing = edit_lock.lock().await;
+
+        let data = self.read_config_binary().await?;
+        let old = BinaryConfig::from_bytes(&data)
+            .map_err(|e| DeviceError::ProtocolError(format!("Invalid config data: {}", e)))?;
+        let mut new = old.clone();
+        edit(&mut new).map_err(DeviceError::InvalidConfiguration)?;
+
+        // Problems the device config already had do not block an unrelated edit
+        let existing: std::collections::HashSet<String> = crate::config::validate_config(&old)
+            .into_iter().map(|d| d.message).collect();
+        let introduced: Vec<String> = crate::config::validate_config(&new).into_iter()
+            .filter(|d| d.severity == crate::config::validate::Severity::Error && !existing.contains(&d.message))
+            .map(|d| d.message)
+            .collect();
+        if !introduced.is_empty() {
+            return Err(DeviceError::InvalidConfiguration(introduced.join("; ")));
+        }
+
+        let diff = crate::config::diff_configs(&old, &new);
+        if diff.identical {
+            return Ok(diff);
+        }
+        let bytes = new.to_bytes()
+            .map_err(|e| DeviceError::ProtocolError(format!("Failed to serialize config: {}", e)))?;
+        self.write_config_binary(&bytes).await?;
+
+        if let Some(app) = &*self.app_handle.lock().await {
+            let payload = serde_json::json!({ "device_id": device_id, "diff": diff });
+            if let Err(e) = app.emit("device_config_changed", &payload) {
+                log::warn!("Failed to emit device_config_changed: {}", e);
+            }
+        }
+        Ok(diff)
+    }
+
+    /// Replace one axis of the device config (matched by `axis.index`)
+    pub async fn update_axis_in_config(&self, axis: &crate::config::json::AxisJson) -> Result<crate::config::ConfigDiff> {
+        self.edit_device_config(|config| config.set_axis(axis)).await
+    }
+
+    /// Apply range, deadzone, curve and inversion of the UI axis list to the device config
+    pub async fn update_axis_configs(&self, axes: &[crate::config::binary::UIAxisConfig]) -> Result<Vec<crate::config::binary::UIAxisConfig>> {
+        let mut result = Vec::new();
+        self.edit_device_config(|config| {
+            config.apply_axis_configs(axes)?;
+            result = config.to_axis_configs();
+            Ok(())
+        }).await?;
+        Ok(result)
+    }
+
+    /// Store `curve` on an axis as the firmware preset the user confirmed (see `config::curve`)
+    pub async fn set_axis_curve(&self, axis: u8, curve: &crate::config::AxisCurve, confirmed_preset: &str) -> Result<crate::config::PresetMatch> {
+        let mut preset = None;
+        self.edit_device_config(|config| {
+            preset = Some(config.set_axis_curve(axis, curve, confirmed_preset)?);
+            Ok(())
+        }).await?;
+        preset.ok_or_else(|| DeviceError::ProtocolError("Curve edit produced no preset".to_string()))
+    }
+
+    /// Apply buttons, latch mode and direction of the UI encoder list to the device config
+    pub async fn update_encoder_configs(&self, encoders: &[crate::config::UIEncoderConfig]) -> Result<Vec<crate::config::UIEncoderConfig>> {
+        let mut result = Vec::new();
+        self.edit_device_config(|config| {
+            config.apply_encoder_configs(encoders)?;
+            result = config.to_encoder_configs();
+            Ok(())
+        }).await?;
+        Ok(result)
+    }
+
+    /// Replace the button matrix of the device config
+    pub async fn update_matrix_config(&self, matrix: &crate::config::MatrixConfig) -> Result<crate::config::MatrixConfig> {
+        let mut result = None;
+        self.edit_device_config(|config| {
+            config.apply_matrix_config(matrix)?;
+            result = Some(config.to_matrix_config());
+            Ok(())
+        }).await?;
+        result.ok_or_else(|| DeviceError::ProtocolError("Matrix edit produced no config".to_string()))
+    }
+
+    /// Change the USB descriptor of the device config; takes effect after the device re-enumerates
+    pub async fn update_usb_descriptor(&self, descriptor: &crate::config::UsbDescriptorConfig) -> Result<crate::config::UsbDescriptorConfig> {
+        let mut result = None;
+        self.edit_device_config(|config| {
+            config.apply_usb_descriptor(descriptor)?;
+            result = Some(config.to_usb_descriptor());
+            Ok(())
+        }).await?;
+        result.ok_or_else(|| DeviceError::ProtocolError("USB descriptor edit produced no descriptor".to_string()))
+    }
+
+    /// Replace the logical input at `index` of the device config, or append one at the end
+    pub async fn update_logical_input_in_config(&self, index: usize, input: &crate::config::json::LogicalInputJson) -> Result<crate::config::ConfigDiff> {
+        self.edit_device_config(|config| config.set_logical_input(index, input)).await
+    }
+
+    /// Reassign joystick button IDs of the device config (see `config::remap`). Moves are checked
+    /// against the button count of the HID report, or the firmware maximum before it is known.
+    pub async fn remap_logical_buttons(&self, mapping: &[(u8, u8)], conflict: crate::config::RemapConflict) -> Result<crate::config::RemapSummary> {
+        let button_count = self.hid_button_count().await;
+        let mut summary = None;
+        self.edit_device_config(|config| {
+            summary = Some(config.remap_logical_buttons(mapping, conflict, button_count)?);
+            Ok(())
+        }).await?;
+        summary.ok_or_else(|| DeviceError::ProtocolError("Remap produced no summary".to_string()))
+    }
+
+    /// Buttons in the HID report, or the firmware maximum before the report layout is known
+    async fn hid_button_count(&self) -> u8 {
+        self.hid_button_mapping().await
+            .map(|m| m.len().min(u8::MAX as usize) as u8)
+            .filter(|&count| count > 0)
+            .unwrap_or(crate::config::binary::MAX_LOGICAL_INPUT_COUNT)
+    }
+
+    /// Shift layer of the connected device: stored in its config when the firmware supports
+    /// shift layers, otherwise the layer the virtual joystick bridge emulates
+    pub async fn get_shift_layer(&self) -> Result<ShiftLayerState> {
+        let data = self.read_config_binary().await?;
+        let config = BinaryConfig::from_bytes(&data)
+            .map_err(|e| DeviceError::ProtocolError(format!("Invalid config data: {}", e)))?;
+        let firmware = self.get_firmware_capabilities().await.is_some_and(|c| c.shift_layers);
+        let button_count = self.hid_button_count().await;
+        Ok(ShiftLayerState {
+            mode: if firmware { ShiftLayerMode::Firmware } else { ShiftLayerMode::Bridge },
+            layer: if firmware { config.shift_layer() } else { crate::interop::get_virtual_bridge().status().shift_layer },
+            button_count,
+            free_buttons: config.free_base_buttons(button_count),
+        })
+    }
+
+    /// Validate `layer` against the device config and store it (firmware support) or hand it to
+    /// the virtual joystick bridge; None removes the layer
+    pub async fn set_shift_layer(&self, layer: Option<ShiftLayer>) -> Result<ShiftLayerState> {
+        let button_count = self.hid_button_count().await;
+        if self.get_firmware_capabilities().await.is_some_and(|c| c.shift_layers) {
+            self.edit_device_config(|config| {
+                if let Some(layer) = &layer {
+                    config.check_shift_layer(layer, button_count)?;
+                }
+                config.set_shift_layer(layer.as_ref())
+            }).await?;
+        } else {
+            if let Some(layer) = &layer {
+                let data = self.read_config_binary().await?;
+                let mut config = BinaryConfig::from_bytes(&data)
+                    .map_err(|e| DeviceError::ProtocolError(format!("Invalid config data: {}", e)))?;
+                // A layer stored by newer firmware would be applied twice
+                config.set_shift_layer(None).map_err(DeviceError::InvalidConfiguration)?;
+                config.check_shift_layer(layer, button_count).map_err(DeviceError::InvalidConfiguration)?;
+            }
+            crate::interop::get_virtual_bridge().set_shift_layer(layer);
+        }
+        self.get_shift_layer().await
+    }
+
+    /// Hat switches of the device config
+    pub async fn get_hat_configs(&self) -> Result<Vec<crate::config::UIHatConfig>> {
+        let data = self.read_config_binary().await?;
+        let config = BinaryConfig::from_bytes(&data)
+            .map_err(|e| DeviceError::ProtocolError(format!("Invalid config data: {}", e)))?;
+        Ok(config.to_hat_configs())
+    }
+
+    /// Replace the hats of the device config (see `config::hats`). Firmware without hat support
+    /// can only have its hats removed.
+    pub async fn set_hat_configs(&self, hats: &[crate::config::UIHatConfig]) -> Result<Vec<crate::config::UIHatConfig>> {
+        if !hats.is_empty() {
+            self.require_capability(Capability::Hats).await?;
+        }
+        let mut result = Vec::new();
+        self.edit_device_config(|config| {
+            config.set_hat_configs(hats)?;
+            result = config.to_hat_configs();
+            Ok(())
+        }).await?;
+        Ok(result)
+    }
+
+    /// Replace the device config with template `id`, keeping the device's USB descriptor.
+    /// Firmware whose config layout differs from the one templates are built in is refused,
+    /// since its current config does not parse.
+    pub async fn apply_config_template(&self, id: &str) -> Result<crate::config::ConfigDiff> {
+        let template = crate::config::templates::build_config_template(id).map_err(DeviceError::InvalidConfiguration)?;
+        self.edit_device_config(move |config| {
+            let usb_descriptor = config.stored_config.usb_descriptor;
+            *config = template;
+            config.stored_config.usb_descriptor = usb_descriptor;
+            Ok(())
+        }).await
+    }
+
+    /// The firmware's own copy of the previous config
+    async fn read_device_backup(&self) -> Result<BinaryConfig> {
+        let (_, data) = self.read_device_file(DEVICE_BACKUP_FILE).await?;
+        BinaryConfig::from_bytes(&data)
+            .map_err(|e| DeviceError::ProtocolError(format!("Invalid device backup {}: {}", DEVICE_BACKUP_FILE, e)))
+    }
+
+    /// What restoring the firmware's backup would change in the current device config
+    pub async fn compare_with_device_backup(&self) -> Result<crate::config::ConfigDiff> {
+        let backup = self.read_device_backup().await?;
+        let data = self.read_config_binary().await?;
+        let current = BinaryConfig::from_bytes(&data)
+            .map_err(|e| DeviceError::ProtocolError(format!("Invalid config data: {}", e)))?;
+        Ok(crate::config::diff_configs(&current, &backup))
+    }
+
+    /// Write the firmware's backup back as the device config. The firmware backs up the config
+    /// being replaced first, so restoring twice swaps back.
+    pub async fn restore_from_device_backup(&self) -> Result<crate::config::ConfigDiff> {
+        let backup = self.read_device_backup().await?;
+        log::info!("Restoring config from {}", DEVICE_BACKUP_FILE);
+        self.edit_device_config(move |config| {
+            *config = backup;
+            Ok(())
+        }).await
+    }
+
+    /// Keep a copy of a config transferred to or from the device; failures are only logged
+    async fn archive_config(&self, device_id: &Uuid, reason: BackupReason, data: &[u8]) {
+        let serial = self.get_device(device_id).await.and_then(|d| d.serial_number);
+        let archived = self.config_backups.lock().await.archive(serial.as_deref(), reason, data);
+        match archived {
+            Ok(Some(backup)) => {
+                if let Some(serial) = &serial { self.known_devices.lock().await.record_backup(serial, &backup); }
+            }
+            Ok(None) => {}
+            Err(e) => log::warn!("Failed to archive config backup: {}", e),
+        }
+    }
+
+    /// Archived configs, newest first; `serial` limits the list to one device
+    pub async fn list_config_backups(&self, serial: Option<&str>) -> Result<Vec<ConfigBackupInfo>> {
+        Ok(self.config_backups.lock().await.list(serial)?)
+    }
+
+    /// Write an archived config back to the connected device
+    pub async fn restore_config_backup(&self, id: &str) -> Result<()> {
+        let data = self.config_backups.lock().await.read(id)?;
+        log::info!("Restoring config backup {}", id);
+        self.write_config_binary(&data).await
+    }
+
+    /// Contents of an archived config
+    pub async fn read_config_backup(&self, id: &str) -> Result<Vec<u8>> {
+        Ok(self.config_backups.lock().await.read(id)?)
+    }
+
+    pub async fn delete_config_backup(&self, id: &str) -> Result<()> {
+        Ok(self.config_backups.lock().await.delete(id)?)
+    }
+
+    /// Delete configuration file (forces regeneration on next boot)
+    pub async fn delete_config_file(&self) -> Result<()> {
+        let mut connected_guard = self.connected_device.lock().await;
+        
+        if let Some((_, protocol)) = connected_guard.as_mut() {
+            let result = protocol.delete_file("/config.bin").await;
+            self.record_file_command_support(protocol).await;
+            match result {
+                Ok(()) => log::warn!("Configuration file deleted - will regenerate on next boot"),
+                Err(crate::serial::SerialError::Unsupported(_)) => {
+                    // Older firmware: restoring defaults rewrites config.bin, which has the same effect
+                    log::warn!("DELETE_FILE unsupported, falling back to FORCE_DEFAULT_CONFIG");
+                    protocol.reset_to_defaults().await
+                        .map_err(DeviceError::SerialError)?;
+                }
+                Err(e) => return Err(DeviceError::SerialError(e)),
+            }
+            Ok(())
+        } else {
+            Err(DeviceError::NotConnected)
+        }
+    }
+
+    /// Reset device to factory defaults
+    pub async fn reset_device_to_defaults(&self) -> Result<()> {
+        let mut connected_guard = self.connected_device.lock().await;
+        
+        if let Some((_, protocol)) = connected_guard.as_mut() {
+            protocol.reset_to_defaults().await
+                .map_err(DeviceError::SerialError)?;
+            log::info!("Device reset to factory defaults");
+            Ok(())
+        } else {
+            Err(DeviceError::NotConnected)
+        }
+    }
+
+    /// Format device storage (nuclear option - deletes all files)
+    pub async fn format_device_storage(&self) -> Result<()> {
+        let mut connected_guard = self.connected_device.lock().await;
+        
+        if let Some((_, protocol)) = connected_guard.as_mut() {
+            protocol.format_storage().await
+                .map_err(DeviceError::SerialError)?;
+            log::warn!("Device storage formatted - all files deleted");
+            Ok(())
+        } else {
+            Err(DeviceError::NotConnected)
+        }
+    }
+
+    /// Get device storage information
+    pub async fn get_device_storage_info(&self) -> Result<StorageInfo> {
+        let mut connected_guard = self.connected_device.lock().await;
+        
+        if let Some((_, protocol)) = connected_guard.as_mut() {
+            let info = protocol.get_storage_details().await
+                .map_err(DeviceError::SerialError)?;
+            Ok(info)
+        } else {
+            Err(DeviceError::NotConnected)
+        }
+    }
+
+    /// List files on device storage
+    pub async fn list_device_files(&self) -> Result<Vec<String>> {
+        let mut connected_guard = self.connected_device.lock().await;
+        
         if let Some((_, protocol)) = connected_guard.as_mut() {
             let files = protocol.list_files().await
                 .map_err(DeviceError::SerialError)?;
@@ -865,9 +1849,9 @@ impl DeviceManager {
         let mut connected_guard = self.connected_device.lock().await;
         
         if let Some((_, protocol)) = connected_guard.as_mut() {
-            protocol.write_raw_file(filename, data).await
-                .map_err(DeviceError::SerialError)?;
-            Ok(())
+            let result = protocol.write_raw_file(filename, data).await;
+            self.record_file_command_support(protocol).await;
+            result.map_err(DeviceError::SerialError)
         } else {
             Err(DeviceError::NotConnected)
         }
@@ -878,14 +1862,56 @@ impl DeviceManager {
         let mut connected_guard = self.connected_device.lock().await;
         
         if let Some((_, protocol)) = connected_guard.as_mut() {
-            protocol.delete_file(filename).await
-                .map_err(DeviceError::SerialError)?;
-            Ok(())
+            let result = protocol.delete_file(filename).await;
+            self.record_file_command_support(protocol).await;
+            result.map_err(DeviceError::SerialError)
         } else {
             Err(DeviceError::NotConnected)
         }
     }
 
+    /// Store negotiated capabilities on the device and seed protocol support for `describe_protocol`
+    async fn apply_capabilities(&self, device_id: &Uuid, capabilities: FirmwareCapabilities) {
+        {
+            let mut support = self.protocol_support.lock().await;
+            support.insert("CAPABILITIES".to_string(), capabilities.source == CapabilitySource::Reported);
+            for capability in Capability::ALL {
+                let supported = capabilities.supports(capability);
+                // Heuristic positives are assumptions; leave those to probing
+                if capabilities.source == CapabilitySource::Reported || !supported {
+                    for name in capability.catalog_commands() {
+                        support.insert(name.to_string(), supported);
+                    }
+                }
+            }
+        }
+        if let Some(device) = self.devices.write().await.get_mut(device_id) {
+            device.capabilities = Some(capabilities);
+        }
+    }
+
+    /// Capabilities of the connected firmware (None when not connected)
+    pub async fn get_firmware_capabilities(&self) -> Option<FirmwareCapabilities> {
+        let connected_guard = self.connected_device.lock().await;
+        connected_guard.as_ref().and_then(|(_, protocol)| protocol.capabilities().cloned())
+    }
+
+    /// Fail with an "unsupported by firmware" error instead of sending a command that would time out
+    pub async fn require_capability(&self, capability: Capability) -> Result<()> {
+        match self.get_firmware_capabilities().await {
+            Some(caps) => caps.require(capability).map_err(DeviceError::SerialError),
+            None => Ok(()),
+        }
+    }
+
+    /// Remember what the firmware reported about WRITE_FILE / DELETE_FILE for `describe_protocol`
+    async fn record_file_command_support(&self, protocol: &ConfigProtocol) {
+        let (write, delete) = protocol.file_command_support();
+        let mut support = self.protocol_support.lock().await;
+        if let Some(write) = write { support.insert("WRITE_FILE".to_string(), write); }
+        if let Some(delete) = delete { support.insert("DELETE_FILE".to_string(), delete); }
+    }
+
     /// Read button states from HID device
     pub async fn read_button_states(&self) -> Result<ButtonStates> {
     // Check display mode allows HID (HID or Both)
@@ -961,6 +1987,34 @@ impl DeviceManager {
         hid_reader.mapping_details().await
     }
 
+    /// HID report bit -> logical button mapping, independent of the display mode
+    pub async fn hid_button_mapping(&self) -> Option<Vec<u8>> {
+        self.hid_reader.lock().await.button_mapping()
+    }
+
+    /// Frame counter statistics of the HID link, independent of the display mode
+    pub async fn hid_link_stats(&self) -> crate::hid::HidLinkStats {
+        self.hid_reader.lock().await.link_stats()
+    }
+
+    /// HID controls the connected firmware accepts
+    pub async fn hid_control_support(&self) -> crate::hid::HidControlSupport {
+        self.hid_reader.lock().await.control_support()
+    }
+
+    /// Write a raw feature report to the HID interface
+    pub async fn send_hid_feature_report(&self, report_id: u8, data: &[u8]) -> crate::hid::Result<()> {
+        self.hid_reader.lock().await.send_hid_feature_report(report_id, data).await
+    }
+
+    pub async fn hid_identify(&self, seconds: u8) -> crate::hid::Result<()> {
+        self.hid_reader.lock().await.identify(seconds).await
+    }
+
+    pub async fn hid_set_test_mode(&self, enabled: bool) -> crate::hid::Result<()> {
+        self.hid_reader.lock().await.set_test_mode(enabled).await
+    }
+
     /// Diagnostic: raw vs logical button bits (first 16) for offset debugging
     pub async fn hid_button_bit_diagnostics(&self) -> Option<serde_json::Value> {
     if !matches!(crate::raw_state::get_display_mode(), crate::raw_state::DisplayMode::HID | crate::raw_state::DisplayMode::Both) {
@@ -1005,6 +2059,53 @@ impl DeviceManager {
         }
     }
 
+    /// Watch the HID link and reconnect after the interface drops (USB reset, firmware flash).
+    /// Started once with the app handle.
+    fn spawn_hid_supervisor(&self, mut events: tokio::sync::watch::Receiver<crate::hid::HidConnectionEvent>) {
+        let mgr = self.clone();
+        let task = tokio::spawn(async move {
+            while events.changed().await.is_ok() {
+                let lost = events.borrow_and_update().is_lost();
+                if lost {
+                    mgr.reconnect_hid_with_backoff().await;
+                }
+            }
+        });
+        self.lifecycle.track("hid supervisor", task);
+    }
+
+    /// Retry the HID connection with exponential backoff while a serial device is connected in a
+    /// HID display mode. Ends when HID is back, or when the serial side goes away (its own
+    /// reconnect path connects HID again).
+    async fn reconnect_hid_with_backoff(&self) {
+        let mut delay = crate::hid::HID_RECONNECT_INITIAL_DELAY;
+        loop {
+            tokio::time::sleep(delay).await;
+            if self.get_connected_device_id().await.is_none()
+                || !matches!(crate::raw_state::get_display_mode(), crate::raw_state::DisplayMode::HID | crate::raw_state::DisplayMode::Both)
+            {
+                log::info!("HID reconnect abandoned: no serial device connected in a HID display mode");
+                return;
+            }
+            {
+                let hid_reader = self.hid_reader.lock().await;
+                if hid_reader.is_connected().await {
+                    return;
+                }
+                if hid_reader.device_present().await {
+                    match hid_reader.connect().await {
+                        Ok(()) => {
+                            log::info!("HID interface reconnected");
+                            return;
+                        }
+                        Err(e) => log::debug!("HID reconnect attempt failed: {}", e),
+                    }
+                }
+            }
+            delay = (delay * 2).min(crate::hid::HID_RECONNECT_MAX_DELAY);
+        }
+    }
+
     // Raw hardware state methods
 
     /// Read raw GPIO states from connected device
@@ -1087,6 +2188,19 @@ impl DeviceManager {
         }
     }
 
+    /// Latest raw state from the unified reader's snapshot; `None` without a unified connection
+    pub async fn raw_state_snapshot(&self) -> Option<crate::raw_state::RawStateSnapshotInfo> {
+        let handle = self.get_unified_serial_handle().await?;
+        let device_id = self.get_connected_device_id().await?;
+        let snapshot = handle.snapshot_receiver().borrow().clone();
+        Some(crate::raw_state::RawStateSnapshotInfo {
+            device_id: device_id.to_string(),
+            seq: snapshot.seq,
+            last_update_us: snapshot.last_update_us,
+            state: (&*snapshot).into(),
+        })
+    }
+
     /// Start raw state monitoring for connected device
     pub async fn start_raw_state_monitoring(&self, app_handle: tauri::AppHandle) -> Result<()> {
     // Check display mode allows Raw (Raw or Both)
@@ -1100,6 +2214,7 @@ impl DeviceManager {
         if self.raw_monitoring_active.load(Ordering::Relaxed) {
             return Ok(());
         }
+        self.require_capability(Capability::RawMonitor).await?;
 
         // Set monitoring flag
         self.raw_monitoring_active.store(true, Ordering::Relaxed);
@@ -1159,67 +2274,600 @@ impl DeviceManager {
         Ok(())
     }
 
-    /// Get access to connected protocol for monitoring (internal use)
-    pub(crate) async fn get_connected_protocol_for_monitoring(&self) -> Result<()> {
-        let connected_guard = self.connected_device.lock().await;
-        if connected_guard.is_some() {
-            Ok(())
-        } else {
-            Err(DeviceError::NotConnected)
-        }
-    }
-
     /// Send a raw monitor command
-    pub(crate) async fn send_raw_monitor_command(&self, command: &str) -> std::result::Result<String, String> {
+    pub(crate) async fn send_raw_monitor_command(&self, command: &'static str) -> std::result::Result<String, String> {
         let mut connected_guard = self.connected_device.lock().await;
         
         if let Some((_, protocol)) = &mut *connected_guard {
-            protocol.send_locked(command).await.map_err(|e| format!("Command failed: {}", e))
+            protocol.send_raw_command(command).await.map_err(|e| format!("Command failed: {}", e))
         } else {
             Err("No device connected".to_string())
         }
     }
 
-    /// Read monitor data (non-blocking) - reads directly from serial port
-    pub(crate) async fn read_monitor_data(&self, timeout_ms: u64) -> std::result::Result<String, String> {
-    let mut connected_guard = self.connected_device.lock().await;
-        if let Some((_, protocol)) = &mut *connected_guard {
-            let mut buffer = vec![0u8; 1024];
-            let read_res = protocol.read_data_locked(&mut buffer, timeout_ms).await;
-            match read_res {
-                Ok(bytes_read) => {
-                    if bytes_read > 0 {
-                        buffer.truncate(bytes_read);
-                        Ok(String::from_utf8_lossy(&buffer).to_string())
-                    } else {
-                        Ok(String::new())
+    /// Ports currently excluded from discovery
+    pub async fn get_quarantined_ports(&self) -> Vec<QuarantinedPort> {
+        self.port_quarantine.lock().await.list()
+    }
+
+    /// Release one quarantined port, or all when `port_name` is None. Returns the number removed.
+    pub async fn clear_port_quarantine(&self, port_name: Option<&str>) -> usize {
+        self.port_quarantine.lock().await.clear(port_name)
+    }
+
+    /// Describe the known firmware protocol, annotated with support on the connected device.
+    /// When `probe` is set, side-effect free commands not yet probed on this connection are sent once.
+    pub async fn describe_protocol(&self, probe: bool) -> crate::serial::catalog::ProtocolDescription {
+        use crate::serial::catalog::{PROTOCOL_COMMANDS, ProtocolDescription};
+
+        let connected_id = self.get_connected_device_id().await;
+        if probe && connected_id.is_some() && !self.raw_monitoring_active.load(Ordering::Relaxed) {
+            let pending: Vec<&'static str> = {
+                let support = self.protocol_support.lock().await;
+                PROTOCOL_COMMANDS.iter()
+                    .filter(|c| c.probe_safe && c.known_support.is_none() && !support.contains_key(c.name))
+                    .map(|c| c.name)
+                    .collect()
+            };
+            for name in pending {
+                let result = {
+                    let connected_guard = self.connected_device.lock().await;
+                    match &*connected_guard {
+                        Some((_, protocol)) => protocol.probe_command(name).await,
+                        None => break,
                     }
+                };
+                if let Some(supported) = result {
+                    log::debug!("Protocol probe {} -> supported={}", name, supported);
+                    self.protocol_support.lock().await.insert(name.to_string(), supported);
                 }
-        Err(_e) => Ok(String::new()), // No data available
             }
-        } else {
-            Err("No device connected".to_string())
         }
+
+        let firmware_version = match connected_id {
+            Some(id) => self.get_device(&id).await
+                .and_then(|d| d.device_status.map(|s| s.firmware_version)),
+            None => None,
+        };
+        let support = self.protocol_support.lock().await;
+        let commands = PROTOCOL_COMMANDS.iter()
+            .map(|c| {
+                let mut info = c.to_info(support.get(c.name).copied());
+                // Without a device nothing can be claimed about support
+                if connected_id.is_none() { info.supported = None; }
+                info
+            })
+            .collect();
+
+        ProtocolDescription { connected: connected_id.is_some(), firmware_version, commands }
     }
 
 }
 
+impl DeviceManager {
+    pub async fn get_app_settings(&self) -> AppSettings {
+        self.settings.lock().await.settings().clone()
+    }
+
+    /// Persist new settings and apply the runtime ones immediately
+    pub async fn set_app_settings(&self, settings: AppSettings) -> AppSettings {
+        self.switch_display_mode(settings.display_mode).await;
+        Self::apply_settings(&settings);
+        crate::telemetry::apply_settings(&settings.telemetry).await;
+        let auto_connect_enabled = settings.auto_connect && !self.get_app_settings().await.auto_connect;
+        self.settings.lock().await.set_settings(settings.clone());
+        if auto_connect_enabled {
+            self.maybe_auto_connect().await;
+        }
+        settings
+    }
+
+    /// Store or remove the GitHub token of the update checks; true when one is stored afterwards
+    pub async fn set_github_token(&self, token: Option<String>) -> bool {
+        let mut settings = self.settings.lock().await;
+        settings.set_github_token(token);
+        settings.settings().firmware_update.github_token.is_some()
+    }
+
+    pub async fn has_github_token(&self) -> bool {
+        self.settings.lock().await.settings().firmware_update.github_token.is_some()
+    }
+
+    fn apply_settings(settings: &AppSettings) {
+        crate::hid::set_state_sync_interval_ms(settings.update_rate_ms);
+        crate::logging::apply_level_setting(&settings.log_level);
+        crate::raw_state::apply_monitor_settings(&settings.raw_monitor);
+        crate::raw_state::set_display_mode(settings.display_mode);
+    }
+
+    /// Change the display mode and persist it
+    pub async fn set_display_mode(&self, mode: crate::raw_state::DisplayMode) -> crate::raw_state::DisplayMode {
+        let mut settings = self.get_app_settings().await;
+        settings.display_mode = mode;
+        self.set_app_settings(settings).await.display_mode
+    }
+
+    /// Switch the display mode, starting or stopping raw monitoring and the HID reader of the
+    /// connected device to match
+    async fn switch_display_mode(&self, mode: crate::raw_state::DisplayMode) {
+        use crate::raw_state::DisplayMode;
+        let current = crate::raw_state::get_display_mode();
+        if current == mode {
+            return;
+        }
+        log::info!("Changing display mode from {:?} to {:?}", current, mode);
+        crate::raw_state::set_display_mode(mode);
+        let connected = self.get_connected_device_id().await.is_some();
+
+        if matches!(mode, DisplayMode::Raw | DisplayMode::Both) {
+            let app_handle = self.app_handle.lock().await.clone();
+            if let (true, Some(app_handle)) = (connected, app_handle) {
+                if let Err(e) = self.start_raw_state_monitoring(app_handle).await {
+                    log::warn!("Failed to start raw monitoring after mode change: {}", e);
+                }
+            }
+        } else if let Err(e) = self.stop_raw_state_monitoring().await {
+            log::debug!("Stop raw monitoring (mode change) error: {}", e);
+        }
+
+        if matches!(mode, DisplayMode::HID | DisplayMode::Both) {
+            if connected {
+                if let Err(e) = self.connect_hid().await {
+                    log::warn!("Failed to connect HID after mode change: {}", e);
+                }
+            }
+        } else if let Err(e) = self.disconnect_hid().await {
+            log::debug!("Disconnect HID (mode change) error: {}", e);
+        }
+    }
+
+    /// Honor `AppSettings::auto_connect`: connect when nothing is connected and exactly one device is known
+    pub async fn maybe_auto_connect(&self) {
+        if !self.get_app_settings().await.auto_connect || self.get_connected_device_id().await.is_some() {
+            return;
+        }
+        let devices = self.get_devices().await;
+        let candidates: Vec<&Device> = devices.iter()
+            .filter(|d| d.present && matches!(d.connection_state, ConnectionState::Disconnected))
+            .collect();
+        match candidates.as_slice() {
+            [device] => {
+                log::info!("Auto-connecting to {}", device.port_name);
+                if let Err(e) = self.connect_device(&device.id).await {
+                    log::warn!("Auto-connect to {} failed: {}", device.port_name, e);
+                }
+            }
+            [] => {}
+            many => log::info!("Auto-connect skipped: {} devices available", many.len()),
+        }
+    }
+
+    pub async fn get_dev_mode_settings(&self) -> DevModeSettings {
+        self.dev_mode.lock().await.settings().clone()
+    }
+
+    /// Store developer mode settings; zero timeouts are refused and long ones clamped
+    pub async fn set_dev_mode_settings(&self, settings: DevModeSettings) -> Result<DevModeSettings> {
+        let settings = settings.validated().map_err(DeviceError::InvalidConfiguration)?;
+        self.dev_mode.lock().await.set_settings(settings.clone());
+        Ok(settings)
+    }
+
+    /// Last local UF2 flashed to the given device in developer mode
+    pub async fn get_last_flashed_firmware(&self, device_id: &Uuid) -> Result<Option<LastFlashedFirmware>> {
+        let device = self.get_device(device_id).await.ok_or(DeviceError::NotFound)?;
+        let key = super::dev_mode::device_key(&device);
+        Ok(self.dev_mode.lock().await.last_flashed(&key).cloned())
+    }
+
+    /// Re-flash the last local UF2 used for this device
+    pub async fn reflash_last_firmware(&self, device_id: &Uuid) -> Result<DevFlashReport> {
+        let last = self.get_last_flashed_firmware(device_id).await?
+            .ok_or_else(|| DeviceError::InvalidConfiguration("No firmware has been flashed to this device in developer mode".to_string()))?;
+        self.dev_flash_firmware(device_id, last.image_path).await
+    }
+
+    /// Milliseconds since the connected device booted: UPTIME, or the TIME clock on firmware without it
+    pub async fn get_device_uptime(&self) -> Result<u64> {
+        let handle = self.get_unified_serial_handle().await.ok_or(DeviceError::NotConnected)?;
+        let response = handle.send_command(clock_sync::UPTIME_COMMAND.to_string(), clock_sync::uptime_spec()).await?;
+        if let Some(uptime_ms) = clock_sync::parse_uptime_response(&response.lines) {
+            return Ok(uptime_ms);
+        }
+        log::debug!("No UPTIME reply ({:?}), reading the TIME clock", response.lines);
+        let response = handle.send_command(clock_sync::TIME_COMMAND.to_string(), clock_sync::time_spec()).await?;
+        clock_sync::parse_time_response(&response.lines)
+            .map(|device_us| device_us / 1000)
+            .ok_or_else(|| DeviceError::SerialError(crate::serial::SerialError::Unsupported("Firmware reports neither UPTIME nor TIME".to_string())))
+    }
+
+    /// Restart the connected device. Success needs the device to drop off its port; the
+    /// connection is then closed here, so the reboot shows as Disconnected rather than as a lost
+    /// connection. With auto-reconnect on, the device is reconnected once discovery sees it again.
+    pub async fn reboot_device(&self) -> Result<()> {
+        use crate::serial::unified::types::{CommandSpec, ResponseMatcher};
+        let device_id = self.get_connected_device_id().await.ok_or(DeviceError::NotConnected)?;
+        let device = self.get_device(&device_id).await.ok_or(DeviceError::NotFound)?;
+        self.require_capability(Capability::Reboot).await?;
+        let handle = self.get_unified_serial_handle().await.ok_or(DeviceError::NotConnected)?;
+        let spec = CommandSpec { name: REBOOT_COMMAND, timeout: std::time::Duration::from_millis(300), matcher: ResponseMatcher::Custom(|lines| !lines.is_empty()), test_min_duration_ms: None };
+        match handle.send_command(REBOOT_COMMAND.to_string(), spec).await {
+            Ok(resp) if !crate::serial::catalog::probe_response_supported(&resp.lines) => {
+                return Err(DeviceError::SerialError(crate::serial::SerialError::Unsupported(format!("{} is not supported by this firmware", REBOOT_COMMAND))));
+            }
+            // The device usually drops off the bus before answering; confirmed below
+            Ok(_) | Err(crate::serial::SerialError::Timeout) => {}
+            Err(e) => return Err(DeviceError::SerialError(e)),
+        }
+        if !crate::flasher::wait_for_port_gone(&device.port_name, REBOOT_CONFIRM_MS).await {
+            return Err(DeviceError::ProtocolError(format!("Device stayed on {} after {}", device.port_name, REBOOT_COMMAND)));
+        }
+        log::info!("Rebooting device {}", device_id);
+        self.disconnect_device().await?;
+        if self.get_app_settings().await.auto_reconnect {
+            *self.reconnect_target.lock().await = Some(Self::device_key(&device.port_name, device.serial_number.as_deref()));
+        }
+        Ok(())
+    }
+
+    /// Reboot the device into the UF2 bootloader. Uses the serial BOOTLOADER command when the
+    /// device is connected, falling back to the 1200 baud touch when the command is unsupported or
+    /// the device is still on its port afterwards. The connection is released either way.
+    pub async fn reboot_to_bootloader(&self, device_id: &Uuid) -> Result<()> {
+        use crate::serial::unified::types::{CommandSpec, ResponseMatcher};
+        let device = self.get_device(device_id).await.ok_or(DeviceError::NotFound)?;
+        let mut commanded = false;
+        if self.get_connected_device_id().await == Some(*device_id) {
+            let has_command = self.get_firmware_capabilities().await.map_or(true, |c| c.bootloader);
+            if let Some(handle) = self.get_unified_serial_handle().await.filter(|_| has_command) {
+                let spec = CommandSpec { name: crate::flasher::BOOTLOADER_COMMAND, timeout: std::time::Duration::from_millis(300), matcher: ResponseMatcher::Custom(|lines| !lines.is_empty()), test_min_duration_ms: None };
+                match handle.send_command(crate::flasher::BOOTLOADER_COMMAND.to_string(), spec).await {
+                    Ok(resp) => commanded = crate::serial::catalog::probe_response_supported(&resp.lines),
+                    // The device usually drops off the bus before answering; confirmed below
+                    Err(crate::serial::SerialError::Timeout) => commanded = true,
+                    Err(e) => log::debug!("{} command failed, using 1200 baud touch: {}", crate::flasher::BOOTLOADER_COMMAND, e),
+                }
+            }
+            let _ = self.disconnect_device().await;
+        }
+        // Firmware that ignores the command leaves the port in place
+        if commanded && !crate::flasher::wait_for_bootloader(&device.port_name, crate::flasher::BOOTLOADER_CONFIRM_MS).await {
+            log::info!("Device stayed on {} after {}, using 1200 baud touch", device.port_name, crate::flasher::BOOTLOADER_COMMAND);
+            commanded = false;
+        }
+        if !commanded {
+            let port_name = device.port_name.clone();
+            tokio::task::spawn_blocking(move || crate::flasher::bootsel_touch(&port_name))
+                .await
+                .map_err(|e| DeviceError::ProtocolError(format!("Bootloader task failed: {}", e)))??;
+        }
+        Ok(())
+    }
+
+    /// Flash a UF2 image: reboot the device (if given) into BOOTSEL, wait for the UF2 drive and copy
+    /// the image onto it. Returns the drive path. Without a device the board must already be in BOOTSEL.
+    pub async fn flash_firmware<F>(&self, device_id: Option<&Uuid>, image_path: &std::path::Path, drive_timeout_ms: u64, progress: F) -> Result<std::path::PathBuf>
+    where
+        F: Fn(crate::flasher::FlashProgress) + Send + Sync,
+    {
+        use crate::flasher::{FlashProgress, FlashStage};
+        let image = crate::flasher::validate_uf2(image_path)?;
+        let total = image.size_bytes;
+        if let Some(id) = device_id {
+            progress(FlashProgress::stage(FlashStage::RebootingToBootloader, total));
+            self.reboot_to_bootloader(id).await?;
+        }
+        progress(FlashProgress::stage(FlashStage::WaitingForDrive, total));
+        let drive = crate::flasher::wait_for_uf2_drive(drive_timeout_ms).await?;
+        crate::flasher::copy_to_drive_with_progress(&image.path, &drive, |written, total| {
+            progress(FlashProgress::bytes(FlashStage::Copying, written, total));
+        }).await?;
+        progress(FlashProgress::stage(FlashStage::Complete, total));
+        Ok(drive)
+    }
+
+    /// Developer flash cycle: reboot to BOOTSEL, copy the UF2, remember it, reconnect and smoke test
+    pub async fn dev_flash_firmware(&self, device_id: &Uuid, image_path: std::path::PathBuf) -> Result<DevFlashReport> {
+        let settings = self.require_dev_mode().await?;
+        let started = std::time::Instant::now();
+        let image = crate::flasher::validate_uf2(&image_path)?;
+        let device = self.get_device(device_id).await.ok_or(DeviceError::NotFound)?;
+        let key = super::dev_mode::device_key(&device);
+
+        let drive = self.flash_firmware(Some(device_id), &image.path, settings.uf2_drive_timeout_ms, |_| {}).await?;
+        self.dev_mode.lock().await.record_flash(&key, image.path.clone());
+        log::info!("Developer flash of {:?} to {} complete", image.path, key);
+
+        let mut report = DevFlashReport {
+            device_key: key.clone(),
+            image_path: image.path,
+            drive_path: drive,
+            reconnected: false,
+            device_id: None,
+            smoke_test: None,
+            passed: true,
+            duration_ms: 0,
+        };
+
+        if settings.auto_reconnect {
+            match self.wait_for_device_key(&key, settings.reconnect_timeout_ms).await {
+                Some(new_id) => {
+                    report.device_id = Some(new_id.to_string());
+                    match self.connect_device(&new_id).await {
+                        Ok(()) => report.reconnected = true,
+                        Err(e) => log::warn!("Reconnect after flash failed: {}", e),
+                    }
+                }
+                None => log::warn!("Device {} did not reappear within {} ms", key, settings.reconnect_timeout_ms),
+            }
+            report.passed = report.reconnected;
+            if report.reconnected && !settings.smoke_test.is_empty() {
+                let smoke = self.run_smoke_test(&settings.smoke_test).await?;
+                report.passed = smoke.passed;
+                report.smoke_test = Some(smoke);
+            }
+        }
+
+        report.duration_ms = started.elapsed().as_millis() as u64;
+        Ok(report)
+    }
+
+    /// Developer mode settings, or an error while developer mode is off
+    async fn require_dev_mode(&self) -> Result<DevModeSettings> {
+        let settings = self.get_dev_mode_settings().await;
+        if !settings.enabled {
+            return Err(DeviceError::InvalidConfiguration("Developer mode is disabled".to_string()));
+        }
+        Ok(settings)
+    }
+
+    /// Run a command sequence against the connected device, checking each response (developer mode only)
+    pub async fn run_smoke_test(&self, steps: &[SmokeTestStep]) -> Result<SmokeTestReport> {
+        use crate::serial::unified::types::{CommandSpec, ResponseMatcher};
+        self.require_dev_mode().await?;
+        let handle = self.get_unified_serial_handle().await.ok_or(DeviceError::NotConnected)?;
+        let mut results = Vec::with_capacity(steps.len());
+        for step in steps {
+            let started = std::time::Instant::now();
+            let spec = CommandSpec { name: "SMOKE_TEST", timeout: std::time::Duration::from_millis(step.timeout_ms), matcher: ResponseMatcher::Custom(|lines| !lines.is_empty()), test_min_duration_ms: None };
+            let result = match handle.send_command(step.command.clone(), spec).await {
+                Ok(resp) => {
+                    let passed = step.expect.as_deref().map_or(true, |expect| resp.lines.iter().any(|l| l.contains(expect)));
+                    SmokeTestStepResult { command: step.command.clone(), passed, response: resp.lines, error: None, duration_ms: 0 }
+                }
+                Err(e) => SmokeTestStepResult { command: step.command.clone(), passed: false, response: Vec::new(), error: Some(e.to_string()), duration_ms: 0 },
+            };
+            results.push(SmokeTestStepResult { duration_ms: started.elapsed().as_millis() as u64, ..result });
+        }
+        let passed = results.iter().all(|r| r.passed);
+        log::info!("Smoke test {} ({} steps)", if passed { "passed" } else { "FAILED" }, results.len());
+        Ok(SmokeTestReport { passed, steps: results })
+    }
+
+    /// Look for setup problems of a freshly connected device in the background and emit
+    /// `device_setup_required` when there are any
+    fn spawn_setup_check(&self, device_id: Uuid, handle: UnifiedSerialHandle) {
+        let mgr = self.clone();
+        tokio::spawn(async move {
+            let reasons = mgr.detect_setup_issues(&handle).await;
+            if reasons.is_empty() || mgr.get_connected_device_id().await != Some(device_id) {
+                return;
+            }
+            log::info!("Device {} needs setup: {:?}", device_id, reasons);
+            let payload = DeviceSetupRequired { device_id, firmware_version: mgr.get_device_firmware_version().await, reasons };
+            if let Some(app) = &*mgr.app_handle.lock().await {
+                if let Err(e) = app.emit(setup_check::DEVICE_SETUP_REQUIRED_EVENT, &payload) {
+                    log::warn!("Failed to emit {}: {}", setup_check::DEVICE_SETUP_REQUIRED_EVENT, e);
+                }
+            }
+        });
+    }
+
+    /// Missing or default config, schema version mismatch and empty input maps
+    async fn detect_setup_issues(&self, handle: &UnifiedSerialHandle) -> Vec<SetupIssue> {
+        use crate::serial::unified::types::{CommandSpec, ResponseMatcher};
+        let mut issues = Vec::new();
+        if let Ok(files) = self.list_device_files().await {
+            if !setup_check::lists_config_file(&files) {
+                // Firmware necessarily runs its defaults; no point reading the file
+                return vec![SetupIssue::MissingConfig];
+            }
+        }
+        let spec = CommandSpec { name: "STATUS", timeout: std::time::Duration::from_millis(1200), matcher: ResponseMatcher::Contains("Config Status"), test_min_duration_ms: None };
+        match handle.send_command("STATUS".to_string(), spec).await {
+            Ok(resp) if setup_check::status_uses_defaults(&resp.lines.join("\n")) => issues.push(SetupIssue::DefaultConfig),
+            Ok(_) => {}
+            Err(e) => log::debug!("Setup check: STATUS failed: {}", e),
+        }
+        match self.read_config_binary().await {
+            Ok(data) => issues.extend(setup_check::config_issues(&data)),
+            Err(DeviceError::SerialError(crate::serial::SerialError::FileNotFound(_))) => return vec![SetupIssue::MissingConfig],
+            Err(e) => log::debug!("Setup check: config read failed: {}", e),
+        }
+        issues
+    }
+
+    /// Scripted health check: status, config read and CRC, HID mapping, a sample of the monitor
+    /// stream, storage and link error counters. Every check runs even when an earlier one fails.
+    pub async fn run_device_self_test(&self, monitor_secs: u64) -> Result<SelfTestReport> {
+        use crate::serial::SerialError;
+        let handle = self.get_unified_serial_handle().await.ok_or(DeviceError::NotConnected)?;
+        let monitor_secs = monitor_secs.clamp(1, self_test::MAX_MONITOR_SAMPLE_SECS);
+        let started_at = chrono::Utc::now();
+        let started = std::time::Instant::now();
+        let metrics_before = handle.metrics_snapshot();
+        let mut checks = Vec::new();
+
+        let t = std::time::Instant::now();
+        let status = self.execute_with_protocol(|protocol| Box::pin(async move {
+            protocol.get_device_status().await.map_err(DeviceError::SerialError)
+        })).await;
+        checks.push(match status {
+            Ok(status) => SelfTestCheck::new("status", CheckStatus::Pass, format!("{} firmware {}", status.device_name, status.firmware_version)),
+            Err(e) => SelfTestCheck::new("status", CheckStatus::Fail, e.to_string()),
+        }.timed(t));
+
+        let t = std::time::Instant::now();
+        let config = self.read_config_binary().await;
+        checks.push(match &config {
+            Ok(data) => SelfTestCheck::new("config_read", CheckStatus::Pass, format!("Read {} bytes", data.len())),
+            Err(e) => SelfTestCheck::new("config_read", CheckStatus::Fail, e.to_string()),
+        }.timed(t));
+        checks.push(match &config {
+            Ok(data) => match BinaryConfig::from_bytes(data) {
+                Ok(parsed) => SelfTestCheck::new("config_crc", CheckStatus::Pass, format!("Checksum 0x{:08X} valid", { parsed.stored_config.header.checksum })),
+                Err(e) => SelfTestCheck::new("config_crc", CheckStatus::Fail, e),
+            },
+            Err(_) => SelfTestCheck::new("config_crc", CheckStatus::Skipped, "Config could not be read"),
+        });
+
+        let hid_connected = self.hid_reader.lock().await.is_connected().await;
+        checks.push(match (hid_connected, self.hid_button_mapping().await) {
+            (false, _) => SelfTestCheck::new("hid_mapping", CheckStatus::Warn, "HID interface not connected"),
+            (true, Some(mapping)) => SelfTestCheck::new("hid_mapping", CheckStatus::Pass, format!("{} buttons mapped", mapping.len())),
+            (true, None) => SelfTestCheck::new("hid_mapping", CheckStatus::Warn, "No mapping; buttons decoded heuristically"),
+        });
+
+        let t = std::time::Instant::now();
+        let monitor = if self.require_capability(Capability::RawMonitor).await.is_err() {
+            SelfTestCheck::new("monitor_stream", CheckStatus::Skipped, "Firmware has no raw monitor")
+        } else {
+            let mut events = handle.subscribe_events();
+            let already_running = self.is_raw_state_monitoring().await;
+            let start = if already_running { Ok(String::new()) } else { self.send_raw_monitor_command("START_RAW_MONITOR").await };
+            match start {
+                Err(e) => SelfTestCheck::new("monitor_stream", CheckStatus::Fail, format!("START_RAW_MONITOR failed: {}", e)),
+                Ok(_) => {
+                    let mut count = 0u64;
+                    let deadline = tokio::time::Instant::now() + std::time::Duration::from_secs(monitor_secs);
+                    loop {
+                        match tokio::time::timeout_at(deadline, events.recv()).await {
+                            Ok(Ok(_)) => count += 1,
+                            Ok(Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped))) => count += skipped,
+                            Ok(Err(tokio::sync::broadcast::error::RecvError::Closed)) | Err(_) => break,
+                        }
+                    }
+                    if !already_running {
+                        let _ = self.send_raw_monitor_command("STOP_RAW_MONITOR").await;
+                    }
+                    self_test::monitor_check(count, monitor_secs)
+                }
+            }
+        };
+        checks.push(monitor.timed(t));
+
+        let t = std::time::Instant::now();
+        checks.push(match self.get_device_storage_info().await {
+            Ok(info) => self_test::storage_check(&info),
+            Err(DeviceError::SerialError(SerialError::Unsupported(_))) => SelfTestCheck::new("storage", CheckStatus::Skipped, "Firmware does not report storage"),
+            Err(e) => SelfTestCheck::new("storage", CheckStatus::Fail, e.to_string()),
+        }.timed(t));
+
+        checks.push(self_test::serial_link_check(&metrics_before, &handle.metrics_snapshot()));
+        checks.push(if hid_connected {
+            self_test::hid_link_check(&self.hid_link_stats().await)
+        } else {
+            SelfTestCheck::new("hid_link", CheckStatus::Skipped, "HID interface not connected")
+        });
+
+        let report = SelfTestReport::new(checks, started_at, started.elapsed().as_millis() as u64);
+        log::info!("Device self-test finished: {:?} ({} checks)", report.overall, report.checks.len());
+        Ok(report)
+    }
+
+    /// Developer console: send one arbitrary command line and collect every reply line until the
+    /// device goes quiet. Goes through the unified reader so it never competes for the port.
+    pub async fn send_custom_serial_command(&self, command: &str, timeout_ms: Option<u64>) -> Result<ConsoleResponse> {
+        use crate::serial::unified::types::{CommandSpec, FinishReason, ResponseMatcher, CONSOLE_COMMAND};
+        use super::dev_mode::{validate_console_command, CONSOLE_DEFAULT_TIMEOUT_MS, CONSOLE_MAX_TIMEOUT_MS, CONSOLE_QUIET_MS};
+        self.require_dev_mode().await?;
+        let command = validate_console_command(command).map_err(DeviceError::InvalidConfiguration)?;
+        let handle = self.get_unified_serial_handle().await.ok_or(DeviceError::NotConnected)?;
+        let timeout_ms = timeout_ms.unwrap_or(CONSOLE_DEFAULT_TIMEOUT_MS).clamp(CONSOLE_QUIET_MS, CONSOLE_MAX_TIMEOUT_MS);
+        let spec = CommandSpec { name: CONSOLE_COMMAND, timeout: std::time::Duration::from_millis(timeout_ms), matcher: ResponseMatcher::Settled(std::time::Duration::from_millis(CONSOLE_QUIET_MS)), test_min_duration_ms: None };
+        let started = std::time::Instant::now();
+        let (lines, timed_out) = match handle.send_command(command.to_string(), spec).await {
+            Ok(resp) => {
+                let timed_out = matches!(resp.finished_reason, FinishReason::Timeout);
+                (resp.lines, timed_out)
+            }
+            // Expired while still queued behind other commands
+            Err(crate::serial::SerialError::Timeout) => (Vec::new(), true),
+            Err(e) => return Err(e.into()),
+        };
+        log::info!("Console command '{}' returned {} lines", command, lines.len());
+        Ok(ConsoleResponse { command: command.to_string(), lines, timed_out, duration_ms: started.elapsed().as_millis() as u64 })
+    }
+
+    /// Poll discovery until a device with the given key enumerates again
+    async fn wait_for_device_key(&self, key: &str, timeout_ms: u64) -> Option<Uuid> {
+        let start = std::time::Instant::now();
+        while start.elapsed().as_millis() < timeout_ms as u128 {
+            tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+            if let Ok(devices) = self.discover_devices().await {
+                if let Some(d) = devices.iter().find(|d| super::dev_mode::device_key(d) == key) {
+                    return Some(d.id);
+                }
+            }
+        }
+        None
+    }
+}
+
 impl Default for DeviceManager {
     fn default() -> Self {
         Self::new()
     }
 }
 impl DeviceManager {
-    /// Explicit asynchronous shutdown hook.
+    /// Stop background work and release the device on app exit (see `super::lifecycle`).
     ///
-    /// This must be called before dropping the last `Arc<DeviceManager>`.
-    /// It performs cleanup that previously occurred in `Drop` but required
-    /// awaiting async tasks (stopping the port monitor). Performing that work
-    /// inside `Drop` forced creation of a new Tokio runtime which could panic
-    /// if `Drop` executed on an existing runtime worker thread ("Cannot start a runtime from within a runtime").
-    ///
-    /// Call this during application shutdown (e.g. in a Tauri on_exit handler).
+    /// Called from Tauri's exit event, before the last `Arc<DeviceManager>` is dropped. The
+    /// cleanup awaits async tasks, so it cannot live in `Drop`: that would need a new Tokio
+    /// runtime, which panics when `Drop` runs on a runtime worker thread. Runs once; later calls
+    /// return immediately.
     pub async fn shutdown(&self) {
+        if !self.lifecycle.begin_shutdown() {
+            return;
+        }
+        log::info!("Shutting down device manager");
+        let started = std::time::Instant::now();
+        match tokio::time::timeout(SHUTDOWN_DEADLINE, self.shutdown_steps()).await {
+            Ok(()) => log::info!("Device manager shut down in {} ms", started.elapsed().as_millis()),
+            Err(_) => log::warn!("Shutdown did not finish within {:?}; exiting anyway", SHUTDOWN_DEADLINE),
+        }
+    }
+
+    async fn shutdown_steps(&self) {
+        // Nothing may start a new connection from here on
         self.stop_port_monitor().await;
+        self.reconnect_target.lock().await.take();
+        let aborted = self.lifecycle.abort_tasks().await;
+        log::debug!("Stopped {} background tasks", aborted);
+
+        crate::interop::get_virtual_bridge().stop();
+        if crate::telemetry::get_telemetry_server().is_running() {
+            crate::telemetry::get_telemetry_server().stop();
+        }
+        let recorder = crate::recording::get_recorder();
+        if recorder.is_recording() {
+            if let Err(e) = recorder.stop() {
+                log::warn!("Failed to finish input recording on exit: {}", e);
+            }
+        }
+
+        // Raw monitoring is stopped whatever the display mode so the firmware stops streaming
+        if self.raw_monitoring_active.load(Ordering::Relaxed) {
+            let _ = self.stop_raw_state_monitoring().await;
+        }
+        if let Some(device_id) = self.teardown_connection().await {
+            log::info!("Closed connection to device {} on exit", device_id);
+        }
+        // Joins the HID reader thread; teardown only does this in HID display modes
+        let _ = self.disconnect_hid().await;
+
+        // A backup being archived holds the store until its file is written
+        drop(self.config_backups.lock().await);
     }
+
 }
\ No newline at end of file
@@ -1,9 +1,27 @@
+pub mod aliases;
+pub mod clock_sync;
+pub mod config_backups;
+pub mod config_preview;
+pub mod dev_mode;
+pub mod heartbeat;
+pub mod identify_cache;
+pub mod known_devices;
+pub mod lifecycle;
+pub mod list_events;
+pub mod macros;
 pub mod manager;
 pub mod models;
+pub mod port_filter;
 pub mod port_monitor;
+pub mod profile_store;
+pub mod quarantine;
+pub mod self_test;
+pub mod settings;
+pub mod setup_check;
 
 pub use manager::DeviceManager;
 pub use models::*;
+pub use profile_store::ProfileStore;
 
 
 #[derive(Debug, thiserror::Error)]
@@ -31,6 +49,9 @@ pub enum DeviceError {
     
     #[error("Protocol error: {0}")]
     ProtocolError(String),
+
+    #[error("Flash error: {0}")]
+    FlashError(#[from] crate::flasher::FlashError),
 }
 
 pub type Result<T> = std::result::Result<T, DeviceError>;
\ No newline at end of file
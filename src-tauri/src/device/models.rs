@@ -22,11 +22,24 @@ pub struct Device {
     pub serial_number: Option<String>,
     pub manufacturer: Option<String>,
     pub product: Option<String>,
+    /// User-chosen name, looked up by serial number
+    #[serde(default)]
+    pub alias: Option<String>,
+    /// Plugged in; false for a known device listed from the registry
+    #[serde(default = "default_present")]
+    pub present: bool,
     pub connection_state: ConnectionState,
     pub device_status: Option<DeviceStatus>,
+    /// Feature matrix negotiated on the last connection
+    #[serde(default)]
+    pub capabilities: Option<crate::serial::capabilities::FirmwareCapabilities>,
     pub last_seen: DateTime<Utc>,
 }
 
+fn default_present() -> bool {
+    true
+}
+
 impl Device {
     pub fn new(port_name: String) -> Self {
         Self {
@@ -35,8 +48,11 @@ impl Device {
             serial_number: None,
             manufacturer: None,
             product: None,
+            alias: None,
+            present: true,
             connection_state: ConnectionState::Disconnected,
             device_status: None,
+            capabilities: None,
             last_seen: Utc::now(),
         }
     }
@@ -48,8 +64,11 @@ impl Device {
             serial_number: info.serial_number.clone(),
             manufacturer: info.manufacturer.clone(),
             product: info.product.clone(),
+            alias: None,
+            present: true,
             connection_state: ConnectionState::Disconnected,
             device_status: None,
+            capabilities: None,
             last_seen: Utc::now(),
         }
     }
@@ -74,6 +93,9 @@ impl Device {
 pub struct ProfileManager {
     pub profiles: Vec<ProfileConfig>,
     pub active_profile_id: Option<String>,
+    /// Macro definitions, stored alongside the profiles
+    #[serde(default)]
+    pub macros: Vec<super::macros::MacroDefinition>,
 }
 
 impl ProfileManager {
@@ -81,6 +103,7 @@ impl ProfileManager {
         Self {
             profiles: Vec::new(),
             active_profile_id: None,
+            macros: Vec::new(),
         }
     }
 
@@ -125,6 +148,24 @@ impl ProfileManager {
             .and_then(|id| self.get_profile(id))
     }
 
+    pub fn get_macro(&self, macro_id: &str) -> Option<&super::macros::MacroDefinition> {
+        self.macros.iter().find(|m| m.id == macro_id)
+    }
+
+    /// Insert or replace a macro by id
+    pub fn upsert_macro(&mut self, definition: super::macros::MacroDefinition) {
+        match self.macros.iter_mut().find(|m| m.id == definition.id) {
+            Some(existing) => *existing = definition,
+            None => self.macros.push(definition),
+        }
+    }
+
+    pub fn remove_macro(&mut self, macro_id: &str) -> bool {
+        let before = self.macros.len();
+        self.macros.retain(|m| m.id != macro_id);
+        self.macros.len() != before
+    }
+
     pub fn create_default_profile(device_status: &DeviceStatus) -> ProfileConfig {
         let now = Utc::now();
         
@@ -174,52 +215,143 @@ impl Default for ProfileManager {
 
 /// Application settings
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct AppSettings {
+    /// Connect automatically when exactly one device is discovered and none is connected
     pub auto_connect: bool,
+    /// Reconnect when a device that dropped while connected is plugged back in
+    pub auto_reconnect: bool,
     pub auto_save: bool,
     pub log_level: String,
     pub theme: String, // "light", "dark", "system"
     pub language: String,
+    /// Interval of the periodic HID `button-state-sync` event
     pub update_rate_ms: u64,
     pub firmware_update: FirmwareUpdateSettings,
+    pub telemetry: TelemetrySettings,
+    pub raw_monitor: crate::raw_state::RawMonitorSettings,
+    pub discovery: DiscoverySettings,
+    /// Input display source, restored at startup; HID like the frontend default it replaced
+    pub display_mode: crate::raw_state::DisplayMode,
+}
+
+/// Which serial ports discovery may open (see `super::port_filter`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DiscoverySettings {
+    /// Only probe USB ports with a JoyCore VID (or an id in `extra_usb_ids`); ports without USB
+    /// metadata are still probed
+    pub usb_filter: bool,
+    /// Additional `VID:PID` or `VID` ids in hex, for devices with a custom USB descriptor
+    pub extra_usb_ids: Vec<String>,
+    /// Ports never opened by the app (`COM5`, `/dev/ttyACM1`)
+    pub ignored_ports: Vec<String>,
+}
+
+/// Local WebSocket telemetry server (see `crate::telemetry`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TelemetrySettings {
+    /// Start the server with the app; it only ever listens on 127.0.0.1
+    pub enabled: bool,
+    pub port: u16,
+    /// Browser origins (e.g. `http://localhost:3000`) allowed to connect; web pages from any
+    /// other origin are refused. Native clients send no origin and are always accepted.
+    pub allowed_origins: Vec<String>,
 }
 
 /// Firmware update settings
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct FirmwareUpdateSettings {
     pub auto_check: bool,
     pub check_interval_hours: u64,
     pub repo_owner: String,
     pub repo_name: String,
     pub download_directory: String,
+    /// Refuse to flash images without a valid signature unless the flash explicitly allows it.
+    /// Only takes effect with a public key; defaults to on when one is bundled.
     pub verify_signatures: bool,
     pub last_check: Option<DateTime<Utc>>,
+    /// Release channel used by update checks
+    pub channel: crate::update::UpdateChannel,
+    /// HTTP(S) proxy for GitHub requests
+    pub proxy_url: Option<String>,
+    /// GitHub token for authenticated API requests (higher rate limit). Kept in its own
+    /// owner-only file (see `super::settings`); never written to the settings file or returned
+    /// to the frontend, and only read from settings files of older versions.
+    #[serde(skip_serializing)]
+    pub github_token: Option<String>,
+    /// Minisign public key for firmware signatures; the bundled key is used when unset
+    pub signature_public_key: Option<String>,
 }
 
 impl Default for AppSettings {
     fn default() -> Self {
         Self {
-            auto_connect: true,
+            // Off and the former fixed sync interval, so upgrading changes nothing until opted in
+            auto_connect: false,
+            auto_reconnect: true,
             auto_save: true,
             log_level: "info".to_string(),
             theme: "system".to_string(),
             language: "en".to_string(),
-            update_rate_ms: 100,
+            update_rate_ms: crate::hid::DEFAULT_STATE_SYNC_INTERVAL_MS,
             firmware_update: FirmwareUpdateSettings::default(),
+            telemetry: TelemetrySettings::default(),
+            raw_monitor: crate::raw_state::RawMonitorSettings::default(),
+            discovery: DiscoverySettings::default(),
+            display_mode: crate::raw_state::DisplayMode::HID,
         }
     }
 }
 
+impl Default for DiscoverySettings {
+    fn default() -> Self {
+        Self { usb_filter: true, extra_usb_ids: Vec::new(), ignored_ports: Vec::new() }
+    }
+}
+
+impl Default for TelemetrySettings {
+    fn default() -> Self {
+        Self { enabled: false, port: crate::telemetry::DEFAULT_TELEMETRY_PORT, allowed_origins: Vec::new() }
+    }
+}
+
 impl Default for FirmwareUpdateSettings {
     fn default() -> Self {
         Self {
-            auto_check: true,
+            // Background checks contact GitHub, so they are opt-in
+            auto_check: false,
             check_interval_hours: 24,
             repo_owner: "gingerskull".to_string(),
             repo_name: "JoyCore-FW".to_string(),
             download_directory: "downloads".to_string(),
-            verify_signatures: true,
+            verify_signatures: crate::update::signature::BUNDLED_PUBLIC_KEY.is_some(),
             last_check: None,
+            channel: crate::update::UpdateChannel::default(),
+            proxy_url: None,
+            github_token: None,
+            signature_public_key: None,
         }
     }
+}
+
+impl FirmwareUpdateSettings {
+    pub fn client_options(&self) -> crate::update::UpdateClientOptions {
+        crate::update::UpdateClientOptions {
+            proxy_url: self.proxy_url.clone(),
+            github_token: self.github_token.clone(),
+        }
+    }
+
+    /// Key firmware signatures are checked against
+    pub fn effective_signature_key(&self) -> Option<String> {
+        crate::update::signature::effective_public_key(self.signature_public_key.as_deref())
+    }
+
+    /// Key flashes are verified against, if they are verified at all
+    pub fn flash_verification_key(&self) -> Option<String> {
+        crate::update::signature::flash_verification_key(self.verify_signatures, self.signature_public_key.as_deref())
+    }
 }
\ No newline at end of file
@@ -0,0 +1,110 @@
+use std::collections::HashSet;
+use serialport::{SerialPortInfo, SerialPortType};
+use super::DiscoverySettings;
+
+/// The vendor id the HID reader detects JoyCore devices by; every descriptor preset uses it
+pub use crate::hid::JOYCORE_VID as JOYCORE_USB_VID;
+
+/// Decides which serial ports discovery may open, from [`DiscoverySettings`]
+#[derive(Debug, Clone)]
+pub struct PortFilter {
+    usb_filter: bool,
+    /// (VID, PID); `None` accepts any PID of the vendor
+    usb_ids: Vec<(u16, Option<u16>)>,
+    ignored: HashSet<String>,
+}
+
+/// Comparable port name: the last path component, lowercased, so `COM5`/`com5` and
+/// `/dev/ttyACM0`/`ttyACM0` (as reported by the port monitors) match
+pub fn port_key(port_name: &str) -> String {
+    port_name.rsplit('/').next().unwrap_or(port_name).trim().to_ascii_lowercase()
+}
+
+/// `VID:PID` or `VID` in hex, with or without `0x`
+pub fn parse_usb_id(value: &str) -> Result<(u16, Option<u16>), String> {
+    let hex = |part: &str| {
+        let digits = part.trim();
+        let digits = digits.strip_prefix("0x").or_else(|| digits.strip_prefix("0X")).unwrap_or(digits);
+        u16::from_str_radix(digits, 16).map_err(|_| format!("Invalid USB id '{}': expected hex VID:PID", value))
+    };
+    match value.split_once(':') {
+        Some((vid, pid)) => Ok((hex(vid)?, Some(hex(pid)?))),
+        None => Ok((hex(value)?, None)),
+    }
+}
+
+impl PortFilter {
+    /// Invalid `extra_usb_ids` are skipped with a warning
+    pub fn new(settings: &DiscoverySettings) -> Self {
+        let mut usb_ids = vec![(JOYCORE_USB_VID, None)];
+        for id in &settings.extra_usb_ids {
+            match parse_usb_id(id) {
+                Ok(id) => usb_ids.push(id),
+                Err(e) => log::warn!("{}", e),
+            }
+        }
+        Self {
+            usb_filter: settings.usb_filter,
+            usb_ids,
+            ignored: settings.ignored_ports.iter().map(|p| port_key(p)).collect(),
+        }
+    }
+
+    pub fn is_ignored(&self, port_name: &str) -> bool {
+        self.ignored.contains(&port_key(port_name))
+    }
+
+    /// Whether discovery may open `port` to IDENTIFY it
+    pub fn should_probe(&self, port: &SerialPortInfo) -> bool {
+        if self.is_ignored(&port.port_name) {
+            return false;
+        }
+        match &port.port_type {
+            SerialPortType::UsbPort(usb) if self.usb_filter => {
+                self.usb_ids.iter().any(|&(vid, pid)| vid == usb.vid && pid.unwrap_or(usb.pid) == usb.pid)
+            }
+            // Built-in and Bluetooth ports are never JoyCore devices
+            SerialPortType::PciPort | SerialPortType::BluetoothPort => !self.usb_filter,
+            _ => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serialport::UsbPortInfo;
+
+    fn usb(port_name: &str, vid: u16, pid: u16) -> SerialPortInfo {
+        SerialPortInfo {
+            port_name: port_name.to_string(),
+            port_type: SerialPortType::UsbPort(UsbPortInfo { vid, pid, serial_number: None, manufacturer: None, product: None }),
+        }
+    }
+
+    #[test]
+    fn probes_only_joycore_usb_ids() {
+        let settings = DiscoverySettings { extra_usb_ids: vec!["0x1209:0x0001".into(), "nope".into()], ..Default::default() };
+        let filter = PortFilter::new(&settings);
+        assert!(filter.should_probe(&usb("COM3", 0x2E8A, 0xA02F)));
+        assert!(filter.should_probe(&usb("COM4", 0x2E8A, 0xA030)), "any pid of the JoyCore vendor");
+        assert!(filter.should_probe(&usb("COM5", 0x1209, 0x0001)));
+        assert!(!filter.should_probe(&usb("COM6", 0x2341, 0x0043)), "Arduino Uno");
+        assert!(!filter.should_probe(&SerialPortInfo { port_name: "/dev/ttyS0".into(), port_type: SerialPortType::PciPort }));
+        assert!(filter.should_probe(&SerialPortInfo { port_name: "MOCK".into(), port_type: SerialPortType::Unknown }));
+
+        let open = PortFilter::new(&DiscoverySettings { usb_filter: false, ..Default::default() });
+        assert!(open.should_probe(&usb("COM6", 0x2341, 0x0043)));
+        assert_eq!(parse_usb_id("2e8a"), Ok((0x2E8A, None)));
+        assert!(parse_usb_id("2E8A:xyz").is_err());
+    }
+
+    #[test]
+    fn ignores_listed_ports() {
+        let settings = DiscoverySettings { ignored_ports: vec!["com3".into(), "/dev/ttyACM1".into()], ..Default::default() };
+        let filter = PortFilter::new(&settings);
+        assert!(!filter.should_probe(&usb("COM3", 0x2E8A, 0xA02F)));
+        assert!(filter.is_ignored("ttyACM1"), "monitor events carry the bare device name");
+        assert!(!filter.is_ignored("/dev/ttyACM0"));
+    }
+}
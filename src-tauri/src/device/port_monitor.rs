@@ -52,6 +52,9 @@ impl PortEventDebouncer {
     }
 }
 
+mod polling;
+pub use polling::PollingPortMonitor;
+
 // Platform-specific implementations
 #[cfg(target_os = "windows")]
 mod windows;
@@ -87,35 +90,7 @@ pub fn create_port_monitor() -> Box<dyn PortMonitor> {
     
     #[cfg(not(any(target_os = "windows", target_os = "linux", target_os = "macos")))]
     {
-        // Fallback for unsupported platforms
-        Box::new(NoOpPortMonitor::new())
-    }
-}
-
-/// No-op implementation for unsupported platforms
-#[cfg(not(any(target_os = "windows", target_os = "linux", target_os = "macos")))]
-struct NoOpPortMonitor;
-
-#[cfg(not(any(target_os = "windows", target_os = "linux", target_os = "macos")))]
-impl NoOpPortMonitor {
-    fn new() -> Self {
-        log::warn!("Port monitoring not supported on this platform");
-        Self
+        // No native notification API: poll
+        Box::new(PollingPortMonitor::new())
     }
 }
-
-#[cfg(not(any(target_os = "windows", target_os = "linux", target_os = "macos")))]
-#[async_trait::async_trait]
-impl PortMonitor for NoOpPortMonitor {
-    async fn start(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        Ok(())
-    }
-    
-    async fn stop(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        Ok(())
-    }
-    
-    fn get_receiver(&mut self) -> Option<mpsc::Receiver<PortEvent>> {
-        None
-    }
-}
\ No newline at end of file
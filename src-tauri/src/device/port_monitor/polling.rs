@@ -0,0 +1,175 @@
+//! Port monitor that polls `serialport::available_ports()`.
+//!
+//! Used where the native notification API is missing or fails to start. It polls every
+//! [`FAST_INTERVAL`] for [`FAST_PERIOD`] after a change (a replug or the second port of a
+//! composite device follows closely), then backs off by doubling up to [`SLOW_INTERVAL`].
+use super::{PortEvent, PortMonitor};
+use async_trait::async_trait;
+use std::collections::BTreeSet;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+
+pub const FAST_INTERVAL: Duration = Duration::from_millis(250);
+pub const SLOW_INTERVAL: Duration = Duration::from_secs(3);
+/// How long polling stays fast after the last change
+pub const FAST_PERIOD: Duration = Duration::from_secs(10);
+
+/// Poll interval that is fast after changes and backs off while nothing happens
+#[derive(Debug, Clone)]
+pub struct AdaptiveInterval {
+    current: Duration,
+    last_change: Option<Instant>,
+}
+
+impl AdaptiveInterval {
+    /// Starts slow: nothing has changed yet
+    pub fn new() -> Self {
+        Self { current: SLOW_INTERVAL, last_change: None }
+    }
+
+    /// Interval until the next poll, after a poll at `now` that did or did not see a change
+    pub fn next(&mut self, changed: bool, now: Instant) -> Duration {
+        if changed {
+            self.last_change = Some(now);
+        }
+        let recent = self.last_change.is_some_and(|t| now.saturating_duration_since(t) < FAST_PERIOD);
+        self.current = if recent { FAST_INTERVAL } else { (self.current * 2).min(SLOW_INTERVAL) };
+        self.current
+    }
+}
+
+impl Default for AdaptiveInterval {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn current_ports() -> Option<BTreeSet<String>> {
+    match serialport::available_ports() {
+        Ok(ports) => Some(ports.into_iter().map(|p| p.port_name).collect()),
+        Err(e) => {
+            log::debug!("Port poll failed: {}", e);
+            None
+        }
+    }
+}
+
+/// Events turning port set `old` into `new`, removals first
+fn diff_ports(old: &BTreeSet<String>, new: &BTreeSet<String>) -> Vec<PortEvent> {
+    old.difference(new).cloned().map(PortEvent::PortRemoved)
+        .chain(new.difference(old).cloned().map(PortEvent::PortAdded))
+        .collect()
+}
+
+pub struct PollingPortMonitor {
+    tx: Option<mpsc::Sender<PortEvent>>,
+    rx: Option<mpsc::Receiver<PortEvent>>,
+    stop_tx: Option<mpsc::Sender<()>>,
+    thread_handle: Option<tokio::task::JoinHandle<()>>,
+}
+
+impl PollingPortMonitor {
+    pub fn new() -> Self {
+        let (tx, rx) = mpsc::channel(100);
+
+        Self {
+            tx: Some(tx),
+            rx: Some(rx),
+            stop_tx: None,
+            thread_handle: None,
+        }
+    }
+}
+
+impl Default for PollingPortMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl PortMonitor for PollingPortMonitor {
+    async fn start(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let tx = self.tx.take().ok_or("Already started")?;
+        let (stop_tx, mut stop_rx) = mpsc::channel(1);
+        self.stop_tx = Some(stop_tx);
+
+        let handle = tokio::spawn(async move {
+            log::info!("Polling port monitor started");
+            let mut known = current_ports().unwrap_or_default();
+            let mut interval = AdaptiveInterval::new();
+            let mut wait = SLOW_INTERVAL;
+
+            loop {
+                tokio::select! {
+                    _ = stop_rx.recv() => {
+                        log::info!("Polling port monitor stopping");
+                        break;
+                    }
+                    _ = tokio::time::sleep(wait) => {
+                        let events = match current_ports() {
+                            Some(ports) => {
+                                let events = diff_ports(&known, &ports);
+                                known = ports;
+                                events
+                            }
+                            None => Vec::new(),
+                        };
+                        wait = interval.next(!events.is_empty(), Instant::now());
+                        for event in events {
+                            if tx.send(event).await.is_err() {
+                                return;
+                            }
+                        }
+                    }
+                }
+            }
+        });
+
+        self.thread_handle = Some(handle);
+        Ok(())
+    }
+
+    async fn stop(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        if let Some(stop_tx) = self.stop_tx.take() {
+            let _ = stop_tx.send(()).await;
+        }
+
+        if let Some(handle) = self.thread_handle.take() {
+            let _ = handle.await;
+        }
+
+        Ok(())
+    }
+
+    fn get_receiver(&mut self) -> Option<mpsc::Receiver<PortEvent>> {
+        self.rx.take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn speeds_up_after_changes_and_backs_off_when_idle() {
+        let start = Instant::now();
+        let mut interval = AdaptiveInterval::new();
+        assert_eq!(interval.next(false, start), SLOW_INTERVAL);
+        assert_eq!(interval.next(true, start), FAST_INTERVAL);
+        assert_eq!(interval.next(false, start + FAST_PERIOD / 2), FAST_INTERVAL);
+        let idle = start + FAST_PERIOD;
+        assert_eq!(interval.next(false, idle), FAST_INTERVAL * 2);
+        assert_eq!(interval.next(false, idle), FAST_INTERVAL * 4);
+        for _ in 0..8 { interval.next(false, idle); }
+        assert_eq!(interval.next(false, idle), SLOW_INTERVAL);
+    }
+
+    #[test]
+    fn diffs_port_sets() {
+        let set = |names: &[&str]| names.iter().map(|n| n.to_string()).collect::<BTreeSet<_>>();
+        let events = diff_ports(&set(&["COM3", "COM4"]), &set(&["COM4", "COM7"]));
+        assert!(matches!(&events[..], [PortEvent::PortRemoved(a), PortEvent::PortAdded(b)] if a == "COM3" && b == "COM7"));
+        assert!(diff_ports(&set(&["COM3"]), &set(&["COM3"])).is_empty());
+    }
+}
@@ -1,24 +1,173 @@
+//! Windows port monitor on device interface notifications.
+//!
+//! `CM_Register_Notification` calls back (on a system thread pool thread, no window or message
+//! loop needed) whenever a COM port interface arrives or is removed, USB CDC ports included. The
+//! callback only forwards the interface's symbolic link; the event loop turns it into the port
+//! name (`COM7`) from the `PortName` value of the device's hardware registry key. Every
+//! notification becomes exactly one event, so a quick unplug/replug yields a removal and an
+//! arrival instead of being collapsed into nothing.
 use super::{PortEvent, PortMonitor};
 use async_trait::async_trait;
-use tokio::sync::{mpsc, broadcast};
-use std::time::Duration;
+use std::collections::HashMap;
+use std::ffi::c_void;
+use tokio::sync::mpsc;
+use windows::core::{GUID, PCWSTR};
+use windows::Win32::Devices::DeviceAndDriverInstallation::{
+    CM_Locate_DevNodeW, CM_Open_DevNode_Key, CM_Register_Notification, CM_Unregister_Notification,
+    CM_LOCATE_DEVNODE_PHANTOM, CM_NOTIFY_ACTION, CM_NOTIFY_ACTION_DEVICEINTERFACEARRIVAL,
+    CM_NOTIFY_ACTION_DEVICEINTERFACEREMOVAL, CM_NOTIFY_EVENT_DATA, CM_NOTIFY_FILTER, CM_NOTIFY_FILTER_0,
+    CM_NOTIFY_FILTER_0_2, CM_NOTIFY_FILTER_TYPE_DEVICEINTERFACE, CM_REGISTRY_HARDWARE, CR_SUCCESS,
+    HCMNOTIFICATION, RegDisposition_OpenExisting,
+};
+use windows::Win32::System::Registry::{RegCloseKey, RegQueryValueExW, HKEY, KEY_READ, REG_SZ, REG_VALUE_TYPE};
+
+/// GUID_DEVINTERFACE_COMPORT
+const GUID_DEVINTERFACE_COMPORT: GUID = GUID::from_u128(0x86e0d1e0_8089_11d0_9ce4_08003e301f73);
+const ERROR_SUCCESS: u32 = 0;
+
+#[derive(Debug)]
+enum Notification {
+    Arrival(String),
+    Removal(String),
+}
+
+type NotificationSender = mpsc::UnboundedSender<Notification>;
+
+/// A live notification registration; unregistering on drop waits for running callbacks, after
+/// which the callback context can be freed
+struct Registration {
+    handle: HCMNOTIFICATION,
+    context: *mut NotificationSender,
+}
+
+// The context is only used by the callback, which no longer runs once the registration is dropped
+unsafe impl Send for Registration {}
+
+impl Registration {
+    fn register(tx: NotificationSender) -> Result<Self, String> {
+        let context = Box::into_raw(Box::new(tx));
+        let filter = CM_NOTIFY_FILTER {
+            cbSize: std::mem::size_of::<CM_NOTIFY_FILTER>() as u32,
+            Flags: 0,
+            FilterType: CM_NOTIFY_FILTER_TYPE_DEVICEINTERFACE,
+            Reserved: 0,
+            u: CM_NOTIFY_FILTER_0 { DeviceInterface: CM_NOTIFY_FILTER_0_2 { ClassGuid: GUID_DEVINTERFACE_COMPORT } },
+        };
+        let mut handle = HCMNOTIFICATION::default();
+        let result = unsafe {
+            CM_Register_Notification(&filter, Some(context as *const c_void), Some(notification_callback), &mut handle)
+        };
+        if result != CR_SUCCESS {
+            drop(unsafe { Box::from_raw(context) });
+            return Err(format!("CM_Register_Notification failed (CONFIGRET {})", result.0));
+        }
+        Ok(Self { handle, context })
+    }
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        unsafe {
+            let result = CM_Unregister_Notification(self.handle);
+            if result != CR_SUCCESS {
+                log::warn!("CM_Unregister_Notification failed (CONFIGRET {})", result.0);
+            }
+            drop(Box::from_raw(self.context));
+        }
+    }
+}
+
+unsafe extern "system" fn notification_callback(
+    _notify: HCMNOTIFICATION,
+    context: *const c_void,
+    action: CM_NOTIFY_ACTION,
+    event_data: *const CM_NOTIFY_EVENT_DATA,
+    event_data_size: u32,
+) -> u32 {
+    if context.is_null() || event_data.is_null() {
+        return ERROR_SUCCESS;
+    }
+    let tx = &*(context as *const NotificationSender);
+    let notification = if action == CM_NOTIFY_ACTION_DEVICEINTERFACEARRIVAL {
+        Notification::Arrival(symbolic_link(event_data, event_data_size))
+    } else if action == CM_NOTIFY_ACTION_DEVICEINTERFACEREMOVAL {
+        Notification::Removal(symbolic_link(event_data, event_data_size))
+    } else {
+        return ERROR_SUCCESS;
+    };
+    let _ = tx.send(notification);
+    ERROR_SUCCESS
+}
+
+/// NUL-terminated symbolic link at the end of a device interface event, bounded by the event size
+unsafe fn symbolic_link(event_data: *const CM_NOTIFY_EVENT_DATA, event_data_size: u32) -> String {
+    let start = std::ptr::addr_of!((*event_data).u.DeviceInterface.SymbolicLink) as *const u16;
+    let offset = start as usize - event_data as usize;
+    let max_chars = (event_data_size as usize).saturating_sub(offset) / 2;
+    let len = (0..max_chars).take_while(|&i| *start.add(i) != 0).count();
+    String::from_utf16_lossy(std::slice::from_raw_parts(start, len))
+}
+
+/// Device instance id of an interface symbolic link:
+/// `\\?\USB#VID_2E8A&PID_A02F#E661#{guid}` -> `USB\VID_2E8A&PID_A02F\E661`
+fn instance_id(symbolic_link: &str) -> Option<String> {
+    let path = symbolic_link.strip_prefix(r"\\?\").or_else(|| symbolic_link.strip_prefix(r"\??\"))?;
+    let (device, _interface_guid) = path.rsplit_once('#')?;
+    Some(device.replace('#', "\\"))
+}
+
+/// COM port name of the device behind `symbolic_link`. The devnode is located as a phantom so a
+/// port that was just removed still resolves.
+fn port_name(symbolic_link: &str) -> Option<String> {
+    let instance_id: Vec<u16> = instance_id(symbolic_link)?.encode_utf16().chain(Some(0)).collect();
+    unsafe {
+        let mut devinst = 0u32;
+        if CM_Locate_DevNodeW(&mut devinst, PCWSTR(instance_id.as_ptr()), CM_LOCATE_DEVNODE_PHANTOM) != CR_SUCCESS {
+            return None;
+        }
+        let mut key = HKEY::default();
+        if CM_Open_DevNode_Key(devinst, KEY_READ.0, 0, RegDisposition_OpenExisting, &mut key, CM_REGISTRY_HARDWARE) != CR_SUCCESS {
+            return None;
+        }
+        let mut buffer = [0u16; 64];
+        let mut size = std::mem::size_of_val(&buffer) as u32;
+        let mut value_type = REG_VALUE_TYPE::default();
+        let result = RegQueryValueExW(
+            key,
+            windows::core::w!("PortName"),
+            None,
+            Some(&mut value_type),
+            Some(buffer.as_mut_ptr() as *mut u8),
+            Some(&mut size),
+        );
+        let _ = RegCloseKey(key);
+        if result.0 != ERROR_SUCCESS {
+            return None;
+        }
+        if value_type != REG_SZ {
+            return None;
+        }
+        let chars = &buffer[..(size as usize / 2).min(buffer.len())];
+        let len = chars.iter().position(|&c| c == 0).unwrap_or(chars.len());
+        Some(String::from_utf16_lossy(&chars[..len])).filter(|name| !name.is_empty())
+    }
+}
 
 pub struct WindowsPortMonitor {
     tx: Option<mpsc::Sender<PortEvent>>,
     rx: Option<mpsc::Receiver<PortEvent>>,
-    stop_tx: Option<broadcast::Sender<()>>,
+    stop_tx: Option<mpsc::Sender<()>>,
     thread_handle: Option<tokio::task::JoinHandle<()>>,
 }
 
 impl WindowsPortMonitor {
     pub fn new() -> Self {
         let (tx, rx) = mpsc::channel(100);
-        let (stop_tx, _) = broadcast::channel(1);
-        
+
         Self {
             tx: Some(tx),
             rx: Some(rx),
-            stop_tx: Some(stop_tx),
+            stop_tx: None,
             thread_handle: None,
         }
     }
@@ -28,79 +177,80 @@ impl WindowsPortMonitor {
 impl PortMonitor for WindowsPortMonitor {
     async fn start(&mut self) -> std::result::Result<(), Box<dyn std::error::Error + Send + Sync>> {
         let tx = self.tx.take().ok_or("Already started")?;
-        let mut stop_rx = self.stop_tx.as_ref().unwrap().subscribe();
-        
-        // For Windows, we'll use a simple polling approach for now
-        // This is temporary until we can properly implement WM_DEVICECHANGE
+        let (notify_tx, mut notify_rx) = mpsc::unbounded_channel();
+        let registration = Registration::register(notify_tx)?;
+        let (stop_tx, mut stop_rx) = mpsc::channel(1);
+        self.stop_tx = Some(stop_tx);
+
         let handle = tokio::spawn(async move {
-            log::info!("Windows port monitor started (polling mode)");
-            let mut last_ports = std::collections::HashSet::new();
-            
-            // Get initial ports
-            if let Ok(ports) = serialport::available_ports() {
-                for port in ports {
-                    if port.port_name.starts_with("COM") {
-                        last_ports.insert(port.port_name);
-                    }
-                }
-            }
-            
-            let mut interval = tokio::time::interval(Duration::from_secs(2));
-            
+            log::info!("Windows port monitor started (device interface notifications)");
+            // Unregisters when the loop ends
+            let _registration = registration;
+            // Port names by lowercased symbolic link, so removals resolve without the registry
+            let mut names: HashMap<String, String> = HashMap::new();
+
             loop {
-                tokio::select! {
+                let notification = tokio::select! {
                     _ = stop_rx.recv() => {
                         log::info!("Windows port monitor stopping");
                         break;
                     }
-                    _ = interval.tick() => {
-                        // Check for port changes
-                        if let Ok(ports) = serialport::available_ports() {
-                            let mut current_ports = std::collections::HashSet::new();
-                            
-                            for port in ports {
-                                if port.port_name.starts_with("COM") {
-                                    current_ports.insert(port.port_name.clone());
-                                    
-                                    // Check for new ports
-                                    if !last_ports.contains(&port.port_name) {
-                                        let _ = tx.send(PortEvent::PortAdded(port.port_name)).await;
-                                    }
-                                }
-                            }
-                            
-                            // Check for removed ports
-                            for old_port in &last_ports {
-                                if !current_ports.contains(old_port) {
-                                    let _ = tx.send(PortEvent::PortRemoved(old_port.clone())).await;
-                                }
-                            }
-                            
-                            last_ports = current_ports;
+                    notification = notify_rx.recv() => match notification {
+                        Some(notification) => notification,
+                        None => break,
+                    },
+                };
+                let event = match &notification {
+                    Notification::Arrival(link) => port_name(link).map(|name| {
+                        names.insert(link.to_lowercase(), name.clone());
+                        PortEvent::PortAdded(name)
+                    }),
+                    Notification::Removal(link) => names.remove(&link.to_lowercase())
+                        .or_else(|| port_name(link))
+                        .map(PortEvent::PortRemoved),
+                };
+                match event {
+                    Some(event) => {
+                        if tx.send(event).await.is_err() {
+                            break;
                         }
                     }
+                    None => log::debug!("No COM port name for {:?}", notification),
                 }
             }
         });
-        
+
         self.thread_handle = Some(handle);
         Ok(())
     }
-    
+
     async fn stop(&mut self) -> std::result::Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        if let Some(stop_tx) = &self.stop_tx {
-            let _ = stop_tx.send(());
+        if let Some(stop_tx) = self.stop_tx.take() {
+            let _ = stop_tx.send(()).await;
         }
-        
+
         if let Some(handle) = self.thread_handle.take() {
-            handle.abort();
             let _ = handle.await;
         }
-        
+
         Ok(())
     }
-    
+
     fn get_receiver(&mut self) -> Option<mpsc::Receiver<PortEvent>> {
         self.rx.take()
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn derives_instance_id_from_symbolic_link() {
+        assert_eq!(
+            instance_id(r"\\?\USB#VID_2E8A&PID_A02F&MI_00#7&1a2b3c4d&0&0000#{86e0d1e0-8089-11d0-9ce4-08003e301f73}").as_deref(),
+            Some(r"USB\VID_2E8A&PID_A02F&MI_00\7&1a2b3c4d&0&0000"),
+        );
+        assert_eq!(instance_id("COM3"), None);
+    }
+}
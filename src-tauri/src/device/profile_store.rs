@@ -0,0 +1,97 @@
+use std::path::{Path, PathBuf};
+
+use super::ProfileManager;
+
+pub const PROFILES_FILE_NAME: &str = "profiles.json";
+
+/// Persists the `ProfileManager` as JSON under the app data directory.
+/// Writes go to a temporary file first and are renamed into place so a crash
+/// mid-save never leaves a truncated profiles file behind.
+#[derive(Debug, Clone)]
+pub struct ProfileStore {
+    path: PathBuf,
+}
+
+impl ProfileStore {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    /// Store located in the given app data directory
+    pub fn in_dir(dir: &Path) -> Self {
+        Self::new(dir.join(PROFILES_FILE_NAME))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Load saved profiles. A missing file yields an empty manager; a corrupt file is
+    /// moved aside (`.corrupt`) so the next save does not silently overwrite it.
+    pub fn load(&self) -> ProfileManager {
+        let contents = match std::fs::read_to_string(&self.path) {
+            Ok(c) => c,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return ProfileManager::new(),
+            Err(e) => {
+                log::error!("Failed to read profiles from {:?}: {}", self.path, e);
+                return ProfileManager::new();
+            }
+        };
+        match serde_json::from_str::<ProfileManager>(&contents) {
+            Ok(mut pm) => {
+                // Drop a dangling active profile reference
+                if pm.active_profile_id.as_ref().is_some_and(|id| pm.get_profile(id).is_none()) {
+                    pm.active_profile_id = None;
+                }
+                log::info!("Loaded {} profile(s) from {:?}", pm.profiles.len(), self.path);
+                pm
+            }
+            Err(e) => {
+                let aside = self.path.with_extension("json.corrupt");
+                log::error!("Profiles file {:?} is corrupt ({}), moving it to {:?}", self.path, e, aside);
+                let _ = std::fs::rename(&self.path, &aside);
+                ProfileManager::new()
+            }
+        }
+    }
+
+    /// Atomically write all profiles to disk
+    pub fn save(&self, profiles: &ProfileManager) -> std::io::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string_pretty(profiles)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        let tmp = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp, json)?;
+        std::fs::rename(&tmp, &self.path)?;
+        log::debug!("Saved {} profile(s) to {:?}", profiles.profiles.len(), self.path);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::device::DeviceStatus;
+
+    #[test]
+    fn profiles_round_trip_through_disk() {
+        let dir = std::env::temp_dir().join(format!("joycore_profiles_{}", uuid::Uuid::new_v4()));
+        let store = ProfileStore::in_dir(&dir);
+        assert!(store.load().profiles.is_empty());
+
+        let status = DeviceStatus { firmware_version: "1.0.0".into(), device_name: "Test".into(), axes_count: 2, buttons_count: 4, connected: true, ..Default::default() };
+        let profile = ProfileManager::create_default_profile(&status);
+        let id = profile.id.clone();
+        let mut pm = ProfileManager::new();
+        pm.add_profile(profile);
+        pm.set_active_profile(&id);
+        store.save(&pm).unwrap();
+
+        let loaded = store.load();
+        assert_eq!(loaded.profiles.len(), 1);
+        assert_eq!(loaded.active_profile_id.as_deref(), Some(id.as_str()));
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
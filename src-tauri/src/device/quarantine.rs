@@ -0,0 +1,126 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use crate::util::persist::{load_json, save_json};
+
+/// Consecutive IDENTIFY failures before a port is quarantined
+pub const QUARANTINE_FAILURE_THRESHOLD: u32 = 3;
+pub const QUARANTINE_FILE_NAME: &str = "port_quarantine.json";
+
+/// A port that discovery no longer probes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuarantinedPort {
+    pub port_name: String,
+    pub failure_count: u32,
+    pub last_error: String,
+    pub quarantined_at: DateTime<Utc>,
+}
+
+/// Persisted list of ports that repeatedly failed or hung during IDENTIFY.
+/// Failure counters are kept in memory only; the quarantine list itself is saved to disk.
+#[derive(Debug, Default)]
+pub struct PortQuarantine {
+    path: Option<PathBuf>,
+    failures: HashMap<String, u32>,
+    quarantined: HashMap<String, QuarantinedPort>,
+}
+
+impl PortQuarantine {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load a persisted quarantine list; a missing or unreadable file yields an empty list
+    pub fn load(path: PathBuf) -> Self {
+        let list: Vec<QuarantinedPort> = load_json(&path, "port quarantine");
+        let quarantined = list.into_iter().map(|p| (p.port_name.clone(), p)).collect();
+        Self { path: Some(path), failures: HashMap::new(), quarantined }
+    }
+
+    pub fn is_quarantined(&self, port_name: &str) -> bool {
+        self.quarantined.contains_key(port_name)
+    }
+
+    /// Record a failed/hung IDENTIFY. Returns true if the port was newly quarantined.
+    pub fn record_failure(&mut self, port_name: &str, error: &str) -> bool {
+        if self.is_quarantined(port_name) { return false; }
+        let count = self.failures.entry(port_name.to_string()).or_insert(0);
+        *count += 1;
+        if *count < QUARANTINE_FAILURE_THRESHOLD { return false; }
+
+        let count = self.failures.remove(port_name).unwrap_or(QUARANTINE_FAILURE_THRESHOLD);
+        log::warn!("Quarantining port {} after {} failed IDENTIFY attempts: {}", port_name, count, error);
+        self.quarantined.insert(port_name.to_string(), QuarantinedPort {
+            port_name: port_name.to_string(),
+            failure_count: count,
+            last_error: error.to_string(),
+            quarantined_at: Utc::now(),
+        });
+        self.save();
+        true
+    }
+
+    /// A clean probe resets the failure streak
+    pub fn record_success(&mut self, port_name: &str) {
+        self.failures.remove(port_name);
+    }
+
+    pub fn list(&self) -> Vec<QuarantinedPort> {
+        let mut list: Vec<QuarantinedPort> = self.quarantined.values().cloned().collect();
+        list.sort_by(|a, b| a.port_name.cmp(&b.port_name));
+        list
+    }
+
+    /// Remove one port (or all when `port_name` is None) from quarantine
+    pub fn clear(&mut self, port_name: Option<&str>) -> usize {
+        let removed = match port_name {
+            Some(name) => {
+                self.failures.remove(name);
+                usize::from(self.quarantined.remove(name).is_some())
+            }
+            None => {
+                self.failures.clear();
+                let n = self.quarantined.len();
+                self.quarantined.clear();
+                n
+            }
+        };
+        if removed > 0 { self.save(); }
+        removed
+    }
+
+    fn save(&self) {
+        if let Some(path) = &self.path {
+            save_json(path, &self.list(), "port quarantine");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quarantines_after_threshold_and_resets_on_success() {
+        let mut q = PortQuarantine::new();
+        assert!(!q.record_failure("COM7", "hang"));
+        q.record_success("COM7");
+        assert!(!q.record_failure("COM7", "hang"));
+        assert!(!q.record_failure("COM7", "hang"));
+        assert!(q.record_failure("COM7", "hang"));
+        assert!(q.is_quarantined("COM7"));
+        assert_eq!(q.clear(Some("COM7")), 1);
+        assert!(!q.is_quarantined("COM7"));
+    }
+
+    #[test]
+    fn persists_quarantine_list() {
+        let path = std::env::temp_dir().join(format!("joycore_quarantine_{}.json", uuid::Uuid::new_v4()));
+        let mut q = PortQuarantine::load(path.clone());
+        for _ in 0..QUARANTINE_FAILURE_THRESHOLD { q.record_failure("/dev/ttyUSB3", "io error"); }
+        let reloaded = PortQuarantine::load(path.clone());
+        assert!(reloaded.is_quarantined("/dev/ttyUSB3"));
+        let _ = std::fs::remove_file(path);
+    }
+}
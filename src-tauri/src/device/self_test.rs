@@ -0,0 +1,132 @@
+//! One-click device health check.
+//!
+//! [`DeviceManager::run_device_self_test`](super::DeviceManager::run_device_self_test) runs the
+//! checks in order and records one [`SelfTestCheck`] each; a check whose input is missing
+//! (e.g. CRC after a failed config read) is skipped rather than failed. This module holds the
+//! report types and the judgement of collected numbers, so thresholds are testable without
+//! a device.
+use serde::Serialize;
+
+use crate::hid::HidLinkStats;
+use crate::serial::unified::types::MetricsSnapshot;
+use crate::serial::StorageInfo;
+
+pub const DEFAULT_MONITOR_SAMPLE_SECS: u64 = 3;
+pub const MAX_MONITOR_SAMPLE_SECS: u64 = 30;
+/// Storage fuller than this is a warning (config writes need room for the backup copy)
+pub const STORAGE_WARN_USAGE: f64 = 0.9;
+
+/// Outcome of one check, ordered from best to worst
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Skipped,
+    Pass,
+    Warn,
+    Fail,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SelfTestCheck {
+    pub name: &'static str,
+    pub status: CheckStatus,
+    pub detail: String,
+    pub duration_ms: u64,
+}
+
+impl SelfTestCheck {
+    pub fn new(name: &'static str, status: CheckStatus, detail: impl Into<String>) -> Self {
+        Self { name, status, detail: detail.into(), duration_ms: 0 }
+    }
+
+    pub fn timed(self, started: std::time::Instant) -> Self {
+        Self { duration_ms: started.elapsed().as_millis() as u64, ..self }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SelfTestReport {
+    /// Worst status of all checks that ran
+    pub overall: CheckStatus,
+    pub checks: Vec<SelfTestCheck>,
+    pub started_at: chrono::DateTime<chrono::Utc>,
+    pub duration_ms: u64,
+}
+
+impl SelfTestReport {
+    pub fn new(checks: Vec<SelfTestCheck>, started_at: chrono::DateTime<chrono::Utc>, duration_ms: u64) -> Self {
+        let overall = checks.iter().map(|c| c.status).max().unwrap_or(CheckStatus::Pass).max(CheckStatus::Pass);
+        Self { overall, checks, started_at, duration_ms }
+    }
+}
+
+pub fn storage_check(info: &StorageInfo) -> SelfTestCheck {
+    let usage = if info.total_bytes == 0 { 1.0 } else { info.used_bytes as f64 / info.total_bytes as f64 };
+    let detail = format!("{} of {} bytes used, {} of {} files", info.used_bytes, info.total_bytes, info.file_count, info.max_files);
+    let status = if info.total_bytes == 0 {
+        CheckStatus::Fail
+    } else if usage > STORAGE_WARN_USAGE || (info.max_files > 0 && info.file_count >= info.max_files) {
+        CheckStatus::Warn
+    } else {
+        CheckStatus::Pass
+    };
+    SelfTestCheck::new("storage", status, detail)
+}
+
+pub fn monitor_check(events: u64, secs: u64) -> SelfTestCheck {
+    if events == 0 {
+        SelfTestCheck::new("monitor_stream", CheckStatus::Warn, format!("No monitor events in {} s", secs))
+    } else {
+        SelfTestCheck::new("monitor_stream", CheckStatus::Pass, format!("{} events in {} s", events, secs))
+    }
+}
+
+/// Serial errors that occurred while the self-test ran
+pub fn serial_link_check(before: &MetricsSnapshot, after: &MetricsSnapshot) -> SelfTestCheck {
+    let timeouts = after.command_timeouts.saturating_sub(before.command_timeouts);
+    let retries = after.command_retries.saturating_sub(before.command_retries);
+    let decode_errors = (after.utf8_decode_errors + after.frame_errors).saturating_sub(before.utf8_decode_errors + before.frame_errors);
+    let detail = format!("{} timeouts, {} retries, {} decode errors", timeouts, retries, decode_errors);
+    let status = if timeouts + retries + decode_errors == 0 { CheckStatus::Pass } else { CheckStatus::Warn };
+    SelfTestCheck::new("serial_link", status, detail)
+}
+
+pub fn hid_link_check(stats: &HidLinkStats) -> SelfTestCheck {
+    if stats.frames == 0 {
+        return SelfTestCheck::new("hid_link", CheckStatus::Skipped, "Firmware reports no frame counter");
+    }
+    let detail = format!("{} frames, {} dropped ({:.2}%), {} stalls", stats.frames, stats.dropped_frames, stats.drop_rate * 100.0, stats.stalls);
+    let status = if stats.drop_rate > crate::hid::link::DROP_WARNING_RATE { CheckStatus::Warn } else { CheckStatus::Pass };
+    SelfTestCheck::new("hid_link", status, detail)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overall_is_worst_status_that_ran() {
+        let now = chrono::Utc::now();
+        let checks = vec![SelfTestCheck::new("a", CheckStatus::Pass, ""), SelfTestCheck::new("b", CheckStatus::Skipped, "")];
+        assert_eq!(SelfTestReport::new(checks, now, 0).overall, CheckStatus::Pass);
+        assert_eq!(SelfTestReport::new(vec![SelfTestCheck::new("a", CheckStatus::Skipped, "")], now, 0).overall, CheckStatus::Pass);
+        let checks = vec![SelfTestCheck::new("a", CheckStatus::Warn, ""), SelfTestCheck::new("b", CheckStatus::Fail, "")];
+        assert_eq!(SelfTestReport::new(checks, now, 0).overall, CheckStatus::Fail);
+    }
+
+    #[test]
+    fn judges_storage_and_links() {
+        let info = StorageInfo { used_bytes: 1000, total_bytes: 64 * 1024, available_bytes: 63 * 1024, file_count: 2, max_files: 16 };
+        assert_eq!(storage_check(&info).status, CheckStatus::Pass);
+        assert_eq!(storage_check(&StorageInfo { used_bytes: 62 * 1024, ..info.clone() }).status, CheckStatus::Warn);
+        assert_eq!(storage_check(&StorageInfo { total_bytes: 0, ..info }).status, CheckStatus::Fail);
+
+        let before = MetricsSnapshot { command_timeouts: 2, ..Default::default() };
+        assert_eq!(serial_link_check(&before, &before.clone()).status, CheckStatus::Pass);
+        assert_eq!(serial_link_check(&before, &MetricsSnapshot { command_timeouts: 3, ..Default::default() }).status, CheckStatus::Warn);
+
+        assert_eq!(hid_link_check(&HidLinkStats::default()).status, CheckStatus::Skipped);
+        let lossy = HidLinkStats { frames: 1000, dropped_frames: 50, drop_rate: 0.05, ..Default::default() };
+        assert_eq!(hid_link_check(&lossy).status, CheckStatus::Warn);
+    }
+}
@@ -0,0 +1,147 @@
+use std::path::{Path, PathBuf};
+use crate::util::persist::{load_json, save_json};
+use super::AppSettings;
+
+pub const SETTINGS_FILE_NAME: &str = "settings.json";
+/// GitHub token of the update checks, next to the settings file and readable by its owner only
+pub const GITHUB_TOKEN_FILE_NAME: &str = "github_token";
+
+/// Persisted application settings (app config directory)
+#[derive(Debug, Default)]
+pub struct SettingsStore {
+    path: Option<PathBuf>,
+    settings: AppSettings,
+}
+
+impl SettingsStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load from disk; a missing or corrupt file yields defaults. A token stored in the settings
+    /// file by an older version moves to the token file.
+    pub fn load(path: PathBuf) -> Self {
+        let mut settings: AppSettings = load_json(&path, "settings");
+        let token_path = path.with_file_name(GITHUB_TOKEN_FILE_NAME);
+        let legacy_token = settings.firmware_update.github_token.take();
+        settings.firmware_update.github_token = read_token(&token_path);
+        let mut store = Self { path: Some(path), settings };
+        if let Some(token) = legacy_token {
+            if store.settings.firmware_update.github_token.is_none() {
+                store.set_github_token(Some(token));
+            }
+            store.save();
+        }
+        store
+    }
+
+    /// Current settings, including the GitHub token (which serialization leaves out)
+    pub fn settings(&self) -> &AppSettings {
+        &self.settings
+    }
+
+    /// Replace the settings; the GitHub token is kept, it only changes through `set_github_token`
+    pub fn set_settings(&mut self, mut settings: AppSettings) {
+        settings.firmware_update.github_token = self.settings.firmware_update.github_token.take();
+        self.settings = settings;
+        self.save();
+    }
+
+    /// Store or (with None or a blank token) remove the GitHub token
+    pub fn set_github_token(&mut self, token: Option<String>) {
+        let token = token.map(|t| t.trim().to_string()).filter(|t| !t.is_empty());
+        if let Some(path) = &self.path {
+            let token_path = path.with_file_name(GITHUB_TOKEN_FILE_NAME);
+            let result = match &token {
+                Some(token) => write_token(&token_path, token),
+                None => std::fs::remove_file(&token_path).or_else(|e| if e.kind() == std::io::ErrorKind::NotFound { Ok(()) } else { Err(e) }),
+            };
+            if let Err(e) = result {
+                log::error!("Failed to save GitHub token: {}", e);
+            }
+        }
+        self.settings.firmware_update.github_token = token;
+    }
+
+    fn save(&self) {
+        if let Some(path) = &self.path {
+            save_json(path, &self.settings, "settings");
+        }
+    }
+}
+
+fn read_token(path: &Path) -> Option<String> {
+    std::fs::read_to_string(path).ok()
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty())
+}
+
+fn write_token(path: &Path, token: &str) -> std::io::Result<()> {
+    use std::io::Write;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+        options.mode(0o600);
+        // The mode only applies to new files
+        if path.exists() {
+            std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+        }
+    }
+    options.open(path)?.write_all(token.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn persists_settings_and_fills_missing_fields() {
+        let path = std::env::temp_dir().join(format!("joycore_settings_{}.json", uuid::Uuid::new_v4()));
+        std::fs::write(&path, r#"{"auto_connect": true}"#).unwrap();
+        let mut store = SettingsStore::load(path.clone());
+        assert!(store.settings().auto_connect);
+        // Files from before these settings existed keep the behavior they had
+        assert!(!AppSettings::default().auto_connect);
+        assert_eq!(store.settings().update_rate_ms, crate::hid::DEFAULT_STATE_SYNC_INTERVAL_MS);
+
+        let mut settings = store.settings().clone();
+        settings.update_rate_ms = 250;
+        store.set_settings(settings);
+        assert_eq!(SettingsStore::load(path.clone()).settings().update_rate_ms, 250);
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn keeps_github_token_out_of_the_settings_file() {
+        let dir = std::env::temp_dir().join(format!("joycore_settings_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(SETTINGS_FILE_NAME);
+        std::fs::write(&path, r#"{"firmware_update": {"github_token": "ghp_old"}}"#).unwrap();
+
+        let mut store = SettingsStore::load(path.clone());
+        assert_eq!(store.settings().firmware_update.github_token.as_deref(), Some("ghp_old"), "migrated");
+        assert!(!std::fs::read_to_string(&path).unwrap().contains("ghp_old"));
+        assert!(!serde_json::to_string(store.settings()).unwrap().contains("ghp_old"));
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(dir.join(GITHUB_TOKEN_FILE_NAME)).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        // A settings round trip through the frontend carries no token and keeps the stored one
+        let mut settings = store.settings().clone();
+        settings.firmware_update.github_token = None;
+        store.set_settings(settings);
+        assert_eq!(SettingsStore::load(path.clone()).settings().firmware_update.github_token.as_deref(), Some("ghp_old"));
+
+        store.set_github_token(None);
+        assert!(!dir.join(GITHUB_TOKEN_FILE_NAME).exists());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,94 @@
+//! Detection of devices that need setup before they are usable.
+//!
+//! Run after every successful connect; when anything is found the manager emits
+//! [`DEVICE_SETUP_REQUIRED_EVENT`] so the frontend can open the matching wizard.
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::config::binary::{BinaryConfig, ConfigHeader, CONFIG_MAGIC, CONFIG_VERSION};
+
+pub const DEVICE_SETUP_REQUIRED_EVENT: &str = "device_setup_required";
+pub const CONFIG_FILE: &str = "/config.bin";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "reason", rename_all = "snake_case")]
+pub enum SetupIssue {
+    /// No config file on the device storage
+    MissingConfig,
+    /// Firmware runs its built-in defaults instead of a stored config
+    DefaultConfig,
+    /// Stored config was written for a different schema than this app edits
+    ConfigVersionMismatch { device_version: u16, expected_version: u16 },
+    /// Config is stored but cannot be parsed
+    InvalidConfig { error: String },
+    /// Config maps no physical input to a button
+    NoLogicalInputs,
+}
+
+/// Payload of `device_setup_required`
+#[derive(Debug, Clone, Serialize)]
+pub struct DeviceSetupRequired {
+    pub device_id: Uuid,
+    pub firmware_version: Option<String>,
+    pub reasons: Vec<SetupIssue>,
+}
+
+/// STATUS reports `Loaded: NO` when no stored config was applied
+pub fn status_uses_defaults(status: &str) -> bool {
+    crate::serial::status::parse_status(status).config_loaded == Some(false)
+}
+
+pub fn lists_config_file(files: &[String]) -> bool {
+    files.iter().any(|f| f.trim().trim_start_matches('/') == CONFIG_FILE.trim_start_matches('/'))
+}
+
+/// Problems with the stored config bytes
+pub fn config_issues(data: &[u8]) -> Vec<SetupIssue> {
+    if data.len() < std::mem::size_of::<ConfigHeader>() {
+        return vec![SetupIssue::InvalidConfig { error: format!("{} bytes is shorter than the config header", data.len()) }];
+    }
+    let header = unsafe { std::ptr::read_unaligned(data.as_ptr() as *const ConfigHeader) };
+    let (magic, version) = (header.magic, header.version);
+    if magic != CONFIG_MAGIC {
+        return vec![SetupIssue::InvalidConfig { error: format!("Invalid magic number: 0x{:08X}", magic) }];
+    }
+    if version != CONFIG_VERSION {
+        // The rest of the layout is unknown, so nothing else can be judged
+        return vec![SetupIssue::ConfigVersionMismatch { device_version: version, expected_version: CONFIG_VERSION }];
+    }
+    match BinaryConfig::from_bytes(data) {
+        Ok(config) if config.logical_inputs.is_empty() => vec![SetupIssue::NoLogicalInputs],
+        Ok(_) => Vec::new(),
+        Err(error) => vec![SetupIssue::InvalidConfig { error }],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_defaults_from_status() {
+        assert!(status_uses_defaults("Config Status - Storage: OK, Loaded: NO, Version: 7"));
+        assert!(!status_uses_defaults("Config Status - Storage: OK, Loaded: YES, Version: 7"));
+        assert!(lists_config_file(&["config.bin".to_string()]));
+        assert!(lists_config_file(&["/config.bin".to_string(), "/config.bak".to_string()]));
+        assert!(!lists_config_file(&["/config.bak".to_string()]));
+    }
+
+    #[test]
+    fn judges_stored_config() {
+        let empty = BinaryConfig::new().to_bytes().unwrap();
+        assert_eq!(config_issues(&empty), vec![SetupIssue::NoLogicalInputs]);
+
+        let mut old = empty.clone();
+        old[4..6].copy_from_slice(&(CONFIG_VERSION - 1).to_le_bytes());
+        assert_eq!(config_issues(&old), vec![SetupIssue::ConfigVersionMismatch { device_version: CONFIG_VERSION - 1, expected_version: CONFIG_VERSION }]);
+
+        let mut corrupt = empty;
+        let last = corrupt.len() - 1;
+        corrupt[last] ^= 0xFF;
+        assert!(matches!(config_issues(&corrupt)[..], [SetupIssue::InvalidConfig { .. }]));
+        assert!(matches!(config_issues(&[0u8; 4])[..], [SetupIssue::InvalidConfig { .. }]));
+    }
+}
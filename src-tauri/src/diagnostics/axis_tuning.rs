@@ -0,0 +1,314 @@
+//! Axis filter tuning assistant.
+//!
+//! Samples one axis from the HID reports in two phases: at rest (noise) and during a slow sweep
+//! (movement speed). The firmware filter is not documented, so it is approximated by an assumed
+//! model: an EWMA stage (`ewma_alpha / 65536` per report) followed by a change threshold
+//! (`deadband`: the output only moves once the smoothed value is more than `deadband` away from
+//! it). The recommendation is the largest alpha that brings the rest noise down to 0.1 % of the
+//! swept travel without more than [`MAX_LAG_MS`] of lag, with a deadband covering what noise is
+//! left. `filter_level` is a heuristic that follows the noise band. Lags, traces and
+//! recommendations are estimates under this model; every report says so in `filter_model` and its
+//! first note.
+//!
+//! HID values have already passed the device's current filter. Both traces run the captured
+//! samples through the model, once with the current and once with the recommended settings, so
+//! they compare like for like; tuning is most accurate with the device filter turned down.
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+
+use super::{DiagnosticsError, Result};
+
+pub const DEFAULT_REST_MS: u64 = 3_000;
+pub const DEFAULT_SWEEP_MS: u64 = 6_000;
+pub const MAX_PHASE_MS: u64 = 30_000;
+pub const AXIS_TUNING_PHASE_EVENT: &str = "axis_tuning_phase";
+/// Lag the recommendation may add
+pub const MAX_LAG_MS: f64 = 20.0;
+/// Candidate `ewma_alpha` values, least smoothing first
+const CANDIDATE_ALPHAS: [u16; 7] = [65535, 32768, 19661, 13107, 6554, 3277, 1311];
+/// Target rest noise (standard deviation) as a share of the swept travel
+const NOISE_TARGET: f64 = 0.001;
+/// Rest noise bands (share of travel) above which filter levels 1, 2 and 3 are recommended
+const FILTER_LEVEL_BANDS: [f64; 3] = [0.0005, 0.002, 0.008];
+const MIN_SAMPLES: usize = 20;
+const MAX_SAMPLES: usize = 100_000;
+const MAX_TRACE_POINTS: usize = 1_000;
+/// The assumed filter model, reported with every result
+pub const FILTER_MODEL: &str = "approximation: EWMA (ewma_alpha / 65536 per report) then deadband; filter_level by noise band";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AxisFilterSettings {
+    pub filter_level: u8,
+    pub ewma_alpha: u16,
+    pub deadband: u16,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct NoiseStats {
+    pub samples: usize,
+    pub mean: f64,
+    pub std_dev: f64,
+    pub peak_to_peak: u16,
+}
+
+/// Captured samples with both filter settings applied by the model, decimated for plotting
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AxisTrace {
+    /// Milliseconds since the phase started
+    pub t_ms: Vec<f64>,
+    pub captured: Vec<u16>,
+    pub before: Vec<f64>,
+    pub after: Vec<f64>,
+}
+
+/// Recommended settings and traces, all estimated with the assumed filter model
+#[derive(Debug, Clone, Serialize)]
+pub struct AxisTuningReport {
+    pub axis_id: u8,
+    /// Description of the filter model the figures are based on ([`FILTER_MODEL`])
+    pub filter_model: &'static str,
+    pub sample_interval_ms: f64,
+    pub rest_noise: NoiseStats,
+    /// Average speed during the sweep, in HID units per second
+    pub sweep_speed: f64,
+    /// Travel covered by the sweep
+    pub sweep_span: u16,
+    pub current: AxisFilterSettings,
+    pub recommended: AxisFilterSettings,
+    /// EWMA time constant of each setting
+    pub current_lag_ms: f64,
+    pub recommended_lag_ms: f64,
+    pub rest_trace: AxisTrace,
+    pub sweep_trace: AxisTrace,
+    pub notes: Vec<String>,
+}
+
+type Samples = Vec<(Instant, u16)>;
+
+/// Collects HID values of one axis for one tuning run at a time
+#[derive(Default)]
+pub struct AxisSampler {
+    running: AtomicBool,
+    axis_id: AtomicU8,
+    samples: Mutex<Samples>,
+}
+
+impl AxisSampler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::Relaxed)
+    }
+
+    /// Start collecting `axis_id`; fails while another run is in progress
+    pub fn begin(&self, axis_id: u8) -> Result<()> {
+        if self.running.swap(true, Ordering::SeqCst) {
+            return Err(DiagnosticsError::AlreadyRunning("axis tuning run"));
+        }
+        self.axis_id.store(axis_id, Ordering::Relaxed);
+        self.samples.lock().unwrap_or_else(|e| e.into_inner()).clear();
+        Ok(())
+    }
+
+    /// Samples collected so far; collection continues for the next phase
+    pub fn take(&self) -> Samples {
+        std::mem::take(&mut *self.samples.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    pub fn finish(&self) -> Samples {
+        self.running.store(false, Ordering::SeqCst);
+        self.take()
+    }
+
+    /// Feed the axis values of one HID report
+    pub fn record(&self, values: &[u16], at: Instant) {
+        let Some(&value) = values.get(self.axis_id.load(Ordering::Relaxed) as usize) else { return };
+        let mut samples = self.samples.lock().unwrap_or_else(|e| e.into_inner());
+        if samples.len() < MAX_SAMPLES {
+            samples.push((at, value));
+        }
+    }
+}
+
+/// Output of the assumed firmware filter model for `values`
+fn simulate(values: &[u16], settings: AxisFilterSettings) -> Vec<f64> {
+    let alpha = f64::from(settings.ewma_alpha) / 65536.0;
+    let deadband = f64::from(settings.deadband);
+    let Some(&first) = values.first() else { return Vec::new() };
+    let (mut smoothed, mut output) = (f64::from(first), f64::from(first));
+    values.iter().map(|&v| {
+        smoothed += alpha * (f64::from(v) - smoothed);
+        if (smoothed - output).abs() > deadband {
+            output = smoothed;
+        }
+        output
+    }).collect()
+}
+
+/// EWMA time constant in milliseconds under the model
+fn lag_ms(ewma_alpha: u16, interval_ms: f64) -> f64 {
+    let alpha = (f64::from(ewma_alpha) / 65536.0).max(f64::EPSILON);
+    interval_ms * (1.0 - alpha).max(0.0) / alpha
+}
+
+fn std_dev(values: &[f64]) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    let n = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / n;
+    (values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n).sqrt()
+}
+
+fn span(values: &[u16]) -> u16 {
+    let (min, max) = values.iter().fold((u16::MAX, 0), |(min, max), &v| (min.min(v), max.max(v)));
+    max.saturating_sub(min)
+}
+
+fn median_interval_ms(samples: &[(Instant, u16)]) -> Option<f64> {
+    let mut intervals: Vec<f64> = samples.windows(2)
+        .map(|w| w[1].0.saturating_duration_since(w[0].0).as_secs_f64() * 1000.0)
+        .filter(|ms| *ms > 0.0)
+        .collect();
+    if intervals.is_empty() {
+        return None;
+    }
+    intervals.sort_by(|a, b| a.total_cmp(b));
+    Some(intervals[intervals.len() / 2])
+}
+
+fn trace(samples: &[(Instant, u16)], current: AxisFilterSettings, recommended: AxisFilterSettings) -> AxisTrace {
+    let Some(&(start, _)) = samples.first() else { return AxisTrace::default() };
+    let values: Vec<u16> = samples.iter().map(|&(_, v)| v).collect();
+    let (before, after) = (simulate(&values, current), simulate(&values, recommended));
+    let step = samples.len().div_ceil(MAX_TRACE_POINTS).max(1);
+    let mut trace = AxisTrace::default();
+    for i in (0..samples.len()).step_by(step) {
+        trace.t_ms.push(samples[i].0.saturating_duration_since(start).as_secs_f64() * 1000.0);
+        trace.captured.push(values[i]);
+        trace.before.push(before[i]);
+        trace.after.push(after[i]);
+    }
+    trace
+}
+
+/// Analyze the rest and sweep phases of axis `axis_id`
+pub fn analyze(axis_id: u8, current: AxisFilterSettings, rest: &[(Instant, u16)], sweep: &[(Instant, u16)]) -> Result<AxisTuningReport> {
+    if rest.len() < MIN_SAMPLES {
+        return Err(DiagnosticsError::Unavailable(format!(
+            "Only {} HID samples of axis {} at rest; is the HID reader running and the axis reported?", rest.len(), axis_id,
+        )));
+    }
+    let mut notes = vec!["Recommendations come from an approximate model of the firmware filter; check the result on the device".to_string()];
+    let interval_ms = median_interval_ms(rest).unwrap_or(1.0);
+    let rest_values: Vec<u16> = rest.iter().map(|&(_, v)| v).collect();
+    let sweep_values: Vec<u16> = sweep.iter().map(|&(_, v)| v).collect();
+    let rest_f: Vec<f64> = rest_values.iter().map(|&v| f64::from(v)).collect();
+    let rest_noise = NoiseStats {
+        samples: rest.len(),
+        mean: rest_f.iter().sum::<f64>() / rest_f.len() as f64,
+        std_dev: std_dev(&rest_f),
+        peak_to_peak: span(&rest_values),
+    };
+
+    let sweep_span = span(&sweep_values);
+    let sweep_secs = match (sweep.first(), sweep.last()) {
+        (Some(first), Some(last)) => last.0.saturating_duration_since(first.0).as_secs_f64(),
+        _ => 0.0,
+    };
+    let travelled: f64 = sweep_values.windows(2).map(|w| f64::from(w[1].abs_diff(w[0]))).sum();
+    let sweep_speed = if sweep_secs > 0.0 { travelled / sweep_secs } else { 0.0 };
+    let travel = if sweep_span > rest_noise.peak_to_peak.saturating_mul(4) {
+        f64::from(sweep_span)
+    } else {
+        notes.push("The sweep barely moved the axis; noise targets assume the full 16-bit range".to_string());
+        f64::from(u16::MAX)
+    };
+
+    let target = travel * NOISE_TARGET;
+    let residual = |alpha: u16| simulate(&rest_values, AxisFilterSettings { filter_level: 0, ewma_alpha: alpha, deadband: 0 });
+    let within_lag: Vec<u16> = CANDIDATE_ALPHAS.iter().copied().filter(|&a| lag_ms(a, interval_ms) <= MAX_LAG_MS).collect();
+    let ewma_alpha = within_lag.iter().copied()
+        .find(|&a| std_dev(&residual(a)) <= target)
+        .or_else(|| within_lag.last().copied())
+        .unwrap_or(CANDIDATE_ALPHAS[0]);
+    let left = residual(ewma_alpha);
+    let left_span = left.iter().copied().fold(f64::NEG_INFINITY, f64::max) - left.iter().copied().fold(f64::INFINITY, f64::min);
+    let deadband = if left_span < 1.0 { 0 } else { left_span.ceil().min(f64::from(u16::MAX)) as u16 };
+    if std_dev(&left) > target {
+        notes.push(format!("Noise stays above the target within {} ms of lag; the deadband absorbs the rest", MAX_LAG_MS));
+    }
+    let noise_share = rest_noise.std_dev / travel;
+    let filter_level = FILTER_LEVEL_BANDS.iter().filter(|&&band| noise_share > band).count() as u8;
+    let recommended = AxisFilterSettings { filter_level, ewma_alpha, deadband };
+    if current.filter_level != 0 || current.ewma_alpha < CANDIDATE_ALPHAS[0] || current.deadband != 0 {
+        notes.push("Samples already include the device's current filtering".to_string());
+    }
+    if sweep.len() < MIN_SAMPLES {
+        notes.push("Too few sweep samples to judge the lag".to_string());
+    }
+
+    Ok(AxisTuningReport {
+        axis_id,
+        filter_model: FILTER_MODEL,
+        sample_interval_ms: interval_ms,
+        rest_noise,
+        sweep_speed,
+        sweep_span,
+        current,
+        recommended,
+        current_lag_ms: lag_ms(current.ewma_alpha, interval_ms),
+        recommended_lag_ms: lag_ms(ewma_alpha, interval_ms),
+        rest_trace: trace(rest, current, recommended),
+        sweep_trace: trace(sweep, current, recommended),
+        notes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn samples(values: impl Iterator<Item = u16>) -> Samples {
+        let t0 = Instant::now();
+        values.enumerate().map(|(i, v)| (t0 + Duration::from_millis(i as u64), v)).collect()
+    }
+
+    const OFF: AxisFilterSettings = AxisFilterSettings { filter_level: 0, ewma_alpha: 65535, deadband: 0 };
+
+    #[test]
+    fn models_ewma_and_deadband() {
+        let out = simulate(&[100, 200, 200, 200], AxisFilterSettings { filter_level: 0, ewma_alpha: 32768, deadband: 0 });
+        assert_eq!(out, vec![100.0, 150.0, 175.0, 187.5]);
+        let held = simulate(&[100, 102, 98, 101, 110], AxisFilterSettings { deadband: 3, ..OFF });
+        assert!(held[..4].iter().all(|&v| v == 100.0) && (held[4] - 110.0).abs() < 0.01);
+        assert!((lag_ms(6554, 1.0) - 9.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn recommends_smoothing_for_noisy_axis() {
+        // 400 units of noise at rest, then a 1000 ms sweep over 0..50000
+        let rest = samples((0..2000u32).map(|i| 30_000 + [0, 200, 400, 100, 300][(i % 5) as usize]));
+        let sweep = samples((0..1000u32).map(|i| (i * 50) as u16));
+        let report = analyze(2, OFF, &rest, &sweep).unwrap();
+        assert_eq!(report.rest_noise.peak_to_peak, 400);
+        assert_eq!(report.sweep_span, 49_950);
+        assert!((report.sweep_speed - 50_000.0).abs() < 100.0);
+        assert!(report.recommended.ewma_alpha < 65535 && report.recommended_lag_ms <= MAX_LAG_MS);
+        assert!(report.recommended.deadband > 0 && report.recommended.filter_level >= 1);
+        assert!(report.rest_trace.captured.len() <= MAX_TRACE_POINTS);
+        assert_eq!(report.sweep_trace.after.len(), report.sweep_trace.t_ms.len());
+        assert_eq!(report.filter_model, FILTER_MODEL);
+        assert!(report.notes[0].contains("approximate model"));
+
+        let quiet = analyze(2, OFF, &samples(std::iter::repeat(1000).take(100)), &sweep).unwrap();
+        assert_eq!(quiet.recommended, OFF);
+        assert!(analyze(2, OFF, &rest[..5], &sweep).is_err());
+    }
+}
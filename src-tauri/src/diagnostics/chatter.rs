@@ -0,0 +1,259 @@
+//! Button chatter (bounce) analyzer.
+//!
+//! Every transition of a button is compared with that button's previous transition; one that
+//! follows within `threshold_ms` is a bounce. A release right after a press is a short press
+//! (the contact opened again), a press right after a release a re-press (a spurious double
+//! click). The analyzer sees the debounced HID stream, so any bounce it counts got past the
+//! firmware's debouncing.
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use super::{DiagnosticsError, Result};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChatterOptions {
+    /// Transitions closer than this to the previous one count as bounces
+    pub threshold_ms: u64,
+    /// Length of the test window; the test stops by itself afterwards
+    pub duration_ms: u64,
+    /// Buttons to watch; empty watches all
+    pub buttons: Vec<u8>,
+}
+
+impl Default for ChatterOptions {
+    fn default() -> Self {
+        Self { threshold_ms: 30, duration_ms: 60_000, buttons: Vec::new() }
+    }
+}
+
+impl ChatterOptions {
+    pub fn validate(&self) -> Result<()> {
+        if self.threshold_ms == 0 || self.threshold_ms > 1000 {
+            return Err(DiagnosticsError::InvalidOption(format!("threshold_ms {} is outside 1-1000", self.threshold_ms)));
+        }
+        if self.duration_ms < self.threshold_ms {
+            return Err(DiagnosticsError::InvalidOption(format!("duration_ms {} is shorter than threshold_ms", self.duration_ms)));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ButtonChatterStats {
+    pub button_id: u8,
+    pub presses: u32,
+    pub releases: u32,
+    /// Transitions within the threshold of the previous one (`short_presses + quick_represses`)
+    pub bounces: u32,
+    /// Releases within the threshold after a press
+    pub short_presses: u32,
+    /// Presses within the threshold after a release
+    pub quick_represses: u32,
+    /// Shortest interval between two transitions of this button
+    pub min_interval_ms: Option<f64>,
+    /// Bounces per press
+    pub bounce_rate: f64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ChatterReport {
+    pub running: bool,
+    pub started_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub elapsed_ms: u64,
+    pub threshold_ms: u64,
+    pub duration_ms: u64,
+    pub total_bounces: u32,
+    /// Buttons that changed during the test, worst first
+    pub buttons: Vec<ButtonChatterStats>,
+}
+
+#[derive(Debug, Default)]
+struct ButtonTrack {
+    stats: ButtonChatterStats,
+    last: Option<(Instant, bool)>,
+}
+
+struct ChatterSession {
+    options: ChatterOptions,
+    started: Instant,
+    started_at: chrono::DateTime<chrono::Utc>,
+    /// Set when stopped early; otherwise the window ends at `started + duration_ms`
+    stopped: Option<Instant>,
+    buttons: BTreeMap<u8, ButtonTrack>,
+}
+
+impl ChatterSession {
+    fn end(&self) -> Instant {
+        self.stopped.unwrap_or(self.started + Duration::from_millis(self.options.duration_ms))
+    }
+
+    fn transition(&mut self, button_id: u8, pressed: bool, at: Instant) {
+        if !self.options.buttons.is_empty() && !self.options.buttons.contains(&button_id) {
+            return;
+        }
+        let threshold = Duration::from_millis(self.options.threshold_ms);
+        let track = self.buttons.entry(button_id).or_insert_with(|| ButtonTrack {
+            stats: ButtonChatterStats { button_id, ..Default::default() },
+            last: None,
+        });
+        let stats = &mut track.stats;
+        if pressed { stats.presses += 1 } else { stats.releases += 1 }
+        if let Some((last_at, last_pressed)) = track.last {
+            let interval = at.saturating_duration_since(last_at);
+            let interval_ms = interval.as_secs_f64() * 1000.0;
+            stats.min_interval_ms = Some(stats.min_interval_ms.map_or(interval_ms, |min| min.min(interval_ms)));
+            if interval < threshold && last_pressed != pressed {
+                stats.bounces += 1;
+                if pressed { stats.quick_represses += 1 } else { stats.short_presses += 1 }
+            }
+        }
+        track.last = Some((at, pressed));
+    }
+
+    fn report(&self, now: Instant) -> ChatterReport {
+        let end = self.end();
+        let mut buttons: Vec<ButtonChatterStats> = self.buttons.values().map(|track| {
+            let mut stats = track.stats.clone();
+            stats.bounce_rate = if stats.presses == 0 { 0.0 } else { stats.bounces as f64 / stats.presses as f64 };
+            stats
+        }).collect();
+        buttons.sort_by(|a, b| b.bounces.cmp(&a.bounces).then(a.button_id.cmp(&b.button_id)));
+        ChatterReport {
+            running: now < end,
+            started_at: Some(self.started_at),
+            elapsed_ms: now.min(end).saturating_duration_since(self.started).as_millis() as u64,
+            threshold_ms: self.options.threshold_ms,
+            duration_ms: self.options.duration_ms,
+            total_bounces: buttons.iter().map(|b| b.bounces).sum(),
+            buttons,
+        }
+    }
+}
+
+/// Runs one chatter test at a time and keeps the last one's results until the next start
+#[derive(Default)]
+pub struct ChatterAnalyzer {
+    running: AtomicBool,
+    session: Mutex<Option<ChatterSession>>,
+}
+
+impl ChatterAnalyzer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::Relaxed)
+    }
+
+    /// Start a new test window, discarding the previous results
+    pub fn start(&self, options: ChatterOptions) -> Result<ChatterReport> {
+        options.validate()?;
+        let now = Instant::now();
+        let session = ChatterSession {
+            options,
+            started: now,
+            started_at: chrono::Utc::now(),
+            stopped: None,
+            buttons: BTreeMap::new(),
+        };
+        let report = session.report(now);
+        *self.session.lock().unwrap_or_else(|e| e.into_inner()) = Some(session);
+        self.running.store(true, Ordering::Relaxed);
+        log::info!("Chatter test started ({} ms window, {} ms threshold)", report.duration_ms, report.threshold_ms);
+        Ok(report)
+    }
+
+    /// End the test window early
+    pub fn stop(&self) -> Result<ChatterReport> {
+        let mut guard = self.session.lock().unwrap_or_else(|e| e.into_inner());
+        let session = guard.as_mut().ok_or(DiagnosticsError::NotStarted("chatter test"))?;
+        let now = Instant::now();
+        if now < session.end() {
+            session.stopped = Some(now);
+        }
+        self.running.store(false, Ordering::Relaxed);
+        Ok(session.report(now))
+    }
+
+    pub fn report(&self) -> Result<ChatterReport> {
+        let guard = self.session.lock().unwrap_or_else(|e| e.into_inner());
+        let session = guard.as_ref().ok_or(DiagnosticsError::NotStarted("chatter test"))?;
+        Ok(session.report(Instant::now()))
+    }
+
+    /// Feed button transitions observed at `at`; ignored once the window has ended
+    pub fn record(&self, pressed: &[u8], released: &[u8], at: Instant) {
+        let mut guard = self.session.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(session) = guard.as_mut() else { return };
+        if at >= session.end() {
+            self.running.store(false, Ordering::Relaxed);
+            return;
+        }
+        for &button_id in released {
+            session.transition(button_id, false, at);
+        }
+        for &button_id in pressed {
+            session.transition(button_id, true, at);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_bounces_under_threshold() {
+        let analyzer = ChatterAnalyzer::new();
+        analyzer.start(ChatterOptions { threshold_ms: 20, ..Default::default() }).unwrap();
+        let t0 = Instant::now();
+        let at = |ms: u64| t0 + Duration::from_millis(ms);
+        // Button 1: clean click, then a press that bounces open and a double click on release
+        analyzer.record(&[1], &[], at(0));
+        analyzer.record(&[], &[1], at(150));
+        analyzer.record(&[1], &[], at(400));
+        analyzer.record(&[], &[1], at(405));
+        analyzer.record(&[1], &[], at(412));
+        analyzer.record(&[], &[1], at(600));
+        analyzer.record(&[1], &[], at(610));
+        analyzer.record(&[], &[1], at(700));
+        // Button 2: slow clicks only
+        analyzer.record(&[2], &[], at(0));
+        analyzer.record(&[], &[2], at(100));
+
+        let report = analyzer.report().unwrap();
+        assert!(report.running);
+        assert_eq!(report.total_bounces, 3);
+        let worst = &report.buttons[0];
+        assert_eq!(worst.button_id, 1);
+        assert_eq!((worst.presses, worst.releases), (4, 4));
+        assert_eq!((worst.short_presses, worst.quick_represses), (1, 2));
+        assert_eq!(worst.min_interval_ms.map(|ms| ms.round() as u64), Some(5));
+        assert_eq!(report.buttons[1].bounces, 0);
+    }
+
+    #[test]
+    fn window_ends_and_filters_apply() {
+        let analyzer = ChatterAnalyzer::new();
+        assert!(matches!(analyzer.report(), Err(DiagnosticsError::NotStarted(_))));
+        assert!(analyzer.start(ChatterOptions { threshold_ms: 0, ..Default::default() }).is_err());
+
+        analyzer.start(ChatterOptions { duration_ms: 100, buttons: vec![3], ..Default::default() }).unwrap();
+        let t0 = Instant::now();
+        analyzer.record(&[4], &[], t0);
+        analyzer.record(&[3], &[], t0 + Duration::from_millis(200));
+        assert!(!analyzer.is_running(), "events after the window end the test");
+        let report = analyzer.report().unwrap();
+        assert!(report.buttons.is_empty());
+
+        analyzer.start(ChatterOptions::default()).unwrap();
+        let stopped = analyzer.stop().unwrap();
+        assert!(!stopped.running && !analyzer.is_running());
+    }
+}
@@ -0,0 +1,387 @@
+//! Encoder direction and detent test.
+//!
+//! Follows the two channels of one configured encoder in the raw monitor stream and decodes
+//! them the way the firmware does: the quadrature state is `A | B << 1` and every single-channel
+//! change moves the position one step. A change of both channels at once is a step the sampling
+//! missed. A detent is emitted when the position reaches a latch state of the latch mode (`four3`:
+//! state 3, `four0`: state 0, `two03`: both), so the wrong mode drops or doubles detents.
+//!
+//! The states the knob comes to rest in (no change for `rest_ms`) are its mechanical detents.
+//! They give the suggested latch mode and the number of detents the user actually turned.
+//!
+//! Levels are electrical: a pin or shift register bit is its raw value, and a matrix cell reads 0
+//! while its contact is closed, like a pull-up pin.
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+
+use super::ghost::InputSource;
+use super::{DiagnosticsError, Result};
+use crate::config::binary::{INPUT_TYPE_MATRIX, INPUT_TYPE_PIN, INPUT_TYPE_SHIFTREG};
+use crate::config::encoders::latch_mode_name;
+use crate::config::BinaryConfig;
+use crate::serial::ParsedEvent;
+
+/// Firmware latch modes: code, latch states, position shift (steps per detent as a power of two)
+const LATCH_MODES: [(u8, &[u8], u32); 3] = [(1, &[3], 2), (2, &[0], 2), (3, &[0, 3], 1)];
+/// Step direction by `old << 2 | new` (the firmware's table)
+const KNOB_DIRECTION: [i8; 16] = [0, -1, 1, 0, 1, 0, 0, -1, -1, 0, 0, 1, 0, 1, -1, 0];
+/// Rests needed before a latch mode is suggested
+const MIN_RESTS: u32 = 3;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EncoderTestOptions {
+    /// Time without a change after which the knob is at rest in a detent
+    pub rest_ms: u64,
+}
+
+impl Default for EncoderTestOptions {
+    fn default() -> Self {
+        Self { rest_ms: 40 }
+    }
+}
+
+impl EncoderTestOptions {
+    pub fn validate(&self) -> Result<()> {
+        if self.rest_ms == 0 || self.rest_ms > 1000 {
+            return Err(DiagnosticsError::InvalidOption(format!("rest_ms {} is outside 1-1000", self.rest_ms)));
+        }
+        Ok(())
+    }
+}
+
+/// The encoder under test, as configured on the device
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncoderTarget {
+    pub encoder_id: u8,
+    pub a: InputSource,
+    pub b: InputSource,
+    pub latch_mode: u8,
+    pub reversed: bool,
+}
+
+impl EncoderTarget {
+    /// Encoder `encoder_id` of `config` (numbered like `UIEncoderConfig::id`)
+    pub fn from_config(config: &BinaryConfig, encoder_id: u8) -> Result<Self> {
+        let &(a, b) = config.encoder_pairs().get(encoder_id as usize)
+            .ok_or_else(|| DiagnosticsError::InvalidOption(format!("encoder {} does not exist in the device config", encoder_id)))?;
+        let (a, b) = (&config.logical_inputs[a], &config.logical_inputs[b]);
+        Ok(Self {
+            encoder_id,
+            a: source(a.input_type, a.data)?,
+            b: source(b.input_type, b.data)?,
+            latch_mode: a.encoder_latch_mode,
+            reversed: a.reverse != 0,
+        })
+    }
+}
+
+fn source(input_type: u8, data: [u8; 2]) -> Result<InputSource> {
+    match input_type {
+        INPUT_TYPE_PIN if data[0] < 32 => Ok(InputSource::Pin { pin: data[0] }),
+        INPUT_TYPE_MATRIX => Ok(InputSource::Matrix { row: data[0], col: data[1] }),
+        INPUT_TYPE_SHIFTREG if data[1] < 8 => Ok(InputSource::ShiftReg { register: data[0], bit: data[1] }),
+        _ => Err(DiagnosticsError::InvalidOption(format!("encoder channel {:?} of input type {} is not in the raw monitor", data, input_type))),
+    }
+}
+
+/// Electrical level of `source` in `event`, `None` when the event does not concern it
+fn level(source: InputSource, event: &ParsedEvent) -> Option<bool> {
+    match (source, event) {
+        (InputSource::Pin { pin }, ParsedEvent::Gpio { mask, .. }) => Some(mask & (1u32 << pin) != 0),
+        (InputSource::Matrix { row, col }, ParsedEvent::MatrixDelta { row: r, col: c, is_connected, .. }) if (row, col) == (*r, *c) => Some(!is_connected),
+        (InputSource::ShiftReg { register, bit }, ParsedEvent::Shift { register_id, value, .. }) if register == *register_id => Some(value & (1u8 << bit) != 0),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EncoderDirection {
+    Clockwise,
+    CounterClockwise,
+    None,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EncoderTestReport {
+    pub running: bool,
+    pub encoder_id: u8,
+    pub started_at: chrono::DateTime<chrono::Utc>,
+    pub elapsed_ms: u64,
+    /// Net direction of the steps decoded so far, after the encoder's `reversed` flag
+    pub direction: EncoderDirection,
+    /// Net quadrature steps (positive is clockwise)
+    pub steps: i64,
+    pub transitions: u32,
+    /// Both channels changed between two samples: a step was lost
+    pub invalid_transitions: u32,
+    /// How often the knob came to rest in each quadrature state (`A | B << 1`)
+    pub rest_states: [u32; 4],
+    /// Detents the firmware emits with the configured latch mode
+    pub detents_cw: u32,
+    pub detents_ccw: u32,
+    /// Detents turned according to the rest states
+    pub expected_detents: u32,
+    /// Turned detents the configured latch mode did not emit
+    pub missed_detents: u32,
+    pub configured_latch_mode: String,
+    /// Latch mode matching the rest states; `None` until enough rests, or when the knob rests
+    /// between latch positions
+    pub suggested_latch_mode: Option<String>,
+    pub latch_mode_mismatch: bool,
+}
+
+struct EncoderSession {
+    target: EncoderTarget,
+    rest_us: u64,
+    started: Instant,
+    started_at: chrono::DateTime<chrono::Utc>,
+    stopped: Option<Instant>,
+    levels: [Option<bool>; 2],
+    /// Quadrature state and the firmware time it was entered
+    state: Option<(u8, u64)>,
+    position: i64,
+    transitions: u32,
+    invalid_transitions: u32,
+    rest_states: [u32; 4],
+    last_rest_position: i64,
+    /// Position change between consecutive rests
+    movements: Vec<i64>,
+    /// Detents emitted per latch mode (`LATCH_MODES` order) as (position, increments, decrements)
+    emitted: [(i64, u32, u32); 3],
+}
+
+impl EncoderSession {
+    fn new(target: EncoderTarget, options: &EncoderTestOptions) -> Self {
+        // A matrix cell is only reported when it changes, so an unseen one is open
+        let levels = [target.a, target.b].map(|source| matches!(source, InputSource::Matrix { .. }).then_some(true));
+        Self {
+            target,
+            rest_us: options.rest_ms * 1000,
+            started: Instant::now(),
+            started_at: chrono::Utc::now(),
+            stopped: None,
+            levels,
+            state: None,
+            position: 0,
+            transitions: 0,
+            invalid_transitions: 0,
+            rest_states: [0; 4],
+            last_rest_position: 0,
+            movements: Vec::new(),
+            emitted: [(0, 0, 0); 3],
+        }
+    }
+
+    fn observe(&mut self, event: &ParsedEvent) {
+        let timestamp = match *event {
+            ParsedEvent::Gpio { timestamp, .. } | ParsedEvent::MatrixDelta { timestamp, .. } | ParsedEvent::Shift { timestamp, .. } => timestamp,
+            _ => return,
+        };
+        for (i, source) in [self.target.a, self.target.b].into_iter().enumerate() {
+            if let Some(level) = level(source, event) {
+                self.levels[i] = Some(level);
+            }
+        }
+        let [Some(a), Some(b)] = self.levels else { return };
+        let new = u8::from(a) | u8::from(b) << 1;
+        let Some((old, since)) = self.state else {
+            self.state = Some((new, timestamp));
+            return;
+        };
+        if new == old {
+            return;
+        }
+        if timestamp.saturating_sub(since) >= self.rest_us {
+            self.rest(old);
+        }
+        self.transitions += 1;
+        if old ^ new == 3 {
+            self.invalid_transitions += 1;
+        }
+        self.position += i64::from(KNOB_DIRECTION[usize::from(old << 2 | new)]);
+        self.state = Some((new, timestamp));
+        for ((_, latch_states, shift), emitted) in LATCH_MODES.iter().zip(self.emitted.iter_mut()) {
+            if latch_states.contains(&new) {
+                let detent = self.position >> shift;
+                let delta = detent - emitted.0;
+                if delta > 0 { emitted.1 += delta as u32 } else { emitted.2 += (-delta) as u32 }
+                emitted.0 = detent;
+            }
+        }
+    }
+
+    fn rest(&mut self, state: u8) {
+        self.rest_states[usize::from(state)] += 1;
+        if self.position != self.last_rest_position {
+            self.movements.push(self.position - self.last_rest_position);
+            self.last_rest_position = self.position;
+        }
+    }
+
+    fn report(&self, now: Instant) -> EncoderTestReport {
+        // The knob is at rest in its current state once the test is read
+        let mut rest_states = self.rest_states;
+        let mut movements = self.movements.clone();
+        if let Some((state, _)) = self.state {
+            if self.position != self.last_rest_position {
+                rest_states[usize::from(state)] += 1;
+                movements.push(self.position - self.last_rest_position);
+            }
+        }
+        let suggested = suggest_latch_mode(&rest_states);
+        let configured = LATCH_MODES.iter().position(|(code, _, _)| *code == self.target.latch_mode);
+        let (mut detents_cw, mut detents_ccw) = configured.map_or((0, 0), |i| (self.emitted[i].1, self.emitted[i].2));
+        if self.target.reversed {
+            std::mem::swap(&mut detents_cw, &mut detents_ccw);
+        }
+        let expected_detents = match suggested.or(configured) {
+            Some(i) => {
+                let steps_per_detent = 1i64 << LATCH_MODES[i].2;
+                movements.iter().map(|m| ((m.abs() + steps_per_detent / 2) / steps_per_detent) as u32).sum()
+            }
+            None => detents_cw + detents_ccw,
+        };
+        let steps = if self.target.reversed { -self.position } else { self.position };
+        EncoderTestReport {
+            running: self.stopped.is_none(),
+            encoder_id: self.target.encoder_id,
+            started_at: self.started_at,
+            elapsed_ms: self.stopped.unwrap_or(now).saturating_duration_since(self.started).as_millis() as u64,
+            direction: match steps {
+                s if s > 0 => EncoderDirection::Clockwise,
+                s if s < 0 => EncoderDirection::CounterClockwise,
+                _ => EncoderDirection::None,
+            },
+            steps,
+            transitions: self.transitions,
+            invalid_transitions: self.invalid_transitions,
+            rest_states,
+            detents_cw,
+            detents_ccw,
+            expected_detents,
+            missed_detents: expected_detents.saturating_sub(detents_cw + detents_ccw),
+            configured_latch_mode: latch_mode_name(self.target.latch_mode),
+            suggested_latch_mode: suggested.map(|i| latch_mode_name(LATCH_MODES[i].0)),
+            latch_mode_mismatch: suggested.is_some() && suggested != configured,
+        }
+    }
+}
+
+/// Index into `LATCH_MODES` of the mode whose latch states match where the knob rests
+fn suggest_latch_mode(rest_states: &[u32; 4]) -> Option<usize> {
+    let total: u32 = rest_states.iter().sum();
+    let (low, high) = (rest_states[0], rest_states[3]);
+    // Mostly resting between latch positions: no mode fits
+    if total < MIN_RESTS || (low + high) * 4 < total * 3 {
+        return None;
+    }
+    Some(if low.min(high) * 4 >= low + high { 2 } else if high > low { 0 } else { 1 })
+}
+
+/// Runs one encoder test at a time and keeps the last one's results until the next start
+#[derive(Default)]
+pub struct EncoderTester {
+    running: AtomicBool,
+    session: Mutex<Option<EncoderSession>>,
+}
+
+impl EncoderTester {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::Relaxed)
+    }
+
+    /// Start following `target`, discarding the previous results
+    pub fn start(&self, target: EncoderTarget, options: EncoderTestOptions) -> Result<EncoderTestReport> {
+        options.validate()?;
+        let session = EncoderSession::new(target, &options);
+        let report = session.report(session.started);
+        *self.session.lock().unwrap_or_else(|e| e.into_inner()) = Some(session);
+        self.running.store(true, Ordering::Relaxed);
+        log::info!("Encoder test started for encoder {} ({})", report.encoder_id, report.configured_latch_mode);
+        Ok(report)
+    }
+
+    pub fn stop(&self) -> Result<EncoderTestReport> {
+        let mut guard = self.session.lock().unwrap_or_else(|e| e.into_inner());
+        let session = guard.as_mut().ok_or(DiagnosticsError::NotStarted("encoder test"))?;
+        let now = Instant::now();
+        session.stopped.get_or_insert(now);
+        self.running.store(false, Ordering::Relaxed);
+        Ok(session.report(now))
+    }
+
+    pub fn report(&self) -> Result<EncoderTestReport> {
+        let guard = self.session.lock().unwrap_or_else(|e| e.into_inner());
+        let session = guard.as_ref().ok_or(DiagnosticsError::NotStarted("encoder test"))?;
+        Ok(session.report(Instant::now()))
+    }
+
+    pub fn observe_raw(&self, event: &ParsedEvent) {
+        if !self.is_running() {
+            return;
+        }
+        if let Some(session) = self.session.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+            session.observe(event);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn target(latch_mode: u8) -> EncoderTarget {
+        EncoderTarget { encoder_id: 0, a: InputSource::Pin { pin: 4 }, b: InputSource::Pin { pin: 5 }, latch_mode, reversed: false }
+    }
+
+    /// Feed quadrature states (`A | B << 1`) 1 ms apart; `None` pauses long enough to rest
+    fn turn(tester: &EncoderTester, states: &[Option<u8>]) {
+        let mut t = 0u64;
+        for state in states {
+            t += match state { Some(_) => 1_000, None => 100_000 };
+            if let Some(state) = state {
+                let mask = u32::from(state & 1) << 4 | u32::from(state >> 1) << 5;
+                tester.observe_raw(&ParsedEvent::Gpio { mask, timestamp: t });
+            }
+        }
+    }
+
+    #[test]
+    fn decodes_direction_and_detents() {
+        let tester = EncoderTester::new();
+        tester.start(target(1), EncoderTestOptions::default()).unwrap();
+        // Two clockwise detents of a four3 encoder (3 -> 1 -> 0 -> 2 -> 3)
+        turn(&tester, &[Some(3), None, Some(1), Some(0), Some(2), Some(3), None, Some(1), Some(0), Some(2), Some(3), None, Some(3)]);
+        let report = tester.stop().unwrap();
+        assert_eq!((report.direction, report.steps, report.transitions), (EncoderDirection::Clockwise, 8, 8));
+        assert_eq!((report.detents_cw, report.detents_ccw, report.expected_detents), (2, 0, 2));
+        assert_eq!(report.rest_states, [0, 0, 0, 3]);
+        assert_eq!(report.suggested_latch_mode.as_deref(), Some("four3"));
+        assert!(!report.latch_mode_mismatch && report.missed_detents == 0);
+        assert!(!tester.is_running());
+    }
+
+    #[test]
+    fn suggests_two03_when_detents_are_missed() {
+        let tester = EncoderTester::new();
+        tester.start(EncoderTarget { reversed: true, ..target(1) }, EncoderTestOptions::default()).unwrap();
+        // A two03 knob turned the other way: rests alternate between 0 and 3
+        turn(&tester, &[Some(0), None, Some(1), Some(3), None, Some(2), Some(0), None, Some(1), Some(3), None, Some(2), Some(0), None]);
+        let report = tester.report().unwrap();
+        assert_eq!(report.direction, EncoderDirection::Clockwise, "reversed flag swaps the direction");
+        assert_eq!(report.expected_detents, 4);
+        assert_eq!(report.detents_cw + report.detents_ccw, 2, "four3 emits one detent per full cycle");
+        assert_eq!(report.missed_detents, 2);
+        assert_eq!(report.suggested_latch_mode.as_deref(), Some("two03"));
+        assert!(report.latch_mode_mismatch);
+    }
+}
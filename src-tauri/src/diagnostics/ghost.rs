@@ -0,0 +1,375 @@
+//! Stuck/ghost button detector.
+//!
+//! Cross-checks the raw hardware state (GPIO, matrix, shift registers from the raw monitor) with
+//! the logical HID buttons, using the device config to know which raw source drives which
+//! button. A button whose HID state disagrees with its sources for longer than `grace_ms` is
+//! reported once as a [`MappingAnomaly`] until the two agree again. The grace period absorbs the
+//! different latencies of the serial and HID paths.
+//!
+//! Only `normal` inputs are checked: momentary and encoder inputs produce pulses that do not
+//! follow the raw level. Sources whose raw state has not been seen yet are skipped.
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use super::{DiagnosticsError, Result};
+use crate::config::binary::{BEHAVIOR_NORMAL, INPUT_TYPE_MATRIX, INPUT_TYPE_PIN, INPUT_TYPE_SHIFTREG};
+use crate::config::BinaryConfig;
+use crate::serial::ParsedEvent;
+
+/// Anomalies kept for `GhostDetectorStatus::recent`
+const RECENT_ANOMALIES: usize = 50;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GhostDetectorOptions {
+    /// GPIO inputs read LOW when active
+    pub gpio_pull_up: bool,
+    /// Shift register bits read 0 when active
+    pub shift_reg_pull_up: bool,
+    /// How long raw and HID state may disagree before it is reported
+    pub grace_ms: u64,
+}
+
+impl Default for GhostDetectorOptions {
+    fn default() -> Self {
+        Self { gpio_pull_up: true, shift_reg_pull_up: true, grace_ms: 100 }
+    }
+}
+
+/// Raw hardware source of a logical input
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum InputSource {
+    Pin { pin: u8 },
+    Matrix { row: u8, col: u8 },
+    ShiftReg { register: u8, bit: u8 },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WatchedInput {
+    pub button_id: u8,
+    pub source: InputSource,
+    pub reverse: bool,
+}
+
+impl WatchedInput {
+    /// The `normal` logical inputs of `config`
+    pub fn from_config(config: &BinaryConfig) -> Vec<Self> {
+        config.logical_inputs.iter()
+            .filter(|input| input.behavior == BEHAVIOR_NORMAL)
+            .filter_map(|input| {
+                let source = match input.input_type {
+                    INPUT_TYPE_PIN => InputSource::Pin { pin: input.data[0] },
+                    INPUT_TYPE_MATRIX => InputSource::Matrix { row: input.data[0], col: input.data[1] },
+                    INPUT_TYPE_SHIFTREG => InputSource::ShiftReg { register: input.data[0], bit: input.data[1] },
+                    _ => return None,
+                };
+                Some(Self { button_id: input.joy_button_id, source, reverse: input.reverse != 0 })
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnomalyKind {
+    /// HID reports the button pressed while none of its sources is active (ghost press)
+    LogicalWithoutRaw,
+    /// A source is active but HID reports the button released (stuck or unmapped input)
+    RawWithoutLogical,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MappingAnomaly {
+    pub kind: AnomalyKind,
+    pub button_id: u8,
+    /// Sources configured for the button; for `RawWithoutLogical` only the active ones
+    pub sources: Vec<InputSource>,
+    /// How long the states had disagreed when reported
+    pub duration_ms: u64,
+    pub detected_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct GhostDetectorStatus {
+    pub running: bool,
+    pub watched_buttons: usize,
+    pub anomaly_count: u32,
+    /// Latest anomalies, oldest first
+    pub recent: Vec<MappingAnomaly>,
+}
+
+#[derive(Debug, Default)]
+struct RawSnapshot {
+    gpio: Option<u32>,
+    matrix: HashMap<(u8, u8), bool>,
+    shift: HashMap<u8, u8>,
+}
+
+impl RawSnapshot {
+    fn apply(&mut self, event: &ParsedEvent) {
+        match *event {
+            ParsedEvent::Gpio { mask, .. } => self.gpio = Some(mask),
+            ParsedEvent::MatrixDelta { row, col, is_connected, .. } => {
+                self.matrix.insert((row, col), is_connected);
+            }
+            ParsedEvent::Shift { register_id, value, .. } => {
+                self.shift.insert(register_id, value);
+            }
+            _ => {}
+        }
+    }
+
+    /// Whether `input` is active, `None` while its source has not been reported
+    fn is_active(&self, input: &WatchedInput, options: &GhostDetectorOptions) -> Option<bool> {
+        let level = match input.source {
+            InputSource::Pin { pin } if pin < 32 => {
+                let high = self.gpio? & (1u32 << pin) != 0;
+                high != options.gpio_pull_up
+            }
+            InputSource::Pin { .. } => return None,
+            InputSource::Matrix { row, col } => *self.matrix.get(&(row, col))?,
+            InputSource::ShiftReg { register, bit } if bit < 8 => {
+                let set = self.shift.get(&register)? & (1u8 << bit) != 0;
+                set != options.shift_reg_pull_up
+            }
+            InputSource::ShiftReg { .. } => return None,
+        };
+        Some(level != input.reverse)
+    }
+}
+
+type AnomalySink = Box<dyn Fn(&MappingAnomaly) + Send + Sync>;
+
+struct GhostSession {
+    options: GhostDetectorOptions,
+    inputs: BTreeMap<u8, Vec<WatchedInput>>,
+    raw: RawSnapshot,
+    pressed: HashSet<u8>,
+    mismatch_since: HashMap<u8, Instant>,
+    reported: HashSet<u8>,
+    anomaly_count: u32,
+    recent: VecDeque<MappingAnomaly>,
+    sink: AnomalySink,
+}
+
+impl GhostSession {
+    /// Compare raw and logical state of every watched button; returns newly detected anomalies
+    fn check(&mut self, now: Instant) -> Vec<MappingAnomaly> {
+        let grace = Duration::from_millis(self.options.grace_ms);
+        let mut found = Vec::new();
+        for (&button_id, inputs) in &self.inputs {
+            let states: Vec<(InputSource, bool)> = inputs.iter()
+                .filter_map(|input| self.raw.is_active(input, &self.options).map(|active| (input.source, active)))
+                .collect();
+            if states.is_empty() {
+                continue;
+            }
+            let raw_active = states.iter().any(|&(_, active)| active);
+            let logical = self.pressed.contains(&button_id);
+            if raw_active == logical {
+                self.mismatch_since.remove(&button_id);
+                self.reported.remove(&button_id);
+                continue;
+            }
+            let since = *self.mismatch_since.entry(button_id).or_insert(now);
+            let elapsed = now.saturating_duration_since(since);
+            if elapsed < grace || !self.reported.insert(button_id) {
+                continue;
+            }
+            let (kind, sources) = if logical {
+                (AnomalyKind::LogicalWithoutRaw, inputs.iter().map(|input| input.source).collect())
+            } else {
+                (AnomalyKind::RawWithoutLogical, states.iter().filter(|(_, active)| *active).map(|&(source, _)| source).collect())
+            };
+            found.push(MappingAnomaly {
+                kind,
+                button_id,
+                sources,
+                duration_ms: elapsed.as_millis() as u64,
+                detected_at: chrono::Utc::now(),
+            });
+        }
+        for anomaly in &found {
+            log::warn!("Mapping anomaly on button {}: {:?} {:?}", anomaly.button_id, anomaly.kind, anomaly.sources);
+            (self.sink)(anomaly);
+            self.anomaly_count += 1;
+            if self.recent.len() == RECENT_ANOMALIES {
+                self.recent.pop_front();
+            }
+            self.recent.push_back(anomaly.clone());
+        }
+        found
+    }
+}
+
+/// Runs at most one cross-check at a time
+#[derive(Default)]
+pub struct GhostDetector {
+    running: AtomicBool,
+    session: Mutex<Option<GhostSession>>,
+}
+
+impl GhostDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::Relaxed)
+    }
+
+    pub fn status(&self) -> GhostDetectorStatus {
+        let guard = self.session.lock().unwrap_or_else(|e| e.into_inner());
+        match guard.as_ref() {
+            Some(session) => GhostDetectorStatus {
+                running: self.is_running(),
+                watched_buttons: session.inputs.len(),
+                anomaly_count: session.anomaly_count,
+                recent: session.recent.iter().cloned().collect(),
+            },
+            None => GhostDetectorStatus::default(),
+        }
+    }
+
+    /// Start cross-checking `inputs`; `pressed` seeds the HID buttons already held down and
+    /// `sink` receives every anomaly as it is detected
+    pub fn start(
+        &self,
+        inputs: Vec<WatchedInput>,
+        options: GhostDetectorOptions,
+        pressed: &[u8],
+        sink: impl Fn(&MappingAnomaly) + Send + Sync + 'static,
+    ) -> Result<GhostDetectorStatus> {
+        if inputs.is_empty() {
+            return Err(DiagnosticsError::InvalidOption("the device config has no pin, matrix or shift register buttons".into()));
+        }
+        let mut grouped: BTreeMap<u8, Vec<WatchedInput>> = BTreeMap::new();
+        for input in inputs {
+            grouped.entry(input.button_id).or_default().push(input);
+        }
+        let session = GhostSession {
+            options,
+            inputs: grouped,
+            raw: RawSnapshot::default(),
+            pressed: pressed.iter().copied().collect(),
+            mismatch_since: HashMap::new(),
+            reported: HashSet::new(),
+            anomaly_count: 0,
+            recent: VecDeque::new(),
+            sink: Box::new(sink),
+        };
+        *self.session.lock().unwrap_or_else(|e| e.into_inner()) = Some(session);
+        self.running.store(true, Ordering::Relaxed);
+        log::info!("Ghost button detector started");
+        Ok(self.status())
+    }
+
+    pub fn stop(&self) -> GhostDetectorStatus {
+        self.running.store(false, Ordering::Relaxed);
+        let status = self.status();
+        if let Some(session) = self.session.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+            // Keep the results for `status`, but release the event sink
+            session.sink = Box::new(|_| {});
+        }
+        status
+    }
+
+    pub fn observe_buttons(&self, pressed: &[u8], released: &[u8]) {
+        self.with_session(|session| {
+            session.pressed.extend(pressed.iter().copied());
+            for button_id in released {
+                session.pressed.remove(button_id);
+            }
+        });
+    }
+
+    pub fn observe_raw(&self, event: &ParsedEvent) {
+        self.with_session(|session| session.raw.apply(event));
+    }
+
+    /// Run the comparison; called periodically while running
+    pub fn check(&self, now: Instant) -> Vec<MappingAnomaly> {
+        let mut found = Vec::new();
+        self.with_session(|session| found = session.check(now));
+        found
+    }
+
+    fn with_session(&self, f: impl FnOnce(&mut GhostSession)) {
+        if !self.is_running() {
+            return;
+        }
+        if let Some(session) = self.session.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+            f(session);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    fn inputs() -> Vec<WatchedInput> {
+        vec![
+            WatchedInput { button_id: 0, source: InputSource::Pin { pin: 2 }, reverse: false },
+            WatchedInput { button_id: 1, source: InputSource::Matrix { row: 1, col: 0 }, reverse: false },
+            WatchedInput { button_id: 2, source: InputSource::ShiftReg { register: 0, bit: 3 }, reverse: true },
+        ]
+    }
+
+    #[test]
+    fn reports_disagreements_after_grace_period() {
+        let detector = GhostDetector::new();
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let sink_seen = seen.clone();
+        detector.start(inputs(), GhostDetectorOptions::default(), &[], move |a| sink_seen.lock().unwrap().push(a.button_id)).unwrap();
+        let t0 = Instant::now();
+        let at = |ms: u64| t0 + Duration::from_millis(ms);
+
+        // Pull-up GPIO: pin 2 LOW is active, and HID agrees after 30 ms
+        detector.observe_raw(&ParsedEvent::Gpio { mask: !(1 << 2), timestamp: 0 });
+        assert!(detector.check(at(0)).is_empty());
+        detector.observe_buttons(&[0], &[]);
+        assert!(detector.check(at(30)).is_empty());
+
+        // Matrix cell closed, HID never follows: stuck/unmapped input
+        detector.observe_raw(&ParsedEvent::MatrixDelta { row: 1, col: 0, is_connected: true, timestamp: 0 });
+        assert!(detector.check(at(40)).is_empty(), "within the grace period");
+        let found = detector.check(at(150));
+        assert_eq!(found.len(), 1);
+        assert_eq!((found[0].kind, found[0].button_id), (AnomalyKind::RawWithoutLogical, 1));
+        assert_eq!(found[0].sources, vec![InputSource::Matrix { row: 1, col: 0 }]);
+        assert!(detector.check(at(300)).is_empty(), "reported once until resolved");
+
+        // Reversed shift bit: pull-up 0 is active, reversed makes it inactive; HID pressed is a ghost
+        detector.observe_raw(&ParsedEvent::Shift { register_id: 0, value: 0x00, timestamp: 0 });
+        detector.observe_buttons(&[2], &[]);
+        detector.check(at(300));
+        let found = detector.check(at(450));
+        assert_eq!((found[0].kind, found[0].button_id), (AnomalyKind::LogicalWithoutRaw, 2));
+
+        let status = detector.stop();
+        assert_eq!(status.anomaly_count, 2);
+        assert_eq!(*seen.lock().unwrap(), vec![1, 2]);
+        assert!(detector.check(at(1000)).is_empty());
+    }
+
+    #[test]
+    fn builds_inputs_from_config() {
+        use crate::config::StoredLogicalInput;
+        let input = |input_type, behavior, button, data| StoredLogicalInput {
+            input_type, behavior, joy_button_id: button, reverse: 0, encoder_latch_mode: 0, reserved: [0; 3], data,
+        };
+        let mut config = BinaryConfig::new();
+        config.logical_inputs = vec![input(0, 0, 0, [5, 0]), input(1, 0, 1, [2, 3]), input(0, 2, 2, [6, 0])];
+        let watched = WatchedInput::from_config(&config);
+        assert_eq!(watched.len(), 2, "encoder channels are skipped");
+        assert_eq!(watched[1].source, InputSource::Matrix { row: 2, col: 3 });
+        assert!(GhostDetector::new().start(Vec::new(), GhostDetectorOptions::default(), &[], |_| {}).is_err());
+    }
+}
@@ -0,0 +1,238 @@
+//! End-to-end input latency benchmark.
+//!
+//! Two measurements over the same window:
+//! - Raw monitor: each GPIO/matrix/shift line carries the firmware timestamp (µs since boot).
+//!   Host and firmware clocks share no epoch, so a line's latency is its host-minus-firmware
+//!   offset relative to the smallest offset seen: the delay above the fastest line of the run.
+//! - HID frames: the 16-bit little-endian frame counter at `frame_counter_offset` of the input
+//!   report gives dropped frames (counter gaps, see [`frame_step`]) and the host-side interval
+//!   between reports.
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use super::{DiagnosticsError, Result};
+
+/// Counter steps of at least this size are a firmware restart (wrapped backwards jump), not a loss
+const RESET_STEP: u16 = 0x8000;
+
+/// How the HID frame counter moved between two consecutive reports
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameStep {
+    Next,
+    Repeated,
+    Reset,
+    /// Frames lost in between
+    Gap(u64),
+}
+
+/// Classify a counter step; shared with the HID link statistics (`crate::hid::link`)
+pub fn frame_step(previous: u16, counter: u16) -> FrameStep {
+    match counter.wrapping_sub(previous) {
+        0 => FrameStep::Repeated,
+        1 => FrameStep::Next,
+        step if step >= RESET_STEP => FrameStep::Reset,
+        step => FrameStep::Gap(u64::from(step - 1)),
+    }
+}
+
+/// Samples kept per source; later samples of a long run are dropped
+const MAX_SAMPLES: usize = 200_000;
+pub const DEFAULT_BENCHMARK_SECS: u64 = 10;
+pub const MAX_BENCHMARK_SECS: u64 = 120;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LatencyStats {
+    pub samples: u64,
+    pub avg_ms: f64,
+    pub p95_ms: f64,
+    pub max_ms: f64,
+    /// Standard deviation
+    pub jitter_ms: f64,
+}
+
+impl LatencyStats {
+    /// `None` without samples
+    pub fn from_samples(mut values: Vec<f64>) -> Option<Self> {
+        if values.is_empty() {
+            return None;
+        }
+        values.sort_by(|a, b| a.total_cmp(b));
+        let n = values.len() as f64;
+        let avg = values.iter().sum::<f64>() / n;
+        let variance = values.iter().map(|v| (v - avg).powi(2)).sum::<f64>() / n;
+        // Nearest-rank percentile
+        let p95_index = ((0.95 * n).ceil() as usize).clamp(1, values.len()) - 1;
+        Some(Self {
+            samples: values.len() as u64,
+            avg_ms: avg,
+            p95_ms: values[p95_index],
+            max_ms: values[values.len() - 1],
+            jitter_ms: variance.sqrt(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HidFrameStats {
+    pub reports: u64,
+    /// Frames missing between consecutive reports according to the frame counter
+    pub missed_frames: u64,
+    /// Host-side time between consecutive reports
+    pub interval: Option<LatencyStats>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LatencyReport {
+    pub duration_ms: u64,
+    /// Raw monitor latency above the run's fastest line; `None` without raw monitor traffic
+    pub raw_monitor: Option<LatencyStats>,
+    /// `None` when no HID frame counter was received
+    pub hid_frames: Option<HidFrameStats>,
+    /// Why a measurement is missing or may be unreliable
+    pub notes: Vec<String>,
+}
+
+#[derive(Default)]
+struct Samples {
+    /// (host receive time, firmware timestamp in µs)
+    raw: Vec<(Instant, u64)>,
+    /// (host receive time, frame counter)
+    hid: Vec<(Instant, u16)>,
+}
+
+/// Collects samples for one benchmark run at a time
+#[derive(Default)]
+pub struct LatencyBenchmark {
+    running: AtomicBool,
+    samples: Mutex<Samples>,
+}
+
+impl LatencyBenchmark {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::Relaxed)
+    }
+
+    /// Start collecting; fails while another run is in progress
+    pub fn begin(&self) -> Result<()> {
+        if self.running.swap(true, Ordering::SeqCst) {
+            return Err(DiagnosticsError::AlreadyRunning("latency benchmark"));
+        }
+        *self.samples.lock().unwrap_or_else(|e| e.into_inner()) = Samples::default();
+        Ok(())
+    }
+
+    /// Stop collecting and analyze what was collected
+    pub fn finish(&self, duration: Duration) -> LatencyReport {
+        self.running.store(false, Ordering::SeqCst);
+        let samples = std::mem::take(&mut *self.samples.lock().unwrap_or_else(|e| e.into_inner()));
+        analyze(&samples.raw, &samples.hid, duration)
+    }
+
+    pub fn record_raw(&self, firmware_us: u64, at: Instant) {
+        let mut samples = self.samples.lock().unwrap_or_else(|e| e.into_inner());
+        if samples.raw.len() < MAX_SAMPLES {
+            samples.raw.push((at, firmware_us));
+        }
+    }
+
+    pub fn record_hid_frame(&self, counter: u16, at: Instant) {
+        let mut samples = self.samples.lock().unwrap_or_else(|e| e.into_inner());
+        if samples.hid.len() < MAX_SAMPLES {
+            samples.hid.push((at, counter));
+        }
+    }
+}
+
+fn analyze(raw: &[(Instant, u64)], hid: &[(Instant, u16)], duration: Duration) -> LatencyReport {
+    let mut notes = Vec::new();
+
+    let raw_monitor = match raw.first() {
+        Some(&(host_start, _)) => {
+            // Host-minus-firmware offset of each line in µs; only differences between them matter
+            let offsets: Vec<i128> = raw.iter()
+                .map(|&(at, fw)| at.duration_since(host_start).as_micros() as i128 - fw as i128)
+                .collect();
+            let best = offsets.iter().copied().min().unwrap_or_default();
+            if raw.windows(2).any(|pair| pair[1].1 < pair[0].1) {
+                notes.push("Firmware timestamps went backwards (device reset?); raw latency is unreliable".into());
+            }
+            LatencyStats::from_samples(offsets.iter().map(|&offset| (offset - best) as f64 / 1000.0).collect())
+        }
+        None => {
+            notes.push("No raw monitor lines received; start raw monitoring (raw or both display mode)".into());
+            None
+        }
+    };
+
+    let hid_frames = if hid.is_empty() {
+        notes.push("No HID frame counter received; the device or firmware does not report one".into());
+        None
+    } else {
+        let mut missed = 0u64;
+        let mut intervals = Vec::with_capacity(hid.len().saturating_sub(1));
+        for pair in hid.windows(2) {
+            let (prev_at, prev_counter) = pair[0];
+            let (at, counter) = pair[1];
+            if let FrameStep::Gap(lost) = frame_step(prev_counter, counter) {
+                missed += lost;
+            }
+            intervals.push(at.saturating_duration_since(prev_at).as_secs_f64() * 1000.0);
+        }
+        Some(HidFrameStats { reports: hid.len() as u64, missed_frames: missed, interval: LatencyStats::from_samples(intervals) })
+    };
+
+    LatencyReport { duration_ms: duration.as_millis() as u64, raw_monitor, hid_frames, notes }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summarizes_samples() {
+        let stats = LatencyStats::from_samples((1..=20).map(f64::from).collect()).unwrap();
+        assert_eq!((stats.samples, stats.p95_ms, stats.max_ms), (20, 19.0, 20.0));
+        assert!((stats.avg_ms - 10.5).abs() < 1e-9);
+        assert!((stats.jitter_ms - 5.766).abs() < 1e-3);
+        assert!(LatencyStats::from_samples(Vec::new()).is_none());
+    }
+
+    #[test]
+    fn measures_raw_offsets_and_frame_gaps() {
+        let bench = LatencyBenchmark::new();
+        bench.begin().unwrap();
+        assert!(matches!(bench.begin(), Err(DiagnosticsError::AlreadyRunning(_))));
+        let t0 = Instant::now();
+        let at = |us: u64| t0 + Duration::from_micros(us);
+        // Firmware clock starts at 5 s; lines arrive 1 ms, 3 ms and 1 ms after they were stamped
+        bench.record_raw(5_000_000, at(1_000));
+        bench.record_raw(5_010_000, at(13_000));
+        bench.record_raw(5_020_000, at(21_000));
+        // Counter wraps and skips two frames
+        bench.record_hid_frame(65_534, at(0));
+        bench.record_hid_frame(65_535, at(1_000));
+        bench.record_hid_frame(2, at(2_000));
+
+        let report = bench.finish(Duration::from_secs(1));
+        assert!(!bench.is_running());
+        let raw = report.raw_monitor.unwrap();
+        assert_eq!(raw.samples, 3);
+        assert!((raw.max_ms - 2.0).abs() < 1e-9, "latency above the fastest line");
+        let hid = report.hid_frames.unwrap();
+        assert_eq!((hid.reports, hid.missed_frames), (3, 2));
+        assert!((hid.interval.unwrap().avg_ms - 1.0).abs() < 1e-9);
+        assert!(report.notes.is_empty());
+
+        bench.begin().unwrap();
+        let empty = bench.finish(Duration::from_secs(1));
+        assert!(empty.raw_monitor.is_none() && empty.hid_frames.is_none());
+        assert_eq!(empty.notes.len(), 2);
+    }
+}
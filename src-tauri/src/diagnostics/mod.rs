@@ -0,0 +1,148 @@
+//! Input diagnostics built on the live HID and raw monitor streams.
+//!
+//! - [`axis_tuning`]: axis noise and lag measurement with model-based filter setting recommendations
+//! - [`chatter`]: per-button bounce measurement over a test window
+//! - [`encoder`]: quadrature direction, detent and latch mode test for one encoder
+//! - [`ghost`]: raw hardware vs HID button cross-check (Both display mode)
+//! - [`latency`]: firmware-to-host latency and HID frame loss benchmark
+pub mod axis_tuning;
+pub mod chatter;
+pub mod encoder;
+pub mod ghost;
+pub mod latency;
+
+pub use axis_tuning::{AxisFilterSettings, AxisSampler, AxisTuningReport};
+pub use chatter::{ButtonChatterStats, ChatterAnalyzer, ChatterOptions, ChatterReport};
+pub use encoder::{EncoderTarget, EncoderTestOptions, EncoderTestReport, EncoderTester};
+pub use ghost::{GhostDetector, GhostDetectorOptions, GhostDetectorStatus, MappingAnomaly, WatchedInput};
+pub use latency::{LatencyBenchmark, LatencyReport};
+
+use crate::serial::ParsedEvent;
+
+#[derive(Debug, thiserror::Error)]
+pub enum DiagnosticsError {
+    #[error("No {0} has been started")]
+    NotStarted(&'static str),
+
+    #[error("Invalid diagnostics option: {0}")]
+    InvalidOption(String),
+
+    #[error("A {0} is already running")]
+    AlreadyRunning(&'static str),
+
+    #[error("{0}")]
+    Unavailable(String),
+}
+
+pub type Result<T> = std::result::Result<T, DiagnosticsError>;
+
+/// Global chatter analyzer fed by the HID reader
+static CHATTER: once_cell::sync::Lazy<ChatterAnalyzer> = once_cell::sync::Lazy::new(ChatterAnalyzer::new);
+
+/// Get the global chatter analyzer
+pub fn get_chatter_analyzer() -> &'static ChatterAnalyzer {
+    &CHATTER
+}
+
+/// Global encoder tester fed by the raw monitor
+static ENCODER: once_cell::sync::Lazy<EncoderTester> = once_cell::sync::Lazy::new(EncoderTester::new);
+
+/// Get the global encoder tester
+pub fn get_encoder_tester() -> &'static EncoderTester {
+    &ENCODER
+}
+
+/// Global ghost button detector fed by the HID reader and the raw monitor
+static GHOST: once_cell::sync::Lazy<GhostDetector> = once_cell::sync::Lazy::new(GhostDetector::new);
+
+/// Get the global ghost button detector
+pub fn get_ghost_detector() -> &'static GhostDetector {
+    &GHOST
+}
+
+/// Start the global ghost detector and its periodic check, which ends when the detector stops
+pub fn start_ghost_detector(
+    inputs: Vec<WatchedInput>,
+    options: GhostDetectorOptions,
+    pressed: &[u8],
+    sink: impl Fn(&MappingAnomaly) + Send + Sync + 'static,
+) -> Result<GhostDetectorStatus> {
+    let period = std::time::Duration::from_millis((options.grace_ms / 2).clamp(10, 250));
+    let already_checking = GHOST.is_running();
+    let status = GHOST.start(inputs, options, pressed, sink)?;
+    if !already_checking {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            while GHOST.is_running() {
+                interval.tick().await;
+                GHOST.check(std::time::Instant::now());
+            }
+        });
+    }
+    Ok(status)
+}
+
+/// Global latency benchmark collector
+static LATENCY: once_cell::sync::Lazy<LatencyBenchmark> = once_cell::sync::Lazy::new(LatencyBenchmark::new);
+
+/// Get the global latency benchmark collector
+pub fn get_latency_benchmark() -> &'static LatencyBenchmark {
+    &LATENCY
+}
+
+/// Forward button transitions to running diagnostics (no-op when none runs)
+pub fn feed_buttons(pressed: &[u8], released: &[u8], at: std::time::Instant) {
+    if CHATTER.is_running() {
+        CHATTER.record(pressed, released, at);
+    }
+    if GHOST.is_running() {
+        GHOST.observe_buttons(pressed, released);
+    }
+}
+
+/// Forward a raw monitor event to running diagnostics (no-op when none runs)
+pub fn feed_raw(event: &ParsedEvent) {
+    if GHOST.is_running() {
+        GHOST.observe_raw(event);
+    }
+    if ENCODER.is_running() {
+        ENCODER.observe_raw(event);
+    }
+    if LATENCY.is_running() {
+        if let ParsedEvent::Gpio { timestamp, .. } | ParsedEvent::MatrixDelta { timestamp, .. } | ParsedEvent::Shift { timestamp, .. } = event {
+            LATENCY.record_raw(*timestamp, std::time::Instant::now());
+        }
+    }
+}
+
+/// Global axis sampler for filter tuning
+static AXIS_SAMPLER: once_cell::sync::Lazy<AxisSampler> = once_cell::sync::Lazy::new(AxisSampler::new);
+
+/// Get the global axis sampler
+pub fn get_axis_sampler() -> &'static AxisSampler {
+    &AXIS_SAMPLER
+}
+
+/// Whether the HID reader should decode axis values
+pub fn wants_hid_axes() -> bool {
+    AXIS_SAMPLER.is_running()
+}
+
+/// Forward the axis values of a received HID report (no-op unless tuning)
+pub fn feed_axes(values: &[u16], at: std::time::Instant) {
+    if AXIS_SAMPLER.is_running() {
+        AXIS_SAMPLER.record(values, at);
+    }
+}
+
+/// Whether the HID reader should extract frame counters
+pub fn wants_hid_frames() -> bool {
+    LATENCY.is_running()
+}
+
+/// Forward the frame counter of a received HID report (no-op unless benchmarking)
+pub fn feed_hid_frame(counter: u16, at: std::time::Instant) {
+    if LATENCY.is_running() {
+        LATENCY.record_hid_frame(counter, at);
+    }
+}
@@ -0,0 +1,282 @@
+//! Error type returned by every Tauri command.
+//!
+//! Serialized as `{ "code": "NOT_CONNECTED", "message": "...", "context": "Failed to ..." }` so the
+//! frontend can branch on `code` instead of parsing message text.
+use serde::Serialize;
+
+use crate::device::DeviceError;
+use crate::diagnostics::DiagnosticsError;
+use crate::flasher::FlashError;
+use crate::hid::HidError;
+use crate::interop::BridgeError;
+use crate::recording::RecordingError;
+use crate::serial::SerialError;
+use crate::update::UpdateError;
+
+/// Machine-readable error category
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    NotFound,
+    NotConnected,
+    AlreadyConnected,
+    ConnectionFailed,
+    Timeout,
+    Protocol,
+    Unsupported,
+    InvalidArgument,
+    InvalidConfiguration,
+    Parse,
+    Io,
+    Serial,
+    Hid,
+    Network,
+    RateLimited,
+    NoUpdateAvailable,
+    InvalidSignature,
+    UnsignedFirmware,
+    DownloadInterrupted,
+    Cancelled,
+    Update,
+    Flash,
+    Busy,
+    InvalidState,
+    Internal,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AppError {
+    pub code: ErrorCode,
+    /// Underlying error description
+    pub message: String,
+    /// What the command was doing when it failed, e.g. "Failed to connect to device"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context: Option<String>,
+}
+
+pub type AppResult<T> = std::result::Result<T, AppError>;
+
+impl AppError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self { code, message: message.into(), context: None }
+    }
+
+    pub fn invalid_argument(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::InvalidArgument, message)
+    }
+
+    pub fn invalid_configuration(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::InvalidConfiguration, message)
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Internal, message)
+    }
+
+    pub fn with_context(mut self, context: impl Into<String>) -> Self {
+        self.context = Some(context.into());
+        self
+    }
+}
+
+impl std::fmt::Display for AppError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.context {
+            Some(context) => write!(f, "{}: {}", context, self.message),
+            None => f.write_str(&self.message),
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
+
+/// Attach a context string while converting into [`AppError`]
+pub trait ResultExt<T> {
+    fn context(self, context: &str) -> AppResult<T>;
+}
+
+impl<T, E: Into<AppError>> ResultExt<T> for std::result::Result<T, E> {
+    fn context(self, context: &str) -> AppResult<T> {
+        self.map_err(|e| e.into().with_context(context))
+    }
+}
+
+fn io_code(e: &std::io::Error) -> ErrorCode {
+    match e.kind() {
+        std::io::ErrorKind::TimedOut => ErrorCode::Timeout,
+        std::io::ErrorKind::NotFound => ErrorCode::NotFound,
+        std::io::ErrorKind::AddrInUse => ErrorCode::Busy,
+        _ => ErrorCode::Io,
+    }
+}
+
+impl From<std::io::Error> for AppError {
+    fn from(e: std::io::Error) -> Self {
+        Self::new(io_code(&e), e.to_string())
+    }
+}
+
+impl From<SerialError> for AppError {
+    fn from(e: SerialError) -> Self {
+        let code = match &e {
+            SerialError::PortNotFound(_) => ErrorCode::NotFound,
+            SerialError::ConnectionFailed(_) => ErrorCode::ConnectionFailed,
+            SerialError::Timeout => ErrorCode::Timeout,
+            SerialError::ProtocolError(_) => ErrorCode::Protocol,
+            SerialError::Unsupported(_) => ErrorCode::Unsupported,
+            SerialError::FileNotFound(_) => ErrorCode::NotFound,
+            SerialError::IoError(io) => io_code(io),
+            SerialError::SerialportError(_) => ErrorCode::Serial,
+        };
+        Self::new(code, e.to_string())
+    }
+}
+
+impl From<FlashError> for AppError {
+    fn from(e: FlashError) -> Self {
+        let code = match &e {
+            FlashError::Io(io) => io_code(io),
+            FlashError::InvalidImage(_) => ErrorCode::InvalidArgument,
+            FlashError::DriveNotFound(_) => ErrorCode::Timeout,
+            FlashError::Bootloader(_) => ErrorCode::Flash,
+        };
+        Self::new(code, e.to_string())
+    }
+}
+
+impl From<DeviceError> for AppError {
+    fn from(e: DeviceError) -> Self {
+        let code = match e {
+            DeviceError::SerialError(inner) => return inner.into(),
+            DeviceError::FlashError(inner) => return inner.into(),
+            DeviceError::IoError(inner) => return inner.into(),
+            DeviceError::NotFound => ErrorCode::NotFound,
+            DeviceError::AlreadyConnected => ErrorCode::AlreadyConnected,
+            DeviceError::NotConnected => ErrorCode::NotConnected,
+            DeviceError::InvalidConfiguration(_) => ErrorCode::InvalidConfiguration,
+            DeviceError::UpdateError(_) => ErrorCode::Update,
+            DeviceError::ProtocolError(_) => ErrorCode::Protocol,
+        };
+        Self::new(code, e.to_string())
+    }
+}
+
+impl From<HidError> for AppError {
+    fn from(e: HidError) -> Self {
+        let code = match &e {
+            HidError::DeviceNotFound => ErrorCode::NotFound,
+            HidError::InvalidData => ErrorCode::Parse,
+            HidError::HidApiError(_) | HidError::ReadError => ErrorCode::Hid,
+            HidError::InvalidReport(_) => ErrorCode::InvalidArgument,
+            HidError::Unsupported(_) => ErrorCode::Unsupported,
+            HidError::Timeout => ErrorCode::Timeout,
+        };
+        Self::new(code, e.to_string())
+    }
+}
+
+impl From<UpdateError> for AppError {
+    fn from(e: UpdateError) -> Self {
+        let code = match &e {
+            UpdateError::Network(err) if err.is_timeout() => ErrorCode::Timeout,
+            UpdateError::Network(_) => ErrorCode::Network,
+            UpdateError::Io(io) => io_code(io),
+            UpdateError::Version(_) | UpdateError::Json(_) | UpdateError::Parse(_) => ErrorCode::Parse,
+            UpdateError::NoUpdateAvailable => ErrorCode::NoUpdateAvailable,
+            UpdateError::InvalidSignature => ErrorCode::InvalidSignature,
+            UpdateError::DownloadInterrupted => ErrorCode::DownloadInterrupted,
+            UpdateError::Cancelled => ErrorCode::Cancelled,
+            UpdateError::UnsignedFirmware(_) => ErrorCode::UnsignedFirmware,
+            UpdateError::RateLimited { .. } => ErrorCode::RateLimited,
+        };
+        Self::new(code, e.to_string())
+    }
+}
+
+impl From<RecordingError> for AppError {
+    fn from(e: RecordingError) -> Self {
+        let code = match &e {
+            RecordingError::Io(io) => io_code(io),
+            RecordingError::Json(_) | RecordingError::InvalidFile(_) => ErrorCode::Parse,
+            RecordingError::AlreadyRecording => ErrorCode::Busy,
+            RecordingError::NotRecording => ErrorCode::InvalidState,
+        };
+        Self::new(code, e.to_string())
+    }
+}
+
+impl From<BridgeError> for AppError {
+    fn from(e: BridgeError) -> Self {
+        let code = match &e {
+            BridgeError::Io(io) => io_code(io),
+            BridgeError::Unavailable(_) | BridgeError::Unsupported => ErrorCode::Unsupported,
+            BridgeError::InvalidOption(_) => ErrorCode::InvalidArgument,
+        };
+        Self::new(code, e.to_string())
+    }
+}
+
+impl From<DiagnosticsError> for AppError {
+    fn from(e: DiagnosticsError) -> Self {
+        let code = match &e {
+            DiagnosticsError::NotStarted(_) | DiagnosticsError::Unavailable(_) => ErrorCode::InvalidState,
+            DiagnosticsError::InvalidOption(_) => ErrorCode::InvalidArgument,
+            DiagnosticsError::AlreadyRunning(_) => ErrorCode::Busy,
+        };
+        Self::new(code, e.to_string())
+    }
+}
+
+impl From<uuid::Error> for AppError {
+    fn from(e: uuid::Error) -> Self {
+        Self::invalid_argument(e.to_string())
+    }
+}
+
+impl From<semver::Error> for AppError {
+    fn from(e: semver::Error) -> Self {
+        Self::invalid_argument(e.to_string())
+    }
+}
+
+impl From<chrono::ParseError> for AppError {
+    fn from(e: chrono::ParseError) -> Self {
+        Self::invalid_argument(e.to_string())
+    }
+}
+
+impl From<tauri::Error> for AppError {
+    fn from(e: tauri::Error) -> Self {
+        Self::internal(e.to_string())
+    }
+}
+
+impl From<serde_json::Error> for AppError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::new(ErrorCode::Parse, e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_nested_device_errors_to_specific_codes() {
+        let err: AppError = DeviceError::SerialError(SerialError::Timeout).into();
+        assert_eq!(err.code, ErrorCode::Timeout);
+        let err: AppError = DeviceError::NotConnected.into();
+        assert_eq!(err.code, ErrorCode::NotConnected);
+    }
+
+    #[test]
+    fn serializes_code_and_context() {
+        let result: std::result::Result<(), DeviceError> = Err(DeviceError::NotConnected);
+        let err = result.context("Failed to read axis config").unwrap_err();
+        let json = serde_json::to_value(&err).unwrap();
+        assert_eq!(json["code"], "NOT_CONNECTED");
+        assert_eq!(json["message"], "Device not connected");
+        assert_eq!(json["context"], "Failed to read axis config");
+        assert_eq!(err.to_string(), "Failed to read axis config: Device not connected");
+    }
+}
@@ -0,0 +1,29 @@
+pub mod models;
+pub mod uf2;
+
+pub use models::*;
+pub use uf2::{validate_uf2, find_uf2_drive, wait_for_uf2_drive, wait_for_bootloader, wait_for_port_gone, port_present, bootsel_touch, copy_to_drive, copy_to_drive_with_progress, Uf2Image};
+
+/// Serial command asking the firmware to reboot into the RP2040 UF2 bootloader
+pub const BOOTLOADER_COMMAND: &str = "BOOTLOADER";
+/// How long to wait for the UF2 drive after requesting the bootloader
+pub const UF2_DRIVE_TIMEOUT_MS: u64 = 15000;
+/// How long the device gets to leave its serial port after the bootloader command
+pub const BOOTLOADER_CONFIRM_MS: u64 = 2000;
+
+#[derive(Debug, thiserror::Error)]
+pub enum FlashError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Invalid UF2 image: {0}")]
+    InvalidImage(String),
+
+    #[error("UF2 drive did not appear within {0} ms")]
+    DriveNotFound(u64),
+
+    #[error("Failed to enter bootloader: {0}")]
+    Bootloader(String),
+}
+
+pub type Result<T> = std::result::Result<T, FlashError>;
@@ -0,0 +1,50 @@
+use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+
+/// Phase of a flash operation, reported in `flash_progress` events
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FlashStage {
+    RebootingToBootloader,
+    WaitingForDrive,
+    Copying,
+    Complete,
+}
+
+/// Payload of the `flash_started` event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlashStarted {
+    pub device_id: Option<String>,
+    pub image_path: PathBuf,
+    pub size_bytes: u64,
+}
+
+/// Payload of the `flash_progress` event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlashProgress {
+    pub stage: FlashStage,
+    pub bytes_written: u64,
+    pub total_bytes: u64,
+    pub percentage: f64,
+}
+
+/// Payload of the `flash_complete` event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlashComplete {
+    pub success: bool,
+    pub error: Option<String>,
+    pub drive_path: Option<PathBuf>,
+    pub duration_ms: u64,
+}
+
+impl FlashProgress {
+    pub fn stage(stage: FlashStage, total_bytes: u64) -> Self {
+        let bytes_written = if stage == FlashStage::Complete { total_bytes } else { 0 };
+        Self::bytes(stage, bytes_written, total_bytes)
+    }
+
+    pub fn bytes(stage: FlashStage, bytes_written: u64, total_bytes: u64) -> Self {
+        let percentage = if total_bytes > 0 { (bytes_written as f64 / total_bytes as f64) * 100.0 } else { 0.0 };
+        Self { stage, bytes_written, total_bytes, percentage }
+    }
+}
@@ -0,0 +1,210 @@
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use super::{FlashError, Result};
+
+/// UF2 block layout (see https://github.com/microsoft/uf2)
+pub const UF2_BLOCK_SIZE: usize = 512;
+pub const UF2_MAGIC_START0: u32 = 0x0A32_4655;
+pub const UF2_MAGIC_START1: u32 = 0x9E5D_5157;
+pub const UF2_MAGIC_END: u32 = 0x0AB1_6F30;
+/// Marker file present at the root of the RP2040 BOOTSEL mass-storage drive
+pub const UF2_INFO_FILE: &str = "INFO_UF2.TXT";
+pub const RP2040_VOLUME_LABEL: &str = "RPI-RP2";
+/// Opening the CDC port at this baud rate reboots arduino-pico / TinyUSB firmware into BOOTSEL
+pub const BOOTSEL_TOUCH_BAUD: u32 = 1200;
+pub const UF2_DRIVE_POLL_MS: u64 = 250;
+/// Short, since a restarting device can be back on the same port within a second
+pub const PORT_GONE_POLL_MS: u64 = 50;
+/// Copy granularity for progress reporting (multiple of the UF2 block size)
+pub const UF2_COPY_CHUNK_BYTES: usize = 32 * UF2_BLOCK_SIZE;
+
+/// Summary of a validated UF2 file
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Uf2Image {
+    pub path: PathBuf,
+    pub size_bytes: u64,
+    pub block_count: u32,
+}
+
+/// Check that `path` is a well-formed UF2 image (magic numbers on every block)
+pub fn validate_uf2(path: &Path) -> Result<Uf2Image> {
+    let data = std::fs::read(path)?;
+    if data.is_empty() || data.len() % UF2_BLOCK_SIZE != 0 {
+        return Err(FlashError::InvalidImage(format!("size {} is not a multiple of {} bytes", data.len(), UF2_BLOCK_SIZE)));
+    }
+    let word = |block: &[u8], off: usize| u32::from_le_bytes([block[off], block[off + 1], block[off + 2], block[off + 3]]);
+    for (i, block) in data.chunks(UF2_BLOCK_SIZE).enumerate() {
+        if word(block, 0) != UF2_MAGIC_START0 || word(block, 4) != UF2_MAGIC_START1 || word(block, UF2_BLOCK_SIZE - 4) != UF2_MAGIC_END {
+            return Err(FlashError::InvalidImage(format!("bad magic in block {}", i)));
+        }
+    }
+    Ok(Uf2Image {
+        path: path.to_path_buf(),
+        size_bytes: data.len() as u64,
+        block_count: (data.len() / UF2_BLOCK_SIZE) as u32,
+    })
+}
+
+/// Ask the firmware to reboot into BOOTSEL using the 1200 baud "touch".
+/// Fallback for firmware that does not understand the serial bootloader command.
+pub fn bootsel_touch(port_name: &str) -> Result<()> {
+    let port = serialport::new(port_name, BOOTSEL_TOUCH_BAUD)
+        .timeout(Duration::from_millis(200))
+        .open()
+        .map_err(|e| FlashError::Bootloader(e.to_string()))?;
+    if let Err(e) = port.clear(serialport::ClearBuffer::All) {
+        log::debug!("Ignoring clear error during bootloader touch: {}", e);
+    }
+    drop(port);
+    log::info!("Requested BOOTSEL reboot on {}", port_name);
+    Ok(())
+}
+
+/// Candidate directories that may hold mounted removable volumes on this platform
+fn mount_roots() -> Vec<PathBuf> {
+    let mut roots = Vec::new();
+    #[cfg(target_os = "windows")]
+    {
+        for letter in b'D'..=b'Z' {
+            roots.push(PathBuf::from(format!("{}:\\", letter as char)));
+        }
+    }
+    #[cfg(target_os = "macos")]
+    {
+        roots.push(PathBuf::from("/Volumes").join(RP2040_VOLUME_LABEL));
+    }
+    #[cfg(target_os = "linux")]
+    {
+        let user = std::env::var("USER").unwrap_or_default();
+        for base in ["/media", "/run/media"] {
+            if !user.is_empty() {
+                roots.push(PathBuf::from(base).join(&user).join(RP2040_VOLUME_LABEL));
+            }
+            roots.push(PathBuf::from(base).join(RP2040_VOLUME_LABEL));
+        }
+        roots.push(PathBuf::from("/mnt").join(RP2040_VOLUME_LABEL));
+    }
+    roots
+}
+
+/// Locate a mounted UF2 bootloader drive, if any
+pub fn find_uf2_drive() -> Option<PathBuf> {
+    mount_roots().into_iter().find(|root| root.join(UF2_INFO_FILE).is_file())
+}
+
+/// Whether a serial port named `port_name` is enumerated; assumed present when ports cannot be listed
+pub fn port_present(port_name: &str) -> bool {
+    serialport::available_ports().map_or(true, |ports| ports.iter().any(|p| p.port_name == port_name))
+}
+
+/// Poll until `port_name` is no longer enumerated; false when it still is after `timeout_ms`
+pub async fn wait_for_port_gone(port_name: &str, timeout_ms: u64) -> bool {
+    let start = Instant::now();
+    loop {
+        let port = port_name.to_string();
+        if !tokio::task::spawn_blocking(move || port_present(&port)).await.unwrap_or(true) {
+            return true;
+        }
+        if start.elapsed() >= Duration::from_millis(timeout_ms) {
+            return false;
+        }
+        tokio::time::sleep(Duration::from_millis(PORT_GONE_POLL_MS)).await;
+    }
+}
+
+/// Poll until the device has left `port_name` for the bootloader: the port is gone or a UF2 drive
+/// has mounted. False when neither happens within `timeout_ms`.
+pub async fn wait_for_bootloader(port_name: &str, timeout_ms: u64) -> bool {
+    let start = Instant::now();
+    loop {
+        let port = port_name.to_string();
+        let entered = tokio::task::spawn_blocking(move || !port_present(&port) || find_uf2_drive().is_some())
+            .await
+            .unwrap_or(false);
+        if entered {
+            return true;
+        }
+        if start.elapsed() >= Duration::from_millis(timeout_ms) {
+            return false;
+        }
+        tokio::time::sleep(Duration::from_millis(UF2_DRIVE_POLL_MS)).await;
+    }
+}
+
+/// Poll for the UF2 drive to enumerate and mount
+pub async fn wait_for_uf2_drive(timeout_ms: u64) -> Result<PathBuf> {
+    let start = Instant::now();
+    loop {
+        if let Some(drive) = tokio::task::spawn_blocking(find_uf2_drive).await.ok().flatten() {
+            log::info!("Found UF2 drive at {:?}", drive);
+            return Ok(drive);
+        }
+        if start.elapsed() >= Duration::from_millis(timeout_ms) {
+            return Err(FlashError::DriveNotFound(timeout_ms));
+        }
+        tokio::time::sleep(Duration::from_millis(UF2_DRIVE_POLL_MS)).await;
+    }
+}
+
+/// Copy the image onto the UF2 drive; the device reboots once the last block is written
+pub async fn copy_to_drive(image: &Path, drive: &Path) -> Result<u64> {
+    copy_to_drive_with_progress(image, drive, |_, _| {}).await
+}
+
+/// Chunked copy onto the UF2 drive, reporting `(bytes_written, total_bytes)` after each chunk
+pub async fn copy_to_drive_with_progress<F>(image: &Path, drive: &Path, progress: F) -> Result<u64>
+where
+    F: Fn(u64, u64) + Send + Sync,
+{
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let file_name = image.file_name().map(|n| n.to_os_string()).unwrap_or_else(|| "firmware.uf2".into());
+    let target = drive.join(file_name);
+    let total = tokio::fs::metadata(image).await?.len();
+    let mut src = tokio::fs::File::open(image).await?;
+    let mut dst = tokio::fs::File::create(&target).await?;
+    let mut buf = vec![0u8; UF2_COPY_CHUNK_BYTES];
+    let mut written = 0u64;
+    loop {
+        let n = src.read(&mut buf).await?;
+        if n == 0 { break; }
+        dst.write_all(&buf[..n]).await?;
+        written += n as u64;
+        progress(written, total);
+    }
+    // The bootloader may reboot (and unmount) as soon as the final block lands; ignore flush errors then
+    if let Err(e) = dst.flush().await {
+        log::debug!("Ignoring flush error after UF2 copy: {}", e);
+    }
+    log::info!("Copied {} bytes to {:?}", written, target);
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block() -> Vec<u8> {
+        let mut b = vec![0u8; UF2_BLOCK_SIZE];
+        b[0..4].copy_from_slice(&UF2_MAGIC_START0.to_le_bytes());
+        b[4..8].copy_from_slice(&UF2_MAGIC_START1.to_le_bytes());
+        b[UF2_BLOCK_SIZE - 4..].copy_from_slice(&UF2_MAGIC_END.to_le_bytes());
+        b
+    }
+
+    #[test]
+    fn validates_uf2_blocks() {
+        let path = std::env::temp_dir().join(format!("joycore_test_{}.uf2", uuid::Uuid::new_v4()));
+        let mut data = block();
+        data.extend(block());
+        std::fs::write(&path, &data).unwrap();
+        let image = validate_uf2(&path).unwrap();
+        assert_eq!(image.block_count, 2);
+
+        data[UF2_BLOCK_SIZE] = 0;
+        std::fs::write(&path, &data).unwrap();
+        assert!(validate_uf2(&path).is_err());
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -0,0 +1,107 @@
+//! Device control over HID feature reports.
+//!
+//! Firmware with HID controls answers a GET of feature report [`CONTROL_REPORT_ID`] with the
+//! controls it implements, and acts on a SET of the same report:
+//!
+//! ```text
+//! GET  05 | version:u8 | supported:u16 LE
+//! SET  05 | opcode:u8 | argument:u8
+//! ```
+//!
+//! Firmware without the report fails the GET, so every control is reported unsupported.
+use serde::Serialize;
+
+pub const CONTROL_REPORT_ID: u8 = 5;
+/// Largest feature report the app sends, report ID included (full-speed HID)
+pub const MAX_FEATURE_REPORT_LEN: usize = 64;
+/// Longest identify blink the firmware accepts
+pub const MAX_IDENTIFY_SECS: u8 = 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HidControl {
+    /// Blink the status LED so the board can be told apart from others
+    Identify,
+    /// Report every input as pressed in turn, for checking the host side end to end
+    TestMode,
+}
+
+impl HidControl {
+    pub const ALL: [HidControl; 2] = [HidControl::Identify, HidControl::TestMode];
+
+    fn bit(self) -> u16 {
+        match self {
+            Self::Identify => 0x0001,
+            Self::TestMode => 0x0002,
+        }
+    }
+
+    fn opcode(self) -> u8 {
+        match self {
+            Self::Identify => 0x01,
+            Self::TestMode => 0x02,
+        }
+    }
+
+    /// SET report requesting this control
+    pub fn request(self, argument: u8) -> [u8; 3] {
+        [CONTROL_REPORT_ID, self.opcode(), argument]
+    }
+}
+
+/// Controls the connected firmware implements
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct HidControlSupport {
+    /// Control report version; `None` when the firmware has no control report
+    pub version: Option<u8>,
+    pub controls: Vec<HidControl>,
+}
+
+impl HidControlSupport {
+    /// Parse a GET of the control report (report ID included)
+    pub fn parse(report: &[u8]) -> Option<Self> {
+        let [id, version, lo, hi, ..] = *report else { return None };
+        if id != CONTROL_REPORT_ID || version == 0 {
+            return None;
+        }
+        let supported = u16::from_le_bytes([lo, hi]);
+        let controls = HidControl::ALL.into_iter().filter(|c| supported & c.bit() != 0).collect();
+        Some(Self { version: Some(version), controls })
+    }
+
+    pub fn supports(&self, control: HidControl) -> bool {
+        self.controls.contains(&control)
+    }
+}
+
+/// Ask an opened interface which controls it implements
+pub fn query_support(dev: &hidapi::HidDevice) -> HidControlSupport {
+    let mut buf = [0u8; 1 + 3];
+    buf[0] = CONTROL_REPORT_ID;
+    match dev.get_feature_report(&mut buf) {
+        Ok(len) => HidControlSupport::parse(&buf[..len]).unwrap_or_default(),
+        Err(e) => {
+            log::debug!("Control feature report unavailable: {}", e);
+            HidControlSupport::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_supported_controls() {
+        let support = HidControlSupport::parse(&[5, 1, 0x02, 0x00]).unwrap();
+        assert_eq!(support.version, Some(1));
+        assert!(!support.supports(HidControl::Identify));
+        assert!(support.supports(HidControl::TestMode));
+
+        assert_eq!(HidControlSupport::parse(&[5, 1, 0x03, 0x00]).unwrap().controls, HidControl::ALL.to_vec());
+        assert!(HidControlSupport::parse(&[3, 1, 0x03, 0x00]).is_none());
+        assert!(HidControlSupport::parse(&[5, 0, 0x03, 0x00]).is_none());
+        assert!(HidControlSupport::parse(&[5, 1]).is_none());
+        assert_eq!(HidControl::Identify.request(10), [5, 1, 10]);
+    }
+}
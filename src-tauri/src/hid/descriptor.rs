@@ -0,0 +1,353 @@
+//! HID report descriptor parsing.
+//!
+//! Firmware without the mapping feature reports (IDs 3/4) still describes its input report in
+//! the report descriptor. Walking the descriptor's short items gives the exact bit position of
+//! every button and axis, so the reader can decode such devices like mapped ones instead of
+//! guessing the button offset from report changes.
+use std::collections::HashMap;
+
+use thiserror::Error;
+
+const USAGE_PAGE_GENERIC_DESKTOP: u16 = 0x01;
+const USAGE_PAGE_BUTTON: u16 = 0x09;
+/// Generic Desktop X, Y, Z, Rx, Ry, Rz, Slider, Dial, Wheel
+const AXIS_USAGES: std::ops::RangeInclusive<u16> = 0x30..=0x38;
+const USAGE_HAT_SWITCH: u16 = 0x39;
+/// Buttons beyond this are not tracked
+const MAX_LAYOUT_BUTTONS: usize = 128;
+/// Elements taken from one input item; guards against bogus report counts
+const MAX_ITEM_ELEMENTS: u32 = 1024;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum DescriptorError {
+    #[error("Report descriptor truncated at byte {0}")]
+    Truncated(usize),
+
+    #[error("Report descriptor pops more global state than it pushes")]
+    UnbalancedPop,
+
+    #[error("Report descriptor declares no input buttons")]
+    NoButtons,
+
+    #[error("Buttons of input report {0} are not contiguous")]
+    ButtonsNotContiguous(u8),
+}
+
+/// One variable, non-constant element of an input report
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InputElement {
+    /// 0 when the descriptor declares no report IDs
+    pub report_id: u8,
+    /// Position in the report, after the report ID byte
+    pub bit_offset: u32,
+    pub bit_size: u32,
+    pub usage_page: u16,
+    pub usage: u16,
+    pub logical_min: i32,
+    pub logical_max: i32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AxisField {
+    pub usage: u16,
+    pub bit_offset: u32,
+    pub bit_size: u32,
+    pub logical_min: i32,
+    pub logical_max: i32,
+}
+
+/// Hat switch; values outside the logical range mean centered
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HatField {
+    pub bit_offset: u32,
+    pub bit_size: u32,
+    pub logical_min: i32,
+    pub logical_max: i32,
+}
+
+/// Button, axis and hat positions of the input report that carries the buttons
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReportLayout {
+    pub report_id: u8,
+    /// Bit position of the first button, after the report ID byte
+    pub button_bit_offset: u32,
+    /// `buttons[bit]` = zero-based button number (button usage - 1) of the bit at
+    /// `button_bit_offset + bit`
+    pub buttons: Vec<u8>,
+    /// Ordered by position in the report
+    pub axes: Vec<AxisField>,
+    /// Ordered by position in the report
+    pub hats: Vec<HatField>,
+    /// Input report length without the report ID byte
+    pub report_bytes: usize,
+}
+
+#[derive(Clone, Copy, Default)]
+struct Globals {
+    usage_page: u16,
+    logical_min: i32,
+    logical_max: i32,
+    report_size: u32,
+    report_id: u8,
+    report_count: u32,
+}
+
+#[derive(Default)]
+struct Locals {
+    /// (page, id) in declaration order
+    usages: Vec<(u16, u16)>,
+    usage_min: Option<(u16, u16)>,
+    usage_max: Option<u16>,
+}
+
+impl Locals {
+    /// Usage of the `index`-th element; the last usage repeats for the remaining elements
+    fn usage(&self, index: u32) -> (u16, u16) {
+        if let Some(&usage) = self.usages.get(index as usize).or(self.usages.last()) {
+            return usage;
+        }
+        match (self.usage_min, self.usage_max) {
+            (Some((page, min)), Some(max)) => (page, min.saturating_add(index.min(u16::MAX as u32) as u16).min(max.max(min))),
+            (Some(usage), None) => usage,
+            _ => (0, 0),
+        }
+    }
+}
+
+fn unsigned(data: &[u8]) -> u32 {
+    data.iter().rev().fold(0, |value, &byte| value << 8 | byte as u32)
+}
+
+fn signed(data: &[u8]) -> i32 {
+    match data.len() {
+        1 => data[0] as i8 as i32,
+        2 => i16::from_le_bytes([data[0], data[1]]) as i32,
+        4 => i32::from_le_bytes([data[0], data[1], data[2], data[3]]),
+        _ => 0,
+    }
+}
+
+/// Split a usage item into (page, id); 4-byte usages carry their own page
+fn usage_of(data: &[u8], page: u16) -> (u16, u16) {
+    let value = unsigned(data);
+    if data.len() == 4 { ((value >> 16) as u16, value as u16) } else { (page, value as u16) }
+}
+
+/// All variable, non-constant input elements of the descriptor
+pub fn input_elements(descriptor: &[u8]) -> Result<Vec<InputElement>, DescriptorError> {
+    parse(descriptor).map(|(elements, _)| elements)
+}
+
+/// Input elements plus the total input bits per report ID (padding included)
+fn parse(descriptor: &[u8]) -> Result<(Vec<InputElement>, HashMap<u8, u32>), DescriptorError> {
+    let mut elements = Vec::new();
+    let mut globals = Globals::default();
+    let mut stack: Vec<Globals> = Vec::new();
+    let mut locals = Locals::default();
+    let mut offsets: HashMap<u8, u32> = HashMap::new();
+    let mut pos = 0;
+    while pos < descriptor.len() {
+        let prefix = descriptor[pos];
+        if prefix == 0xFE {
+            // Long item: size byte, tag byte, data; none are defined for reports
+            let size = *descriptor.get(pos + 1).ok_or(DescriptorError::Truncated(pos))? as usize;
+            pos += 3 + size;
+            continue;
+        }
+        let size = [0, 1, 2, 4][(prefix & 0x03) as usize];
+        let data = descriptor.get(pos + 1..pos + 1 + size).ok_or(DescriptorError::Truncated(pos))?;
+        pos += 1 + size;
+        match (prefix >> 2) & 0x03 {
+            // Main items
+            0 => {
+                // Input
+                if prefix >> 4 == 0x8 {
+                    let flags = unsigned(data);
+                    let constant = flags & 0x01 != 0;
+                    let variable = flags & 0x02 != 0;
+                    let offset = offsets.entry(globals.report_id).or_insert(0);
+                    if variable && !constant {
+                        for index in 0..globals.report_count.min(MAX_ITEM_ELEMENTS) {
+                            let (usage_page, usage) = locals.usage(index);
+                            elements.push(InputElement {
+                                report_id: globals.report_id,
+                                bit_offset: offset.saturating_add(index.saturating_mul(globals.report_size)),
+                                bit_size: globals.report_size,
+                                usage_page,
+                                usage,
+                                logical_min: globals.logical_min,
+                                logical_max: globals.logical_max,
+                            });
+                        }
+                    }
+                    *offset = offset.saturating_add(globals.report_size.saturating_mul(globals.report_count));
+                }
+                locals = Locals::default();
+            }
+            // Global items
+            1 => match prefix >> 4 {
+                0x0 => globals.usage_page = unsigned(data) as u16,
+                0x1 => globals.logical_min = signed(data),
+                0x2 => {
+                    globals.logical_max = signed(data);
+                    // Common descriptors declare e.g. 0..255 in one byte; read unsigned then
+                    if globals.logical_min >= 0 && globals.logical_max < globals.logical_min {
+                        globals.logical_max = unsigned(data) as i32;
+                    }
+                }
+                0x7 => globals.report_size = unsigned(data),
+                0x8 => globals.report_id = unsigned(data) as u8,
+                0x9 => globals.report_count = unsigned(data),
+                0xA => stack.push(globals),
+                0xB => globals = stack.pop().ok_or(DescriptorError::UnbalancedPop)?,
+                _ => {}
+            },
+            // Local items
+            2 => match prefix >> 4 {
+                0x0 => locals.usages.push(usage_of(data, globals.usage_page)),
+                0x1 => locals.usage_min = Some(usage_of(data, globals.usage_page)),
+                0x2 => locals.usage_max = Some(usage_of(data, globals.usage_page).1),
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+    Ok((elements, offsets))
+}
+
+/// Locate buttons, axes and hats in the input report with the most buttons
+pub fn input_layout(descriptor: &[u8]) -> Result<ReportLayout, DescriptorError> {
+    let (elements, report_bits) = parse(descriptor)?;
+    let is_button = |e: &&InputElement| e.usage_page == USAGE_PAGE_BUTTON && e.bit_size == 1;
+    let mut button_counts: HashMap<u8, usize> = HashMap::new();
+    for element in elements.iter().filter(is_button) {
+        *button_counts.entry(element.report_id).or_insert(0) += 1;
+    }
+    // Lowest report ID wins a tie so the choice is deterministic
+    let report_id = button_counts
+        .iter()
+        .max_by_key(|&(&id, &count)| (count, std::cmp::Reverse(id)))
+        .map(|(&id, _)| id)
+        .ok_or(DescriptorError::NoButtons)?;
+    let report: Vec<&InputElement> = elements.iter().filter(|e| e.report_id == report_id).collect();
+
+    let mut buttons: Vec<&InputElement> = report.iter().copied().filter(|e| is_button(e)).collect();
+    buttons.sort_by_key(|e| e.bit_offset);
+    buttons.truncate(MAX_LAYOUT_BUTTONS);
+    let button_bit_offset = buttons[0].bit_offset;
+    if buttons.iter().enumerate().any(|(i, e)| e.bit_offset != button_bit_offset + i as u32) {
+        return Err(DescriptorError::ButtonsNotContiguous(report_id));
+    }
+
+    let mut axes: Vec<AxisField> = report
+        .iter()
+        .filter(|e| e.usage_page == USAGE_PAGE_GENERIC_DESKTOP && AXIS_USAGES.contains(&e.usage))
+        .map(|e| AxisField {
+            usage: e.usage,
+            bit_offset: e.bit_offset,
+            bit_size: e.bit_size,
+            logical_min: e.logical_min,
+            logical_max: e.logical_max,
+        })
+        .collect();
+    axes.sort_by_key(|axis| axis.bit_offset);
+
+    let mut hats: Vec<HatField> = report
+        .iter()
+        .filter(|e| e.usage_page == USAGE_PAGE_GENERIC_DESKTOP && e.usage == USAGE_HAT_SWITCH)
+        .map(|e| HatField {
+            bit_offset: e.bit_offset,
+            bit_size: e.bit_size,
+            logical_min: e.logical_min,
+            logical_max: e.logical_max,
+        })
+        .collect();
+    hats.sort_by_key(|hat| hat.bit_offset);
+
+    Ok(ReportLayout {
+        report_id,
+        button_bit_offset,
+        buttons: buttons.iter().enumerate().map(|(i, e)| e.usage.checked_sub(1).unwrap_or(i as u16).min(u8::MAX as u16) as u8).collect(),
+        axes,
+        hats,
+        report_bytes: report_bits.get(&report_id).copied().unwrap_or(0).div_ceil(8) as usize,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Joystick with report ID 1: six 16-bit axes, 32 buttons, one padding byte
+    const JOYSTICK: &[u8] = &[
+        0x05, 0x01, // Usage Page (Generic Desktop)
+        0x09, 0x04, // Usage (Joystick)
+        0xA1, 0x01, // Collection (Application)
+        0x85, 0x01, //   Report ID (1)
+        0x09, 0x30, 0x09, 0x31, 0x09, 0x32, 0x09, 0x33, 0x09, 0x34, 0x09, 0x35, // X Y Z Rx Ry Rz
+        0x16, 0x00, 0x80, //   Logical Minimum (-32768)
+        0x26, 0xFF, 0x7F, //   Logical Maximum (32767)
+        0x75, 0x10, //   Report Size (16)
+        0x95, 0x06, //   Report Count (6)
+        0x81, 0x02, //   Input (Data, Var, Abs)
+        0x05, 0x09, //   Usage Page (Button)
+        0x19, 0x01, //   Usage Minimum (1)
+        0x29, 0x20, //   Usage Maximum (32)
+        0x15, 0x00, //   Logical Minimum (0)
+        0x25, 0x01, //   Logical Maximum (1)
+        0x75, 0x01, //   Report Size (1)
+        0x95, 0x20, //   Report Count (32)
+        0x81, 0x02, //   Input (Data, Var, Abs)
+        0x75, 0x08, //   Report Size (8)
+        0x95, 0x01, //   Report Count (1)
+        0x81, 0x03, //   Input (Const) padding
+        0xC0, // End Collection
+    ];
+
+    #[test]
+    fn locates_buttons_and_axes() {
+        let layout = input_layout(JOYSTICK).unwrap();
+        assert_eq!(layout.report_id, 1);
+        assert_eq!(layout.button_bit_offset, 96);
+        assert_eq!(layout.buttons, (0..32).collect::<Vec<u8>>());
+        assert_eq!(layout.axes.len(), 6);
+        assert_eq!((layout.axes[1].usage, layout.axes[1].bit_offset, layout.axes[1].bit_size), (0x31, 16, 16));
+        assert_eq!((layout.axes[0].logical_min, layout.axes[0].logical_max), (-32768, 32767));
+        assert_eq!(layout.report_bytes, 17);
+    }
+
+    #[test]
+    fn reads_unsigned_logical_max_and_rejects_truncation() {
+        // Hat-less gamepad without report IDs: 4-bit padding before 8 buttons, 8-bit axis 0..255
+        let descriptor = [
+            0x05, 0x01, 0x09, 0x05, 0xA1, 0x01,
+            0x75, 0x04, 0x95, 0x01, 0x81, 0x01, // 4-bit constant
+            0x05, 0x09, 0x19, 0x01, 0x29, 0x08, 0x75, 0x01, 0x95, 0x08, 0x81, 0x02,
+            0x05, 0x01, 0x09, 0x30, 0x15, 0x00, 0x25, 0xFF, 0x75, 0x08, 0x95, 0x01, 0x81, 0x02,
+            0xC0,
+        ];
+        let layout = input_layout(&descriptor).unwrap();
+        assert_eq!((layout.report_id, layout.button_bit_offset, layout.buttons.len()), (0, 4, 8));
+        assert_eq!((layout.axes[0].bit_offset, layout.axes[0].logical_max), (12, 255));
+        assert_eq!(input_layout(&descriptor[..descriptor.len() - 3]).unwrap().report_bytes, 2);
+        assert_eq!(input_elements(&[0x26, 0xFF]), Err(DescriptorError::Truncated(0)));
+        assert_eq!(input_layout(&JOYSTICK[..28]), Err(DescriptorError::NoButtons));
+    }
+
+    #[test]
+    fn locates_hat_switch() {
+        // 8 buttons, then a 4-bit 8-way hat with a null state and 4 bits of padding
+        let descriptor = [
+            0x05, 0x01, 0x09, 0x05, 0xA1, 0x01,
+            0x05, 0x09, 0x19, 0x01, 0x29, 0x08, 0x15, 0x00, 0x25, 0x01, 0x75, 0x01, 0x95, 0x08, 0x81, 0x02,
+            0x05, 0x01, 0x09, 0x39, 0x15, 0x00, 0x25, 0x07, 0x75, 0x04, 0x95, 0x01, 0x81, 0x42,
+            0x75, 0x04, 0x95, 0x01, 0x81, 0x03,
+            0xC0,
+        ];
+        let layout = input_layout(&descriptor).unwrap();
+        assert_eq!(layout.hats, vec![HatField { bit_offset: 8, bit_size: 4, logical_min: 0, logical_max: 7 }]);
+        assert!(layout.axes.is_empty());
+        assert_eq!(layout.report_bytes, 2);
+        assert!(input_layout(JOYSTICK).unwrap().hats.is_empty());
+    }
+}
@@ -0,0 +1,112 @@
+//! Hat switch (POV) decoding.
+//!
+//! Hats are located like buttons: from the report descriptor (Generic Desktop usage Hat Switch)
+//! or, for firmware mapping reports, as `hat_count` bytes right after the button bitmap, each
+//! holding 0-7 clockwise from up and any other value when centered. Every position change is
+//! emitted as [`HAT_CHANGED_EVENT`].
+use serde::{Deserialize, Serialize};
+
+use super::descriptor::HatField;
+
+pub const HAT_CHANGED_EVENT: &str = "hat-changed";
+/// Hats decoded per report
+pub const MAX_HATS: usize = 4;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HatPosition {
+    #[default]
+    Centered,
+    Up,
+    UpRight,
+    Right,
+    DownRight,
+    Down,
+    DownLeft,
+    Left,
+    UpLeft,
+}
+
+impl HatPosition {
+    /// Directions clockwise from up, 45° apart
+    const DIRECTIONS: [HatPosition; 8] = [
+        HatPosition::Up,
+        HatPosition::UpRight,
+        HatPosition::Right,
+        HatPosition::DownRight,
+        HatPosition::Down,
+        HatPosition::DownLeft,
+        HatPosition::Left,
+        HatPosition::UpLeft,
+    ];
+
+    /// Degrees clockwise from up; None when centered
+    pub fn angle(self) -> Option<u16> {
+        Self::DIRECTIONS.iter().position(|&d| d == self).map(|step| step as u16 * 45)
+    }
+}
+
+/// Event payload of `hat-changed`
+#[derive(Debug, Clone, Serialize)]
+pub struct HatEvent {
+    /// Hat number in report order
+    pub hat_id: u8,
+    pub position: HatPosition,
+    pub angle: Option<u16>,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+}
+
+impl HatEvent {
+    pub fn new(hat_id: u8, position: HatPosition, timestamp: chrono::DateTime<chrono::Utc>) -> Self {
+        Self { hat_id, position, angle: position.angle(), timestamp }
+    }
+}
+
+/// Fields of `count` firmware-reported hats, one byte each starting at byte `offset`
+pub fn firmware_hats(offset: usize, count: u8) -> Vec<HatField> {
+    (0..(count as usize).min(MAX_HATS))
+        .map(|i| HatField { bit_offset: ((offset + i) * 8) as u32, bit_size: 8, logical_min: 0, logical_max: 7 })
+        .collect()
+}
+
+/// Position of `hat` in a report payload; None when the payload is too short. A 4-position hat
+/// (logical range of 4 values) steps 90° per value.
+pub fn read_hat(payload: &[u8], hat: &HatField) -> Option<HatPosition> {
+    if hat.bit_size == 0 || hat.bit_size > 16 || (hat.bit_offset + hat.bit_size).div_ceil(8) as usize > payload.len() {
+        return None;
+    }
+    let mut value = 0i32;
+    for bit in 0..hat.bit_size {
+        let position = hat.bit_offset + bit;
+        if payload[(position / 8) as usize] & (1 << (position % 8)) != 0 {
+            value |= 1 << bit;
+        }
+    }
+    let steps = hat.logical_max - hat.logical_min + 1;
+    if steps <= 0 || value < hat.logical_min || value > hat.logical_max {
+        return Some(HatPosition::Centered);
+    }
+    let step = ((value - hat.logical_min) * 8 / steps) as usize;
+    Some(HatPosition::DIRECTIONS[step.min(7)])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_positions() {
+        let hats = firmware_hats(1, 2);
+        let payload = [0xFF, 2, 0x0F];
+        assert_eq!(read_hat(&payload, &hats[0]), Some(HatPosition::Right));
+        assert_eq!(read_hat(&payload, &hats[1]), Some(HatPosition::Centered));
+        assert_eq!(read_hat(&payload[..2], &hats[1]), None);
+        assert_eq!(HatPosition::UpLeft.angle(), Some(315));
+        assert_eq!(HatPosition::Centered.angle(), None);
+
+        // 4-bit, 4-position hat in the high nibble
+        let four_way = HatField { bit_offset: 4, bit_size: 4, logical_min: 0, logical_max: 3 };
+        assert_eq!(read_hat(&[0x30], &four_way), Some(HatPosition::Left));
+        assert_eq!(read_hat(&[0x40], &four_way), Some(HatPosition::Centered));
+    }
+}
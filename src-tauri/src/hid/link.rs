@@ -0,0 +1,170 @@
+//! HID link quality from the firmware frame counter.
+//!
+//! The firmware advances a 16-bit counter with every input report it sends. When consecutive
+//! reports differ by more than one, the reports in between were lost on the way to the host. A
+//! large backwards jump is taken as a firmware restart rather than a loss. A stall is a
+//! host-side gap between two reports longer than [`STALL_THRESHOLD`].
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+use crate::diagnostics::latency::{frame_step, FrameStep};
+
+pub const HID_LINK_WARNING_EVENT: &str = "hid-link-warning";
+pub const STALL_THRESHOLD: Duration = Duration::from_millis(250);
+/// Share of frames lost within one window above which a warning is raised
+pub const DROP_WARNING_RATE: f64 = 0.01;
+pub const WARNING_WINDOW: Duration = Duration::from_secs(5);
+/// Windows with fewer expected frames are too small to judge
+const MIN_WINDOW_FRAMES: u64 = 100;
+
+/// Counters since the reader thread started
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct HidLinkStats {
+    /// Reports that carried a frame counter; zero when the firmware does not report one
+    pub frames: u64,
+    pub dropped_frames: u64,
+    /// Counter gaps; one gap may cover several dropped frames
+    pub gaps: u64,
+    /// Reports repeating the previous counter
+    pub repeated_frames: u64,
+    pub counter_resets: u64,
+    pub stalls: u64,
+    pub longest_stall_ms: u64,
+    /// `dropped_frames / (frames + dropped_frames)`
+    pub drop_rate: f64,
+    pub last_counter: Option<u16>,
+    pub since: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Payload of `hid-link-warning`: one window whose drop rate exceeded the threshold
+#[derive(Debug, Clone, Serialize)]
+pub struct HidLinkWarning {
+    pub drop_rate: f64,
+    pub threshold: f64,
+    pub dropped_frames: u64,
+    /// Frames received in the window
+    pub frames: u64,
+    pub window_ms: u64,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Default)]
+struct Window {
+    started: Option<Instant>,
+    frames: u64,
+    dropped: u64,
+}
+
+#[derive(Default)]
+pub struct LinkTracker {
+    stats: HidLinkStats,
+    last_at: Option<Instant>,
+    window: Window,
+}
+
+impl LinkTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Account one report received at `at`; returns a warning when a window closes above
+    /// [`DROP_WARNING_RATE`]
+    pub fn observe(&mut self, counter: u16, at: Instant) -> Option<HidLinkWarning> {
+        let stats = &mut self.stats;
+        if stats.since.is_none() {
+            stats.since = Some(chrono::Utc::now());
+        }
+        stats.frames += 1;
+        self.window.frames += 1;
+        if let Some(last) = stats.last_counter {
+            match frame_step(last, counter) {
+                FrameStep::Repeated => stats.repeated_frames += 1,
+                FrameStep::Next => {}
+                FrameStep::Reset => stats.counter_resets += 1,
+                FrameStep::Gap(dropped) => {
+                    stats.gaps += 1;
+                    stats.dropped_frames += dropped;
+                    self.window.dropped += dropped;
+                }
+            }
+        }
+        if let Some(last_at) = self.last_at {
+            let gap = at.saturating_duration_since(last_at);
+            if gap > STALL_THRESHOLD {
+                stats.stalls += 1;
+                stats.longest_stall_ms = stats.longest_stall_ms.max(gap.as_millis() as u64);
+            }
+        }
+        stats.last_counter = Some(counter);
+        stats.drop_rate = rate(stats.dropped_frames, stats.frames);
+        self.last_at = Some(at);
+
+        let started = *self.window.started.get_or_insert(at);
+        let elapsed = at.saturating_duration_since(started);
+        if elapsed < WARNING_WINDOW {
+            return None;
+        }
+        let window = std::mem::replace(&mut self.window, Window { started: Some(at), ..Default::default() });
+        let drop_rate = rate(window.dropped, window.frames);
+        (window.frames + window.dropped >= MIN_WINDOW_FRAMES && drop_rate > DROP_WARNING_RATE).then(|| HidLinkWarning {
+            drop_rate,
+            threshold: DROP_WARNING_RATE,
+            dropped_frames: window.dropped,
+            frames: window.frames,
+            window_ms: elapsed.as_millis() as u64,
+            timestamp: chrono::Utc::now(),
+        })
+    }
+
+    pub fn stats(&self) -> HidLinkStats {
+        self.stats.clone()
+    }
+}
+
+fn rate(dropped: u64, received: u64) -> f64 {
+    let expected = dropped + received;
+    if expected == 0 { 0.0 } else { dropped as f64 / expected as f64 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_gaps_resets_and_stalls() {
+        let mut tracker = LinkTracker::new();
+        let t0 = Instant::now();
+        let ms = |n: u64| t0 + Duration::from_millis(n);
+        for (counter, at) in [(0xFFFE, 0), (0xFFFF, 1), (2, 2), (2, 3), (0x0100, 4), (3, 500)] {
+            assert!(tracker.observe(counter, ms(at)).is_none());
+        }
+        let stats = tracker.stats();
+        assert_eq!(stats.frames, 6);
+        assert_eq!((stats.gaps, stats.dropped_frames), (2, 2 + 0xFD), "wrap 0xFFFF -> 2 loses 0 and 1");
+        assert_eq!((stats.repeated_frames, stats.counter_resets), (1, 1));
+        assert_eq!((stats.stalls, stats.longest_stall_ms), (1, 496));
+        assert_eq!(stats.last_counter, Some(3));
+    }
+
+    #[test]
+    fn warns_when_window_drop_rate_exceeds_threshold() {
+        let mut tracker = LinkTracker::new();
+        let t0 = Instant::now();
+        // Every other frame lost for one window, at 1 kHz
+        let mut warnings = Vec::new();
+        for n in 0..=WARNING_WINDOW.as_millis() as u16 {
+            warnings.extend(tracker.observe(n.wrapping_mul(2), t0 + Duration::from_millis(n as u64)));
+        }
+        assert_eq!(warnings.len(), 1);
+        assert!((warnings[0].drop_rate - 0.5).abs() < 0.01);
+
+        // A clean window stays quiet
+        let start = WARNING_WINDOW.as_millis() as u16 * 2;
+        let t1 = t0 + WARNING_WINDOW;
+        let quiet = (1..=WARNING_WINDOW.as_millis() as u16)
+            .filter_map(|n| tracker.observe(start.wrapping_add(n), t1 + Duration::from_millis(n as u64)))
+            .count();
+        assert_eq!(quiet, 0);
+    }
+}
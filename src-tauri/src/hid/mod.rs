@@ -1,13 +1,44 @@
+pub mod control;
+pub mod descriptor;
+pub mod hat;
+pub mod link;
+
+pub use control::{HidControl, HidControlSupport};
+pub use hat::{HatEvent, HatPosition, HAT_CHANGED_EVENT};
+pub use link::{HidLinkStats, HidLinkWarning, HID_LINK_WARNING_EVENT};
+
 use hidapi::{HidApi, HidDevice};
-use std::sync::{Arc, atomic::{AtomicBool, Ordering}, Mutex as StdMutex};
+use std::sync::{Arc, atomic::{AtomicBool, AtomicI64, AtomicU64, AtomicUsize, Ordering}, mpsc, Mutex as StdMutex};
 use std::thread::{self, JoinHandle};
 use tokio::sync::Mutex;
 use thiserror::Error;
 use tauri::{AppHandle, Emitter};
 
 // JoyCore device identifiers
-const JOYCORE_VID: u16 = 0x2E8A; // Raspberry Pi
-const JOYCORE_PID: u16 = 0xA02F;
+pub const JOYCORE_VID: u16 = 0x2E8A; // Raspberry Pi
+pub const JOYCORE_PID: u16 = 0xA02F;
+
+// Interval of the periodic `button-state-sync` event (driven by AppSettings::update_rate_ms)
+pub const DEFAULT_STATE_SYNC_INTERVAL_MS: u64 = 1000;
+pub const MIN_STATE_SYNC_INTERVAL_MS: u64 = 10;
+/// Consecutive failed reads after which the interface is considered gone (USB reset, unplug, flash)
+const MAX_CONSECUTIVE_READ_ERRORS: u32 = 5;
+/// Backoff between reconnect attempts after the interface dropped
+pub const HID_RECONNECT_INITIAL_DELAY: std::time::Duration = std::time::Duration::from_millis(250);
+pub const HID_RECONNECT_MAX_DELAY: std::time::Duration = std::time::Duration::from_secs(5);
+static STATE_SYNC_INTERVAL_MS_ATOMIC: AtomicU64 = AtomicU64::new(DEFAULT_STATE_SYNC_INTERVAL_MS);
+
+pub fn get_state_sync_interval_ms() -> u64 {
+    STATE_SYNC_INTERVAL_MS_ATOMIC.load(Ordering::Relaxed)
+}
+
+/// Set the state sync interval, clamped to `MIN_STATE_SYNC_INTERVAL_MS`. Returns the applied value.
+pub fn set_state_sync_interval_ms(interval_ms: u64) -> u64 {
+    let applied = interval_ms.max(MIN_STATE_SYNC_INTERVAL_MS);
+    STATE_SYNC_INTERVAL_MS_ATOMIC.store(applied, Ordering::Relaxed);
+    log::info!("HID state sync interval set to {}ms", applied);
+    applied
+}
 
 #[derive(Error, Debug)]
 pub enum HidError {
@@ -22,45 +53,133 @@ pub enum HidError {
     
     #[error("Invalid button data")]
     InvalidData,
+
+    #[error("Invalid feature report: {0}")]
+    InvalidReport(String),
+
+    #[error("Not supported by this firmware: {0}")]
+    Unsupported(String),
+
+    #[error("HID reader did not answer")]
+    Timeout,
 }
 
 pub type Result<T> = std::result::Result<T, HidError>;
 
+/// Logical buttons supported by the firmware
+pub const MAX_BUTTONS: usize = 128;
+
 /// Represents the button states read from the HID device
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct ButtonStates {
-    /// Bit-packed button states (up to 64 buttons)
-    /// Each bit represents a button: 1 = pressed, 0 = not pressed
-    pub buttons: u64,
+    /// Bit-packed button states for all `MAX_BUTTONS` logical buttons, LSB-first:
+    /// bit `n % 8` of byte `n / 8` is button `n` (1 = pressed, 0 = not pressed)
+    pub buttons: [u8; MAX_BUTTONS / 8],
     
     /// Timestamp when the state was read
     pub timestamp: chrono::DateTime<chrono::Utc>,
 }
 
+/// Payload of the `hid-connection-changed` event
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct HidConnectionEvent {
+    pub connected: bool,
+    /// Selected HID interface while connected
+    pub interface: Option<i32>,
+    /// Why the connection ended; `None` for a requested disconnect
+    pub reason: Option<String>,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+}
+
+impl HidConnectionEvent {
+    fn new(connected: bool, interface: Option<i32>, reason: Option<String>) -> Self {
+        Self { connected, interface, reason, timestamp: chrono::Utc::now() }
+    }
+
+    /// The interface dropped without a disconnect request
+    pub fn is_lost(&self) -> bool {
+        !self.connected && self.reason.is_some()
+    }
+}
+
 /// Event payload for button press/release events
 #[derive(Debug, Clone, serde::Serialize)]
 pub struct ButtonEvent {
-    /// Button ID (0-63)
+    /// Button ID (0-127)
     pub button_id: u8,
     /// True if pressed, false if released
     pub pressed: bool,
     /// Timestamp of the event
     pub timestamp: chrono::DateTime<chrono::Utc>,
+    /// Device clock (µs since boot) at `timestamp` while the device clock is synchronized, for
+    /// lining button events up with raw monitor timestamps
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub device_time_us: Option<u64>,
+    /// Time between the report carrying this change and the report before it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub since_previous_report_us: Option<u64>,
+}
+
+impl ButtonEvent {
+    pub fn new(button_id: u8, pressed: bool, timestamp: chrono::DateTime<chrono::Utc>) -> Self {
+        let device_time_us = crate::device::clock_sync::current().and_then(|clock| clock.to_device_time(timestamp));
+        Self { button_id, pressed, timestamp, device_time_us, since_previous_report_us: None }
+    }
+
+    pub fn with_report_interval(mut self, interval: Option<std::time::Duration>) -> Self {
+        self.since_previous_report_us = interval.map(|d| d.as_micros() as u64);
+        self
+    }
+}
+
+/// Host timestamps for HID reports, taken the moment a read returns. hidapi exposes no OS
+/// report timestamps, so the monotonic clock is anchored to wall time once per reader thread
+/// and every report time is derived from it: consistent spacing, immune to wall-clock steps.
+struct ReportClock {
+    anchor: std::time::Instant,
+    anchor_utc: chrono::DateTime<chrono::Utc>,
+    previous: Option<std::time::Instant>,
+}
+
+impl ReportClock {
+    fn new() -> Self {
+        Self { anchor: std::time::Instant::now(), anchor_utc: chrono::Utc::now(), previous: None }
+    }
+
+    fn utc(&self, at: std::time::Instant) -> chrono::DateTime<chrono::Utc> {
+        self.anchor_utc + chrono::Duration::from_std(at.saturating_duration_since(self.anchor)).unwrap_or_else(|_| chrono::Duration::zero())
+    }
+
+    /// Record a report arrival; the time since the previous report
+    fn report(&mut self, at: std::time::Instant) -> Option<std::time::Duration> {
+        let since = self.previous.map(|previous| at.saturating_duration_since(previous));
+        self.previous = Some(at);
+        since
+    }
 }
 
 impl ButtonStates {
+    pub fn from_mask(mask: u128, timestamp: chrono::DateTime<chrono::Utc>) -> Self {
+        Self { buttons: mask.to_le_bytes(), timestamp }
+    }
+
+    /// Bitmap as one 128-bit mask (bit `n` = button `n`)
+    pub fn mask(&self) -> u128 {
+        u128::from_le_bytes(self.buttons)
+    }
+
     /// Check if a specific button is pressed
     pub fn is_button_pressed(&self, button_index: u8) -> bool {
-        if button_index >= 64 {
+        if button_index as usize >= MAX_BUTTONS {
             return false;
         }
-        (self.buttons & (1u64 << button_index)) != 0
+        (self.mask() & (1u128 << button_index)) != 0
     }
     
     /// Get a list of all pressed button indices
     pub fn get_pressed_buttons(&self) -> Vec<u8> {
         let mut pressed = Vec::new();
-        for i in 0..64 {
+        for i in 0..MAX_BUTTONS as u8 {
             if self.is_button_pressed(i) {
                 pressed.push(i);
             }
@@ -69,24 +188,108 @@ impl ButtonStates {
     }
 }
 
-/// HID device reader for JoyCore devices
+/// HID device reader for JoyCore devices.
+///
+/// The opened `HidDevice` is owned by the reader thread. The thread publishes button state
+/// through [`SharedState`] atomics; everything else (mapping updates, app handle, debug report
+/// copies, stop) goes to it as [`ReaderCommand`]s, handled between reads.
 pub struct HidReader {
-    device: Arc<Mutex<Option<HidDevice>>>,
     api: Arc<Mutex<HidApi>>,
-    last_state: Arc<StdMutex<ButtonStates>>, // Cached last known state (std mutex for thread use)
-    running: Arc<AtomicBool>,
-    reader_handle: Arc<Mutex<Option<JoinHandle<()>>>>,
-    // Selected offset (once determined) for raw button bitmap inside report
-    selected_offset: Arc<StdMutex<Option<usize>>>,
-    // Last raw 64-bit value captured at that offset for debug (mirrors last_state.buttons but before any future transforms)
-    last_raw_value: Arc<StdMutex<u64>>,
-    // Last full HID report bytes (for mapping investigation)
-    last_report: Arc<StdMutex<[u8;64]>>,
-    last_report_len: Arc<StdMutex<usize>>,
-    // Parsed mapping information from feature reports (if supported by firmware)
-    mapping_data: Arc<StdMutex<Option<MappingData>>>,
+    shared: Arc<SharedState>,
+    reader: StdMutex<Option<ReaderThread>>,
+    // Parsed mapping information from feature reports (if supported by firmware); the reader
+    // thread decodes with its own copy
+    mapping_data: StdMutex<Option<MappingData>>,
     // Tauri app handle for emitting events
-    app_handle: Arc<StdMutex<Option<AppHandle>>>,
+    app_handle: StdMutex<Option<AppHandle>>,
+}
+
+/// Requests from the async API to the reader thread
+enum ReaderCommand {
+    /// Replace the mapping reports are decoded with
+    SetMapping(MappingData),
+    SetAppHandle(AppHandle),
+    /// Copy of the last full input report
+    LastReport(tokio::sync::oneshot::Sender<Option<Vec<u8>>>),
+    /// Write a feature report (report ID first)
+    SendFeatureReport(Vec<u8>, tokio::sync::oneshot::Sender<Result<()>>),
+    Stop,
+}
+
+/// Running reader thread and its command channel
+struct ReaderThread {
+    commands: mpsc::Sender<ReaderCommand>,
+    handle: JoinHandle<()>,
+}
+
+/// State published by the reader thread, read lock-free by the async API
+struct SharedState {
+    connected: AtomicBool,
+    /// Last connection change, for in-process watchers (see `HidReader::connection_events`)
+    connection: tokio::sync::watch::Sender<HidConnectionEvent>,
+    /// Cached last known logical button state (`u128` mask split into low and high words)
+    buttons: [AtomicU64; 2],
+    /// Unix time (ms) of the last state update
+    updated_ms: AtomicI64,
+    // Selected offset of the raw button bitmap inside the report; `usize::MAX` until determined
+    selected_offset: AtomicUsize,
+    // Last raw 64-bit value captured at that offset for debug (legacy heuristic; low word of the logical mask when mapped)
+    last_raw_value: AtomicU64,
+    /// Frame counter statistics of the current reader thread
+    link: StdMutex<link::LinkTracker>,
+    /// Controls of the connected interface, probed when the reader thread starts
+    controls: StdMutex<HidControlSupport>,
+}
+
+impl SharedState {
+    fn new() -> Self {
+        Self {
+            connected: AtomicBool::new(false),
+            connection: tokio::sync::watch::channel(HidConnectionEvent::new(false, None, None)).0,
+            buttons: [AtomicU64::new(0), AtomicU64::new(0)],
+            updated_ms: AtomicI64::new(chrono::Utc::now().timestamp_millis()),
+            selected_offset: AtomicUsize::new(usize::MAX),
+            last_raw_value: AtomicU64::new(0),
+            link: StdMutex::new(link::LinkTracker::new()),
+            controls: StdMutex::new(HidControlSupport::default()),
+        }
+    }
+
+    fn set_buttons(&self, buttons: u128, timestamp: chrono::DateTime<chrono::Utc>) {
+        self.buttons[0].store(buttons as u64, Ordering::Relaxed);
+        self.buttons[1].store((buttons >> 64) as u64, Ordering::Relaxed);
+        self.updated_ms.store(timestamp.timestamp_millis(), Ordering::Relaxed);
+    }
+
+    /// Refresh the timestamp so the UI doesn't stale out
+    fn touch(&self) {
+        self.updated_ms.store(chrono::Utc::now().timestamp_millis(), Ordering::Relaxed);
+    }
+
+    fn mask(&self) -> u128 {
+        self.buttons[0].load(Ordering::Relaxed) as u128 | (self.buttons[1].load(Ordering::Relaxed) as u128) << 64
+    }
+
+    fn snapshot(&self) -> ButtonStates {
+        let updated_ms = self.updated_ms.load(Ordering::Relaxed);
+        ButtonStates::from_mask(
+            self.mask(),
+            chrono::DateTime::from_timestamp_millis(updated_ms).unwrap_or_else(chrono::Utc::now),
+        )
+    }
+
+    /// Publish a connection change to watchers and the frontend
+    fn set_connection(&self, event: HidConnectionEvent, app_handle: Option<&AppHandle>) {
+        self.connected.store(event.connected, Ordering::SeqCst);
+        if let Some(handle) = app_handle {
+            let _ = handle.emit("hid-connection-changed", &event);
+        }
+        self.connection.send_replace(event);
+    }
+
+    fn selected_offset(&self) -> Option<usize> {
+        Some(self.selected_offset.load(Ordering::Relaxed)).filter(|&offset| offset != usize::MAX)
+    }
 }
 
 /// Raw HID mapping information structure as provided by firmware feature report ID 3.
@@ -102,7 +305,29 @@ struct HIDMappingInfoRaw {
     button_bit_order: u8, // 0 = LSB-first, 1 = MSB-first (only 0 currently used)
     mapping_crc: u16,     // 0x0000 = sequential
     frame_counter_offset: u8,
-    reserved: [u8;7],
+    hat_count: u8,        // one byte per hat after the button bitmap (see `hat`)
+    reserved: [u8;6],
+}
+
+/// Where a mapping came from
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum MappingSource {
+    /// HID feature reports 3/4
+    FeatureReport,
+    /// Serial protocol fallback (`apply_external_mapping`)
+    Serial,
+    /// Derived from the HID report descriptor for firmware without the mapping feature
+    ReportDescriptor,
+}
+
+impl MappingSource {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::FeatureReport => "feature-report",
+            Self::Serial => "serial",
+            Self::ReportDescriptor => "report-descriptor",
+        }
+    }
 }
 
 /// Processed mapping data used by reader thread.
@@ -111,6 +336,47 @@ struct MappingData {
     info: HIDMappingInfoRaw,
     // mapping[bit_index] = logical joy button id. If sequential, identity mapping stored.
     mapping: Vec<u8>,
+    axes: Vec<descriptor::AxisField>,
+    hats: Vec<descriptor::HatField>,
+    source: MappingSource,
+}
+
+/// End of the button bitmap in the payload, where firmware-reported hats start
+fn button_bitmap_end(info: &HIDMappingInfoRaw) -> usize {
+    info.button_byte_offset as usize + (info.button_count as usize).div_ceil(8)
+}
+
+/// Axis fields described by firmware mapping info: `axis_count` little-endian values in the
+/// bytes ahead of the button bitmap, stepping over the frame counter when it sits there. Axes
+/// are 16-bit, or 8-bit when 16-bit ones do not fit; empty when neither does.
+fn firmware_axes(info: &HIDMappingInfoRaw) -> Vec<descriptor::AxisField> {
+    let count = info.axis_count as usize;
+    let end = info.button_byte_offset as usize;
+    let counter = (info.frame_counter_offset != 0xFF).then_some(info.frame_counter_offset as usize);
+    let available = end - counter.map_or(0, |c| end.min(c + 2).saturating_sub(c));
+    let width = match count {
+        0 => return Vec::new(),
+        _ if count * 2 <= available => 2,
+        _ if count <= available => 1,
+        _ => return Vec::new(),
+    };
+    let mut axes = Vec::with_capacity(count);
+    let mut offset = 0usize;
+    for i in 0..count {
+        if let Some(c) = counter {
+            if offset < c + 2 && c < offset + width { offset = c + 2; }
+        }
+        if offset + width > end { return Vec::new(); }
+        axes.push(descriptor::AxisField {
+            usage: 0x30 + i as u16, // Generic Desktop X, Y, Z, ...
+            bit_offset: (offset * 8) as u32,
+            bit_size: (width * 8) as u32,
+            logical_min: 0,
+            logical_max: (1i32 << (width * 8)) - 1,
+        });
+        offset += width;
+    }
+    axes
 }
 
 /// Public friendly struct for external mapping injection (e.g., from serial protocol)
@@ -124,6 +390,7 @@ pub struct ExternalMappingInfo {
     pub button_bit_order: u8,
     pub mapping_crc: u16,
     pub frame_counter_offset: Option<u8>,
+    pub hat_count: u8,
 }
 
 impl HidReader {
@@ -131,25 +398,20 @@ impl HidReader {
     pub fn new() -> Result<Self> {
         let api = HidApi::new()?;
         Ok(Self {
-            device: Arc::new(Mutex::new(None)),
             api: Arc::new(Mutex::new(api)),
-            last_state: Arc::new(StdMutex::new(ButtonStates { buttons: 0, timestamp: chrono::Utc::now() })),
-            running: Arc::new(AtomicBool::new(false)),
-            reader_handle: Arc::new(Mutex::new(None)),
-            selected_offset: Arc::new(StdMutex::new(None)),
-            last_raw_value: Arc::new(StdMutex::new(0)),
-            last_report: Arc::new(StdMutex::new([0u8;64])),
-            last_report_len: Arc::new(StdMutex::new(0)),
-            mapping_data: Arc::new(StdMutex::new(None)),
-            app_handle: Arc::new(StdMutex::new(None)),
+            shared: Arc::new(SharedState::new()),
+            reader: StdMutex::new(None),
+            mapping_data: StdMutex::new(None),
+            app_handle: StdMutex::new(None),
         })
     }
-    
+
     /// Set the Tauri app handle for event emission
     pub fn set_app_handle(&self, handle: AppHandle) {
         if let Ok(mut app_handle) = self.app_handle.lock() {
-            *app_handle = Some(handle);
+            *app_handle = Some(handle.clone());
         }
+        self.send_command(ReaderCommand::SetAppHandle(handle));
     }
 
     /// Inject mapping information obtained via an alternate path (e.g., serial fallback)
@@ -165,30 +427,43 @@ impl HidReader {
             button_bit_order: info.button_bit_order,
             mapping_crc: info.mapping_crc,
             frame_counter_offset: info.frame_counter_offset.unwrap_or(0xFF), // 0xFF meaning unknown
-            reserved: [0u8;7],
+            hat_count: info.hat_count,
+            reserved: [0u8;6],
         };
 
-        let mut guard = self.mapping_data.lock().unwrap();
-        if guard.is_some() && !force_replace { return false; }
-        *guard = Some(MappingData { info: raw, mapping });
-        log::info!("External mapping injected: buttons={} axes={} sequential={} source=serial-fallback", raw.button_count, raw.axis_count, raw.mapping_crc==0);
+        let hats = hat::firmware_hats(button_bitmap_end(&raw), raw.hat_count);
+        let axes = firmware_axes(&raw);
+        let data = MappingData { info: raw, mapping, axes, hats, source: MappingSource::Serial };
+        {
+            let mut guard = self.mapping_data.lock().unwrap();
+            // A descriptor-derived layout always yields to firmware-provided mapping
+            let firmware_mapped = guard.as_ref().is_some_and(|md| md.source != MappingSource::ReportDescriptor);
+            if firmware_mapped && !force_replace { return false; }
+            *guard = Some(data.clone());
+        }
+        self.send_command(ReaderCommand::SetMapping(data));
+        log::info!("External mapping injected: buttons={} axes={} hats={} sequential={} source=serial-fallback", raw.button_count, raw.axis_count, raw.hat_count, raw.mapping_crc==0);
         true
     }
-    
+
     /// Connect to the JoyCore HID device
     pub async fn connect(&self) -> Result<()> {
+        if self.is_connected().await {
+            // Reconnect from scratch; the reader thread owns the previously opened device
+            self.disconnect().await?;
+        }
         let mut api = self.api.lock().await;
-        
+
         // Refresh device list
         api.refresh_devices()?;
-        
+
         log::info!("Searching for JoyCore HID device (VID: 0x{:04X}, PID: 0x{:04X})", JOYCORE_VID, JOYCORE_PID);
-        
+
         // List all HID devices for debugging
         let mut device_count = 0;
         for device_info in api.device_list() {
-            log::debug!("HID Device: VID=0x{:04X}, PID=0x{:04X}, Path={:?}, Interface={}", 
-                device_info.vendor_id(), 
+            log::debug!("HID Device: VID=0x{:04X}, PID=0x{:04X}, Path={:?}, Interface={}",
+                device_info.vendor_id(),
                 device_info.product_id(),
                 device_info.path(),
                 device_info.interface_number()
@@ -196,7 +471,7 @@ impl HidReader {
             device_count += 1;
         }
         log::info!("Found {} HID devices total", device_count);
-        
+
         // Collect all JoyCore top-level collections (Windows enumerates each HID collection as separate path '...&ColXX#')
         let mut found_devices: Vec<(i32, String)> = Vec::new();
         for device_info in api.device_list() {
@@ -207,60 +482,44 @@ impl HidReader {
                 found_devices.push((interface, path_str));
             }
         }
-        
+
         if found_devices.is_empty() {
             log::error!("No JoyCore HID devices found!");
             return Err(HidError::DeviceNotFound);
         }
-        
+
         log::info!("Found {} JoyCore HID interfaces (collections)", found_devices.len());
 
         // Sort by interface then path for deterministic order
         found_devices.sort_by_key(|(iface, path)| (*iface, path.clone()));
 
         // PASS 1: Prefer a collection that supports mapping feature report (ID 3)
-        use std::mem::size_of;
         for (interface, path) in &found_devices {
             if let Some(info) = api.device_list().find(|d| d.path().to_str().unwrap_or("") == path) {
                 if let Ok(dev) = info.open_device(&api) {
-                    let mut buf = [0u8; 1 + size_of::<HIDMappingInfoRaw>()];
-                    buf[0] = 3;
-                    if let Ok(sz) = dev.get_feature_report(&mut buf) { if sz == buf.len() { // looks promising
-                        // Store device so mapping fetch can use it
-                        {
-                            let mut device_guard = self.device.lock().await; *device_guard = Some(dev);
+                    // Parse mapping
+                    if let Ok(mapping) = fetch_mapping(&dev) {
+                        // Quick sanity check: ensure this interface yields input reports
+                        let mut probe_ok = false;
+                        let mut rbuf = [0u8; 64];
+                        for _ in 0..6 {
+                            if let Ok(rs) = dev.read_timeout(&mut rbuf, 40) { if rs > 0 { probe_ok = true; break; } }
                         }
-                        // Parse mapping
-                        if self.try_fetch_mapping().await.is_ok() {
-                            // Quick sanity check: ensure this interface yields input reports
-                            let mut probe_ok = false;
-                            {
-                                let guard = self.device.lock().await;
-                                if let Some(device) = guard.as_ref() {
-                                    let mut rbuf = [0u8; 64];
-                                    for _ in 0..6 {
-                                        if let Ok(rs) = device.read_timeout(&mut rbuf, 40) { if rs > 0 { probe_ok = true; break; } }
-                                    }
-                                }
-                            }
-                            if probe_ok {
-                                log::info!("Selected JoyCore HID interface {} (mapping feature supported) path={}", interface, path);
-                                self.start_reader_task(*interface).await?;
-                                return Ok(());
-                            } else {
-                                log::warn!("Interface {} had mapping but produced no input reports; trying next", interface);
-                                let mut device_guard = self.device.lock().await; *device_guard = None;
-                            }
+                        if probe_ok {
+                            *self.mapping_data.lock().unwrap() = Some(mapping);
+                            log::info!("Selected JoyCore HID interface {} (mapping feature supported) path={}", interface, path);
+                            self.start_reader_task(*interface, dev);
+                            return Ok(());
                         } else {
-                            // Clear device again to retry in pass 2
-                            let mut device_guard = self.device.lock().await; *device_guard = None;
+                            log::warn!("Interface {} had mapping but produced no input reports; trying next", interface);
                         }
-                    }}
+                    }
                 }
             }
         }
 
-        // PASS 2: Heuristic fallback - pick first interface that produces any input report bytes
+        // PASS 2: No mapping feature - pick first interface that produces any input report bytes and
+        // decode it with the layout from its report descriptor, or heuristically when that fails
         let mut fallback: Option<(i32, HidDevice)> = None;
         for (interface, path) in &found_devices {
             if let Some(info) = api.device_list().find(|d| d.path().to_str().unwrap_or("") == path) {
@@ -271,11 +530,9 @@ impl HidReader {
                         if let Ok(sz) = dev.read_timeout(&mut buf, 40) { if sz > 0 { success = true; break; } }
                     }
                     if success {
-                        {
-                            let mut device_guard = self.device.lock().await; *device_guard = Some(dev);
-                        }
                         log::info!("Selected JoyCore HID interface {} via fallback (no mapping feature)", interface);
-                        self.start_reader_task(*interface).await?;
+                        self.apply_descriptor_mapping(&dev);
+                        self.start_reader_task(*interface, dev);
                         return Ok(());
                     } else if fallback.is_none() { fallback = Some((*interface, dev)); }
                 }
@@ -283,54 +540,127 @@ impl HidReader {
         }
 
         if let Some((interface, dev)) = fallback {
-            let mut device_guard = self.device.lock().await; *device_guard = Some(dev);
             log::warn!("Using fallback JoyCore HID interface {} (no immediate reports, no mapping feature)", interface);
-            self.start_reader_task(interface).await?;
+            self.apply_descriptor_mapping(&dev);
+            self.start_reader_task(interface, dev);
             return Ok(());
         }
 
         log::error!("Failed to open/validate any JoyCore HID interface");
         Err(HidError::DeviceNotFound)
     }
-    
+
+    /// Use the report descriptor layout unless a firmware-provided mapping is already loaded
+    fn apply_descriptor_mapping(&self, dev: &HidDevice) {
+        let mut guard = self.mapping_data.lock().unwrap();
+        if guard.as_ref().is_some_and(|md| md.source != MappingSource::ReportDescriptor) {
+            return;
+        }
+        *guard = descriptor_mapping(dev);
+        if guard.is_none() {
+            log::warn!("No usable report descriptor layout; falling back to heuristic button detection");
+        }
+    }
+
     /// Disconnect from the HID device
     pub async fn disconnect(&self) -> Result<()> {
-        // Signal reader thread to stop
-        self.running.store(false, Ordering::SeqCst);
-        {
-            let mut handle_guard = self.reader_handle.lock().await;
-            if let Some(handle) = handle_guard.take() {
-                log::info!("Joining HID reader thread...");
-                let _ = handle.join();
-            }
-        }
-        {
-            let mut device_guard = self.device.lock().await;
-            *device_guard = None;
+        // Signal reader thread to stop; it closes the device when it exits
+        let reader = self.reader.lock().unwrap().take();
+        *self.shared.controls.lock().unwrap() = HidControlSupport::default();
+        if let Some(reader) = reader {
+            let _ = reader.commands.send(ReaderCommand::Stop);
+            log::info!("Joining HID reader thread...");
+            let _ = tokio::task::spawn_blocking(move || reader.handle.join()).await;
         }
+        self.shared.connected.store(false, Ordering::SeqCst);
         log::info!("Disconnected from JoyCore HID device");
         Ok(())
     }
-    
+
     /// Check if connected to a HID device
     pub async fn is_connected(&self) -> bool {
-        let device_guard = self.device.lock().await;
-        device_guard.is_some()
+        self.shared.connected.load(Ordering::SeqCst)
     }
-    
+
+    /// Watch connection changes, including the reader thread giving up on a dropped interface
+    pub fn connection_events(&self) -> tokio::sync::watch::Receiver<HidConnectionEvent> {
+        self.shared.connection.subscribe()
+    }
+
+    /// Whether a JoyCore HID interface (VID/PID) is currently enumerated
+    pub async fn device_present(&self) -> bool {
+        let mut api = self.api.lock().await;
+        if let Err(e) = api.refresh_devices() {
+            log::debug!("HID device refresh failed: {}", e);
+            return false;
+        }
+        let present = api.device_list().any(|d| d.vendor_id() == JOYCORE_VID && d.product_id() == JOYCORE_PID);
+        present
+    }
+
     /// Read current button states from the HID device
     pub async fn read_button_states(&self) -> Result<ButtonStates> {
         // Simply return the cached last state. This prevents flicker to zero when no new report.
         if !self.is_connected().await { return Err(HidError::DeviceNotFound); }
-    let state = self.last_state.lock().unwrap().clone();
-    Ok(state)
+        Ok(self.shared.snapshot())
     }
 
     /// Debug info: selected offset & last raw value
     pub async fn debug_hid_mapping(&self) -> Option<(usize, u64)> {
-        let off = *self.selected_offset.lock().unwrap();
-        let raw = *self.last_raw_value.lock().unwrap();
-        off.map(|o| (o, raw))
+        let raw = self.shared.last_raw_value.load(Ordering::Relaxed);
+        self.shared.selected_offset().map(|o| (o, raw))
+    }
+
+    /// Logical button carried by each report bit, when the device reported a mapping
+    pub fn button_mapping(&self) -> Option<Vec<u8>> {
+        self.mapping_data.lock().unwrap().as_ref().map(|md| md.mapping.clone())
+    }
+
+    /// Frame counter statistics since the reader thread last started
+    pub fn link_stats(&self) -> HidLinkStats {
+        self.shared.link.lock().unwrap().stats()
+    }
+
+    /// Controls the connected firmware accepts over the control feature report
+    pub fn control_support(&self) -> HidControlSupport {
+        self.shared.controls.lock().unwrap().clone()
+    }
+
+    /// Write feature report `report_id` with `data` to the connected interface
+    pub async fn send_hid_feature_report(&self, report_id: u8, data: &[u8]) -> Result<()> {
+        if !self.is_connected().await { return Err(HidError::DeviceNotFound); }
+        if data.len() >= control::MAX_FEATURE_REPORT_LEN {
+            return Err(HidError::InvalidReport(format!("{} data bytes, at most {} fit in a report", data.len(), control::MAX_FEATURE_REPORT_LEN - 1)));
+        }
+        let mut report = Vec::with_capacity(data.len() + 1);
+        report.push(report_id);
+        report.extend_from_slice(data);
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        self.send_command(ReaderCommand::SendFeatureReport(report, tx));
+        match tokio::time::timeout(std::time::Duration::from_millis(500), rx).await {
+            Ok(Ok(result)) => result,
+            // Thread exited or is stuck in a read
+            _ => Err(HidError::Timeout),
+        }
+    }
+
+    /// Request a firmware control, failing with `Unsupported` when the firmware lacks it
+    pub async fn send_control(&self, control: HidControl, argument: u8) -> Result<()> {
+        if !self.is_connected().await { return Err(HidError::DeviceNotFound); }
+        if !self.control_support().supports(control) {
+            return Err(HidError::Unsupported(format!("{:?} control", control)));
+        }
+        let [report_id, data @ ..] = control.request(argument);
+        self.send_hid_feature_report(report_id, &data).await
+    }
+
+    /// Blink the status LED for `seconds`
+    pub async fn identify(&self, seconds: u8) -> Result<()> {
+        self.send_control(HidControl::Identify, seconds.clamp(1, control::MAX_IDENTIFY_SECS)).await
+    }
+
+    pub async fn set_test_mode(&self, enabled: bool) -> Result<()> {
+        self.send_control(HidControl::TestMode, enabled as u8).await
     }
 
     /// Detailed mapping info (if feature reports supported)
@@ -346,6 +676,7 @@ impl HidReader {
             let button_byte_offset = info.button_byte_offset;
             let button_bit_order = info.button_bit_order;
             let frame_counter_offset = info.frame_counter_offset;
+            let hat_count = info.hat_count;
             let mapping_crc = info.mapping_crc;
             let sequential = mapping_crc == 0;
             return Some(serde_json::json!({
@@ -356,9 +687,11 @@ impl HidReader {
                 "button_byte_offset": button_byte_offset,
                 "button_bit_order": button_bit_order,
                 "frame_counter_offset": frame_counter_offset,
+                "hat_count": hat_count,
                 "sequential": sequential,
                 "mapping_crc": mapping_crc,
                 "mapping": map_vec,
+                "source": md.source.as_str(),
             }));
         }
         None
@@ -366,29 +699,27 @@ impl HidReader {
 
     /// Debug: get last full HID report as hex (truncated to actual length)
     pub async fn debug_full_report(&self) -> Option<(usize, String)> {
-        let len = *self.last_report_len.lock().unwrap();
-        if len == 0 { return None; }
-        let mut buf = [0u8;64];
-        buf.copy_from_slice(&*self.last_report.lock().unwrap());
-        Some((len, hex::encode(&buf[..len])))
+        let report = self.last_report().await?;
+        Some((report.len(), hex::encode(&report)))
     }
 
     /// Diagnostic: return a JSON string summarizing raw button bytes vs mapped logical bits (first 16 buttons)
     pub async fn debug_button_bit_diagnostics(&self) -> Option<serde_json::Value> {
-        let len = *self.last_report_len.lock().unwrap();
-        if len == 0 { return None; }
-        let report = self.last_report.lock().unwrap().clone();
+        let last = self.last_report().await?;
+        let mut report = [0u8; 64];
+        report[..last.len()].copy_from_slice(&last);
         let mapping_opt = { self.mapping_data.lock().unwrap().clone() };
-        let selected_off_opt = { *self.selected_offset.lock().unwrap() };
-        let last_raw_val = { *self.last_raw_value.lock().unwrap() };
+        let selected_off_opt = self.shared.selected_offset();
+        let last_raw_val = self.shared.last_raw_value.load(Ordering::Relaxed);
         let mut raw_bits: Vec<u8> = Vec::new();
         // Interpret report[0..16] as raw button bytes regardless of report ID presence
         for byte_index in 0..16 { raw_bits.push(report[byte_index]); }
         // Derive bit->logical (0..15) pressed arrays from current cached state
-        let logical_state = self.last_state.lock().unwrap().buttons;
+        let logical_state = self.shared.mask();
         let mut logical_pressed: Vec<u8> = Vec::new();
-        for b in 0..16 { if (logical_state & (1u64 << b)) != 0 { logical_pressed.push(b as u8); } }
+        for b in 0..16 { if (logical_state & (1u128 << b)) != 0 { logical_pressed.push(b as u8); } }
         let mapping_summary = mapping_opt.as_ref().map(|m| serde_json::json!({
+            "source": m.source.as_str(),
             "button_byte_offset": m.info.button_byte_offset,
             "button_bit_order": m.info.button_bit_order,
             "button_count": m.info.button_count,
@@ -415,12 +746,12 @@ impl HidReader {
             "legacy": legacy_extra,
         }))
     }
-    
+
     /// Find and list all JoyCore HID devices
     pub async fn list_devices() -> Result<Vec<String>> {
         let api = HidApi::new()?;
         let mut devices = Vec::new();
-        
+
         for device_info in api.device_list() {
             if device_info.vendor_id() == JOYCORE_VID && device_info.product_id() == JOYCORE_PID {
                 let info = format!(
@@ -431,85 +762,147 @@ impl HidReader {
                 devices.push(info);
             }
         }
-        
+
         Ok(devices)
     }
 }
 
-impl HidReader {
-    /// Attempt to fetch HID mapping feature reports (IDs 3 & 4). Stores mapping_data if successful.
-    async fn try_fetch_mapping(&self) -> Result<()> {
-        use std::mem::size_of;
-        let guard = self.device.lock().await;
-        let Some(dev) = guard.as_ref() else { return Err(HidError::DeviceNotFound); };
-
-        // Feature report ID 3: mapping info (1 + 16 bytes)
-        let mut buf = [0u8; 1 + size_of::<HIDMappingInfoRaw>()];
-        buf[0] = 3; // report ID
-        let sz = dev.get_feature_report(&mut buf)?; // returns number of bytes read
-        if sz < buf.len() { return Err(HidError::InvalidData); }
-        // SAFETY: bytes are from device, copy into struct
-        let mut raw = HIDMappingInfoRaw::default();
-        let raw_slice = unsafe {
-            std::slice::from_raw_parts_mut((&mut raw as *mut HIDMappingInfoRaw) as *mut u8, size_of::<HIDMappingInfoRaw>())
-        };
-        raw_slice.copy_from_slice(&buf[1..]);
+/// Attempt to fetch HID mapping feature reports (IDs 3 & 4) from an opened interface.
+fn fetch_mapping(dev: &HidDevice) -> Result<MappingData> {
+    use std::mem::size_of;
 
-        if raw.protocol_version == 0 || raw.button_count == 0 || raw.button_count > 128 { return Err(HidError::InvalidData); }
+    // Feature report ID 3: mapping info (1 + 16 bytes)
+    let mut buf = [0u8; 1 + size_of::<HIDMappingInfoRaw>()];
+    buf[0] = 3; // report ID
+    let sz = dev.get_feature_report(&mut buf)?; // returns number of bytes read
+    if sz < buf.len() { return Err(HidError::InvalidData); }
+    // SAFETY: bytes are from device, copy into struct
+    let mut raw = HIDMappingInfoRaw::default();
+    let raw_slice = unsafe {
+        std::slice::from_raw_parts_mut((&mut raw as *mut HIDMappingInfoRaw) as *mut u8, size_of::<HIDMappingInfoRaw>())
+    };
+    raw_slice.copy_from_slice(&buf[1..]);
 
-        // Prefer explicit mapping report (ID 4) if available; otherwise fall back to identity
-        let mut mapping: Vec<u8> = (0..raw.button_count).collect();
-        {
-            let mut map_buf = vec![0u8; 1 + raw.button_count as usize];
-            map_buf[0] = 4; // feature report ID 4
-            match dev.get_feature_report(&mut map_buf) {
-                Ok(sz2) if sz2 >= map_buf.len() => {
-                    mapping = map_buf[1..].to_vec();
-                }
-                Ok(_) => {
-                    // too short; keep identity
-                }
-                Err(e) => {
-                    // Some firmware may omit ID 4 when sequential; keep identity
-                    log::debug!("Feature report 4 unavailable: {} (using identity)", e);
-                }
+    if raw.protocol_version == 0 || raw.button_count == 0 || raw.button_count > 128 { return Err(HidError::InvalidData); }
+
+    // Prefer explicit mapping report (ID 4) if available; otherwise fall back to identity
+    let mut mapping: Vec<u8> = (0..raw.button_count).collect();
+    {
+        let mut map_buf = vec![0u8; 1 + raw.button_count as usize];
+        map_buf[0] = 4; // feature report ID 4
+        match dev.get_feature_report(&mut map_buf) {
+            Ok(sz2) if sz2 >= map_buf.len() => {
+                mapping = map_buf[1..].to_vec();
+            }
+            Ok(_) => {
+                // too short; keep identity
+            }
+            Err(e) => {
+                // Some firmware may omit ID 4 when sequential; keep identity
+                log::debug!("Feature report 4 unavailable: {} (using identity)", e);
             }
         }
+    }
 
-        {
-            let mut md = self.mapping_data.lock().unwrap();
-            *md = Some(MappingData { info: raw, mapping });
+    log::info!("HID mapping feature reports loaded: buttons={}, axes={}, hats={}, sequential={}", raw.button_count, raw.axis_count, raw.hat_count, raw.mapping_crc == 0);
+    let hats = hat::firmware_hats(button_bitmap_end(&raw), raw.hat_count);
+    let axes = firmware_axes(&raw);
+    if axes.len() != raw.axis_count as usize {
+        log::debug!("HID mapping reports {} axes that do not fit ahead of byte {}; axes not decoded", raw.axis_count, raw.button_byte_offset);
+    }
+    Ok(MappingData { info: raw, mapping, axes, hats, source: MappingSource::FeatureReport })
+}
+
+/// Derive a mapping from the interface's report descriptor (buttons must start on a byte boundary)
+fn descriptor_mapping(dev: &HidDevice) -> Option<MappingData> {
+    let mut buf = [0u8; hidapi::MAX_REPORT_DESCRIPTOR_SIZE];
+    let len = match dev.get_report_descriptor(&mut buf) {
+        Ok(len) => len,
+        Err(e) => {
+            log::debug!("Report descriptor unavailable: {}", e);
+            return None;
         }
-        log::info!("HID mapping feature reports loaded: buttons={}, axes={}, sequential={}", raw.button_count, raw.axis_count, raw.mapping_crc == 0);
-        Ok(())
+    };
+    let layout = match descriptor::input_layout(&buf[..len]) {
+        Ok(layout) => layout,
+        Err(e) => {
+            log::debug!("Report descriptor not usable: {}", e);
+            return None;
+        }
+    };
+    mapping_from_layout(&layout)
+}
+
+fn mapping_from_layout(layout: &descriptor::ReportLayout) -> Option<MappingData> {
+    if layout.button_bit_offset % 8 != 0 || layout.button_bit_offset / 8 > u8::MAX as u32 {
+        log::debug!("Descriptor buttons start at bit {}, not on a byte boundary", layout.button_bit_offset);
+        return None;
     }
+    let axes: Vec<descriptor::AxisField> = layout.axes.iter().filter(|axis| (1..=16).contains(&axis.bit_size)).take(32).cloned().collect();
+    let sequential = layout.buttons.iter().enumerate().all(|(i, &id)| id as usize == i);
+    let hats: Vec<descriptor::HatField> = layout.hats.iter().take(hat::MAX_HATS).cloned().collect();
+    let info = HIDMappingInfoRaw {
+        protocol_version: 0,
+        input_report_id: layout.report_id,
+        button_count: layout.buttons.len() as u8,
+        axis_count: axes.len() as u8,
+        button_byte_offset: (layout.button_bit_offset / 8) as u8,
+        button_bit_order: 0,
+        mapping_crc: if sequential { 0 } else { (crate::util::crc::crc32(&layout.buttons) as u16).max(1) },
+        frame_counter_offset: 0xFF,
+        hat_count: hats.len() as u8,
+        reserved: [0u8; 6],
+    };
+    log::info!(
+        "HID layout from report descriptor: report_id={} buttons={} at byte {} axes={}/{} hats={} sequential={}",
+        layout.report_id, layout.buttons.len(), info.button_byte_offset, axes.len(), layout.axes.len(), hats.len(), sequential
+    );
+    Some(MappingData { info, mapping: layout.buttons.clone(), axes, hats, source: MappingSource::ReportDescriptor })
+}
 
-    /// Start background reader task (idempotent)
-    async fn start_reader_task(&self, interface: i32) -> Result<()> {
-        if self.running.load(Ordering::SeqCst) { return Ok(()); }
-        self.running.store(true, Ordering::SeqCst);
-        let device_arc = self.device.clone();
-        let state_arc = self.last_state.clone();
-        let sel_offset_arc = self.selected_offset.clone();
-        let last_raw_arc = self.last_raw_value.clone();
-        let last_report_arc = self.last_report.clone();
-        let last_report_len_arc = self.last_report_len.clone();
-        let mapping_data_arc = self.mapping_data.clone();
-        let running_flag = self.running.clone();
-        let app_handle_arc = self.app_handle.clone();
+impl HidReader {
+    /// Send a command to the reader thread (dropped when no thread runs)
+    fn send_command(&self, command: ReaderCommand) {
+        if let Some(reader) = self.reader.lock().unwrap().as_ref() {
+            let _ = reader.commands.send(command);
+        }
+    }
+
+    /// Copy of the last full input report, fetched from the reader thread
+    async fn last_report(&self) -> Option<Vec<u8>> {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        self.send_command(ReaderCommand::LastReport(tx));
+        // The thread answers between reads (read timeout 50 ms)
+        tokio::time::timeout(std::time::Duration::from_millis(500), rx).await.ok()?.ok()?
+    }
+
+    /// Start the background reader thread, handing it the opened device
+    fn start_reader_task(&self, interface: i32, device: HidDevice) {
+        let (commands, command_rx) = mpsc::channel();
+        let shared = self.shared.clone();
+        let mut mapping_data = self.mapping_data.lock().unwrap().clone();
+        let mut app_handle = self.app_handle.lock().unwrap().clone();
+        *shared.link.lock().unwrap() = link::LinkTracker::new();
+        let controls = control::query_support(&device);
+        if controls.version.is_some() {
+            log::info!("HID control report v{} supported: {:?}", controls.version.unwrap_or_default(), controls.controls);
+        }
+        *shared.controls.lock().unwrap() = controls;
+        shared.set_connection(HidConnectionEvent::new(true, Some(interface), None), app_handle.as_ref());
 
         let handle = thread::spawn(move || {
-            // Build a small single-threaded runtime once for locking the tokio::Mutex
-            let rt = match tokio::runtime::Builder::new_current_thread().enable_time().build() {
-                Ok(r) => r,
-                Err(e) => { log::error!("Failed to build runtime for HID reader: {}", e); return; }
-            };
             let mut preferred_offset: Option<usize> = None; // For heuristic fallback only
             let mut report_count: u64 = 0;
             let mut last_sync_time = std::time::Instant::now();
-            const SYNC_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1); // Sync every second
+            let mut report_clock = ReportClock::new();
+            // Last full report, copied out on request for debugging
+            let mut last_report = [0u8; 64];
+            let mut last_report_len = 0usize;
             // Track full-range logical IDs (supports >64) for mapped mode
             let mut prev_pressed_set: std::collections::HashSet<u8> = std::collections::HashSet::new();
+            // Last axis values seen while an input recording is active
+            let mut prev_axes: Vec<Option<i32>> = Vec::new();
+            let mut prev_hats: Vec<HatPosition> = Vec::new();
             // previous logical state no longer needed (we derive changes from stored state)
             // Heuristic baseline variables (used only if mapping feature unsupported)
             let mut baseline_0: Option<u64> = None;
@@ -517,25 +910,51 @@ impl HidReader {
             let mut baseline_extra: std::collections::HashMap<usize, u64> = std::collections::HashMap::new();
             let mut first_byte_constant: Option<u8> = None;
             let mut first_byte_varies = false;
-            while running_flag.load(Ordering::SeqCst) {
-                // Build a tiny runtime per loop (cost acceptable given low frequency)
+            let mut consecutive_errors = 0u32;
+            // Set when the interface stops answering; a requested stop leaves it `None`
+            let mut lost_reason: Option<String> = None;
+            'reader: loop {
+                // Handle pending commands between reads; a dropped sender stops the thread too
+                loop {
+                    match command_rx.try_recv() {
+                        Ok(ReaderCommand::SetMapping(mapping)) => mapping_data = Some(mapping),
+                        Ok(ReaderCommand::SetAppHandle(handle)) => app_handle = Some(handle),
+                        Ok(ReaderCommand::LastReport(reply)) => {
+                            let _ = reply.send((last_report_len > 0).then(|| last_report[..last_report_len].to_vec()));
+                        }
+                        Ok(ReaderCommand::SendFeatureReport(report, reply)) => {
+                            let _ = reply.send(device.send_feature_report(&report).map_err(HidError::from));
+                        }
+                        Ok(ReaderCommand::Stop) | Err(mpsc::TryRecvError::Disconnected) => break 'reader,
+                        Err(mpsc::TryRecvError::Empty) => break,
+                    }
+                }
                 let mut buf = [0u8; 64];
-                let maybe_size = rt.block_on(async {
-                    let guard = device_arc.lock().await; // MutexGuard<Option<HidDevice>>
-                    if let Some(device) = guard.as_ref() {
-                        device.read_timeout(&mut buf, 50).ok()
-                    } else { None }
-                });
-                let Some(sz) = maybe_size else { std::thread::sleep(std::time::Duration::from_millis(10)); continue; };
+                let read_result = device.read_timeout(&mut buf, 50);
+                let received_at = std::time::Instant::now();
+                let sz = match read_result {
+                    Ok(sz) => { consecutive_errors = 0; sz }
+                    Err(e) => {
+                        consecutive_errors += 1;
+                        if consecutive_errors >= MAX_CONSECUTIVE_READ_ERRORS {
+                            log::warn!("HID interface {} stopped responding after {} read errors: {}", interface, consecutive_errors, e);
+                            lost_reason = Some(format!("HID read failed: {}", e));
+                            break 'reader;
+                        }
+                        std::thread::sleep(std::time::Duration::from_millis(10));
+                        continue;
+                    }
+                };
                 if sz == 0 { continue; }
+                let report_interval = report_clock.report(received_at);
+                let timestamp = report_clock.utc(received_at);
                 // Store raw report for debugging
-                if let Ok(mut lr) = last_report_arc.lock() { lr[..sz.min(64)].copy_from_slice(&buf[..sz.min(64)]); }
-                if let Ok(mut ll) = last_report_len_arc.lock() { *ll = sz as usize; }
+                last_report[..sz.min(64)].copy_from_slice(&buf[..sz.min(64)]);
+                last_report_len = sz.min(64);
                 report_count += 1;
 
                 // Check if mapping feature available
-                let mapping_opt = { mapping_data_arc.lock().unwrap().clone() };
-                if let Some(mapping) = mapping_opt {
+                if let Some(mapping) = mapping_data.as_ref() {
                     // Determine if the first byte is a report ID and derive payload accordingly
                     let has_report_id = mapping.info.input_report_id != 0 && buf[0] == mapping.info.input_report_id;
                     let payload_start = if has_report_id { 1 } else { 0 };
@@ -546,9 +965,46 @@ impl HidReader {
                     let btn_bytes_len = ((mapping.info.button_count as usize + 7) / 8).min(16);
                     if payload.len() < btn_off + btn_bytes_len { continue; }
                     let buttons_slice = &payload[btn_off..btn_off+btn_bytes_len];
-                    // Build full-range logical pressed set and 64-bit mask for UI
+                    if let Some(counter) = frame_counter(payload, mapping.info.frame_counter_offset) {
+                        if crate::diagnostics::wants_hid_frames() {
+                            crate::diagnostics::feed_hid_frame(counter, received_at);
+                        }
+                        let warning = shared.link.lock().unwrap().observe(counter, received_at);
+                        if let Some(warning) = warning {
+                            log::warn!(
+                                "[HID iface {}] {} of {} frames dropped in {} ms ({:.1}%)",
+                                interface, warning.dropped_frames, warning.frames + warning.dropped_frames,
+                                warning.window_ms, warning.drop_rate * 100.0
+                            );
+                            if let Some(handle) = app_handle.as_ref() {
+                                let _ = handle.emit(HID_LINK_WARNING_EVENT, &warning);
+                            }
+                        }
+                    }
+                    if crate::recording::get_recorder().is_recording() || crate::telemetry::is_streaming() {
+                        record_axis_changes(payload, &mapping.axes, &mut prev_axes);
+                    }
+                    if crate::interop::get_virtual_bridge().is_running() {
+                        crate::interop::feed_axes(&axis_values(payload, &mapping.axes));
+                    }
+                    if crate::diagnostics::wants_hid_axes() {
+                        crate::diagnostics::feed_axes(&axis_values(payload, &mapping.axes), received_at);
+                    }
+                    let hats: Vec<HatPosition> = mapping.hats.iter().map_while(|field| hat::read_hat(payload, field)).collect();
+                    if hats != prev_hats {
+                        if let Some(handle) = app_handle.as_ref() {
+                            for (hat_id, &position) in hats.iter().enumerate() {
+                                if prev_hats.get(hat_id).copied().unwrap_or_default() != position {
+                                    let _ = handle.emit(HAT_CHANGED_EVENT, &HatEvent::new(hat_id as u8, position, timestamp));
+                                }
+                            }
+                        }
+                        log::debug!("[HID iface {}] hats: {:?}", interface, hats);
+                        prev_hats = hats;
+                    }
+                    // Build full-range logical pressed set and 128-bit mask for UI
                     let mut new_pressed_set: std::collections::HashSet<u8> = std::collections::HashSet::new();
-                    let mut logical_u64: u64 = 0;
+                    let mut logical_mask: u128 = 0;
                     for bit_index in 0..(mapping.info.button_count as usize) {
                         let byte = buttons_slice[bit_index / 8];
                         let bit_pos = bit_index % 8;
@@ -556,7 +1012,7 @@ impl HidReader {
                         if pressed {
                             let logical_id = mapping.mapping.get(bit_index).copied().unwrap_or(bit_index as u8);
                             new_pressed_set.insert(logical_id);
-                            if (logical_id as usize) < 64 { logical_u64 |= 1u64 << (logical_id as usize); }
+                            if (logical_id as usize) < MAX_BUTTONS { logical_mask |= 1u128 << (logical_id as usize); }
                         }
                     }
                     // Diff sets to detect changes across the entire logical range
@@ -568,27 +1024,24 @@ impl HidReader {
                     if !pressed_delta.is_empty() || !released_delta.is_empty() {
                         // Keep the previous set in sync
                         prev_pressed_set = new_pressed_set;
-                        let timestamp = chrono::Utc::now();
-                        // Emit events for all changed buttons (including >63)
-                        if let Ok(app_handle) = app_handle_arc.lock() {
-                            if let Some(handle) = app_handle.as_ref() {
-                                for &button_id in &pressed_delta {
-                                    let event = ButtonEvent { button_id, pressed: true, timestamp };
-                                    let _ = handle.emit("button-changed", &event);
-                                }
-                                for &button_id in &released_delta {
-                                    let event = ButtonEvent { button_id, pressed: false, timestamp };
-                                    let _ = handle.emit("button-changed", &event);
-                                }
+                        record_button_changes(&pressed_delta, &released_delta);
+                        crate::interop::feed_buttons(&pressed_delta, &released_delta);
+                        crate::diagnostics::feed_buttons(&pressed_delta, &released_delta, received_at);
+                        // Emit events for all changed buttons
+                        if let Some(handle) = app_handle.as_ref() {
+                            for &button_id in &pressed_delta {
+                                let event = ButtonEvent::new(button_id, true, timestamp).with_report_interval(report_interval);
+                                let _ = handle.emit("button-changed", &event);
+                            }
+                            for &button_id in &released_delta {
+                                let event = ButtonEvent::new(button_id, false, timestamp).with_report_interval(report_interval);
+                                let _ = handle.emit("button-changed", &event);
                             }
                         }
-                        // Update cached 64-bit state for UI
-                        if let Ok(mut state_guard) = state_arc.lock() {
-                            state_guard.buttons = logical_u64;
-                            state_guard.timestamp = timestamp;
-                        }
-                        if let Ok(mut off) = sel_offset_arc.lock() { *off = Some(btn_off + payload_start); }
-                        if let Ok(mut raw) = last_raw_arc.lock() { *raw = logical_u64; }
+                        // Update cached state for UI
+                        shared.set_buttons(logical_mask, timestamp);
+                        shared.selected_offset.store(btn_off + payload_start, Ordering::Relaxed);
+                        shared.last_raw_value.store(logical_mask as u64, Ordering::Relaxed);
                         // Trim for logging readability
                         let mut p0 = pressed_delta.clone(); p0.sort(); let p0 = if p0.len()>8 { p0[..8].to_vec() } else { p0 };
                         let mut r0 = released_delta.clone(); r0.sort(); let r0 = if r0.len()>8 { r0[..8].to_vec() } else { r0 };
@@ -596,14 +1049,12 @@ impl HidReader {
                         let p_disp: Vec<u8> = p0.iter().map(|v| v.saturating_add(1)).collect();
                         let r_disp: Vec<u8> = r0.iter().map(|v| v.saturating_add(1)).collect();
                         log::info!(
-                            "[HID iface {}] mapped change: pressed={:?} released={:?} mask64=0x{:016X} ({} logical, off {} rid_present={} len={}, id_base=1)",
-                            interface, p_disp, r_disp, logical_u64, mapping.info.button_count, btn_off + payload_start, has_report_id, sz
+                            "[HID iface {}] mapped change: pressed={:?} released={:?} mask=0x{:032X} ({} logical, off {} rid_present={} len={}, id_base=1)",
+                            interface, p_disp, r_disp, logical_mask, mapping.info.button_count, btn_off + payload_start, has_report_id, sz
                         );
                     } else if report_count % 200 == 0 {
                         // Heartbeat: refresh timestamp so UI doesn’t stale out
-                        if let Ok(mut state_guard) = state_arc.lock() {
-                            state_guard.timestamp = chrono::Utc::now();
-                        }
+                        shared.touch();
                         log::debug!("[HID iface {}] heartbeat rpt#{} no change", interface, report_count);
                     }
                     continue; // processed
@@ -636,79 +1087,127 @@ impl HidReader {
                 // Previously we shifted dynamic bits left by 1 assuming firmware logical button IDs started at 1.
                 // This caused off-by-one mismatches in UI highlighting. Use raw dynamic bits directly.
                 let logical_val = chosen_dyn_val;
-                if let Ok(mut state_guard) = state_arc.lock() {
-                    if state_guard.buttons != logical_val {
-                        let changed = state_guard.buttons ^ logical_val;
-                        let pressed_now = changed & logical_val;
-                        let released_now = changed & state_guard.buttons;
-                        let mut newly_pressed: Vec<u8> = Vec::new();
-                        let mut newly_released: Vec<u8> = Vec::new();
-                        for b in 0..64 { if (pressed_now & (1u64<<b)) != 0 { newly_pressed.push(b as u8); if newly_pressed.len()>=8 { break; }}}
-                        for b in 0..64 { if (released_now & (1u64<<b)) != 0 { newly_released.push(b as u8); if newly_released.len()>=8 { break; }}}
-                        let timestamp = chrono::Utc::now();
-                        log::info!(
-                            "[BACKEND HID {} LEGACY @ {}] Button change: pressed={:?} released={:?} (report #{}, offset={}, raw=0x{:016X})",
-                            interface, timestamp.format("%H:%M:%S%.3f"), newly_pressed, newly_released, report_count, chosen_offset, logical_val
-                        );
-                        
-                        // Emit events for button changes
-                        if let Ok(app_handle) = app_handle_arc.lock() {
-                            if let Some(handle) = app_handle.as_ref() {
-                                // Emit events for pressed buttons
-                                for &button_id in &newly_pressed {
-                                    let event = ButtonEvent {
-                                        button_id,
-                                        pressed: true,
-                                        timestamp,
-                                    };
-                                    let _ = handle.emit("button-changed", &event);
-                                }
-                                // Emit events for released buttons
-                                for &button_id in &newly_released {
-                                    let event = ButtonEvent {
-                                        button_id,
-                                        pressed: false,
-                                        timestamp,
-                                    };
-                                    let _ = handle.emit("button-changed", &event);
-                                }
-                            }
+                // The heuristic only ever covers 64 buttons
+                let prev_buttons = shared.mask() as u64;
+                if prev_buttons != logical_val {
+                    let changed = prev_buttons ^ logical_val;
+                    let pressed_now = changed & logical_val;
+                    let released_now = changed & prev_buttons;
+                    let mut newly_pressed: Vec<u8> = Vec::new();
+                    let mut newly_released: Vec<u8> = Vec::new();
+                    for b in 0..64 { if (pressed_now & (1u64<<b)) != 0 { newly_pressed.push(b as u8); if newly_pressed.len()>=8 { break; }}}
+                    for b in 0..64 { if (released_now & (1u64<<b)) != 0 { newly_released.push(b as u8); if newly_released.len()>=8 { break; }}}
+                    record_button_changes(&newly_pressed, &newly_released);
+                    crate::interop::feed_buttons(&newly_pressed, &newly_released);
+                    crate::diagnostics::feed_buttons(&newly_pressed, &newly_released, received_at);
+                    log::info!(
+                        "[BACKEND HID {} LEGACY @ {}] Button change: pressed={:?} released={:?} (report #{}, offset={}, raw=0x{:016X})",
+                        interface, timestamp.format("%H:%M:%S%.3f"), newly_pressed, newly_released, report_count, chosen_offset, logical_val
+                    );
+
+                    // Emit events for button changes
+                    if let Some(handle) = app_handle.as_ref() {
+                        // Emit events for pressed buttons
+                        for &button_id in &newly_pressed {
+                            let event = ButtonEvent::new(button_id, true, timestamp).with_report_interval(report_interval);
+                            let _ = handle.emit("button-changed", &event);
                         }
-                        state_guard.buttons = logical_val;
-                        state_guard.timestamp = chrono::Utc::now();
-                        if let Ok(mut o) = sel_offset_arc.lock() { *o = Some(chosen_offset); }
-                        if let Ok(mut lr) = last_raw_arc.lock() { *lr = logical_val; }
-                        if report_count <= 5 {
-                            log::info!(
-                                "[HID iface {} LEGACY] initial chosen offset {} dyn_raw=0x{:016X} logical=0x{:016X}",
-                                interface, chosen_offset, chosen_dyn_val, logical_val
-                            );
+                        // Emit events for released buttons
+                        for &button_id in &newly_released {
+                            let event = ButtonEvent::new(button_id, false, timestamp).with_report_interval(report_interval);
+                            let _ = handle.emit("button-changed", &event);
                         }
-                    } else if report_count % 400 == 0 {
-                        state_guard.timestamp = chrono::Utc::now();
-                        log::debug!("[HID iface {} LEGACY] heartbeat rpt#{}", interface, report_count);
                     }
+                    shared.set_buttons(logical_val as u128, timestamp);
+                    shared.selected_offset.store(chosen_offset, Ordering::Relaxed);
+                    shared.last_raw_value.store(logical_val, Ordering::Relaxed);
+                    if report_count <= 5 {
+                        log::info!(
+                            "[HID iface {} LEGACY] initial chosen offset {} dyn_raw=0x{:016X} logical=0x{:016X}",
+                            interface, chosen_offset, chosen_dyn_val, logical_val
+                        );
+                    }
+                } else if report_count % 400 == 0 {
+                    shared.touch();
+                    log::debug!("[HID iface {} LEGACY] heartbeat rpt#{}", interface, report_count);
                 }
-                
+
                 // Emit periodic state sync event
-                if last_sync_time.elapsed() >= SYNC_INTERVAL {
+                if last_sync_time.elapsed() >= std::time::Duration::from_millis(get_state_sync_interval_ms()) {
                     last_sync_time = std::time::Instant::now();
-                    if let Ok(state) = state_arc.lock() {
-                        if let Ok(app_handle) = app_handle_arc.lock() {
-                            if let Some(handle) = app_handle.as_ref() {
-                                let _ = handle.emit("button-state-sync", &state.clone());
-                                log::debug!("Emitted button state sync: 0x{:016X}", state.buttons);
-                            }
-                        }
+                    if let Some(handle) = app_handle.as_ref() {
+                        let state = shared.snapshot();
+                        let _ = handle.emit("button-state-sync", &state);
+                        log::debug!("Emitted button state sync: 0x{:032X}", state.mask());
                     }
                 }
             }
             log::info!("HID reader thread exiting (interface {})", interface);
+            shared.set_connection(HidConnectionEvent::new(false, None, lost_reason), app_handle.as_ref());
         });
 
-        let mut handle_guard = self.reader_handle.lock().await;
-        *handle_guard = Some(handle);
-        Ok(())
+        *self.reader.lock().unwrap() = Some(ReaderThread { commands, handle });
+    }
+}
+
+/// Forward button transitions to the input recorder and telemetry clients (no-op unless
+/// recording or streaming)
+fn record_button_changes(pressed: &[u8], released: &[u8]) {
+    use crate::recording::{record, RecordedEvent};
+    use crate::telemetry::{publish, TelemetryEvent};
+    for &button_id in pressed { record(RecordedEvent::Button { button_id, pressed: true }); }
+    for &button_id in released { record(RecordedEvent::Button { button_id, pressed: false }); }
+    if crate::telemetry::is_streaming() {
+        let timestamp = chrono::Utc::now();
+        for &button_id in pressed { publish(TelemetryEvent::Button { button_id, pressed: true, timestamp }); }
+        for &button_id in released { publish(TelemetryEvent::Button { button_id, pressed: false, timestamp }); }
+    }
+}
+
+/// Axis values of a report payload, read from the mapping's axis fields and shifted so the
+/// logical minimum reads as 0; empty when the payload is too short for any of them.
+fn axis_values(payload: &[u8], axes: &[descriptor::AxisField]) -> Vec<u16> {
+    let mut values = Vec::with_capacity(axes.len());
+    for axis in axes {
+        if (axis.bit_offset + axis.bit_size).div_ceil(8) as usize > payload.len() { return Vec::new(); }
+        let mut raw = 0u32;
+        for bit in 0..axis.bit_size {
+            let position = axis.bit_offset + bit;
+            if payload[(position / 8) as usize] & (1 << (position % 8)) != 0 {
+                raw |= 1 << bit;
+            }
+        }
+        let value = if axis.logical_min < 0 {
+            // Sign-extend two's complement fields
+            let shift = 32 - axis.bit_size;
+            ((raw << shift) as i32) >> shift
+        } else {
+            raw as i32
+        };
+        values.push((value as i64 - axis.logical_min as i64).clamp(0, u16::MAX as i64) as u16);
+    }
+    values
+}
+
+/// 16-bit little-endian frame counter at `offset` of the payload (0xFF = not reported)
+fn frame_counter(payload: &[u8], offset: u8) -> Option<u16> {
+    if offset == 0xFF { return None; }
+    let offset = offset as usize;
+    payload.get(offset..offset + 2).map(|word| u16::from_le_bytes([word[0], word[1]]))
+}
+
+/// Record changed axis values (see `axis_values`) and stream them to telemetry clients
+fn record_axis_changes(payload: &[u8], axes: &[descriptor::AxisField], prev: &mut Vec<Option<i32>>) {
+    let values = axis_values(payload, axes);
+    if values.is_empty() { return; }
+    prev.resize(values.len(), None);
+    for (axis_id, value) in values.into_iter().enumerate() {
+        let value = value as i32;
+        if prev[axis_id] != Some(value) {
+            prev[axis_id] = Some(value);
+            crate::recording::record(crate::recording::RecordedEvent::Axis { axis_id: axis_id as u8, value });
+            crate::telemetry::publish(crate::telemetry::TelemetryEvent::Axis { axis_id: axis_id as u8, value });
+        }
     }
 }
 
@@ -799,4 +1298,80 @@ mod tests {
             for (j, other) in feature4.iter().enumerate() { if j != bit_index { assert_ne!(logical_id, other); } }
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn button_states_cover_128_buttons() {
+        let states = ButtonStates::from_mask(1 | 1u128 << 64 | 1u128 << 127, chrono::Utc::now());
+        assert_eq!(states.buttons[8], 0x01);
+        assert_eq!(states.buttons[15], 0x80);
+        assert!(states.is_button_pressed(127) && !states.is_button_pressed(126));
+        assert_eq!(states.get_pressed_buttons(), vec![0, 64, 127]);
+        let shared = SharedState::new();
+        shared.set_buttons(states.mask(), states.timestamp);
+        assert_eq!(shared.snapshot().buttons, states.buttons);
+    }
+
+    #[test]
+    fn maps_descriptor_layout() {
+        let axis = |i: u32| descriptor::AxisField { usage: 0x30 + i as u16, bit_offset: i * 16, bit_size: 16, logical_min: 0, logical_max: 65535 };
+        let mut layout = descriptor::ReportLayout {
+            report_id: 1,
+            button_bit_offset: 32,
+            buttons: vec![0, 1, 2, 3, 4, 5, 6, 7, 8],
+            axes: vec![axis(0), axis(1)],
+            hats: vec![descriptor::HatField { bit_offset: 48, bit_size: 4, logical_min: 0, logical_max: 7 }],
+            report_bytes: 7,
+        };
+        let md = mapping_from_layout(&layout).unwrap();
+        let (button_count, axis_count, offset, crc) = (md.info.button_count, md.info.axis_count, md.info.button_byte_offset, md.info.mapping_crc);
+        assert_eq!((button_count, axis_count, offset, crc), (9, 2, 4, 0));
+        let hat_count = md.info.hat_count;
+        assert_eq!((hat_count, md.hats.len()), (1, 1));
+        assert_eq!(md.source, MappingSource::ReportDescriptor);
+
+        // Axes keep their descriptor layout, reordered buttons are not sequential
+        layout.axes[1].bit_size = 8;
+        layout.buttons.swap(0, 1);
+        let md = mapping_from_layout(&layout).unwrap();
+        let (axis_count, crc) = (md.info.axis_count, md.info.mapping_crc);
+        assert_eq!(axis_count, 2);
+        assert_eq!(axis_values(&[0x34, 0x12, 0x80, 0x00, 0x00], &md.axes), vec![0x1234, 0x80]);
+        assert_ne!(crc, 0);
+
+        layout.button_bit_offset = 36;
+        assert!(mapping_from_layout(&layout).is_none());
+    }
+
+    #[test]
+    fn derives_firmware_axes() {
+        let mut raw = HIDMappingInfoRaw { axis_count: 2, button_byte_offset: 6, frame_counter_offset: 0, ..Default::default() };
+        let axes = firmware_axes(&raw);
+        assert_eq!(axes.iter().map(|a| (a.bit_offset, a.bit_size)).collect::<Vec<_>>(), vec![(16, 16), (32, 16)]);
+        let payload = [0xAA, 0xBB, 0x01, 0x00, 0xFF, 0xFF, 0x00];
+        assert_eq!(axis_values(&payload, &axes), vec![1, 0xFFFF]);
+
+        raw.frame_counter_offset = 0xFF;
+        raw.axis_count = 4;
+        let axes = firmware_axes(&raw);
+        assert_eq!(axes.iter().map(|a| (a.bit_offset, a.bit_size)).collect::<Vec<_>>(), vec![(0, 8), (8, 8), (16, 8), (24, 8)]);
+        raw.axis_count = 7;
+        assert!(firmware_axes(&raw).is_empty(), "axes do not fit ahead of the buttons");
+        assert!(axis_values(&payload[..3], &firmware_axes(&HIDMappingInfoRaw { axis_count: 2, button_byte_offset: 4, frame_counter_offset: 0xFF, ..Default::default() })).is_empty());
+    }
+
+    #[test]
+    fn reads_signed_axes() {
+        let axis = descriptor::AxisField { usage: 0x30, bit_offset: 0, bit_size: 16, logical_min: -32767, logical_max: 32767 };
+        assert_eq!(axis_values(&[0x01, 0x80], &[axis.clone()]), vec![0]);
+        assert_eq!(axis_values(&[0x00, 0x00], &[axis.clone()]), vec![32767]);
+        assert_eq!(axis_values(&[0xFF, 0x7F], &[axis]), vec![65534]);
+    }
+
+    #[test]
+    fn reads_frame_counter() {
+        let payload = [0x00, 0x34, 0x12, 0xFF];
+        assert_eq!(frame_counter(&payload, 1), Some(0x1234));
+        assert_eq!(frame_counter(&payload, 3), None, "counter runs past the payload");
+        assert_eq!(frame_counter(&payload, 0xFF), None);
+    }
+}
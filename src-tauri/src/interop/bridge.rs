@@ -0,0 +1,378 @@
+//! Maps source buttons/axes onto a virtual joystick and pushes changes to its backend.
+//!
+//! The bridge can also emulate a shift layer for firmware that cannot store one: the modifier
+//! button is not forwarded, and while it is held, buttons pressed with a layered assignment
+//! report their shifted button (then `button_map` applies). A button keeps the virtual button it
+//! was pressed with until it is released, even if the modifier changes in between.
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+use super::{open_backend, BridgeError, Result};
+use crate::config::shift_layer::ShiftLayer;
+
+/// Virtual joystick device driven by the bridge. Buttons and axes are 0-based; axis values span
+/// the full `u16` range and are rescaled by the backend.
+pub trait VirtualJoystick: Send {
+    fn backend_name(&self) -> &'static str;
+    fn button_count(&self) -> u8;
+    fn axis_count(&self) -> u8;
+    fn set_button(&mut self, button: u8, pressed: bool) -> std::io::Result<()>;
+    fn set_axis(&mut self, axis: u8, value: u16) -> std::io::Result<()>;
+    /// Publish the changes made since the last flush
+    fn flush(&mut self) -> std::io::Result<()>;
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VirtualBridgeOptions {
+    /// vJoy device number (1-16); uinput always creates a new device
+    pub device_index: u8,
+    /// Source logical button -> virtual button; unmapped buttons pass through
+    pub button_map: HashMap<u8, u8>,
+    /// Source axis -> virtual axis; unmapped axes pass through
+    pub axis_map: HashMap<u8, u8>,
+    /// Source axes whose value is mirrored
+    pub inverted_axes: Vec<u8>,
+}
+
+impl Default for VirtualBridgeOptions {
+    fn default() -> Self {
+        Self {
+            device_index: 1,
+            button_map: HashMap::new(),
+            axis_map: HashMap::new(),
+            inverted_axes: Vec::new(),
+        }
+    }
+}
+
+impl VirtualBridgeOptions {
+    pub fn validate(&self) -> Result<()> {
+        if !(1..=16).contains(&self.device_index) {
+            return Err(BridgeError::InvalidOption(format!("device_index {} is outside 1-16", self.device_index)));
+        }
+        Ok(())
+    }
+
+    pub fn virtual_button(&self, source: u8) -> u8 {
+        self.button_map.get(&source).copied().unwrap_or(source)
+    }
+
+    pub fn virtual_axis(&self, source: u8) -> u8 {
+        self.axis_map.get(&source).copied().unwrap_or(source)
+    }
+
+    pub fn axis_value(&self, source: u8, value: u16) -> u16 {
+        if self.inverted_axes.contains(&source) { u16::MAX - value } else { value }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct VirtualBridgeStatus {
+    pub running: bool,
+    pub backend: Option<String>,
+    pub started_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub button_updates: u64,
+    pub axis_updates: u64,
+    /// Updates for inputs mapped beyond the virtual device's buttons or axes
+    pub dropped_updates: u64,
+    /// Backend error that stopped the bridge
+    pub last_error: Option<String>,
+    /// Emulated shift layer, kept across restarts of the bridge
+    pub shift_layer: Option<ShiftLayer>,
+}
+
+struct ActiveBridge {
+    device: Box<dyn VirtualJoystick>,
+    options: VirtualBridgeOptions,
+    /// Held source buttons and the virtual button each was pressed with
+    pressed_sources: HashMap<u8, u8>,
+    shift_held: bool,
+    last_axes: Vec<Option<u16>>,
+}
+
+impl ActiveBridge {
+    fn update_buttons(&mut self, pressed: &[u8], released: &[u8], status: &mut VirtualBridgeStatus) -> std::io::Result<()> {
+        let mut touched: Vec<u8> = Vec::with_capacity(pressed.len() + released.len());
+        let shift = status.shift_layer.as_ref();
+        let is_modifier = |source: u8| shift.is_some_and(|layer| layer.modifier_button == source);
+        for &source in pressed {
+            if is_modifier(source) {
+                self.shift_held = true;
+                continue;
+            }
+            let logical = shift.filter(|_| self.shift_held).and_then(|layer| layer.shifted_button(source)).unwrap_or(source);
+            let button = self.options.virtual_button(logical);
+            self.pressed_sources.insert(source, button);
+            touched.push(button);
+        }
+        for &source in released {
+            if is_modifier(source) {
+                self.shift_held = false;
+                continue;
+            }
+            let button = self.pressed_sources.remove(&source).unwrap_or_else(|| self.options.virtual_button(source));
+            touched.push(button);
+        }
+        touched.sort_unstable();
+        touched.dedup();
+        for button in touched {
+            if button >= self.device.button_count() {
+                status.dropped_updates += 1;
+                continue;
+            }
+            // Several sources may share a virtual button; it stays down while any of them is
+            let down = self.pressed_sources.values().any(|&b| b == button);
+            self.device.set_button(button, down)?;
+            status.button_updates += 1;
+        }
+        self.device.flush()
+    }
+
+    fn update_axes(&mut self, values: &[u16], status: &mut VirtualBridgeStatus) -> std::io::Result<()> {
+        if self.last_axes.len() < values.len() {
+            self.last_axes.resize(values.len(), None);
+        }
+        let mut changed = false;
+        for (source, &value) in values.iter().enumerate() {
+            if self.last_axes[source] == Some(value) {
+                continue;
+            }
+            self.last_axes[source] = Some(value);
+            let source = source as u8;
+            let axis = self.options.virtual_axis(source);
+            if axis >= self.device.axis_count() {
+                status.dropped_updates += 1;
+                continue;
+            }
+            self.device.set_axis(axis, self.options.axis_value(source, value))?;
+            status.axis_updates += 1;
+            changed = true;
+        }
+        if changed {
+            self.device.flush()?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct BridgeState {
+    active: Option<ActiveBridge>,
+    status: VirtualBridgeStatus,
+}
+
+/// Runs at most one virtual joystick at a time
+#[derive(Default)]
+pub struct VirtualBridge {
+    running: AtomicBool,
+    state: Mutex<BridgeState>,
+}
+
+impl VirtualBridge {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::Relaxed)
+    }
+
+    pub fn status(&self) -> VirtualBridgeStatus {
+        self.state.lock().unwrap_or_else(|e| e.into_inner()).status.clone()
+    }
+
+    /// Open the platform backend and start bridging, replacing a running bridge.
+    /// `pressed` seeds the buttons already held down.
+    pub fn start(&self, options: VirtualBridgeOptions, pressed: &[u8]) -> Result<VirtualBridgeStatus> {
+        options.validate()?;
+        self.stop();
+        let device = open_backend(&options)?;
+        self.start_with(device, options, pressed)
+    }
+
+    /// Start bridging into an already opened device
+    pub fn start_with(&self, device: Box<dyn VirtualJoystick>, options: VirtualBridgeOptions, pressed: &[u8]) -> Result<VirtualBridgeStatus> {
+        self.stop();
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let backend = device.backend_name();
+        let mut active = ActiveBridge { device, options, pressed_sources: HashMap::new(), shift_held: false, last_axes: Vec::new() };
+        let mut status = VirtualBridgeStatus {
+            running: true,
+            backend: Some(backend.to_string()),
+            started_at: Some(chrono::Utc::now()),
+            shift_layer: state.status.shift_layer.clone(),
+            ..Default::default()
+        };
+        active.update_buttons(pressed, &[], &mut status)?;
+        log::info!("Virtual joystick bridge started ({})", backend);
+        state.active = Some(active);
+        state.status = status.clone();
+        self.running.store(true, Ordering::Relaxed);
+        Ok(status)
+    }
+
+    /// Stop bridging and remove the virtual device. Returns the final status.
+    pub fn stop(&self) -> VirtualBridgeStatus {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        self.running.store(false, Ordering::Relaxed);
+        if state.active.take().is_some() {
+            log::info!("Virtual joystick bridge stopped");
+        }
+        state.status.running = false;
+        state.status.clone()
+    }
+
+    /// Emulate `layer` (None: no layer) now and in later runs of the bridge
+    pub fn set_shift_layer(&self, layer: Option<ShiftLayer>) -> VirtualBridgeStatus {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.status.shift_layer = layer;
+        if let Some(active) = state.active.as_mut() {
+            active.shift_held = false;
+        }
+        state.status.clone()
+    }
+
+    pub fn update_buttons(&self, pressed: &[u8], released: &[u8]) {
+        self.with_active(|active, status| active.update_buttons(pressed, released, status));
+    }
+
+    pub fn update_axes(&self, values: &[u16]) {
+        self.with_active(|active, status| active.update_axes(values, status));
+    }
+
+    /// Run `f` on the running bridge; a backend error stops the bridge
+    fn with_active(&self, f: impl FnOnce(&mut ActiveBridge, &mut VirtualBridgeStatus) -> std::io::Result<()>) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let BridgeState { active, status } = &mut *state;
+        let Some(bridge) = active.as_mut() else { return };
+        if let Err(e) = f(bridge, status) {
+            log::warn!("Virtual joystick bridge stopped after backend error: {}", e);
+            status.last_error = Some(e.to_string());
+            status.running = false;
+            *active = None;
+            self.running.store(false, Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum Call {
+        Button(u8, bool),
+        Axis(u8, u16),
+        Flush,
+    }
+
+    struct FakeJoystick {
+        calls: Arc<Mutex<Vec<Call>>>,
+        fail: bool,
+    }
+
+    impl VirtualJoystick for FakeJoystick {
+        fn backend_name(&self) -> &'static str { "fake" }
+        fn button_count(&self) -> u8 { 8 }
+        fn axis_count(&self) -> u8 { 2 }
+        fn set_button(&mut self, button: u8, pressed: bool) -> std::io::Result<()> {
+            if self.fail {
+                return Err(std::io::Error::new(std::io::ErrorKind::BrokenPipe, "gone"));
+            }
+            self.calls.lock().unwrap().push(Call::Button(button, pressed));
+            Ok(())
+        }
+        fn set_axis(&mut self, axis: u8, value: u16) -> std::io::Result<()> {
+            self.calls.lock().unwrap().push(Call::Axis(axis, value));
+            Ok(())
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            self.calls.lock().unwrap().push(Call::Flush);
+            Ok(())
+        }
+    }
+
+    fn fake(fail: bool) -> (Box<dyn VirtualJoystick>, Arc<Mutex<Vec<Call>>>) {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        (Box::new(FakeJoystick { calls: calls.clone(), fail }), calls)
+    }
+
+    #[test]
+    fn remaps_merges_and_inverts() {
+        let bridge = VirtualBridge::new();
+        let (device, calls) = fake(false);
+        let options = VirtualBridgeOptions {
+            button_map: HashMap::from([(10, 0), (11, 0)]),
+            axis_map: HashMap::from([(0, 1), (1, 0)]),
+            inverted_axes: vec![1],
+            ..Default::default()
+        };
+        bridge.start_with(device, options, &[10]).unwrap();
+        bridge.update_buttons(&[11], &[]);
+        bridge.update_buttons(&[], &[10]);
+        bridge.update_buttons(&[], &[11]);
+        bridge.update_buttons(&[20], &[]);
+        bridge.update_axes(&[100, 0]);
+        bridge.update_axes(&[100, 0]);
+
+        let calls = calls.lock().unwrap().clone();
+        let buttons: Vec<_> = calls.iter().filter(|c| matches!(c, Call::Button(..))).cloned().collect();
+        assert_eq!(buttons, vec![
+            Call::Button(0, true),
+            Call::Button(0, true),
+            Call::Button(0, true),
+            Call::Button(0, false),
+        ], "shared virtual button is released with its last source");
+        let axes: Vec<_> = calls.iter().filter(|c| matches!(c, Call::Axis(..))).cloned().collect();
+        assert_eq!(axes, vec![Call::Axis(1, 100), Call::Axis(0, u16::MAX)], "unchanged values are not resent");
+
+        let status = bridge.stop();
+        assert!(!status.running);
+        assert_eq!((status.button_updates, status.axis_updates, status.dropped_updates), (4, 2, 1));
+    }
+
+    #[test]
+    fn emulates_shift_layer() {
+        use crate::config::shift_layer::LayeredButton;
+        let bridge = VirtualBridge::new();
+        bridge.set_shift_layer(Some(ShiftLayer { modifier_button: 7, assignments: vec![LayeredButton { base_button: 1, shifted_button: 5 }] }));
+        let (device, calls) = fake(false);
+        bridge.start_with(device, VirtualBridgeOptions::default(), &[]).unwrap();
+        bridge.update_buttons(&[7], &[]);
+        bridge.update_buttons(&[1, 2], &[]);
+        bridge.update_buttons(&[], &[7]);
+        bridge.update_buttons(&[], &[1, 2]);
+        bridge.update_buttons(&[1], &[]);
+
+        let buttons: Vec<_> = calls.lock().unwrap().iter().filter(|c| matches!(c, Call::Button(..))).cloned().collect();
+        assert_eq!(buttons, vec![
+            Call::Button(2, true),
+            Call::Button(5, true),
+            Call::Button(2, false),
+            Call::Button(5, false),
+            Call::Button(1, true),
+        ], "modifier is hidden and shifted presses release on their shifted button");
+        assert!(bridge.stop().shift_layer.is_some());
+    }
+
+    #[test]
+    fn backend_error_stops_bridge() {
+        let bridge = VirtualBridge::new();
+        let (device, _) = fake(true);
+        assert!(bridge.start_with(device, VirtualBridgeOptions::default(), &[1]).is_err());
+        assert!(!bridge.is_running());
+
+        let (device, _) = fake(true);
+        bridge.start_with(device, VirtualBridgeOptions::default(), &[]).unwrap();
+        assert!(bridge.is_running());
+        bridge.update_buttons(&[1], &[]);
+        assert!(!bridge.is_running());
+        assert!(bridge.status().last_error.is_some());
+        assert!(VirtualBridgeOptions { device_index: 0, ..Default::default() }.validate().is_err());
+    }
+}
@@ -0,0 +1,69 @@
+//! Bridges decoded HID input into virtual joysticks so other applications (games, mappers) see
+//! the device as the app interprets it, including remaps that are not yet written to firmware.
+//!
+//! Backends: vJoy on Windows (the vJoy driver must be installed; `vJoyInterface.dll` is loaded at
+//! runtime) and uinput on Linux (needs write access to `/dev/uinput`).
+pub mod bridge;
+#[cfg(target_os = "linux")]
+mod uinput;
+#[cfg(windows)]
+mod vjoy;
+
+pub use bridge::{VirtualBridge, VirtualBridgeOptions, VirtualBridgeStatus, VirtualJoystick};
+
+#[derive(Debug, thiserror::Error)]
+pub enum BridgeError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Virtual joystick backend unavailable: {0}")]
+    Unavailable(String),
+
+    #[error("Virtual joysticks are not supported on this platform")]
+    Unsupported,
+
+    #[error("Invalid bridge option: {0}")]
+    InvalidOption(String),
+}
+
+pub type Result<T> = std::result::Result<T, BridgeError>;
+
+/// Open the platform's virtual joystick backend
+pub fn open_backend(options: &VirtualBridgeOptions) -> Result<Box<dyn VirtualJoystick>> {
+    #[cfg(target_os = "linux")]
+    {
+        let _ = options;
+        Ok(Box::new(uinput::UinputJoystick::create()?))
+    }
+    #[cfg(windows)]
+    {
+        Ok(Box::new(vjoy::VJoyJoystick::acquire(options.device_index)?))
+    }
+    #[cfg(not(any(target_os = "linux", windows)))]
+    {
+        let _ = options;
+        Err(BridgeError::Unsupported)
+    }
+}
+
+/// Global bridge instance fed by the HID reader
+static BRIDGE: once_cell::sync::Lazy<VirtualBridge> = once_cell::sync::Lazy::new(VirtualBridge::new);
+
+/// Get the global bridge instance
+pub fn get_virtual_bridge() -> &'static VirtualBridge {
+    &BRIDGE
+}
+
+/// Forward button transitions to the bridge (no-op unless running)
+pub fn feed_buttons(pressed: &[u8], released: &[u8]) {
+    if BRIDGE.is_running() {
+        BRIDGE.update_buttons(pressed, released);
+    }
+}
+
+/// Forward the current axis values to the bridge (no-op unless running)
+pub fn feed_axes(values: &[u16]) {
+    if BRIDGE.is_running() {
+        BRIDGE.update_axes(values);
+    }
+}
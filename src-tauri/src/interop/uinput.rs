@@ -0,0 +1,160 @@
+//! Linux uinput backend: creates a "JoyCore-X Virtual Joystick" event device.
+//!
+//! Buttons map to BTN_TRIGGER..BTN_DEAD followed by BTN_TRIGGER_HAPPY1..40; axes to
+//! ABS_X..ABS_RUDDER.
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::io::AsRawFd;
+
+use super::bridge::VirtualJoystick;
+use super::{BridgeError, Result};
+
+const UINPUT_PATH: &str = "/dev/uinput";
+const DEVICE_NAME: &str = "JoyCore-X Virtual Joystick";
+
+const EV_SYN: u16 = 0x00;
+const EV_KEY: u16 = 0x01;
+const EV_ABS: u16 = 0x03;
+const SYN_REPORT: u16 = 0;
+const BUS_USB: u16 = 0x03;
+
+const BTN_TRIGGER: u16 = 0x120;
+const BTN_JOYSTICK_COUNT: u8 = 16;
+const BTN_TRIGGER_HAPPY: u16 = 0x2c0;
+const BTN_TRIGGER_HAPPY_COUNT: u8 = 40;
+/// ABS_X, ABS_Y, ABS_Z, ABS_RX, ABS_RY, ABS_RZ, ABS_THROTTLE, ABS_RUDDER
+const AXIS_CODES: [u16; 8] = [0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07];
+
+// ioctl numbers, generic _IOC encoding (x86, ARM)
+const fn io(nr: u64) -> u64 {
+    ((b'U' as u64) << 8) | nr
+}
+const fn iow(nr: u64, size: usize) -> u64 {
+    (1 << 30) | ((size as u64) << 16) | io(nr)
+}
+const UI_DEV_CREATE: u64 = io(1);
+const UI_DEV_DESTROY: u64 = io(2);
+const UI_DEV_SETUP: u64 = iow(3, std::mem::size_of::<libc::uinput_setup>());
+const UI_ABS_SETUP: u64 = iow(4, std::mem::size_of::<libc::uinput_abs_setup>());
+const UI_SET_EVBIT: u64 = iow(100, std::mem::size_of::<libc::c_int>());
+const UI_SET_KEYBIT: u64 = iow(101, std::mem::size_of::<libc::c_int>());
+const UI_SET_ABSBIT: u64 = iow(103, std::mem::size_of::<libc::c_int>());
+
+fn button_code(button: u8) -> Option<u16> {
+    if button < BTN_JOYSTICK_COUNT {
+        Some(BTN_TRIGGER + button as u16)
+    } else if button < BTN_JOYSTICK_COUNT + BTN_TRIGGER_HAPPY_COUNT {
+        Some(BTN_TRIGGER_HAPPY + (button - BTN_JOYSTICK_COUNT) as u16)
+    } else {
+        None
+    }
+}
+
+pub struct UinputJoystick {
+    file: File,
+}
+
+impl UinputJoystick {
+    pub fn create() -> Result<Self> {
+        let file = OpenOptions::new()
+            .write(true)
+            .custom_flags(libc::O_NONBLOCK)
+            .open(UINPUT_PATH)
+            .map_err(|e| BridgeError::Unavailable(format!("cannot open {}: {}", UINPUT_PATH, e)))?;
+        let joystick = Self { file };
+        joystick.ioctl_int(UI_SET_EVBIT, EV_KEY)?;
+        for button in 0..BTN_JOYSTICK_COUNT + BTN_TRIGGER_HAPPY_COUNT {
+            joystick.ioctl_int(UI_SET_KEYBIT, button_code(button).unwrap_or_default())?;
+        }
+        joystick.ioctl_int(UI_SET_EVBIT, EV_ABS)?;
+        for &code in &AXIS_CODES {
+            joystick.ioctl_int(UI_SET_ABSBIT, code)?;
+            // SAFETY: plain C struct, all-zero is a valid value
+            let mut abs: libc::uinput_abs_setup = unsafe { std::mem::zeroed() };
+            abs.code = code;
+            abs.absinfo.maximum = u16::MAX as i32;
+            joystick.ioctl_ptr(UI_ABS_SETUP, &abs)?;
+        }
+
+        // SAFETY: plain C struct, all-zero is a valid value
+        let mut setup: libc::uinput_setup = unsafe { std::mem::zeroed() };
+        // No vendor/product id, so mappers do not mistake it for the physical device
+        setup.id.bustype = BUS_USB;
+        setup.id.version = 1;
+        for (dst, src) in setup.name.iter_mut().zip(DEVICE_NAME.bytes()) {
+            *dst = src as libc::c_char;
+        }
+        joystick.ioctl_ptr(UI_DEV_SETUP, &setup)?;
+        joystick.ioctl_int(UI_DEV_CREATE, 0)?;
+        log::info!("Created uinput joystick '{}'", DEVICE_NAME);
+        Ok(joystick)
+    }
+
+    fn check(result: libc::c_int) -> std::io::Result<()> {
+        if result < 0 { Err(std::io::Error::last_os_error()) } else { Ok(()) }
+    }
+
+    fn ioctl_int(&self, request: u64, value: u16) -> std::io::Result<()> {
+        // SAFETY: the fd is open for the lifetime of self; these requests take an int argument
+        Self::check(unsafe { libc::ioctl(self.file.as_raw_fd(), request as _, value as libc::c_int) })
+    }
+
+    fn ioctl_ptr<T>(&self, request: u64, value: &T) -> std::io::Result<()> {
+        // SAFETY: `request` encodes size_of::<T>() and the kernel only reads from the pointer
+        Self::check(unsafe { libc::ioctl(self.file.as_raw_fd(), request as _, value as *const T) })
+    }
+
+    fn emit(&mut self, kind: u16, code: u16, value: i32) -> std::io::Result<()> {
+        // SAFETY: plain C struct, all-zero is a valid value; the kernel fills in the timestamp
+        let mut event: libc::input_event = unsafe { std::mem::zeroed() };
+        event.type_ = kind;
+        event.code = code;
+        event.value = value;
+        // SAFETY: input_event is a plain C struct without padding-sensitive invariants
+        let bytes = unsafe {
+            std::slice::from_raw_parts((&event as *const libc::input_event) as *const u8, std::mem::size_of::<libc::input_event>())
+        };
+        self.file.write_all(bytes)
+    }
+}
+
+impl VirtualJoystick for UinputJoystick {
+    fn backend_name(&self) -> &'static str {
+        "uinput"
+    }
+
+    fn button_count(&self) -> u8 {
+        BTN_JOYSTICK_COUNT + BTN_TRIGGER_HAPPY_COUNT
+    }
+
+    fn axis_count(&self) -> u8 {
+        AXIS_CODES.len() as u8
+    }
+
+    fn set_button(&mut self, button: u8, pressed: bool) -> std::io::Result<()> {
+        match button_code(button) {
+            Some(code) => self.emit(EV_KEY, code, pressed as i32),
+            None => Ok(()),
+        }
+    }
+
+    fn set_axis(&mut self, axis: u8, value: u16) -> std::io::Result<()> {
+        match AXIS_CODES.get(axis as usize) {
+            Some(&code) => self.emit(EV_ABS, code, value as i32),
+            None => Ok(()),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.emit(EV_SYN, SYN_REPORT, 0)
+    }
+}
+
+impl Drop for UinputJoystick {
+    fn drop(&mut self) {
+        if let Err(e) = self.ioctl_int(UI_DEV_DESTROY, 0) {
+            log::warn!("Failed to destroy uinput joystick: {}", e);
+        }
+    }
+}
@@ -0,0 +1,139 @@
+//! Windows vJoy backend. `vJoyInterface.dll` ships with the vJoy driver and is loaded on first
+//! use, so the app runs without vJoy installed.
+use once_cell::sync::OnceCell;
+use windows::core::{s, PCSTR};
+use windows::Win32::System::LibraryLoader::{GetProcAddress, LoadLibraryA};
+
+use super::bridge::VirtualJoystick;
+use super::{BridgeError, Result};
+
+/// HID usages of the vJoy axes: X, Y, Z, RX, RY, RZ, slider 0, slider 1
+const AXIS_USAGES: [u32; 8] = [0x30, 0x31, 0x32, 0x33, 0x34, 0x35, 0x36, 0x37];
+const AXIS_MIN: i32 = 0x1;
+const AXIS_MAX: i32 = 0x8000;
+/// `VjdStat` values of `GetVJDStatus`
+const VJD_STAT_OWN: i32 = 0;
+const VJD_STAT_FREE: i32 = 1;
+
+struct VJoyApi {
+    enabled: unsafe extern "C" fn() -> i32,
+    status: unsafe extern "C" fn(u32) -> i32,
+    acquire: unsafe extern "C" fn(u32) -> i32,
+    relinquish: unsafe extern "C" fn(u32),
+    reset: unsafe extern "C" fn(u32) -> i32,
+    button_number: unsafe extern "C" fn(u32) -> i32,
+    axis_exists: unsafe extern "C" fn(u32, u32) -> i32,
+    set_axis: unsafe extern "C" fn(i32, u32, u32) -> i32,
+    set_button: unsafe extern "C" fn(i32, u32, u8) -> i32,
+}
+
+static API: OnceCell<std::result::Result<VJoyApi, String>> = OnceCell::new();
+
+fn api() -> Result<&'static VJoyApi> {
+    API.get_or_init(load_api).as_ref().map_err(|e| BridgeError::Unavailable(e.clone()))
+}
+
+fn load_api() -> std::result::Result<VJoyApi, String> {
+    // SAFETY: loading a library by name; the handle stays loaded for the process lifetime
+    let module = unsafe { LoadLibraryA(s!("vJoyInterface.dll")) }
+        .map_err(|e| format!("vJoyInterface.dll not found, is vJoy installed? ({})", e))?;
+    macro_rules! function {
+        ($name:literal) => {{
+            // SAFETY: the vJoy SDK declares these exports with the signatures of `VJoyApi`
+            let address = unsafe { GetProcAddress(module, PCSTR(concat!($name, "\0").as_ptr())) }
+                .ok_or_else(|| format!("vJoyInterface.dll has no export {}", $name))?;
+            unsafe { std::mem::transmute(address) }
+        }};
+    }
+    Ok(VJoyApi {
+        enabled: function!("vJoyEnabled"),
+        status: function!("GetVJDStatus"),
+        acquire: function!("AcquireVJD"),
+        relinquish: function!("RelinquishVJD"),
+        reset: function!("ResetVJD"),
+        button_number: function!("GetVJDButtonNumber"),
+        axis_exists: function!("GetVJDAxisExist"),
+        set_axis: function!("SetAxis"),
+        set_button: function!("SetBtn"),
+    })
+}
+
+fn failed(what: &str) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::Other, format!("vJoy {} failed", what))
+}
+
+pub struct VJoyJoystick {
+    api: &'static VJoyApi,
+    id: u32,
+    buttons: u8,
+    /// Usages of the axes configured on this vJoy device, in `AXIS_USAGES` order
+    axes: Vec<u32>,
+}
+
+impl VJoyJoystick {
+    pub fn acquire(device_index: u8) -> Result<Self> {
+        let api = api()?;
+        let id = device_index as u32;
+        // SAFETY: calls into the loaded vJoy library with plain integer arguments
+        unsafe {
+            if (api.enabled)() == 0 {
+                return Err(BridgeError::Unavailable("the vJoy driver is not enabled".into()));
+            }
+            let status = (api.status)(id);
+            if status != VJD_STAT_FREE && status != VJD_STAT_OWN {
+                return Err(BridgeError::Unavailable(format!("vJoy device {} is busy or not configured", id)));
+            }
+            if (api.acquire)(id) == 0 {
+                return Err(BridgeError::Unavailable(format!("could not acquire vJoy device {}", id)));
+            }
+            (api.reset)(id);
+            let buttons = (api.button_number)(id).clamp(0, 128) as u8;
+            let axes = AXIS_USAGES.iter().copied().filter(|&usage| (api.axis_exists)(id, usage) != 0).collect();
+            log::info!("Acquired vJoy device {} ({} buttons)", id, buttons);
+            Ok(Self { api, id, buttons, axes })
+        }
+    }
+}
+
+impl VirtualJoystick for VJoyJoystick {
+    fn backend_name(&self) -> &'static str {
+        "vjoy"
+    }
+
+    fn button_count(&self) -> u8 {
+        self.buttons
+    }
+
+    fn axis_count(&self) -> u8 {
+        self.axes.len() as u8
+    }
+
+    fn set_button(&mut self, button: u8, pressed: bool) -> std::io::Result<()> {
+        // SAFETY: see `acquire`; vJoy buttons are 1-based
+        let ok = unsafe { (self.api.set_button)(pressed as i32, self.id, button + 1) };
+        if ok == 0 { Err(failed("SetBtn")) } else { Ok(()) }
+    }
+
+    fn set_axis(&mut self, axis: u8, value: u16) -> std::io::Result<()> {
+        let Some(&usage) = self.axes.get(axis as usize) else { return Ok(()) };
+        let scaled = AXIS_MIN + ((value as i64 * (AXIS_MAX - AXIS_MIN) as i64) / u16::MAX as i64) as i32;
+        // SAFETY: see `acquire`
+        let ok = unsafe { (self.api.set_axis)(scaled, self.id, usage) };
+        if ok == 0 { Err(failed("SetAxis")) } else { Ok(()) }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        // vJoy applies every call immediately
+        Ok(())
+    }
+}
+
+impl Drop for VJoyJoystick {
+    fn drop(&mut self) {
+        // SAFETY: see `acquire`
+        unsafe {
+            (self.api.reset)(self.id);
+            (self.api.relinquish)(self.id);
+        }
+    }
+}
@@ -1,10 +1,19 @@
 pub mod serial;
 pub mod device;
+pub mod diagnostics;
+pub mod flasher;
 pub mod commands;
 pub mod update;
 pub mod config;
+pub mod error;
 pub mod hid;
+pub mod interop;
+pub mod logging;
 pub mod raw_state;
+pub mod recording;
+pub mod support;
+pub mod telemetry;
+pub mod util;
 
 use std::sync::Arc;
 use device::DeviceManager;
@@ -12,30 +21,38 @@ use tauri::Manager;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+  // Panics on any thread leave a crash report behind
+  support::crash::install_panic_hook();
+
   // Create shared device manager
   let device_manager = Arc::new(DeviceManager::new());
 
   tauri::Builder::default()
     .manage(device_manager)
-    .on_window_event(|window, event| {
-      if let tauri::WindowEvent::CloseRequested { .. } = event {
-  let dm_opt = window.try_state::<Arc<DeviceManager>>().map(|s| s.inner().clone());
-        if let Some(dm) = dm_opt {
-          tauri::async_runtime::spawn(async move { dm.shutdown().await; });
-        }
-      }
-    })
     .invoke_handler(tauri::generate_handler![
       commands::discover_devices,
   commands::force_discover_devices,
       commands::get_devices,
+      commands::get_device_list_snapshot,
+      commands::get_quarantined_ports,
+      commands::clear_port_quarantine,
+      commands::get_device_alias,
+      commands::set_device_alias,
+      commands::get_known_devices,
+      commands::forget_known_device,
+      commands::get_device_connection_settings,
+      commands::set_device_connection_settings,
+      commands::get_port_connection_settings,
+      commands::set_port_connection_settings,
       commands::connect_device,
       commands::disconnect_device,
       commands::get_connected_device,
       commands::get_device_status,
       commands::read_axis_config,
+      commands::read_all_axis_configs,
       commands::write_axis_config,
       commands::read_button_config,
+      commands::read_all_button_configs,
       commands::write_button_config,
       commands::save_device_config,
       commands::load_device_config,
@@ -44,13 +61,45 @@ pub fn run() {
       commands::update_profile,
       commands::delete_profile,
       commands::set_active_profile,
+      commands::apply_profile_to_device,
+      commands::create_profile_from_device,
+      commands::get_macros,
+      commands::validate_macro,
+      commands::create_macro,
+      commands::update_macro,
+      commands::delete_macro,
       commands::check_firmware_updates,
+      commands::set_github_token,
+      commands::has_github_token,
+      commands::set_update_channel,
       commands::download_firmware_update,
+      commands::cancel_firmware_download,
       commands::get_available_firmware_versions,
       commands::verify_firmware,
+      commands::verify_firmware_signature,
+      commands::get_device_uptime,
+      commands::reboot_device,
+      commands::enter_bootloader,
+      commands::flash_firmware,
+      commands::list_cached_firmware,
+      commands::rollback_firmware,
       // Binary config commands
       commands::read_device_config_raw,
       commands::write_device_config_raw,
+      commands::preview_device_config,
+      commands::get_config_preview,
+      commands::commit_preview_config,
+      commands::discard_preview_config,
+      commands::export_device_config_json,
+      commands::get_config_schema,
+      commands::import_device_config_json,
+      commands::export_mapping,
+      commands::list_config_backups,
+      commands::restore_config_backup,
+      commands::compare_with_device_backup,
+      commands::restore_from_device_backup,
+      commands::export_config_backup_json,
+      commands::delete_config_backup,
       commands::delete_device_config,
       commands::reset_device_to_defaults,
       commands::format_device_storage,
@@ -63,29 +112,117 @@ pub fn run() {
       commands::test_list_device_files,
       commands::read_parsed_device_config,
       commands::read_device_pin_assignments,
+      commands::read_device_encoder_configs,
+      commands::update_device_encoder_configs,
+      commands::update_device_axis_configs,
+      commands::update_axis_in_config,
+      commands::update_logical_input_in_config,
+      commands::remap_logical_buttons,
+      commands::get_shift_layer,
+      commands::set_shift_layer,
+      commands::get_hat_configs,
+      commands::set_hat_configs,
+      commands::list_config_templates,
+      commands::apply_config_template,
+      commands::get_axis_curve_presets,
+      commands::preview_axis_curve,
+      commands::apply_axis_curve,
+      commands::validate_config_binary,
+            commands::validate_config_on_device,
+      commands::diagnose_config_binary,
+      commands::validate_device_config,
+      commands::diff_device_configs,
+      commands::read_matrix_config,
+      commands::write_matrix_config,
+      commands::read_usb_descriptor,
+      commands::write_usb_descriptor,
       commands::read_parsed_device_config_with_pins,
       commands::read_button_states,
       commands::debug_hid_mapping,
       commands::debug_full_hid_report,
       commands::hid_mapping_details,
       commands::hid_button_bit_diagnostics,
+      commands::get_hid_link_stats,
+      commands::get_hid_control_support,
+      commands::send_hid_feature_report,
+      commands::identify_device,
+      commands::set_device_test_mode,
       // Raw hardware state commands
       commands::get_raw_state_display_mode,
   commands::set_raw_state_display_mode,
+      commands::get_display_mode,
+      commands::set_display_mode,
+  commands::get_raw_monitor_coalesce_interval,
+  commands::set_raw_monitor_coalesce_interval,
+  commands::configure_raw_monitoring,
       commands::read_raw_gpio_states,
       commands::read_raw_matrix_state,
       commands::read_raw_shift_reg_state,
       commands::read_all_raw_states,
+      commands::get_raw_state_snapshot,
+      commands::get_raw_state_history,
+      commands::get_input_activity_stats,
+      commands::reset_input_activity_stats,
+      commands::subscribe_raw_events,
+      commands::unsubscribe_raw_events,
       commands::start_raw_state_monitoring,
       commands::stop_raw_state_monitoring,
+      commands::get_buffer_overflow_stats,
+      // Protocol introspection
+      commands::describe_protocol,
+      commands::get_firmware_capabilities,
+      commands::get_device_clock_sync,
+      commands::set_protocol_trace_enabled,
+      commands::get_protocol_trace,
+      commands::clear_protocol_trace,
+      // Application settings
+      commands::get_app_settings,
+      commands::set_app_settings,
+      // Developer mode
+      commands::get_dev_mode_settings,
+      commands::set_dev_mode_settings,
+      commands::get_last_flashed_firmware,
+      commands::dev_flash_firmware,
+      commands::reflash_last_firmware,
+      commands::run_smoke_test,
+      commands::run_device_self_test,
+      commands::send_custom_serial_command,
+      commands::start_input_recording,
+      commands::stop_input_recording,
+      commands::list_recordings,
+      commands::start_input_replay,
+      commands::stop_input_replay,
+      commands::get_replay_status,
+      commands::start_virtual_bridge,
+      commands::stop_virtual_bridge,
+      commands::get_virtual_bridge_status,
+      commands::start_chatter_test,
+      commands::stop_chatter_test,
+      commands::get_chatter_report,
+      commands::start_ghost_detector,
+      commands::stop_ghost_detector,
+      commands::get_ghost_detector_status,
+      commands::start_encoder_test,
+      commands::stop_encoder_test,
+      commands::get_encoder_test_report,
+      commands::run_latency_benchmark,
+      commands::tune_axis_filter,
+      commands::start_telemetry_server,
+      commands::stop_telemetry_server,
+      commands::get_telemetry_status,
+      commands::export_support_bundle,
+      commands::check_system_permissions,
+      commands::write_udev_rules,
+      commands::set_log_level,
+      commands::get_recent_logs,
+      commands::get_log_stream_options,
+      commands::set_log_stream_options,
     ])
     .setup(|app| {
       // Enable logging in all builds to help diagnose blank window issues.
-      app.handle().plugin(
-        tauri_plugin_log::Builder::default()
-          .level(log::LevelFilter::Info)
-          .build(),
-      )?;
+      let log_plugin = logging::init(app.handle())?;
+      app.handle().plugin(log_plugin)?;
+      support::crash::attach_app(app.handle());
       
       // Pass app handle to device manager for event emission
       let device_manager: tauri::State<Arc<DeviceManager>> = app.state();
@@ -98,6 +235,14 @@ pub fn run() {
       log::info!("JoyCore-X application started");
       Ok(())
     })
-    .run(tauri::generate_context!())
-    .expect("error while running tauri application");
+    .build(tauri::generate_context!())
+    .expect("error while building tauri application")
+    .run(|app, event| {
+      // Release the device before the process ends; bounded by device::lifecycle::SHUTDOWN_DEADLINE
+      if let tauri::RunEvent::Exit = event {
+        if let Some(dm) = app.try_state::<Arc<DeviceManager>>().map(|s| s.inner().clone()) {
+          tauri::async_runtime::block_on(dm.shutdown());
+        }
+      }
+    });
 }
@@ -0,0 +1,123 @@
+//! Application logging.
+//!
+//! `tauri_plugin_log` provides the outputs: size-rotated files in the app log directory (inside
+//! the app data directory on Windows and Linux) in every build, plus stdout in debug builds. Its
+//! logger is wrapped in [`AppLogger`], which also keeps the newest records in memory for
+//! `get_recent_logs`, and feeds the [`stream`] behind the `log-line` event. The outputs accept
+//! every level; the effective level is the global `log` max level, so [`set_level`] takes
+//! effect at runtime.
+pub mod recent;
+pub mod stream;
+
+pub use recent::{LogLine, RecentLogs};
+pub use stream::{LogStream, LogStreamOptions, LOG_LINE_EVENT};
+
+use std::cell::Cell;
+use std::str::FromStr;
+
+use log::LevelFilter;
+use tauri::plugin::TauriPlugin;
+use tauri::{AppHandle, Emitter, Runtime};
+use tauri_plugin_log::{RotationStrategy, Target, TargetKind};
+
+/// Size at which the active log file is rotated
+pub const LOG_FILE_MAX_BYTES: u128 = 5 * 1024 * 1024;
+/// Rotated log files kept next to the active one
+pub const LOG_FILES_KEPT: usize = 5;
+pub const DEFAULT_LOG_LEVEL: LevelFilter = LevelFilter::Info;
+const RECENT_LOG_CAPACITY: usize = 2000;
+
+static RECENT: once_cell::sync::Lazy<RecentLogs> = once_cell::sync::Lazy::new(|| RecentLogs::new(RECENT_LOG_CAPACITY));
+static STREAM: once_cell::sync::Lazy<LogStream> = once_cell::sync::Lazy::new(LogStream::new);
+
+thread_local! {
+    /// Set while the stream sink runs so records logged by the event emission are not streamed
+    static IN_STREAM_SINK: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Get the global `log-line` stream
+pub fn get_log_stream() -> &'static LogStream {
+    &STREAM
+}
+
+/// Global logger: the plugin's outputs, the in-memory recent lines and the live stream
+struct AppLogger {
+    inner: Box<dyn log::Log>,
+}
+
+impl log::Log for AppLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= log::max_level() && self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        self.inner.log(record);
+        let line = LogLine::from_record(record);
+        if STREAM.accepts(record.level()) && !IN_STREAM_SINK.with(Cell::get) {
+            IN_STREAM_SINK.with(|flag| flag.set(true));
+            STREAM.offer(&line, std::time::Instant::now());
+            IN_STREAM_SINK.with(|flag| flag.set(false));
+        }
+        RECENT.push(line);
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+/// Build the log plugin and install the global logger; call once during app setup
+pub fn init<R: Runtime>(app_handle: &AppHandle<R>) -> Result<TauriPlugin<R>, Box<dyn std::error::Error>> {
+    let mut targets = vec![Target::new(TargetKind::LogDir { file_name: None })];
+    if cfg!(debug_assertions) {
+        targets.push(Target::new(TargetKind::Stdout));
+    }
+    let (plugin, _, logger) = tauri_plugin_log::Builder::new()
+        .targets(targets)
+        .level(LevelFilter::Trace)
+        .max_file_size(LOG_FILE_MAX_BYTES)
+        .rotation_strategy(RotationStrategy::KeepSome(LOG_FILES_KEPT))
+        .split(app_handle)?;
+    log::set_boxed_logger(Box::new(AppLogger { inner: logger }))?;
+    log::set_max_level(DEFAULT_LOG_LEVEL);
+    let handle = app_handle.clone();
+    STREAM.set_sink(move |line| {
+        let _ = handle.emit(LOG_LINE_EVENT, line);
+    });
+    Ok(plugin)
+}
+
+/// Parse a level name ("off", "error", "warn", "info", "debug", "trace"; any case)
+pub fn parse_level(level: &str) -> Result<LevelFilter, String> {
+    LevelFilter::from_str(level.trim()).map_err(|_| {
+        format!("Unknown log level '{}', expected off, error, warn, info, debug or trace", level)
+    })
+}
+
+/// Change the effective log level at runtime
+pub fn set_level(level: LevelFilter) {
+    log::set_max_level(level);
+    log::info!("Log level set to {}", level);
+}
+
+/// Apply `AppSettings::log_level`; an unknown value keeps the current level
+pub fn apply_level_setting(level: &str) {
+    match parse_level(level) {
+        Ok(filter) if filter != log::max_level() => set_level(filter),
+        Ok(_) => {}
+        Err(e) => log::warn!("{}", e),
+    }
+}
+
+/// Newest `lines` log records, oldest first
+pub fn recent_logs(lines: usize) -> Vec<LogLine> {
+    RECENT.tail(lines)
+}
+
+/// `recent_logs` without blocking, for the panic hook; None while the lines are locked
+pub fn try_recent_logs(lines: usize) -> Option<Vec<LogLine>> {
+    RECENT.try_tail(lines)
+}
@@ -0,0 +1,90 @@
+//! In-memory copy of the newest log records for the in-app log viewer.
+use std::collections::VecDeque;
+use std::sync::{Mutex, TryLockError};
+
+use serde::Serialize;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LogLine {
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    /// Lowercase level name ("error" .. "trace")
+    pub level: String,
+    pub target: String,
+    pub message: String,
+}
+
+impl LogLine {
+    pub fn from_record(record: &log::Record) -> Self {
+        Self {
+            timestamp: chrono::Utc::now(),
+            level: record.level().as_str().to_ascii_lowercase(),
+            target: record.target().to_string(),
+            message: record.args().to_string(),
+        }
+    }
+}
+
+/// Ring of the newest `capacity` lines; older lines are expected to fall off, so eviction is not
+/// counted as overflow
+pub struct RecentLogs {
+    lines: Mutex<VecDeque<LogLine>>,
+    capacity: usize,
+}
+
+impl RecentLogs {
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        Self { lines: Mutex::new(VecDeque::with_capacity(capacity)), capacity }
+    }
+
+    pub fn push(&self, line: LogLine) {
+        let mut lines = self.lines.lock().unwrap_or_else(|e| e.into_inner());
+        if lines.len() >= self.capacity {
+            lines.pop_front();
+        }
+        lines.push_back(line);
+    }
+
+    /// Newest `count` lines, oldest first
+    pub fn tail(&self, count: usize) -> Vec<LogLine> {
+        let lines = self.lines.lock().unwrap_or_else(|e| e.into_inner());
+        lines.iter().skip(lines.len().saturating_sub(count)).cloned().collect()
+    }
+
+    /// `tail` without waiting; None while another thread holds the ring (or this one, when a
+    /// panic hook runs mid-push)
+    pub fn try_tail(&self, count: usize) -> Option<Vec<LogLine>> {
+        let lines = match self.lines.try_lock() {
+            Ok(lines) => lines,
+            Err(TryLockError::Poisoned(e)) => e.into_inner(),
+            Err(TryLockError::WouldBlock) => return None,
+        };
+        Some(lines.iter().skip(lines.len().saturating_sub(count)).cloned().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(message: &str) -> LogLine {
+        LogLine::from_record(&log::Record::builder().level(log::Level::Warn).target("test").args(format_args!("{}", message)).build())
+    }
+
+    #[test]
+    fn keeps_newest_lines() {
+        let recent = RecentLogs::new(3);
+        for message in ["a", "b", "c", "d"] {
+            recent.push(line(message));
+        }
+        let messages: Vec<String> = recent.tail(10).into_iter().map(|l| l.message).collect();
+        assert_eq!(messages, vec!["b", "c", "d"]);
+        let last = recent.tail(1);
+        assert_eq!((last[0].message.as_str(), last[0].level.as_str()), ("d", "warn"));
+        assert!(recent.tail(0).is_empty());
+
+        assert_eq!(recent.try_tail(10).map(|l| l.len()), Some(3));
+        let _held = recent.lines.lock().unwrap();
+        assert!(recent.try_tail(10).is_none(), "a held ring is skipped rather than waited on");
+    }
+}
@@ -0,0 +1,162 @@
+//! Live log stream for the frontend console (`log-line` event).
+//!
+//! Records at or above the stream level are handed to a sink, at most `max_lines_per_sec` per
+//! one-second window. Lines over the limit are dropped and counted; the next window starts with
+//! a warning line reporting how many were lost.
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use log::LevelFilter;
+use serde::{Deserialize, Serialize};
+
+use super::LogLine;
+
+pub const LOG_LINE_EVENT: &str = "log-line";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LogStreamOptions {
+    pub enabled: bool,
+    /// Least severe level streamed ("error" .. "trace")
+    pub level: String,
+    pub max_lines_per_sec: u32,
+}
+
+impl Default for LogStreamOptions {
+    fn default() -> Self {
+        Self { enabled: true, level: "info".to_string(), max_lines_per_sec: 100 }
+    }
+}
+
+type Sink = Arc<dyn Fn(&LogLine) + Send + Sync>;
+
+#[derive(Default)]
+struct Window {
+    started: Option<Instant>,
+    sent: u32,
+    dropped: u64,
+}
+
+pub struct LogStream {
+    enabled: AtomicBool,
+    /// `LevelFilter as usize`; `Level` shares the discriminants
+    level: AtomicUsize,
+    max_lines_per_sec: AtomicUsize,
+    window: Mutex<Window>,
+    sink: Mutex<Option<Sink>>,
+}
+
+impl Default for LogStream {
+    fn default() -> Self {
+        let stream = Self {
+            enabled: AtomicBool::new(false),
+            level: AtomicUsize::new(LevelFilter::Off as usize),
+            max_lines_per_sec: AtomicUsize::new(0),
+            window: Mutex::new(Window::default()),
+            sink: Mutex::new(None),
+        };
+        // Defaults are valid
+        let _ = stream.configure(&LogStreamOptions::default());
+        stream
+    }
+}
+
+impl LogStream {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_sink(&self, sink: impl Fn(&LogLine) + Send + Sync + 'static) {
+        *self.sink.lock().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(sink));
+    }
+
+    pub fn configure(&self, options: &LogStreamOptions) -> Result<(), String> {
+        let level = super::parse_level(&options.level)?;
+        if options.max_lines_per_sec == 0 {
+            return Err("max_lines_per_sec must be greater than zero".to_string());
+        }
+        self.level.store(level as usize, Ordering::Relaxed);
+        self.max_lines_per_sec.store(options.max_lines_per_sec as usize, Ordering::Relaxed);
+        self.enabled.store(options.enabled, Ordering::Relaxed);
+        Ok(())
+    }
+
+    pub fn options(&self) -> LogStreamOptions {
+        let level = [LevelFilter::Off, LevelFilter::Error, LevelFilter::Warn, LevelFilter::Info, LevelFilter::Debug, LevelFilter::Trace]
+            [self.level.load(Ordering::Relaxed).min(5)];
+        LogStreamOptions {
+            enabled: self.enabled.load(Ordering::Relaxed),
+            level: level.to_string().to_ascii_lowercase(),
+            max_lines_per_sec: self.max_lines_per_sec.load(Ordering::Relaxed) as u32,
+        }
+    }
+
+    /// Cheap pre-check before a record is formatted
+    pub fn accepts(&self, level: log::Level) -> bool {
+        self.enabled.load(Ordering::Relaxed) && level as usize <= self.level.load(Ordering::Relaxed)
+    }
+
+    /// Stream `line` if the current window has room; the sink runs without any lock held
+    pub fn offer(&self, line: &LogLine, at: Instant) {
+        let Some(sink) = self.sink.lock().unwrap_or_else(|e| e.into_inner()).clone() else { return };
+        let limit = self.max_lines_per_sec.load(Ordering::Relaxed) as u32;
+        let (notice, send) = {
+            let mut window = self.window.lock().unwrap_or_else(|e| e.into_inner());
+            let mut notice = None;
+            let expired = window.started.map_or(true, |started| at.saturating_duration_since(started) >= Duration::from_secs(1));
+            if expired {
+                if window.dropped > 0 {
+                    notice = Some(LogLine {
+                        timestamp: chrono::Utc::now(),
+                        level: "warn".to_string(),
+                        target: module_path!().to_string(),
+                        message: format!("{} log lines not streamed (limit {}/s)", window.dropped, limit),
+                    });
+                }
+                *window = Window { started: Some(at), sent: 0, dropped: 0 };
+            }
+            let send = window.sent < limit;
+            if send { window.sent += 1 } else { window.dropped += 1 }
+            (notice, send)
+        };
+        if let Some(notice) = notice {
+            sink(&notice);
+        }
+        if send {
+            sink(line);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(level: &str, message: &str) -> LogLine {
+        LogLine { timestamp: chrono::Utc::now(), level: level.to_string(), target: "test".to_string(), message: message.to_string() }
+    }
+
+    #[test]
+    fn filters_level_and_limits_rate() {
+        let stream = LogStream::new();
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let sink_received = received.clone();
+        stream.set_sink(move |line: &LogLine| sink_received.lock().unwrap().push(line.message.clone()));
+        stream.configure(&LogStreamOptions { enabled: true, level: "warn".into(), max_lines_per_sec: 2 }).unwrap();
+        assert!(stream.accepts(log::Level::Error) && stream.accepts(log::Level::Warn));
+        assert!(!stream.accepts(log::Level::Info));
+        assert!(stream.configure(&LogStreamOptions { level: "loud".into(), ..Default::default() }).is_err());
+        assert_eq!(stream.options().level, "warn");
+
+        let t0 = Instant::now();
+        for n in 0..5 {
+            stream.offer(&line("warn", &n.to_string()), t0 + Duration::from_millis(n * 10));
+        }
+        stream.offer(&line("warn", "next"), t0 + Duration::from_millis(1100));
+        let received = received.lock().unwrap().clone();
+        assert_eq!(received[..2], ["0", "1"]);
+        assert_eq!(received[2], "3 log lines not streamed (limit 2/s)");
+        assert_eq!(received[3], "next");
+    }
+}
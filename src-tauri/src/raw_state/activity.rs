@@ -0,0 +1,189 @@
+//! Per-input activity counters for usage heatmaps.
+//!
+//! Every GPIO pin, matrix cell and shift register bit that changes level in the raw monitor
+//! stream gets a counter of changes and activations with the time of its last activity. A
+//! matrix cell is active while connected; GPIO pins and shift register bits are active low, as
+//! the firmware wires them with pull-ups. The first GPIO mask and shift register value only set
+//! the level; matrix cells start disconnected. Counters run for the app session until reset.
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::serial::ParsedEvent;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum InputSource {
+    Gpio { pin: u8 },
+    Matrix { row: u8, col: u8 },
+    Shift { register_id: u8, bit: u8 },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InputActivity {
+    #[serde(flatten)]
+    pub source: InputSource,
+    /// Transitions to the active level
+    pub activations: u64,
+    /// Level changes in either direction
+    pub changes: u64,
+    pub active: bool,
+    pub last_activity: Option<DateTime<Utc>>,
+    /// Firmware time of the last change (µs since boot)
+    pub last_activity_us: Option<u64>,
+}
+
+/// Result of `get_input_activity_stats`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InputActivityStats {
+    /// Start of counting (app start or last reset)
+    pub since: DateTime<Utc>,
+    pub total_activations: u64,
+    /// Inputs that changed at least once, by source
+    pub inputs: Vec<InputActivity>,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Counter {
+    activations: u64,
+    changes: u64,
+    active: bool,
+    last_activity: Option<DateTime<Utc>>,
+    last_activity_us: Option<u64>,
+}
+
+struct Inner {
+    since: DateTime<Utc>,
+    gpio: Option<u32>,
+    shift: BTreeMap<u8, u8>,
+    matrix: BTreeMap<(u8, u8), bool>,
+    counters: BTreeMap<InputSource, Counter>,
+}
+
+impl Inner {
+    fn new() -> Self {
+        Self { since: Utc::now(), gpio: None, shift: BTreeMap::new(), matrix: BTreeMap::new(), counters: BTreeMap::new() }
+    }
+
+    fn count(&mut self, source: InputSource, active: bool, timestamp: u64, now: DateTime<Utc>) {
+        let counter = self.counters.entry(source).or_default();
+        counter.changes += 1;
+        counter.activations += active as u64;
+        counter.active = active;
+        counter.last_activity = Some(now);
+        counter.last_activity_us = Some(timestamp);
+    }
+}
+
+pub struct ActivityCounters {
+    inner: Mutex<Inner>,
+}
+
+impl Default for ActivityCounters {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ActivityCounters {
+    pub fn new() -> Self {
+        Self { inner: Mutex::new(Inner::new()) }
+    }
+
+    pub fn record(&self, event: &ParsedEvent) {
+        self.record_at(event, Utc::now());
+    }
+
+    fn record_at(&self, event: &ParsedEvent, now: DateTime<Utc>) {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        match *event {
+            ParsedEvent::Gpio { mask, timestamp } => {
+                let Some(previous) = inner.gpio.replace(mask) else { return };
+                let changed = previous ^ mask;
+                for pin in (0..32u8).filter(|pin| changed & (1 << pin) != 0) {
+                    inner.count(InputSource::Gpio { pin }, mask & (1 << pin) == 0, timestamp, now);
+                }
+            }
+            ParsedEvent::MatrixDelta { row, col, is_connected, timestamp } => {
+                if inner.matrix.insert((row, col), is_connected).unwrap_or(false) != is_connected {
+                    inner.count(InputSource::Matrix { row, col }, is_connected, timestamp, now);
+                }
+            }
+            ParsedEvent::Shift { register_id, value, timestamp } => {
+                let Some(previous) = inner.shift.insert(register_id, value) else { return };
+                let changed = previous ^ value;
+                for bit in (0..8u8).filter(|bit| changed & (1 << bit) != 0) {
+                    inner.count(InputSource::Shift { register_id, bit }, value & (1 << bit) == 0, timestamp, now);
+                }
+            }
+            ParsedEvent::ProtocolNotice { .. } | ParsedEvent::Unclassified { .. } => {}
+        }
+    }
+
+    pub fn stats(&self) -> InputActivityStats {
+        let inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let inputs: Vec<InputActivity> = inner.counters.iter()
+            .map(|(&source, c)| InputActivity {
+                source,
+                activations: c.activations,
+                changes: c.changes,
+                active: c.active,
+                last_activity: c.last_activity,
+                last_activity_us: c.last_activity_us,
+            })
+            .collect();
+        InputActivityStats {
+            since: inner.since,
+            total_activations: inputs.iter().map(|i| i.activations).sum(),
+            inputs,
+        }
+    }
+
+    /// Drop all counters and start counting now
+    pub fn reset(&self) {
+        *self.inner.lock().unwrap_or_else(|e| e.into_inner()) = Inner::new();
+    }
+}
+
+static ACTIVITY: once_cell::sync::Lazy<ActivityCounters> = once_cell::sync::Lazy::new(ActivityCounters::new);
+
+/// Get the global input activity counters
+pub fn get_activity() -> &'static ActivityCounters {
+    &ACTIVITY
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_activations_per_source() {
+        let counters = ActivityCounters::new();
+        let now = Utc::now();
+        // Pins 0 and 1 idle high; pin 1 is pressed twice
+        for (mask, timestamp) in [(0b11, 0), (0b01, 10), (0b11, 20), (0b01, 30)] {
+            counters.record_at(&ParsedEvent::Gpio { mask, timestamp }, now);
+        }
+        counters.record_at(&ParsedEvent::MatrixDelta { row: 2, col: 3, is_connected: false, timestamp: 40 }, now);
+        counters.record_at(&ParsedEvent::MatrixDelta { row: 2, col: 4, is_connected: true, timestamp: 45 }, now);
+        counters.record_at(&ParsedEvent::MatrixDelta { row: 2, col: 4, is_connected: false, timestamp: 48 }, now);
+        counters.record_at(&ParsedEvent::MatrixDelta { row: 2, col: 3, is_connected: true, timestamp: 50 }, now);
+        counters.record_at(&ParsedEvent::Shift { register_id: 0, value: 0xFF, timestamp: 60 }, now);
+        counters.record_at(&ParsedEvent::Shift { register_id: 0, value: 0x7F, timestamp: 70 }, now);
+
+        let stats = counters.stats();
+        assert_eq!(stats.total_activations, 5);
+        assert_eq!(stats.inputs.len(), 4, "first GPIO and shift reports only set the level");
+        let pin = &stats.inputs[0];
+        assert_eq!((pin.source, pin.activations, pin.changes, pin.active), (InputSource::Gpio { pin: 1 }, 2, 3, true));
+        assert_eq!(pin.last_activity_us, Some(30));
+        assert_eq!(stats.inputs[1].source, InputSource::Matrix { row: 2, col: 3 });
+        assert_eq!((stats.inputs[2].activations, stats.inputs[2].changes), (1, 2));
+        assert_eq!(stats.inputs[3].source, InputSource::Shift { register_id: 0, bit: 7 });
+
+        counters.reset();
+        assert!(counters.stats().inputs.is_empty());
+    }
+}
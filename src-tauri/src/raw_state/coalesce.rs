@@ -0,0 +1,132 @@
+//! Coalescing of raw monitor updates so high report rates don't flood the webview.
+//! Within one interval only the newest value per GPIO bank, matrix cell and shift register is kept.
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use crate::raw_state::types::*;
+
+/// Updates accumulated since the last flush, in the payload shapes of the raw-* events
+#[derive(Debug, Default)]
+pub struct CoalescedBatch {
+    pub gpio: Option<RawGpioStates>,
+    pub matrix: Option<MatrixState>,
+    pub shift: Option<Vec<ShiftRegisterState>>,
+}
+
+#[derive(Debug)]
+pub struct MonitorCoalescer {
+    gpio: Option<RawGpioStates>,
+    matrix: BTreeMap<(u8, u8), MatrixConnection>,
+    matrix_timestamp: u64,
+    shift: BTreeMap<u8, ShiftRegisterState>,
+    last_flush: Instant,
+    /// Updates pushed / batches taken, for diagnostics
+    pub updates_in: u64,
+    pub batches_out: u64,
+}
+
+impl Default for MonitorCoalescer {
+    fn default() -> Self {
+        Self {
+            gpio: None,
+            matrix: BTreeMap::new(),
+            matrix_timestamp: 0,
+            shift: BTreeMap::new(),
+            last_flush: Instant::now(),
+            updates_in: 0,
+            batches_out: 0,
+        }
+    }
+}
+
+impl MonitorCoalescer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push_gpio(&mut self, states: RawGpioStates) {
+        self.updates_in += 1;
+        self.gpio = Some(states);
+    }
+
+    pub fn push_matrix(&mut self, connection: MatrixConnection, timestamp: u64) {
+        self.updates_in += 1;
+        self.matrix_timestamp = self.matrix_timestamp.max(timestamp);
+        self.matrix.insert((connection.row, connection.col), connection);
+    }
+
+    pub fn push_shift(&mut self, state: ShiftRegisterState) {
+        self.updates_in += 1;
+        self.shift.insert(state.register_id, state);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.gpio.is_none() && self.matrix.is_empty() && self.shift.is_empty()
+    }
+
+    /// Pending updates should be flushed now (always true for a zero interval)
+    pub fn is_due(&self, interval: Duration) -> bool {
+        !self.is_empty() && self.last_flush.elapsed() >= interval
+    }
+
+    /// Time left until pending updates are due
+    pub fn time_until_due(&self, interval: Duration) -> Duration {
+        interval.saturating_sub(self.last_flush.elapsed())
+    }
+
+    pub fn take(&mut self) -> CoalescedBatch {
+        self.last_flush = Instant::now();
+        if self.is_empty() {
+            return CoalescedBatch::default();
+        }
+        self.batches_out += 1;
+        let matrix = if self.matrix.is_empty() {
+            None
+        } else {
+            let connections = std::mem::take(&mut self.matrix).into_values().collect();
+            Some(MatrixState { connections, timestamp: std::mem::take(&mut self.matrix_timestamp) })
+        };
+        let shift = if self.shift.is_empty() {
+            None
+        } else {
+            Some(std::mem::take(&mut self.shift).into_values().collect())
+        };
+        CoalescedBatch { gpio: self.gpio.take(), matrix, shift }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_latest_value_per_source() {
+        let mut c = MonitorCoalescer::new();
+        c.push_gpio(RawGpioStates { gpio_mask: 1, timestamp: 10 });
+        c.push_gpio(RawGpioStates { gpio_mask: 2, timestamp: 20 });
+        c.push_matrix(MatrixConnection { row: 0, col: 1, is_connected: true }, 11);
+        c.push_matrix(MatrixConnection { row: 0, col: 1, is_connected: false }, 21);
+        c.push_matrix(MatrixConnection { row: 1, col: 0, is_connected: true }, 15);
+        c.push_shift(ShiftRegisterState { register_id: 0, value: 0xFE, timestamp: 12 });
+
+        assert!(c.is_due(Duration::ZERO));
+        let batch = c.take();
+        assert_eq!(batch.gpio.unwrap().gpio_mask, 2);
+        let matrix = batch.matrix.unwrap();
+        assert_eq!(matrix.connections.len(), 2);
+        assert!(!matrix.connections[0].is_connected);
+        assert_eq!(matrix.timestamp, 21);
+        assert_eq!(batch.shift.unwrap().len(), 1);
+        assert!(c.is_empty());
+        assert_eq!((c.updates_in, c.batches_out), (6, 1));
+    }
+
+    #[test]
+    fn waits_for_interval() {
+        let mut c = MonitorCoalescer::new();
+        c.take();
+        c.push_gpio(RawGpioStates { gpio_mask: 1, timestamp: 1 });
+        assert!(!c.is_due(Duration::from_secs(60)));
+        assert!(c.time_until_due(Duration::from_secs(60)) > Duration::from_secs(59));
+    }
+}
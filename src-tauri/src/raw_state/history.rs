@@ -0,0 +1,223 @@
+//! Recent raw input transitions for timing waveforms (logic-analyzer style views).
+//!
+//! Only changes are kept: a GPIO line that repeats the previous mask, or a matrix cell / shift
+//! register reporting its current value again, is not a transition. Entries older than the
+//! window (firmware time) are folded into a baseline so a query can still report the level every
+//! signal had when its range starts. A firmware timestamp going backwards means the device
+//! restarted and clears the history.
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+use crate::raw_state::types::*;
+use crate::serial::ParsedEvent;
+
+/// Firmware time kept, in microseconds
+pub const HISTORY_WINDOW_US: u64 = 10_000_000;
+/// Hard cap for bursts (e.g. a chattering switch)
+pub const MAX_HISTORY_ENTRIES: usize = 50_000;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RawTransition {
+    Gpio { timestamp: u64, gpio_mask: u32 },
+    Matrix { timestamp: u64, row: u8, col: u8, is_connected: bool },
+    Shift { timestamp: u64, register_id: u8, value: u8 },
+}
+
+impl RawTransition {
+    /// State changes only; protocol notices and unclassified lines yield `None`
+    pub fn from_event(event: &ParsedEvent) -> Option<Self> {
+        match *event {
+            ParsedEvent::Gpio { mask, timestamp } => Some(Self::Gpio { timestamp, gpio_mask: mask }),
+            ParsedEvent::MatrixDelta { row, col, is_connected, timestamp } => Some(Self::Matrix { timestamp, row, col, is_connected }),
+            ParsedEvent::Shift { register_id, value, timestamp } => Some(Self::Shift { timestamp, register_id, value }),
+            ParsedEvent::ProtocolNotice { .. } | ParsedEvent::Unclassified { .. } => None,
+        }
+    }
+
+    /// Firmware timestamp in microseconds since boot
+    pub fn timestamp(&self) -> u64 {
+        match *self {
+            Self::Gpio { timestamp, .. } | Self::Matrix { timestamp, .. } | Self::Shift { timestamp, .. } => timestamp,
+        }
+    }
+}
+
+/// Result of `get_raw_state_history`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RawStateHistory {
+    /// Start of the returned range (firmware µs since boot)
+    pub since_us: u64,
+    /// Newest firmware timestamp seen
+    pub latest_us: u64,
+    /// Transitions before `since_us` were already dropped, so `initial` may be older than the range
+    pub truncated: bool,
+    /// Level of every known signal at `since_us`
+    pub initial: RawHardwareState,
+    /// Oldest first
+    pub transitions: Vec<RawTransition>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Levels {
+    gpio: Option<u32>,
+    matrix: BTreeMap<(u8, u8), bool>,
+    shift: BTreeMap<u8, u8>,
+}
+
+impl Levels {
+    /// Apply `transition`; false when it repeats the current level
+    fn apply(&mut self, transition: &RawTransition) -> bool {
+        match *transition {
+            RawTransition::Gpio { gpio_mask, .. } => self.gpio.replace(gpio_mask) != Some(gpio_mask),
+            RawTransition::Matrix { row, col, is_connected, .. } => self.matrix.insert((row, col), is_connected) != Some(is_connected),
+            RawTransition::Shift { register_id, value, .. } => self.shift.insert(register_id, value) != Some(value),
+        }
+    }
+
+    fn to_state(&self, timestamp: u64) -> RawHardwareState {
+        RawHardwareState {
+            gpio: self.gpio.map(|gpio_mask| RawGpioStates { gpio_mask, timestamp }),
+            matrix: (!self.matrix.is_empty()).then(|| MatrixState {
+                connections: self.matrix.iter().map(|(&(row, col), &is_connected)| MatrixConnection { row, col, is_connected }).collect(),
+                timestamp,
+            }),
+            shift_registers: self.shift.iter().map(|(&register_id, &value)| ShiftRegisterState { register_id, value, timestamp }).collect(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Inner {
+    /// Levels before the oldest retained entry
+    baseline: Levels,
+    current: Levels,
+    entries: VecDeque<RawTransition>,
+    latest_us: u64,
+    /// Timestamp of the newest dropped entry
+    dropped_until_us: Option<u64>,
+}
+
+pub struct RawHistory {
+    inner: Mutex<Inner>,
+    window_us: u64,
+    max_entries: usize,
+}
+
+impl Default for RawHistory {
+    fn default() -> Self {
+        Self::new(HISTORY_WINDOW_US, MAX_HISTORY_ENTRIES)
+    }
+}
+
+impl RawHistory {
+    pub fn new(window_us: u64, max_entries: usize) -> Self {
+        Self { inner: Mutex::new(Inner::default()), window_us, max_entries: max_entries.max(1) }
+    }
+
+    pub fn record(&self, event: &ParsedEvent) {
+        let Some(transition) = RawTransition::from_event(event) else { return };
+        let timestamp = transition.timestamp();
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        if timestamp < inner.latest_us {
+            log::debug!("Raw history cleared: firmware time went back from {}µs to {}µs", inner.latest_us, timestamp);
+            *inner = Inner::default();
+        }
+        inner.latest_us = timestamp;
+        if !inner.current.apply(&transition) {
+            return;
+        }
+        inner.entries.push_back(transition);
+        let horizon = timestamp.saturating_sub(self.window_us);
+        while let Some(oldest) = inner.entries.front() {
+            if oldest.timestamp() >= horizon && inner.entries.len() <= self.max_entries {
+                break;
+            }
+            let oldest = inner.entries.pop_front().expect("front exists");
+            inner.dropped_until_us = Some(oldest.timestamp());
+            inner.baseline.apply(&oldest);
+        }
+    }
+
+    /// Transitions at or after `since_us` (default: everything retained)
+    pub fn query(&self, since_us: Option<u64>) -> RawStateHistory {
+        let inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let oldest = inner.entries.front().map(RawTransition::timestamp).unwrap_or(inner.latest_us);
+        let since_us = since_us.unwrap_or(oldest);
+        let mut initial = inner.baseline.clone();
+        let mut transitions = Vec::new();
+        for entry in &inner.entries {
+            if entry.timestamp() < since_us {
+                initial.apply(entry);
+            } else {
+                transitions.push(entry.clone());
+            }
+        }
+        RawStateHistory {
+            since_us,
+            latest_us: inner.latest_us,
+            truncated: inner.dropped_until_us.is_some_and(|dropped| dropped >= since_us),
+            initial: initial.to_state(since_us),
+            transitions,
+        }
+    }
+
+    pub fn clear(&self) {
+        *self.inner.lock().unwrap_or_else(|e| e.into_inner()) = Inner::default();
+    }
+}
+
+static HISTORY: once_cell::sync::Lazy<RawHistory> = once_cell::sync::Lazy::new(RawHistory::default);
+
+/// Get the global raw transition history
+pub fn get_history() -> &'static RawHistory {
+    &HISTORY
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gpio(mask: u32, timestamp: u64) -> ParsedEvent {
+        ParsedEvent::Gpio { mask, timestamp }
+    }
+
+    #[test]
+    fn keeps_transitions_and_initial_levels() {
+        let history = RawHistory::new(1_000, 100);
+        history.record(&gpio(0b01, 100));
+        history.record(&gpio(0b01, 150));
+        history.record(&ParsedEvent::MatrixDelta { row: 1, col: 2, is_connected: true, timestamp: 200 });
+        history.record(&gpio(0b11, 300));
+        history.record(&ParsedEvent::Unclassified { line: "GPIO_STATES:bad".into() });
+
+        let all = history.query(None);
+        assert_eq!(all.transitions.len(), 3, "repeated mask is not a transition");
+        assert_eq!((all.since_us, all.latest_us, all.truncated), (100, 300, false));
+
+        let recent = history.query(Some(250));
+        assert_eq!(recent.transitions, vec![RawTransition::Gpio { timestamp: 300, gpio_mask: 0b11 }]);
+        assert_eq!(recent.initial.gpio.unwrap().gpio_mask, 0b01);
+        assert!(recent.initial.matrix.unwrap().connections[0].is_connected);
+    }
+
+    #[test]
+    fn drops_old_entries_into_baseline_and_resets_on_reboot() {
+        let history = RawHistory::new(1_000, 100);
+        history.record(&gpio(1, 0));
+        history.record(&gpio(2, 500));
+        history.record(&gpio(3, 1_600));
+        let window = history.query(None);
+        assert_eq!((window.since_us, window.transitions.len()), (1_600, 1));
+        assert_eq!(window.initial.gpio.unwrap().gpio_mask, 2, "dropped entries still define the start level");
+        let old = history.query(Some(0));
+        assert!(old.truncated && !window.truncated);
+
+        history.record(&gpio(4, 10));
+        let rebooted = history.query(None);
+        assert_eq!((rebooted.latest_us, rebooted.transitions.len()), (10, 1));
+        assert!(rebooted.initial.gpio.is_none());
+    }
+}
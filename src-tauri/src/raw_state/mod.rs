@@ -2,16 +2,30 @@ pub mod types;
 pub mod parser;
 pub mod reader;
 pub mod monitor;
+pub mod coalesce;
+pub mod history;
+pub mod activity;
+pub mod subscriptions;
 
 pub use types::*;
 pub use reader::*;
+pub use history::{RawStateHistory, RawTransition};
+pub use activity::{InputActivity, InputActivityStats, InputSource};
+pub use subscriptions::RawEventKind;
 
-use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicU8, Ordering};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
 
 // Runtime display mode (was compile-time). Now supports Both to allow concurrent HID + Raw.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+// Persisted in `AppSettings::display_mode` and restored when the settings load.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum DisplayMode {
+    #[serde(rename = "hid")]
     HID = 0,
+    #[default]
     Raw = 1,
     Both = 2,
 }
@@ -47,10 +61,106 @@ pub fn set_display_mode(mode: DisplayMode) {
     log::info!("Display mode set to {}", mode.as_str());
 }
 
-// Performance configuration
-pub const RAW_STATE_POLLING_MS: u64 = 50; // Firmware sends updates every 50ms in continuous mode
-pub const ENABLE_DEBUG_LOGGING: bool = false;
-pub const ENABLE_PERFORMANCE_METRICS: bool = false;
+// Monitor event coalescing interval (0 = emit every line immediately). Default is roughly one animation frame.
+pub const DEFAULT_MONITOR_COALESCE_MS: u64 = 16;
+pub const MAX_MONITOR_COALESCE_MS: u64 = 1000;
+pub const MAX_MONITOR_EVENT_RATE_HZ: u32 = 1000;
+static MONITOR_COALESCE_MS_ATOMIC: AtomicU64 = AtomicU64::new(DEFAULT_MONITOR_COALESCE_MS);
+static MONITOR_EVENT_RATE_HZ_ATOMIC: AtomicU32 = AtomicU32::new(0);
+static MONITOR_DEBUG_ATOMIC: AtomicBool = AtomicBool::new(false);
+
+pub fn get_monitor_coalesce_ms() -> u64 {
+    MONITOR_COALESCE_MS_ATOMIC.load(Ordering::Relaxed)
+}
+
+/// Raw monitor tuning, persisted as `AppSettings::raw_monitor`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RawMonitorSettings {
+    /// Upper bound on raw-* event batches per second (0 = no limit)
+    pub max_event_rate_hz: u32,
+    /// Coalescing window in milliseconds (0 = emit every line immediately)
+    pub batch_ms: u64,
+    /// Per-line debug logging and periodic throughput metrics
+    pub debug: bool,
+}
+
+impl Default for RawMonitorSettings {
+    fn default() -> Self {
+        Self { max_event_rate_hz: 0, batch_ms: DEFAULT_MONITOR_COALESCE_MS, debug: false }
+    }
+}
+
+impl RawMonitorSettings {
+    /// Limited to `MAX_MONITOR_EVENT_RATE_HZ` and `MAX_MONITOR_COALESCE_MS`
+    pub fn clamped(&self) -> Self {
+        Self {
+            max_event_rate_hz: self.max_event_rate_hz.min(MAX_MONITOR_EVENT_RATE_HZ),
+            batch_ms: self.batch_ms.min(MAX_MONITOR_COALESCE_MS),
+            debug: self.debug,
+        }
+    }
+}
+
+/// Current raw monitor tuning
+pub fn monitor_settings() -> RawMonitorSettings {
+    RawMonitorSettings {
+        max_event_rate_hz: MONITOR_EVENT_RATE_HZ_ATOMIC.load(Ordering::Relaxed),
+        batch_ms: get_monitor_coalesce_ms(),
+        debug: debug_enabled(),
+    }
+}
+
+/// Apply raw monitor tuning (clamped); takes effect on the running monitor
+pub fn apply_monitor_settings(settings: &RawMonitorSettings) {
+    let applied = settings.clamped();
+    if applied == monitor_settings() {
+        return;
+    }
+    MONITOR_EVENT_RATE_HZ_ATOMIC.store(applied.max_event_rate_hz, Ordering::Relaxed);
+    MONITOR_COALESCE_MS_ATOMIC.store(applied.batch_ms, Ordering::Relaxed);
+    MONITOR_DEBUG_ATOMIC.store(applied.debug, Ordering::Relaxed);
+    log::info!(
+        "Raw monitor settings: batch {}ms, max rate {}, debug {}",
+        applied.batch_ms,
+        if applied.max_event_rate_hz == 0 { "unlimited".to_string() } else { format!("{}Hz", applied.max_event_rate_hz) },
+        applied.debug
+    );
+}
+
+/// Verbose raw monitor logging and throughput metrics
+pub fn debug_enabled() -> bool {
+    MONITOR_DEBUG_ATOMIC.load(Ordering::Relaxed)
+}
+
+/// Minimum time between raw-* event batches: the coalescing window, stretched to honor the rate cap
+pub fn monitor_emit_interval() -> Duration {
+    emit_interval(get_monitor_coalesce_ms(), MONITOR_EVENT_RATE_HZ_ATOMIC.load(Ordering::Relaxed))
+}
+
+fn emit_interval(batch_ms: u64, max_event_rate_hz: u32) -> Duration {
+    let batch = Duration::from_millis(batch_ms);
+    if max_event_rate_hz == 0 {
+        return batch;
+    }
+    batch.max(Duration::from_micros(1_000_000 / max_event_rate_hz as u64))
+}
+
+// Firmware sends updates every 50ms in continuous mode
+pub const RAW_STATE_POLLING_MS: u64 = 50;
 
 // Helper function to get display mode as string for frontend
-pub fn get_display_mode_string() -> String { get_display_mode().as_str().to_string() }
\ No newline at end of file
+pub fn get_display_mode_string() -> String { get_display_mode().as_str().to_string() }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rate_cap_stretches_emit_interval() {
+        assert_eq!(emit_interval(16, 0), Duration::from_millis(16));
+        assert_eq!(emit_interval(16, 20), Duration::from_millis(50));
+        assert_eq!(emit_interval(100, 20), Duration::from_millis(100));
+        assert_eq!(emit_interval(0, 0), Duration::ZERO);
+    }
+}
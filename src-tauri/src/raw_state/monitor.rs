@@ -1,11 +1,15 @@
 use crate::raw_state::types::*;
-use crate::raw_state::parser::*;
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Instant;
-use tokio::sync::{Mutex, mpsc};
+use tokio::sync::{Mutex, broadcast, mpsc};
 use tokio::time::{Duration, timeout};
 use tauri::Emitter;
+use crate::raw_state::coalesce::{CoalescedBatch, MonitorCoalescer};
+use crate::raw_state::subscriptions::{get_subscriptions, ActiveKinds, RawEventKind};
+use crate::recording::RecordedEvent;
+use crate::serial::ParsedEvent;
+use crate::telemetry::TelemetryEvent;
 
 /// Raw state monitoring manager
 pub struct RawStateMonitor {
@@ -92,7 +96,7 @@ impl RawStateMonitor {
         }
     }
 
-    /// Continuous monitoring loop using firmware's streaming mode
+    /// Continuous monitoring loop fed by the unified reader's parsed monitor events
     async fn monitoring_loop_continuous(
         device_id: String,
         app_handle: tauri::AppHandle,
@@ -102,44 +106,37 @@ impl RawStateMonitor {
         let start_time = Instant::now();
         log::info!("Starting continuous raw state monitoring for device: {}", device_id);
 
-        // Get access to the device's protocol
-        let protocol_result = device_manager.get_connected_protocol_for_monitoring().await;
-        if protocol_result.is_err() {
-            log::error!("Failed to get device protocol for monitoring");
+        // The unified reader owns the port and already parses monitor lines
+        let Some(handle) = device_manager.get_unified_serial_handle().await else {
+            log::error!("No unified serial connection for monitoring");
             return;
-        }
-
-        // Start continuous monitoring only (no polling fallback)
-        let use_continuous_mode = match Self::start_continuous_stream(&device_manager).await {
-            Ok(()) => {
-                log::info!("Successfully started continuous monitoring stream");
-                true
-            }
-            Err(e) => {
-                log::error!("Continuous monitoring failed: {}", e);
-                return; // Exit if continuous monitoring fails - no fallback
-            }
         };
+        // Subscribe before the stream starts so the first updates are not missed
+        let mut events = handle.subscribe_events();
+        // Waveforms start fresh with each session (the device may have changed)
+        crate::raw_state::history::get_history().clear();
 
-        log::info!("Starting continuous monitoring mode only (no polling fallback)");
+        // Start continuous monitoring only (no polling fallback)
+        if let Err(e) = Self::start_continuous_stream(&device_manager).await {
+            log::error!("Continuous monitoring failed: {}", e);
+            return;
+        }
+        log::info!("Successfully started continuous monitoring stream");
 
-        // No throttling - emit all events immediately for real-time responsiveness
+        // Updates are batched per coalescing interval (runtime tunable; 0 emits immediately)
+        let mut coalescer = MonitorCoalescer::new();
+        // Only kinds some view subscribed to are queued for the webview
+        let mut subscription_changes = get_subscriptions().watch();
+        let mut active = *subscription_changes.borrow_and_update();
 
-        // Buffer for accumulating partial lines
-        let mut line_buffer = String::new();
-        
         // Performance tracking
-        let mut lines_processed = 0u64;
+        let mut events_processed = 0u64;
         let mut last_perf_report = Instant::now();
-        let mut gpio_lines = 0u64;
-        let mut matrix_lines = 0u64;
-        let mut shift_lines = 0u64;
+        let mut gpio_events = 0u64;
+        let mut matrix_events = 0u64;
+        let mut shift_events = 0u64;
         let mut unknown_lines = 0u64;
-    let _last_gpio_time = Instant::now();
-        
-        // Log monitoring mode for validation
-        log::info!("Raw state monitoring mode: {}", if use_continuous_mode { "Continuous" } else { "Optimized Polling" });
-        
+
         loop {
             tokio::select! {
                 // Check for stop signal
@@ -147,86 +144,84 @@ impl RawStateMonitor {
                     log::info!("Received stop signal for monitoring");
                     break;
                 }
-                
-                // Handle continuous monitoring only
-                state_result = async {
-                    // Continuous mode: read from stream
-                    match Self::read_next_monitor_line(&device_manager, &mut line_buffer).await {
-                        Ok(Some(line)) => Ok(vec![line]),
-                        Ok(None) => Ok(vec![]),
-                        Err(e) => Err(e),
+
+                // Flush coalesced updates once their interval elapses
+                _ = tokio::time::sleep(coalescer.time_until_due(Self::coalesce_interval())), if !coalescer.is_empty() => {
+                    Self::emit_batch(&app_handle, coalescer.take());
+                }
+
+                Ok(()) = subscription_changes.changed() => {
+                    let now_active = *subscription_changes.borrow_and_update();
+                    if now_active.gained(&active) {
+                        // New views start from the current state rather than the next change
+                        Self::resync_from_snapshot(&handle, &mut coalescer, now_active);
                     }
-                } => {
-                    match state_result {
-                        Ok(lines) => {
-                            let _lines_count = lines.len();
-                            // Process all received lines
-                            for line in lines {
-                                // Track line types for metrics
-                                if line.starts_with("GPIO_STATES:") {
-                                    gpio_lines += 1;
-                                    if crate::raw_state::ENABLE_DEBUG_LOGGING {
-                                        log::info!("GPIO line received: {}", line);
-                                    }
-                                } else if line.starts_with("MATRIX_STATE:") {
-                                    matrix_lines += 1;
-                                } else if line.starts_with("SHIFT_REG:") {
-                                    shift_lines += 1;
-                                } else {
+                    active = now_active;
+                }
+
+                received = events.recv() => {
+                    match received {
+                        Ok(event) => {
+                            // Track event types for metrics
+                            match &event {
+                                ParsedEvent::Gpio { .. } => gpio_events += 1,
+                                ParsedEvent::MatrixDelta { .. } => matrix_events += 1,
+                                ParsedEvent::Shift { .. } => shift_events += 1,
+                                ParsedEvent::Unclassified { line } => {
                                     unknown_lines += 1;
-                                    if crate::raw_state::ENABLE_DEBUG_LOGGING {
+                                    if crate::raw_state::debug_enabled() {
                                         log::debug!("Unknown monitor line type: {}", line);
                                     }
                                 }
-                                
-                                // Process the line
-                                Self::process_monitor_line(
-                                    &line,
-                                    &app_handle
-                                );
-                                
-                                lines_processed += 1;
+                                ParsedEvent::ProtocolNotice { message } => log::debug!("Serial notice during monitoring: {}", message),
+                            }
+                            Self::process_monitor_event(event, &mut coalescer, active);
+                            events_processed += 1;
+                            if coalescer.is_due(Self::coalesce_interval()) {
+                                Self::emit_batch(&app_handle, coalescer.take());
                             }
-                            
-                            // Performance reporting (after processing all lines)
-                            if crate::raw_state::ENABLE_PERFORMANCE_METRICS && last_perf_report.elapsed().as_secs() >= 10 {
+
+                            if crate::raw_state::debug_enabled() && last_perf_report.elapsed().as_secs() >= 10 {
                                 let elapsed = last_perf_report.elapsed();
-                                let rate = lines_processed as f64 / elapsed.as_secs_f64();
-                                log::info!("Raw state monitoring performance: {:.1} lines/sec ({} lines in {:?}) - GPIO: {}, Matrix: {}, Shift: {}, Unknown: {}", 
-                                    rate, lines_processed, elapsed, gpio_lines, matrix_lines, shift_lines, unknown_lines);
-                                
+                                let rate = events_processed as f64 / elapsed.as_secs_f64();
+                                log::info!("Raw state monitoring performance: {:.1} events/sec ({} events in {:?}) - GPIO: {}, Matrix: {}, Shift: {}, Unknown: {}",
+                                    rate, events_processed, elapsed, gpio_events, matrix_events, shift_events, unknown_lines);
+
                                 // Reset counters
-                                lines_processed = 0;
-                                gpio_lines = 0;
-                                matrix_lines = 0;
-                                shift_lines = 0;
+                                events_processed = 0;
+                                gpio_events = 0;
+                                matrix_events = 0;
+                                shift_events = 0;
                                 unknown_lines = 0;
                                 last_perf_report = Instant::now();
                             }
-                            
-                            // Continuous mode - no artificial delays needed
                         }
-                        Err(e) => {
-                            log::warn!("Error reading monitor stream: {}", e);
-                            // Small delay before retrying
-                            tokio::time::sleep(Duration::from_millis(10)).await;
+                        Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                            log::warn!("Raw state monitor missed {} events; resyncing from the reader snapshot", skipped);
+                            Self::resync_from_snapshot(&handle, &mut coalescer, active);
+                        }
+                        Err(broadcast::error::RecvError::Closed) => {
+                            log::warn!("Unified serial reader stopped; ending raw state monitoring");
+                            break;
                         }
                     }
                 }
             }
         }
 
-        // Stop continuous monitoring before returning
+        // Deliver anything still pending, then stop continuous monitoring
+        Self::emit_batch(&app_handle, coalescer.take());
         let _ = Self::stop_continuous_stream(&device_manager).await;
-        
+        log::debug!("Raw monitor coalescing: {} updates emitted in {} batches", coalescer.updates_in, coalescer.batches_out);
+
         let elapsed = start_time.elapsed();
-        if crate::raw_state::ENABLE_PERFORMANCE_METRICS {
-            let total_lines = gpio_lines + matrix_lines + shift_lines + unknown_lines;
-            let avg_rate = if elapsed.as_secs_f64() > 0.0 { total_lines as f64 / elapsed.as_secs_f64() } else { 0.0 };
-            log::info!("Stopped raw state monitoring for device: {} (ran for {:?}, {} total lines, {:.1} avg lines/sec)", 
-                device_id, elapsed, total_lines, avg_rate);
-            log::info!("Final line breakdown - GPIO: {}, Matrix: {}, Shift: {}, Unknown: {}", 
-                gpio_lines, matrix_lines, shift_lines, unknown_lines);
+        if crate::raw_state::debug_enabled() {
+            let total_events = gpio_events + matrix_events + shift_events + unknown_lines;
+            let avg_rate = if elapsed.as_secs_f64() > 0.0 { total_events as f64 / elapsed.as_secs_f64() } else { 0.0 };
+            log::info!("Stopped raw state monitoring for device: {} (ran for {:?}, {} total events, {:.1} avg events/sec)",
+                device_id, elapsed, total_events, avg_rate);
+            log::info!("Final event breakdown - GPIO: {}, Matrix: {}, Shift: {}, Unknown: {}",
+                gpio_events, matrix_events, shift_events, unknown_lines);
         } else {
             log::info!("Stopped raw state monitoring for device: {} (ran for {:?})", device_id, elapsed);
         }
@@ -279,120 +274,117 @@ impl RawStateMonitor {
         Ok(())
     }
 
-    /// Read next line from monitoring stream
-    async fn read_next_monitor_line(
-        device_manager: &Arc<crate::device::DeviceManager>,
-        buffer: &mut String,
-    ) -> Result<Option<String>, String> {
-        // 1. If we already have a complete line in the buffer, return it immediately (no new read)
-        if let Some(newline_pos) = buffer.find('\n') {
-            let line = buffer[..newline_pos].to_string();
-            buffer.drain(..=newline_pos);
-            return Ok(Some(line));
-        }
+    fn coalesce_interval() -> Duration {
+        crate::raw_state::monitor_emit_interval()
+    }
 
-        // 2. Otherwise read more data (short timeout) and then attempt to extract a line
-        let data = device_manager.read_monitor_data(20).await?; // shorter timeout to reduce latency
-        if !data.is_empty() {
-            buffer.push_str(&data);
-            // Drain as many blank leading newlines / returns as possible
-            loop {
-                if let Some(newline_pos) = buffer.find('\n') {
-                    // Extract first line (could be empty if leading newline)
-                    let line = buffer[..newline_pos].to_string();
-                    buffer.drain(..=newline_pos);
-                    if line.trim().is_empty() {
-                        // Skip empty line and continue scanning
-                        continue;
-                    }
-                    return Ok(Some(line));
-                } else {
-                    break;
-                }
+    /// Emit a coalesced batch using the same event names and payloads as single updates; kinds
+    /// whose last subscriber left since they were queued are dropped
+    fn emit_batch(app_handle: &tauri::AppHandle, batch: CoalescedBatch) {
+        let active = get_subscriptions().active();
+        let clock = crate::device::clock_sync::current();
+        if let Some(gpio_states) = batch.gpio.filter(|_| active.gpio) {
+            let timestamp = gpio_states.timestamp;
+            if let Err(e) = app_handle.emit(RawEventKind::Gpio.event_name(), &WithHostTime::new(gpio_states, timestamp, clock.as_ref())) {
+                log::warn!("Failed to emit GPIO state: {}", e);
+            }
+        }
+        if let Some(matrix_update) = batch.matrix.filter(|_| active.matrix) {
+            let timestamp = matrix_update.timestamp;
+            if let Err(e) = app_handle.emit(RawEventKind::Matrix.event_name(), &WithHostTime::new(matrix_update, timestamp, clock.as_ref())) {
+                log::warn!("Failed to emit matrix state: {}", e);
+            }
+        }
+        if let Some(shift_states) = batch.shift.filter(|_| active.shift) {
+            let shift_states: Vec<_> = shift_states.into_iter()
+                .map(|s| { let timestamp = s.timestamp; WithHostTime::new(s, timestamp, clock.as_ref()) })
+                .collect();
+            if let Err(e) = app_handle.emit(RawEventKind::Shift.event_name(), &shift_states) {
+                log::warn!("Failed to emit shift register state: {}", e);
             }
         }
-
-        Ok(None)
     }
 
+    /// Queue the reader's full snapshot of the active kinds, after missed events or for new
+    /// subscribers, so the UI converges again
+    fn resync_from_snapshot(handle: &crate::serial::UnifiedSerialHandle, coalescer: &mut MonitorCoalescer, active: ActiveKinds) {
+        let snapshot = handle.snapshot_receiver().borrow().clone();
+        let state = RawHardwareState::from(&*snapshot);
+        if let Some(gpio_states) = state.gpio.filter(|_| active.gpio) {
+            coalescer.push_gpio(gpio_states);
+        }
+        if let Some(matrix) = state.matrix.filter(|_| active.matrix) {
+            for connection in matrix.connections {
+                coalescer.push_matrix(connection, matrix.timestamp);
+            }
+        }
+        for shift_state in state.shift_registers.into_iter().filter(|_| active.shift) {
+            coalescer.push_shift(shift_state);
+        }
+    }
 
-    /// Process a line from the monitoring stream
-    fn process_monitor_line(
-        line: &str,
-        app_handle: &tauri::AppHandle,
+    /// Record and publish one parsed monitor event, and queue it for the webview when its kind
+    /// has subscribers
+    fn process_monitor_event(
+        event: ParsedEvent,
+        coalescer: &mut MonitorCoalescer,
+        active: ActiveKinds,
     ) {
-        let line = line.trim();
-        let parse_start = if crate::raw_state::ENABLE_PERFORMANCE_METRICS { Some(Instant::now()) } else { None };
-        
-        if line.starts_with("GPIO_STATES:") {
-            if let Some(gpio_states) = parse_gpio_response(line) {
+        let parse_start = if crate::raw_state::debug_enabled() { Some(Instant::now()) } else { None };
+        crate::raw_state::history::get_history().record(&event);
+        crate::raw_state::activity::get_activity().record(&event);
+
+        match event {
+            ParsedEvent::Gpio { mask, timestamp } => {
                 // Debug the actual GPIO values
-                if crate::raw_state::ENABLE_DEBUG_LOGGING {
-                    log::info!("GPIO state parsed - mask: 0x{:08X} ({:032b})", gpio_states.gpio_mask, gpio_states.gpio_mask);
-                }
-                // Always print to stdout for high-precision latency tracing (bypasses log buffering)
-                // Format: RAW_GPIO_EMIT <unix_nanos> <mask_hex>
-                if crate::raw_state::ENABLE_DEBUG_LOGGING {
+                if crate::raw_state::debug_enabled() {
+                    log::info!("GPIO state parsed - mask: 0x{:08X} ({:032b})", mask, mask);
                     use std::sync::atomic::{AtomicU32, Ordering};
                     static LAST_MASK: AtomicU32 = AtomicU32::new(0xFFFFFFFF);
                     let prev = LAST_MASK.load(Ordering::Relaxed);
-                    if prev != 0xFFFFFFFF && prev != gpio_states.gpio_mask {
-                        let changed = prev ^ gpio_states.gpio_mask;
+                    if prev != 0xFFFFFFFF && prev != mask {
+                        let changed = prev ^ mask;
                         let mut set_bits = Vec::new();
                         let mut cleared_bits = Vec::new();
-                        for bit in 0..32 { let bit_mask = 1u32 << bit; if (changed & bit_mask)!=0 { if (gpio_states.gpio_mask & bit_mask)!=0 { set_bits.push(bit);} else { cleared_bits.push(bit);} } }
-                        log::debug!("GPIO change mask=0x{:08X} set={:?} cleared={:?}", gpio_states.gpio_mask, set_bits, cleared_bits);
+                        for bit in 0..32 { let bit_mask = 1u32 << bit; if (changed & bit_mask)!=0 { if (mask & bit_mask)!=0 { set_bits.push(bit);} else { cleared_bits.push(bit);} } }
+                        log::debug!("GPIO change mask=0x{:08X} set={:?} cleared={:?}", mask, set_bits, cleared_bits);
                     }
-                    LAST_MASK.store(gpio_states.gpio_mask, Ordering::Relaxed);
+                    LAST_MASK.store(mask, Ordering::Relaxed);
                 }
-                
-                // Calculate latency from firmware timestamp
-                if crate::raw_state::ENABLE_PERFORMANCE_METRICS {
-                    let firmware_time_us = gpio_states.timestamp;
-                    log::debug!("GPIO state received - firmware timestamp: {}µs", firmware_time_us);
+
+                if crate::raw_state::debug_enabled() {
+                    log::debug!("GPIO state received - firmware timestamp: {}µs", timestamp);
                 }
-                
-                // Emit immediately without throttling
-                if let Err(e) = app_handle.emit("raw-gpio-changed", &gpio_states) {
-                    log::warn!("Failed to emit GPIO state: {}", e);
+
+                crate::recording::record(RecordedEvent::Gpio { gpio_mask: mask, device_ts: timestamp });
+                if active.gpio {
+                    coalescer.push_gpio(RawGpioStates { gpio_mask: mask, timestamp });
                 }
             }
-        } else if line.starts_with("MATRIX_STATE:") {
-            // Parse single matrix line
-            if let Some((row, col, state, timestamp)) = parse_single_matrix_line(line) {
-                let connection = MatrixConnection { row, col, is_connected: state };
-                
-                if crate::raw_state::ENABLE_PERFORMANCE_METRICS {
-                    log::debug!("Matrix state received - R{}C{}: {} @ {}µs", row, col, state, timestamp);
+            ParsedEvent::MatrixDelta { row, col, is_connected, timestamp } => {
+                if crate::raw_state::debug_enabled() {
+                    log::debug!("Matrix state received - R{}C{}: {} @ {}µs", row, col, is_connected, timestamp);
                 }
-                if crate::raw_state::ENABLE_DEBUG_LOGGING {
+                if crate::raw_state::debug_enabled() {
                     use std::sync::{OnceLock, Mutex};
                     static LAST_MATRIX: OnceLock<Mutex<std::collections::HashMap<(u8,u8), bool>>> = OnceLock::new();
                     let map = LAST_MATRIX.get_or_init(|| Mutex::new(std::collections::HashMap::new()));
                     let mut guard = map.lock().unwrap();
                     let key = (row,col);
-                    if let Some(prev) = guard.get(&key) { if *prev != state { log::debug!("Matrix change R{}C{} -> {}", row, col, state); } } else { log::debug!("Matrix baseline R{}C{} = {}", row, col, state); }
-                    guard.insert(key, state);
+                    if let Some(prev) = guard.get(&key) { if *prev != is_connected { log::debug!("Matrix change R{}C{} -> {}", row, col, is_connected); } } else { log::debug!("Matrix baseline R{}C{} = {}", row, col, is_connected); }
+                    guard.insert(key, is_connected);
                 }
-                
-                // Emit as a single connection update immediately
-                let matrix_update = MatrixState {
-                    connections: vec![connection],
-                    timestamp,
-                };
-                
-                if let Err(e) = app_handle.emit("raw-matrix-changed", &matrix_update) {
-                    log::warn!("Failed to emit matrix state: {}", e);
+
+                crate::recording::record(RecordedEvent::Matrix { row, col, is_connected, device_ts: timestamp });
+                if active.matrix {
+                    coalescer.push_matrix(MatrixConnection { row, col, is_connected }, timestamp);
                 }
             }
-        } else if line.starts_with("SHIFT_REG:") {
-            if let Some((register_id, value, timestamp)) = parse_single_shift_line(line) {
-                let shift_state = ShiftRegisterState { register_id, value, timestamp };
-                
-                if crate::raw_state::ENABLE_PERFORMANCE_METRICS {
+            ParsedEvent::Shift { register_id, value, timestamp } => {
+                if crate::raw_state::debug_enabled() {
                     log::debug!("Shift register state received - Reg{}: 0x{:02X} @ {}µs", register_id, value, timestamp);
                 }
-                if crate::raw_state::ENABLE_DEBUG_LOGGING {
+                if crate::raw_state::debug_enabled() {
                     use std::sync::{OnceLock, Mutex};
                     static LAST_SHIFT: OnceLock<Mutex<std::collections::HashMap<u8,u8>>> = OnceLock::new();
                     let map = LAST_SHIFT.get_or_init(|| Mutex::new(std::collections::HashMap::new()));
@@ -400,55 +392,26 @@ impl RawStateMonitor {
                     if let Some(prev) = guard.get(&register_id) { if *prev != value { log::debug!("Shift reg change R{} 0x{:02X} -> 0x{:02X}", register_id, prev, value); } } else { log::debug!("Shift reg baseline R{} = 0x{:02X}", register_id, value); }
                     guard.insert(register_id, value);
                 }
-                
-                // Emit as array for consistency immediately
-                if let Err(e) = app_handle.emit("raw-shift-changed", &vec![shift_state]) {
-                    log::warn!("Failed to emit shift register state: {}", e);
+
+                crate::recording::record(RecordedEvent::Shift { register_id, value, device_ts: timestamp });
+                if active.shift {
+                    coalescer.push_shift(ShiftRegisterState { register_id, value, timestamp });
                 }
             }
+            ParsedEvent::ProtocolNotice { .. } | ParsedEvent::Unclassified { .. } => return,
         }
-        
+        crate::diagnostics::feed_raw(&event);
+        crate::telemetry::publish(TelemetryEvent::Raw { event });
+
         if let Some(start) = parse_start {
-            if crate::raw_state::ENABLE_PERFORMANCE_METRICS {
-                let parse_time = start.elapsed();
-                if parse_time.as_micros() > 100 {
-                    log::debug!("Line parsing took: {:?} for: {}", parse_time, line);
-                }
+            let parse_time = start.elapsed();
+            if parse_time.as_micros() > 100 {
+                log::debug!("Monitor event handling took: {:?}", parse_time);
             }
         }
     }
 }
 
-/// Parse a single matrix line for continuous monitoring
-fn parse_single_matrix_line(line: &str) -> Option<(u8, u8, bool, u64)> {
-    // Format: MATRIX_STATE:row:col:state:timestamp
-    let parts: Vec<&str> = line.split(':').collect();
-    if parts.len() >= 5 && parts[0] == "MATRIX_STATE" {
-        let row = parts[1].parse().ok()?;
-        let col = parts[2].parse().ok()?;
-        let state = parts[3] == "1";
-        let timestamp = parts[4].parse().ok()?;
-        Some((row, col, state, timestamp))
-    } else {
-        None
-    }
-}
-
-/// Parse a single shift register line for continuous monitoring
-fn parse_single_shift_line(line: &str) -> Option<(u8, u8, u64)> {
-    // Format: SHIFT_REG:reg_id:0xHH:timestamp
-    let parts: Vec<&str> = line.split(':').collect();
-    if parts.len() >= 4 && parts[0] == "SHIFT_REG" {
-        let register_id = parts[1].parse().ok()?;
-        let value_str = parts[2].strip_prefix("0x")?;
-        let value = u8::from_str_radix(value_str, 16).ok()?;
-        let timestamp = parts[3].parse().ok()?;
-        Some((register_id, value, timestamp))
-    } else {
-        None
-    }
-}
-
 /// Global monitor instance
 static MONITOR: once_cell::sync::Lazy<RawStateMonitor> = 
     once_cell::sync::Lazy::new(|| RawStateMonitor::new());
@@ -9,7 +9,7 @@ impl RawStateReader {
     /// Read current GPIO states from device
     pub async fn read_gpio_states(protocol: &mut ConfigProtocol) -> Result<RawGpioStates, String> {
         // Send command via the interface
-    let response = protocol.send_locked("READ_GPIO_STATES").await.map_err(|e| format!("Failed to send GPIO command: {}", e))?;
+    let response = protocol.send_raw_command("READ_GPIO_STATES").await.map_err(|e| format!("Failed to send GPIO command: {}", e))?;
 
         // Parse response
         parse_gpio_response(&response)
@@ -18,8 +18,8 @@ impl RawStateReader {
 
     /// Read current matrix states from device
     pub async fn read_matrix_state(protocol: &mut ConfigProtocol) -> Result<MatrixState, String> {
-        // Send command and get response (the send_command method handles multiple lines)
-    let response = protocol.send_locked("READ_MATRIX_STATE").await.map_err(|e| format!("Failed to send matrix command: {}", e))?;
+        // One MATRIX_STATE line per cell; the command completes once the device goes quiet
+    let response = protocol.send_raw_command("READ_MATRIX_STATE").await.map_err(|e| format!("Failed to send matrix command: {}", e))?;
 
         // Split response into lines for parsing
         let lines: Vec<String> = response.lines().map(|s| s.to_string()).collect();
@@ -44,7 +44,7 @@ impl RawStateReader {
     /// Read current shift register states from device
     pub async fn read_shift_reg_state(protocol: &mut ConfigProtocol) -> Result<Vec<ShiftRegisterState>, String> {
         // Send command and get response
-    let response = protocol.send_locked("READ_SHIFT_REG").await.map_err(|e| format!("Failed to send shift register command: {}", e))?;
+    let response = protocol.send_raw_command("READ_SHIFT_REG").await.map_err(|e| format!("Failed to send shift register command: {}", e))?;
 
         // Split response into lines for parsing
         let lines: Vec<String> = response.lines().map(|s| s.to_string()).collect();
@@ -75,8 +75,8 @@ impl RawStateReader {
         match Self::read_gpio_states(protocol).await {
             Ok(gpio_states) => hardware_state.gpio = Some(gpio_states),
             Err(e) => {
-                if crate::raw_state::ENABLE_DEBUG_LOGGING {
-                    eprintln!("Failed to read GPIO states: {}", e);
+                if crate::raw_state::debug_enabled() {
+                    log::warn!("Failed to read GPIO states: {}", e);
                 }
             }
         }
@@ -85,8 +85,8 @@ impl RawStateReader {
         match Self::read_matrix_state(protocol).await {
             Ok(matrix_state) => hardware_state.matrix = Some(matrix_state),
             Err(e) => {
-                if crate::raw_state::ENABLE_DEBUG_LOGGING {
-                    eprintln!("Failed to read matrix states: {}", e);
+                if crate::raw_state::debug_enabled() {
+                    log::debug!("Failed to read matrix states: {}", e);
                 }
             }
         }
@@ -95,8 +95,8 @@ impl RawStateReader {
         match Self::read_shift_reg_state(protocol).await {
             Ok(shift_states) => hardware_state.shift_registers = shift_states,
             Err(e) => {
-                if crate::raw_state::ENABLE_DEBUG_LOGGING {
-                    eprintln!("Failed to read shift register states: {}", e);
+                if crate::raw_state::debug_enabled() {
+                    log::debug!("Failed to read shift register states: {}", e);
                 }
             }
         }
@@ -107,7 +107,7 @@ impl RawStateReader {
     /// Start raw state monitoring on device
     pub async fn start_monitoring(protocol: &mut ConfigProtocol) -> Result<(), String> {
         // Send start command
-    let response = protocol.send_locked("START_RAW_MONITOR").await.map_err(|e| format!("Failed to start monitoring: {}", e))?;
+    let response = protocol.send_raw_command("START_RAW_MONITOR").await.map_err(|e| format!("Failed to start monitoring: {}", e))?;
 
         if response.contains("OK:RAW_MONITOR_STARTED") {
             Ok(())
@@ -119,7 +119,7 @@ impl RawStateReader {
     /// Stop raw state monitoring on device
     pub async fn stop_monitoring(protocol: &mut ConfigProtocol) -> Result<(), String> {
         // Send stop command
-    let response = protocol.send_locked("STOP_RAW_MONITOR").await.map_err(|e| format!("Failed to stop monitoring: {}", e))?;
+    let response = protocol.send_raw_command("STOP_RAW_MONITOR").await.map_err(|e| format!("Failed to stop monitoring: {}", e))?;
 
         if response.contains("OK:RAW_MONITOR_STOPPED") {
             Ok(())
@@ -0,0 +1,160 @@
+//! Which raw-* events the webview wants.
+//!
+//! Views call `subscribe_raw_events` with the kinds they display and release the token when they
+//! close. The monitor only queues and emits kinds with at least one subscriber: with the
+//! monitoring page closed the stream still feeds history, activity counters, recording and
+//! telemetry, but nothing is serialized for the webview. When a kind gains its first subscriber
+//! the monitor replays the reader snapshot so the view starts from the current state.
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::watch;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RawEventKind {
+    Gpio,
+    Matrix,
+    Shift,
+}
+
+impl RawEventKind {
+    pub const ALL: [RawEventKind; 3] = [RawEventKind::Gpio, RawEventKind::Matrix, RawEventKind::Shift];
+
+    pub fn event_name(self) -> &'static str {
+        match self {
+            RawEventKind::Gpio => "raw-gpio-changed",
+            RawEventKind::Matrix => "raw-matrix-changed",
+            RawEventKind::Shift => "raw-shift-changed",
+        }
+    }
+}
+
+/// Kinds with at least one subscriber
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ActiveKinds {
+    pub gpio: bool,
+    pub matrix: bool,
+    pub shift: bool,
+}
+
+impl ActiveKinds {
+    pub fn contains(&self, kind: RawEventKind) -> bool {
+        match kind {
+            RawEventKind::Gpio => self.gpio,
+            RawEventKind::Matrix => self.matrix,
+            RawEventKind::Shift => self.shift,
+        }
+    }
+
+    fn insert(&mut self, kind: RawEventKind) {
+        match kind {
+            RawEventKind::Gpio => self.gpio = true,
+            RawEventKind::Matrix => self.matrix = true,
+            RawEventKind::Shift => self.shift = true,
+        }
+    }
+
+    /// Some kind is active here but was not in `previous`
+    pub fn gained(&self, previous: &ActiveKinds) -> bool {
+        RawEventKind::ALL.iter().any(|&k| self.contains(k) && !previous.contains(k))
+    }
+}
+
+pub struct RawEventSubscriptions {
+    next_token: AtomicU64,
+    subscribers: Mutex<HashMap<u64, Vec<RawEventKind>>>,
+    active: watch::Sender<ActiveKinds>,
+}
+
+impl Default for RawEventSubscriptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RawEventSubscriptions {
+    pub fn new() -> Self {
+        Self {
+            next_token: AtomicU64::new(1),
+            subscribers: Mutex::new(HashMap::new()),
+            active: watch::channel(ActiveKinds::default()).0,
+        }
+    }
+
+    /// Subscribe to `kinds` (every kind when empty); returns the token for `unsubscribe`
+    pub fn subscribe(&self, kinds: &[RawEventKind]) -> u64 {
+        let token = self.next_token.fetch_add(1, Ordering::Relaxed);
+        let kinds = if kinds.is_empty() { RawEventKind::ALL.to_vec() } else { kinds.to_vec() };
+        let mut subscribers = self.subscribers.lock().unwrap_or_else(|e| e.into_inner());
+        log::debug!("Raw event subscription {}: {:?}", token, kinds);
+        subscribers.insert(token, kinds);
+        self.publish(&subscribers);
+        token
+    }
+
+    /// Drop a subscription; false when the token is unknown
+    pub fn unsubscribe(&self, token: u64) -> bool {
+        let mut subscribers = self.subscribers.lock().unwrap_or_else(|e| e.into_inner());
+        let removed = subscribers.remove(&token).is_some();
+        if removed {
+            log::debug!("Raw event subscription {} released", token);
+            self.publish(&subscribers);
+        }
+        removed
+    }
+
+    pub fn active(&self) -> ActiveKinds {
+        *self.active.borrow()
+    }
+
+    /// Changes of the active kinds
+    pub fn watch(&self) -> watch::Receiver<ActiveKinds> {
+        self.active.subscribe()
+    }
+
+    fn publish(&self, subscribers: &HashMap<u64, Vec<RawEventKind>>) {
+        let mut active = ActiveKinds::default();
+        for &kind in subscribers.values().flatten() {
+            active.insert(kind);
+        }
+        self.active.send_if_modified(|current| std::mem::replace(current, active) != active);
+    }
+}
+
+static SUBSCRIPTIONS: once_cell::sync::Lazy<RawEventSubscriptions> = once_cell::sync::Lazy::new(RawEventSubscriptions::new);
+
+/// Get the global raw event subscriptions
+pub fn get_subscriptions() -> &'static RawEventSubscriptions {
+    &SUBSCRIPTIONS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tracks_active_kinds_per_subscriber() {
+        let subscriptions = RawEventSubscriptions::new();
+        let mut changes = subscriptions.watch();
+        assert_eq!(subscriptions.active(), ActiveKinds::default());
+
+        let gpio = subscriptions.subscribe(&[RawEventKind::Gpio]);
+        let all = subscriptions.subscribe(&[]);
+        assert!(changes.has_changed().unwrap());
+        let active = *changes.borrow_and_update();
+        assert!(active.gpio && active.matrix && active.shift);
+        assert!(active.gained(&ActiveKinds { gpio: true, ..Default::default() }));
+
+        assert!(subscriptions.unsubscribe(all));
+        assert!(!subscriptions.unsubscribe(all));
+        assert_eq!(subscriptions.active(), ActiveKinds { gpio: true, ..Default::default() });
+
+        changes.borrow_and_update();
+        subscriptions.subscribe(&[RawEventKind::Gpio]);
+        assert!(!changes.has_changed().unwrap(), "an already active kind is not republished");
+        assert!(subscriptions.unsubscribe(gpio));
+    }
+}
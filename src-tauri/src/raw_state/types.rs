@@ -1,5 +1,23 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+/// Raw-* event payload with the host time of its firmware timestamp, present while the device
+/// clock is synchronized
+#[derive(Debug, Clone, Serialize)]
+pub struct WithHostTime<T> {
+    #[serde(flatten)]
+    pub state: T,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub host_time: Option<DateTime<Utc>>,
+}
+
+impl<T> WithHostTime<T> {
+    /// `state` with firmware timestamp `timestamp`, placed on the host clock through `clock`
+    pub fn new(state: T, timestamp: u64, clock: Option<&crate::device::clock_sync::ClockModel>) -> Self {
+        Self { state, host_time: clock.map(|c| c.to_host_time(timestamp)) }
+    }
+}
+
 /// Raw GPIO state information from firmware
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RawGpioStates {
@@ -52,6 +70,32 @@ pub struct RawHardwareState {
     pub shift_registers: Vec<ShiftRegisterState>,
 }
 
+impl From<&crate::serial::unified::RawStateSnapshot> for RawHardwareState {
+    fn from(snapshot: &crate::serial::unified::RawStateSnapshot) -> Self {
+        Self {
+            gpio: snapshot.gpio_update_us.map(|timestamp| RawGpioStates { gpio_mask: snapshot.gpio_mask, timestamp }),
+            matrix: snapshot.matrix_update_us.map(|timestamp| MatrixState {
+                connections: snapshot.matrix.iter().map(|cell| MatrixConnection { row: cell.row, col: cell.col, is_connected: cell.is_connected }).collect(),
+                timestamp,
+            }),
+            shift_registers: snapshot.shift_regs.iter().map(|reg| ShiftRegisterState { register_id: reg.register_id, value: reg.value, timestamp: reg.timestamp }).collect(),
+        }
+    }
+}
+
+/// Current raw state kept by the unified serial reader, for rendering before the next event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RawStateSnapshotInfo {
+    /// Device ID the state belongs to
+    pub device_id: String,
+    /// Monitor updates applied so far; 0 until the first monitor line
+    pub seq: u64,
+    /// Timestamp in microseconds since boot of the newest update
+    pub last_update_us: u64,
+    /// Sections without received monitor data are absent
+    pub state: RawHardwareState,
+}
+
 /// Event payload for real-time updates
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RawStateEvent {
@@ -0,0 +1,58 @@
+//! On-disk format of input recordings (JSONL).
+//!
+//! The first line is a [`RecordingHeader`]; every following line is a [`RecordedEntry`] with the
+//! microseconds elapsed since recording start (`t_us`) and the event fields, e.g.
+//! `{"t_us":1520,"kind":"button","button_id":3,"pressed":true}`.
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+pub const RECORDING_FORMAT: &str = "joycore-recording";
+pub const RECORDING_FORMAT_VERSION: u32 = 1;
+pub const RECORDING_EXTENSION: &str = "jsonl";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordingHeader {
+    pub format: String,
+    pub version: u32,
+    pub started_at: DateTime<Utc>,
+    #[serde(default)]
+    pub label: Option<String>,
+    /// Stable device key of the connected device, if any
+    #[serde(default)]
+    pub device: Option<String>,
+    #[serde(default)]
+    pub firmware_version: Option<String>,
+}
+
+/// One captured input event. Device timestamps (`device_ts`) are the firmware's microsecond clock.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RecordedEvent {
+    Button { button_id: u8, pressed: bool },
+    Axis { axis_id: u8, value: i32 },
+    Gpio { gpio_mask: u32, device_ts: u64 },
+    Matrix { row: u8, col: u8, is_connected: bool, device_ts: u64 },
+    Shift { register_id: u8, value: u8, device_ts: u64 },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedEntry {
+    pub t_us: u64,
+    #[serde(flatten)]
+    pub event: RecordedEvent,
+}
+
+/// Summary of a recording file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordingInfo {
+    pub file_name: String,
+    pub path: PathBuf,
+    pub size_bytes: u64,
+    pub started_at: Option<DateTime<Utc>>,
+    pub label: Option<String>,
+    pub device: Option<String>,
+    /// Known for recordings finished in this session; None when only the header was read
+    pub event_count: Option<u64>,
+    pub duration_ms: Option<u64>,
+}
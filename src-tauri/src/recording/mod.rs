@@ -0,0 +1,51 @@
+pub mod format;
+pub mod recorder;
+pub mod replay;
+
+pub use format::*;
+pub use recorder::{list_recordings, read_header, InputRecorder};
+pub use replay::{load_recording, ReplayEngine, ReplayState, ReplayStatus};
+
+/// Subdirectory of the app data directory holding recordings
+pub const RECORDINGS_DIR_NAME: &str = "recordings";
+
+#[derive(Debug, thiserror::Error)]
+pub enum RecordingError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("A recording is already in progress")]
+    AlreadyRecording,
+
+    #[error("No recording in progress")]
+    NotRecording,
+
+    #[error("Invalid recording file: {0}")]
+    InvalidFile(String),
+}
+
+pub type Result<T> = std::result::Result<T, RecordingError>;
+
+/// Global recorder instance
+static RECORDER: once_cell::sync::Lazy<InputRecorder> = once_cell::sync::Lazy::new(InputRecorder::new);
+
+/// Get the global recorder instance
+pub fn get_recorder() -> &'static InputRecorder {
+    &RECORDER
+}
+
+/// Global replay engine instance
+static REPLAY: once_cell::sync::Lazy<ReplayEngine> = once_cell::sync::Lazy::new(ReplayEngine::new);
+
+/// Get the global replay engine instance
+pub fn get_replay_engine() -> &'static ReplayEngine {
+    &REPLAY
+}
+
+/// Record an event if a recording is active
+pub fn record(event: RecordedEvent) {
+    RECORDER.record(event);
+}
@@ -0,0 +1,198 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+
+use super::format::*;
+use super::{RecordingError, Result};
+
+/// Buffered lines are flushed at least this often so a crash loses little
+const FLUSH_EVERY_EVENTS: u64 = 256;
+
+struct ActiveRecording {
+    writer: BufWriter<File>,
+    path: PathBuf,
+    header: RecordingHeader,
+    started: Instant,
+    event_count: u64,
+    write_error: Option<String>,
+}
+
+/// Captures input events to a JSONL file. Recording hooks are cheap no-ops while idle.
+#[derive(Default)]
+pub struct InputRecorder {
+    active_flag: AtomicBool,
+    active: Mutex<Option<ActiveRecording>>,
+}
+
+impl InputRecorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.active_flag.load(Ordering::Relaxed)
+    }
+
+    /// Start a new recording in `dir`
+    pub fn start(&self, dir: &Path, label: Option<String>, device: Option<String>, firmware_version: Option<String>) -> Result<RecordingInfo> {
+        let mut guard = self.active.lock().unwrap_or_else(|e| e.into_inner());
+        if guard.is_some() {
+            return Err(RecordingError::AlreadyRecording);
+        }
+        std::fs::create_dir_all(dir)?;
+        let started_at = chrono::Utc::now();
+        let file_name = format!("recording-{}.{}", started_at.format("%Y%m%d-%H%M%S%.3f"), RECORDING_EXTENSION);
+        let path = dir.join(&file_name);
+        let header = RecordingHeader {
+            format: RECORDING_FORMAT.to_string(),
+            version: RECORDING_FORMAT_VERSION,
+            started_at,
+            label,
+            device,
+            firmware_version,
+        };
+        let mut writer = BufWriter::new(File::create(&path)?);
+        serde_json::to_writer(&mut writer, &header)?;
+        writer.write_all(b"\n")?;
+        writer.flush()?;
+
+        log::info!("Input recording started: {:?}", path);
+        let info = info_for(&path, &header, Some(0), Some(0));
+        *guard = Some(ActiveRecording { writer, path, header, started: Instant::now(), event_count: 0, write_error: None });
+        self.active_flag.store(true, Ordering::Relaxed);
+        Ok(info)
+    }
+
+    /// Append an event to the active recording (ignored while idle)
+    pub fn record(&self, event: RecordedEvent) {
+        if !self.is_recording() {
+            return;
+        }
+        let mut guard = self.active.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(active) = guard.as_mut() else { return; };
+        if active.write_error.is_some() {
+            return;
+        }
+        let entry = RecordedEntry { t_us: active.started.elapsed().as_micros() as u64, event };
+        let result = serde_json::to_writer(&mut active.writer, &entry)
+            .map_err(std::io::Error::from)
+            .and_then(|_| active.writer.write_all(b"\n"));
+        match result {
+            Ok(()) => {
+                active.event_count += 1;
+                if active.event_count % FLUSH_EVERY_EVENTS == 0 {
+                    let _ = active.writer.flush();
+                }
+            }
+            Err(e) => {
+                log::error!("Input recording write failed, further events dropped: {}", e);
+                active.write_error = Some(e.to_string());
+            }
+        }
+    }
+
+    /// Finish the active recording and return its summary
+    pub fn stop(&self) -> Result<RecordingInfo> {
+        let mut guard = self.active.lock().unwrap_or_else(|e| e.into_inner());
+        let mut active = guard.take().ok_or(RecordingError::NotRecording)?;
+        self.active_flag.store(false, Ordering::Relaxed);
+        active.writer.flush()?;
+        let duration_ms = active.started.elapsed().as_millis() as u64;
+        log::info!("Input recording stopped: {:?} ({} events, {} ms)", active.path, active.event_count, duration_ms);
+        if let Some(e) = active.write_error {
+            return Err(RecordingError::Io(std::io::Error::other(e)));
+        }
+        Ok(info_for(&active.path, &active.header, Some(active.event_count), Some(duration_ms)))
+    }
+}
+
+fn info_for(path: &Path, header: &RecordingHeader, event_count: Option<u64>, duration_ms: Option<u64>) -> RecordingInfo {
+    RecordingInfo {
+        file_name: path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default(),
+        path: path.to_path_buf(),
+        size_bytes: std::fs::metadata(path).map(|m| m.len()).unwrap_or(0),
+        started_at: Some(header.started_at),
+        label: header.label.clone(),
+        device: header.device.clone(),
+        event_count,
+        duration_ms,
+    }
+}
+
+/// Read only the header line of a recording
+pub fn read_header(path: &Path) -> Result<RecordingHeader> {
+    let mut first = String::new();
+    BufReader::new(File::open(path)?).read_line(&mut first)?;
+    let header: RecordingHeader = serde_json::from_str(first.trim())?;
+    if header.format != RECORDING_FORMAT {
+        return Err(RecordingError::InvalidFile(format!("unexpected format '{}'", header.format)));
+    }
+    Ok(header)
+}
+
+/// Recordings in `dir`, newest first. Unreadable files are listed without header details.
+pub fn list_recordings(dir: &Path) -> Result<Vec<RecordingInfo>> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let mut list = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().and_then(|e| e.to_str()) != Some(RECORDING_EXTENSION) {
+            continue;
+        }
+        let info = match read_header(&path) {
+            Ok(header) => info_for(&path, &header, None, None),
+            Err(e) => {
+                log::warn!("Unreadable recording {:?}: {}", path, e);
+                RecordingInfo {
+                    file_name: entry.file_name().to_string_lossy().to_string(),
+                    size_bytes: entry.metadata().map(|m| m.len()).unwrap_or(0),
+                    path,
+                    started_at: None,
+                    label: None,
+                    device: None,
+                    event_count: None,
+                    duration_ms: None,
+                }
+            }
+        };
+        list.push(info);
+    }
+    list.sort_by(|a, b| b.started_at.cmp(&a.started_at).then_with(|| b.file_name.cmp(&a.file_name)));
+    Ok(list)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_events_and_lists_file() {
+        let dir = std::env::temp_dir().join(format!("joycore_recordings_{}", uuid::Uuid::new_v4()));
+        let recorder = InputRecorder::new();
+        recorder.record(RecordedEvent::Button { button_id: 1, pressed: true }); // idle: ignored
+        recorder.start(&dir, Some("ghost".to_string()), None, None).unwrap();
+        assert!(matches!(recorder.start(&dir, None, None, None), Err(RecordingError::AlreadyRecording)));
+        recorder.record(RecordedEvent::Button { button_id: 3, pressed: true });
+        recorder.record(RecordedEvent::Gpio { gpio_mask: 0x10, device_ts: 99 });
+        let info = recorder.stop().unwrap();
+        assert_eq!(info.event_count, Some(2));
+
+        let contents = std::fs::read_to_string(&info.path).unwrap();
+        let lines: Vec<&str> = contents.lines().collect();
+        assert_eq!(lines.len(), 3);
+        let entry: RecordedEntry = serde_json::from_str(lines[1]).unwrap();
+        assert_eq!(entry.event, RecordedEvent::Button { button_id: 3, pressed: true });
+
+        let listed = list_recordings(&dir).unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].label.as_deref(), Some("ghost"));
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
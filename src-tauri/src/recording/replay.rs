@@ -0,0 +1,233 @@
+//! Playback of saved recordings: re-emits the original UI events at recorded timing (optionally scaled)
+//! so device behaviour can be reproduced without hardware.
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex as StdMutex};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tauri::Emitter;
+use tokio::sync::Mutex;
+
+use super::format::*;
+use super::recorder::read_header;
+use super::{RecordingError, Result};
+use crate::raw_state::{MatrixConnection, MatrixState, RawGpioStates, ShiftRegisterState};
+
+pub const MIN_REPLAY_SPEED: f64 = 0.1;
+pub const MAX_REPLAY_SPEED: f64 = 10.0;
+
+/// Load a recording: header plus all entries in file order
+pub fn load_recording(path: &Path) -> Result<(RecordingHeader, Vec<RecordedEntry>)> {
+    let header = read_header(path)?;
+    let reader = BufReader::new(File::open(path)?);
+    let mut entries = Vec::new();
+    for (index, line) in reader.lines().enumerate().skip(1) {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry: RecordedEntry = serde_json::from_str(&line)
+            .map_err(|e| RecordingError::InvalidFile(format!("line {}: {}", index + 1, e)))?;
+        entries.push(entry);
+    }
+    Ok((header, entries))
+}
+
+/// Wall-clock offset from playback start at which an entry is due
+pub fn scaled_offset(t_us: u64, speed: f64) -> Duration {
+    Duration::from_secs_f64(t_us as f64 / 1_000_000.0 / speed)
+}
+
+/// Play `entries` in order, sleeping between them according to `speed`
+pub async fn play_entries<F: FnMut(&RecordedEntry)>(entries: &[RecordedEntry], speed: f64, mut emit: F) {
+    let start = tokio::time::Instant::now();
+    for entry in entries {
+        tokio::time::sleep_until(start + scaled_offset(entry.t_us, speed)).await;
+        emit(entry);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReplayState {
+    Idle,
+    Playing,
+    Finished,
+    Stopped,
+}
+
+/// Playback progress, also emitted as the `replay-status` event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayStatus {
+    pub state: ReplayState,
+    pub path: Option<PathBuf>,
+    pub speed: f64,
+    pub events_total: u64,
+    pub events_emitted: u64,
+    pub duration_ms: u64,
+}
+
+impl Default for ReplayStatus {
+    fn default() -> Self {
+        Self { state: ReplayState::Idle, path: None, speed: 1.0, events_total: 0, events_emitted: 0, duration_ms: 0 }
+    }
+}
+
+/// Axis sample payload for the `axis-changed` event (only produced by replay)
+#[derive(Debug, Clone, Serialize)]
+pub struct AxisEvent {
+    pub axis_id: u8,
+    pub value: i32,
+}
+
+/// Tracks pressed buttons during playback so `button-state-sync` stays consistent
+#[derive(Debug, Default)]
+struct ReplayButtonState {
+    buttons: u128,
+}
+
+fn emit_entry(app_handle: &tauri::AppHandle, entry: &RecordedEntry, button_state: &mut ReplayButtonState) {
+    let result = match &entry.event {
+        RecordedEvent::Button { button_id, pressed } => {
+            let timestamp = chrono::Utc::now();
+            if (*button_id as usize) < crate::hid::MAX_BUTTONS {
+                let bit = 1u128 << button_id;
+                if *pressed { button_state.buttons |= bit; } else { button_state.buttons &= !bit; }
+            }
+            let event = crate::hid::ButtonEvent { button_id: *button_id, pressed: *pressed, timestamp, device_time_us: None, since_previous_report_us: None };
+            app_handle.emit("button-changed", &event).and_then(|_| {
+                app_handle.emit("button-state-sync", &crate::hid::ButtonStates::from_mask(button_state.buttons, timestamp))
+            })
+        }
+        RecordedEvent::Axis { axis_id, value } => {
+            app_handle.emit("axis-changed", &AxisEvent { axis_id: *axis_id, value: *value })
+        }
+        RecordedEvent::Gpio { gpio_mask, device_ts } => {
+            app_handle.emit("raw-gpio-changed", &RawGpioStates { gpio_mask: *gpio_mask, timestamp: *device_ts })
+        }
+        RecordedEvent::Matrix { row, col, is_connected, device_ts } => {
+            let update = MatrixState {
+                connections: vec![MatrixConnection { row: *row, col: *col, is_connected: *is_connected }],
+                timestamp: *device_ts,
+            };
+            app_handle.emit("raw-matrix-changed", &update)
+        }
+        RecordedEvent::Shift { register_id, value, device_ts } => {
+            let states = vec![ShiftRegisterState { register_id: *register_id, value: *value, timestamp: *device_ts }];
+            app_handle.emit("raw-shift-changed", &states)
+        }
+    };
+    if let Err(e) = result {
+        log::warn!("Failed to emit replayed event: {}", e);
+    }
+}
+
+/// Runs at most one playback at a time
+#[derive(Default)]
+pub struct ReplayEngine {
+    task: Mutex<Option<tokio::task::JoinHandle<()>>>,
+    status: Arc<StdMutex<ReplayStatus>>,
+}
+
+impl ReplayEngine {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn status(&self) -> ReplayStatus {
+        self.status.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Start replaying `path` at `speed` (clamped), replacing any playback in progress
+    pub async fn start(&self, app_handle: tauri::AppHandle, path: &Path, speed: f64) -> Result<ReplayStatus> {
+        let (_header, entries) = load_recording(path)?;
+        let speed = if speed.is_finite() { speed.clamp(MIN_REPLAY_SPEED, MAX_REPLAY_SPEED) } else { 1.0 };
+        self.stop().await;
+
+        let last_t_us = entries.last().map(|e| e.t_us).unwrap_or(0);
+        let initial = ReplayStatus {
+            state: ReplayState::Playing,
+            path: Some(path.to_path_buf()),
+            speed,
+            events_total: entries.len() as u64,
+            events_emitted: 0,
+            duration_ms: scaled_offset(last_t_us, speed).as_millis() as u64,
+        };
+        *self.status.lock().unwrap_or_else(|e| e.into_inner()) = initial.clone();
+        let _ = app_handle.emit("replay-status", &initial);
+        log::info!("Replaying {:?} ({} events at {}x)", path, entries.len(), speed);
+
+        let status = self.status.clone();
+        let task = tokio::spawn(async move {
+            let mut button_state = ReplayButtonState::default();
+            play_entries(&entries, speed, |entry| {
+                emit_entry(&app_handle, entry, &mut button_state);
+                if let Ok(mut s) = status.lock() { s.events_emitted += 1; }
+            }).await;
+            let finished = {
+                let mut s = status.lock().unwrap_or_else(|e| e.into_inner());
+                s.state = ReplayState::Finished;
+                s.clone()
+            };
+            let _ = app_handle.emit("replay-status", &finished);
+        });
+        *self.task.lock().await = Some(task);
+        Ok(initial)
+    }
+
+    /// Stop playback if running. Returns the final status.
+    pub async fn stop(&self) -> ReplayStatus {
+        if let Some(task) = self.task.lock().await.take() {
+            if !task.is_finished() {
+                task.abort();
+                let _ = task.await;
+                let mut s = self.status.lock().unwrap_or_else(|e| e.into_inner());
+                s.state = ReplayState::Stopped;
+            }
+        }
+        self.status()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scales_offsets_by_speed() {
+        assert_eq!(scaled_offset(2_000_000, 1.0), Duration::from_secs(2));
+        assert_eq!(scaled_offset(2_000_000, 2.0), Duration::from_secs(1));
+        assert_eq!(scaled_offset(1_000_000, 0.5), Duration::from_secs(2));
+    }
+
+    #[tokio::test]
+    async fn plays_entries_in_order_at_recorded_timing() {
+        let entries = vec![
+            RecordedEntry { t_us: 0, event: RecordedEvent::Button { button_id: 1, pressed: true } },
+            RecordedEntry { t_us: 20_000, event: RecordedEvent::Gpio { gpio_mask: 2, device_ts: 7 } },
+            RecordedEntry { t_us: 40_000, event: RecordedEvent::Button { button_id: 1, pressed: false } },
+        ];
+        let start = std::time::Instant::now();
+        let mut seen = Vec::new();
+        play_entries(&entries, 2.0, |e| seen.push((e.event.clone(), start.elapsed()))).await;
+        assert_eq!(seen.len(), 3);
+        assert_eq!(seen[2].0, RecordedEvent::Button { button_id: 1, pressed: false });
+        assert!(seen[2].1 >= Duration::from_millis(20));
+        assert!(seen[0].1 < seen[2].1);
+    }
+
+    #[test]
+    fn loads_recorder_output() {
+        let dir = std::env::temp_dir().join(format!("joycore_replay_{}", uuid::Uuid::new_v4()));
+        let recorder = super::super::InputRecorder::new();
+        recorder.start(&dir, None, None, None).unwrap();
+        recorder.record(RecordedEvent::Shift { register_id: 0, value: 0x7F, device_ts: 5 });
+        let info = recorder.stop().unwrap();
+        let (header, entries) = load_recording(&info.path).unwrap();
+        assert_eq!(header.version, RECORDING_FORMAT_VERSION);
+        assert_eq!(entries.len(), 1);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
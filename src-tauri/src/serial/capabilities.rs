@@ -0,0 +1,272 @@
+//! Firmware capability negotiation.
+//!
+//! Newer firmware answers `CAPABILITIES` with a comma separated feature list, e.g.
+//! `CAPABILITIES:WRITE_FILE,DELETE_FILE,RAW_MONITOR,HID_MAPPING,STORAGE_INFO,BOOTLOADER`.
+//! Firmware that predates the command gets a heuristic feature set: everything the app has
+//! always relied on, but none of the commands introduced alongside `CAPABILITIES`
+//! (WRITE_FILE, DELETE_FILE, STORAGE_INFO).
+use serde::{Deserialize, Serialize};
+
+use super::SerialError;
+
+pub const CAPABILITIES_PREFIX: &str = "CAPABILITIES:";
+
+/// A CAPABILITIES reply is complete on the feature list or an error (legacy firmware answers
+/// with an unknown-command error); other lines, such as monitor output, keep it waiting
+pub fn reply_complete(lines: &[String]) -> bool {
+    lines.iter().any(|l| {
+        let line = l.trim();
+        line.starts_with(CAPABILITIES_PREFIX) || line.to_ascii_uppercase().starts_with("ERROR")
+    })
+}
+
+/// A firmware feature commands can be gated on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Capability {
+    WriteFile,
+    DeleteFile,
+    RawMonitor,
+    HidMapping,
+    StorageInfo,
+    Bootloader,
+    /// Binary framed file transfers (see `framing`)
+    FramedFiles,
+    /// Apply a config from RAM without storing it (see `ConfigProtocol::test_config`)
+    TestConfig,
+    /// Shift layers stored in the config (see `config::shift_layer`)
+    ShiftLayers,
+    /// Hat switches in the config and the input report (see `config::hats`)
+    Hats,
+    /// Check an uploaded config without applying or storing it (see `ConfigProtocol::validate_config`)
+    ValidateConfig,
+    /// Restart the firmware (see `DeviceManager::reboot_device`)
+    Reboot,
+}
+
+impl Capability {
+    pub const ALL: [Capability; 12] = [
+        Capability::WriteFile,
+        Capability::DeleteFile,
+        Capability::RawMonitor,
+        Capability::HidMapping,
+        Capability::StorageInfo,
+        Capability::Bootloader,
+        Capability::FramedFiles,
+        Capability::TestConfig,
+        Capability::ShiftLayers,
+        Capability::Hats,
+        Capability::ValidateConfig,
+        Capability::Reboot,
+    ];
+
+    /// Protocol catalog entries gated by this capability
+    pub fn catalog_commands(self) -> &'static [&'static str] {
+        match self {
+            Capability::WriteFile => &["WRITE_FILE"],
+            Capability::DeleteFile => &["DELETE_FILE"],
+            Capability::RawMonitor => &["START_RAW_MONITOR", "STOP_RAW_MONITOR"],
+            Capability::HidMapping => &["HID_MAPPING_INFO", "HID_BUTTON_MAP"],
+            Capability::StorageInfo => &["STORAGE_INFO"],
+            Capability::Bootloader => &["BOOTLOADER"],
+            Capability::FramedFiles => &["READ_FILE_FRAMED"],
+            Capability::TestConfig => &["TEST_CONFIG", "TEST_CONFIG_REVERT"],
+            Capability::ShiftLayers => &[],
+            Capability::Hats => &[],
+            Capability::ValidateConfig => &["VALIDATE_CONFIG"],
+            Capability::Reboot => &["REBOOT"],
+        }
+    }
+
+    pub fn token(self) -> &'static str {
+        match self {
+            Capability::WriteFile => "WRITE_FILE",
+            Capability::DeleteFile => "DELETE_FILE",
+            Capability::RawMonitor => "RAW_MONITOR",
+            Capability::HidMapping => "HID_MAPPING",
+            Capability::StorageInfo => "STORAGE_INFO",
+            Capability::Bootloader => "BOOTLOADER",
+            Capability::FramedFiles => "FRAMED_FILES",
+            Capability::TestConfig => "TEST_CONFIG",
+            Capability::ShiftLayers => "SHIFT_LAYERS",
+            Capability::Hats => "HATS",
+            Capability::ValidateConfig => "VALIDATE_CONFIG",
+            Capability::Reboot => "REBOOT",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CapabilitySource {
+    /// Reported by the firmware's CAPABILITIES reply
+    Reported,
+    /// Firmware did not understand CAPABILITIES; inferred for legacy firmware
+    Heuristic,
+}
+
+/// Feature matrix of the connected firmware
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FirmwareCapabilities {
+    pub firmware_version: String,
+    pub source: CapabilitySource,
+    pub write_file: bool,
+    pub delete_file: bool,
+    pub raw_monitor: bool,
+    pub hid_mapping: bool,
+    pub storage_info: bool,
+    pub bootloader: bool,
+    #[serde(default)]
+    pub framed_files: bool,
+    #[serde(default)]
+    pub test_config: bool,
+    #[serde(default)]
+    pub shift_layers: bool,
+    #[serde(default)]
+    pub hats: bool,
+    #[serde(default)]
+    pub validate_config: bool,
+    #[serde(default)]
+    pub reboot: bool,
+    /// Reported tokens the app does not know about
+    #[serde(default)]
+    pub other: Vec<String>,
+}
+
+impl FirmwareCapabilities {
+    /// Assumed features of firmware without CAPABILITIES
+    pub fn legacy(firmware_version: &str) -> Self {
+        Self {
+            firmware_version: firmware_version.to_string(),
+            source: CapabilitySource::Heuristic,
+            write_file: false,
+            delete_file: false,
+            raw_monitor: true,
+            hid_mapping: true,
+            // STORAGE_INFO shipped together with CAPABILITIES
+            storage_info: false,
+            bootloader: true,
+            framed_files: false,
+            test_config: false,
+            shift_layers: false,
+            hats: false,
+            validate_config: false,
+            reboot: false,
+            other: Vec::new(),
+        }
+    }
+
+    /// Parse a CAPABILITIES reply; None when no reply line carries the prefix
+    pub fn parse(firmware_version: &str, lines: &[String]) -> Option<Self> {
+        let list = lines.iter().find_map(|l| l.trim().strip_prefix(CAPABILITIES_PREFIX))?;
+        let mut caps = Self {
+            firmware_version: firmware_version.to_string(),
+            source: CapabilitySource::Reported,
+            write_file: false,
+            delete_file: false,
+            raw_monitor: false,
+            hid_mapping: false,
+            storage_info: false,
+            bootloader: false,
+            framed_files: false,
+            test_config: false,
+            shift_layers: false,
+            hats: false,
+            validate_config: false,
+            reboot: false,
+            other: Vec::new(),
+        };
+        for token in list.split(',').map(|t| t.trim().to_ascii_uppercase()).filter(|t| !t.is_empty()) {
+            match token.as_str() {
+                "WRITE_FILE" => caps.write_file = true,
+                "DELETE_FILE" => caps.delete_file = true,
+                "RAW_MONITOR" => caps.raw_monitor = true,
+                "HID_MAPPING" => caps.hid_mapping = true,
+                "STORAGE_INFO" => caps.storage_info = true,
+                "BOOTLOADER" => caps.bootloader = true,
+                "FRAMED_FILES" => caps.framed_files = true,
+                "TEST_CONFIG" => caps.test_config = true,
+                "SHIFT_LAYERS" => caps.shift_layers = true,
+                "HATS" => caps.hats = true,
+                "VALIDATE_CONFIG" => caps.validate_config = true,
+                "REBOOT" => caps.reboot = true,
+                _ => caps.other.push(token),
+            }
+        }
+        Some(caps)
+    }
+
+    pub fn supports(&self, capability: Capability) -> bool {
+        match capability {
+            Capability::WriteFile => self.write_file,
+            Capability::DeleteFile => self.delete_file,
+            Capability::RawMonitor => self.raw_monitor,
+            Capability::HidMapping => self.hid_mapping,
+            Capability::StorageInfo => self.storage_info,
+            Capability::Bootloader => self.bootloader,
+            Capability::FramedFiles => self.framed_files,
+            Capability::TestConfig => self.test_config,
+            Capability::ShiftLayers => self.shift_layers,
+            Capability::Hats => self.hats,
+            Capability::ValidateConfig => self.validate_config,
+            Capability::Reboot => self.reboot,
+        }
+    }
+
+    pub fn set(&mut self, capability: Capability, supported: bool) {
+        let flag = match capability {
+            Capability::WriteFile => &mut self.write_file,
+            Capability::DeleteFile => &mut self.delete_file,
+            Capability::RawMonitor => &mut self.raw_monitor,
+            Capability::HidMapping => &mut self.hid_mapping,
+            Capability::StorageInfo => &mut self.storage_info,
+            Capability::Bootloader => &mut self.bootloader,
+            Capability::FramedFiles => &mut self.framed_files,
+            Capability::TestConfig => &mut self.test_config,
+            Capability::ShiftLayers => &mut self.shift_layers,
+            Capability::Hats => &mut self.hats,
+            Capability::ValidateConfig => &mut self.validate_config,
+            Capability::Reboot => &mut self.reboot,
+        };
+        *flag = supported;
+    }
+
+    /// Error for a command the firmware cannot run
+    pub fn unsupported(&self, capability: Capability) -> SerialError {
+        SerialError::Unsupported(format!("{} is not supported by firmware {}", capability.token(), self.firmware_version))
+    }
+
+    pub fn require(&self, capability: Capability) -> Result<(), SerialError> {
+        if self.supports(capability) { Ok(()) } else { Err(self.unsupported(capability)) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_reported_capabilities() {
+        let lines = vec!["CAPABILITIES:WRITE_FILE, raw_monitor,MACROS".to_string()];
+        let caps = FirmwareCapabilities::parse("1.4.0", &lines).unwrap();
+        assert_eq!(caps.source, CapabilitySource::Reported);
+        assert!(caps.write_file && caps.raw_monitor);
+        assert!(!caps.delete_file && !caps.bootloader);
+        assert_eq!(caps.other, vec!["MACROS".to_string()]);
+        assert!(FirmwareCapabilities::parse("1.4.0", &["ERROR:Unknown command".to_string()]).is_none());
+    }
+
+    #[test]
+    fn reply_completes_on_list_or_error() {
+        assert!(!reply_complete(&["BTN:3:1".to_string()]));
+        assert!(reply_complete(&["BTN:3:1".to_string(), "CAPABILITIES:WRITE_FILE".to_string()]));
+        assert!(reply_complete(&["ERROR:Unknown command: CAPABILITIES".to_string()]));
+    }
+
+    #[test]
+    fn unsupported_error_names_firmware_version() {
+        let caps = FirmwareCapabilities::legacy("1.0.2");
+        assert!(caps.require(Capability::RawMonitor).is_ok());
+        let err = caps.require(Capability::WriteFile).unwrap_err();
+        assert_eq!(err.to_string(), "WRITE_FILE is not supported by firmware 1.0.2");
+    }
+}
@@ -0,0 +1,119 @@
+//! Static catalog of the firmware serial commands known to the app.
+//!
+//! Used by `describe_protocol` to give the debug console live protocol docs.
+//! Support flags are filled in per connection from probing (read-only commands
+//! only) or from what the app already knows about the firmware.
+use serde::{Deserialize, Serialize};
+
+/// Static description of a single firmware command
+#[derive(Debug, Clone, Copy)]
+pub struct ProtocolCommand {
+    pub name: &'static str,
+    pub category: &'static str,
+    pub request: &'static str,
+    pub response: &'static str,
+    pub description: &'static str,
+    /// Safe to send purely to detect support (no side effects on device state)
+    pub probe_safe: bool,
+    /// Known support level without probing (None = unknown)
+    pub known_support: Option<bool>,
+}
+
+/// Serializable command description returned to the frontend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProtocolCommandInfo {
+    pub name: String,
+    pub category: String,
+    pub request: String,
+    pub response: String,
+    pub description: String,
+    pub probe_safe: bool,
+    /// Whether the connected firmware supports the command (None = unknown / not probed)
+    pub supported: Option<bool>,
+}
+
+/// Full protocol description for the connected (or no) device
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProtocolDescription {
+    pub connected: bool,
+    pub firmware_version: Option<String>,
+    pub commands: Vec<ProtocolCommandInfo>,
+}
+
+pub const PROTOCOL_COMMANDS: &[ProtocolCommand] = &[
+    ProtocolCommand { name: "IDENTIFY", category: "discovery", request: "IDENTIFY", response: "JOYCORE_ID:JOYCORE-FW:<signature>:<version>", description: "Identify a JoyCore device during discovery", probe_safe: true, known_support: Some(true) },
+    ProtocolCommand { name: "CAPABILITIES", category: "discovery", request: "CAPABILITIES", response: "CAPABILITIES:<FEATURE>,<FEATURE>,...", description: "List optional firmware features (sent on connect)", probe_safe: true, known_support: None },
+    ProtocolCommand { name: "STATUS", category: "device", request: "STATUS", response: "Multi-line text block containing 'Config Status'", description: "Report firmware configuration status", probe_safe: true, known_support: Some(true) },
+    ProtocolCommand { name: "TIME", category: "device", request: "TIME", response: "TIME:<microseconds since boot>", description: "Read the firmware clock (sent on connect to map device timestamps to host time)", probe_safe: true, known_support: None },
+    ProtocolCommand { name: "UPTIME", category: "device", request: "UPTIME", response: "UPTIME:<milliseconds since boot>", description: "Read how long the firmware has been running", probe_safe: true, known_support: None },
+    ProtocolCommand { name: "REBOOT", category: "device", request: "REBOOT", response: "OK:REBOOTING (the device may drop off the bus first)", description: "Restart the firmware", probe_safe: false, known_support: None },
+    ProtocolCommand { name: "AXIS_GET", category: "config", request: "AXIS_GET:<id>", response: "AXIS:<id>,<name>,<min>,<max>,<center>,<deadzone>,<curve>,<inverted>", description: "Read a single axis configuration", probe_safe: false, known_support: None },
+    ProtocolCommand { name: "AXIS_SET", category: "config", request: "AXIS_SET:<id>,<name>,<min>,<max>,<center>,<deadzone>,<curve>,<inverted>", response: "OK", description: "Write a single axis configuration", probe_safe: false, known_support: None },
+    ProtocolCommand { name: "BUTTON_GET", category: "config", request: "BUTTON_GET:<id>", response: "BUTTON:<id>,<name>,<function>,<enabled>", description: "Read a single button configuration", probe_safe: false, known_support: None },
+    ProtocolCommand { name: "BUTTON_SET", category: "config", request: "BUTTON_SET:<id>,<name>,<function>,<enabled>", response: "OK", description: "Write a single button configuration", probe_safe: false, known_support: None },
+    ProtocolCommand { name: "SAVE_CONFIG", category: "config", request: "SAVE_CONFIG", response: "OK", description: "Persist the active configuration to storage", probe_safe: false, known_support: Some(true) },
+    ProtocolCommand { name: "FORCE_DEFAULT_CONFIG", category: "config", request: "FORCE_DEFAULT_CONFIG", response: "OK", description: "Reset configuration to firmware defaults", probe_safe: false, known_support: Some(true) },
+    ProtocolCommand { name: "TEST_CONFIG", category: "config", request: "TEST_CONFIG <size> <crc32>, WRITE_FILE_CHUNK <offset> <hex> <crc32>..., WRITE_FILE_END", response: "OK:WRITE_BEGIN:<max_chunk> / ACK:<offset>:<len> | NAK:<offset>:<reason> / OK:WRITE_COMPLETE:<size>:<crc32>", description: "Apply a config from RAM without storing it (preview)", probe_safe: false, known_support: None },
+    ProtocolCommand { name: "VALIDATE_CONFIG", category: "config", request: "VALIDATE_CONFIG <size> <crc32>, WRITE_FILE_CHUNK <offset> <hex> <crc32>..., WRITE_FILE_END", response: "OK:WRITE_BEGIN:<max_chunk> / ACK:<offset>:<len> | NAK:<offset>:<reason> / VALIDATE:<ERROR|WARNING>:<code>:<message>... OK:WRITE_COMPLETE:<size>:<crc32>", description: "Check a config without applying or storing it (dry run)", probe_safe: false, known_support: None },
+    ProtocolCommand { name: "TEST_CONFIG_REVERT", category: "config", request: "TEST_CONFIG_REVERT", response: "OK:TEST_CONFIG_REVERTED", description: "Drop a previewed config and reload the stored one", probe_safe: false, known_support: None },
+    ProtocolCommand { name: "STORAGE_INFO", category: "storage", request: "STORAGE_INFO", response: "STORAGE_* key/value lines", description: "Report storage usage", probe_safe: true, known_support: None },
+    ProtocolCommand { name: "LIST_FILES", category: "storage", request: "LIST_FILES", response: "FILES: / <name> lines / END_FILES", description: "List files in device storage", probe_safe: true, known_support: Some(true) },
+    ProtocolCommand { name: "READ_FILE", category: "storage", request: "READ_FILE <path>", response: "FILE_DATA:<path>:<size>:<hex>", description: "Read a file as hex", probe_safe: false, known_support: Some(true) },
+    ProtocolCommand { name: "READ_FILE_FRAMED", category: "storage", request: "READ_FILE_FRAMED <path>", response: "FRAMED_FILE:<path>:<size>:<crc32>, binary file frames, END_FRAMED_FILE", description: "Read a file as COBS framed binary chunks", probe_safe: false, known_support: None },
+    ProtocolCommand { name: "WRITE_FILE", category: "storage", request: "WRITE_FILE_BEGIN <path> <size> <crc32>, WRITE_FILE_CHUNK <offset> <hex> <crc32>..., WRITE_FILE_END", response: "OK:WRITE_BEGIN:<max_chunk> / ACK:<offset>:<len> | NAK:<offset>:<reason> / OK:WRITE_COMPLETE:<size>:<crc32>", description: "Chunked, CRC-verified file upload to device storage", probe_safe: false, known_support: None },
+    ProtocolCommand { name: "DELETE_FILE", category: "storage", request: "DELETE_FILE <path>", response: "OK:FILE_DELETED", description: "Delete a file from device storage", probe_safe: false, known_support: None },
+    ProtocolCommand { name: "HID_MAPPING_INFO", category: "hid", request: "HID_MAPPING_INFO", response: "HID_MAPPING_INFO:<proto>,<report_id>,<buttons>,<axes>,<byte_offset>,<bit_order>,<crc>,<frame_offset>", description: "Describe the HID input report layout", probe_safe: true, known_support: None },
+    ProtocolCommand { name: "HID_BUTTON_MAP", category: "hid", request: "HID_BUTTON_MAP", response: "HID_BUTTON_MAP:SEQUENTIAL | HID_BUTTON_MAP:<bit>,<bit>,...", description: "Map HID report bits to logical buttons", probe_safe: true, known_support: None },
+    ProtocolCommand { name: "READ_GPIO_STATES", category: "raw", request: "READ_GPIO_STATES", response: "GPIO_STATES:0x<mask>:<timestamp>", description: "Snapshot of raw GPIO pin levels", probe_safe: false, known_support: None },
+    ProtocolCommand { name: "READ_MATRIX_STATE", category: "raw", request: "READ_MATRIX_STATE", response: "MATRIX_STATE:<row>:<col>:<state>:<timestamp> (one per cell)", description: "Snapshot of the button matrix", probe_safe: false, known_support: None },
+    ProtocolCommand { name: "READ_SHIFT_REG", category: "raw", request: "READ_SHIFT_REG", response: "SHIFT_REG:<id>:0x<value>:<timestamp> (one per register)", description: "Snapshot of shift register inputs", probe_safe: false, known_support: None },
+    ProtocolCommand { name: "START_RAW_MONITOR", category: "raw", request: "START_RAW_MONITOR", response: "OK:RAW_MONITOR_STARTED, then streamed raw state lines", description: "Start streaming raw hardware state", probe_safe: false, known_support: None },
+    ProtocolCommand { name: "STOP_RAW_MONITOR", category: "raw", request: "STOP_RAW_MONITOR", response: "OK", description: "Stop streaming raw hardware state", probe_safe: false, known_support: None },
+    ProtocolCommand { name: "BOOTLOADER", category: "device", request: "BOOTLOADER", response: "Device reboots into the UF2 bootloader (may not answer)", description: "Reboot into RP2040 BOOTSEL for flashing", probe_safe: false, known_support: None },
+];
+
+/// Look up a catalog entry by command name
+pub fn find_command(name: &str) -> Option<&'static ProtocolCommand> {
+    PROTOCOL_COMMANDS.iter().find(|c| c.name == name)
+}
+
+impl ProtocolCommand {
+    pub fn to_info(&self, supported: Option<bool>) -> ProtocolCommandInfo {
+        ProtocolCommandInfo {
+            name: self.name.to_string(),
+            category: self.category.to_string(),
+            request: self.request.to_string(),
+            response: self.response.to_string(),
+            description: self.description.to_string(),
+            probe_safe: self.probe_safe,
+            supported: supported.or(self.known_support),
+        }
+    }
+}
+
+/// Interpret a probe response: explicit error/unknown replies mean unsupported
+pub fn probe_response_supported(lines: &[String]) -> bool {
+    !lines.iter().any(|l| {
+        let upper = l.trim().to_ascii_uppercase();
+        upper.starts_with("ERROR") || upper.contains("UNKNOWN COMMAND") || upper.contains("UNKNOWN_COMMAND")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn catalog_names_are_unique() {
+        for (i, c) in PROTOCOL_COMMANDS.iter().enumerate() {
+            assert!(PROTOCOL_COMMANDS[i + 1..].iter().all(|o| o.name != c.name), "duplicate {}", c.name);
+        }
+    }
+
+    #[test]
+    fn probe_error_replies_mark_unsupported() {
+        assert!(probe_response_supported(&["STORAGE_USED:512".to_string()]));
+        assert!(!probe_response_supported(&["ERROR:Unknown command".to_string()]));
+        assert!(!probe_response_supported(&["Unknown command: HID_BUTTON_MAP".to_string()]));
+    }
+}
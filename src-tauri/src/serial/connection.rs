@@ -0,0 +1,93 @@
+//! Per-device serial connection parameters.
+//!
+//! Stock firmware runs at [`BAUD_RATE`] and answers IDENTIFY within [`IDENTIFY_TIMEOUT_MS`].
+//! Custom firmware builds may run at another speed or answer more slowly, so a device can
+//! override these in the known-device registry. Discovery probes the device's last port with its
+//! settings and connecting uses them for the port and every command timeout. Unset fields keep
+//! the defaults.
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use super::interface::{BAUD_RATE, IDENTIFY_TIMEOUT_MS};
+
+pub const MIN_BAUD_RATE: u32 = 300;
+pub const MAX_BAUD_RATE: u32 = 4_000_000;
+pub const IDENTIFY_TIMEOUT_RANGE_MS: std::ops::RangeInclusive<u64> = 50..=10_000;
+pub const COMMAND_TIMEOUT_FACTOR_RANGE: std::ops::RangeInclusive<f32> = 0.5..=10.0;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct ConnectionSettings {
+    #[serde(default)]
+    pub baud_rate: Option<u32>,
+    #[serde(default)]
+    pub identify_timeout_ms: Option<u64>,
+    /// Multiplies every command timeout, e.g. 2.0 for firmware answering at half speed
+    #[serde(default)]
+    pub command_timeout_factor: Option<f32>,
+}
+
+impl ConnectionSettings {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    pub fn baud_rate(&self) -> u32 {
+        self.baud_rate.unwrap_or(BAUD_RATE)
+    }
+
+    pub fn identify_timeout_ms(&self) -> u64 {
+        self.identify_timeout_ms.unwrap_or(IDENTIFY_TIMEOUT_MS)
+    }
+
+    pub fn command_timeout_factor(&self) -> f32 {
+        self.command_timeout_factor.unwrap_or(1.0)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(baud) = self.baud_rate.filter(|b| !(MIN_BAUD_RATE..=MAX_BAUD_RATE).contains(b)) {
+            return Err(format!("Baud rate {} is outside {}-{}", baud, MIN_BAUD_RATE, MAX_BAUD_RATE));
+        }
+        if let Some(ms) = self.identify_timeout_ms.filter(|ms| !IDENTIFY_TIMEOUT_RANGE_MS.contains(ms)) {
+            return Err(format!(
+                "Identify timeout {} ms is outside {}-{} ms",
+                ms, IDENTIFY_TIMEOUT_RANGE_MS.start(), IDENTIFY_TIMEOUT_RANGE_MS.end()
+            ));
+        }
+        if let Some(factor) = self.command_timeout_factor.filter(|f| !COMMAND_TIMEOUT_FACTOR_RANGE.contains(f)) {
+            return Err(format!(
+                "Command timeout factor {} is outside {}-{}",
+                factor, COMMAND_TIMEOUT_FACTOR_RANGE.start(), COMMAND_TIMEOUT_FACTOR_RANGE.end()
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// `timeout` scaled by a command timeout factor, in whole milliseconds
+pub fn scale_timeout(timeout: Duration, factor: f32) -> Duration {
+    if factor == 1.0 {
+        return timeout;
+    }
+    Duration::from_millis((timeout.as_millis() as f64 * factor as f64).round() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_and_validation() {
+        let settings = ConnectionSettings::default();
+        assert!(settings.is_default());
+        assert_eq!((settings.baud_rate(), settings.identify_timeout_ms()), (BAUD_RATE, IDENTIFY_TIMEOUT_MS));
+        assert!(settings.validate().is_ok());
+
+        let custom = ConnectionSettings { baud_rate: Some(921_600), command_timeout_factor: Some(2.5), ..settings };
+        assert!(custom.validate().is_ok());
+        assert_eq!(scale_timeout(Duration::from_millis(400), custom.command_timeout_factor()), Duration::from_millis(1000));
+        assert!(ConnectionSettings { baud_rate: Some(100), ..settings }.validate().is_err());
+        assert!(ConnectionSettings { identify_timeout_ms: Some(0), ..settings }.validate().is_err());
+        assert!(ConnectionSettings { command_timeout_factor: Some(f32::NAN), ..settings }.validate().is_err());
+    }
+}
@@ -0,0 +1,327 @@
+//! Chunked file upload / delete over the text protocol.
+//!
+//! ```text
+//! WRITE_FILE_BEGIN <path> <size> <crc32>     -> OK:WRITE_BEGIN:<max_chunk_bytes>
+//! WRITE_FILE_CHUNK <offset> <hex> <crc32>    -> ACK:<offset>:<len> | NAK:<offset>:<reason>
+//! WRITE_FILE_END                             -> OK:WRITE_COMPLETE:<size>:<crc32>
+//! WRITE_FILE_ABORT                           -> OK:WRITE_ABORTED
+//! DELETE_FILE <path>                         -> OK:FILE_DELETED
+//! TEST_CONFIG <size> <crc32>                 -> OK:WRITE_BEGIN:<max_chunk_bytes>
+//! TEST_CONFIG_REVERT                         -> OK:TEST_CONFIG_REVERTED
+//! VALIDATE_CONFIG <size> <crc32>             -> OK:WRITE_BEGIN:<max_chunk_bytes>
+//! ```
+//!
+//! TEST_CONFIG opens an upload like WRITE_FILE_BEGIN, but the completed data is applied as the
+//! running config instead of being stored. VALIDATE_CONFIG only checks it: WRITE_FILE_END
+//! answers with one `VALIDATE:<ERROR|WARNING>:<code>:<message>` line per finding before the
+//! usual completion line, and the data is then discarded.
+//!
+//! CRCs are CRC32 in 8 hex digits. The device verifies each chunk and the assembled file before
+//! committing it; failures reply `ERROR:<reason>`. Firmware without these commands replies
+//! `ERROR:Unknown command`, which is reported as [`SerialError::Unsupported`].
+//!
+//! Transfers report [`ConfigTransferProgress`]: uploads after every acknowledged chunk, reads
+//! from the reply bytes received against the size announced in the reply header.
+use serde::Serialize;
+
+use super::{Result, SerialError};
+use crate::util::crc::crc32;
+
+pub const CONFIG_TRANSFER_PROGRESS_EVENT: &str = "config_transfer_progress";
+
+/// Largest chunk the app sends; the device may ask for smaller ones in its BEGIN reply
+pub const WRITE_CHUNK_SIZE: usize = 64;
+/// Resends of a chunk after a NAK or missing acknowledgement
+pub const MAX_CHUNK_RETRIES: u32 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TransferDirection {
+    Read,
+    Write,
+}
+
+/// Payload of `config_transfer_progress`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ConfigTransferProgress {
+    pub direction: TransferDirection,
+    /// Device path, or what an upload is for (e.g. "preview config")
+    pub file: String,
+    /// File bytes transferred so far; estimated from the reply bytes while reading
+    pub bytes: usize,
+    pub total: usize,
+}
+
+/// Receives progress of the file transfers of a connection
+pub type ProgressSink = std::sync::Arc<dyn Fn(ConfigTransferProgress) + Send + Sync>;
+
+/// File bytes of a READ_FILE reply received so far, as (bytes, total), from the
+/// `FILE_DATA:<path>:<size>:` header at the start of `head` and the `received` reply bytes
+/// (two hex digits per byte); None until the header is complete
+pub fn read_progress(head: &str, received: usize) -> Option<(usize, usize)> {
+    let start = head.find("FILE_DATA:")?;
+    let rest = &head[start + "FILE_DATA:".len()..];
+    let path_end = rest.find(':')?;
+    let size_len = rest[path_end + 1..].find(':')?;
+    let total: usize = rest[path_end + 1..path_end + 1 + size_len].parse().ok()?;
+    let header_len = start + "FILE_DATA:".len() + path_end + size_len + 2;
+    Some(((received.saturating_sub(header_len) / 2).min(total), total))
+}
+
+pub fn hex_encode(data: &[u8]) -> String {
+    data.iter().map(|b| format!("{:02X}", b)).collect()
+}
+
+pub fn hex_decode(hex: &str) -> Option<Vec<u8>> {
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| hex.get(i..i + 2).and_then(|b| u8::from_str_radix(b, 16).ok()))
+        .collect()
+}
+
+pub fn begin_command(path: &str, data: &[u8]) -> String {
+    format!("WRITE_FILE_BEGIN {} {} {:08X}", path, data.len(), crc32(data))
+}
+
+pub fn test_config_command(data: &[u8]) -> String {
+    format!("TEST_CONFIG {} {:08X}", data.len(), crc32(data))
+}
+
+pub fn validate_config_command(data: &[u8]) -> String {
+    format!("VALIDATE_CONFIG {} {:08X}", data.len(), crc32(data))
+}
+
+pub fn chunk_command(offset: usize, chunk: &[u8]) -> String {
+    format!("WRITE_FILE_CHUNK {} {} {:08X}", offset, hex_encode(chunk), crc32(chunk))
+}
+
+pub fn delete_command(path: &str) -> String {
+    format!("DELETE_FILE {}", path)
+}
+
+fn is_error_line(line: &str) -> bool {
+    line.trim().to_ascii_uppercase().starts_with("ERROR")
+}
+
+fn error_text(lines: &[String]) -> Option<String> {
+    lines.iter().find(|l| is_error_line(l)).map(|l| l.trim().to_string())
+}
+
+/// Firmware that predates a command answers with an unknown-command error
+pub fn is_unknown_command(lines: &[String]) -> bool {
+    lines.iter().any(|l| {
+        let upper = l.to_ascii_uppercase();
+        upper.contains("UNKNOWN COMMAND") || upper.contains("UNKNOWN_COMMAND")
+    })
+}
+
+/// Error for a file command the firmware rejected with `line`; a missing file is told apart
+pub fn rejected(command: &str, path: &str, line: &str) -> SerialError {
+    if line.to_ascii_lowercase().contains("not found") {
+        SerialError::FileNotFound(path.to_string())
+    } else {
+        SerialError::ProtocolError(format!("{} rejected: {}", command, line.trim()))
+    }
+}
+
+/// A READ_FILE reply is complete on the file data or an error
+pub fn read_reply_complete(lines: &[String]) -> bool {
+    lines.iter().any(|l| l.contains("FILE_DATA:") || is_error_line(l))
+}
+
+pub fn begin_reply_complete(lines: &[String]) -> bool {
+    lines.iter().any(|l| l.starts_with("OK:WRITE_BEGIN") || is_error_line(l))
+}
+
+pub fn chunk_reply_complete(lines: &[String]) -> bool {
+    lines.iter().any(|l| l.starts_with("ACK:") || l.starts_with("NAK:") || is_error_line(l))
+}
+
+pub fn end_reply_complete(lines: &[String]) -> bool {
+    lines.iter().any(|l| l.starts_with("OK:WRITE_COMPLETE") || is_error_line(l))
+}
+
+pub fn delete_reply_complete(lines: &[String]) -> bool {
+    lines.iter().any(|l| l.starts_with("OK") || is_error_line(l))
+}
+
+/// Chunk size to use for this transfer
+pub fn parse_begin_reply(lines: &[String]) -> Result<usize> {
+    parse_begin_reply_with_limit(lines, WRITE_CHUNK_SIZE)
+}
+
+/// Chunk size for this transfer, at most `limit` (framed transfers allow larger chunks)
+pub fn parse_begin_reply_with_limit(lines: &[String], limit: usize) -> Result<usize> {
+    if let Some(err) = error_text(lines) {
+        return Err(SerialError::ProtocolError(format!("WRITE_FILE_BEGIN rejected: {}", err)));
+    }
+    let line = lines.iter().find(|l| l.starts_with("OK:WRITE_BEGIN"))
+        .ok_or_else(|| SerialError::ProtocolError(format!("Unexpected WRITE_FILE_BEGIN reply: {:?}", lines)))?;
+    let device_max = line.trim().strip_prefix("OK:WRITE_BEGIN:").and_then(|v| v.parse::<usize>().ok());
+    Ok(match device_max {
+        Some(max) if max > 0 => max.min(limit),
+        _ => limit,
+    })
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChunkReply {
+    Ack,
+    /// Device saw a corrupted or out-of-order chunk; resend it
+    Nak(String),
+}
+
+pub fn parse_chunk_reply(lines: &[String], offset: usize, len: usize) -> Result<ChunkReply> {
+    if let Some(err) = error_text(lines) {
+        return Err(SerialError::ProtocolError(format!("Chunk at offset {} rejected: {}", offset, err)));
+    }
+    for line in lines {
+        let line = line.trim();
+        if let Some(rest) = line.strip_prefix("ACK:") {
+            let mut parts = rest.split(':');
+            let acked_offset = parts.next().and_then(|v| v.parse::<usize>().ok());
+            let acked_len = parts.next().and_then(|v| v.parse::<usize>().ok());
+            return if acked_offset == Some(offset) && acked_len.map_or(true, |l| l == len) {
+                Ok(ChunkReply::Ack)
+            } else {
+                Ok(ChunkReply::Nak(format!("acknowledged {} instead of offset {}", rest, offset)))
+            };
+        }
+        if let Some(rest) = line.strip_prefix("NAK:") {
+            return Ok(ChunkReply::Nak(rest.to_string()));
+        }
+    }
+    Err(SerialError::ProtocolError(format!("Unexpected chunk reply: {:?}", lines)))
+}
+
+/// Check the device's final size / CRC against what was sent
+pub fn parse_end_reply(lines: &[String], data: &[u8]) -> Result<()> {
+    if let Some(err) = error_text(lines) {
+        return Err(SerialError::ProtocolError(format!("WRITE_FILE_END rejected: {}", err)));
+    }
+    let line = lines.iter().find(|l| l.starts_with("OK:WRITE_COMPLETE"))
+        .ok_or_else(|| SerialError::ProtocolError(format!("Unexpected WRITE_FILE_END reply: {:?}", lines)))?;
+    let mut parts = line.trim().trim_start_matches("OK:WRITE_COMPLETE").trim_start_matches(':').split(':');
+    let size = parts.next().and_then(|v| v.parse::<usize>().ok());
+    let crc = parts.next().and_then(|v| u32::from_str_radix(v, 16).ok());
+    if size.is_some_and(|s| s != data.len()) {
+        return Err(SerialError::ProtocolError(format!("Device stored {} bytes, sent {}", size.unwrap_or(0), data.len())));
+    }
+    let expected = crc32(data);
+    if crc.is_some_and(|c| c != expected) {
+        return Err(SerialError::ProtocolError(format!(
+            "CRC mismatch after write: device {:08X}, expected {:08X}", crc.unwrap_or(0), expected
+        )));
+    }
+    Ok(())
+}
+
+/// Human-readable difference between the bytes written and the bytes read back
+pub fn describe_mismatch(expected: &[u8], actual: &[u8]) -> String {
+    let mut text = format!(
+        "wrote {} bytes (CRC {:08X}), read back {} bytes (CRC {:08X})",
+        expected.len(), crc32(expected), actual.len(), crc32(actual)
+    );
+    if let Some(offset) = expected.iter().zip(actual).position(|(a, b)| a != b) {
+        text.push_str(&format!(
+            ", first difference at offset {}: expected {:02X}, got {:02X}",
+            offset, expected[offset], actual[offset]
+        ));
+    }
+    text
+}
+
+pub fn parse_delete_reply(lines: &[String], path: &str) -> Result<()> {
+    if let Some(err) = error_text(lines) {
+        return Err(SerialError::ProtocolError(format!("Failed to delete {}: {}", path, err)));
+    }
+    Ok(())
+}
+
+/// One finding of a VALIDATE_CONFIG upload
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationFinding {
+    /// False for warnings
+    pub error: bool,
+    /// Firmware code, e.g. `PIN_CONFLICT`
+    pub code: String,
+    pub message: String,
+}
+
+/// Findings reported before the completion line of a VALIDATE_CONFIG upload
+pub fn parse_validation_findings(lines: &[String]) -> Vec<ValidationFinding> {
+    lines.iter()
+        .filter_map(|line| line.trim().strip_prefix("VALIDATE:"))
+        .filter_map(|rest| {
+            let mut parts = rest.splitn(3, ':');
+            let error = match parts.next()?.trim().to_ascii_uppercase().as_str() {
+                "ERROR" => true,
+                "WARNING" | "WARN" => false,
+                _ => return None,
+            };
+            let code = parts.next()?.trim().to_ascii_uppercase();
+            let message = parts.next().map(|m| m.trim().to_string()).filter(|m| !m.is_empty()).unwrap_or_else(|| code.clone());
+            Some(ValidationFinding { error, code, message })
+        })
+        .collect()
+}
+
+pub fn parse_revert_reply(lines: &[String]) -> Result<()> {
+    if let Some(err) = error_text(lines) {
+        return Err(SerialError::ProtocolError(format!("TEST_CONFIG_REVERT rejected: {}", err)));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(items: &[&str]) -> Vec<String> {
+        items.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn builds_commands_with_crc() {
+        assert_eq!(begin_command("/a.bin", b"123456789"), "WRITE_FILE_BEGIN /a.bin 9 CBF43926");
+        assert_eq!(test_config_command(b"123456789"), "TEST_CONFIG 9 CBF43926");
+        assert_eq!(chunk_command(64, &[0xDE, 0xAD]), format!("WRITE_FILE_CHUNK 64 DEAD {:08X}", crc32(&[0xDE, 0xAD])));
+        assert_eq!(hex_decode("DEAD"), Some(vec![0xDE, 0xAD]));
+        assert_eq!(hex_decode("DEA"), None);
+    }
+
+    #[test]
+    fn parses_replies() {
+        assert_eq!(parse_begin_reply(&lines(&["OK:WRITE_BEGIN:32"])).unwrap(), 32);
+        assert_eq!(parse_begin_reply(&lines(&["OK:WRITE_BEGIN:4096"])).unwrap(), WRITE_CHUNK_SIZE);
+        assert!(is_unknown_command(&lines(&["ERROR:Unknown command: WRITE_FILE_BEGIN"])));
+
+        assert_eq!(parse_chunk_reply(&lines(&["ACK:64:16"]), 64, 16).unwrap(), ChunkReply::Ack);
+        assert!(matches!(parse_chunk_reply(&lines(&["NAK:64:CRC"]), 64, 16).unwrap(), ChunkReply::Nak(_)));
+        assert!(matches!(parse_chunk_reply(&lines(&["ACK:0:16"]), 64, 16).unwrap(), ChunkReply::Nak(_)));
+
+        let data = b"hello";
+        let ok = format!("OK:WRITE_COMPLETE:5:{:08X}", crc32(data));
+        assert!(parse_end_reply(&lines(&[&ok]), data).is_ok());
+        assert!(parse_end_reply(&lines(&["OK:WRITE_COMPLETE:5:00000000"]), data).is_err());
+
+        assert!(describe_mismatch(b"abc", b"abd").ends_with("offset 2: expected 63, got 64"));
+
+        assert_eq!(read_progress("FILE_DATA:/config.bin:6", 30), None);
+        assert_eq!(read_progress("GPIO_STATES:0x1:5\r\nFILE_DATA:/config.bin:606:0A0B", 19 + 26 + 4), Some((2, 606)));
+        assert_eq!(read_progress("FILE_DATA:/config.bin:606:", 5000), Some((606, 606)));
+
+        let findings = parse_validation_findings(&lines(&[
+            "VALIDATE:ERROR:PIN_CONFLICT:GPIO 4: button and matrix row",
+            "VALIDATE:warning:UNMAPPED_BUTTON_PIN",
+            "VALIDATE:INFO:X:ignored",
+            &ok,
+        ]));
+        assert_eq!(findings.len(), 2);
+        assert_eq!(findings[0], ValidationFinding { error: true, code: "PIN_CONFLICT".into(), message: "GPIO 4: button and matrix row".into() });
+        assert_eq!((findings[1].error, findings[1].message.as_str()), (false, "UNMAPPED_BUTTON_PIN"));
+    }
+}
@@ -0,0 +1,271 @@
+//! Binary frames for file transfers on firmware reporting the `FRAMED_FILES` capability.
+//!
+//! A frame is COBS encoded and delimited by `0x00` on both sides. COBS output never contains
+//! `0x00` and the text protocol never sends it, so frames and text lines share the link:
+//!
+//! ```text
+//! 00 | COBS( len:u16 LE | payload | crc32(payload):u32 LE ) | 00
+//! ```
+//!
+//! File frames carry `offset:u32 LE | data`.
+//!
+//! ```text
+//! READ_FILE_FRAMED <path>   -> FRAMED_FILE:<path>:<size>:<crc32>, one frame per chunk, END_FRAMED_FILE
+//! ```
+//!
+//! Uploads keep the `WRITE_FILE_BEGIN` / `WRITE_FILE_END` handshake from [`file_transfer`] but
+//! send each chunk as a bare file frame, acknowledged with `ACK`/`NAK` like `WRITE_FILE_CHUNK`.
+//!
+//! [`file_transfer`]: super::file_transfer
+use super::{Result, SerialError};
+use crate::util::crc::crc32;
+
+pub const FRAME_DELIMITER: u8 = 0x00;
+/// Encoded frames longer than this are discarded as line noise
+pub const MAX_FRAME_BYTES: usize = 2048;
+/// Data bytes per file frame sent by the app
+pub const FRAME_CHUNK_SIZE: usize = 512;
+
+pub fn cobs_encode(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len() + data.len() / 254 + 2);
+    let mut code_index = 0;
+    out.push(0);
+    let mut code = 1u8;
+    for &byte in data {
+        if byte == 0 {
+            out[code_index] = code;
+            code_index = out.len();
+            out.push(0);
+            code = 1;
+            continue;
+        }
+        out.push(byte);
+        code += 1;
+        if code == 0xFF {
+            out[code_index] = code;
+            code_index = out.len();
+            out.push(0);
+            code = 1;
+        }
+    }
+    out[code_index] = code;
+    out
+}
+
+/// None for input that is not valid COBS
+pub fn cobs_decode(data: &[u8]) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(data.len());
+    let mut i = 0;
+    while i < data.len() {
+        let code = data[i] as usize;
+        let end = i + code;
+        if code == 0 || end > data.len() || data[i + 1..end].contains(&0) {
+            return None;
+        }
+        out.extend_from_slice(&data[i + 1..end]);
+        i = end;
+        if code < 0xFF && i < data.len() {
+            out.push(0);
+        }
+    }
+    Some(out)
+}
+
+/// Delimited, encoded frame ready to be written
+pub fn encode_frame(payload: &[u8]) -> Vec<u8> {
+    let mut body = Vec::with_capacity(payload.len() + 6);
+    body.extend_from_slice(&(payload.len() as u16).to_le_bytes());
+    body.extend_from_slice(payload);
+    body.extend_from_slice(&crc32(payload).to_le_bytes());
+    let mut frame = vec![FRAME_DELIMITER];
+    frame.extend(cobs_encode(&body));
+    frame.push(FRAME_DELIMITER);
+    frame
+}
+
+/// Payload of an encoded frame (without delimiters)
+pub fn decode_frame(encoded: &[u8]) -> std::result::Result<Vec<u8>, String> {
+    let body = cobs_decode(encoded).ok_or("invalid COBS encoding")?;
+    if body.len() < 6 {
+        return Err(format!("frame too short ({} bytes)", body.len()));
+    }
+    let len = u16::from_le_bytes([body[0], body[1]]) as usize;
+    if body.len() != len + 6 {
+        return Err(format!("frame length {} does not match header {}", body.len() - 6, len));
+    }
+    let payload = &body[2..2 + len];
+    let crc = u32::from_le_bytes([body[2 + len], body[3 + len], body[4 + len], body[5 + len]]);
+    if crc != crc32(payload) {
+        return Err("frame CRC mismatch".to_string());
+    }
+    Ok(payload.to_vec())
+}
+
+pub fn file_frame(offset: usize, data: &[u8]) -> Vec<u8> {
+    let mut payload = (offset as u32).to_le_bytes().to_vec();
+    payload.extend_from_slice(data);
+    encode_frame(&payload)
+}
+
+/// Offset and data of a file frame payload
+pub fn parse_file_frame(payload: &[u8]) -> Option<(usize, &[u8])> {
+    let offset = u32::from_le_bytes(payload.get(..4)?.try_into().ok()?) as usize;
+    Some((offset, &payload[4..]))
+}
+
+/// Separates frames from text in the received byte stream
+#[derive(Debug, Default)]
+pub struct FrameSplitter {
+    in_frame: bool,
+    frame: Vec<u8>,
+}
+
+/// Bytes received in one read, split by [`FrameSplitter::push`]
+#[derive(Debug, Default)]
+pub struct SplitBytes {
+    pub text: Vec<u8>,
+    /// Decoded payloads, or why a frame was dropped
+    pub frames: Vec<std::result::Result<Vec<u8>, String>>,
+}
+
+impl FrameSplitter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, bytes: &[u8]) -> SplitBytes {
+        let mut split = SplitBytes::default();
+        for &byte in bytes {
+            match (self.in_frame, byte == FRAME_DELIMITER) {
+                (false, false) => split.text.push(byte),
+                (false, true) => self.in_frame = true,
+                // Back-to-back delimiters: the closing one of a frame doubles as the next opener
+                (true, true) if self.frame.is_empty() => {}
+                (true, true) => {
+                    split.frames.push(decode_frame(&self.frame));
+                    self.frame.clear();
+                    self.in_frame = false;
+                }
+                (true, false) => {
+                    self.frame.push(byte);
+                    if self.frame.len() > MAX_FRAME_BYTES {
+                        split.frames.push(Err(format!("frame exceeded {} bytes", MAX_FRAME_BYTES)));
+                        self.frame.clear();
+                        self.in_frame = false;
+                    }
+                }
+            }
+        }
+        split
+    }
+}
+
+pub fn read_command(path: &str) -> String {
+    format!("READ_FILE_FRAMED {}", path)
+}
+
+pub fn read_reply_complete(lines: &[String]) -> bool {
+    lines.iter().any(|l| l.starts_with("END_FRAMED_FILE") || l.trim().to_ascii_uppercase().starts_with("ERROR"))
+}
+
+/// File bytes of a framed read received so far, as (bytes, total), estimated from the
+/// `FRAMED_FILE:<path>:<size>:<crc32>` header line at the start of `head` and the `received`
+/// reply bytes; None until the header line is complete
+pub fn read_progress(head: &str, received: usize) -> Option<(usize, usize)> {
+    let start = head.find("FRAMED_FILE:")?;
+    let header_len = start + head[start..].find('\n')? + 1;
+    let mut fields = head[start..header_len].trim().rsplitn(3, ':');
+    fields.next()?;
+    let total: usize = fields.next()?.parse().ok()?;
+    // Each frame adds delimiters, COBS overhead, length, offset and CRC to its data
+    let per_frame = FRAME_CHUNK_SIZE + FRAME_CHUNK_SIZE / 254 + 14;
+    let data = received.saturating_sub(header_len) * FRAME_CHUNK_SIZE / per_frame;
+    Some((data.min(total), total))
+}
+
+/// Reassemble a framed read and check it against the size and CRC announced in its header
+pub fn assemble_read(path: &str, lines: &[String], frames: &[Vec<u8>]) -> Result<Vec<u8>> {
+    if let Some(err) = lines.iter().find(|l| l.trim().to_ascii_uppercase().starts_with("ERROR")) {
+        return Err(super::file_transfer::rejected("READ_FILE_FRAMED", path, err));
+    }
+    let header = lines.iter().find_map(|l| l.trim().strip_prefix("FRAMED_FILE:"))
+        .ok_or_else(|| SerialError::ProtocolError(format!("Missing FRAMED_FILE header: {:?}", lines)))?;
+    let mut fields = header.rsplitn(3, ':');
+    let crc = fields.next().and_then(|v| u32::from_str_radix(v, 16).ok());
+    let size = fields.next().and_then(|v| v.parse::<usize>().ok());
+    let (Some(crc), Some(size)) = (crc, size) else {
+        return Err(SerialError::ProtocolError(format!("Invalid FRAMED_FILE header: {}", header)));
+    };
+
+    let mut data = vec![0u8; size];
+    let mut received = 0;
+    for payload in frames {
+        let (offset, chunk) = parse_file_frame(payload)
+            .ok_or_else(|| SerialError::ProtocolError("File frame without offset".to_string()))?;
+        let end = offset + chunk.len();
+        if end > size {
+            return Err(SerialError::ProtocolError(format!("File frame at {} runs past the {} byte file", offset, size)));
+        }
+        data[offset..end].copy_from_slice(chunk);
+        received += chunk.len();
+    }
+    if received != size {
+        return Err(SerialError::ProtocolError(format!("Received {} of {} bytes in frames", received, size)));
+    }
+    if crc32(&data) != crc {
+        return Err(SerialError::ProtocolError(format!("File CRC mismatch: expected {:08X}, got {:08X}", crc, crc32(&data))));
+    }
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cobs_round_trips_zeros_and_long_runs() {
+        for data in [vec![], vec![0], vec![0, 0, 1], vec![0x11, 0x22, 0x00, 0x33], (1..=255u8).cycle().take(600).collect::<Vec<_>>()] {
+            let encoded = cobs_encode(&data);
+            assert!(!encoded.contains(&0), "{:?}", data);
+            assert_eq!(cobs_decode(&encoded), Some(data));
+        }
+        assert_eq!(cobs_encode(&[0x11, 0x22, 0x00, 0x33]), vec![0x03, 0x11, 0x22, 0x02, 0x33]);
+        assert_eq!(cobs_decode(&[0x05, 0x11]), None);
+    }
+
+    #[test]
+    fn splitter_separates_frames_from_text() {
+        let mut splitter = FrameSplitter::new();
+        let mut bytes = b"FRAMED_FILE:/a:3:00000000\r\n".to_vec();
+        let frame = file_frame(0, &[0, b'\n', 7]);
+        bytes.extend_from_slice(&frame);
+        bytes.extend_from_slice(b"END_FRAMED_FILE\r\n");
+
+        // Split across reads in the middle of the frame
+        let (first, second) = bytes.split_at(30);
+        let mut text = splitter.push(first).text;
+        let split = splitter.push(second);
+        text.extend(split.text);
+        assert_eq!(String::from_utf8(text).unwrap(), "FRAMED_FILE:/a:3:00000000\r\nEND_FRAMED_FILE\r\n");
+        assert_eq!(read_progress("FRAMED_FILE:/a:3:00000000", 25), None);
+        assert_eq!(read_progress("FRAMED_FILE:/a:3:00000000\r\n", 27 + 20), Some((3, 3)));
+        assert_eq!(split.frames.len(), 1);
+        let payload = split.frames[0].as_ref().unwrap();
+        assert_eq!(parse_file_frame(payload), Some((0, &[0, b'\n', 7][..])));
+
+        let mut corrupt = file_frame(0, &[1, 2, 3]);
+        corrupt[3] ^= 0x40;
+        assert!(splitter.push(&corrupt).frames[0].is_err());
+    }
+
+    #[test]
+    fn assembles_out_of_order_frames_and_checks_crc() {
+        let data: Vec<u8> = (0..10).collect();
+        let header = vec![format!("FRAMED_FILE:/config.bin:10:{:08X}", crc32(&data)), "END_FRAMED_FILE".to_string()];
+        let frames = vec![[&6u32.to_le_bytes()[..], &data[6..]].concat(), [&0u32.to_le_bytes()[..], &data[..6]].concat()];
+        assert_eq!(assemble_read("/config.bin", &header, &frames).unwrap(), data);
+        assert!(assemble_read("/config.bin", &header, &frames[..1]).is_err());
+        assert!(matches!(assemble_read("/config.bin", &["ERROR:File not found".to_string()], &[]), Err(SerialError::FileNotFound(p)) if p == "/config.bin"));
+        assert!(matches!(assemble_read("/config.bin", &["ERROR:Storage busy".to_string()], &[]), Err(SerialError::ProtocolError(_))));
+    }
+}
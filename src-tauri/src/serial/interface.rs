@@ -4,7 +4,8 @@ use serialport::SerialPort;
 use tokio::time::timeout;
 // Removed legacy channel imports
 
-use super::{Result, SerialError, SerialDeviceInfo};
+use super::{ConnectionSettings, Result, SerialError, SerialDeviceInfo};
+use super::unified::reader::is_monitor_line;
 
 // JoyCore device identification constants
 pub const DEVICE_SIGNATURE: &str = "JOYCORE-FW";
@@ -14,6 +15,10 @@ pub const IDENTIFY_RESPONSE_PREFIX: &str = "JOYCORE_ID";
 pub const BAUD_RATE: u32 = 115200;
 pub const IDENTIFY_TIMEOUT_MS: u64 = 500;
 pub const PORT_OPEN_DELAY_MS: u64 = 100;
+/// An IDENTIFY exchange taking longer than this multiple of the timeout counts as a hang
+pub const IDENTIFY_HANG_FACTOR: u64 = 3;
+/// Overall budget for a concurrent discovery pass; enough for one hung probe to be detected
+pub const DISCOVERY_DEADLINE_MS: u64 = 2000;
 
 // Raw state monitoring constants
 pub const MONITOR_TIMEOUT_MS: u64 = 5000;
@@ -22,6 +27,54 @@ pub const MONITOR_TIMEOUT_MS: u64 = 5000;
 // Message routing for unified reader
 // (Future enhancement: could use enum for more sophisticated routing)
 
+/// Result of a discovery pass
+#[derive(Debug, Default)]
+pub struct DiscoveryReport {
+    pub devices: Vec<SerialDeviceInfo>,
+    /// Ports that answered (or stayed cleanly silent) without errors
+    pub healthy_ports: Vec<String>,
+    /// (port, error) for ports whose IDENTIFY exchange failed or hung
+    pub failures: Vec<(String, String)>,
+    /// Ports that are present but were not probed
+    pub skipped_ports: Vec<String>,
+    /// Ports whose probe did not finish before the deadline of a concurrent pass
+    pub timed_out_ports: Vec<String>,
+}
+
+impl DiscoveryReport {
+    /// Record the IDENTIFY outcome for `port_info`
+    fn record(&mut self, port_info: serialport::SerialPortInfo, result: Result<Option<SerialDeviceInfo>>) {
+        match result {
+            Ok(Some(mut device_info)) => {
+                // Enhance device info with USB details if available
+                if let serialport::SerialPortType::UsbPort(usb_info) = &port_info.port_type {
+                    device_info.serial_number = usb_info.serial_number.clone();
+                    if device_info.manufacturer.is_none() {
+                        device_info.manufacturer = usb_info.manufacturer.clone();
+                    }
+                    if device_info.product.is_none() {
+                        device_info.product = usb_info.product.clone();
+                    }
+                    device_info.vid = usb_info.vid;
+                    device_info.pid = usb_info.pid;
+                }
+                self.healthy_ports.push(port_info.port_name);
+                self.devices.push(device_info);
+            }
+            Ok(None) => {
+                // Not a JoyCore device, continue
+                log::debug!("Port {} is not a JoyCore device", port_info.port_name);
+                self.healthy_ports.push(port_info.port_name);
+            }
+            Err(e) => {
+                // Probe errored or hung mid-exchange
+                log::debug!("Failed to identify port {}: {}", port_info.port_name, e);
+                self.failures.push((port_info.port_name, e.to_string()));
+            }
+        }
+    }
+}
+
 pub struct SerialInterface {
     port: Option<Box<dyn SerialPort>>,
     device_info: Option<SerialDeviceInfo>,
@@ -37,56 +90,104 @@ impl SerialInterface {
         }
     }
 
+    /// Wrap an already open port (e.g. a [`super::mock::MockSerialPort`])
+    pub fn from_port(port: Box<dyn SerialPort>, device_info: SerialDeviceInfo) -> Self {
+        Self {
+            port: Some(port),
+            device_info: Some(device_info),
+        }
+    }
+
+    /// Open a port by name; the simulated device port is served in-process
+    fn open_port(port_name: &str, baud_rate: u32, timeout_ms: u64) -> std::result::Result<Box<dyn SerialPort>, serialport::Error> {
+        if super::mock::is_mock_port(port_name) {
+            return Ok(Box::new(super::mock::MockSerialPort::shared()));
+        }
+        serialport::new(port_name, baud_rate)
+            .timeout(Duration::from_millis(timeout_ms))
+            .open()
+    }
+
     /// Discover available JoyCore devices using IDENTIFY command
     pub fn discover_devices() -> Result<Vec<SerialDeviceInfo>> {
-        let ports = serialport::available_ports()?;
-        let mut devices = Vec::new();
+        Ok(Self::discover_devices_filtered(|_| false)?.devices)
+    }
+
+    /// Discover JoyCore devices, skipping ports for which `skip` returns true.
+    /// Ports whose IDENTIFY probe errored or hung are reported in `failures`.
+    pub fn discover_devices_filtered<F: Fn(&serialport::SerialPortInfo) -> bool>(skip: F) -> Result<DiscoveryReport> {
+        Self::discover_devices_with(skip, Self::identify_device)
+    }
 
-        for port_info in ports {
+    /// Like [`Self::discover_devices_filtered`], with `identify` standing in for the IDENTIFY
+    /// probe of a port (e.g. to answer from earlier results)
+    pub fn discover_devices_with<F, I>(skip: F, mut identify: I) -> Result<DiscoveryReport>
+    where
+        F: Fn(&serialport::SerialPortInfo) -> bool,
+        I: FnMut(&str) -> Result<Option<SerialDeviceInfo>>,
+    {
+        let mut report = DiscoveryReport::default();
+        for port_info in Self::enumerate_ports()? {
+            if skip(&port_info) {
+                log::debug!("Skipping port {} during discovery", port_info.port_name);
+                report.skipped_ports.push(port_info.port_name);
+                continue;
+            }
             // Try to identify each port as a potential JoyCore device
-            match Self::identify_device(&port_info.port_name) {
-                Ok(Some(mut device_info)) => {
-                    // Enhance device info with USB details if available
-                    if let serialport::SerialPortType::UsbPort(usb_info) = &port_info.port_type {
-                        device_info.serial_number = usb_info.serial_number.clone();
-                        if device_info.manufacturer.is_none() {
-                            device_info.manufacturer = usb_info.manufacturer.clone();
-                        }
-                        if device_info.product.is_none() {
-                            device_info.product = usb_info.product.clone();
-                        }
-                        device_info.vid = usb_info.vid;
-                        device_info.pid = usb_info.pid;
-                    }
-                    
-                    // log::info!("Found JoyCore device on port: {} (S/N: {:?})", 
-                    //           port_info.port_name, device_info.serial_number);
-                    devices.push(device_info);
-                }
-                Ok(None) => {
-                    // Not a JoyCore device, continue
-                    log::debug!("Port {} is not a JoyCore device", port_info.port_name);
-                }
-                Err(e) => {
-                    // Connection failed, port might be in use or not available
-                    log::debug!("Failed to identify port {}: {}", port_info.port_name, e);
-                }
+            let result = identify(&port_info.port_name);
+            report.record(port_info, result);
+        }
+
+        Ok(report)
+    }
+
+    /// Like [`Self::discover_devices_with`], probing all ports at once on blocking tasks. Probes
+    /// still running after `deadline` are reported in `timed_out_ports`; the devices found by
+    /// then are returned.
+    pub async fn discover_devices_concurrent<F, I>(skip: F, identify: I, deadline: Duration) -> Result<DiscoveryReport>
+    where
+        F: Fn(&serialport::SerialPortInfo) -> bool,
+        I: Fn(&str) -> Result<Option<SerialDeviceInfo>> + Send + Sync + 'static,
+    {
+        let mut report = DiscoveryReport::default();
+        let mut to_probe = Vec::new();
+        for port_info in Self::enumerate_ports()? {
+            if skip(&port_info) {
+                log::debug!("Skipping port {} during discovery", port_info.port_name);
+                report.skipped_ports.push(port_info.port_name);
+            } else {
+                to_probe.push(port_info);
             }
         }
 
-        Ok(devices)
+        let results = super::probe::probe_ports(to_probe, identify, deadline).await;
+        for (port_info, result) in results.finished {
+            report.record(port_info, result);
+        }
+        report.timed_out_ports = results.timed_out;
+        Ok(report)
+    }
+
+    /// Serial ports present on the system, plus the simulated device port when enabled
+    fn enumerate_ports() -> Result<Vec<serialport::SerialPortInfo>> {
+        let mut ports = serialport::available_ports()?;
+        if super::mock::mock_device_enabled() {
+            ports.push(serialport::SerialPortInfo {
+                port_name: super::mock::MOCK_PORT_NAME.to_string(),
+                port_type: serialport::SerialPortType::Unknown,
+            });
+        }
+        Ok(ports)
     }
 
     /// Connect to a specific device
-    pub fn connect(&mut self, port_name: &str) -> Result<()> {
+    pub fn connect(&mut self, port_name: &str, settings: &ConnectionSettings) -> Result<()> {
         // Open the port for persistent connection
-        let port = serialport::new(port_name, BAUD_RATE)
-            .timeout(Duration::from_millis(500))
-            .open()
+        let port = Self::open_port(port_name, settings.baud_rate(), 500)
             .map_err(|e| SerialError::ConnectionFailed(e.to_string()))?;
 
         // Re-identify device to get fresh firmware version
-        let device_info = match Self::identify_device(port_name)? {
+        let device_info = match Self::identify_device_with(port_name, settings).ok().flatten() {
             Some(info) => info,
             None => {
                 // Fallback to basic device info if identification fails
@@ -99,6 +200,7 @@ impl SerialInterface {
                     product: Some("HOTAS Controller".to_string()),
                     firmware_version: Some("JoyCore-FW".to_string()),
                     device_signature: Some(DEVICE_SIGNATURE.to_string()),
+                    hardware_revision: None,
                 }
             }
         };
@@ -113,17 +215,15 @@ impl SerialInterface {
     }
 
     /// Connect to a specific device with known device info
-    pub fn connect_with_info(&mut self, device_info: SerialDeviceInfo) -> Result<()> {
-        let port = serialport::new(&device_info.port_name, BAUD_RATE)
-            .timeout(Duration::from_millis(500))
-            .open()
+    pub fn connect_with_info(&mut self, device_info: SerialDeviceInfo, settings: &ConnectionSettings) -> Result<()> {
+        let port = Self::open_port(&device_info.port_name, settings.baud_rate(), 500)
             .map_err(|e| SerialError::ConnectionFailed(e.to_string()))?;
 
         self.port = Some(port);
         self.device_info = Some(device_info.clone());
     // Unified reader is started externally by UnifiedSerialBuilder
         
-        log::info!("Connected to JoyCore device on {}", device_info.port_name);
+        log::info!("Connected to JoyCore device on {} at {} baud", device_info.port_name, settings.baud_rate());
         Ok(())
     }
 
@@ -206,103 +306,21 @@ impl SerialInterface {
             .map_err(|_| SerialError::Timeout)?
     }
 
-    /// Send a command and wait for response with message routing
-    pub async fn send_command(&mut self, command: &str) -> Result<String> {
-        log::debug!("Sending command: {}", command);
-        let command_with_newline = format!("{}\n", command);
-        self.send_data(command_with_newline.as_bytes()).await?;
-
-    // Use larger buffer and line-by-line reading
-        let mut response_lines = Vec::new();
-        let mut accumulated_data = Vec::new();
-        let start_time = std::time::Instant::now();
-        let timeout_duration = std::time::Duration::from_secs_f32(0.5);
-        
-        // Give device a moment to start responding - reduced delay
-        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
-        
-        while start_time.elapsed() < timeout_duration {
-            let mut buffer = [0u8; 4096]; // Increased buffer size
-            
-            match timeout(std::time::Duration::from_millis(100), self.read_data(&mut buffer, 100)).await {
-                Ok(Ok(bytes_read)) => {
-                    if bytes_read > 0 {
-                        accumulated_data.extend_from_slice(&buffer[..bytes_read]);
-                        
-                        // Process complete lines with routing
-                        while let Some(line_end) = accumulated_data.iter().position(|&b| b == b'\n' || b == b'\r') {
-                            let line_bytes = accumulated_data.drain(..=line_end).collect::<Vec<u8>>();
-                            let line = String::from_utf8_lossy(&line_bytes).trim().to_string();
-                            
-                            if !line.is_empty() {
-                                log::debug!("Received line: {}", line);
-                                
-                                // (legacy routing removed)
-                                // Always add to response for command processing
-                                response_lines.push(line.clone());
-                                
-                                // Check for termination conditions like Python script
-                                if line == "END_FILES" || line.starts_with("ERROR:") || line.starts_with("FILE_DATA:") {
-                                    log::debug!("Found termination condition: {}", line);
-                                    // For FILE_DATA, this should be the complete response
-                                    break;
-                                }
-                            }
-                        }
-                    } else {
-                        // No more data, wait a bit
-                        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
-                    }
-                }
-                Ok(Err(_)) => {
-                    // Serial read error, wait a bit
-                    tokio::time::sleep(std::time::Duration::from_millis(5)).await;
-                }
-                Err(_) => {
-                    // Timeout on this read, continue
-                    tokio::time::sleep(std::time::Duration::from_millis(5)).await;
-                }
-            }
-        }
-        
-        // Process any remaining data as a final line
-        if !accumulated_data.is_empty() {
-            let line = String::from_utf8_lossy(&accumulated_data).trim().to_string();
-            if !line.is_empty() {
-                // (legacy routing removed)
-                response_lines.push(line);
-            }
-        }
-        
-        let full_response = response_lines.join("\n");
-        log::debug!("Complete response ({} lines): {}", response_lines.len(), full_response);
-        Ok(full_response)
-    }
-
-    /// Read a line from the device with timeout (for streaming)
-    pub async fn read_line_timeout(&mut self, timeout_ms: u64) -> Result<String> {
-        let mut buffer = [0u8; 1024];
-        let bytes_read = self.read_data(&mut buffer, timeout_ms).await?;
-        
-        if bytes_read > 0 {
-            Ok(String::from_utf8_lossy(&buffer[..bytes_read]).to_string())
-        } else {
-            Ok(String::new())
-        }
-    }
-
-    // Legacy monitoring & routing helpers removed (handled entirely by unified reader)
+    // Command/response exchanges and monitoring are handled entirely by the unified reader
 
     /// Identify a device on the given port using IDENTIFY command
     /// Returns Ok(Some(device_info)) if it's a JoyCore device
     /// Returns Ok(None) if it's not a JoyCore device
     /// Returns Err if connection or communication failed
-    fn identify_device(port_name: &str) -> Result<Option<SerialDeviceInfo>> {
+    pub fn identify_device(port_name: &str) -> Result<Option<SerialDeviceInfo>> {
+        Self::identify_device_with(port_name, &ConnectionSettings::default())
+    }
+
+    /// [`Self::identify_device`] with a device's baud rate and identify timeout
+    pub fn identify_device_with(port_name: &str, settings: &ConnectionSettings) -> Result<Option<SerialDeviceInfo>> {
+        let identify_timeout_ms = settings.identify_timeout_ms();
         // Try to open the port
-        let mut port = match serialport::new(port_name, BAUD_RATE)
-            .timeout(Duration::from_millis(IDENTIFY_TIMEOUT_MS))
-            .open()
-        {
+        let mut port = match Self::open_port(port_name, settings.baud_rate(), identify_timeout_ms) {
             Ok(port) => port,
             Err(_) => return Ok(None), // Port unavailable, not an error for discovery
         };
@@ -310,51 +328,45 @@ impl SerialInterface {
         // Give the device a moment after opening
         std::thread::sleep(Duration::from_millis(PORT_OPEN_DELAY_MS));
 
-        // Send IDENTIFY command
+        // Send IDENTIFY command. Write/read errors and stalls are reported as errors so
+        // discovery can quarantine ports that misbehave when probed.
+        let exchange_start = std::time::Instant::now();
         let identify_command = format!("{}\n", IDENTIFY_COMMAND);
-        if port.write_all(identify_command.as_bytes()).is_err() {
-            return Ok(None);
-        }
-        
-        if port.flush().is_err() {
-            return Ok(None);
-        }
+        port.write_all(identify_command.as_bytes())?;
+        port.flush()?;
 
-        // Wait for response
+        // Wait for the first response line. A device still streaming raw monitor lines (left on
+        // by a previous session) interleaves them with the reply, so those are skipped.
         let mut buffer = [0u8; 256];
-        let mut total_read = 0;
+        let mut received = String::new();
+        let mut response_line = None;
         let start_time = std::time::Instant::now();
-        
-        while total_read == 0 && start_time.elapsed().as_millis() < IDENTIFY_TIMEOUT_MS as u128 {
-            match port.bytes_to_read() {
-                Ok(0) => {
+
+        while response_line.is_none() && start_time.elapsed().as_millis() < identify_timeout_ms as u128 {
+            match port.bytes_to_read()? {
+                0 => {
                     std::thread::sleep(Duration::from_millis(10));
                     continue;
                 }
-                Ok(_) => {
-                    match port.read(&mut buffer[total_read..]) {
-                        Ok(bytes_read) => {
-                            total_read += bytes_read;
-                            if total_read > 0 {
-                                break;
-                            }
-                        }
-                        Err(_) => break,
-                    }
+                _ => {
+                    let read = port.read(&mut buffer)?;
+                    received.push_str(&String::from_utf8_lossy(&buffer[..read]));
+                    response_line = take_response_line(&mut received);
                 }
-                Err(_) => break,
             }
         }
 
-        if total_read == 0 {
-            return Ok(None); // No response, not a JoyCore device
+        if exchange_start.elapsed() > Duration::from_millis(identify_timeout_ms * IDENTIFY_HANG_FACTOR) {
+            return Err(SerialError::Timeout);
         }
 
-        let response_string = String::from_utf8_lossy(&buffer[..total_read]);
-        let response = response_string.trim();
+        // An unterminated reply is used as is
+        let Some(response) = response_line.or_else(|| last_partial_line(&received)) else {
+            return Ok(None); // No response, not a JoyCore device
+        };
         log::debug!("IDENTIFY response from {}: {}", port_name, response);
 
-        // Parse the response: JOYCORE_ID:JOYCORE-FW:4A4F5943:<FIRMWARE_VERSION>
+        // Parse the response: JOYCORE_ID:JOYCORE-FW:4A4F5943:<FIRMWARE_VERSION>[:<HW_REVISION>]
         if let Some(device_info) = Self::parse_identify_response(port_name, response) {
             Ok(Some(device_info))
         } else {
@@ -384,6 +396,7 @@ impl SerialInterface {
                         product: Some("HOTAS Controller".to_string()),
                         firmware_version: Some(firmware_version),
                         device_signature: Some(DEVICE_SIGNATURE.to_string()),
+                        hardware_revision: parts.get(4).and_then(|rev| crate::update::hardware::normalize_revision(rev)),
                     });
                 }
             }
@@ -394,8 +407,47 @@ impl SerialInterface {
 
 }
 
+/// Remove complete lines from `received` up to the first one that is neither empty nor a raw
+/// monitor line, and return it trimmed
+fn take_response_line(received: &mut String) -> Option<String> {
+    while let Some(end) = received.find('\n') {
+        let line: String = received.drain(..=end).collect();
+        let line = line.trim();
+        if !line.is_empty() && !is_monitor_line(line) {
+            return Some(line.to_string());
+        }
+    }
+    None
+}
+
+/// Text after the last complete line, unless it is empty or the start of a monitor line
+fn last_partial_line(received: &str) -> Option<String> {
+    let partial = received.trim();
+    (!partial.is_empty() && !is_monitor_line(partial)).then(|| partial.to_string())
+}
+
 impl Default for SerialInterface {
     fn default() -> Self {
         Self::new()
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identify_reply_skips_interleaved_monitor_lines() {
+        let mut received = "GPIO_STATES:0x0000FFFF:1200\r\nMATRIX_STATE:0:1:1:1250\nJOYCORE_ID:JOYCORE-FW:4A4F5943:1.2.0\r\nSHIFT_REG:0:0xFF:13".to_string();
+        let line = take_response_line(&mut received).unwrap();
+        assert_eq!(line, "JOYCORE_ID:JOYCORE-FW:4A4F5943:1.2.0");
+        let info = SerialInterface::parse_identify_response("COM3", &line).unwrap();
+        assert_eq!(info.firmware_version.as_deref(), Some("1.2.0"));
+        assert_eq!(received, "SHIFT_REG:0:0xFF:13");
+        assert_eq!(last_partial_line(&received), None);
+
+        let mut partial = "GPIO_STATES:0x1:5\nJOYCORE_ID:JOYCORE-FW".to_string();
+        assert_eq!(take_response_line(&mut partial), None);
+        assert_eq!(last_partial_line(&partial).as_deref(), Some("JOYCORE_ID:JOYCORE-FW"));
+    }
+}
@@ -0,0 +1,549 @@
+//! Simulated JoyCore device for development and tests.
+//!
+//! [`MockSerialPort`] implements `serialport::SerialPort`, so the regular [`SerialInterface`],
+//! unified reader and raw state monitor run unchanged on top of it. Set `JOYCORE_MOCK_DEVICE=1`
+//! to make discovery report a simulated device on [`MOCK_PORT_NAME`].
+//!
+//! [`SerialInterface`]: super::SerialInterface
+
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, Read, Write};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use serialport::{ClearBuffer, DataBits, FlowControl, Parity, SerialPort, StopBits};
+
+use super::framing::{self, FrameSplitter};
+use super::interface::{BAUD_RATE, DEVICE_SIGNATURE, IDENTIFY_RESPONSE_PREFIX, MAGIC_NUMBER};
+
+/// Environment flag enabling the simulated device ("1" or "true")
+pub const MOCK_DEVICE_ENV: &str = "JOYCORE_MOCK_DEVICE";
+pub const MOCK_PORT_NAME: &str = "MOCK:JOYCORE";
+pub const MOCK_FIRMWARE_VERSION: &str = "1.0.0-sim";
+/// Interval between streamed monitor lines while raw monitoring is active
+pub const MOCK_MONITOR_INTERVAL_MS: u64 = crate::raw_state::RAW_STATE_POLLING_MS;
+
+pub fn mock_device_enabled() -> bool {
+    std::env::var(MOCK_DEVICE_ENV)
+        .map(|v| matches!(v.trim().to_ascii_lowercase().as_str(), "1" | "true" | "yes"))
+        .unwrap_or(false)
+}
+
+pub fn is_mock_port(port_name: &str) -> bool {
+    port_name == MOCK_PORT_NAME
+}
+
+/// Largest chunk the simulated device accepts, advertised in its WRITE_FILE_BEGIN reply
+pub const MOCK_MAX_CHUNK: usize = 32;
+/// Pending transfer target of a TEST_CONFIG upload
+const PREVIEW_TARGET: &str = "<test_config>";
+const CONFIG_PATH: &str = "/config.bin";
+const CONFIG_BACKUP_PATH: &str = "/config.bak";
+
+#[derive(Debug, Clone)]
+struct PendingWrite {
+    path: String,
+    size: usize,
+    crc: u32,
+    data: Vec<u8>,
+}
+
+/// Command handling state of the simulated device
+#[derive(Debug, Clone)]
+pub struct SimulatedDevice {
+    pub firmware_version: String,
+    pub files: HashMap<String, Vec<u8>>,
+    /// Scripted replies that override the built-in handling, keyed by command name (first word)
+    pub scripted: HashMap<String, Vec<String>>,
+    /// Commands the device never answers (for timeout tests)
+    pub silent: Vec<String>,
+    /// Number of upcoming WRITE_FILE_CHUNK commands answered with NAK (for retry tests)
+    pub chunk_naks: u32,
+    /// Number of upcoming committed writes stored with a flipped byte (for read-back tests)
+    pub corrupt_writes: u32,
+    /// Config applied by TEST_CONFIG and not stored
+    pub preview_config: Option<Vec<u8>>,
+    pending_write: Option<PendingWrite>,
+    monitoring: bool,
+    started: Instant,
+    last_monitor: Instant,
+    monitor_seq: u64,
+}
+
+impl Default for SimulatedDevice {
+    fn default() -> Self {
+        let mut files = HashMap::new();
+        if let Ok(config) = crate::config::BinaryConfig::new().to_bytes() {
+            files.insert(CONFIG_PATH.to_string(), config);
+        }
+        Self {
+            firmware_version: MOCK_FIRMWARE_VERSION.to_string(),
+            files,
+            scripted: HashMap::new(),
+            silent: Vec::new(),
+            chunk_naks: 0,
+            corrupt_writes: 0,
+            preview_config: None,
+            pending_write: None,
+            monitoring: false,
+            started: Instant::now(),
+            last_monitor: Instant::now(),
+            monitor_seq: 0,
+        }
+    }
+}
+
+impl SimulatedDevice {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reply to `command` with `lines` instead of the built-in response
+    pub fn with_response(mut self, command: &str, lines: &[&str]) -> Self {
+        self.scripted.insert(command.to_string(), lines.iter().map(|l| l.to_string()).collect());
+        self
+    }
+
+    pub fn with_file(mut self, name: &str, data: Vec<u8>) -> Self {
+        self.files.insert(name.to_string(), data);
+        self
+    }
+
+    pub fn with_silent(mut self, command: &str) -> Self {
+        self.silent.push(command.to_string());
+        self
+    }
+
+    pub fn with_chunk_naks(mut self, count: u32) -> Self {
+        self.chunk_naks = count;
+        self
+    }
+
+    pub fn with_corrupt_writes(mut self, count: u32) -> Self {
+        self.corrupt_writes = count;
+        self
+    }
+
+    pub fn is_monitoring(&self) -> bool {
+        self.monitoring
+    }
+
+    fn timestamp_us(&self) -> u64 {
+        self.started.elapsed().as_micros() as u64
+    }
+
+    /// Bytes sent back for one command line: the reply lines, plus binary frames for framed reads
+    pub fn respond_bytes(&mut self, line: &str) -> Vec<u8> {
+        let mut out = Vec::new();
+        let framed_read = line.trim().strip_prefix("READ_FILE_FRAMED ")
+            .filter(|_| !self.scripted.contains_key("READ_FILE_FRAMED") && !self.silent.iter().any(|s| s == "READ_FILE_FRAMED"))
+            .and_then(|path| self.files.get(path.trim()).map(|data| (path.trim().to_string(), data.clone())));
+        let Some((path, data)) = framed_read else {
+            for reply in self.respond(line) {
+                out.extend(reply.bytes());
+                out.extend(b"\r\n");
+            }
+            return out;
+        };
+        out.extend(format!("FRAMED_FILE:{}:{}:{:08X}\r\n", path, data.len(), crate::util::crc::crc32(&data)).bytes());
+        for (index, chunk) in data.chunks(MOCK_MAX_CHUNK).enumerate() {
+            out.extend(framing::file_frame(index * MOCK_MAX_CHUNK, chunk));
+        }
+        out.extend(b"END_FRAMED_FILE\r\n");
+        out
+    }
+
+    /// Reply to an upload chunk sent as a binary frame
+    pub fn respond_frame(&mut self, frame: Result<Vec<u8>, String>) -> String {
+        let Some(pending) = self.pending_write.as_mut() else {
+            return "ERROR:No transfer in progress".to_string();
+        };
+        let expected = pending.data.len();
+        let Ok(payload) = frame else {
+            return format!("NAK:{}:BAD_FRAME", expected);
+        };
+        let Some((offset, chunk)) = framing::parse_file_frame(&payload) else {
+            return format!("NAK:{}:BAD_FRAME", expected);
+        };
+        if self.chunk_naks > 0 {
+            self.chunk_naks -= 1;
+            return format!("NAK:{}:CRC", offset);
+        }
+        if offset != expected || chunk.len() > MOCK_MAX_CHUNK {
+            return format!("NAK:{}:BAD_CHUNK", offset);
+        }
+        pending.data.extend_from_slice(chunk);
+        format!("ACK:{}:{}", offset, chunk.len())
+    }
+
+    /// Response lines for one command line
+    pub fn respond(&mut self, line: &str) -> Vec<String> {
+        let line = line.trim();
+        let name = line.split([' ', ':']).next().unwrap_or("");
+        if name.is_empty() || self.silent.iter().any(|s| s == name) {
+            return Vec::new();
+        }
+        if let Some(lines) = self.scripted.get(name) {
+            return lines.clone();
+        }
+        match name {
+            "IDENTIFY" => vec![format!("{}:{}:{:08X}:{}", IDENTIFY_RESPONSE_PREFIX, DEVICE_SIGNATURE, MAGIC_NUMBER, self.firmware_version)],
+            "CAPABILITIES" => vec!["CAPABILITIES:WRITE_FILE,DELETE_FILE,RAW_MONITOR,STORAGE_INFO,FRAMED_FILES,TEST_CONFIG,REBOOT".to_string()],
+            "TIME" => vec![format!("TIME:{}", self.timestamp_us())],
+            "UPTIME" => vec![format!("UPTIME:{}", self.started.elapsed().as_millis())],
+            "REBOOT" => {
+                // The port stays open; the simulated firmware just starts over
+                self.started = Instant::now();
+                self.monitoring = false;
+                self.preview_config = None;
+                vec!["OK:REBOOTING".to_string()]
+            }
+            "STATUS" => vec![format!(
+                "Config Status - Storage: OK, Loaded: YES, Version: {}, Uptime: {}ms, USB: MOUNTED",
+                crate::config::binary::CONFIG_VERSION,
+                self.started.elapsed().as_millis()
+            )],
+            "STORAGE_INFO" => {
+                let used: usize = self.files.values().map(|f| f.len()).sum();
+                vec![format!("STORAGE_USED:{}", used), "STORAGE_TOTAL:4096".to_string()]
+            }
+            "LIST_FILES" => {
+                let mut names: Vec<&String> = self.files.keys().collect();
+                names.sort();
+                let mut out = vec!["FILES:".to_string()];
+                out.extend(names.into_iter().cloned());
+                out.push("END_FILES".to_string());
+                out
+            }
+            "READ_FILE" => {
+                let file = line.split_whitespace().nth(1).unwrap_or("");
+                match self.files.get(file) {
+                    Some(data) => {
+                        let hex: String = data.iter().map(|b| format!("{:02X}", b)).collect();
+                        vec![format!("FILE_DATA:{}:{}:{}", file, data.len(), hex)]
+                    }
+                    None => vec![format!("ERROR:File not found: {}", file)],
+                }
+            }
+            // Framed reads of existing files are answered by `respond_bytes`
+            "READ_FILE_FRAMED" => vec![format!("ERROR:File not found: {}", line.split_whitespace().nth(1).unwrap_or(""))],
+            "TEST_CONFIG" | "WRITE_FILE_BEGIN" | "WRITE_FILE_CHUNK" | "WRITE_FILE_END" | "WRITE_FILE_ABORT" => vec![self.handle_write(name, line)],
+            "DELETE_FILE" => {
+                let file = line.split_whitespace().nth(1).unwrap_or("");
+                match self.files.remove(file) {
+                    Some(_) => vec!["OK:FILE_DELETED".to_string()],
+                    None => vec![format!("ERROR:File not found: {}", file)],
+                }
+            }
+            "TEST_CONFIG_REVERT" => {
+                self.preview_config = None;
+                vec!["OK:TEST_CONFIG_REVERTED".to_string()]
+            }
+            "SAVE_CONFIG" => {
+                self.preview_config = None;
+                vec!["OK:CONFIG_SAVED".to_string()]
+            }
+            "FORCE_DEFAULT_CONFIG" => {
+                if let Ok(config) = crate::config::BinaryConfig::new().to_bytes() {
+                    self.files.insert(CONFIG_PATH.to_string(), config);
+                }
+                vec!["OK:DEFAULT_CONFIG_LOADED".to_string()]
+            }
+            "READ_GPIO_STATES" => vec![self.gpio_line()],
+            "READ_MATRIX_STATE" => vec![format!("MATRIX_STATE:0:0:0:{}", self.timestamp_us())],
+            "READ_SHIFT_REG" => vec![format!("SHIFT_REG:0:0xFF:{}", self.timestamp_us())],
+            "START_RAW_MONITOR" => {
+                self.monitoring = true;
+                self.last_monitor = Instant::now();
+                vec!["OK:RAW_MONITOR_STARTED".to_string()]
+            }
+            "STOP_RAW_MONITOR" => {
+                self.monitoring = false;
+                vec!["OK:RAW_MONITOR_STOPPED".to_string()]
+            }
+            _ => vec![format!("ERROR:Unknown command: {}", name)],
+        }
+    }
+
+    fn handle_write(&mut self, name: &str, line: &str) -> String {
+        use super::file_transfer::hex_decode;
+        use crate::util::crc::crc32;
+        let args: Vec<&str> = line.split_whitespace().skip(1).collect();
+        match name {
+            "WRITE_FILE_BEGIN" => {
+                let (Some(path), Some(size), Some(crc)) = (
+                    args.first(),
+                    args.get(1).and_then(|v| v.parse().ok()),
+                    args.get(2).and_then(|v| u32::from_str_radix(v, 16).ok()),
+                ) else {
+                    return "ERROR:Invalid WRITE_FILE_BEGIN".to_string();
+                };
+                self.pending_write = Some(PendingWrite { path: path.to_string(), size, crc, data: Vec::new() });
+                format!("OK:WRITE_BEGIN:{}", MOCK_MAX_CHUNK)
+            }
+            "TEST_CONFIG" => {
+                let (Some(size), Some(crc)) = (
+                    args.first().and_then(|v| v.parse().ok()),
+                    args.get(1).and_then(|v| u32::from_str_radix(v, 16).ok()),
+                ) else {
+                    return "ERROR:Invalid TEST_CONFIG".to_string();
+                };
+                self.pending_write = Some(PendingWrite { path: PREVIEW_TARGET.to_string(), size, crc, data: Vec::new() });
+                format!("OK:WRITE_BEGIN:{}", MOCK_MAX_CHUNK)
+            }
+            "WRITE_FILE_CHUNK" => {
+                let Some(pending) = self.pending_write.as_mut() else {
+                    return "ERROR:No transfer in progress".to_string();
+                };
+                let offset: usize = args.first().and_then(|v| v.parse().ok()).unwrap_or(usize::MAX);
+                if self.chunk_naks > 0 {
+                    self.chunk_naks -= 1;
+                    return format!("NAK:{}:CRC", offset);
+                }
+                let chunk = args.get(1).and_then(|h| hex_decode(h));
+                let crc = args.get(2).and_then(|v| u32::from_str_radix(v, 16).ok());
+                match chunk {
+                    Some(chunk) if offset == pending.data.len() && crc == Some(crc32(&chunk)) && chunk.len() <= MOCK_MAX_CHUNK => {
+                        pending.data.extend_from_slice(&chunk);
+                        format!("ACK:{}:{}", offset, chunk.len())
+                    }
+                    _ => format!("NAK:{}:BAD_CHUNK", offset),
+                }
+            }
+            "WRITE_FILE_END" => {
+                let Some(pending) = self.pending_write.take() else {
+                    return "ERROR:No transfer in progress".to_string();
+                };
+                let crc = crc32(&pending.data);
+                if pending.data.len() != pending.size || crc != pending.crc {
+                    return "ERROR:Verification failed".to_string();
+                }
+                let size = pending.data.len();
+                if pending.path == PREVIEW_TARGET {
+                    self.preview_config = Some(pending.data);
+                    return format!("OK:WRITE_COMPLETE:{}:{:08X}", size, crc);
+                }
+                let mut stored = pending.data;
+                if self.corrupt_writes > 0 && !stored.is_empty() {
+                    // Flash corruption after the device verified the transfer
+                    self.corrupt_writes -= 1;
+                    let last = stored.len() - 1;
+                    stored[last] ^= 0xFF;
+                }
+                if pending.path == CONFIG_PATH {
+                    // Like the firmware, keep the replaced config as /config.bak
+                    if let Some(previous) = self.files.get(CONFIG_PATH).cloned() {
+                        self.files.insert(CONFIG_BACKUP_PATH.to_string(), previous);
+                    }
+                }
+                self.files.insert(pending.path, stored);
+                format!("OK:WRITE_COMPLETE:{}:{:08X}", size, crc)
+            }
+            _ => {
+                self.pending_write = None;
+                "OK:WRITE_ABORTED".to_string()
+            }
+        }
+    }
+
+    /// A walking bit across the first 8 GPIOs so the UI sees activity
+    fn gpio_line(&self) -> String {
+        let mask = 1u32 << (self.monitor_seq % 8);
+        format!("GPIO_STATES:0x{:08X}:{}", mask, self.timestamp_us())
+    }
+
+    /// Monitor lines due since the last call (at most one per interval)
+    pub fn poll_monitor(&mut self) -> Vec<String> {
+        if !self.monitoring || self.last_monitor.elapsed() < Duration::from_millis(MOCK_MONITOR_INTERVAL_MS) {
+            return Vec::new();
+        }
+        self.last_monitor = Instant::now();
+        self.monitor_seq += 1;
+        let mut lines = vec![self.gpio_line()];
+        if self.monitor_seq % 4 == 0 {
+            let pressed = u8::from(self.monitor_seq % 8 == 0);
+            lines.push(format!("MATRIX_STATE:0:1:{}:{}", pressed, self.timestamp_us()));
+        }
+        lines
+    }
+}
+
+#[derive(Debug)]
+struct MockPortState {
+    device: SimulatedDevice,
+    rx: VecDeque<u8>,
+    tx_line: Vec<u8>,
+    tx_frames: FrameSplitter,
+}
+
+impl MockPortState {
+    fn pump_monitor(&mut self) {
+        for line in self.device.poll_monitor() {
+            self.rx.extend(line.bytes());
+            self.rx.extend(b"\r\n");
+        }
+    }
+}
+
+/// In-memory serial port backed by a [`SimulatedDevice`]
+#[derive(Debug, Clone)]
+pub struct MockSerialPort {
+    state: Arc<Mutex<MockPortState>>,
+    timeout: Duration,
+    baud_rate: u32,
+}
+
+impl MockSerialPort {
+    pub fn new(device: SimulatedDevice) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(MockPortState { device, rx: VecDeque::new(), tx_line: Vec::new(), tx_frames: FrameSplitter::new() })),
+            timeout: Duration::from_millis(500),
+            baud_rate: BAUD_RATE,
+        }
+    }
+
+    /// Port onto the process-wide simulated device used for [`MOCK_PORT_NAME`], so files
+    /// written through one connection are visible after reconnecting
+    pub fn shared() -> Self {
+        static SHARED: OnceLock<Arc<Mutex<MockPortState>>> = OnceLock::new();
+        let state = SHARED.get_or_init(|| Arc::new(Mutex::new(MockPortState {
+            device: SimulatedDevice::new(),
+            rx: VecDeque::new(),
+            tx_line: Vec::new(),
+            tx_frames: FrameSplitter::new(),
+        }))).clone();
+        let port = Self { state, timeout: Duration::from_millis(500), baud_rate: BAUD_RATE };
+        {
+            // A fresh open starts with empty buffers and the monitor stream stopped
+            let mut state = port.lock();
+            state.rx.clear();
+            state.tx_line.clear();
+            state.device.monitoring = false;
+        }
+        port
+    }
+
+    /// Inject raw bytes as if the device had sent them
+    pub fn inject(&self, data: &[u8]) {
+        self.lock().rx.extend(data);
+    }
+
+    pub fn is_monitoring(&self) -> bool {
+        self.lock().device.is_monitoring()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, MockPortState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Read for MockSerialPort {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut state = self.lock();
+        state.pump_monitor();
+        if state.rx.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::TimedOut, "no data"));
+        }
+        let n = buf.len().min(state.rx.len());
+        for (slot, byte) in buf.iter_mut().zip(state.rx.drain(..n)) {
+            *slot = byte;
+        }
+        Ok(n)
+    }
+}
+
+impl Write for MockSerialPort {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut state = self.lock();
+        let split = state.tx_frames.push(buf);
+        for &byte in &split.text {
+            if byte == b'\n' || byte == b'\r' {
+                let line = String::from_utf8_lossy(&state.tx_line).to_string();
+                state.tx_line.clear();
+                let reply = state.device.respond_bytes(&line);
+                state.rx.extend(reply);
+            } else {
+                state.tx_line.push(byte);
+            }
+        }
+        for frame in split.frames {
+            let reply = state.device.respond_frame(frame);
+            state.rx.extend(reply.bytes());
+            state.rx.extend(b"\r\n");
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl SerialPort for MockSerialPort {
+    fn name(&self) -> Option<String> { Some(MOCK_PORT_NAME.to_string()) }
+    fn baud_rate(&self) -> serialport::Result<u32> { Ok(self.baud_rate) }
+    fn data_bits(&self) -> serialport::Result<DataBits> { Ok(DataBits::Eight) }
+    fn flow_control(&self) -> serialport::Result<FlowControl> { Ok(FlowControl::None) }
+    fn parity(&self) -> serialport::Result<Parity> { Ok(Parity::None) }
+    fn stop_bits(&self) -> serialport::Result<StopBits> { Ok(StopBits::One) }
+    fn timeout(&self) -> Duration { self.timeout }
+    fn set_baud_rate(&mut self, baud_rate: u32) -> serialport::Result<()> { self.baud_rate = baud_rate; Ok(()) }
+    fn set_data_bits(&mut self, _: DataBits) -> serialport::Result<()> { Ok(()) }
+    fn set_flow_control(&mut self, _: FlowControl) -> serialport::Result<()> { Ok(()) }
+    fn set_parity(&mut self, _: Parity) -> serialport::Result<()> { Ok(()) }
+    fn set_stop_bits(&mut self, _: StopBits) -> serialport::Result<()> { Ok(()) }
+    fn set_timeout(&mut self, timeout: Duration) -> serialport::Result<()> { self.timeout = timeout; Ok(()) }
+    fn write_request_to_send(&mut self, _: bool) -> serialport::Result<()> { Ok(()) }
+    fn write_data_terminal_ready(&mut self, _: bool) -> serialport::Result<()> { Ok(()) }
+    fn read_clear_to_send(&mut self) -> serialport::Result<bool> { Ok(true) }
+    fn read_data_set_ready(&mut self) -> serialport::Result<bool> { Ok(true) }
+    fn read_ring_indicator(&mut self) -> serialport::Result<bool> { Ok(false) }
+    fn read_carrier_detect(&mut self) -> serialport::Result<bool> { Ok(true) }
+    fn bytes_to_read(&self) -> serialport::Result<u32> {
+        let mut state = self.lock();
+        state.pump_monitor();
+        Ok(state.rx.len() as u32)
+    }
+    fn bytes_to_write(&self) -> serialport::Result<u32> { Ok(0) }
+    fn clear(&self, buffer_to_clear: ClearBuffer) -> serialport::Result<()> {
+        let mut state = self.lock();
+        match buffer_to_clear {
+            ClearBuffer::Input => state.rx.clear(),
+            ClearBuffer::Output => state.tx_line.clear(),
+            ClearBuffer::All => { state.rx.clear(); state.tx_line.clear(); }
+        }
+        Ok(())
+    }
+    fn try_clone(&self) -> serialport::Result<Box<dyn SerialPort>> { Ok(Box::new(self.clone())) }
+    fn set_break(&self) -> serialport::Result<()> { Ok(()) }
+    fn clear_break(&self) -> serialport::Result<()> { Ok(()) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_all(port: &mut MockSerialPort) -> String {
+        let mut buf = [0u8; 4096];
+        let n = port.read(&mut buf).unwrap_or(0);
+        String::from_utf8_lossy(&buf[..n]).to_string()
+    }
+
+    #[test]
+    fn answers_identify_and_scripted_commands() {
+        let mut port = MockSerialPort::new(SimulatedDevice::new().with_response("STATUS", &["Config Status - scripted"]));
+        port.write_all(b"IDENTIFY\n").unwrap();
+        assert!(read_all(&mut port).starts_with("JOYCORE_ID:JOYCORE-FW:4A4F5943:"));
+        port.write_all(b"STATUS\n").unwrap();
+        assert_eq!(read_all(&mut port).trim(), "Config Status - scripted");
+    }
+
+    #[test]
+    fn streams_monitor_lines_while_monitoring() {
+        let mut port = MockSerialPort::new(SimulatedDevice::new());
+        port.write_all(b"START_RAW_MONITOR\n").unwrap();
+        assert!(read_all(&mut port).contains("OK:RAW_MONITOR_STARTED"));
+        std::thread::sleep(Duration::from_millis(MOCK_MONITOR_INTERVAL_MS + 5));
+        assert!(read_all(&mut port).starts_with("GPIO_STATES:0x"));
+    }
+}
@@ -1,7 +1,16 @@
+pub mod capabilities;
+pub mod catalog;
+pub mod connection;
+pub mod file_transfer;
+pub mod framing;
 pub mod interface;
+pub mod mock;
+pub mod probe;
 pub mod protocol;
+pub mod status;
 pub mod unified;
 
+pub use connection::ConnectionSettings;
 pub use interface::SerialInterface;
 pub use protocol::{ConfigProtocol, StorageInfo};
 pub use unified::*;
@@ -18,6 +27,9 @@ pub struct SerialDeviceInfo {
     pub product: Option<String>,
     pub firmware_version: Option<String>,
     pub device_signature: Option<String>,
+    /// Board revision from an optional fifth IDENTIFY field
+    #[serde(default)]
+    pub hardware_revision: Option<String>,
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -33,6 +45,13 @@ pub enum SerialError {
     
     #[error("Protocol error: {0}")]
     ProtocolError(String),
+
+    #[error("{0}")]
+    Unsupported(String),
+
+    /// The firmware reported that a file it was asked to read does not exist
+    #[error("File not found: {0}")]
+    FileNotFound(String),
     
     #[error("IO error: {0}")]
     IoError(#[from] std::io::Error),
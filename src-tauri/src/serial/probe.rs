@@ -0,0 +1,130 @@
+//! Concurrent IDENTIFY probes with an overall deadline.
+//!
+//! Each port is probed on its own blocking task, so a pass takes about as long as the slowest
+//! port instead of the sum of all of them. Probes still running at the deadline are reported as
+//! timed out and left to finish in the background (a blocking read cannot be cancelled); until
+//! one finishes, its port is reported as timed out again instead of being opened a second time.
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use once_cell::sync::Lazy;
+use serialport::SerialPortInfo;
+use tokio::task::JoinSet;
+
+use super::{Result, SerialDeviceInfo};
+
+/// Ports with a probe running, possibly left over from an earlier pass
+static PROBES_IN_FLIGHT: Lazy<Mutex<HashSet<String>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+/// Marks a port as being probed until dropped
+struct InFlight(String);
+
+impl InFlight {
+    fn claim(port_name: &str) -> Option<Self> {
+        PROBES_IN_FLIGHT.lock().unwrap().insert(port_name.to_string()).then(|| Self(port_name.to_string()))
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        PROBES_IN_FLIGHT.lock().unwrap().remove(&self.0);
+    }
+}
+
+/// Outcome of probing a set of ports
+#[derive(Debug, Default)]
+pub struct ProbeResults {
+    /// Finished probes, in completion order
+    pub finished: Vec<(SerialPortInfo, Result<Option<SerialDeviceInfo>>)>,
+    /// Ports whose probe did not finish before the deadline
+    pub timed_out: Vec<String>,
+}
+
+/// Run `identify` for every port concurrently, collecting what finishes within `deadline`
+pub async fn probe_ports<I>(ports: Vec<SerialPortInfo>, identify: I, deadline: Duration) -> ProbeResults
+where
+    I: Fn(&str) -> Result<Option<SerialDeviceInfo>> + Send + Sync + 'static,
+{
+    let identify = Arc::new(identify);
+    let mut results = ProbeResults::default();
+    let mut pending: HashSet<String> = HashSet::new();
+    let mut probes = JoinSet::new();
+
+    for port_info in ports {
+        let Some(claim) = InFlight::claim(&port_info.port_name) else {
+            log::debug!("Port {} is still being probed by an earlier pass", port_info.port_name);
+            results.timed_out.push(port_info.port_name);
+            continue;
+        };
+        pending.insert(port_info.port_name.clone());
+        let identify = identify.clone();
+        probes.spawn_blocking(move || {
+            let _claim = claim;
+            let result = identify(&port_info.port_name);
+            (port_info, result)
+        });
+    }
+
+    let expired = tokio::time::sleep(deadline);
+    tokio::pin!(expired);
+    while !pending.is_empty() {
+        tokio::select! {
+            joined = probes.join_next() => match joined {
+                Some(Ok((port_info, result))) => {
+                    pending.remove(&port_info.port_name);
+                    results.finished.push((port_info, result));
+                }
+                Some(Err(e)) => log::warn!("IDENTIFY probe task failed: {}", e),
+                None => break,
+            },
+            _ = &mut expired => break,
+        }
+    }
+    // Dropping the set would only abort probes that have not started yet; let them all run out
+    probes.detach_all();
+
+    for port_name in pending {
+        log::warn!("IDENTIFY probe of {} did not finish within {:?}", port_name, deadline);
+        results.timed_out.push(port_name);
+    }
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn port(name: &str) -> SerialPortInfo {
+        SerialPortInfo { port_name: name.to_string(), port_type: serialport::SerialPortType::Unknown }
+    }
+
+    /// Sleeps for the number of milliseconds in the port name's suffix
+    fn slow_identify(port_name: &str) -> Result<Option<SerialDeviceInfo>> {
+        let ms: u64 = port_name.rsplit('_').next().unwrap().parse().unwrap();
+        std::thread::sleep(Duration::from_millis(ms));
+        Ok(None)
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn probes_concurrently_and_reports_timeouts() {
+        let ports = vec![port("probe_a_200"), port("probe_b_200"), port("probe_c_200"), port("probe_d_2000")];
+        let started = std::time::Instant::now();
+        let results = probe_ports(ports, slow_identify, Duration::from_millis(600)).await;
+        assert!(started.elapsed() < Duration::from_millis(1000), "probes ran one after another");
+        assert_eq!(results.finished.len(), 3);
+        assert_eq!(results.timed_out, vec!["probe_d_2000".to_string()]);
+
+        // The slow probe is still running: a second pass does not open the port again
+        let again = probe_ports(vec![port("probe_d_2000")], |_: &str| Ok(None), Duration::from_millis(100)).await;
+        assert!(again.finished.is_empty());
+        assert_eq!(again.timed_out, vec!["probe_d_2000".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn releases_ports_once_probed() {
+        let first = probe_ports(vec![port("probe_e_0")], slow_identify, Duration::from_secs(1)).await;
+        assert_eq!(first.finished.len(), 1);
+        let second = probe_ports(vec![port("probe_e_0")], slow_identify, Duration::from_secs(1)).await;
+        assert_eq!((second.finished.len(), second.timed_out.len()), (1, 0));
+    }
+}
@@ -1,21 +1,58 @@
 use serde::{Deserialize, Serialize};
 use super::{Result, SerialError, SerialInterface};
+use super::capabilities::{Capability, FirmwareCapabilities};
+use super::file_transfer::{self, ChunkReply, ConfigTransferProgress, ProgressSink, TransferDirection};
+use super::framing;
+use super::status::{self, UsbState};
 use crate::serial::unified::{UnifiedSerialHandle};
 use crate::serial::unified::types::{CommandSpec, ResponseMatcher};
 use std::time::Duration;
 
+/// Axes of the firmware (X, Y, Z, RX, RY, RZ, S1, S2)
+pub const AXIS_COUNT: u8 = 8;
+/// Logical inputs of the firmware
+pub const BUTTON_COUNT: u8 = 64;
+
 /// JoyCore configuration protocol implementation
 /// Based on the Qt C++ implementation, this handles the text-based protocol
 /// for communicating with RP2040-based HOTAS controllers
-pub struct ConfigProtocol { handle: UnifiedSerialHandle, interface: std::sync::Arc<tokio::sync::Mutex<SerialInterface>> }
+pub struct ConfigProtocol {
+    handle: UnifiedSerialHandle,
+    interface: std::sync::Arc<tokio::sync::Mutex<SerialInterface>>,
+    /// Negotiated in `init`; None means unknown, so commands are attempted
+    capabilities: Option<FirmwareCapabilities>,
+    progress: Option<ProgressSink>,
+}
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct DeviceStatus {
     pub firmware_version: String,
     pub device_name: String,
     pub axes_count: u8,
     pub buttons_count: u8,
     pub connected: bool,
+    /// Board revision from STATUS or IDENTIFY, normalized (`rev2` -> `2`)
+    #[serde(default)]
+    pub hardware_revision: Option<String>,
+    /// `axes_count` and `buttons_count` were reported by STATUS; otherwise they are the
+    /// protocol limits ([`AXIS_COUNT`], [`BUTTON_COUNT`])
+    #[serde(default)]
+    pub counts_reported: bool,
+    /// Logical inputs in the loaded config
+    #[serde(default)]
+    pub input_count: Option<u16>,
+    /// Device uptime when STATUS was read
+    #[serde(default)]
+    pub uptime_ms: Option<u64>,
+    #[serde(default)]
+    pub storage_ok: Option<bool>,
+    /// False when the firmware runs its built-in defaults instead of a stored config
+    #[serde(default)]
+    pub config_loaded: Option<bool>,
+    #[serde(default)]
+    pub config_version: Option<u32>,
+    #[serde(default)]
+    pub usb_state: Option<UsbState>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -50,7 +87,54 @@ pub struct ProfileConfig {
 }
 
 impl ConfigProtocol {
-    pub fn new(handle: UnifiedSerialHandle, interface: std::sync::Arc<tokio::sync::Mutex<SerialInterface>>) -> Self { Self { handle, interface } }
+    pub fn new(handle: UnifiedSerialHandle, interface: std::sync::Arc<tokio::sync::Mutex<SerialInterface>>) -> Self { Self { handle, interface, capabilities: None, progress: None } }
+
+    /// Report progress of file reads and uploads to `sink`
+    pub fn set_progress_sink(&mut self, sink: Option<ProgressSink>) {
+        self.progress = sink;
+    }
+
+    fn report_progress(&self, direction: TransferDirection, file: &str, bytes: usize, total: usize) {
+        if let Some(sink) = &self.progress {
+            sink(ConfigTransferProgress { direction, file: file.to_string(), bytes, total });
+        }
+    }
+
+    /// Report read progress of `command` from the reader until the returned task is aborted
+    fn watch_read_progress(&self, command: &'static str, file: &str, estimate: fn(&str, usize) -> Option<(usize, usize)>) -> Option<tokio::task::JoinHandle<()>> {
+        let sink = self.progress.clone()?;
+        let mut progress = self.handle.receive_progress_receiver();
+        let file = file.to_string();
+        Some(tokio::spawn(async move {
+            let mut last = None;
+            while progress.changed().await.is_ok() {
+                let (bytes, total) = {
+                    let current = progress.borrow_and_update();
+                    if current.command != command {
+                        continue;
+                    }
+                    match estimate(&current.head, current.bytes) {
+                        Some(p) => p,
+                        None => continue,
+                    }
+                };
+                if last != Some(bytes) {
+                    last = Some(bytes);
+                    sink(ConfigTransferProgress { direction: TransferDirection::Read, file: file.clone(), bytes, total });
+                }
+            }
+        }))
+    }
+
+    /// Read reply of `command` with progress reported while it arrives
+    async fn send_with_read_progress(&self, command: String, spec: CommandSpec, file: &str, estimate: fn(&str, usize) -> Option<(usize, usize)>) -> Result<crate::serial::unified::types::CommandResponse> {
+        let watcher = self.watch_read_progress(spec.name, file, estimate);
+        let result = self.handle.send_command(command, spec).await;
+        if let Some(watcher) = watcher {
+            watcher.abort();
+        }
+        result
+    }
 
 
     /// Initialize communication with the device
@@ -60,10 +144,54 @@ impl ConfigProtocol {
             return Err(SerialError::ConnectionFailed("Device not connected".to_string()));
         }
 
-        log::info!("Protocol initialized successfully");
+        let capabilities = self.negotiate_capabilities().await;
+        log::info!("Protocol initialized successfully, capabilities: {:?}", capabilities);
+        self.capabilities = Some(capabilities);
         Ok(())
     }
 
+    /// Query CAPABILITIES, falling back to the legacy feature set when the firmware does not know it
+    pub async fn negotiate_capabilities(&mut self) -> FirmwareCapabilities {
+        let firmware_version = { let guard = self.interface.lock().await; guard.device_info()
+            .and_then(|info| info.firmware_version.clone())
+            .unwrap_or_else(|| "Unknown".to_string()) };
+        let spec = CommandSpec { name: "CAPABILITIES", timeout: Duration::from_millis(500), matcher: ResponseMatcher::Custom(super::capabilities::reply_complete), test_min_duration_ms: None };
+        match self.handle.send_command("CAPABILITIES".to_string(), spec).await {
+            Ok(resp) => FirmwareCapabilities::parse(&firmware_version, &resp.lines).unwrap_or_else(|| {
+                log::info!("Firmware {} does not report capabilities ({:?}), using legacy feature set", firmware_version, resp.lines);
+                FirmwareCapabilities::legacy(&firmware_version)
+            }),
+            Err(e) => {
+                log::info!("CAPABILITIES query failed on firmware {} ({}), using legacy feature set", firmware_version, e);
+                FirmwareCapabilities::legacy(&firmware_version)
+            }
+        }
+    }
+
+    pub fn capabilities(&self) -> Option<&FirmwareCapabilities> {
+        self.capabilities.as_ref()
+    }
+
+    /// Fail fast when the negotiated capabilities rule a command out
+    pub fn require(&self, capability: Capability) -> Result<()> {
+        match &self.capabilities {
+            Some(caps) => caps.require(capability),
+            None => Ok(()),
+        }
+    }
+
+    /// Record that the firmware rejected a command as unknown and build the matching error
+    fn mark_unsupported(&mut self, capability: Capability) -> SerialError {
+        log::warn!("Firmware does not support {}", capability.token());
+        match self.capabilities.as_mut() {
+            Some(caps) => {
+                caps.set(capability, false);
+                caps.unsupported(capability)
+            }
+            None => SerialError::Unsupported(format!("{} is not supported by this firmware", capability.token())),
+        }
+    }
+
     /// Get device status and capabilities using actual JoyCore-FW protocol
     pub async fn get_device_status(&mut self) -> Result<DeviceStatus> {
         // Get firmware version from device info if available
@@ -75,8 +203,10 @@ impl ConfigProtocol {
         let device_name = { let guard = self.interface.lock().await; guard.device_info()
             .and_then(|info| info.product.clone())
             .unwrap_or_else(|| "JoyCore HOTAS Controller".to_string()) };
+        let identify_revision = { let guard = self.interface.lock().await; guard.device_info()
+            .and_then(|info| info.hardware_revision.clone()) };
 
-        // STATUS response sample: "Config Status - Storage: OK, Loaded: YES, Version: 7"
+        // STATUS response sample: "Config Status - Storage: OK, Loaded: YES, Version: 7" (fields in `serial::status`)
         // Single line; matcher now directly targets stable prefix. No retry/settle delay needed after correct matcher.
         let status_spec = CommandSpec { name: "STATUS", timeout: Duration::from_millis(1200), matcher: ResponseMatcher::Contains("Config Status"), test_min_duration_ms: None };
         let status_response = self.handle.send_command("STATUS".to_string(), status_spec).await
@@ -84,16 +214,22 @@ impl ConfigProtocol {
             .lines.join("\n");
         
         log::debug!("Raw status response: {}", status_response);
-        // log::info!("Device status: firmware={}, device={}", firmware_version, device_name);
-        
-        // For now, create a basic status since we just need to verify connection
-        // In the future, we could parse the actual status response format
+        let fields = status::parse_status(&status_response);
+
         let status = DeviceStatus {
             firmware_version,
             device_name,
-            axes_count: 8, // JoyCore supports up to 8 axes (X,Y,Z,RX,RY,RZ,S1,S2)
-            buttons_count: 64, // JoyCore supports up to 64 logical inputs
+            axes_count: fields.axes.unwrap_or(AXIS_COUNT),
+            buttons_count: fields.buttons.unwrap_or(BUTTON_COUNT),
             connected: true,
+            hardware_revision: crate::update::hardware::status_revision(&status_response).or(identify_revision),
+            counts_reported: fields.axes.is_some() && fields.buttons.is_some(),
+            input_count: fields.inputs,
+            uptime_ms: fields.uptime_ms,
+            storage_ok: fields.storage_ok,
+            config_loaded: fields.config_loaded,
+            config_version: fields.config_version,
+            usb_state: fields.usb,
         };
 
         Ok(status)
@@ -101,32 +237,31 @@ impl ConfigProtocol {
 
     /// Read current axis configuration
     pub async fn read_axis_config(&mut self, axis_id: u8) -> Result<AxisConfig> {
-        let command = format!("AXIS_GET:{}", axis_id);
-    let spec = CommandSpec { name: "AXIS_GET", timeout: Duration::from_millis(500), matcher: ResponseMatcher::UntilPrefix("AXIS:"), test_min_duration_ms: None };
-        let response = { let resp = self.handle.send_command(command.clone(), spec).await?; resp.lines.join("\n") };
-        
-        // Parse axis configuration from response
-        // Format: "AXIS:id,name,min,max,center,deadzone,curve,inverted"
-        let config_str = response.strip_prefix("AXIS:")
-            .ok_or_else(|| SerialError::ProtocolError("Invalid axis response".to_string()))?;
-        
-        let parts: Vec<&str> = config_str.split(',').collect();
-        if parts.len() < 8 {
-            return Err(SerialError::ProtocolError("Incomplete axis data".to_string()));
-        }
+        let response = { let resp = self.handle.send_command(format!("AXIS_GET:{}", axis_id), axis_get_spec()).await?; resp.lines.join("\n") };
+        parse_axis_config(&response)
+    }
 
-        let config = AxisConfig {
-            id: parts[0].parse().map_err(|_| SerialError::ProtocolError("Invalid axis ID".to_string()))?,
-            name: parts[1].to_string(),
-            min_value: parts[2].parse().map_err(|_| SerialError::ProtocolError("Invalid min value".to_string()))?,
-            max_value: parts[3].parse().map_err(|_| SerialError::ProtocolError("Invalid max value".to_string()))?,
-            center_value: parts[4].parse().map_err(|_| SerialError::ProtocolError("Invalid center value".to_string()))?,
-            deadzone: parts[5].parse().map_err(|_| SerialError::ProtocolError("Invalid deadzone".to_string()))?,
-            curve: parts[6].to_string(),
-            inverted: parts[7].parse().map_err(|_| SerialError::ProtocolError("Invalid inverted flag".to_string()))?,
-        };
+    /// Read every axis in one batch. All AXIS_GET commands are queued at once, so the reader
+    /// writes each one as soon as the previous answer arrives.
+    pub async fn read_all_axis_configs(&mut self) -> Result<Vec<AxisConfig>> {
+        let requests = (0..AXIS_COUNT).map(|id| {
+            let handle = self.handle.clone();
+            async move { handle.send_command(format!("AXIS_GET:{}", id), axis_get_spec()).await }
+        });
+        futures_util::future::join_all(requests).await.into_iter()
+            .map(|resp| parse_axis_config(&resp?.lines.join("\n")))
+            .collect()
+    }
 
-        Ok(config)
+    /// Read every button in one batch, pipelined like [`Self::read_all_axis_configs`]
+    pub async fn read_all_button_configs(&mut self) -> Result<Vec<ButtonConfig>> {
+        let requests = (0..BUTTON_COUNT).map(|id| {
+            let handle = self.handle.clone();
+            async move { handle.send_command(format!("BUTTON_GET:{}", id), button_get_spec()).await }
+        });
+        futures_util::future::join_all(requests).await.into_iter()
+            .map(|resp| parse_button_config(&resp?.lines.join("\n")))
+            .collect()
     }
 
     /// Write axis configuration to device
@@ -154,27 +289,8 @@ impl ConfigProtocol {
 
     /// Read button configuration
     pub async fn read_button_config(&mut self, button_id: u8) -> Result<ButtonConfig> {
-        let command = format!("BUTTON_GET:{}", button_id);
-    let spec = CommandSpec { name: "BUTTON_GET", timeout: Duration::from_millis(500), matcher: ResponseMatcher::UntilPrefix("BUTTON:"), test_min_duration_ms: None }; let response = { let resp = self.handle.send_command(command.clone(), spec).await?; resp.lines.join("\n") };
-        
-        // Parse button configuration from response
-        // Format: "BUTTON:id,name,function,enabled"
-        let config_str = response.strip_prefix("BUTTON:")
-            .ok_or_else(|| SerialError::ProtocolError("Invalid button response".to_string()))?;
-        
-        let parts: Vec<&str> = config_str.split(',').collect();
-        if parts.len() < 4 {
-            return Err(SerialError::ProtocolError("Incomplete button data".to_string()));
-        }
-
-        let config = ButtonConfig {
-            id: parts[0].parse().map_err(|_| SerialError::ProtocolError("Invalid button ID".to_string()))?,
-            name: parts[1].to_string(),
-            function: parts[2].to_string(),
-            enabled: parts[3].parse().map_err(|_| SerialError::ProtocolError("Invalid enabled flag".to_string()))?,
-        };
-
-        Ok(config)
+        let response = { let resp = self.handle.send_command(format!("BUTTON_GET:{}", button_id), button_get_spec()).await?; resp.lines.join("\n") };
+        parse_button_config(&response)
     }
 
     /// Write button configuration to device
@@ -214,7 +330,7 @@ impl ConfigProtocol {
     }
 
     /// Get storage information from the device
-    pub async fn get_storage_info(&mut self) -> Result<String> { let spec = CommandSpec { name: "STORAGE_INFO", timeout: Duration::from_millis(500), matcher: ResponseMatcher::Contains("STORAGE_"), test_min_duration_ms: None }; let response = { let resp = self.handle.send_command("STORAGE_INFO".to_string(), spec).await?; resp.lines.join("\n") }; Ok(response) }
+    pub async fn get_storage_info(&mut self) -> Result<String> { self.require(Capability::StorageInfo)?; let spec = CommandSpec { name: "STORAGE_INFO", timeout: Duration::from_millis(500), matcher: ResponseMatcher::Contains("STORAGE_"), test_min_duration_ms: None }; let response = { let resp = self.handle.send_command("STORAGE_INFO".to_string(), spec).await?; resp.lines.join("\n") }; Ok(response) }
 
     /// List files available on the device
     pub async fn list_files(&mut self) -> Result<Vec<String>> {
@@ -230,11 +346,43 @@ impl ConfigProtocol {
         Ok(files)
     }
 
-    /// Read a file from the device storage
+    /// Firmware reported binary framed file transfers
+    fn framed_files(&self) -> bool {
+        self.capabilities.as_ref().is_some_and(|c| c.framed_files)
+    }
+
+    /// Read a file from the device storage, framed when the firmware supports it and over the
+    /// hex text protocol otherwise or when the framed read fails
     pub async fn read_file(&mut self, filename: &str) -> Result<Vec<u8>> {
+        if self.framed_files() {
+            match self.read_file_framed(filename).await {
+                Ok(bytes) => return Ok(bytes),
+                Err(e @ SerialError::FileNotFound(_)) => return Err(e),
+                Err(e) => log::warn!("Framed read of {} failed, retrying over the text protocol: {}", filename, e),
+            }
+        }
+        self.read_file_text(filename).await
+    }
+
+    async fn read_file_framed(&mut self, filename: &str) -> Result<Vec<u8>> {
+        let spec = CommandSpec { name: "READ_FILE_FRAMED", timeout: Duration::from_millis(3000), matcher: ResponseMatcher::Custom(framing::read_reply_complete), test_min_duration_ms: None };
+        let resp = self.send_with_read_progress(framing::read_command(filename), spec, filename, framing::read_progress).await?;
+        if file_transfer::is_unknown_command(&resp.lines) {
+            return Err(self.mark_unsupported(Capability::FramedFiles));
+        }
+        let bytes = framing::assemble_read(filename, &resp.lines, &resp.frames)?;
+        self.report_progress(TransferDirection::Read, filename, bytes.len(), bytes.len());
+        log::info!("Read {} ({} bytes in {} frames)", filename, bytes.len(), resp.frames.len());
+        Ok(bytes)
+    }
+
+    async fn read_file_text(&mut self, filename: &str) -> Result<Vec<u8>> {
         log::info!("Reading file: {}", filename);
         let command = format!("READ_FILE {}", filename);
-    let spec = CommandSpec { name: "READ_FILE", timeout: Duration::from_millis(3000), matcher: ResponseMatcher::Contains("FILE_DATA:"), test_min_duration_ms: None }; let response = { let resp = self.handle.send_command(command.clone(), spec).await?; resp.lines.join("\n") };
+    let spec = CommandSpec { name: "READ_FILE", timeout: Duration::from_millis(3000), matcher: ResponseMatcher::Custom(file_transfer::read_reply_complete), test_min_duration_ms: None }; let response = { let resp = self.send_with_read_progress(command.clone(), spec, filename, file_transfer::read_progress).await?; resp.lines.join("\n") };
+        if let Some(err) = response.lines().find(|l| l.trim().to_ascii_uppercase().starts_with("ERROR")) {
+            return Err(file_transfer::rejected("READ_FILE", filename, err));
+        }
         
         log::info!("Raw response length: {} chars", response.len());
         log::info!("Raw response: '{}'", response);
@@ -290,27 +438,200 @@ impl ConfigProtocol {
             }
             log::info!("Size validation passed: {} bytes", bytes.len());
         }
-        
+        self.report_progress(TransferDirection::Read, filename, bytes.len(), bytes.len());
+
         Ok(bytes)
     }
 
     /// Save current configuration to device storage
     pub async fn save_config(&mut self) -> Result<()> { let spec = CommandSpec { name: "SAVE_CONFIG", timeout: Duration::from_millis(1000), matcher: ResponseMatcher::Contains("OK"), test_min_duration_ms: None }; let _ = self.handle.send_command("SAVE_CONFIG".to_string(), spec).await?; log::info!("Configuration saved to device"); Ok(()) }
 
-    /// Write a file to the device storage with raw binary data
-    pub async fn write_raw_file(&mut self, _filename: &str, _data: &[u8]) -> Result<()> {
-        // Note: WRITE_FILE is a suggested extension not yet implemented in firmware
-        return Err(SerialError::ProtocolError(
-            "WRITE_FILE command not implemented in firmware. Use SAVE_CONFIG for configuration updates.".to_string()
-        ));
+    /// Write a file to the device storage with raw binary data.
+    /// Uses the chunked WRITE_FILE_* sequence (see `file_transfer`); each chunk is acknowledged
+    /// and the device confirms size and CRC of the assembled file before committing it.
+    /// Chunks go out as binary frames when the firmware supports them, falling back to hex text
+    /// chunks if the framed transfer fails.
+    pub async fn write_raw_file(&mut self, filename: &str, data: &[u8]) -> Result<()> {
+        self.require(Capability::WriteFile)?;
+        if self.framed_files() {
+            match self.write_file_once(filename, data, true).await {
+                Err(e @ SerialError::Unsupported(_)) => return Err(e),
+                Err(e) => log::warn!("Framed write of {} failed, retrying over the text protocol: {}", filename, e),
+                ok => return ok,
+            }
+        }
+        self.write_file_once(filename, data, false).await
+    }
+
+    async fn write_file_once(&mut self, filename: &str, data: &[u8], framed: bool) -> Result<()> {
+        log::info!("Writing {} bytes to {}{}", data.len(), filename, if framed { " (framed)" } else { "" });
+        self.upload(file_transfer::begin_command(filename, data), Capability::WriteFile, filename, data, framed).await.map(|_| ())
+    }
+
+    /// Chunked upload opened by `begin` (WRITE_FILE_BEGIN, TEST_CONFIG or VALIDATE_CONFIG, which
+    /// share the reply format); `target` names the destination in logs. Returns the lines of the
+    /// completion reply.
+    async fn upload(&mut self, begin: String, capability: Capability, target: &str, data: &[u8], framed: bool) -> Result<Vec<String>> {
+        let name = match capability {
+            Capability::TestConfig => "TEST_CONFIG",
+            Capability::ValidateConfig => "VALIDATE_CONFIG",
+            _ => "WRITE_FILE_BEGIN",
+        };
+        let spec = CommandSpec { name, timeout: Duration::from_millis(1000), matcher: ResponseMatcher::Custom(file_transfer::begin_reply_complete), test_min_duration_ms: None };
+        let resp = self.handle.send_command(begin, spec).await?;
+        if file_transfer::is_unknown_command(&resp.lines) {
+            return Err(self.mark_unsupported(capability));
+        }
+        let chunk_size = if framed {
+            file_transfer::parse_begin_reply_with_limit(&resp.lines, framing::FRAME_CHUNK_SIZE)?
+        } else {
+            file_transfer::parse_begin_reply(&resp.lines)?
+        };
+
+        let result = async {
+            self.send_file_chunks(target, data, chunk_size, framed).await?;
+            let spec = CommandSpec { name: "WRITE_FILE_END", timeout: Duration::from_millis(2000), matcher: ResponseMatcher::Custom(file_transfer::end_reply_complete), test_min_duration_ms: None };
+            let resp = self.handle.send_command("WRITE_FILE_END".to_string(), spec).await?;
+            file_transfer::parse_end_reply(&resp.lines, data)?;
+            Ok(resp.lines)
+        }.await;
+        if let Err(e) = &result {
+            log::error!("Write of {} failed, aborting transfer: {}", target, e);
+            let spec = CommandSpec { name: "WRITE_FILE_ABORT", timeout: Duration::from_millis(500), matcher: ResponseMatcher::Custom(file_transfer::delete_reply_complete), test_min_duration_ms: None };
+            let _ = self.handle.send_command("WRITE_FILE_ABORT".to_string(), spec).await;
+        } else {
+            log::info!("Wrote {} ({} bytes, CRC verified)", target, data.len());
+        }
+        result
+    }
+
+    /// Run `data` as the active config from RAM without storing it. The upload is the chunked
+    /// file write opened with `TEST_CONFIG <size> <crc32>`; the firmware keeps the previewed
+    /// config until TEST_CONFIG_REVERT, SAVE_CONFIG or a reset.
+    pub async fn test_config(&mut self, data: &[u8]) -> Result<()> {
+        self.require(Capability::TestConfig)?;
+        log::info!("Previewing {} byte config from RAM", data.len());
+        self.upload(file_transfer::test_config_command(data), Capability::TestConfig, "preview config", data, false).await.map(|_| ())
+    }
+
+    /// Dry run: upload `data` with `VALIDATE_CONFIG <size> <crc32>` and return the firmware's
+    /// findings. The firmware neither applies nor stores the config.
+    pub async fn validate_config(&mut self, data: &[u8]) -> Result<Vec<file_transfer::ValidationFinding>> {
+        self.require(Capability::ValidateConfig)?;
+        log::info!("Validating {} byte config on the device", data.len());
+        let lines = self.upload(file_transfer::validate_config_command(data), Capability::ValidateConfig, "config for validation", data, false).await?;
+        Ok(file_transfer::parse_validation_findings(&lines))
+    }
+
+    /// Drop a previewed config; the firmware reloads the stored one
+    pub async fn revert_test_config(&mut self) -> Result<()> {
+        self.require(Capability::TestConfig)?;
+        let spec = CommandSpec { name: "TEST_CONFIG_REVERT", timeout: Duration::from_millis(1000), matcher: ResponseMatcher::Custom(file_transfer::delete_reply_complete), test_min_duration_ms: None };
+        let resp = self.handle.send_command("TEST_CONFIG_REVERT".to_string(), spec).await?;
+        if file_transfer::is_unknown_command(&resp.lines) {
+            return Err(self.mark_unsupported(Capability::TestConfig));
+        }
+        file_transfer::parse_revert_reply(&resp.lines)?;
+        log::info!("Previewed config reverted to the stored config");
+        Ok(())
+    }
+
+    /// Write a config file as a transaction: write, read back and compare, and only then
+    /// SAVE_CONFIG. If the write or the comparison fails, the previous contents (read before
+    /// writing) are written back and the error describes the mismatch and the restore result.
+    pub async fn write_config_transaction(&mut self, filename: &str, data: &[u8]) -> Result<()> {
+        self.require(Capability::WriteFile)?;
+        let backup = match self.read_file(filename).await {
+            Ok(bytes) => Some(bytes),
+            Err(e) => {
+                log::warn!("Could not back up {} before writing, continuing without rollback: {}", filename, e);
+                None
+            }
+        };
+
+        let failure = match self.write_raw_file(filename, data).await {
+            Err(e @ SerialError::Unsupported(_)) => return Err(e),
+            Err(e) => format!("write failed: {}", e),
+            Ok(()) => match self.read_file(filename).await {
+                Ok(read_back) if read_back == data => {
+                    self.save_config().await?;
+                    log::info!("Config transaction for {} committed ({} bytes verified)", filename, data.len());
+                    return Ok(());
+                }
+                Ok(read_back) => format!("verification failed: {}", file_transfer::describe_mismatch(data, &read_back)),
+                Err(e) => format!("read-back failed: {}", e),
+            },
+        };
+        log::error!("Config transaction for {} aborted, {}", filename, failure);
+
+        let restore = match backup {
+            None => "no backup was available to restore".to_string(),
+            Some(backup) => match self.restore_file(filename, &backup).await {
+                Ok(()) => format!("previous config ({} bytes) restored", backup.len()),
+                Err(e) => format!("restoring the previous config failed: {}", e),
+            },
+        };
+        Err(SerialError::ProtocolError(format!("Config write to {} {}; {}", filename, failure, restore)))
+    }
+
+    /// Write `backup` back and confirm it by reading it again
+    async fn restore_file(&mut self, filename: &str, backup: &[u8]) -> Result<()> {
+        self.write_raw_file(filename, backup).await?;
+        let read_back = self.read_file(filename).await?;
+        if read_back != backup {
+            return Err(SerialError::ProtocolError(file_transfer::describe_mismatch(backup, &read_back)));
+        }
+        Ok(())
+    }
+
+    async fn send_file_chunks(&self, target: &str, data: &[u8], chunk_size: usize, framed: bool) -> Result<()> {
+        self.report_progress(TransferDirection::Write, target, 0, data.len());
+        for (index, chunk) in data.chunks(chunk_size).enumerate() {
+            let offset = index * chunk_size;
+            let mut attempt = 0;
+            loop {
+                let spec = CommandSpec { name: "WRITE_FILE_CHUNK", timeout: Duration::from_millis(1000), matcher: ResponseMatcher::Custom(file_transfer::chunk_reply_complete), test_min_duration_ms: None };
+                let sent = if framed {
+                    self.handle.send_framed_command(String::new(), framing::file_frame(offset, chunk), spec).await
+                } else {
+                    self.handle.send_command(file_transfer::chunk_command(offset, chunk), spec).await
+                };
+                let reply = sent
+                    .and_then(|resp| file_transfer::parse_chunk_reply(&resp.lines, offset, chunk.len()));
+                let retry_reason = match reply {
+                    Ok(ChunkReply::Ack) => break,
+                    Ok(ChunkReply::Nak(reason)) => reason,
+                    Err(SerialError::Timeout) => "no acknowledgement".to_string(),
+                    Err(e) => return Err(e),
+                };
+                attempt += 1;
+                if attempt > file_transfer::MAX_CHUNK_RETRIES {
+                    return Err(SerialError::ProtocolError(format!("Chunk at offset {} failed after {} retries: {}", offset, file_transfer::MAX_CHUNK_RETRIES, retry_reason)));
+                }
+                log::warn!("Resending chunk at offset {} (attempt {}): {}", offset, attempt, retry_reason);
+            }
+            self.report_progress(TransferDirection::Write, target, offset + chunk.len(), data.len());
+        }
+        Ok(())
     }
 
     /// Delete a file from the device storage
-    pub async fn delete_file(&mut self, _filename: &str) -> Result<()> {
-        // Note: DELETE_FILE is a suggested extension not yet implemented in firmware
-        return Err(SerialError::ProtocolError(
-            "DELETE_FILE command not implemented in firmware. Use FORMAT_STORAGE to clear all files.".to_string()
-        ));
+    pub async fn delete_file(&mut self, filename: &str) -> Result<()> {
+        self.require(Capability::DeleteFile)?;
+        let spec = CommandSpec { name: "DELETE_FILE", timeout: Duration::from_millis(1000), matcher: ResponseMatcher::Custom(file_transfer::delete_reply_complete), test_min_duration_ms: None };
+        let resp = self.handle.send_command(file_transfer::delete_command(filename), spec).await?;
+        if file_transfer::is_unknown_command(&resp.lines) {
+            return Err(self.mark_unsupported(Capability::DeleteFile));
+        }
+        file_transfer::parse_delete_reply(&resp.lines, filename)?;
+        log::info!("Deleted {}", filename);
+        Ok(())
+    }
+
+    /// Support for the file write / delete commands known on this connection
+    pub fn file_command_support(&self) -> (Option<bool>, Option<bool>) {
+        let caps = self.capabilities.as_ref();
+        (caps.map(|c| c.write_file), caps.map(|c| c.delete_file))
     }
 
     /// Format the device storage (deletes all files)
@@ -348,13 +669,85 @@ impl ConfigProtocol {
         })
     }
 
-    /// Get reference to the serial interface
-    pub(crate) async fn send_locked(&self, cmd: &str) -> Result<String> { let spec = CommandSpec { name: "GENERIC", timeout: Duration::from_millis(500), matcher: ResponseMatcher::Contains("OK"), test_min_duration_ms: None }; let resp = self.handle.send_command(cmd.to_string(), spec).await?; Ok(resp.lines.join("\n")) }
-    pub(crate) async fn read_data_locked(&self, buffer: &mut [u8], timeout_ms: u64) -> Result<usize> { let mut guard = self.interface.lock().await; guard.read_data(buffer, timeout_ms).await }
+    /// Probe whether the firmware understands a side-effect free command.
+    /// Returns None when the device stays silent (support unknown).
+    pub async fn probe_command(&self, command: &'static str) -> Option<bool> {
+        let spec = CommandSpec { name: command, timeout: Duration::from_millis(400), matcher: ResponseMatcher::Custom(|lines| !lines.is_empty()), test_min_duration_ms: None };
+        let result = match self.handle.send_command(command.to_string(), spec).await {
+            Ok(resp) => Some(super::catalog::probe_response_supported(&resp.lines)),
+            Err(SerialError::Timeout) => None,
+            Err(e) => { log::debug!("Probe of {} failed: {}", command, e); None }
+        };
+        // Let any trailing response lines drain before the next command is issued
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        result
+    }
+
+    /// Send a raw-state command (READ_GPIO_STATES, START_RAW_MONITOR, ...) through the unified reader
+    pub(crate) async fn send_raw_command(&self, command: &'static str) -> Result<String> { let resp = self.handle.send_command(command.to_string(), raw_command_spec(command)).await?; Ok(resp.lines.join("\n")) }
     pub(crate) async fn disconnect_locked(&self) { let mut guard = self.interface.lock().await; guard.disconnect(); }
     pub fn clone_interface_arc(&self) -> std::sync::Arc<tokio::sync::Mutex<SerialInterface>> { self.interface.clone() }
 }
 
+/// Response shape of the raw-state commands. Matrix and shift register snapshots are one line
+/// per cell/register without a terminator, so they complete once the device goes quiet.
+fn raw_command_spec(command: &'static str) -> CommandSpec {
+    let matcher = match command {
+        "READ_GPIO_STATES" => ResponseMatcher::Custom(|lines| lines.iter().any(|l| l.starts_with("GPIO_STATES:") || l.starts_with("ERROR"))),
+        "READ_MATRIX_STATE" | "READ_SHIFT_REG" => ResponseMatcher::Settled(Duration::from_millis(50)),
+        _ => ResponseMatcher::Custom(|lines| lines.iter().any(|l| l.starts_with("OK") || l.starts_with("ERROR"))),
+    };
+    CommandSpec { name: command, timeout: Duration::from_millis(500), matcher, test_min_duration_ms: None }
+}
+
+fn axis_get_spec() -> CommandSpec {
+    CommandSpec { name: "AXIS_GET", timeout: Duration::from_millis(500), matcher: ResponseMatcher::UntilPrefix("AXIS:"), test_min_duration_ms: None }
+}
+
+fn button_get_spec() -> CommandSpec {
+    CommandSpec { name: "BUTTON_GET", timeout: Duration::from_millis(500), matcher: ResponseMatcher::UntilPrefix("BUTTON:"), test_min_duration_ms: None }
+}
+
+/// Parse an AXIS_GET reply: "AXIS:id,name,min,max,center,deadzone,curve,inverted"
+fn parse_axis_config(response: &str) -> Result<AxisConfig> {
+    let config_str = response.strip_prefix("AXIS:")
+        .ok_or_else(|| SerialError::ProtocolError("Invalid axis response".to_string()))?;
+
+    let parts: Vec<&str> = config_str.split(',').collect();
+    if parts.len() < 8 {
+        return Err(SerialError::ProtocolError("Incomplete axis data".to_string()));
+    }
+
+    Ok(AxisConfig {
+        id: parts[0].parse().map_err(|_| SerialError::ProtocolError("Invalid axis ID".to_string()))?,
+        name: parts[1].to_string(),
+        min_value: parts[2].parse().map_err(|_| SerialError::ProtocolError("Invalid min value".to_string()))?,
+        max_value: parts[3].parse().map_err(|_| SerialError::ProtocolError("Invalid max value".to_string()))?,
+        center_value: parts[4].parse().map_err(|_| SerialError::ProtocolError("Invalid center value".to_string()))?,
+        deadzone: parts[5].parse().map_err(|_| SerialError::ProtocolError("Invalid deadzone".to_string()))?,
+        curve: parts[6].to_string(),
+        inverted: parts[7].parse().map_err(|_| SerialError::ProtocolError("Invalid inverted flag".to_string()))?,
+    })
+}
+
+/// Parse a BUTTON_GET reply: "BUTTON:id,name,function,enabled"
+fn parse_button_config(response: &str) -> Result<ButtonConfig> {
+    let config_str = response.strip_prefix("BUTTON:")
+        .ok_or_else(|| SerialError::ProtocolError("Invalid button response".to_string()))?;
+
+    let parts: Vec<&str> = config_str.split(',').collect();
+    if parts.len() < 4 {
+        return Err(SerialError::ProtocolError("Incomplete button data".to_string()));
+    }
+
+    Ok(ButtonConfig {
+        id: parts[0].parse().map_err(|_| SerialError::ProtocolError("Invalid button ID".to_string()))?,
+        name: parts[1].to_string(),
+        function: parts[2].to_string(),
+        enabled: parts[3].parse().map_err(|_| SerialError::ProtocolError("Invalid enabled flag".to_string()))?,
+    })
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StorageInfo {
     pub used_bytes: usize,
@@ -0,0 +1,114 @@
+//! Fields of the firmware's STATUS reply.
+//!
+//! STATUS answers with comma separated `Key: Value` fields after a `Config Status - ` prefix,
+//! possibly over several lines:
+//!
+//! ```text
+//! Config Status - Storage: OK, Loaded: YES, Version: 7, Axes: 6, Buttons: 32, Inputs: 40, Uptime: 81234ms, USB: MOUNTED
+//! ```
+//!
+//! Older firmware only sends the storage and config fields; every field is optional and unknown
+//! keys are ignored.
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UsbState {
+    /// Enumerated by the host; HID reports are delivered
+    Mounted,
+    Suspended,
+    /// Powered without an enumerated USB connection
+    Unmounted,
+}
+
+impl UsbState {
+    fn parse(value: &str) -> Option<Self> {
+        match value.to_ascii_uppercase().as_str() {
+            "MOUNTED" | "CONFIGURED" | "CONNECTED" | "OK" | "YES" => Some(UsbState::Mounted),
+            "SUSPENDED" => Some(UsbState::Suspended),
+            "UNMOUNTED" | "NOT_MOUNTED" | "DISCONNECTED" | "NO" => Some(UsbState::Unmounted),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StatusFields {
+    pub storage_ok: Option<bool>,
+    /// False when the firmware runs its built-in defaults
+    pub config_loaded: Option<bool>,
+    pub config_version: Option<u32>,
+    pub axes: Option<u8>,
+    pub buttons: Option<u8>,
+    /// Logical inputs in the loaded config
+    pub inputs: Option<u16>,
+    pub uptime_ms: Option<u64>,
+    pub usb: Option<UsbState>,
+}
+
+fn parse_yes_no(value: &str) -> Option<bool> {
+    match value.to_ascii_uppercase().as_str() {
+        "YES" | "TRUE" | "1" => Some(true),
+        "NO" | "FALSE" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+/// Milliseconds from `81234`, `81234ms` or `81s`; a bare number is milliseconds
+fn parse_uptime(value: &str) -> Option<u64> {
+    let value = value.to_ascii_lowercase();
+    if let Some(ms) = value.strip_suffix("ms") {
+        return ms.trim().parse().ok();
+    }
+    if let Some(s) = value.strip_suffix('s') {
+        return s.trim().parse::<u64>().ok().map(|s| s * 1000);
+    }
+    value.parse().ok()
+}
+
+pub fn parse_status(reply: &str) -> StatusFields {
+    let mut fields = StatusFields::default();
+    for field in reply.split([',', '\n']) {
+        let Some((key, value)) = field.split_once(':') else { continue };
+        let key = key.rsplit(" - ").next().unwrap_or(key).trim().to_ascii_uppercase().replace([' ', '-'], "_");
+        let value = value.trim();
+        match key.as_str() {
+            "STORAGE" => fields.storage_ok = Some(value.eq_ignore_ascii_case("OK")),
+            "LOADED" => fields.config_loaded = parse_yes_no(value),
+            "VERSION" => fields.config_version = value.parse().ok(),
+            "AXES" | "AXIS_COUNT" => fields.axes = value.parse().ok(),
+            "BUTTONS" | "BUTTON_COUNT" => fields.buttons = value.parse().ok(),
+            "INPUTS" | "LOGICAL_INPUTS" => fields.inputs = value.parse().ok(),
+            "UPTIME" | "UPTIME_MS" => fields.uptime_ms = parse_uptime(value),
+            "USB" | "USB_STATE" => fields.usb = UsbState::parse(value),
+            _ => {}
+        }
+    }
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_status_fields() {
+        let full = parse_status("Config Status - Storage: OK, Loaded: YES, Version: 7, Axes: 6, Buttons: 32\nInputs: 40, Uptime: 81234ms, USB: MOUNTED, HW: rev2");
+        assert_eq!(full, StatusFields {
+            storage_ok: Some(true),
+            config_loaded: Some(true),
+            config_version: Some(7),
+            axes: Some(6),
+            buttons: Some(32),
+            inputs: Some(40),
+            uptime_ms: Some(81234),
+            usb: Some(UsbState::Mounted),
+        });
+
+        let legacy = parse_status("Config Status - Storage: FAIL, Loaded: NO, Version: 7");
+        assert_eq!((legacy.storage_ok, legacy.config_loaded, legacy.axes, legacy.usb), (Some(false), Some(false), None, None));
+        assert_eq!(parse_status("Uptime: 81s, USB: suspended").uptime_ms, Some(81000));
+        assert_eq!(parse_status("USB: SUSPENDED").usb, Some(UsbState::Suspended));
+        assert_eq!(parse_status("Axes: lots"), StatusFields::default());
+    }
+}
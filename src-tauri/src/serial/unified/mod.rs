@@ -1,5 +1,13 @@
 pub mod types;
 pub mod reader;
+pub mod queue;
+pub mod retry;
+pub mod trace;
+#[cfg(test)]
+mod scripted;
 
 pub use reader::{UnifiedSerialBuilder, UnifiedSerialHandle};
+pub use queue::CommandPriority;
+pub use retry::RetryPolicy;
 pub use types::{ParsedEvent, RawStateSnapshot, CommandSpec, ResponseMatcher, SerialCommand};
+pub use trace::{get_protocol_tracer, ProtocolTrace, TraceDirection, TraceEntry};
@@ -0,0 +1,220 @@
+//! Commands waiting for the serial line.
+//!
+//! The firmware answers one command at a time, so the reader keeps a single command in flight and
+//! queues the rest: config operations first, then diagnostics, then background polls, each class
+//! in arrival order. A queued command whose caller stopped waiting (its future was dropped) is
+//! discarded without being sent; one that waits longer than its class allows fails with a
+//! protocol error saying another command is in flight, so watchdogs can tell a busy link from a
+//! dead one. A command's own response timeout only starts once it is written.
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+use tokio::sync::oneshot;
+
+use super::types::{CommandResponse, CommandSpec};
+use crate::serial::catalog::PROTOCOL_COMMANDS;
+use crate::serial::SerialError;
+
+/// Order in which queued commands are sent (highest first)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CommandPriority {
+    /// Heartbeats and periodic resampling; stale results are worthless
+    Background,
+    Diagnostics,
+    /// Config and storage operations
+    Config,
+}
+
+impl CommandPriority {
+    /// Priority of a command by its catalog category: config and storage commands are config
+    /// operations, everything else is diagnostics. Background polls ask for their priority explicitly.
+    pub fn for_command(name: &str) -> Self {
+        match PROTOCOL_COMMANDS.iter().find(|c| name.starts_with(c.name)).map(|c| c.category) {
+            Some("config") | Some("storage") => CommandPriority::Config,
+            _ => CommandPriority::Diagnostics,
+        }
+    }
+
+    /// Longest a command of this class waits in the queue before failing with a timeout
+    pub fn max_queue_wait(self) -> Duration {
+        match self {
+            CommandPriority::Config => Duration::from_secs(10),
+            CommandPriority::Diagnostics => Duration::from_secs(5),
+            CommandPriority::Background => Duration::from_secs(1),
+        }
+    }
+}
+
+pub struct QueuedCommand {
+    pub cmd: String,
+    pub frame: Option<Vec<u8>>,
+    pub spec: CommandSpec,
+    pub priority: CommandPriority,
+    pub enqueued: Instant,
+    pub responder: oneshot::Sender<Result<CommandResponse, SerialError>>,
+}
+
+impl QueuedCommand {
+    fn overdue(&self, now: Instant) -> bool {
+        now.saturating_duration_since(self.enqueued) > self.priority.max_queue_wait()
+    }
+}
+
+struct Entry {
+    seq: u64,
+    command: QueuedCommand,
+}
+
+impl Entry {
+    fn key(&self) -> (CommandPriority, Reverse<u64>) {
+        (self.command.priority, Reverse(self.seq))
+    }
+}
+
+impl PartialEq for Entry {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for Entry {}
+
+impl PartialOrd for Entry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Entry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+/// Error for a command that never left the queue
+pub fn queue_timeout(name: &str, waited: Duration) -> SerialError {
+    SerialError::ProtocolError(format!("'{}' still queued after {:?} with another command in flight", name, waited))
+}
+
+/// What [`CommandQueue::expire`] dropped
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Expired {
+    /// Failed with a timeout after waiting too long
+    pub timed_out: usize,
+    /// Discarded because the caller stopped waiting
+    pub cancelled: usize,
+}
+
+#[derive(Default)]
+pub struct CommandQueue {
+    entries: BinaryHeap<Entry>,
+    next_seq: u64,
+}
+
+impl CommandQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, command: QueuedCommand) {
+        self.entries.push(Entry { seq: self.next_seq, command });
+        self.next_seq += 1;
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Drop cancelled commands and fail those that waited too long
+    pub fn expire(&mut self, now: Instant) -> Expired {
+        let mut expired = Expired::default();
+        for entry in std::mem::take(&mut self.entries).into_vec() {
+            if entry.command.responder.is_closed() {
+                expired.cancelled += 1;
+            } else if entry.command.overdue(now) {
+                let waited = entry.command.priority.max_queue_wait();
+                log::warn!("Command '{}' waited more than {:?} in the queue", entry.command.spec.name, waited);
+                let _ = entry.command.responder.send(Err(queue_timeout(entry.command.spec.name, waited)));
+                expired.timed_out += 1;
+            } else {
+                self.entries.push(entry);
+            }
+        }
+        expired
+    }
+
+    /// The next command to send, after expiring what can no longer be sent
+    pub fn next(&mut self, now: Instant) -> (Option<QueuedCommand>, Expired) {
+        let expired = self.expire(now);
+        (self.entries.pop().map(|e| e.command), expired)
+    }
+
+    /// Fail every queued command with `error`, e.g. when the reader stops
+    pub fn fail_all(&mut self, error: impl Fn() -> SerialError) {
+        for entry in self.entries.drain() {
+            let _ = entry.command.responder.send(Err(error()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serial::unified::types::ResponseMatcher;
+
+    type Reply = oneshot::Receiver<Result<CommandResponse, SerialError>>;
+
+    fn command(name: &'static str, priority: CommandPriority, enqueued: Instant) -> (QueuedCommand, Reply) {
+        let (responder, rx) = oneshot::channel();
+        let spec = CommandSpec { name, timeout: Duration::from_millis(500), matcher: ResponseMatcher::Contains("OK"), test_min_duration_ms: None };
+        (QueuedCommand { cmd: name.to_string(), frame: None, spec, priority, enqueued, responder }, rx)
+    }
+
+    #[test]
+    fn sends_by_priority_then_arrival() {
+        let now = Instant::now();
+        let mut queue = CommandQueue::new();
+        let mut replies = Vec::new();
+        for (name, priority) in [
+            ("STATUS", CommandPriority::Background),
+            ("STORAGE_INFO", CommandPriority::Diagnostics),
+            ("READ_FILE", CommandPriority::Config),
+            ("WRITE_FILE_BEGIN", CommandPriority::Config),
+        ] {
+            let (c, rx) = command(name, priority, now);
+            queue.push(c);
+            replies.push(rx);
+        }
+        let order: Vec<&str> = std::iter::from_fn(|| queue.next(now).0.map(|c| c.spec.name)).collect();
+        assert_eq!(order, vec!["READ_FILE", "WRITE_FILE_BEGIN", "STORAGE_INFO", "STATUS"]);
+
+        assert_eq!(CommandPriority::for_command("WRITE_FILE_CHUNK"), CommandPriority::Config);
+        assert_eq!(CommandPriority::for_command("SAVE_CONFIG"), CommandPriority::Config);
+        assert_eq!(CommandPriority::for_command("HID_MAPPING_INFO"), CommandPriority::Diagnostics);
+    }
+
+    #[test]
+    fn drops_cancelled_and_times_out_stale_commands() {
+        let start = Instant::now();
+        let mut queue = CommandQueue::new();
+        let (poll, mut poll_rx) = command("STATUS", CommandPriority::Background, start);
+        let (read, read_rx) = command("READ_FILE", CommandPriority::Config, start);
+        let (info, _info_rx) = command("STORAGE_INFO", CommandPriority::Diagnostics, start);
+        queue.push(poll);
+        queue.push(read);
+        queue.push(info);
+        drop(read_rx);
+
+        let (next, expired) = queue.next(start + Duration::from_secs(2));
+        assert_eq!(next.map(|c| c.spec.name), Some("STORAGE_INFO"));
+        assert_eq!(expired, Expired { timed_out: 1, cancelled: 1 });
+        assert!(matches!(poll_rx.try_recv(), Ok(Err(SerialError::ProtocolError(msg))) if msg.contains("in flight")));
+        assert!(queue.is_empty());
+    }
+}
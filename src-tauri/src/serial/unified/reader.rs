@@ -4,7 +4,16 @@ use tokio::sync::{mpsc, broadcast, watch};
 use crate::serial::{SerialInterface, SerialError};
 use tokio::sync::Mutex;
 use super::types::*;
+use super::queue::{CommandPriority, CommandQueue, QueuedCommand};
+use super::retry::{RetryPolicy, RetryStats};
+use super::trace::{get_protocol_tracer, TraceDirection};
 use std::time::Duration;
+use crate::util::BoundedTextBuffer;
+use crate::serial::framing::FrameSplitter;
+
+/// Partial (unterminated) line data beyond this is trimmed to the newest `PARTIAL_BUFFER_KEEP_BYTES`
+pub const PARTIAL_BUFFER_MAX_BYTES: usize = 8192;
+pub const PARTIAL_BUFFER_KEEP_BYTES: usize = 4096;
 
 #[derive(Clone)]
 pub struct UnifiedSerialHandle {
@@ -12,16 +21,74 @@ pub struct UnifiedSerialHandle {
     pub events_tx: broadcast::Sender<ParsedEvent>,
     pub snapshot_rx: watch::Receiver<Arc<RawStateSnapshot>>,
     pub metrics_rx: watch::Receiver<MetricsSnapshot>,
+    pub progress_rx: watch::Receiver<ReceiveProgress>,
+    pub retry_stats: Arc<RetryStats>,
+    /// Applied to every command timeout (see `serial::connection`)
+    pub timeout_factor: f32,
 }
 
 impl UnifiedSerialHandle {
+    /// Scale command timeouts for firmware that answers slower or faster than stock builds
+    pub fn with_timeout_factor(mut self, factor: f32) -> Self {
+        self.timeout_factor = factor;
+        self
+    }
     pub fn subscribe_events(&self) -> broadcast::Receiver<ParsedEvent> { self.events_tx.subscribe() }
     pub fn snapshot_receiver(&self) -> watch::Receiver<Arc<RawStateSnapshot>> { self.snapshot_rx.clone() }
     pub fn metrics_receiver(&self) -> watch::Receiver<MetricsSnapshot> { self.metrics_rx.clone() }
+    /// Reply bytes received for the command in flight
+    pub fn receive_progress_receiver(&self) -> watch::Receiver<ReceiveProgress> { self.progress_rx.clone() }
+    /// Reader metrics plus the retry counters kept on the handle side
+    pub fn metrics_snapshot(&self) -> MetricsSnapshot {
+        use std::sync::atomic::Ordering;
+        let mut metrics = self.metrics_rx.borrow().clone();
+        metrics.command_retries = self.retry_stats.retries.load(Ordering::Relaxed);
+        metrics.command_retry_recoveries = self.retry_stats.recovered.load(Ordering::Relaxed);
+        metrics.command_retry_exhausted = self.retry_stats.exhausted.load(Ordering::Relaxed);
+        metrics
+    }
+    /// Queue a command at the priority of its catalog category and wait for its response,
+    /// retrying timeouts per [`RetryPolicy::for_command`]
     pub async fn send_command(&self, cmd: String, spec: CommandSpec) -> Result<CommandResponse, SerialError> {
+        let policy = RetryPolicy::for_command(spec.name);
+        self.send_command_with_retry(cmd, spec, policy).await
+    }
+    /// Send with an explicit retry policy; only timeouts are retried
+    pub async fn send_command_with_retry(&self, cmd: String, spec: CommandSpec, policy: RetryPolicy) -> Result<CommandResponse, SerialError> {
+        let priority = CommandPriority::for_command(spec.name);
+        let mut attempt = 1;
+        loop {
+            let result = self.submit(cmd.clone(), None, spec.clone(), priority).await;
+            match &result {
+                Err(e) if policy.should_retry(attempt, e) => {
+                    let delay = policy.delay(attempt);
+                    log::debug!("Command '{}' attempt {} failed ({}), retrying in {:?}", spec.name, attempt, e, delay);
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                _ => {
+                    self.retry_stats.record(attempt, &result);
+                    if attempt > 1 && result.is_err() { log::warn!("Command '{}' failed after {} attempts", spec.name, attempt); }
+                    return result;
+                }
+            }
+        }
+    }
+    /// Send `cmd` followed by a binary frame (see `serial::framing`)
+    pub async fn send_framed_command(&self, cmd: String, frame: Vec<u8>, spec: CommandSpec) -> Result<CommandResponse, SerialError> {
+        let priority = CommandPriority::for_command(spec.name);
+        self.submit(cmd, Some(frame), spec, priority).await
+    }
+    /// Queue a command behind those of higher priority. Dropping the returned future before the
+    /// command is written cancels it.
+    pub async fn send_command_with_priority(&self, cmd: String, spec: CommandSpec, priority: CommandPriority) -> Result<CommandResponse, SerialError> {
+        self.submit(cmd, None, spec, priority).await
+    }
+    async fn submit(&self, cmd: String, frame: Option<Vec<u8>>, mut spec: CommandSpec, priority: CommandPriority) -> Result<CommandResponse, SerialError> {
         use tokio::sync::oneshot;
+        spec.timeout = crate::serial::connection::scale_timeout(spec.timeout, self.timeout_factor);
         let (tx, rx) = oneshot::channel();
-        self.cmd_tx.send(SerialCommand::Write { cmd, spec, responder: tx }).await.map_err(|_| SerialError::ProtocolError("Command channel closed".into()))?;
+        self.cmd_tx.send(SerialCommand::Write { cmd, frame, spec, priority, responder: tx }).await.map_err(|_| SerialError::ProtocolError("Command channel closed".into()))?;
         rx.await.map_err(|_| SerialError::ProtocolError("Response dropped".into()))?
     }
 }
@@ -39,10 +106,11 @@ impl UnifiedSerialBuilder {
         let (events_tx, _events_rx) = broadcast::channel(self.event_capacity);
     let (snapshot_tx, snapshot_rx) = watch::channel(Arc::new(RawStateSnapshot::default()));
     let (metrics_tx, metrics_rx) = watch::channel(MetricsSnapshot::default());
+    let (progress_tx, progress_rx) = watch::channel(ReceiveProgress::default());
 
-    tokio::spawn(reader_task(self.interface.clone(), cmd_rx, events_tx.clone(), snapshot_tx, metrics_tx));
+    tokio::spawn(reader_task(self.interface.clone(), cmd_rx, events_tx.clone(), snapshot_tx, metrics_tx, progress_tx));
 
-    UnifiedSerialHandle { cmd_tx, events_tx, snapshot_rx, metrics_rx }
+    UnifiedSerialHandle { cmd_tx, events_tx, snapshot_rx, metrics_rx, progress_rx, retry_stats: Arc::new(RetryStats::default()), timeout_factor: 1.0 }
     }
 }
 
@@ -52,25 +120,28 @@ pub(crate) async fn reader_task(
     events_tx: broadcast::Sender<ParsedEvent>,
     snapshot_tx: watch::Sender<Arc<RawStateSnapshot>>,
     metrics_tx: watch::Sender<MetricsSnapshot>,
+    progress_tx: watch::Sender<ReceiveProgress>,
 ) {
     use tokio::select;
     use tokio::time::sleep;
 
-    let mut partial = String::new();
+    let mut frames = FrameSplitter::new();
+    let mut utf8_tail = Vec::new();
+    let mut partial = BoundedTextBuffer::new("unified_partial_line", PARTIAL_BUFFER_MAX_BYTES, PARTIAL_BUFFER_KEEP_BYTES);
     let mut pending: Option<PendingCommand> = None;
+    let mut queue = CommandQueue::new();
     let mut snapshot = Arc::new(RawStateSnapshot::default());
-    let monitor_prefixes = ["GPIO_STATES:", "MATRIX_STATE:", "SHIFT_REG:"];
+    let monitor_prefixes = MONITOR_LINE_PREFIXES;
     let mut metrics = MetricsSnapshot::default();
+    let tracer = get_protocol_tracer();
 
     loop {
         select! {
             maybe_cmd = cmd_rx.recv() => {
                 match maybe_cmd {
-                    Some(SerialCommand::Write { cmd, spec, responder }) => {
-                        if pending.is_some() { let _ = responder.send(Err(SerialError::ProtocolError("Another command in flight".into()))); continue; }
-                        let write_line = format!("{}\n", cmd);
-                        if let Err(e) = { let mut guard = interface.lock().await; guard.send_data(write_line.as_bytes()).await } { let _ = responder.send(Err(e)); continue; }
-                        pending = Some(PendingCommand { spec, started: std::time::Instant::now(), responder, buffer: Vec::new() });
+                    Some(SerialCommand::Write { cmd, frame, spec, priority, responder }) => {
+                        queue.push(QueuedCommand { cmd, frame, spec, priority, enqueued: std::time::Instant::now(), responder });
+                        if pending.is_none() { dispatch_next(&interface, &mut queue, &mut pending, &mut metrics, &metrics_tx).await; }
                     },
                     Some(SerialCommand::Shutdown) => { break; },
                     None => break,
@@ -83,40 +154,139 @@ pub(crate) async fn reader_task(
             } => {
                 match read_res {
                     Ok((buf, n)) if n > 0 => {
-                        let chunk_result = std::str::from_utf8(&buf[..n]);
-                        let chunk = match chunk_result { Ok(s) => s.to_string(), Err(_) => { metrics.utf8_decode_errors +=1; String::from_utf8_lossy(&buf[..n]).to_string() } }; 
-                        partial.push_str(&chunk);
-                        let mut idx = 0;
-                        while let Some(pos) = partial[idx..].find(['\n','\r']) {
-                            let abs = idx + pos; let line = partial[..abs].to_string();
-                            if !line.trim().is_empty() { metrics.lines_read +=1; let before = metrics.monitor_events; let before_unclassified = metrics.unclassified_lines; process_line(&line, &events_tx, &mut snapshot, &snapshot_tx, pending.as_mut(), &monitor_prefixes, &mut metrics); if metrics.monitor_events != before || metrics.unclassified_lines != before_unclassified { let _ = metrics_tx.send(metrics.clone()); }
-                if let Some(p) = pending.as_mut() { if !monitor_prefixes.iter().any(|pre| line.starts_with(pre)) { p.buffer.push(line.clone()); if p.spec.matcher.is_complete(&p.buffer) {
-                    // Enforce optional minimum duration before allowing completion (used by tests for latency metrics)
-                    if let Some(min_ms) = p.spec.test_min_duration_ms { if p.started.elapsed().as_millis() < min_ms as u128 { continue; } }
-                    let p_done = pending.take().unwrap(); let latency_ms = p_done.started.elapsed().as_millis() as u64; metrics.command_completed +=1; metrics.command_last_latency_ms = Some(latency_ms); metrics.command_min_latency_ms = Some(match metrics.command_min_latency_ms { Some(m) => m.min(latency_ms), None => latency_ms }); metrics.command_max_latency_ms = Some(match metrics.command_max_latency_ms { Some(m) => m.max(latency_ms), None => latency_ms }); metrics.command_latency_samples +=1; // update avg
-                    metrics.command_avg_latency_ms = Some(match (metrics.command_avg_latency_ms, metrics.command_latency_samples) { (Some(avg), samples) if samples>1 => ((avg * (samples as f64 -1.0)) + latency_ms as f64) / samples as f64, _ => latency_ms as f64 });
-                    metrics.command_ema_latency_ms = Some(match metrics.command_ema_latency_ms { Some(prev) => (prev * 0.8) + (latency_ms as f64 * 0.2), None => latency_ms as f64 });
-                    let _ = metrics_tx.send(metrics.clone()); let resp = CommandResponse { lines: p_done.buffer, finished_reason: FinishReason::MatcherSatisfied }; let _ = p_done.responder.send(Ok(resp)); } } }
-                            }
-                            let mut advance = abs + 1; while advance < partial.len() && (partial.as_bytes()[advance]==b'\n' || partial.as_bytes()[advance]==b'\r') { advance+=1; }
-                            partial.drain(..advance); idx = 0;
+                        let split = frames.push(&buf[..n]);
+                        if let Some(p) = pending.as_ref() { progress_tx.send_modify(|progress| progress.record(p, n, &split.text)); }
+                        if !split.frames.is_empty() { route_frames(split.frames, pending.as_mut(), &mut metrics, &metrics_tx); }
+                        let (chunk, invalid) = decode_utf8(&mut utf8_tail, &split.text);
+                        if invalid { metrics.utf8_decode_errors +=1; }
+                        let dropped = partial.push_str(&chunk);
+                        if dropped > 0 { metrics.partial_buffer_trims +=1; metrics.partial_buffer_dropped_bytes += dropped as u64; let _ = metrics_tx.send(metrics.clone()); }
+                        while let Some(line) = partial.take_line() {
+                            tracer.record(TraceDirection::Rx, &line);
+                            metrics.lines_read +=1; let before = metrics.monitor_events; let before_unclassified = metrics.unclassified_lines; process_line(&line, &events_tx, &mut snapshot, &snapshot_tx, pending.as_mut(), &monitor_prefixes, &mut metrics); if metrics.monitor_events != before || metrics.unclassified_lines != before_unclassified { let _ = metrics_tx.send(metrics.clone()); }
+                            if let Some(p) = pending.as_mut() { if p.spec.collects_monitor_lines() || !monitor_prefixes.iter().any(|pre| line.starts_with(pre)) { p.push_line(line); } }
+                            if pending_ready(pending.as_ref()) { finish_pending(&mut pending, &mut metrics, &metrics_tx); }
                         }
-                        if partial.len() > 8192 { partial = partial[partial.len()-4096..].to_string(); metrics.partial_buffer_trims +=1; let _ = metrics_tx.send(metrics.clone()); }
+                        if pending.is_none() { dispatch_next(&interface, &mut queue, &mut pending, &mut metrics, &metrics_tx).await; }
                     },
                     Ok(_) => {},
                     Err(SerialError::Timeout) => {},
                     Err(e) => { let msg = format!("IO error: {}", e); let _ = events_tx.send(ParsedEvent::ProtocolNotice { message: msg.clone() }); metrics.last_error = Some(msg.clone()); let _ = metrics_tx.send(metrics.clone()); if let Some(p) = pending.take() { let _ = p.responder.send(Err(e)); } break; }
                 }
             },
-            _ = sleep(Duration::from_millis(5)) => { if let Some(p) = pending.as_mut() { if p.started.elapsed() > p.spec.timeout { let p_done = pending.take().unwrap(); metrics.command_timeouts +=1; let _ = metrics_tx.send(metrics.clone());
+            _ = sleep(Duration::from_millis(5)) => {
+                // Completion deferred by a minimum duration (tests) is released here
+                if pending_ready(pending.as_ref()) { finish_pending(&mut pending, &mut metrics, &metrics_tx); }
+                if let Some(p) = pending.as_mut() { if p.started.elapsed() > p.spec.timeout { let p_done = pending.take().unwrap(); metrics.command_timeouts +=1; let _ = metrics_tx.send(metrics.clone());
                 // Diagnostic log with partial buffer for troubleshooting timeouts
                 if !p_done.buffer.is_empty() { log::warn!("Command '{}' timeout after {:?}; partial lines: {:?}", p_done.spec.name, p_done.spec.timeout, p_done.buffer); } else { log::warn!("Command '{}' timeout after {:?}; no lines received", p_done.spec.name, p_done.spec.timeout); }
-                let _ = p_done.responder.send(Err(SerialError::Timeout)); } } }
+                if p_done.spec.keeps_partial_reply() {
+                    let resp = CommandResponse { lines: p_done.buffer, finished_reason: FinishReason::Timeout, frames: p_done.frames };
+                    let _ = p_done.responder.send(Ok(resp));
+                } else {
+                    let _ = p_done.responder.send(Err(SerialError::Timeout));
+                } } }
+                if pending.is_none() { dispatch_next(&interface, &mut queue, &mut pending, &mut metrics, &metrics_tx).await; } else { expire_queued(&mut queue, &mut metrics, &metrics_tx); }
+            }
         }
     }
     if let Some(p) = pending.take() { let _ = p.responder.send(Err(SerialError::ProtocolError("Reader terminated".into()))); }
+    queue.fail_all(|| SerialError::ProtocolError("Reader terminated".into()));
 }
 
+/// Decode `text` following the bytes kept in `tail`. A multi-byte character cut off at the end
+/// goes back to `tail` to complete with the next read; true when invalid bytes were replaced.
+fn decode_utf8(tail: &mut Vec<u8>, text: &[u8]) -> (String, bool) {
+    let mut data = std::mem::take(tail);
+    data.extend_from_slice(text);
+    let keep = incomplete_utf8_suffix(&data);
+    *tail = data.split_off(data.len() - keep);
+    match String::from_utf8(data) {
+        Ok(s) => (s, false),
+        Err(e) => (String::from_utf8_lossy(e.as_bytes()).into_owned(), true),
+    }
+}
+
+/// Length of a UTF-8 sequence started in the last three bytes of `data` but not finished
+fn incomplete_utf8_suffix(data: &[u8]) -> usize {
+    for back in 1..=data.len().min(3) {
+        let byte = data[data.len() - back];
+        if byte & 0xC0 == 0x80 {
+            continue;
+        }
+        let needed = match byte { 0xC0..=0xDF => 2, 0xE0..=0xEF => 3, 0xF0..=0xF7 => 4, _ => 1 };
+        return if needed > back { back } else { 0 };
+    }
+    0
+}
+
+/// Write the highest-priority queued command; commands that fail to write are answered with the error
+async fn dispatch_next(
+    interface: &Arc<Mutex<SerialInterface>>,
+    queue: &mut CommandQueue,
+    pending: &mut Option<PendingCommand>,
+    metrics: &mut MetricsSnapshot,
+    metrics_tx: &watch::Sender<MetricsSnapshot>,
+) {
+    let tracer = get_protocol_tracer();
+    while pending.is_none() {
+        let (next, expired) = queue.next(std::time::Instant::now());
+        record_expired(expired, queue.len(), metrics, metrics_tx);
+        let Some(QueuedCommand { cmd, frame, spec, responder, .. }) = next else { return; };
+        let mut bytes = if cmd.is_empty() { Vec::new() } else { format!("{}\n", cmd).into_bytes() };
+        if let Some(frame) = &frame { bytes.extend_from_slice(frame); }
+        if let Err(e) = { let mut guard = interface.lock().await; guard.send_data(&bytes).await } { let _ = responder.send(Err(e)); continue; }
+        if !cmd.is_empty() { tracer.record(TraceDirection::Tx, &cmd); }
+        if let Some(frame) = &frame { tracer.record(TraceDirection::Tx, &format!("<frame {} bytes>", frame.len())); }
+        *pending = Some(PendingCommand::new(spec, responder));
+    }
+}
+
+/// Hand decoded frames to the pending command; frames nobody waits for are dropped
+fn route_frames(decoded: Vec<Result<Vec<u8>, String>>, mut pending: Option<&mut PendingCommand>, metrics: &mut MetricsSnapshot, metrics_tx: &watch::Sender<MetricsSnapshot>) {
+    for frame in decoded {
+        match frame {
+            Ok(payload) => {
+                metrics.frames_received += 1;
+                get_protocol_tracer().record(TraceDirection::Rx, &format!("<frame {} bytes>", payload.len()));
+                if let Some(p) = pending.as_deref_mut() { p.frames.push(payload); }
+            }
+            Err(reason) => { metrics.frame_errors += 1; log::warn!("Dropped binary frame: {}", reason); }
+        }
+    }
+    let _ = metrics_tx.send(metrics.clone());
+}
+
+/// Expire queued commands while another one is in flight
+fn expire_queued(queue: &mut CommandQueue, metrics: &mut MetricsSnapshot, metrics_tx: &watch::Sender<MetricsSnapshot>) {
+    if queue.is_empty() { return; }
+    let expired = queue.expire(std::time::Instant::now());
+    record_expired(expired, queue.len(), metrics, metrics_tx);
+}
+
+fn record_expired(expired: super::queue::Expired, depth: usize, metrics: &mut MetricsSnapshot, metrics_tx: &watch::Sender<MetricsSnapshot>) {
+    let changed = expired.timed_out > 0 || expired.cancelled > 0 || metrics.command_queue_depth != depth as u64;
+    metrics.command_queue_timeouts += expired.timed_out as u64;
+    metrics.command_queue_cancelled += expired.cancelled as u64;
+    metrics.command_queue_depth = depth as u64;
+    if changed { let _ = metrics_tx.send(metrics.clone()); }
+}
+
+
+/// Pending command has a complete response and satisfied any minimum duration
+fn pending_ready(pending: Option<&PendingCommand>) -> bool {
+    let Some(p) = pending else { return false; };
+    if !p.is_complete() { return false; }
+    match p.spec.test_min_duration_ms { Some(min_ms) => p.started.elapsed().as_millis() >= min_ms as u128, None => true }
+}
+
+fn finish_pending(pending: &mut Option<PendingCommand>, metrics: &mut MetricsSnapshot, metrics_tx: &watch::Sender<MetricsSnapshot>) {
+    let Some(p_done) = pending.take() else { return; };
+    let latency_ms = p_done.started.elapsed().as_millis() as u64; metrics.command_completed +=1; metrics.command_last_latency_ms = Some(latency_ms); metrics.command_min_latency_ms = Some(match metrics.command_min_latency_ms { Some(m) => m.min(latency_ms), None => latency_ms }); metrics.command_max_latency_ms = Some(match metrics.command_max_latency_ms { Some(m) => m.max(latency_ms), None => latency_ms }); metrics.command_latency_samples +=1; // update avg
+    metrics.command_avg_latency_ms = Some(match (metrics.command_avg_latency_ms, metrics.command_latency_samples) { (Some(avg), samples) if samples>1 => ((avg * (samples as f64 -1.0)) + latency_ms as f64) / samples as f64, _ => latency_ms as f64 });
+    metrics.command_ema_latency_ms = Some(match metrics.command_ema_latency_ms { Some(prev) => (prev * 0.8) + (latency_ms as f64 * 0.2), None => latency_ms as f64 });
+    let _ = metrics_tx.send(metrics.clone()); let resp = CommandResponse { lines: p_done.buffer, finished_reason: FinishReason::MatcherSatisfied, frames: p_done.frames }; let _ = p_done.responder.send(Ok(resp));
+}
 
 fn process_line(
     line: &str,
@@ -134,11 +304,11 @@ fn process_line(
             let mut updated = (**snapshot).clone();
             let mut changed = false;
             match &evt {
-                ParsedEvent::Gpio { mask, timestamp } => { updated.gpio_mask = *mask; updated.last_update_us = *timestamp; updated.seq +=1; changed = true; },
+                ParsedEvent::Gpio { mask, timestamp } => { updated.gpio_mask = *mask; updated.gpio_update_us = Some(*timestamp); updated.last_update_us = *timestamp; updated.seq +=1; changed = true; },
                 ParsedEvent::MatrixDelta { row, col, is_connected, timestamp } => {
                     // replace or insert
                     if let Some(cell) = updated.matrix.iter_mut().find(|c| c.row==*row && c.col==*col) { cell.is_connected = *is_connected; } else { updated.matrix.push(super::types::MatrixCell { row:*row, col:*col, is_connected:*is_connected }); }
-                    updated.last_update_us = *timestamp; updated.seq +=1; changed = true;
+                    updated.matrix_update_us = Some(*timestamp); updated.last_update_us = *timestamp; updated.seq +=1; changed = true;
                 },
                 ParsedEvent::Shift { register_id, value, timestamp } => {
                     if let Some(reg) = updated.shift_regs.iter_mut().find(|r| r.register_id==*register_id) { reg.value = *value; reg.timestamp = *timestamp; } else { updated.shift_regs.push(super::types::ShiftRegEntry { register_id:*register_id, value:*value, timestamp:*timestamp }); }
@@ -159,6 +329,14 @@ fn process_line(
     }
 }
 
+/// Prefixes of the lines streamed while raw monitoring is on; they can arrive in the middle of
+/// any command response
+pub const MONITOR_LINE_PREFIXES: [&str; 3] = ["GPIO_STATES:", "MATRIX_STATE:", "SHIFT_REG:"];
+
+pub fn is_monitor_line(line: &str) -> bool {
+    MONITOR_LINE_PREFIXES.iter().any(|pre| line.starts_with(pre))
+}
+
 pub fn parse_monitor_line(line: &str) -> Option<ParsedEvent> {
     if let Some(rest) = line.strip_prefix("GPIO_STATES:") {
         let parts: Vec<&str> = rest.split(':').collect();
@@ -185,9 +363,9 @@ pub fn test_drive_lines(lines: &[&str], matcher: super::types::ResponseMatcher)
     use tokio::sync::oneshot;
     let (tx, mut rx) = oneshot::channel();
     let spec = CommandSpec { name: "TEST", timeout: Duration::from_millis(100), matcher, test_min_duration_ms: None };
-    let mut pending = Some(PendingCommand { spec: spec.clone(), started: Instant::now(), responder: tx, buffer: Vec::new() });
+    let mut pending = Some(PendingCommand { spec: spec.clone(), started: Instant::now(), responder: tx, buffer: Vec::new(), last_line_at: None, frames: Vec::new() });
     let mut metrics = MetricsSnapshot::default();
-    let monitor_prefixes = ["GPIO_STATES:", "MATRIX_STATE:", "SHIFT_REG:"];
+    let monitor_prefixes = MONITOR_LINE_PREFIXES;
     // Dummy channels for snapshot/events
     let (events_tx, _events_rx) = broadcast::channel(16);
     let (snapshot_tx, snapshot_rx) = watch::channel(Arc::new(RawStateSnapshot::default()));
@@ -198,7 +376,7 @@ pub fn test_drive_lines(lines: &[&str], matcher: super::types::ResponseMatcher)
         if !monitor_prefixes.iter().any(|pre| line.starts_with(pre)) {
             if let Some(p) = pending.as_mut() { p.buffer.push((*line).to_string()); if p.spec.matcher.is_complete(&p.buffer) {
                 if let Some(min_ms) = p.spec.test_min_duration_ms { if p.started.elapsed().as_millis() < min_ms as u128 { deferred_completion = true; continue; } }
-                let p_done = pending.take().unwrap(); let resp = CommandResponse { lines: p_done.buffer, finished_reason: FinishReason::MatcherSatisfied }; metrics.command_completed +=1; let _ = p_done.responder.send(Ok(resp)); break; } }
+                let p_done = pending.take().unwrap(); let resp = CommandResponse { lines: p_done.buffer, finished_reason: FinishReason::MatcherSatisfied, frames: Vec::new() }; metrics.command_completed +=1; let _ = p_done.responder.send(Ok(resp)); break; } }
         } else {
             process_line(line, &events_tx, &mut snapshot, &snapshot_tx, pending.as_mut(), &monitor_prefixes, &mut metrics);
         }
@@ -207,7 +385,7 @@ pub fn test_drive_lines(lines: &[&str], matcher: super::types::ResponseMatcher)
     if deferred_completion {
         if let Some(p) = pending.take() {
             if let Some(min_ms) = p.spec.test_min_duration_ms { while p.started.elapsed().as_millis() < min_ms as u128 { std::thread::sleep(std::time::Duration::from_millis(1)); }
-                let resp = CommandResponse { lines: p.buffer, finished_reason: FinishReason::MatcherSatisfied }; metrics.command_completed +=1; let _ = p.responder.send(Ok(resp)); }
+                let resp = CommandResponse { lines: p.buffer, finished_reason: FinishReason::MatcherSatisfied, frames: Vec::new() }; metrics.command_completed +=1; let _ = p.responder.send(Ok(resp)); }
         }
     }
     let completed = metrics.command_completed;
@@ -223,19 +401,19 @@ pub fn test_drive_lines_with_min(lines: &[&str], matcher: super::types::Response
     let (tx, mut rx) = oneshot::channel();
     let spec = CommandSpec { name: "TEST", timeout: Duration::from_millis(min_ms+100), matcher, test_min_duration_ms: Some(min_ms) };
     let start = Instant::now();
-    let mut pending = Some(PendingCommand { spec: spec.clone(), started: start, responder: tx, buffer: Vec::new() });
+    let mut pending = Some(PendingCommand { spec: spec.clone(), started: start, responder: tx, buffer: Vec::new(), last_line_at: None, frames: Vec::new() });
     let mut metrics = MetricsSnapshot::default();
-    let monitor_prefixes = ["GPIO_STATES:", "MATRIX_STATE:", "SHIFT_REG:"];
+    let monitor_prefixes = MONITOR_LINE_PREFIXES;
     let (events_tx, _events_rx) = broadcast::channel(16);
     let (snapshot_tx, snapshot_rx) = watch::channel(Arc::new(RawStateSnapshot::default()));
     let mut snapshot = snapshot_rx.borrow().clone();
     let mut deferred = false;
     for line in lines {
         if !monitor_prefixes.iter().any(|pre| line.starts_with(pre)) {
-            if let Some(p) = pending.as_mut() { p.buffer.push((*line).to_string()); if p.spec.matcher.is_complete(&p.buffer) { if p.started.elapsed().as_millis() < min_ms as u128 { deferred = true; continue; } let p_done = pending.take().unwrap(); let resp = CommandResponse { lines: p_done.buffer, finished_reason: FinishReason::MatcherSatisfied }; metrics.command_completed +=1; let _ = p_done.responder.send(Ok(resp)); break; } }
+            if let Some(p) = pending.as_mut() { p.buffer.push((*line).to_string()); if p.spec.matcher.is_complete(&p.buffer) { if p.started.elapsed().as_millis() < min_ms as u128 { deferred = true; continue; } let p_done = pending.take().unwrap(); let resp = CommandResponse { lines: p_done.buffer, finished_reason: FinishReason::MatcherSatisfied, frames: Vec::new() }; metrics.command_completed +=1; let _ = p_done.responder.send(Ok(resp)); break; } }
         } else { process_line(line, &events_tx, &mut snapshot, &snapshot_tx, pending.as_mut(), &monitor_prefixes, &mut metrics); }
     }
-    if deferred { if let Some(p) = pending.take() { while p.started.elapsed().as_millis() < min_ms as u128 { std::thread::sleep(Duration::from_millis(1)); } let elapsed = p.started.elapsed().as_millis() as u64; let resp = CommandResponse { lines: p.buffer, finished_reason: FinishReason::MatcherSatisfied }; metrics.command_completed +=1; let _ = p.responder.send(Ok(resp)); return (metrics.command_completed as usize, rx.try_recv().is_ok(), elapsed); } }
+    if deferred { if let Some(p) = pending.take() { while p.started.elapsed().as_millis() < min_ms as u128 { std::thread::sleep(Duration::from_millis(1)); } let elapsed = p.started.elapsed().as_millis() as u64; let resp = CommandResponse { lines: p.buffer, finished_reason: FinishReason::MatcherSatisfied, frames: Vec::new() }; metrics.command_completed +=1; let _ = p.responder.send(Ok(resp)); return (metrics.command_completed as usize, rx.try_recv().is_ok(), elapsed); } }
     let elapsed = start.elapsed().as_millis() as u64;
     (metrics.command_completed as usize, rx.try_recv().is_ok(), elapsed)
 }
@@ -0,0 +1,125 @@
+//! Retries for commands that time out on a flaky link.
+//!
+//! Only [`SerialError::Timeout`] is retried: the device never answered, so resending an
+//! idempotent query is safe. Errors reported by the device, I/O failures and commands that
+//! expired in the queue are returned as they are. Delays double from `initial_delay` up to
+//! `max_delay`, each stretched by up to `jitter` of itself so several retrying callers do not
+//! resend in lockstep.
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::serial::SerialError;
+
+/// Read-only queries retried by default when sent through `UnifiedSerialHandle::send_command`
+const RETRIED_BY_DEFAULT: &[&str] = &[
+    "STATUS", "CAPABILITIES", "HID_MAPPING_INFO", "HID_BUTTON_MAP", "STORAGE_INFO", "LIST_FILES", "AXIS_GET", "BUTTON_GET",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RetryPolicy {
+    /// Total attempts including the first; 1 disables retries
+    pub max_attempts: u32,
+    pub initial_delay: Duration,
+    pub max_delay: Duration,
+    /// Fraction (0..=1) of each delay added at random
+    pub jitter: f64,
+}
+
+impl RetryPolicy {
+    pub const NONE: RetryPolicy = RetryPolicy { max_attempts: 1, initial_delay: Duration::ZERO, max_delay: Duration::ZERO, jitter: 0.0 };
+    pub const DEFAULT: RetryPolicy = RetryPolicy {
+        max_attempts: 3,
+        initial_delay: Duration::from_millis(100),
+        max_delay: Duration::from_secs(1),
+        jitter: 0.2,
+    };
+
+    /// Default policy for a command: idempotent queries retry, everything else is sent once
+    pub fn for_command(name: &str) -> Self {
+        if RETRIED_BY_DEFAULT.contains(&name) { Self::DEFAULT } else { Self::NONE }
+    }
+
+    pub fn should_retry(&self, attempt: u32, error: &SerialError) -> bool {
+        attempt < self.max_attempts && matches!(error, SerialError::Timeout)
+    }
+
+    /// Delay before attempt `attempt + 1` (attempts count from 1), without jitter
+    pub fn base_delay(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.initial_delay.saturating_mul(factor).min(self.max_delay)
+    }
+
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let base = self.base_delay(attempt);
+        base + base.mul_f64(self.jitter.clamp(0.0, 1.0) * random_fraction())
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Value in 0..1; randomness only spreads retries out, so a hasher seed is enough
+fn random_fraction() -> f64 {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u128(std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_nanos());
+    (hasher.finish() % 10_000) as f64 / 10_000.0
+}
+
+/// Retry counters shared by all clones of a handle
+#[derive(Debug, Default)]
+pub struct RetryStats {
+    /// Resends after a timeout
+    pub retries: AtomicU64,
+    /// Commands that succeeded after at least one retry
+    pub recovered: AtomicU64,
+    /// Commands that still timed out after their last attempt
+    pub exhausted: AtomicU64,
+}
+
+impl RetryStats {
+    pub fn record(&self, attempts: u32, result: &Result<super::types::CommandResponse, SerialError>) {
+        if attempts <= 1 {
+            return;
+        }
+        self.retries.fetch_add(u64::from(attempts - 1), Ordering::Relaxed);
+        match result {
+            Ok(_) => self.recovered.fetch_add(1, Ordering::Relaxed),
+            Err(_) => self.exhausted.fetch_add(1, Ordering::Relaxed),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delays_double_up_to_the_cap() {
+        let policy = RetryPolicy { max_attempts: 6, initial_delay: Duration::from_millis(100), max_delay: Duration::from_millis(500), jitter: 0.0 };
+        let delays: Vec<u64> = (1..=5).map(|a| policy.delay(a).as_millis() as u64).collect();
+        assert_eq!(delays, vec![100, 200, 400, 500, 500]);
+
+        let jittered = RetryPolicy { jitter: 0.5, ..policy };
+        for _ in 0..20 {
+            let d = jittered.delay(2);
+            assert!(d >= Duration::from_millis(200) && d <= Duration::from_millis(300), "{:?}", d);
+        }
+    }
+
+    #[test]
+    fn retries_only_timeouts_of_default_queries() {
+        let policy = RetryPolicy::for_command("STATUS");
+        assert!(policy.should_retry(1, &SerialError::Timeout));
+        assert!(policy.should_retry(2, &SerialError::Timeout));
+        assert!(!policy.should_retry(3, &SerialError::Timeout));
+        assert!(!policy.should_retry(1, &SerialError::ProtocolError("ERROR:bad".into())));
+        assert!(!RetryPolicy::for_command("SAVE_CONFIG").should_retry(1, &SerialError::Timeout));
+    }
+}
@@ -0,0 +1,311 @@
+//! Property tests of `reader_task` against a scripted in-memory port.
+//!
+//! [`ScriptedPort`] hands out bytes in exactly the chunks it was given, one chunk per read, so
+//! tests control where reads split lines and characters. Each command line written pops the next
+//! scripted reply. Inputs come from a seeded generator, so failures reproduce.
+use std::collections::VecDeque;
+use std::io::{self, Read, Write};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serialport::{ClearBuffer, DataBits, FlowControl, Parity, SerialPort, StopBits};
+
+use super::queue::CommandPriority;
+use super::reader::{UnifiedSerialBuilder, UnifiedSerialHandle, PARTIAL_BUFFER_MAX_BYTES};
+use super::types::{CommandResponse, CommandSpec, MetricsSnapshot, ResponseMatcher};
+use crate::serial::{SerialDeviceInfo, SerialError, SerialInterface};
+
+#[derive(Debug, Default)]
+struct Script {
+    /// Chunks returned by upcoming reads
+    rx: VecDeque<Vec<u8>>,
+    /// Reply chunks queued for each command line written, in order
+    replies: VecDeque<Vec<Vec<u8>>>,
+    tx_line: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct ScriptedPort {
+    script: Arc<Mutex<Script>>,
+}
+
+impl ScriptedPort {
+    fn new() -> Self {
+        Self::default()
+    }
+
+    /// Answer the next command line with `chunks`
+    fn reply(self, chunks: Vec<Vec<u8>>) -> Self {
+        self.lock().replies.push_back(chunks);
+        self
+    }
+
+    /// Bytes sent before any command
+    fn unsolicited(self, chunks: Vec<Vec<u8>>) -> Self {
+        self.lock().rx.extend(chunks);
+        self
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Script> {
+        self.script.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Read for ScriptedPort {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut script = self.lock();
+        let Some(chunk) = script.rx.front_mut() else {
+            return Err(io::Error::new(io::ErrorKind::TimedOut, "no data"));
+        };
+        let n = buf.len().min(chunk.len());
+        buf[..n].copy_from_slice(&chunk[..n]);
+        chunk.drain(..n);
+        if chunk.is_empty() {
+            script.rx.pop_front();
+        }
+        Ok(n)
+    }
+}
+
+impl Write for ScriptedPort {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut script = self.lock();
+        for &byte in buf {
+            if byte != b'\n' {
+                script.tx_line.push(byte);
+                continue;
+            }
+            script.tx_line.clear();
+            if let Some(reply) = script.replies.pop_front() {
+                script.rx.extend(reply.into_iter().filter(|chunk| !chunk.is_empty()));
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl SerialPort for ScriptedPort {
+    fn name(&self) -> Option<String> { Some("SCRIPTED".to_string()) }
+    fn baud_rate(&self) -> serialport::Result<u32> { Ok(115200) }
+    fn data_bits(&self) -> serialport::Result<DataBits> { Ok(DataBits::Eight) }
+    fn flow_control(&self) -> serialport::Result<FlowControl> { Ok(FlowControl::None) }
+    fn parity(&self) -> serialport::Result<Parity> { Ok(Parity::None) }
+    fn stop_bits(&self) -> serialport::Result<StopBits> { Ok(StopBits::One) }
+    fn timeout(&self) -> Duration { Duration::from_millis(10) }
+    fn set_baud_rate(&mut self, _: u32) -> serialport::Result<()> { Ok(()) }
+    fn set_data_bits(&mut self, _: DataBits) -> serialport::Result<()> { Ok(()) }
+    fn set_flow_control(&mut self, _: FlowControl) -> serialport::Result<()> { Ok(()) }
+    fn set_parity(&mut self, _: Parity) -> serialport::Result<()> { Ok(()) }
+    fn set_stop_bits(&mut self, _: StopBits) -> serialport::Result<()> { Ok(()) }
+    fn set_timeout(&mut self, _: Duration) -> serialport::Result<()> { Ok(()) }
+    fn write_request_to_send(&mut self, _: bool) -> serialport::Result<()> { Ok(()) }
+    fn write_data_terminal_ready(&mut self, _: bool) -> serialport::Result<()> { Ok(()) }
+    fn read_clear_to_send(&mut self) -> serialport::Result<bool> { Ok(true) }
+    fn read_data_set_ready(&mut self) -> serialport::Result<bool> { Ok(true) }
+    fn read_ring_indicator(&mut self) -> serialport::Result<bool> { Ok(false) }
+    fn read_carrier_detect(&mut self) -> serialport::Result<bool> { Ok(true) }
+    fn bytes_to_read(&self) -> serialport::Result<u32> {
+        Ok(self.lock().rx.front().map_or(0, |chunk| chunk.len() as u32))
+    }
+    fn bytes_to_write(&self) -> serialport::Result<u32> { Ok(0) }
+    fn clear(&self, _: ClearBuffer) -> serialport::Result<()> { Ok(()) }
+    fn try_clone(&self) -> serialport::Result<Box<dyn SerialPort>> { Ok(Box::new(self.clone())) }
+    fn set_break(&self) -> serialport::Result<()> { Ok(()) }
+    fn clear_break(&self) -> serialport::Result<()> { Ok(()) }
+}
+
+/// xorshift64; deterministic per seed
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+
+    /// `data` cut at random points into chunks of 1..=`max` bytes
+    fn split(&mut self, data: &[u8], max: u64) -> Vec<Vec<u8>> {
+        let mut chunks = Vec::new();
+        let mut rest = data;
+        while !rest.is_empty() {
+            let n = (1 + self.below(max) as usize).min(rest.len());
+            chunks.push(rest[..n].to_vec());
+            rest = &rest[n..];
+        }
+        chunks
+    }
+
+    /// A valid monitor line and whether it is a GPIO mask (with the mask)
+    fn monitor_line(&mut self, timestamp: u64) -> (String, Option<u32>) {
+        match self.below(3) {
+            0 => {
+                let mask = self.next() as u32;
+                (format!("GPIO_STATES:0x{:08X}:{}", mask, timestamp), Some(mask))
+            }
+            1 => (format!("MATRIX_STATE:{}:{}:{}:{}", self.below(8), self.below(8), self.below(2), timestamp), None),
+            _ => (format!("SHIFT_REG:{}:0x{:02X}:{}", self.below(4), self.below(256), timestamp), None),
+        }
+    }
+}
+
+fn start(port: &ScriptedPort) -> UnifiedSerialHandle {
+    let info = SerialDeviceInfo {
+        port_name: "SCRIPTED".to_string(),
+        vid: 0,
+        pid: 0,
+        serial_number: None,
+        manufacturer: None,
+        product: None,
+        firmware_version: None,
+        device_signature: None,
+        hardware_revision: None,
+    };
+    let interface = SerialInterface::from_port(Box::new(port.clone()), info);
+    UnifiedSerialBuilder { interface: Arc::new(tokio::sync::Mutex::new(interface)), event_capacity: 256, command_capacity: 16 }.build()
+}
+
+/// Send without retries so every scripted reply is consumed once
+async fn send(handle: &UnifiedSerialHandle, name: &'static str, matcher: ResponseMatcher) -> Result<CommandResponse, SerialError> {
+    let spec = CommandSpec { name, timeout: Duration::from_secs(2), matcher, test_min_duration_ms: None };
+    handle.send_command_with_priority(name.to_string(), spec, CommandPriority::Config).await
+}
+
+async fn wait_for_metrics(handle: &UnifiedSerialHandle, done: impl Fn(&MetricsSnapshot) -> bool) -> MetricsSnapshot {
+    let mut metrics = handle.metrics_receiver();
+    let wait = async {
+        loop {
+            let current = metrics.borrow_and_update().clone();
+            if done(&current) || metrics.changed().await.is_err() {
+                return current;
+            }
+        }
+    };
+    tokio::time::timeout(Duration::from_secs(5), wait).await.expect("reader metrics did not converge")
+}
+
+#[tokio::test]
+async fn split_utf8_decodes_at_every_boundary() {
+    let reply = "NAME:Jöystick ✈ 𝕏\r\n".as_bytes();
+    for cut in 1..reply.len() {
+        let port = ScriptedPort::new().reply(vec![reply[..cut].to_vec(), reply[cut..].to_vec()]);
+        let handle = start(&port);
+        let response = send(&handle, "GET_NAME", ResponseMatcher::UntilPrefix("NAME:")).await.expect("reply");
+        assert_eq!(response.lines, vec!["NAME:Jöystick ✈ 𝕏".to_string()], "cut at byte {}", cut);
+        assert_eq!(handle.metrics_snapshot().utf8_decode_errors, 0, "cut at byte {}", cut);
+    }
+
+    // Invalid bytes and a sequence cut short by ASCII are replaced and counted
+    let port = ScriptedPort::new().reply(vec![b"BAD:\xFF".to_vec(), b"ok\r\nCUT:\xE2\x9C".to_vec(), b"x\r\n".to_vec()]);
+    let handle = start(&port);
+    let response = send(&handle, "GET_NAME", ResponseMatcher::UntilPrefix("CUT:")).await.expect("reply");
+    assert_eq!(response.lines, vec!["BAD:\u{FFFD}ok".to_string(), "CUT:\u{FFFD}x".to_string()]);
+    assert_eq!(handle.metrics_snapshot().utf8_decode_errors, 2);
+}
+
+#[tokio::test]
+async fn interleaved_monitor_lines_never_reach_the_reply() {
+    let mut rng = Rng(0x4A4F_5943);
+    for round in 0..40u64 {
+        let mut expected: Vec<String> = (0..rng.below(6)).map(|i| format!("STATUS:{}:{}", round, i)).collect();
+        expected.push("END".to_string());
+        let mut stream = String::new();
+        let mut monitor_lines = 0u64;
+        let mut last_mask = None;
+        for (i, line) in expected.iter().enumerate() {
+            for _ in 0..rng.below(4) {
+                let (monitor, mask) = rng.monitor_line(round * 100 + i as u64);
+                stream.push_str(&monitor);
+                stream.push_str(if rng.below(2) == 0 { "\r\n" } else { "\n" });
+                monitor_lines += 1;
+                last_mask = mask.or(last_mask);
+            }
+            stream.push_str(line);
+            stream.push_str("\r\n");
+        }
+
+        let port = ScriptedPort::new().reply(rng.split(stream.as_bytes(), 48));
+        let handle = start(&port);
+        let response = send(&handle, "STATUS", ResponseMatcher::Contains("END")).await.expect("reply");
+        assert_eq!(response.lines, expected, "round {}", round);
+
+        let metrics = wait_for_metrics(&handle, |m| m.monitor_events >= monitor_lines).await;
+        assert_eq!((metrics.monitor_events, metrics.utf8_decode_errors), (monitor_lines, 0), "round {}", round);
+        let snapshot = handle.snapshot_receiver().borrow().clone();
+        assert_eq!(snapshot.seq, monitor_lines, "round {}", round);
+        if let Some(mask) = last_mask {
+            assert_eq!(snapshot.gpio_mask, mask, "round {}", round);
+        }
+    }
+}
+
+#[tokio::test]
+async fn malformed_input_does_not_stop_the_reader() {
+    const MALFORMED: [&str; 5] = ["GPIO_STATES:zz:1", "MATRIX_STATE:1:2", "SHIFT_REG:999:0x1FF:x", "GPIO_STATES:", ":::"];
+    const ROUNDS: u64 = 20;
+    let mut rng = Rng(0xBAD5_EED5);
+    let mut port = ScriptedPort::new();
+    for _ in 0..ROUNDS {
+        let mut garbage: Vec<u8> = (0..rng.below(512)).map(|_| 1 + rng.below(255) as u8).collect();
+        for line in MALFORMED {
+            garbage.extend(b"\r\n");
+            garbage.extend(line.bytes());
+        }
+        // A frame that fails its length/CRC check
+        garbage.extend([0, 5, 1, 2, 0]);
+        garbage.extend(b"\r\nPONG\r\n");
+        port = port.reply(rng.split(&garbage, 64));
+    }
+    let handle = start(&port);
+    for round in 0..ROUNDS {
+        let response = send(&handle, "PING", ResponseMatcher::Contains("PONG")).await.expect("reader keeps answering");
+        assert_eq!(response.lines.last().map(String::as_str), Some("PONG"), "round {}", round);
+    }
+
+    let metrics = handle.metrics_snapshot();
+    assert_eq!(metrics.command_completed, ROUNDS);
+    assert_eq!(metrics.frame_errors, ROUNDS);
+    assert_eq!(metrics.monitor_events, 0);
+    assert!(metrics.unclassified_lines >= ROUNDS * MALFORMED.len() as u64, "{:?}", metrics);
+    assert!(metrics.utf8_decode_errors > 0, "random bytes include invalid UTF-8");
+}
+
+#[tokio::test]
+async fn giant_bursts_stay_bounded() {
+    let mut rng = Rng(0x0B0B_5EED);
+    let runaway = vec![b'A'; 64 * 1024];
+    let mut reply = rng.split(&runaway, 512);
+    reply.push(b"\r\nDONE\r\n".to_vec());
+    let port = ScriptedPort::new().reply(reply);
+    let handle = start(&port);
+    let response = send(&handle, "DUMP", ResponseMatcher::Contains("DONE")).await.expect("reply after runaway line");
+    assert_eq!(response.lines.last().map(String::as_str), Some("DONE"));
+    assert!(response.lines[0].len() <= PARTIAL_BUFFER_MAX_BYTES);
+    let metrics = handle.metrics_snapshot();
+    assert!(metrics.partial_buffer_trims > 0);
+    assert!(metrics.partial_buffer_dropped_bytes >= (runaway.len() - PARTIAL_BUFFER_MAX_BYTES) as u64, "{:?}", metrics);
+
+    // A burst of monitor lines between commands is applied in order
+    let mut burst = String::new();
+    let mut last_mask = 0;
+    for i in 0..5000u64 {
+        let mask = rng.next() as u32;
+        burst.push_str(&format!("GPIO_STATES:0x{:08X}:{}\r\n", mask, i));
+        last_mask = mask;
+    }
+    let port = ScriptedPort::new().unsolicited(rng.split(burst.as_bytes(), 512));
+    let handle = start(&port);
+    let metrics = wait_for_metrics(&handle, |m| m.monitor_events >= 5000).await;
+    assert_eq!((metrics.monitor_events, metrics.lines_read, metrics.unclassified_lines), (5000, 5000, 0));
+    let snapshot = handle.snapshot_receiver().borrow().clone();
+    assert_eq!((snapshot.seq, snapshot.gpio_mask, snapshot.last_update_us), (5000, last_mask, 4999));
+}
@@ -0,0 +1,237 @@
+//! Protocol trace: every command written and every line read by the unified reader, timestamped.
+//!
+//! Entries are kept in a bounded in-memory ring for the UI and, when a directory is given,
+//! appended as JSONL to `protocol_trace.jsonl`. The file rotates to `protocol_trace.jsonl.1`
+//! once it grows past the size limit. Hooks are cheap no-ops while tracing is disabled.
+use std::collections::VecDeque;
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+pub const TRACE_FILE_NAME: &str = "protocol_trace.jsonl";
+pub const MAX_TRACE_FILE_BYTES: u64 = 2 * 1024 * 1024;
+pub const TRACE_MEMORY_ENTRIES: usize = 2000;
+/// Buffered entries are flushed at least this often
+const FLUSH_EVERY_ENTRIES: u64 = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TraceDirection {
+    /// Command written to the device
+    Tx,
+    /// Line read from the device
+    Rx,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TraceEntry {
+    pub timestamp: DateTime<Utc>,
+    pub direction: TraceDirection,
+    pub line: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProtocolTrace {
+    pub enabled: bool,
+    pub file_path: Option<String>,
+    /// Entries recorded since the last clear, including ones dropped from memory
+    pub total_entries: u64,
+    pub write_error: Option<String>,
+    pub entries: Vec<TraceEntry>,
+}
+
+struct TraceFile {
+    path: PathBuf,
+    writer: BufWriter<File>,
+    bytes: u64,
+}
+
+impl TraceFile {
+    fn open(path: PathBuf) -> std::io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let bytes = file.metadata()?.len();
+        Ok(Self { path, writer: BufWriter::new(file), bytes })
+    }
+
+    fn rotated_path(&self) -> PathBuf {
+        let mut name = self.path.as_os_str().to_os_string();
+        name.push(".1");
+        PathBuf::from(name)
+    }
+}
+
+#[derive(Default)]
+struct TraceState {
+    entries: VecDeque<TraceEntry>,
+    total: u64,
+    file: Option<TraceFile>,
+    write_error: Option<String>,
+}
+
+pub struct ProtocolTracer {
+    enabled: AtomicBool,
+    max_file_bytes: u64,
+    state: Mutex<TraceState>,
+}
+
+impl Default for ProtocolTracer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProtocolTracer {
+    pub fn new() -> Self {
+        Self::with_max_file_bytes(MAX_TRACE_FILE_BYTES)
+    }
+
+    pub fn with_max_file_bytes(max_file_bytes: u64) -> Self {
+        Self { enabled: AtomicBool::new(false), max_file_bytes, state: Mutex::new(TraceState::default()) }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    /// Start tracing; with `dir` the trace is also appended to a file there
+    pub fn enable(&self, dir: Option<&Path>) -> std::io::Result<()> {
+        let mut state = self.lock();
+        if let Some(dir) = dir {
+            if state.file.as_ref().map(|f| f.path.parent()) != Some(Some(dir)) {
+                std::fs::create_dir_all(dir)?;
+                state.file = Some(TraceFile::open(dir.join(TRACE_FILE_NAME))?);
+                state.write_error = None;
+            }
+        }
+        self.enabled.store(true, Ordering::Relaxed);
+        log::info!("Protocol trace enabled");
+        Ok(())
+    }
+
+    pub fn disable(&self) {
+        self.enabled.store(false, Ordering::Relaxed);
+        let mut state = self.lock();
+        if let Some(mut file) = state.file.take() {
+            let _ = file.writer.flush();
+        }
+        log::info!("Protocol trace disabled");
+    }
+
+    pub fn record(&self, direction: TraceDirection, line: &str) {
+        if !self.is_enabled() {
+            return;
+        }
+        let entry = TraceEntry { timestamp: Utc::now(), direction, line: line.trim_end().to_string() };
+        let mut state = self.lock();
+        state.total += 1;
+        let total = state.total;
+        if let Some(file) = state.file.as_mut() {
+            if let Err(e) = Self::append(file, &entry, total, self.max_file_bytes) {
+                log::warn!("Protocol trace file write failed, tracing to memory only: {}", e);
+                state.file = None;
+                state.write_error = Some(e.to_string());
+            }
+        }
+        if state.entries.len() >= TRACE_MEMORY_ENTRIES {
+            state.entries.pop_front();
+        }
+        state.entries.push_back(entry);
+    }
+
+    fn append(file: &mut TraceFile, entry: &TraceEntry, total: u64, max_file_bytes: u64) -> std::io::Result<()> {
+        let mut line = serde_json::to_vec(entry)?;
+        line.push(b'\n');
+        if file.bytes > 0 && file.bytes + line.len() as u64 > max_file_bytes {
+            file.writer.flush()?;
+            std::fs::rename(&file.path, file.rotated_path())?;
+            *file = TraceFile::open(file.path.clone())?;
+        }
+        file.writer.write_all(&line)?;
+        file.bytes += line.len() as u64;
+        if total % FLUSH_EVERY_ENTRIES == 0 {
+            file.writer.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Current trace; `limit` keeps only the newest entries
+    pub fn snapshot(&self, limit: Option<usize>) -> ProtocolTrace {
+        let mut state = self.lock();
+        if let Some(file) = state.file.as_mut() {
+            let _ = file.writer.flush();
+        }
+        let skip = limit.map_or(0, |l| state.entries.len().saturating_sub(l));
+        ProtocolTrace {
+            enabled: self.is_enabled(),
+            file_path: state.file.as_ref().map(|f| f.path.to_string_lossy().to_string()),
+            total_entries: state.total,
+            write_error: state.write_error.clone(),
+            entries: state.entries.iter().skip(skip).cloned().collect(),
+        }
+    }
+
+    /// Drop all entries in memory and truncate the trace file (and its rotation)
+    pub fn clear(&self) -> std::io::Result<()> {
+        let mut state = self.lock();
+        state.entries.clear();
+        state.total = 0;
+        state.write_error = None;
+        if let Some(file) = state.file.as_mut() {
+            let rotated = file.rotated_path();
+            if rotated.exists() {
+                std::fs::remove_file(rotated)?;
+            }
+            let path = file.path.clone();
+            file.writer = BufWriter::new(File::create(&path)?);
+            file.bytes = 0;
+        }
+        Ok(())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, TraceState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+static TRACER: once_cell::sync::Lazy<ProtocolTracer> = once_cell::sync::Lazy::new(ProtocolTracer::new);
+
+/// Global tracer fed by every unified reader
+pub fn get_protocol_tracer() -> &'static ProtocolTracer {
+    &TRACER
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_only_while_enabled_and_rotates_file() {
+        let dir = std::env::temp_dir().join(format!("joycore_trace_{}", uuid::Uuid::new_v4()));
+        let tracer = ProtocolTracer::with_max_file_bytes(300);
+        tracer.record(TraceDirection::Tx, "STATUS");
+        assert_eq!(tracer.snapshot(None).total_entries, 0);
+
+        tracer.enable(Some(&dir)).unwrap();
+        for i in 0..10 {
+            tracer.record(TraceDirection::Tx, "STATUS");
+            tracer.record(TraceDirection::Rx, &format!("Config Status: {}\r\n", i));
+        }
+        let trace = tracer.snapshot(Some(3));
+        assert_eq!(trace.total_entries, 20);
+        assert_eq!(trace.entries.len(), 3);
+        assert_eq!(trace.entries[2].line, "Config Status: 9");
+        assert!(dir.join(format!("{}.1", TRACE_FILE_NAME)).exists());
+        assert!(std::fs::metadata(dir.join(TRACE_FILE_NAME)).unwrap().len() <= 300);
+
+        tracer.clear().unwrap();
+        assert!(tracer.snapshot(None).entries.is_empty());
+        assert!(!dir.join(format!("{}.1", TRACE_FILE_NAME)).exists());
+        tracer.disable();
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -10,6 +10,10 @@ pub struct RawStateSnapshot {
     pub shift_regs: Vec<ShiftRegEntry>,
     pub last_update_us: u64,
     pub seq: u64,
+    /// Firmware timestamp of the last GPIO line; `None` until one arrives
+    pub gpio_update_us: Option<u64>,
+    /// Firmware timestamp of the last matrix line
+    pub matrix_update_us: Option<u64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -18,7 +22,7 @@ pub struct MatrixCell { pub row: u8, pub col: u8, pub is_connected: bool }
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ShiftRegEntry { pub register_id: u8, pub value: u8, pub timestamp: u64 }
 
-impl Default for RawStateSnapshot { fn default() -> Self { Self { gpio_mask:0, matrix:Vec::new(), shift_regs:Vec::new(), last_update_us:0, seq:0 } } }
+impl Default for RawStateSnapshot { fn default() -> Self { Self { gpio_mask:0, matrix:Vec::new(), shift_regs:Vec::new(), last_update_us:0, seq:0, gpio_update_us:None, matrix_update_us:None } } }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ParsedEvent {
@@ -31,7 +35,12 @@ pub enum ParsedEvent {
 
 // Command response container
 #[derive(Debug, Clone)]
-pub struct CommandResponse { pub lines: Vec<String>, pub finished_reason: FinishReason }
+pub struct CommandResponse {
+    pub lines: Vec<String>,
+    pub finished_reason: FinishReason,
+    /// Payloads of binary frames received while the command was pending
+    pub frames: Vec<Vec<u8>>,
+}
 
 #[derive(Debug, Clone)]
 pub enum FinishReason { MatcherSatisfied, Timeout, Error(String) }
@@ -43,6 +52,8 @@ pub enum ResponseMatcher {
     FixedLines(usize),
     Contains(&'static str),
     Custom(fn(&[String]) -> bool),
+    /// At least one line, then silence for the given time (multi-line replies without a terminator)
+    Settled(Duration),
 }
 
 impl ResponseMatcher {
@@ -52,6 +63,8 @@ impl ResponseMatcher {
             ResponseMatcher::FixedLines(n) => lines.len() >= *n,
             ResponseMatcher::Contains(s) => lines.iter().any(|l| l.contains(s)),
             ResponseMatcher::Custom(f) => f(lines),
+            // Needs the arrival time of the last line, see `PendingCommand::is_complete`
+            ResponseMatcher::Settled(_) => false,
         }
     }
 }
@@ -65,16 +78,85 @@ pub struct CommandSpec {
     pub test_min_duration_ms: Option<u64>,
 }
 
+/// Commands whose replies have the same shape as monitor stream lines
+const MONITOR_SHAPED_REPLIES: &[&str] = &["READ_GPIO_STATES", "READ_MATRIX_STATE", "READ_SHIFT_REG"];
+/// Spec name of free-form developer console commands, whose reply can be anything
+pub const CONSOLE_COMMAND: &str = "CONSOLE";
+
+impl CommandSpec {
+    /// Monitor-shaped lines belong to the response instead of being skipped as stream noise
+    pub fn collects_monitor_lines(&self) -> bool {
+        self.name == CONSOLE_COMMAND || MONITOR_SHAPED_REPLIES.contains(&self.name)
+    }
+
+    /// On timeout, answer with the lines received so far ([`FinishReason::Timeout`]) instead of
+    /// [`SerialError::Timeout`]
+    pub fn keeps_partial_reply(&self) -> bool {
+        self.name == CONSOLE_COMMAND
+    }
+}
+
 pub struct PendingCommand {
     pub spec: CommandSpec,
     pub started: std::time::Instant,
     pub responder: tokio::sync::oneshot::Sender<Result<CommandResponse, SerialError>>,
     pub buffer: Vec<String>,
+    pub last_line_at: Option<std::time::Instant>,
+    pub frames: Vec<Vec<u8>>,
+}
+
+impl PendingCommand {
+    pub fn new(spec: CommandSpec, responder: tokio::sync::oneshot::Sender<Result<CommandResponse, SerialError>>) -> Self {
+        Self { spec, started: std::time::Instant::now(), responder, buffer: Vec::new(), last_line_at: None, frames: Vec::new() }
+    }
+
+    pub fn push_line(&mut self, line: String) {
+        self.buffer.push(line);
+        self.last_line_at = Some(std::time::Instant::now());
+    }
+
+    pub fn is_complete(&self) -> bool {
+        match self.spec.matcher {
+            ResponseMatcher::Settled(quiet) => self.last_line_at.is_some_and(|t| t.elapsed() >= quiet),
+            ref matcher => matcher.is_complete(&self.buffer),
+        }
+    }
+}
+
+/// Reply text kept in [`ReceiveProgress::head`]
+pub const RECEIVE_HEAD_BYTES: usize = 128;
+
+/// Bytes received for the command in flight, so long replies (file reads) can report progress
+#[derive(Debug, Clone, Default)]
+pub struct ReceiveProgress {
+    /// Spec name of the command; empty before the first reply bytes arrive
+    pub command: &'static str,
+    /// When the command was written; identifies it
+    pub started: Option<std::time::Instant>,
+    /// Text and frame bytes received since it was written
+    pub bytes: usize,
+    /// Start of the reply text (up to [`RECEIVE_HEAD_BYTES`]), e.g. a FILE_DATA header
+    pub head: String,
+}
+
+impl ReceiveProgress {
+    /// Count `read` bytes, of which `text` is line text, for `pending`
+    pub fn record(&mut self, pending: &PendingCommand, read: usize, text: &[u8]) {
+        if self.started != Some(pending.started) {
+            *self = ReceiveProgress { command: pending.spec.name, started: Some(pending.started), ..Default::default() };
+        }
+        self.bytes += read;
+        if self.head.len() < RECEIVE_HEAD_BYTES {
+            let take = text.len().min(RECEIVE_HEAD_BYTES - self.head.len());
+            self.head.push_str(&String::from_utf8_lossy(&text[..take]));
+        }
+    }
 }
 
 #[derive(Debug)]
 pub enum SerialCommand {
-    Write { cmd: String, spec: CommandSpec, responder: tokio::sync::oneshot::Sender<Result<CommandResponse, SerialError>> },
+    /// `cmd` is written as a line, followed by `frame` when present (an empty `cmd` sends only the frame)
+    Write { cmd: String, frame: Option<Vec<u8>>, spec: CommandSpec, priority: super::queue::CommandPriority, responder: tokio::sync::oneshot::Sender<Result<CommandResponse, SerialError>> },
     Shutdown,
 }
 
@@ -93,6 +175,18 @@ pub struct MetricsSnapshot {
     pub command_ema_latency_ms: Option<f64>,
     pub command_latency_samples: u64,
     pub partial_buffer_trims: u64,
+    pub partial_buffer_dropped_bytes: u64,
     pub unclassified_lines: u64,
     pub utf8_decode_errors: u64,
+    /// Commands waiting behind the one in flight
+    pub command_queue_depth: u64,
+    pub command_queue_timeouts: u64,
+    pub command_queue_cancelled: u64,
+    /// Resends after timeouts, filled in by `UnifiedSerialHandle::metrics_snapshot`
+    pub command_retries: u64,
+    pub command_retry_recoveries: u64,
+    pub command_retry_exhausted: u64,
+    pub frames_received: u64,
+    /// Frames dropped for bad encoding, length or CRC
+    pub frame_errors: u64,
 }
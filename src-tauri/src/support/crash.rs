@@ -0,0 +1,187 @@
+//! Crash reports for backend panics.
+//!
+//! [`install_panic_hook`] runs before the previous hook on every panic, on any thread: it writes
+//! a JSON report (message, location, backtrace, the newest log lines, the last connection state
+//! and serial metrics) to `crashes/` in the app log directory and emits [`BACKEND_PANIC_EVENT`]
+//! while a webview is open. The hook cannot wait on async locks, so the device manager publishes
+//! connection state and the metrics channel here as they change. It does not wait on these
+//! locks either: the panicking thread may hold them, so a report leaves out whatever is locked.
+//! For the same reason the hook never logs: the logger locks its recent-line buffer, which the
+//! panic may have interrupted. It reports to stderr instead.
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard, TryLockError};
+
+use once_cell::sync::{Lazy, OnceCell};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::watch;
+
+use crate::device::Device;
+use crate::logging::LogLine;
+use crate::serial::unified::types::MetricsSnapshot;
+
+pub const BACKEND_PANIC_EVENT: &str = "backend_panic";
+pub const CRASH_DIR_NAME: &str = "crashes";
+/// Log lines included in a report
+pub const CRASH_LOG_LINES: usize = 200;
+
+#[derive(Default)]
+struct CrashContext {
+    device: Option<Device>,
+    metrics: Option<watch::Receiver<MetricsSnapshot>>,
+}
+
+static CONTEXT: Lazy<Mutex<CrashContext>> = Lazy::new(|| Mutex::new(CrashContext::default()));
+static APP_HANDLE: OnceCell<AppHandle> = OnceCell::new();
+static CRASH_DIR: OnceCell<PathBuf> = OnceCell::new();
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CrashReport {
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub app_version: &'static str,
+    pub thread: String,
+    pub message: String,
+    pub location: Option<String>,
+    pub backtrace: String,
+    /// Device of the last connection state change
+    pub device: Option<Device>,
+    pub serial_metrics: Option<MetricsSnapshot>,
+    /// Empty when the log ring was locked at the time of the panic
+    pub logs: Vec<LogLine>,
+}
+
+/// Payload of `backend_panic`
+#[derive(Debug, Clone, Serialize)]
+pub struct BackendPanic {
+    pub thread: String,
+    pub message: String,
+    pub location: Option<String>,
+    /// Written report; None when it could not be saved
+    pub report_path: Option<String>,
+}
+
+/// Record the device after a connection state change
+pub fn record_device(device: Option<Device>) {
+    CONTEXT.lock().unwrap_or_else(|e| e.into_inner()).device = device;
+}
+
+/// Metrics of the open serial connection; None when it closes
+pub fn record_serial_metrics(metrics: Option<watch::Receiver<MetricsSnapshot>>) {
+    CONTEXT.lock().unwrap_or_else(|e| e.into_inner()).metrics = metrics;
+}
+
+/// Emit panics to the frontend and save reports in the app log directory; call during setup
+pub fn attach_app(app: &AppHandle) {
+    let _ = APP_HANDLE.set(app.clone());
+    match app.path().app_log_dir() {
+        Ok(dir) => {
+            let _ = CRASH_DIR.set(dir.join(CRASH_DIR_NAME));
+        }
+        Err(e) => log::warn!("No log directory for crash reports, using the temp directory: {}", e),
+    }
+}
+
+/// Where reports go before the app is attached, or without a log directory
+fn crash_dir() -> PathBuf {
+    CRASH_DIR.get().cloned().unwrap_or_else(|| std::env::temp_dir().join("joycore-x").join(CRASH_DIR_NAME))
+}
+
+/// Lock without waiting; None while held, which may be by the panicking thread itself
+fn try_lock<T>(mutex: &Mutex<T>) -> Option<MutexGuard<'_, T>> {
+    match mutex.try_lock() {
+        Ok(guard) => Some(guard),
+        Err(TryLockError::Poisoned(e)) => Some(e.into_inner()),
+        Err(TryLockError::WouldBlock) => None,
+    }
+}
+
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    payload.downcast_ref::<&str>().map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "<non-string panic payload>".to_string())
+}
+
+impl CrashReport {
+    /// Report for a panic on the current thread
+    pub fn capture(message: String, location: Option<String>) -> Self {
+        let context = try_lock(&CONTEXT);
+        Self {
+            created_at: chrono::Utc::now(),
+            app_version: env!("CARGO_PKG_VERSION"),
+            thread: std::thread::current().name().unwrap_or("<unnamed>").to_string(),
+            message,
+            location,
+            backtrace: std::backtrace::Backtrace::force_capture().to_string(),
+            device: context.as_ref().and_then(|c| c.device.clone()),
+            serial_metrics: context.as_ref().and_then(|c| c.metrics.as_ref()).map(|rx| rx.borrow().clone()),
+            logs: crate::logging::try_recent_logs(CRASH_LOG_LINES).unwrap_or_default(),
+        }
+    }
+
+    /// Write the report as `crash-<timestamp>.json` in `dir`
+    pub fn write(&self, dir: &Path) -> std::io::Result<PathBuf> {
+        std::fs::create_dir_all(dir)?;
+        let path = dir.join(format!("crash-{}.json", self.created_at.format("%Y%m%d-%H%M%S-%3f")));
+        std::fs::write(&path, serde_json::to_vec_pretty(self)?)?;
+        Ok(path)
+    }
+}
+
+/// Write a crash report and notify the frontend on every panic, then run the previous hook
+pub fn install_panic_hook() {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let message = panic_message(info.payload());
+        let location = info.location().map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column()));
+        let report = CrashReport::capture(message.clone(), location.clone());
+        let report_path = match report.write(&crash_dir()) {
+            Ok(path) => Some(path),
+            Err(e) => {
+                eprintln!("Failed to write crash report: {}", e);
+                None
+            }
+        };
+        eprintln!(
+            "Panic on thread '{}' at {}: {} (report: {})",
+            report.thread, location.as_deref().unwrap_or("<unknown>"), message,
+            report_path.as_ref().map_or_else(|| "not written".to_string(), |p| p.display().to_string())
+        );
+        if let Some(app) = APP_HANDLE.get().filter(|app| !app.webview_windows().is_empty()) {
+            let payload = BackendPanic {
+                thread: report.thread.clone(),
+                message,
+                location,
+                report_path: report_path.map(|p| p.display().to_string()),
+            };
+            let _ = app.emit(BACKEND_PANIC_EVENT, &payload);
+        }
+        previous(info);
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_report_with_serial_metrics() {
+        let (tx, rx) = watch::channel(MetricsSnapshot::default());
+        tx.send_modify(|m| m.command_timeouts = 3);
+        record_serial_metrics(Some(rx));
+        let report = CrashReport::capture("boom".to_string(), Some("src/hid/mod.rs:1:1".to_string()));
+        let held = CONTEXT.lock().unwrap();
+        let without_context = CrashReport::capture("boom".to_string(), None);
+        drop(held);
+        record_serial_metrics(None);
+        assert_eq!(report.serial_metrics.as_ref().map(|m| m.command_timeouts), Some(3));
+        assert!(without_context.serial_metrics.is_none(), "a held context is left out instead of deadlocking");
+        assert!(!report.backtrace.is_empty());
+
+        let dir = std::env::temp_dir().join(format!("joycore-crash-{}", uuid::Uuid::new_v4()));
+        let path = report.write(&dir).unwrap();
+        let written: serde_json::Value = serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+        assert_eq!(written["message"], "boom");
+        assert_eq!(written["serial_metrics"]["command_timeouts"], 3);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
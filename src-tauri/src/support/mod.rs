@@ -0,0 +1,194 @@
+//! Support bundle: one zip archive with what is needed to diagnose a user's setup.
+//!
+//! Entries are collected best-effort; anything that cannot be gathered (no device connected,
+//! unreadable log file) is listed under `notes` in `manifest.json` instead of failing the export.
+//! Secrets in the app settings are redacted before they are added.
+pub mod crash;
+pub mod permissions;
+
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use serde::Serialize;
+
+/// Only the newest part of each log file is bundled
+pub const MAX_LOG_FILE_BYTES: u64 = 2 * 1024 * 1024;
+pub const MANIFEST_NAME: &str = "manifest.json";
+const REDACTED: &str = "<redacted>";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SupportBundleInfo {
+    pub path: String,
+    /// Archive entry names, manifest last
+    pub entries: Vec<String>,
+    /// Size of the written archive
+    pub bytes: u64,
+    /// What could not be collected, and why
+    pub notes: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct Manifest<'a> {
+    created_at: chrono::DateTime<chrono::Utc>,
+    app_version: &'static str,
+    entries: Vec<&'a str>,
+    notes: &'a [String],
+}
+
+/// Entries of a support bundle before it is written
+#[derive(Default)]
+pub struct SupportBundle {
+    entries: Vec<(String, Vec<u8>)>,
+    notes: Vec<String>,
+}
+
+impl SupportBundle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, name: impl Into<String>, data: impl Into<Vec<u8>>) {
+        self.entries.push((name.into(), data.into()));
+    }
+
+    /// Add `value` as pretty JSON; a serialization failure becomes a note
+    pub fn add_json<T: Serialize + ?Sized>(&mut self, name: &str, value: &T) {
+        match serde_json::to_vec_pretty(value) {
+            Ok(data) => self.add(name, data),
+            Err(e) => self.note(format!("{}: {}", name, e)),
+        }
+    }
+
+    pub fn note(&mut self, note: impl Into<String>) {
+        self.notes.push(note.into());
+    }
+
+    /// Add the tail of every file in `dir` under `logs/`
+    pub fn add_log_dir(&mut self, dir: &Path, max_bytes: u64) {
+        let read_dir = match std::fs::read_dir(dir) {
+            Ok(read_dir) => read_dir,
+            Err(e) => return self.note(format!("logs: {}: {}", dir.display(), e)),
+        };
+        let mut files: Vec<_> = read_dir
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().map(|t| t.is_file()).unwrap_or(false))
+            .collect();
+        files.sort_by_key(|entry| entry.file_name());
+        for entry in files {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            match read_tail(&entry.path(), max_bytes) {
+                Ok(data) => self.add(format!("logs/{}", name), data),
+                Err(e) => self.note(format!("logs/{}: {}", name, e)),
+            }
+        }
+    }
+
+    /// Write the archive with a trailing manifest
+    pub fn write(&self, path: &Path) -> std::io::Result<SupportBundleInfo> {
+        let file = std::fs::File::create(path)?;
+        let mut zip = zip::ZipWriter::new(file);
+        let options = zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+        for (name, data) in &self.entries {
+            zip.start_file(name.as_str(), options)?;
+            zip.write_all(data)?;
+        }
+        let manifest = Manifest {
+            created_at: chrono::Utc::now(),
+            app_version: env!("CARGO_PKG_VERSION"),
+            entries: self.entries.iter().map(|(name, _)| name.as_str()).collect(),
+            notes: &self.notes,
+        };
+        zip.start_file(MANIFEST_NAME, options)?;
+        zip.write_all(&serde_json::to_vec_pretty(&manifest)?)?;
+        let bytes = zip.finish()?.metadata()?.len();
+
+        let mut entries: Vec<String> = self.entries.iter().map(|(name, _)| name.clone()).collect();
+        entries.push(MANIFEST_NAME.to_string());
+        Ok(SupportBundleInfo { path: path.display().to_string(), entries, bytes, notes: self.notes.clone() })
+    }
+}
+
+/// Last `max_bytes` of a file
+fn read_tail(path: &Path, max_bytes: u64) -> std::io::Result<Vec<u8>> {
+    let mut file = std::fs::File::open(path)?;
+    let len = file.metadata()?.len();
+    if len > max_bytes {
+        file.seek(SeekFrom::Start(len - max_bytes))?;
+    }
+    let mut data = Vec::with_capacity(len.min(max_bytes) as usize);
+    file.take(max_bytes).read_to_end(&mut data)?;
+    Ok(data)
+}
+
+/// Host OS and app build information
+pub fn system_info() -> serde_json::Value {
+    serde_json::json!({
+        "app_version": env!("CARGO_PKG_VERSION"),
+        "os": std::env::consts::OS,
+        "os_family": std::env::consts::FAMILY,
+        "arch": std::env::consts::ARCH,
+        "debug_build": cfg!(debug_assertions),
+    })
+}
+
+/// Replace the string values of `keys` anywhere in `value`
+pub fn redact_keys(value: &mut serde_json::Value, keys: &[&str]) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, field) in map.iter_mut() {
+                if keys.contains(&key.as_str()) && field.is_string() {
+                    *field = serde_json::Value::String(REDACTED.to_string());
+                } else {
+                    redact_keys(field, keys);
+                }
+            }
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(|item| redact_keys(item, keys)),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_bundle_with_log_tails_and_manifest() {
+        let dir = std::env::temp_dir().join(format!("joycore-support-{}", uuid::Uuid::new_v4()));
+        let logs = dir.join("logs");
+        std::fs::create_dir_all(&logs).unwrap();
+        std::fs::write(logs.join("joycore-x.log"), "first line\nsecond line\n").unwrap();
+
+        let mut bundle = SupportBundle::new();
+        bundle.add("config.bin", vec![1, 2, 3]);
+        bundle.add_json("system.json", &system_info());
+        bundle.add_log_dir(&logs, 12);
+        bundle.add_log_dir(&dir.join("missing"), 12);
+        let path = dir.join("bundle.zip");
+        let info = bundle.write(&path).unwrap();
+        assert_eq!(info.entries, vec!["config.bin", "system.json", "logs/joycore-x.log", MANIFEST_NAME]);
+        assert_eq!(info.notes.len(), 1, "missing log dir is noted");
+
+        let mut archive = zip::ZipArchive::new(std::fs::File::open(&path).unwrap()).unwrap();
+        let mut log = String::new();
+        archive.by_name("logs/joycore-x.log").unwrap().read_to_string(&mut log).unwrap();
+        assert_eq!(log, "second line\n");
+        let mut manifest = String::new();
+        archive.by_name(MANIFEST_NAME).unwrap().read_to_string(&mut manifest).unwrap();
+        let manifest: serde_json::Value = serde_json::from_str(&manifest).unwrap();
+        assert_eq!(manifest["entries"].as_array().unwrap().len(), 3);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn redacts_nested_secrets() {
+        let mut settings = serde_json::json!({
+            "log_level": "info",
+            "firmware_update": { "github_token": "ghp_secret", "proxy_url": null, "repo_name": "x" },
+        });
+        redact_keys(&mut settings, &["github_token", "proxy_url"]);
+        assert_eq!(settings["firmware_update"]["github_token"], REDACTED);
+        assert!(settings["firmware_update"]["proxy_url"].is_null());
+        assert_eq!(settings["firmware_update"]["repo_name"], "x");
+    }
+}
@@ -0,0 +1,327 @@
+//! Host permission checks for serial and HID access.
+//!
+//! hidapi and serialport report a missing permission as a generic open error. [`check`] looks
+//! for the usual causes and says how to fix each: on Linux missing udev rules for the JoyCore
+//! USB id, device nodes the user cannot open and group memberships that only apply after a new
+//! login; on macOS the Input Monitoring (TCC) state. Windows needs no setup.
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::device::port_filter::JOYCORE_USB_VID;
+
+pub const UDEV_RULES_FILE_NAME: &str = "99-joycore.rules";
+/// Directories udev reads rules from, local overrides first
+pub const UDEV_RULES_DIRS: [&str; 3] = ["/etc/udev/rules.d", "/usr/lib/udev/rules.d", "/lib/udev/rules.d"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PermissionIssueKind {
+    /// No udev rule grants access to the JoyCore USB id
+    MissingUdevRules,
+    /// A JoyCore serial or hidraw node cannot be opened for reading and writing
+    DeviceNodeAccess,
+    /// The user was added to the node's group but this login session predates it
+    GroupLoginRequired,
+    /// Input Monitoring was denied to the app (macOS)
+    InputMonitoringDenied,
+    /// Input Monitoring was never asked for; macOS prompts on the first HID read
+    InputMonitoringNotDetermined,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PermissionIssue {
+    pub kind: PermissionIssueKind,
+    pub message: String,
+    pub remediation: String,
+    /// Shell commands that fix the issue, in order
+    pub commands: Vec<String>,
+    /// Device node or file concerned
+    pub path: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PermissionReport {
+    pub platform: &'static str,
+    pub ok: bool,
+    /// What was looked at (rule directories, device nodes), for the report view
+    pub checked: Vec<String>,
+    pub issues: Vec<PermissionIssue>,
+}
+
+/// A written udev rules file and how to install it
+#[derive(Debug, Clone, Serialize)]
+pub struct UdevRulesFile {
+    pub path: String,
+    pub install_commands: Vec<String>,
+}
+
+/// Rules giving the logged-in user access to JoyCore serial, hidraw and raw USB (flashing) nodes
+pub fn udev_rules() -> String {
+    let vid = format!("{:04x}", JOYCORE_USB_VID);
+    format!(
+        "# JoyCore devices: serial, HID and USB access for the logged-in user\n\
+         SUBSYSTEM==\"tty\", ATTRS{{idVendor}}==\"{vid}\", MODE=\"0660\", TAG+=\"uaccess\"\n\
+         SUBSYSTEM==\"hidraw\", ATTRS{{idVendor}}==\"{vid}\", MODE=\"0660\", TAG+=\"uaccess\"\n\
+         SUBSYSTEM==\"usb\", ATTR{{idVendor}}==\"{vid}\", MODE=\"0660\", TAG+=\"uaccess\"\n"
+    )
+}
+
+/// `s` as a single POSIX shell word
+fn shell_quote(s: &str) -> String {
+    if !s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric() || "/._-+:@".contains(c)) {
+        s.to_string()
+    } else {
+        format!("'{}'", s.replace('\'', "'\\''"))
+    }
+}
+
+fn udev_install_commands(rules_file: &str) -> Vec<String> {
+    vec![
+        format!("sudo cp {} {}", shell_quote(rules_file), shell_quote(&format!("/etc/udev/rules.d/{}", UDEV_RULES_FILE_NAME))),
+        "sudo udevadm control --reload-rules".to_string(),
+        "sudo udevadm trigger".to_string(),
+    ]
+}
+
+/// Write [`udev_rules`] into `dir` for the user to install with the returned commands
+pub fn write_udev_rules(dir: &Path) -> std::io::Result<UdevRulesFile> {
+    std::fs::create_dir_all(dir)?;
+    let path = dir.join(UDEV_RULES_FILE_NAME);
+    std::fs::write(&path, udev_rules())?;
+    let path = path.display().to_string();
+    Ok(UdevRulesFile { install_commands: udev_install_commands(&path), path })
+}
+
+/// Whether a rules file matches `vid` in an active (uncommented) rule
+pub fn rules_mention_vid(rules: &str, vid: u16) -> bool {
+    let needle = format!("\"{:04x}\"", vid);
+    rules.lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.starts_with('#'))
+        .any(|line| {
+            let line = line.to_ascii_lowercase();
+            line.contains("idvendor") && line.contains(&needle)
+        })
+}
+
+/// Whether a hidraw node's `uevent` (`HID_ID=0003:00002E8A:0000A02F`) belongs to `vid`
+pub fn hid_uevent_matches(uevent: &str, vid: u16) -> bool {
+    uevent.lines()
+        .filter_map(|line| line.strip_prefix("HID_ID="))
+        .any(|id| id.split(':').nth(1).and_then(|v| u32::from_str_radix(v, 16).ok()) == Some(vid as u32))
+}
+
+/// Groups (name, gid) listing `user` as a member in an `/etc/group` file
+pub fn groups_of_user(group_file: &str, user: &str) -> Vec<(String, u32)> {
+    group_file.lines()
+        .filter_map(|line| {
+            let mut fields = line.split(':');
+            let name = fields.next()?;
+            let gid = fields.nth(1)?.parse().ok()?;
+            let members = fields.next()?;
+            members.split(',').any(|m| m.trim() == user).then(|| (name.to_string(), gid))
+        })
+        .collect()
+}
+
+/// Name of group `gid` in an `/etc/group` file
+pub fn group_name(group_file: &str, gid: u32) -> Option<String> {
+    group_file.lines().find_map(|line| {
+        let fields: Vec<&str> = line.split(':').collect();
+        (fields.len() >= 3 && fields[2].parse() == Ok(gid)).then(|| fields[0].to_string())
+    })
+}
+
+/// Look for permission problems on this host. Missing udev rules are written to `rules_dir`
+/// (see [`write_udev_rules`]) so the remediation can install them from there.
+#[allow(unused_mut, unused_variables)] // Only Linux writes rules; Windows has nothing to check
+pub fn check(rules_dir: &Path) -> PermissionReport {
+    let mut checked = Vec::new();
+    let mut issues = Vec::new();
+    #[cfg(target_os = "linux")]
+    linux::check(rules_dir, &mut checked, &mut issues);
+    #[cfg(target_os = "macos")]
+    macos::check(&mut checked, &mut issues);
+    PermissionReport { platform: std::env::consts::OS, ok: issues.is_empty(), checked, issues }
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+    use std::os::unix::fs::MetadataExt;
+    use std::path::PathBuf;
+
+    use super::*;
+
+    /// JoyCore serial and hidraw nodes currently present
+    fn device_nodes() -> Vec<PathBuf> {
+        let mut nodes: Vec<PathBuf> = serialport::available_ports().unwrap_or_default().into_iter()
+            .filter(|p| matches!(&p.port_type, serialport::SerialPortType::UsbPort(usb) if usb.vid == JOYCORE_USB_VID))
+            .map(|p| PathBuf::from(p.port_name))
+            .collect();
+        if let Ok(entries) = std::fs::read_dir("/sys/class/hidraw") {
+            for entry in entries.flatten() {
+                let uevent = std::fs::read_to_string(entry.path().join("device/uevent")).unwrap_or_default();
+                if hid_uevent_matches(&uevent, JOYCORE_USB_VID) {
+                    nodes.push(Path::new("/dev").join(entry.file_name()));
+                }
+            }
+        }
+        nodes
+    }
+
+    fn can_read_write(path: &Path) -> bool {
+        let Ok(path) = CString::new(path.as_os_str().as_bytes()) else { return false };
+        // access() honours ACLs, which is how udev's uaccess tag grants the seat user access
+        unsafe { libc::access(path.as_ptr(), libc::R_OK | libc::W_OK) == 0 }
+    }
+
+    /// Supplementary groups of this process, plus its own group
+    fn process_groups() -> Vec<u32> {
+        unsafe {
+            let count = libc::getgroups(0, std::ptr::null_mut());
+            let mut groups = vec![0 as libc::gid_t; count.max(0) as usize];
+            let count = libc::getgroups(groups.len() as libc::c_int, groups.as_mut_ptr());
+            groups.truncate(count.max(0) as usize);
+            groups.push(libc::getegid());
+            groups
+        }
+    }
+
+    fn user_name() -> Option<String> {
+        std::env::var("USER").or_else(|_| std::env::var("LOGNAME")).ok().filter(|u| !u.is_empty())
+    }
+
+    pub(super) fn check(rules_dir: &Path, checked: &mut Vec<String>, issues: &mut Vec<PermissionIssue>) {
+        // Written on the first issue that needs it
+        let mut rules_file: Option<Option<UdevRulesFile>> = None;
+        let mut install_commands = || {
+            rules_file.get_or_insert_with(|| write_udev_rules(rules_dir)
+                .inspect_err(|e| log::warn!("Failed to write udev rules to {}: {}", rules_dir.display(), e))
+                .ok())
+                .as_ref()
+                .map(|file| file.install_commands.clone())
+                .unwrap_or_default()
+        };
+
+        let rules_found = UDEV_RULES_DIRS.iter().any(|dir| {
+            checked.push(dir.to_string());
+            std::fs::read_dir(dir).into_iter().flatten().flatten()
+                .filter(|e| e.path().extension().is_some_and(|ext| ext == "rules"))
+                .any(|e| std::fs::read_to_string(e.path()).is_ok_and(|rules| rules_mention_vid(&rules, JOYCORE_USB_VID)))
+        });
+        if !rules_found {
+            issues.push(PermissionIssue {
+                kind: PermissionIssueKind::MissingUdevRules,
+                message: format!("No udev rule mentions the JoyCore USB vendor id {:04x}", JOYCORE_USB_VID),
+                remediation: format!("Install the JoyCore udev rules written to {}, then replug the device", rules_dir.display()),
+                commands: install_commands(),
+                path: None,
+            });
+        }
+
+        let group_file = std::fs::read_to_string("/etc/group").unwrap_or_default();
+        let active_groups = process_groups();
+        let member_of = user_name().map(|user| groups_of_user(&group_file, &user)).unwrap_or_default();
+        for node in device_nodes() {
+            checked.push(node.display().to_string());
+            if can_read_write(&node) {
+                continue;
+            }
+            let path = Some(node.display().to_string());
+            let gid = std::fs::metadata(&node).map(|m| m.gid()).unwrap_or(0);
+            let group = group_name(&group_file, gid).unwrap_or_else(|| gid.to_string());
+            let issue = if gid == 0 {
+                PermissionIssue {
+                    kind: PermissionIssueKind::DeviceNodeAccess,
+                    message: format!("{} is only accessible to root", node.display()),
+                    remediation: "Install the JoyCore udev rules, then replug the device".to_string(),
+                    commands: install_commands(),
+                    path,
+                }
+            } else if member_of.iter().any(|(_, g)| *g == gid) && !active_groups.contains(&gid) {
+                PermissionIssue {
+                    kind: PermissionIssueKind::GroupLoginRequired,
+                    message: format!("You were added to group '{}' but this session started before that", group),
+                    remediation: "Log out and back in (or reboot) so the group membership applies".to_string(),
+                    commands: Vec::new(),
+                    path,
+                }
+            } else {
+                PermissionIssue {
+                    kind: PermissionIssueKind::DeviceNodeAccess,
+                    message: format!("{} belongs to group '{}', which you are not a member of", node.display(), group),
+                    remediation: format!("Add yourself to '{}' and log in again, or install the JoyCore udev rules", group),
+                    commands: vec![format!("sudo usermod -aG {} $USER", group)],
+                    path,
+                }
+            };
+            issues.push(issue);
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod macos {
+    use super::*;
+
+    const REQUEST_TYPE_LISTEN_EVENT: u32 = 1;
+    const ACCESS_GRANTED: u32 = 0;
+    const ACCESS_DENIED: u32 = 1;
+
+    #[link(name = "IOKit", kind = "framework")]
+    extern "C" {
+        fn IOHIDCheckAccess(request_type: u32) -> u32;
+    }
+
+    pub(super) fn check(checked: &mut Vec<String>, issues: &mut Vec<PermissionIssue>) {
+        checked.push("Input Monitoring".to_string());
+        let settings_command = "open \"x-apple.systempreferences:com.apple.preference.security?Privacy_ListenEvent\"".to_string();
+        match unsafe { IOHIDCheckAccess(REQUEST_TYPE_LISTEN_EVENT) } {
+            ACCESS_GRANTED => {}
+            ACCESS_DENIED => issues.push(PermissionIssue {
+                kind: PermissionIssueKind::InputMonitoringDenied,
+                message: "Input Monitoring is denied, so HID button states may not be readable".to_string(),
+                remediation: "Allow JoyCore-X under System Settings > Privacy & Security > Input Monitoring, then restart the app".to_string(),
+                commands: vec![settings_command],
+                path: None,
+            }),
+            _ => issues.push(PermissionIssue {
+                kind: PermissionIssueKind::InputMonitoringNotDetermined,
+                message: "Input Monitoring has not been granted yet".to_string(),
+                remediation: "Allow the prompt shown on the first HID connection, or enable JoyCore-X under Privacy & Security > Input Monitoring".to_string(),
+                commands: vec![settings_command],
+                path: None,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_rules_groups_and_hid_ids() {
+        assert!(rules_mention_vid(&udev_rules(), JOYCORE_USB_VID));
+        assert!(!rules_mention_vid("# SUBSYSTEM==\"tty\", ATTRS{idVendor}==\"2e8a\"", JOYCORE_USB_VID));
+        assert!(rules_mention_vid("SUBSYSTEMS==\"usb\", ATTRS{idVendor}==\"2E8A\", MODE=\"0666\"", JOYCORE_USB_VID));
+
+        assert!(hid_uevent_matches("DRIVER=hid-generic\nHID_ID=0003:00002E8A:0000A02F\n", JOYCORE_USB_VID));
+        assert!(!hid_uevent_matches("HID_ID=0003:0000046D:0000C52B", JOYCORE_USB_VID));
+
+        let groups = "root:x:0:\ndialout:x:20:alice,bob\nplugdev:x:46:bob\n";
+        assert_eq!(groups_of_user(groups, "alice"), vec![("dialout".to_string(), 20)]);
+        assert_eq!(group_name(groups, 46).as_deref(), Some("plugdev"));
+        assert_eq!(group_name(groups, 99), None);
+    }
+
+    #[test]
+    fn quotes_install_paths() {
+        let commands = udev_install_commands("/home/o'neil/My Config/99-joycore.rules");
+        assert_eq!(commands[0], "sudo cp '/home/o'\\''neil/My Config/99-joycore.rules' /etc/udev/rules.d/99-joycore.rules");
+        assert_eq!(udev_install_commands("/tmp/99-joycore.rules")[0], "sudo cp /tmp/99-joycore.rules /etc/udev/rules.d/99-joycore.rules");
+    }
+}
@@ -0,0 +1,72 @@
+//! Opt-in WebSocket server on localhost streaming live input to external tools (dashboards,
+//! stream overlays, SimHub-style plugins) without going through the Tauri frontend.
+//!
+//! Every message is one JSON object tagged by `type`: `hello` on connect, then `button`, `axis`
+//! and `raw` (a `ParsedEvent`) as input changes, and `lagged` when a slow client missed events.
+pub mod server;
+
+use serde::Serialize;
+
+use crate::serial::ParsedEvent;
+
+pub use server::{TelemetryServer, TelemetryStatus};
+
+/// Port used when the settings or the start command do not name one
+pub const DEFAULT_TELEMETRY_PORT: u16 = 8765;
+/// Version of the message format, sent in `hello`
+pub const TELEMETRY_PROTOCOL_VERSION: u32 = 1;
+
+/// Message sent to telemetry clients
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TelemetryEvent {
+    Hello { protocol: u32, app_version: String },
+    Button { button_id: u8, pressed: bool, timestamp: chrono::DateTime<chrono::Utc> },
+    Axis { axis_id: u8, value: i32 },
+    Raw { event: ParsedEvent },
+    /// The client fell behind and `skipped` events were dropped
+    Lagged { skipped: u64 },
+}
+
+impl TelemetryEvent {
+    pub fn hello() -> Self {
+        Self::Hello { protocol: TELEMETRY_PROTOCOL_VERSION, app_version: env!("CARGO_PKG_VERSION").to_string() }
+    }
+}
+
+/// Global telemetry server instance
+static SERVER: once_cell::sync::Lazy<TelemetryServer> = once_cell::sync::Lazy::new(TelemetryServer::new);
+
+/// Get the global telemetry server instance
+pub fn get_telemetry_server() -> &'static TelemetryServer {
+    &SERVER
+}
+
+/// Whether publishing is worthwhile (the server runs); lets callers skip building events
+pub fn is_streaming() -> bool {
+    SERVER.is_running()
+}
+
+/// Send an event to connected clients (no-op unless the server runs)
+pub fn publish(event: TelemetryEvent) {
+    if SERVER.is_running() {
+        SERVER.publish(event);
+    }
+}
+
+/// Start or stop the server to match `AppSettings::telemetry`
+pub async fn apply_settings(settings: &crate::device::TelemetrySettings) {
+    let status = SERVER.status();
+    if !settings.enabled {
+        if status.running {
+            SERVER.stop();
+        }
+        return;
+    }
+    if status.running && status.port == Some(settings.port) && status.allowed_origins == settings.allowed_origins {
+        return;
+    }
+    if let Err(e) = SERVER.start(settings.port, &settings.allowed_origins).await {
+        log::warn!("Failed to start telemetry server on port {}: {}", settings.port, e);
+    }
+}
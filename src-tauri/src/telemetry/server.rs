@@ -0,0 +1,269 @@
+//! WebSocket listener bound to 127.0.0.1; each client gets its own task forwarding the shared
+//! event channel as JSON text messages.
+//!
+//! Binding to localhost does not keep web pages out: a browser lets any site open a WebSocket to
+//! 127.0.0.1. Browsers always send an `Origin` header with it, so handshakes carrying one are
+//! refused unless the origin is in `TelemetrySettings::allowed_origins`. Native tools send none.
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use futures_util::{SinkExt, StreamExt};
+use serde::Serialize;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+use tokio_tungstenite::tungstenite::handshake::server::{ErrorResponse, Request, Response};
+use tokio_tungstenite::tungstenite::http::{header::ORIGIN, StatusCode};
+use tokio_tungstenite::tungstenite::Message;
+
+use super::TelemetryEvent;
+
+/// Events buffered per client before it is considered lagging
+const CLIENT_QUEUE_CAPACITY: usize = 1024;
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TelemetryStatus {
+    pub running: bool,
+    /// Bound address, e.g. `127.0.0.1:8765`
+    pub address: Option<String>,
+    pub port: Option<u16>,
+    /// Browser origins allowed to connect
+    pub allowed_origins: Vec<String>,
+    pub clients: usize,
+    /// Events sent while at least one client was connected
+    pub events_published: u64,
+    pub started_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+struct ActiveServer {
+    events: broadcast::Sender<TelemetryEvent>,
+    accept_task: JoinHandle<()>,
+    address: SocketAddr,
+    allowed_origins: Arc<[String]>,
+    started_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Runs at most one listener at a time
+#[derive(Default)]
+pub struct TelemetryServer {
+    running: AtomicBool,
+    published: AtomicU64,
+    active: Mutex<Option<ActiveServer>>,
+}
+
+impl TelemetryServer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::Relaxed)
+    }
+
+    pub fn status(&self) -> TelemetryStatus {
+        let active = self.active.lock().unwrap_or_else(|e| e.into_inner());
+        match active.as_ref() {
+            Some(server) => TelemetryStatus {
+                running: true,
+                address: Some(server.address.to_string()),
+                port: Some(server.address.port()),
+                allowed_origins: server.allowed_origins.to_vec(),
+                clients: server.events.receiver_count(),
+                events_published: self.published.load(Ordering::Relaxed),
+                started_at: Some(server.started_at),
+            },
+            None => TelemetryStatus { events_published: self.published.load(Ordering::Relaxed), ..Default::default() },
+        }
+    }
+
+    /// Listen on `127.0.0.1:port` (0 picks a free port), replacing a running server. Browser
+    /// clients are only accepted from `allowed_origins`.
+    pub async fn start(&self, port: u16, allowed_origins: &[String]) -> std::io::Result<TelemetryStatus> {
+        self.stop();
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port)).await?;
+        let address = listener.local_addr()?;
+        let (events, _) = broadcast::channel(CLIENT_QUEUE_CAPACITY);
+        let allowed_origins: Arc<[String]> = allowed_origins.into();
+        let accept_task = tokio::spawn(accept_loop(listener, events.clone(), allowed_origins.clone()));
+        {
+            let mut active = self.active.lock().unwrap_or_else(|e| e.into_inner());
+            *active = Some(ActiveServer { events, accept_task, address, allowed_origins, started_at: chrono::Utc::now() });
+        }
+        self.published.store(0, Ordering::Relaxed);
+        self.running.store(true, Ordering::Relaxed);
+        log::info!("Telemetry server listening on ws://{}", address);
+        Ok(self.status())
+    }
+
+    /// Stop listening and disconnect every client. Returns the final status.
+    pub fn stop(&self) -> TelemetryStatus {
+        let status = self.status();
+        let mut active = self.active.lock().unwrap_or_else(|e| e.into_inner());
+        self.running.store(false, Ordering::Relaxed);
+        // Dropping the sender closes every client's receiver, which ends its task
+        if let Some(server) = active.take() {
+            server.accept_task.abort();
+            log::info!("Telemetry server on {} stopped", server.address);
+        }
+        TelemetryStatus { running: false, clients: 0, ..status }
+    }
+
+    pub fn publish(&self, event: TelemetryEvent) {
+        let active = self.active.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(server) = active.as_ref() {
+            // An error only means no client is connected
+            if server.events.send(event).is_ok() {
+                self.published.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+async fn accept_loop(listener: TcpListener, events: broadcast::Sender<TelemetryEvent>, allowed_origins: Arc<[String]>) {
+    loop {
+        match listener.accept().await {
+            Ok((stream, peer)) => {
+                tokio::spawn(serve_client(stream, peer, events.subscribe(), allowed_origins.clone()));
+            }
+            Err(e) => log::warn!("Telemetry server failed to accept a client: {}", e),
+        }
+    }
+}
+
+/// Origins compare case-insensitively, ignoring a trailing slash
+fn origin_allowed(origin: &str, allowed: &[String]) -> bool {
+    let normalize = |o: &str| o.trim().trim_end_matches('/').to_ascii_lowercase();
+    let origin = normalize(origin);
+    allowed.iter().any(|a| normalize(a) == origin)
+}
+
+async fn serve_client(
+    stream: TcpStream,
+    peer: SocketAddr,
+    mut events: broadcast::Receiver<TelemetryEvent>,
+    allowed_origins: Arc<[String]>,
+) {
+    let check_origin = |request: &Request, response: Response| -> Result<Response, ErrorResponse> {
+        let Some(origin) = request.headers().get(ORIGIN) else { return Ok(response) };
+        let origin = origin.to_str().unwrap_or_default();
+        if origin_allowed(origin, &allowed_origins) {
+            return Ok(response);
+        }
+        log::warn!("Refused telemetry client {} from origin '{}'", peer, origin);
+        let mut refused = ErrorResponse::new(Some("Origin not allowed".to_string()));
+        *refused.status_mut() = StatusCode::FORBIDDEN;
+        Err(refused)
+    };
+    let socket = match tokio_tungstenite::accept_hdr_async(stream, check_origin).await {
+        Ok(socket) => socket,
+        Err(e) => {
+            log::debug!("Telemetry handshake with {} failed: {}", peer, e);
+            return;
+        }
+    };
+    log::info!("Telemetry client {} connected", peer);
+    let (mut sink, mut incoming) = socket.split();
+    let mut next = Some(TelemetryEvent::hello());
+    loop {
+        let event = match next.take() {
+            Some(event) => event,
+            None => tokio::select! {
+                received = events.recv() => match received {
+                    Ok(event) => event,
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => TelemetryEvent::Lagged { skipped },
+                    Err(broadcast::error::RecvError::Closed) => break,
+                },
+                message = incoming.next() => match message {
+                    // Pings are answered by tungstenite; anything else from the client is ignored
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(_)) => continue,
+                },
+            },
+        };
+        let text = match serde_json::to_string(&event) {
+            Ok(text) => text,
+            Err(e) => {
+                log::warn!("Failed to serialize telemetry event: {}", e);
+                continue;
+            }
+        };
+        if sink.send(Message::Text(text)).await.is_err() {
+            break;
+        }
+    }
+    let _ = sink.close().await;
+    log::info!("Telemetry client {} disconnected", peer);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serial::ParsedEvent;
+
+    async fn next_json(socket: &mut (impl StreamExt<Item = tokio_tungstenite::tungstenite::Result<Message>> + Unpin)) -> serde_json::Value {
+        loop {
+            match socket.next().await.unwrap().unwrap() {
+                Message::Text(text) => return serde_json::from_str(&text).unwrap(),
+                _ => continue,
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn streams_events_to_clients() {
+        let server = TelemetryServer::new();
+        let status = server.start(0, &[]).await.unwrap();
+        let url = format!("ws://{}", status.address.unwrap());
+        let (mut socket, _) = tokio_tungstenite::connect_async(url).await.unwrap();
+
+        let hello = next_json(&mut socket).await;
+        assert_eq!(hello["type"], "hello");
+        assert_eq!(hello["protocol"], 1);
+        assert_eq!(server.status().clients, 1);
+
+        server.publish(TelemetryEvent::Axis { axis_id: 2, value: 512 });
+        server.publish(TelemetryEvent::Raw { event: ParsedEvent::Gpio { mask: 0b101, timestamp: 42 } });
+        let axis = next_json(&mut socket).await;
+        assert_eq!((axis["type"].as_str(), axis["axis_id"].as_u64(), axis["value"].as_i64()), (Some("axis"), Some(2), Some(512)));
+        let raw = next_json(&mut socket).await;
+        assert_eq!(raw["type"], "raw");
+        assert_eq!(raw["event"]["Gpio"]["mask"], 5);
+        assert_eq!(server.status().events_published, 2);
+
+        let stopped = server.stop();
+        assert!(!stopped.running && !server.is_running());
+        let closed = loop {
+            match socket.next().await {
+                Some(Ok(Message::Close(_))) | None | Some(Err(_)) => break true,
+                Some(Ok(_)) => continue,
+            }
+        };
+        assert!(closed, "clients are disconnected on stop");
+        server.publish(TelemetryEvent::Lagged { skipped: 1 });
+        assert_eq!(server.status().events_published, 2);
+    }
+
+    #[tokio::test]
+    async fn refuses_foreign_origins() {
+        use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+        use tokio_tungstenite::tungstenite::Error;
+
+        let server = TelemetryServer::new();
+        let status = server.start(0, &["http://localhost:3000".to_string()]).await.unwrap();
+        let url = format!("ws://{}", status.address.unwrap());
+        let from = |origin: &str| {
+            let mut request = url.as_str().into_client_request().unwrap();
+            request.headers_mut().insert(ORIGIN, origin.parse().unwrap());
+            request
+        };
+
+        match tokio_tungstenite::connect_async(from("https://evil.example")).await {
+            Err(Error::Http(response)) => assert_eq!(response.status(), StatusCode::FORBIDDEN),
+            other => panic!("a foreign origin must be refused, got {:?}", other.map(|_| ())),
+        }
+        let (mut socket, _) = tokio_tungstenite::connect_async(from("http://LOCALHOST:3000/")).await.unwrap();
+        assert_eq!(next_json(&mut socket).await["type"], "hello");
+        server.stop();
+    }
+}
@@ -0,0 +1,142 @@
+//! Local cache of downloaded firmware images, keyed by version, for rollbacks.
+//!
+//! Images are copied to `firmware_cache/firmware-<version>.uf2` under the app data directory,
+//! together with their `.sig` signature when there is one. Caching a version again replaces the
+//! previous copy; only the newest [`MAX_CACHED_FIRMWARE`] versions are kept.
+use std::path::{Path, PathBuf};
+
+use semver::Version;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use super::models::UpdateResult;
+use super::signature::signature_path;
+
+/// Subdirectory of the app data directory holding cached firmware
+pub const FIRMWARE_CACHE_DIR_NAME: &str = "firmware_cache";
+pub const MAX_CACHED_FIRMWARE: usize = 10;
+const FILE_PREFIX: &str = "firmware-";
+const FILE_EXTENSION: &str = "uf2";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CachedFirmware {
+    pub version: Version,
+    pub path: PathBuf,
+    pub size_bytes: u64,
+    pub cached_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub sha256: String,
+    /// Signature saved with the image, if the release had one
+    pub signature_path: Option<PathBuf>,
+}
+
+pub struct FirmwareCache {
+    dir: PathBuf,
+}
+
+impl FirmwareCache {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    /// Cache located in the given app data directory
+    pub fn in_dir(dir: &Path) -> Self {
+        Self::new(dir.join(FIRMWARE_CACHE_DIR_NAME))
+    }
+
+    fn path_for(&self, version: &Version) -> PathBuf {
+        self.dir.join(format!("{}{}.{}", FILE_PREFIX, version, FILE_EXTENSION))
+    }
+
+    /// Copy a downloaded image into the cache
+    pub fn store(&self, version: &Version, image: &Path) -> UpdateResult<CachedFirmware> {
+        std::fs::create_dir_all(&self.dir)?;
+        let target = self.path_for(version);
+        if target != image {
+            std::fs::copy(image, &target)?;
+            let signature = signature_path(image);
+            if signature.exists() {
+                std::fs::copy(&signature, signature_path(&target))?;
+            } else if signature_path(&target).exists() {
+                std::fs::remove_file(signature_path(&target))?;
+            }
+        }
+        log::info!("Cached firmware {} at {:?}", version, target);
+        for old in self.list()?.iter().skip(MAX_CACHED_FIRMWARE) {
+            if let Err(e) = std::fs::remove_file(&old.path) {
+                log::warn!("Failed to prune cached firmware {}: {}", old.version, e);
+            }
+            let _ = std::fs::remove_file(signature_path(&old.path));
+        }
+        Ok(describe(version.clone(), target)?)
+    }
+
+    /// Cached images, newest version first
+    pub fn list(&self) -> UpdateResult<Vec<CachedFirmware>> {
+        let entries = match std::fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let mut list = Vec::new();
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) != Some(FILE_EXTENSION) {
+                continue;
+            }
+            let version = path.file_stem()
+                .and_then(|s| s.to_str())
+                .and_then(|s| s.strip_prefix(FILE_PREFIX))
+                .and_then(|v| Version::parse(v).ok());
+            if let Some(version) = version {
+                list.push(describe(version, path)?);
+            }
+        }
+        list.sort_by(|a, b| b.version.cmp(&a.version));
+        Ok(list)
+    }
+
+    pub fn get(&self, version: &Version) -> UpdateResult<Option<CachedFirmware>> {
+        let path = self.path_for(version);
+        if !path.exists() {
+            return Ok(None);
+        }
+        Ok(Some(describe(version.clone(), path)?))
+    }
+}
+
+fn describe(version: Version, path: PathBuf) -> std::io::Result<CachedFirmware> {
+    let data = std::fs::read(&path)?;
+    let metadata = std::fs::metadata(&path)?;
+    Ok(CachedFirmware {
+        version,
+        size_bytes: metadata.len(),
+        cached_at: metadata.modified().ok().map(chrono::DateTime::<chrono::Utc>::from),
+        sha256: format!("{:x}", Sha256::digest(&data)),
+        signature_path: Some(signature_path(&path)).filter(|p| p.exists()),
+        path,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stores_and_lists_versions_newest_first() {
+        let root = std::env::temp_dir().join(format!("joycore_fw_cache_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&root).unwrap();
+        let download = root.join("download.uf2");
+        let cache = FirmwareCache::in_dir(&root);
+        for version in ["1.2.0", "1.10.0", "1.3.0-beta.1"] {
+            std::fs::write(&download, version.as_bytes()).unwrap();
+            cache.store(&Version::parse(version).unwrap(), &download).unwrap();
+        }
+        let versions: Vec<String> = cache.list().unwrap().iter().map(|c| c.version.to_string()).collect();
+        assert_eq!(versions, vec!["1.10.0", "1.3.0-beta.1", "1.2.0"]);
+
+        let cached = cache.get(&Version::parse("1.2.0").unwrap()).unwrap().unwrap();
+        assert_eq!(std::fs::read(&cached.path).unwrap(), b"1.2.0");
+        assert!(cache.get(&Version::parse("0.9.0").unwrap()).unwrap().is_none());
+        let _ = std::fs::remove_dir_all(root);
+    }
+}
@@ -0,0 +1,85 @@
+//! Checksum release assets: locating them and reading the firmware asset's SHA256.
+//!
+//! Supported formats are `sha256sum` output (`<hash>  <file>`, `<hash> *<file>`), BSD style
+//! (`SHA256 (<file>) = <hash>`) and a bare hash, which is only trusted from a file named after
+//! the asset (`<asset>.sha256`).
+use serde_json::Value;
+
+fn is_sha256(value: &str) -> bool {
+    value.len() == 64 && value.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Final path component, so `./dist/fw.uf2` matches `fw.uf2`
+fn base_name(path: &str) -> &str {
+    path.rsplit(['/', '\\']).next().unwrap_or(path)
+}
+
+/// URL of the checksum asset covering `asset_name`: `<asset>.sha256` first, then a shared
+/// SHA256SUMS / checksums file
+pub fn find_checksum_asset(assets: &[Value], asset_name: &str) -> Option<(String, String)> {
+    let named = |asset: &Value| -> Option<(String, String)> {
+        Some((asset["name"].as_str()?.to_string(), asset["browser_download_url"].as_str()?.to_string()))
+    };
+    let dedicated = format!("{}.sha256", asset_name).to_lowercase();
+    assets.iter().filter_map(named).find(|(name, _)| name.to_lowercase() == dedicated)
+        .or_else(|| {
+            assets.iter().filter_map(named).find(|(name, _)| {
+                let lower = name.to_lowercase();
+                lower.contains("sha256sums") || lower.contains("checksums") || lower == "sha256.txt"
+            })
+        })
+}
+
+/// SHA256 (lowercase) of `asset_name` in a checksum file named `checksum_file`
+pub fn parse_checksum_file(contents: &str, checksum_file: &str, asset_name: &str) -> Option<String> {
+    for line in contents.lines().map(str::trim).filter(|l| !l.is_empty() && !l.starts_with('#')) {
+        // BSD: SHA256 (file) = hash
+        if let Some(rest) = line.strip_prefix("SHA256 (") {
+            if let Some((file, hash)) = rest.split_once(") = ") {
+                if base_name(file) == asset_name && is_sha256(hash.trim()) {
+                    return Some(hash.trim().to_lowercase());
+                }
+            }
+            continue;
+        }
+        let mut parts = line.split_whitespace();
+        let (Some(hash), file) = (parts.next(), parts.next()) else { continue };
+        if !is_sha256(hash) {
+            continue;
+        }
+        match file {
+            Some(file) if base_name(file.trim_start_matches('*')) == asset_name => return Some(hash.to_lowercase()),
+            None if checksum_file.eq_ignore_ascii_case(&format!("{}.sha256", asset_name)) => return Some(hash.to_lowercase()),
+            _ => {}
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HASH: &str = "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08";
+
+    #[test]
+    fn parses_checksum_formats() {
+        let sums = format!("{}  other.bin\n{} *dist/joycore.uf2\n", "0".repeat(64), HASH.to_uppercase());
+        assert_eq!(parse_checksum_file(&sums, "SHA256SUMS", "joycore.uf2").as_deref(), Some(HASH));
+        let bsd = format!("SHA256 (joycore.uf2) = {}", HASH);
+        assert_eq!(parse_checksum_file(&bsd, "checksums.txt", "joycore.uf2").as_deref(), Some(HASH));
+        assert_eq!(parse_checksum_file(HASH, "joycore.uf2.sha256", "joycore.uf2").as_deref(), Some(HASH));
+        assert_eq!(parse_checksum_file(HASH, "SHA256SUMS", "joycore.uf2"), None, "bare hash needs a per-asset file");
+    }
+
+    #[test]
+    fn prefers_dedicated_checksum_asset() {
+        let assets: Vec<Value> = serde_json::from_str(r#"[
+            {"name": "SHA256SUMS", "browser_download_url": "https://x/SHA256SUMS"},
+            {"name": "joycore.uf2.sha256", "browser_download_url": "https://x/joycore.uf2.sha256"},
+            {"name": "joycore.uf2", "browser_download_url": "https://x/joycore.uf2"}
+        ]"#).unwrap();
+        assert_eq!(find_checksum_asset(&assets, "joycore.uf2").unwrap().0, "joycore.uf2.sha256");
+        assert_eq!(find_checksum_asset(&assets[..1], "joycore.uf2").unwrap().0, "SHA256SUMS");
+    }
+}
@@ -0,0 +1,147 @@
+//! Binary delta firmware updates.
+//!
+//! A release may publish patches next to the full image, named
+//! `<name>-<from version>-to-<to version>.bsdiff` (e.g. `joycore-rev2-1.3.0-to-1.4.0.bsdiff`).
+//! When the image of `<from version>` is in the local [`FirmwareCache`](super::FirmwareCache),
+//! the patch is downloaded instead of the full image and applied to it. The rebuilt image must
+//! match the release SHA256; releases without a known checksum are always downloaded in full.
+//!
+//! Patches use the format of the `bsdiff` crate: bsdiff 4 control, diff and extra data
+//! interleaved per block and not compressed, with no header. Each block is
+//!
+//! ```text
+//! add_len:i64 | copy_len:i64 | seek_len:i64 | add_len diff bytes | copy_len extra bytes
+//! ```
+//!
+//! with integers as 8 bytes little-endian magnitude and the sign in the top bit.
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::hardware::asset_revision;
+
+pub const DELTA_EXTENSION: &str = ".bsdiff";
+/// Rebuilt images larger than this are rejected as a corrupt patch
+const MAX_IMAGE_BYTES: usize = 16 * 1024 * 1024;
+
+/// Patch asset turning the image of `from` into the image of the release it belongs to
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeltaPatch {
+    pub from: Version,
+    pub asset_name: String,
+    pub download_url: String,
+    pub size_bytes: u64,
+}
+
+/// `(from, to)` versions of a patch asset name
+pub fn parse_delta_name(name: &str) -> Option<(Version, Version)> {
+    let stem = name.strip_suffix(DELTA_EXTENSION)?;
+    let (left, right) = stem.rsplit_once("-to-")?;
+    let parse = |v: &str| Version::parse(v.strip_prefix('v').unwrap_or(v)).ok();
+    let to = parse(right)?;
+    // The version is the part after the first dash that parses, so pre-release dashes stay in it
+    let from = left.match_indices('-').find_map(|(i, _)| parse(&left[i + 1..])).or_else(|| parse(left))?;
+    Some((from, to))
+}
+
+/// Patches of a release that produce `version` for boards of `revision` (None: generic image)
+pub fn find_delta_patches(assets: &[Value], version: &Version, revision: Option<&str>) -> Vec<DeltaPatch> {
+    assets.iter()
+        .filter_map(|asset| {
+            let name = asset["name"].as_str()?;
+            let (from, to) = parse_delta_name(name)?;
+            if &to != version || from >= to || asset_revision(name).as_deref() != revision {
+                return None;
+            }
+            Some(DeltaPatch {
+                from,
+                asset_name: name.to_string(),
+                download_url: asset["browser_download_url"].as_str()?.to_string(),
+                size_bytes: asset["size"].as_u64().unwrap_or(0),
+            })
+        })
+        .collect()
+}
+
+fn offtin(bytes: &[u8]) -> i64 {
+    let mut raw = [0u8; 8];
+    raw.copy_from_slice(&bytes[..8]);
+    let magnitude = (u64::from_le_bytes(raw) & !(1 << 63)) as i64;
+    if raw[7] & 0x80 != 0 { -magnitude } else { magnitude }
+}
+
+/// Rebuild the new image from `old` and a patch
+pub fn apply_patch(old: &[u8], patch: &[u8]) -> Result<Vec<u8>, String> {
+    let mut new = Vec::with_capacity(old.len());
+    let mut old_pos: i64 = 0;
+    let mut at = 0usize;
+    while at < patch.len() {
+        let control = patch.get(at..at + 24).ok_or("Truncated patch control block")?;
+        let (add, copy, seek) = (offtin(&control[..8]), offtin(&control[8..16]), offtin(&control[16..]));
+        at += 24;
+        if add < 0 || copy < 0 || new.len() as i64 + add + copy > MAX_IMAGE_BYTES as i64 {
+            return Err(format!("Invalid patch block at byte {}", at - 24));
+        }
+        let (add, copy) = (add as usize, copy as usize);
+
+        let diff = patch.get(at..at + add).ok_or("Truncated patch diff data")?;
+        at += add;
+        for (i, &d) in diff.iter().enumerate() {
+            let pos = old_pos + i as i64;
+            let base = if pos >= 0 { old.get(pos as usize).copied().unwrap_or(0) } else { 0 };
+            new.push(base.wrapping_add(d));
+        }
+        old_pos += add as i64;
+
+        let extra = patch.get(at..at + copy).ok_or("Truncated patch extra data")?;
+        at += copy;
+        new.extend_from_slice(extra);
+        old_pos += seek;
+    }
+    Ok(new)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn int(v: i64) -> [u8; 8] {
+        let mut bytes = v.unsigned_abs().to_le_bytes();
+        if v < 0 {
+            bytes[7] |= 0x80;
+        }
+        bytes
+    }
+
+    #[test]
+    fn parses_patch_names() {
+        let v = |s: &str| Version::parse(s).unwrap();
+        assert_eq!(parse_delta_name("joycore-rev2-1.3.0-to-1.4.0.bsdiff"), Some((v("1.3.0"), v("1.4.0"))));
+        assert_eq!(parse_delta_name("joycore-1.3.0-beta.1-to-v1.3.0.bsdiff"), Some((v("1.3.0-beta.1"), v("1.3.0"))));
+        assert_eq!(parse_delta_name("joycore-1.4.0.uf2"), None);
+
+        let assets: Vec<Value> = ["joycore-1.3.0-to-1.4.0.bsdiff", "joycore-rev2-1.2.0-to-1.4.0.bsdiff", "joycore-1.2.0-to-1.3.0.bsdiff"]
+            .iter().map(|n| serde_json::json!({ "name": n, "browser_download_url": format!("https://x/{}", n), "size": 10 })).collect();
+        let generic = find_delta_patches(&assets, &v("1.4.0"), None);
+        assert_eq!(generic.len(), 1);
+        assert_eq!(generic[0].from, v("1.3.0"));
+        assert_eq!(find_delta_patches(&assets, &v("1.4.0"), Some("2"))[0].from, v("1.2.0"));
+    }
+
+    #[test]
+    fn applies_blocks_and_rejects_truncation() {
+        let old = b"JOYCORE v1 firmware".to_vec();
+        // Change "1" to "2" by adding 1, keep the rest, then append extra bytes
+        let mut patch = Vec::new();
+        patch.extend(int(old.len() as i64));
+        patch.extend(int(3));
+        patch.extend(int(0));
+        patch.extend(old.iter().enumerate().map(|(i, _)| if i == 9 { 1u8 } else { 0 }));
+        patch.extend(b"!!!");
+        assert_eq!(apply_patch(&old, &patch).unwrap(), b"JOYCORE v2 firmware!!!");
+
+        assert!(apply_patch(&old, &patch[..patch.len() - 1]).is_err());
+        assert!(apply_patch(&old, &patch[..10]).is_err());
+        assert_eq!(offtin(&int(-5)), -5);
+    }
+}
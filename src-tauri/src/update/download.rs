@@ -0,0 +1,158 @@
+//! Cancellation and resume support for firmware downloads.
+//!
+//! Downloads are written to `<output>.part` and renamed once complete. A cancelled or failed
+//! download leaves the partial file behind; the next download of the same release continues it
+//! with an HTTP `Range` request. Servers that ignore the range answer 200 and the download
+//! restarts from zero.
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use once_cell::sync::Lazy;
+use tokio::sync::Notify;
+
+/// Suffix appended to the output file name while downloading
+pub const PARTIAL_SUFFIX: &str = "part";
+/// Resume attempts within one download after the connection drops
+pub const MAX_RESUME_ATTEMPTS: u32 = 3;
+
+/// Cheap cloneable cancellation flag for one download
+#[derive(Clone, Default)]
+pub struct CancelToken {
+    inner: Arc<CancelInner>,
+}
+
+#[derive(Default)]
+struct CancelInner {
+    cancelled: AtomicBool,
+    notify: Notify,
+}
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.inner.cancelled.store(true, Ordering::SeqCst);
+        self.inner.notify.notify_waiters();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Resolves once `cancel` has been called
+    pub async fn cancelled(&self) {
+        loop {
+            // Register before checking the flag so a concurrent cancel is not missed
+            let notified = self.inner.notify.notified();
+            if self.is_cancelled() {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
+
+/// Where a download to `output_path` keeps its partial data
+pub fn partial_path(output_path: &Path) -> PathBuf {
+    let mut name = output_path.file_name().unwrap_or_default().to_os_string();
+    name.push(".");
+    name.push(PARTIAL_SUFFIX);
+    output_path.with_file_name(name)
+}
+
+/// First byte offset of a `Content-Range: bytes <start>-<end>/<total>` header
+pub fn content_range_start(value: &str) -> Option<u64> {
+    let range = value.trim().strip_prefix("bytes")?.trim_start();
+    let (start, _) = range.split_once('-')?;
+    start.trim().parse().ok()
+}
+
+/// Downloads in progress, keyed by firmware version
+#[derive(Default)]
+pub struct ActiveDownloads {
+    tokens: Mutex<HashMap<String, CancelToken>>,
+}
+
+impl ActiveDownloads {
+    /// Track a new download of `version`, cancelling an earlier one of the same version
+    pub fn register(&self, version: &str) -> CancelToken {
+        let token = CancelToken::new();
+        let mut tokens = self.tokens.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(previous) = tokens.insert(version.to_string(), token.clone()) {
+            previous.cancel();
+        }
+        token
+    }
+
+    /// Stop tracking `version` if `token` is still its current download
+    pub fn finish(&self, version: &str, token: &CancelToken) {
+        let mut tokens = self.tokens.lock().unwrap_or_else(|e| e.into_inner());
+        if tokens.get(version).is_some_and(|t| Arc::ptr_eq(&t.inner, &token.inner)) {
+            tokens.remove(version);
+        }
+    }
+
+    /// Cancel the download of `version`, or every download when `None`. Returns the cancelled versions.
+    pub fn cancel(&self, version: Option<&str>) -> Vec<String> {
+        let tokens = self.tokens.lock().unwrap_or_else(|e| e.into_inner());
+        let mut cancelled = Vec::new();
+        for (v, token) in tokens.iter() {
+            if version.map_or(true, |wanted| wanted == v.as_str()) {
+                token.cancel();
+                cancelled.push(v.clone());
+            }
+        }
+        cancelled
+    }
+}
+
+static ACTIVE_DOWNLOADS: Lazy<ActiveDownloads> = Lazy::new(ActiveDownloads::default);
+
+/// Process-wide registry used by `download_firmware_update` and `cancel_firmware_download`
+pub fn active_downloads() -> &'static ActiveDownloads {
+    &ACTIVE_DOWNLOADS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_content_range() {
+        assert_eq!(content_range_start("bytes 1024-4095/4096"), Some(1024));
+        assert_eq!(content_range_start("bytes 0-0/*"), Some(0));
+        assert_eq!(content_range_start("bytes */4096"), None);
+        assert_eq!(content_range_start("items 1-2/3"), None);
+    }
+
+    #[test]
+    fn partial_file_sits_next_to_output() {
+        let path = Path::new("downloads").join("firmware-1.2.0.uf2");
+        assert_eq!(partial_path(&path), Path::new("downloads").join("firmware-1.2.0.uf2.part"));
+    }
+
+    #[tokio::test]
+    async fn cancel_wakes_waiters_and_replaces_registrations() {
+        let downloads = ActiveDownloads::default();
+        let first = downloads.register("1.0.0");
+        let second = downloads.register("1.0.0");
+        assert!(first.is_cancelled(), "re-registering cancels the older download");
+
+        let waiter = tokio::spawn({
+            let second = second.clone();
+            async move { second.cancelled().await }
+        });
+        assert_eq!(downloads.cancel(Some("2.0.0")), Vec::<String>::new());
+        assert_eq!(downloads.cancel(None), vec!["1.0.0".to_string()]);
+        tokio::time::timeout(std::time::Duration::from_secs(1), waiter).await.unwrap().unwrap();
+
+        downloads.finish("1.0.0", &first);
+        assert_eq!(downloads.cancel(Some("1.0.0")).len(), 1, "stale token does not unregister the current one");
+        downloads.finish("1.0.0", &second);
+        assert!(downloads.cancel(None).is_empty());
+    }
+}
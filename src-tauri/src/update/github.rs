@@ -0,0 +1,115 @@
+//! GitHub API helpers: rate-limit detection and the cache of recent update checks.
+//!
+//! Unauthenticated clients get 60 API requests per hour. When the budget runs out GitHub answers
+//! 403 with `x-ratelimit-remaining: 0` (primary limit) or 403/429 with `retry-after` (secondary
+//! limit). Successful checks are remembered for [`CHECK_CACHE_TTL`] so repeated checks do not
+//! spend the budget.
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use reqwest::header::HeaderMap;
+use reqwest::StatusCode;
+
+use super::models::{FirmwareRelease, UpdateChannel, UpdateError};
+
+/// How long a successful update check is reused
+pub const CHECK_CACHE_TTL: Duration = Duration::from_secs(10 * 60);
+
+fn header<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers.get(name).and_then(|v| v.to_str().ok()).map(str::trim)
+}
+
+/// `UpdateError::RateLimited` when the response is a GitHub rate-limit rejection
+pub fn rate_limit_error(status: StatusCode, headers: &HeaderMap, now: DateTime<Utc>) -> Option<UpdateError> {
+    let exhausted = header(headers, "x-ratelimit-remaining") == Some("0");
+    let retry_after = header(headers, "retry-after").and_then(|v| v.parse::<i64>().ok());
+    let limited = status == StatusCode::TOO_MANY_REQUESTS
+        || (status == StatusCode::FORBIDDEN && (exhausted || retry_after.is_some()));
+    if !limited {
+        return None;
+    }
+    let reset_at = match retry_after {
+        Some(secs) => Some(now + chrono::Duration::seconds(secs)),
+        None => header(headers, "x-ratelimit-reset")
+            .and_then(|v| v.parse::<i64>().ok())
+            .and_then(|secs| DateTime::from_timestamp(secs, 0)),
+    };
+    Some(UpdateError::RateLimited { reset_at })
+}
+
+/// Newest release per repository and channel from recent successful checks
+#[derive(Default)]
+pub struct CheckCache {
+    entries: Mutex<HashMap<String, (Instant, FirmwareRelease)>>,
+}
+
+fn cache_key(repo_owner: &str, repo_name: &str, channel: UpdateChannel, revision: Option<&str>) -> String {
+    format!("{}/{}/{:?}/{}", repo_owner.to_lowercase(), repo_name.to_lowercase(), channel, revision.unwrap_or("any"))
+}
+
+impl CheckCache {
+    /// Release found by a check younger than `max_age`
+    pub fn get(&self, repo_owner: &str, repo_name: &str, channel: UpdateChannel, revision: Option<&str>, max_age: Duration) -> Option<FirmwareRelease> {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.get(&cache_key(repo_owner, repo_name, channel, revision))
+            .filter(|(checked_at, _)| checked_at.elapsed() < max_age)
+            .map(|(_, release)| release.clone())
+    }
+
+    pub fn put(&self, repo_owner: &str, repo_name: &str, channel: UpdateChannel, revision: Option<&str>, release: FirmwareRelease) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.insert(cache_key(repo_owner, repo_name, channel, revision), (Instant::now(), release));
+    }
+}
+
+static LAST_CHECKS: Lazy<CheckCache> = Lazy::new(CheckCache::default);
+
+/// Process-wide cache shared by all `UpdateService` instances
+pub fn last_checks() -> &'static CheckCache {
+    &LAST_CHECKS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    #[test]
+    fn detects_rate_limit_responses() {
+        let now = Utc::now();
+        let mut headers = HeaderMap::new();
+        headers.insert("x-ratelimit-remaining", HeaderValue::from_static("0"));
+        headers.insert("x-ratelimit-reset", HeaderValue::from_static("1700000000"));
+        match rate_limit_error(StatusCode::FORBIDDEN, &headers, now) {
+            Some(UpdateError::RateLimited { reset_at }) => assert_eq!(reset_at.unwrap().timestamp(), 1_700_000_000),
+            other => panic!("expected rate limit, got {:?}", other),
+        }
+
+        let mut secondary = HeaderMap::new();
+        secondary.insert("retry-after", HeaderValue::from_static("60"));
+        match rate_limit_error(StatusCode::TOO_MANY_REQUESTS, &secondary, now) {
+            Some(UpdateError::RateLimited { reset_at }) => assert_eq!(reset_at, Some(now + chrono::Duration::seconds(60))),
+            other => panic!("expected rate limit, got {:?}", other),
+        }
+
+        assert!(rate_limit_error(StatusCode::FORBIDDEN, &HeaderMap::new(), now).is_none(), "plain 403 is not a rate limit");
+        assert!(rate_limit_error(StatusCode::NOT_FOUND, &headers, now).is_none());
+    }
+
+    #[test]
+    fn cached_checks_expire() {
+        let cache = CheckCache::default();
+        let release: FirmwareRelease = serde_json::from_value(serde_json::json!({
+            "version": "1.2.0", "download_url": "", "changelog": "",
+            "published_at": "2024-05-01T00:00:00Z", "size_bytes": 0, "sha256_hash": null
+        })).unwrap();
+        cache.put("GingerSkull", "JoyCore-FW", UpdateChannel::Stable, Some("2"), release);
+        assert!(cache.get("gingerskull", "joycore-fw", UpdateChannel::Stable, Some("2"), CHECK_CACHE_TTL).is_some());
+        assert!(cache.get("gingerskull", "joycore-fw", UpdateChannel::Stable, None, CHECK_CACHE_TTL).is_none());
+        assert!(cache.get("gingerskull", "joycore-fw", UpdateChannel::Beta, Some("2"), CHECK_CACHE_TTL).is_none());
+        assert!(cache.get("gingerskull", "joycore-fw", UpdateChannel::Stable, Some("2"), Duration::ZERO).is_none());
+    }
+}
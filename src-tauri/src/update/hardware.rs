@@ -0,0 +1,107 @@
+//! Hardware revision gating of firmware assets.
+//!
+//! Boards report their revision as a fifth IDENTIFY field
+//! (`JOYCORE_ID:JOYCORE-FW:<magic>:<version>:rev2`) or a STATUS field (`HW: rev2`). Assets built
+//! for one revision carry it in the file name, e.g. `joycore-rev2-1.4.0.uf2`; assets without a
+//! revision tag run on every board. An asset for a different revision is never offered.
+use serde_json::Value;
+
+/// Canonical form of a revision: lowercase without the `rev` prefix ("Rev 2" -> "2")
+pub fn normalize_revision(raw: &str) -> Option<String> {
+    let lower = raw.trim().to_ascii_lowercase();
+    let rev = lower.strip_prefix("rev").unwrap_or(&lower).trim_start_matches(['.', '_', '-', ' ']);
+    (!rev.is_empty() && rev.chars().all(|c| c.is_ascii_alphanumeric())).then(|| rev.to_string())
+}
+
+/// Revision in a STATUS reply (`HW`, `HARDWARE`, `BOARD` or `REV` field)
+pub fn status_revision(status: &str) -> Option<String> {
+    status.split([',', '\n']).find_map(|field| {
+        let (key, value) = field.split_once(':')?;
+        let key = key.rsplit(" - ").next().unwrap_or(key).trim().to_ascii_uppercase();
+        matches!(key.as_str(), "HW" | "HW_REV" | "HARDWARE" | "BOARD" | "REV").then(|| normalize_revision(value)).flatten()
+    })
+}
+
+pub fn is_firmware_asset(name: &str) -> bool {
+    let lower = name.to_lowercase();
+    let is_checksum = lower.ends_with(".sha256");
+    let is_signature = lower.ends_with(".sig") || lower.ends_with(".minisig");
+    !is_checksum && !is_signature && (lower.ends_with(".uf2") || lower.ends_with(".bin") || lower.contains("firmware"))
+}
+
+/// Revision an asset was built for, from a `rev<N>` name component
+pub fn asset_revision(name: &str) -> Option<String> {
+    name.to_ascii_lowercase()
+        .split(['-', '_', '.'])
+        .find(|part| part.len() > 3 && part.starts_with("rev"))
+        .and_then(normalize_revision)
+}
+
+/// Firmware asset of a release that fits `revision`, preferring one built for exactly that
+/// revision over a generic one. With an unknown revision only generic assets, or the assets of
+/// a release targeting a single revision, are offered.
+pub fn select_firmware_asset<'a>(assets: &'a [Value], revision: Option<&str>) -> Result<(&'a Value, Option<String>), String> {
+    let candidates: Vec<(&Value, Option<String>)> = assets.iter()
+        .filter(|asset| is_firmware_asset(asset["name"].as_str().unwrap_or("")))
+        .map(|asset| (asset, asset_revision(asset["name"].as_str().unwrap_or(""))))
+        .collect();
+    if candidates.is_empty() {
+        return Err("No firmware asset found in GitHub release".to_string());
+    }
+    let mut revisions: Vec<&str> = candidates.iter().filter_map(|(_, rev)| rev.as_deref()).collect();
+    revisions.sort_unstable();
+    revisions.dedup();
+
+    let revision = revision.and_then(normalize_revision);
+    let exact = revision.as_ref().and_then(|wanted| candidates.iter().find(|(_, rev)| rev.as_ref() == Some(wanted)));
+    let generic = candidates.iter().find(|(_, rev)| rev.is_none());
+    if let Some((asset, rev)) = exact.or(generic) {
+        return Ok((asset, rev.clone()));
+    }
+    match revision {
+        Some(wanted) => Err(format!(
+            "Release only has firmware for hardware revision {}; the device is revision {}",
+            revisions.join(", "), wanted
+        )),
+        None if revisions.len() == 1 => Ok((candidates[0].0, candidates[0].1.clone())),
+        None => Err(format!(
+            "Release has firmware for hardware revisions {} and the device did not report its revision",
+            revisions.join(", ")
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assets(names: &[&str]) -> Vec<Value> {
+        names.iter().map(|n| serde_json::json!({ "name": n, "browser_download_url": format!("https://x/{}", n) })).collect()
+    }
+
+    #[test]
+    fn parses_revisions() {
+        assert_eq!(normalize_revision("Rev 2").as_deref(), Some("2"));
+        assert_eq!(normalize_revision("revB").as_deref(), Some("b"));
+        assert_eq!(normalize_revision(" "), None);
+        assert_eq!(asset_revision("joycore-rev2-1.4.0.uf2").as_deref(), Some("2"));
+        assert_eq!(asset_revision("joycore-1.4.0.uf2"), None);
+        assert_eq!(status_revision("Config Status - Storage: OK, Loaded: YES, Version: 7, HW: rev3").as_deref(), Some("3"));
+        assert_eq!(status_revision("Config Status - Storage: OK, Loaded: YES, Version: 7"), None);
+    }
+
+    #[test]
+    fn selects_only_matching_assets() {
+        let release = assets(&["joycore-rev1-1.4.0.uf2", "joycore-rev2-1.4.0.uf2", "joycore-rev2-1.4.0.uf2.sha256"]);
+        let (asset, rev) = select_firmware_asset(&release, Some("rev2")).unwrap();
+        assert_eq!(asset["name"], "joycore-rev2-1.4.0.uf2");
+        assert_eq!(rev.as_deref(), Some("2"));
+        assert!(select_firmware_asset(&release, Some("3")).is_err());
+        assert!(select_firmware_asset(&release, None).is_err());
+
+        let mixed = assets(&["joycore-1.4.0.uf2", "joycore-rev1-1.4.0.uf2"]);
+        assert_eq!(select_firmware_asset(&mixed, Some("3")).unwrap().0["name"], "joycore-1.4.0.uf2");
+        assert_eq!(select_firmware_asset(&mixed, None).unwrap().1, None);
+        assert_eq!(select_firmware_asset(&assets(&["joycore-rev1-1.4.0.uf2"]), None).unwrap().1.as_deref(), Some("1"));
+    }
+}
@@ -1,5 +1,15 @@
+pub mod cache;
+pub mod checksum;
+pub mod delta;
+pub mod download;
+pub mod github;
+pub mod hardware;
+pub mod scheduler;
 pub mod service;
+pub mod signature;
 pub mod models;
 
+pub use cache::{CachedFirmware, FirmwareCache};
+pub use download::{active_downloads, CancelToken};
 pub use service::UpdateService;
 pub use models::*;
\ No newline at end of file
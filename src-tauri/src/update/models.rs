@@ -9,6 +9,62 @@ pub struct FirmwareRelease {
     pub published_at: chrono::DateTime<chrono::Utc>,
     pub size_bytes: u64,
     pub sha256_hash: Option<String>,
+    /// Marked as pre-release on GitHub
+    #[serde(default)]
+    pub prerelease: bool,
+    /// File name of the firmware asset
+    #[serde(default)]
+    pub asset_name: String,
+    /// Checksum asset (`<asset>.sha256` or SHA256SUMS) covering the firmware asset
+    #[serde(default)]
+    pub checksum_url: Option<String>,
+    /// Minisign signature asset (`<asset>.minisig` or `<asset>.sig`)
+    #[serde(default)]
+    pub signature_url: Option<String>,
+    /// Hardware revision the firmware asset was built for; None runs on every board
+    #[serde(default)]
+    pub hardware_revision: Option<String>,
+    /// Binary patches producing this image from earlier versions
+    #[serde(default)]
+    pub delta_patches: Vec<super::delta::DeltaPatch>,
+}
+
+/// Firmware release channel. Each channel also offers the releases of the more stable ones.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UpdateChannel {
+    /// Full releases only
+    #[default]
+    Stable,
+    /// Pre-releases (`-beta`, `-rc`, ... or the GitHub pre-release flag)
+    Beta,
+    /// Development builds (tags containing `nightly` or `dev`)
+    Nightly,
+}
+
+impl FirmwareRelease {
+    /// Most stable channel that offers this release
+    pub fn channel(&self) -> UpdateChannel {
+        let pre = self.version.pre.as_str().to_ascii_lowercase();
+        if pre.contains("nightly") || pre.contains("dev") {
+            UpdateChannel::Nightly
+        } else if self.prerelease || !pre.is_empty() {
+            UpdateChannel::Beta
+        } else {
+            UpdateChannel::Stable
+        }
+    }
+}
+
+impl UpdateChannel {
+    pub fn includes(self, release: &FirmwareRelease) -> bool {
+        release.channel() <= self
+    }
+
+    /// Newest release offered on this channel
+    pub fn latest(self, releases: &[FirmwareRelease]) -> Option<&FirmwareRelease> {
+        releases.iter().filter(|r| self.includes(r)).max_by(|a, b| a.version.cmp(&b.version))
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -19,6 +75,15 @@ pub struct VersionCheckResult {
     pub release_info: Option<FirmwareRelease>,
 }
 
+/// HTTP client settings for reaching GitHub
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UpdateClientOptions {
+    /// Proxy for all requests, e.g. `http://proxy.corp:8080` (credentials may be embedded)
+    pub proxy_url: Option<String>,
+    /// Personal access token; raises the API rate limit from 60 to 5000 requests per hour
+    pub github_token: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DownloadProgress {
     pub downloaded_bytes: u64,
@@ -27,6 +92,23 @@ pub struct DownloadProgress {
     pub speed_bps: u64,
 }
 
+/// Payload of the `download_resumed` event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DownloadResumed {
+    pub version: Version,
+    /// Bytes already on disk that were not downloaded again
+    pub resumed_from_bytes: u64,
+    pub total_bytes: u64,
+}
+
+/// Payload of the `download_cancelled` event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DownloadCancelled {
+    pub version: Version,
+    /// Bytes kept in the partial file for a later resume
+    pub downloaded_bytes: u64,
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum UpdateError {
     #[error("Network error: {0}")]
@@ -52,6 +134,51 @@ pub enum UpdateError {
     
     #[error("Download interrupted")]
     DownloadInterrupted,
+
+    #[error("Download cancelled")]
+    Cancelled,
+
+    #[error("Firmware signature not verified: {0}")]
+    UnsignedFirmware(String),
+
+    #[error("GitHub API rate limit exceeded{}", .reset_at.map(|t| format!(", resets at {}", t.to_rfc3339())).unwrap_or_default())]
+    RateLimited { reset_at: Option<chrono::DateTime<chrono::Utc>> },
 }
 
-pub type UpdateResult<T> = Result<T, UpdateError>;
\ No newline at end of file
+pub type UpdateResult<T> = Result<T, UpdateError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn release(version: &str, prerelease: bool) -> FirmwareRelease {
+        FirmwareRelease {
+            version: Version::parse(version).unwrap(),
+            download_url: String::new(),
+            changelog: String::new(),
+            published_at: chrono::Utc::now(),
+            size_bytes: 0,
+            sha256_hash: None,
+            prerelease,
+            asset_name: String::new(),
+            checksum_url: None,
+            signature_url: None,
+            hardware_revision: None,
+            delta_patches: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn channels_filter_releases() {
+        let releases = vec![
+            release("1.2.0", false),
+            release("1.3.0-beta.1", true),
+            release("1.3.0-nightly.20240501", true),
+            release("1.2.1", true),
+        ];
+        assert_eq!(UpdateChannel::Stable.latest(&releases).unwrap().version.to_string(), "1.2.0");
+        assert_eq!(UpdateChannel::Beta.latest(&releases).unwrap().version.to_string(), "1.3.0-beta.1");
+        assert_eq!(UpdateChannel::Nightly.latest(&releases).unwrap().version.to_string(), "1.3.0-nightly.20240501");
+        assert_eq!(releases[3].channel(), UpdateChannel::Beta, "GitHub pre-release flag without a semver suffix");
+    }
+}
\ No newline at end of file
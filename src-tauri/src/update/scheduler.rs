@@ -0,0 +1,99 @@
+//! When to run background update checks.
+//!
+//! The device manager wakes every [`SCHEDULER_TICK`] and checks the configured channel once
+//! `check_interval_hours` have passed since the `last_check` stored in the settings, so the
+//! schedule survives restarts. Failed checks are retried after [`RETRY_DELAY`], or when GitHub
+//! reports a rate limit, after its reset time. Each newer release is announced once per session
+//! with [`FIRMWARE_UPDATE_AVAILABLE_EVENT`].
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use semver::Version;
+use serde::Serialize;
+
+use super::models::{FirmwareRelease, UpdateError};
+
+pub const FIRMWARE_UPDATE_AVAILABLE_EVENT: &str = "firmware_update_available";
+pub const SCHEDULER_TICK: Duration = Duration::from_secs(15 * 60);
+/// Delay before the first scheduled check, leaving startup to discovery and auto-connect
+pub const STARTUP_DELAY: Duration = Duration::from_secs(60);
+pub const MIN_CHECK_INTERVAL_HOURS: u64 = 1;
+pub const RETRY_DELAY: Duration = Duration::from_secs(60 * 60);
+
+/// Payload of `firmware_update_available`
+#[derive(Debug, Clone, Serialize)]
+pub struct FirmwareUpdateAvailable {
+    pub current_version: Version,
+    /// Release including its notes (`changelog`)
+    pub release: FirmwareRelease,
+}
+
+/// Session state of the scheduler; the last successful check itself lives in the settings
+#[derive(Debug, Default)]
+pub struct UpdateSchedule {
+    retry_at: Option<DateTime<Utc>>,
+    notified: Option<Version>,
+}
+
+impl UpdateSchedule {
+    pub fn is_due(&self, auto_check: bool, interval_hours: u64, last_check: Option<DateTime<Utc>>, now: DateTime<Utc>) -> bool {
+        if !auto_check || self.retry_at.is_some_and(|at| now < at) {
+            return false;
+        }
+        let interval = chrono::Duration::hours(interval_hours.max(MIN_CHECK_INTERVAL_HOURS) as i64);
+        last_check.map_or(true, |last| now - last >= interval)
+    }
+
+    pub fn succeeded(&mut self) {
+        self.retry_at = None;
+    }
+
+    pub fn failed(&mut self, error: &UpdateError, now: DateTime<Utc>) {
+        let retry = now + chrono::Duration::from_std(RETRY_DELAY).unwrap_or_else(|_| chrono::Duration::hours(1));
+        self.retry_at = Some(match error {
+            UpdateError::RateLimited { reset_at: Some(reset_at) } => (*reset_at).max(retry),
+            _ => retry,
+        });
+    }
+
+    /// Whether `version` has not been announced yet; remembers it
+    pub fn should_notify(&mut self, version: &Version) -> bool {
+        if self.notified.as_ref().is_some_and(|notified| notified >= version) {
+            return false;
+        }
+        self.notified = Some(version.clone());
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checks_after_interval_and_backs_off() {
+        let now = Utc::now();
+        let mut schedule = UpdateSchedule::default();
+        assert!(schedule.is_due(true, 24, None, now));
+        assert!(!schedule.is_due(false, 24, None, now));
+        assert!(!schedule.is_due(true, 24, Some(now - chrono::Duration::hours(23)), now));
+        assert!(schedule.is_due(true, 24, Some(now - chrono::Duration::hours(24)), now));
+        assert!(schedule.is_due(true, 0, Some(now - chrono::Duration::hours(1)), now), "interval is at least an hour");
+
+        let reset_at = now + chrono::Duration::hours(3);
+        schedule.failed(&UpdateError::RateLimited { reset_at: Some(reset_at) }, now);
+        assert!(!schedule.is_due(true, 24, None, now + chrono::Duration::hours(2)));
+        assert!(schedule.is_due(true, 24, None, reset_at));
+        schedule.succeeded();
+        assert!(schedule.is_due(true, 24, None, now));
+    }
+
+    #[test]
+    fn announces_each_release_once() {
+        let mut schedule = UpdateSchedule::default();
+        let v = |s: &str| Version::parse(s).unwrap();
+        assert!(schedule.should_notify(&v("1.4.0")));
+        assert!(!schedule.should_notify(&v("1.4.0")));
+        assert!(schedule.should_notify(&v("1.5.0")));
+    }
+}
@@ -1,19 +1,28 @@
 use std::path::Path;
-use tokio::fs::File;
+use tokio::fs::{File, OpenOptions};
 use tokio::io::{AsyncWriteExt, AsyncReadExt};
-use reqwest::Client;
+use reqwest::header::{CONTENT_RANGE, RANGE};
+use reqwest::{Client, StatusCode};
 use semver::Version;
 use serde_json::Value;
 use sha2::{Sha256, Digest};
 use log::{debug, info, error};
 
-use super::models::{FirmwareRelease, VersionCheckResult, DownloadProgress, UpdateResult, UpdateError};
+use super::cache::FirmwareCache;
+use super::checksum::{find_checksum_asset, parse_checksum_file};
+use super::delta::{apply_patch, find_delta_patches};
+use super::download::{content_range_start, partial_path, CancelToken, MAX_RESUME_ATTEMPTS};
+use super::signature::{find_signature_asset, signature_path};
+use super::github::{last_checks, rate_limit_error, CHECK_CACHE_TTL};
+use super::hardware::select_firmware_asset;
+use super::models::{FirmwareRelease, VersionCheckResult, DownloadProgress, DownloadResumed, UpdateChannel, UpdateClientOptions, UpdateResult, UpdateError};
 
 pub struct UpdateService {
     client: Client,
     github_api_base: String,
     repo_owner: String,
     repo_name: String,
+    github_token: Option<String>,
 }
 
 impl UpdateService {
@@ -23,38 +32,56 @@ impl UpdateService {
             github_api_base: "https://api.github.com".to_string(),
             repo_owner,
             repo_name,
+            github_token: None,
         }
     }
 
-    /// Check GitHub releases for the latest firmware version
-    pub async fn check_for_updates(&self, current_version: Version) -> UpdateResult<VersionCheckResult> {
-        info!("Checking for firmware updates, current version: {}", current_version);
-        
-        let url = format!(
-            "{}/repos/{}/{}/releases/latest",
-            self.github_api_base, self.repo_owner, self.repo_name
-        );
-        
-        debug!("Fetching latest release from: {}", url);
-        
-        let response = self.client
-            .get(&url)
-            .header("Accept", "application/vnd.github+json")
-            .header("User-Agent", "JoyCore-X/1.0")
-            .send()
-            .await?;
-        
-        if !response.status().is_success() {
-            error!("GitHub API request failed with status: {}", response.status());
-            return Err(UpdateError::Network(
-                reqwest::Error::from(response.error_for_status().unwrap_err())
-            ));
+    /// Service using a proxy and/or GitHub token; blank options are ignored
+    pub fn with_options(repo_owner: String, repo_name: String, options: &UpdateClientOptions) -> UpdateResult<Self> {
+        let non_blank = |value: &Option<String>| value.as_deref().map(str::trim).filter(|v| !v.is_empty()).map(str::to_string);
+        let mut builder = Client::builder();
+        if let Some(proxy) = non_blank(&options.proxy_url) {
+            debug!("Using proxy for update requests");
+            builder = builder.proxy(reqwest::Proxy::all(proxy.as_str())?);
         }
-        
-        let release_data: Value = response.json().await?;
-        let release = self.parse_github_release(&release_data)?;
+        Ok(Self {
+            client: builder.build()?,
+            github_token: non_blank(&options.github_token),
+            ..Self::new(repo_owner, repo_name)
+        })
+    }
+
+    /// Check GitHub releases for the newest firmware version on `channel` that has an asset for
+    /// the board's `hardware_revision` (None when the device does not report one)
+    pub async fn check_for_updates(&self, current_version: Version, channel: UpdateChannel, hardware_revision: Option<&str>) -> UpdateResult<VersionCheckResult> {
+        info!("Checking for firmware updates on {:?} channel, current version: {}, hardware revision: {}", channel, current_version, hardware_revision.unwrap_or("unknown"));
+        
+        let cached = last_checks().get(&self.repo_owner, &self.repo_name, channel, hardware_revision, CHECK_CACHE_TTL);
+        if cached.is_some() {
+            debug!("Reusing update check from the last {} minutes", CHECK_CACHE_TTL.as_secs() / 60);
+        }
+        let mut release = if let Some(release) = cached {
+            release
+        } else if channel == UpdateChannel::Stable {
+            // releases/latest never returns pre-releases
+            let url = format!(
+                "{}/repos/{}/{}/releases/latest",
+                self.github_api_base, self.repo_owner, self.repo_name
+            );
+            debug!("Fetching latest release from: {}", url);
+            let release_data = self.get_json(&url).await?;
+            self.parse_github_release(&release_data, hardware_revision)?
+        } else {
+            let releases = self.get_available_versions(hardware_revision).await?;
+            channel.latest(&releases).cloned()
+                .ok_or_else(|| anyhow::anyhow!("No releases found on the {:?} channel", channel))?
+        };
+        last_checks().put(&self.repo_owner, &self.repo_name, channel, hardware_revision, release.clone());
         
         let update_available = release.version > current_version;
+        if update_available {
+            release.sha256_hash = self.resolve_sha256(&release).await;
+        }
         
         info!(
             "Version check complete - Current: {}, Latest: {}, Update available: {}",
@@ -69,8 +96,34 @@ impl UpdateService {
         })
     }
 
-    /// Parse GitHub release JSON into FirmwareRelease struct
-    fn parse_github_release(&self, data: &Value) -> UpdateResult<FirmwareRelease> {
+    /// GET a GitHub API URL and parse the JSON body
+    async fn get_json(&self, url: &str) -> UpdateResult<Value> {
+        let mut request = self.client
+            .get(url)
+            .header("Accept", "application/vnd.github+json")
+            .header("User-Agent", "JoyCore-X/1.0");
+        if let Some(token) = &self.github_token {
+            request = request.bearer_auth(token);
+        }
+        let response = request.send().await?;
+        
+        if let Some(limited) = rate_limit_error(response.status(), response.headers(), chrono::Utc::now()) {
+            error!("GitHub API rate limit reached: {}", limited);
+            return Err(limited);
+        }
+        if !response.status().is_success() {
+            error!("GitHub API request failed with status: {}", response.status());
+            return Err(UpdateError::Network(
+                reqwest::Error::from(response.error_for_status().unwrap_err())
+            ));
+        }
+        
+        Ok(response.json().await?)
+    }
+
+    /// Parse GitHub release JSON into FirmwareRelease struct, picking the firmware asset for
+    /// `hardware_revision`; fails when the release has none for it
+    fn parse_github_release(&self, data: &Value, hardware_revision: Option<&str>) -> UpdateResult<FirmwareRelease> {
         let tag_name = data["tag_name"]
             .as_str()
             .ok_or_else(|| anyhow::anyhow!("Missing tag_name in GitHub release"))?;
@@ -93,13 +146,8 @@ impl UpdateService {
             .as_array()
             .ok_or_else(|| anyhow::anyhow!("Missing assets in GitHub release"))?;
         
-        let firmware_asset = assets
-            .iter()
-            .find(|asset| {
-                let name = asset["name"].as_str().unwrap_or("");
-                name.ends_with(".uf2") || name.ends_with(".bin") || name.contains("firmware")
-            })
-            .ok_or_else(|| anyhow::anyhow!("No firmware asset found in GitHub release"))?;
+        let (firmware_asset, asset_revision) = select_firmware_asset(assets, hardware_revision)
+            .map_err(|e| anyhow::anyhow!("{} ({})", e, tag_name))?;
         
         let download_url = firmware_asset["browser_download_url"]
             .as_str()
@@ -110,8 +158,12 @@ impl UpdateService {
             .as_u64()
             .unwrap_or(0);
         
-        // Try to extract SHA256 hash from release notes or find a checksum file
-        let sha256_hash = self.extract_sha256_from_release(data, &assets);
+        let asset_name = firmware_asset["name"].as_str().unwrap_or("").to_string();
+        // The checksum asset is downloaded on demand (see `resolve_sha256`); release notes are the fallback
+        let checksum_url = find_checksum_asset(assets, &asset_name).map(|(_, url)| url);
+        let signature_url = find_signature_asset(assets, &asset_name);
+        let sha256_hash = self.extract_sha256_from_release(data);
+        let delta_patches = find_delta_patches(assets, &version, asset_revision.as_deref());
         
         Ok(FirmwareRelease {
             version,
@@ -120,26 +172,17 @@ impl UpdateService {
             published_at,
             size_bytes,
             sha256_hash,
+            prerelease: data["prerelease"].as_bool().unwrap_or(false),
+            asset_name,
+            checksum_url,
+            signature_url,
+            hardware_revision: asset_revision,
+            delta_patches,
         })
     }
 
-    /// Extract SHA256 hash from release notes or checksum files
-    fn extract_sha256_from_release(&self, release_data: &Value, assets: &[Value]) -> Option<String> {
-        // First, try to find a dedicated checksum file (like SHA256SUMS, checksums.txt, etc.)
-        for asset in assets {
-            if let Some(asset_name) = asset["name"].as_str() {
-                let name_lower = asset_name.to_lowercase();
-                if name_lower.contains("sha256") || 
-                   name_lower.contains("checksum") || 
-                   name_lower.contains("hash") ||
-                   name_lower.ends_with(".sha256") {
-                    debug!("Found potential checksum file: {}", asset_name);
-                    // In a real implementation, we would download and parse this file
-                    // For now, we'll fall back to parsing the release notes
-                }
-            }
-        }
-
+    /// Extract SHA256 hash from release notes
+    fn extract_sha256_from_release(&self, release_data: &Value) -> Option<String> {
         // Try to extract SHA256 from release body/changelog
         if let Some(body) = release_data["body"].as_str() {
             // Look for SHA256 patterns in the release notes using simple string matching
@@ -173,23 +216,210 @@ impl UpdateService {
         None
     }
 
-    /// Download firmware file with progress tracking
-    pub async fn download_firmware<F>(
+    /// SHA256 of the firmware asset from its checksum asset, if the release has one
+    pub async fn fetch_sha256(&self, release: &FirmwareRelease) -> UpdateResult<Option<String>> {
+        let Some(url) = &release.checksum_url else { return Ok(None) };
+        debug!("Fetching checksum file: {}", url);
+        let response = self.client
+            .get(url)
+            .header("User-Agent", "JoyCore-X/1.0")
+            .send()
+            .await?
+            .error_for_status()?;
+        let contents = response.text().await?;
+        let file_name = url.rsplit('/').next().unwrap_or(url);
+        let hash = parse_checksum_file(&contents, file_name, &release.asset_name);
+        if hash.is_none() {
+            log::warn!("Checksum file {} has no entry for {}", file_name, release.asset_name);
+        }
+        Ok(hash)
+    }
+
+    /// Expected SHA256 of the firmware: the checksum asset when available, else the release notes
+    pub async fn resolve_sha256(&self, release: &FirmwareRelease) -> Option<String> {
+        match self.fetch_sha256(release).await {
+            Ok(Some(hash)) => Some(hash),
+            Ok(None) => release.sha256_hash.clone(),
+            Err(e) => {
+                log::warn!("Failed to fetch checksum file, falling back to release notes: {}", e);
+                release.sha256_hash.clone()
+            }
+        }
+    }
+
+    /// Download firmware file with progress tracking. Data goes to a `.part` file first, so a
+    /// cancelled or failed download resumes where it stopped the next time; dropped connections
+    /// are resumed up to [`MAX_RESUME_ATTEMPTS`] times before giving up.
+    pub async fn download_firmware<F, R>(
         &self,
         release: &FirmwareRelease,
         output_path: &Path,
+        cancel: &CancelToken,
         progress_callback: F,
+        on_resumed: R,
     ) -> UpdateResult<()>
     where
         F: Fn(DownloadProgress) + Send + Sync,
+        R: Fn(DownloadResumed) + Send + Sync,
     {
         info!("Downloading firmware from: {}", release.download_url);
         
+        let partial = partial_path(output_path);
+        let mut attempt = 0;
+        let downloaded = loop {
+            match self.download_attempt(release, &partial, cancel, &progress_callback, &on_resumed).await {
+                Ok(downloaded) => break downloaded,
+                Err(UpdateError::DownloadInterrupted) if attempt < MAX_RESUME_ATTEMPTS && !cancel.is_cancelled() => {
+                    attempt += 1;
+                    log::warn!("Resuming firmware download (attempt {}/{})", attempt, MAX_RESUME_ATTEMPTS);
+                }
+                Err(e) => return Err(e),
+            }
+        };
+        tokio::fs::rename(&partial, output_path).await?;
+        
+        info!("Firmware download completed: {} bytes", downloaded);
+        
+        if let Some(expected) = self.resolve_sha256(release).await {
+            if let Err(e) = self.verify_firmware(output_path, Some(&expected)).await {
+                let _ = tokio::fs::remove_file(output_path).await;
+                return Err(e);
+            }
+        } else {
+            log::warn!("No checksum available for firmware {}, download not verified", release.version);
+        }
+        if let Err(e) = self.download_signature(release, output_path).await {
+            log::warn!("Failed to download signature of firmware {}: {}", release.version, e);
+        }
+        Ok(())
+    }
+
+    /// Build the release image from a cached earlier version and a delta patch, when the release
+    /// has a patch for a cached version and a checksum to verify the result against. Returns
+    /// `false` when no delta update applies; errors mean the caller should download in full.
+    pub async fn download_firmware_delta<F>(
+        &self,
+        release: &FirmwareRelease,
+        cache: &FirmwareCache,
+        output_path: &Path,
+        cancel: &CancelToken,
+        progress_callback: F,
+    ) -> UpdateResult<bool>
+    where
+        F: Fn(DownloadProgress) + Send + Sync,
+    {
+        let mut patches = release.delta_patches.clone();
+        patches.sort_by(|a, b| b.from.cmp(&a.from));
+        let mut base = None;
+        for patch in patches {
+            if let Some(cached) = cache.get(&patch.from)? {
+                base = Some((patch, cached));
+                break;
+            }
+        }
+        let Some((patch, cached)) = base else { return Ok(false) };
+        let Some(expected) = self.resolve_sha256(release).await else {
+            info!("Firmware {} has no checksum; not rebuilding it from a delta patch", release.version);
+            return Ok(false);
+        };
+
+        info!("Downloading delta patch {} ({} bytes) to rebuild firmware {} from cached {}", patch.asset_name, patch.size_bytes, release.version, patch.from);
+        let response = tokio::select! {
+            _ = cancel.cancelled() => return Err(UpdateError::Cancelled),
+            response = self.client.get(&patch.download_url).header("User-Agent", "JoyCore-X/1.0").send() => response?,
+        };
+        let response = response.error_for_status()?;
+        let total_bytes = response.content_length().unwrap_or(patch.size_bytes);
+        let mut stream = response.bytes_stream();
+        let mut patch_data = Vec::with_capacity(total_bytes as usize);
+        let start_time = std::time::Instant::now();
+        loop {
+            let next = tokio::select! {
+                _ = cancel.cancelled() => return Err(UpdateError::Cancelled),
+                next = futures_util::StreamExt::next(&mut stream) => next,
+            };
+            let Some(chunk) = next else { break };
+            patch_data.extend_from_slice(&chunk.map_err(|_| UpdateError::DownloadInterrupted)?);
+            let downloaded = patch_data.len() as u64;
+            let elapsed = start_time.elapsed().as_secs_f64();
+            progress_callback(DownloadProgress {
+                downloaded_bytes: downloaded,
+                total_bytes,
+                percentage: if total_bytes > 0 { (downloaded as f64 / total_bytes as f64) * 100.0 } else { 0.0 },
+                speed_bps: if elapsed > 0.0 { (downloaded as f64 / elapsed) as u64 } else { 0 },
+            });
+        }
+
+        let old = tokio::fs::read(&cached.path).await?;
+        let image = apply_patch(&old, &patch_data).map_err(|e| anyhow::anyhow!("{}: {}", patch.asset_name, e))?;
+        let partial = partial_path(output_path);
+        tokio::fs::write(&partial, &image).await?;
+        if let Err(e) = self.verify_firmware(&partial, Some(&expected)).await {
+            let _ = tokio::fs::remove_file(&partial).await;
+            return Err(e);
+        }
+        tokio::fs::rename(&partial, output_path).await?;
+        info!("Rebuilt firmware {} ({} bytes) from a {} byte patch", release.version, image.len(), patch_data.len());
+        if let Err(e) = self.download_signature(release, output_path).await {
+            log::warn!("Failed to download signature of firmware {}: {}", release.version, e);
+        }
+        Ok(true)
+    }
+
+    /// Save the release signature next to the image as `<image>.sig`; without one the image stays unsigned
+    async fn download_signature(&self, release: &FirmwareRelease, output_path: &Path) -> UpdateResult<()> {
+        let target = signature_path(output_path);
+        // Never pair a fresh image with the signature of an earlier download
+        match tokio::fs::remove_file(&target).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+            _ => {}
+        }
+        let Some(url) = &release.signature_url else {
+            log::warn!("Firmware {} has no signature asset", release.version);
+            return Ok(());
+        };
+        debug!("Fetching signature: {}", url);
         let response = self.client
-            .get(&release.download_url)
+            .get(url)
+            .header("User-Agent", "JoyCore-X/1.0")
             .send()
-            .await?;
-        
+            .await?
+            .error_for_status()?;
+        tokio::fs::write(&target, response.bytes().await?).await?;
+        Ok(())
+    }
+
+    /// One request of `download_firmware`, continuing `partial` when the server honours the range.
+    /// Returns the total size of `partial` once the response body has been written.
+    async fn download_attempt<F, R>(
+        &self,
+        release: &FirmwareRelease,
+        partial: &Path,
+        cancel: &CancelToken,
+        progress_callback: &F,
+        on_resumed: &R,
+    ) -> UpdateResult<u64>
+    where
+        F: Fn(DownloadProgress) + Send + Sync,
+        R: Fn(DownloadResumed) + Send + Sync,
+    {
+        let existing = tokio::fs::metadata(partial).await.map(|m| m.len()).unwrap_or(0);
+        let mut request = self.client
+            .get(&release.download_url)
+            .header("User-Agent", "JoyCore-X/1.0");
+        if existing > 0 {
+            request = request.header(RANGE, format!("bytes={}-", existing));
+        }
+        let response = tokio::select! {
+            _ = cancel.cancelled() => return Err(UpdateError::Cancelled),
+            response = request.send() => response?,
+        };
+        
+        if response.status() == StatusCode::RANGE_NOT_SATISFIABLE {
+            log::warn!("Server rejected resume at {} bytes, discarding partial download", existing);
+            tokio::fs::remove_file(partial).await?;
+            return Err(UpdateError::DownloadInterrupted);
+        }
         if !response.status().is_success() {
             error!("Download request failed with status: {}", response.status());
             return Err(UpdateError::Network(
@@ -197,20 +427,67 @@ impl UpdateService {
             ));
         }
         
-        let total_size = response.content_length().unwrap_or(release.size_bytes);
-        let mut file = File::create(output_path).await?;
-        let mut downloaded = 0u64;
+        let range_start = response.headers()
+            .get(CONTENT_RANGE)
+            .and_then(|v| v.to_str().ok())
+            .and_then(content_range_start);
+        let resumed_from = match (response.status(), range_start) {
+            (StatusCode::PARTIAL_CONTENT, Some(start)) if start == existing => existing,
+            (StatusCode::PARTIAL_CONTENT, _) => {
+                log::warn!("Unexpected Content-Range {:?} for resume at {} bytes, discarding partial download", range_start, existing);
+                tokio::fs::remove_file(partial).await?;
+                return Err(UpdateError::DownloadInterrupted);
+            }
+            _ => {
+                if existing > 0 {
+                    debug!("Server ignored range request, restarting download");
+                }
+                0
+            }
+        };
+        
+        let total_size = response.content_length()
+            .map(|len| resumed_from + len)
+            .unwrap_or(release.size_bytes);
+        let mut file = if resumed_from > 0 {
+            info!("Resuming firmware download at {} of {} bytes", resumed_from, total_size);
+            on_resumed(DownloadResumed {
+                version: release.version.clone(),
+                resumed_from_bytes: resumed_from,
+                total_bytes: total_size,
+            });
+            OpenOptions::new().append(true).open(partial).await?
+        } else {
+            File::create(partial).await?
+        };
+        let mut downloaded = resumed_from;
         let mut stream = response.bytes_stream();
         
         let start_time = std::time::Instant::now();
         
-        while let Some(chunk_result) = futures_util::StreamExt::next(&mut stream).await {
-            let chunk = chunk_result.map_err(UpdateError::Network)?;
+        loop {
+            let next = tokio::select! {
+                _ = cancel.cancelled() => {
+                    file.flush().await?;
+                    info!("Firmware download cancelled at {} bytes", downloaded);
+                    return Err(UpdateError::Cancelled);
+                }
+                next = futures_util::StreamExt::next(&mut stream) => next,
+            };
+            let chunk = match next {
+                Some(Ok(chunk)) => chunk,
+                Some(Err(e)) => {
+                    file.flush().await?;
+                    log::warn!("Firmware download interrupted at {} bytes: {}", downloaded, e);
+                    return Err(UpdateError::DownloadInterrupted);
+                }
+                None => break,
+            };
             file.write_all(&chunk).await?;
             
             downloaded += chunk.len() as u64;
             let elapsed = start_time.elapsed().as_secs_f64();
-            let speed_bps = if elapsed > 0.0 { (downloaded as f64 / elapsed) as u64 } else { 0 };
+            let speed_bps = if elapsed > 0.0 { ((downloaded - resumed_from) as f64 / elapsed) as u64 } else { 0 };
             
             let progress = DownloadProgress {
                 downloaded_bytes: downloaded,
@@ -223,9 +500,7 @@ impl UpdateService {
         }
         
         file.flush().await?;
-        
-        info!("Firmware download completed: {} bytes", downloaded);
-        Ok(())
+        Ok(downloaded)
     }
 
     /// Verify firmware file integrity (if hash is provided)
@@ -265,8 +540,8 @@ impl UpdateService {
         }
     }
 
-    /// Get all available firmware versions
-    pub async fn get_available_versions(&self) -> UpdateResult<Vec<FirmwareRelease>> {
+    /// Get all firmware versions with an asset for `hardware_revision`
+    pub async fn get_available_versions(&self, hardware_revision: Option<&str>) -> UpdateResult<Vec<FirmwareRelease>> {
         let url = format!(
             "{}/repos/{}/{}/releases",
             self.github_api_base, self.repo_owner, self.repo_name
@@ -274,25 +549,13 @@ impl UpdateService {
         
         debug!("Fetching all releases from: {}", url);
         
-        let response = self.client
-            .get(&url)
-            .header("Accept", "application/vnd.github+json")
-            .header("User-Agent", "JoyCore-X/1.0")
-            .send()
-            .await?;
-        
-        if !response.status().is_success() {
-            return Err(UpdateError::Network(
-                reqwest::Error::from(response.error_for_status().unwrap_err())
-            ));
-        }
-        
-        let releases_data: Vec<Value> = response.json().await?;
+        let releases_data: Vec<Value> = serde_json::from_value(self.get_json(&url).await?)?;
         let mut releases = Vec::new();
         
         for release_data in releases_data {
-            if let Ok(release) = self.parse_github_release(&release_data) {
-                releases.push(release);
+            match self.parse_github_release(&release_data, hardware_revision) {
+                Ok(release) => releases.push(release),
+                Err(e) => debug!("Skipping release: {}", e),
             }
         }
         
@@ -0,0 +1,215 @@
+//! Minisign (Ed25519) signatures of firmware images.
+//!
+//! Releases may carry a `<asset>.minisig` or `<asset>.sig` asset. It is saved next to the
+//! downloaded image as `<image>.sig` and checked against the configured public key, or the key
+//! bundled at build time through `JOYCORE_FIRMWARE_PUBLIC_KEY`. Keys are accepted as the bare
+//! base64 key or the full contents of a `minisign.pub` file.
+//!
+//! Flashes are only gated on a signature when verification is enabled *and* a key is available;
+//! builds without a bundled key therefore default to verification off rather than refusing every
+//! image.
+use std::path::{Path, PathBuf};
+
+use minisign_verify::{PublicKey, Signature};
+use serde::Serialize;
+use serde_json::Value;
+
+use super::models::{UpdateError, UpdateResult};
+
+/// Public key compiled into the app; forks set their own or configure one in the settings
+pub const BUNDLED_PUBLIC_KEY: Option<&str> = option_env!("JOYCORE_FIRMWARE_PUBLIC_KEY");
+/// Extension appended to the image file name for its local signature
+pub const SIGNATURE_EXTENSION: &str = "sig";
+
+/// Outcome of checking a firmware image's signature
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SignatureStatus {
+    Valid,
+    /// No signature file next to the image
+    Unsigned,
+    /// Neither a configured nor a bundled public key is available
+    NoPublicKey,
+    /// The signature does not match the image or was made with another key
+    Invalid,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SignatureVerification {
+    pub status: SignatureStatus,
+    pub signature_path: Option<PathBuf>,
+    /// Signed comment of the signature (minisign puts a timestamp and file name there)
+    pub trusted_comment: Option<String>,
+    pub error: Option<String>,
+}
+
+impl SignatureVerification {
+    fn new(status: SignatureStatus, signature_path: Option<PathBuf>) -> Self {
+        Self { status, signature_path, trusted_comment: None, error: None }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.status == SignatureStatus::Valid
+    }
+}
+
+/// Where the signature of `image` is stored locally
+pub fn signature_path(image: &Path) -> PathBuf {
+    let mut name = image.file_name().unwrap_or_default().to_os_string();
+    name.push(".");
+    name.push(SIGNATURE_EXTENSION);
+    image.with_file_name(name)
+}
+
+/// URL of the signature asset for `asset_name` (`<asset>.minisig` preferred over `<asset>.sig`)
+pub fn find_signature_asset(assets: &[Value], asset_name: &str) -> Option<String> {
+    [format!("{}.minisig", asset_name), format!("{}.sig", asset_name)]
+        .iter()
+        .find_map(|wanted| {
+            assets.iter().find(|asset| {
+                asset["name"].as_str().is_some_and(|name| name.eq_ignore_ascii_case(wanted))
+            })
+        })
+        .and_then(|asset| asset["browser_download_url"].as_str())
+        .map(str::to_string)
+}
+
+/// Configured key if set, else the bundled one
+pub fn effective_public_key(configured: Option<&str>) -> Option<String> {
+    configured
+        .map(str::trim)
+        .filter(|key| !key.is_empty())
+        .or(BUNDLED_PUBLIC_KEY)
+        .map(str::to_string)
+}
+
+/// Key a flash has to be verified against; None when verification is off or no key is available
+pub fn flash_verification_key(verify_signatures: bool, configured: Option<&str>) -> Option<String> {
+    if !verify_signatures {
+        return None;
+    }
+    let key = effective_public_key(configured);
+    if key.is_none() {
+        log::warn!("Firmware signature verification is enabled but no public key is configured; flashing without verification");
+    }
+    key
+}
+
+fn parse_public_key(key: &str) -> Result<PublicKey, minisign_verify::Error> {
+    let key = key.trim();
+    if key.contains('\n') {
+        PublicKey::decode(key)
+    } else {
+        PublicKey::from_base64(key)
+    }
+}
+
+/// Check `image` against `signature` (default: `<image>.sig`) with `public_key`.
+/// Only failing to read the image is an error; every signature problem is reported in the result.
+pub fn verify_image(image: &Path, signature: Option<&Path>, public_key: Option<&str>) -> UpdateResult<SignatureVerification> {
+    let data = std::fs::read(image)?;
+    let sig_path = signature.map(Path::to_path_buf).unwrap_or_else(|| signature_path(image));
+    if !sig_path.exists() {
+        return Ok(SignatureVerification::new(SignatureStatus::Unsigned, None));
+    }
+    let Some(public_key) = public_key else {
+        return Ok(SignatureVerification::new(SignatureStatus::NoPublicKey, Some(sig_path)));
+    };
+
+    let mut result = SignatureVerification::new(SignatureStatus::Invalid, Some(sig_path.clone()));
+    let checked = parse_public_key(public_key)
+        .map_err(|e| format!("Invalid public key: {}", e))
+        .and_then(|key| {
+            let signature = Signature::from_file(&sig_path).map_err(|e| format!("Invalid signature file: {}", e))?;
+            result.trusted_comment = Some(signature.trusted_comment().to_string());
+            key.verify(&data, &signature, false).map_err(|e| e.to_string())
+        });
+    match checked {
+        Ok(()) => result.status = SignatureStatus::Valid,
+        Err(e) => result.error = Some(e),
+    }
+    Ok(result)
+}
+
+/// Flash gate: a valid signature passes, an unsigned image or missing key only with
+/// `allow_unsigned`, and a signature that fails to verify never does
+pub fn ensure_flashable(verification: &SignatureVerification, allow_unsigned: bool) -> UpdateResult<()> {
+    let reason = match verification.status {
+        SignatureStatus::Valid => return Ok(()),
+        SignatureStatus::Invalid => {
+            log::error!("Firmware signature check failed: {}", verification.error.as_deref().unwrap_or("unknown error"));
+            return Err(UpdateError::InvalidSignature);
+        }
+        SignatureStatus::Unsigned => "the image has no signature",
+        SignatureStatus::NoPublicKey => "no public key is configured",
+    };
+    if allow_unsigned {
+        log::warn!("Flashing unverified firmware ({}), allowed by the user", reason);
+        Ok(())
+    } else {
+        Err(UpdateError::UnsignedFirmware(reason.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Test vector from minisign-verify: prehashed signature of b"test"
+    const PUBLIC_KEY: &str = "RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3";
+    const SIGNATURE: &str = "untrusted comment: signature from minisign secret key
+RUQf6LRCGA9i559r3g7V1qNyJDApGip8MfqcadIgT9CuhV3EMhHoN1mGTkUidF/z7SrlQgXdy8ofjb7bNJJylDOocrCo8KLzZwo=
+trusted comment: timestamp:1556193335\tfile:test
+y/rUw2y8/hOUYjZU71eHp/Wo1KZ40fGy2VJEDl34XMJM+TX48Ss/17u3IvIfbVR1FkZZSNCisQbuQY+bHwhEBg==";
+
+    #[test]
+    fn verifies_signed_images() {
+        let dir = std::env::temp_dir().join(format!("joycore_sig_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let image = dir.join("firmware-1.0.0.uf2");
+        std::fs::write(&image, b"test").unwrap();
+
+        let unsigned = verify_image(&image, None, Some(PUBLIC_KEY)).unwrap();
+        assert_eq!(unsigned.status, SignatureStatus::Unsigned);
+
+        std::fs::write(signature_path(&image), SIGNATURE).unwrap();
+        let valid = verify_image(&image, None, Some(PUBLIC_KEY)).unwrap();
+        assert_eq!(valid.status, SignatureStatus::Valid, "{:?}", valid.error);
+        assert_eq!(valid.trusted_comment.as_deref(), Some("timestamp:1556193335\tfile:test"));
+        assert_eq!(verify_image(&image, None, None).unwrap().status, SignatureStatus::NoPublicKey);
+
+        std::fs::write(&image, b"Test").unwrap();
+        let tampered = verify_image(&image, None, Some(PUBLIC_KEY)).unwrap();
+        assert_eq!(tampered.status, SignatureStatus::Invalid);
+        assert!(tampered.error.is_some());
+
+        assert!(ensure_flashable(&valid, false).is_ok());
+        assert!(matches!(ensure_flashable(&unsigned, false), Err(UpdateError::UnsignedFirmware(_))));
+        assert!(ensure_flashable(&unsigned, true).is_ok());
+        assert!(matches!(ensure_flashable(&tampered, true), Err(UpdateError::InvalidSignature)), "override never accepts a bad signature");
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn flashing_without_a_key_skips_verification() {
+        assert_eq!(flash_verification_key(true, None).as_deref(), BUNDLED_PUBLIC_KEY);
+        assert_eq!(flash_verification_key(true, Some("  ")).as_deref(), BUNDLED_PUBLIC_KEY, "a blank key counts as unset");
+        assert_eq!(flash_verification_key(true, Some(PUBLIC_KEY)).as_deref(), Some(PUBLIC_KEY));
+        assert_eq!(flash_verification_key(false, Some(PUBLIC_KEY)), None);
+        let defaults = crate::device::models::FirmwareUpdateSettings::default();
+        assert_eq!(defaults.verify_signatures, BUNDLED_PUBLIC_KEY.is_some(), "on by default only when a key is bundled");
+    }
+
+    #[test]
+    fn finds_signature_assets() {
+        let assets: Vec<Value> = serde_json::from_str(r#"[
+            {"name": "joycore.uf2.sig", "browser_download_url": "https://x/joycore.uf2.sig"},
+            {"name": "joycore.uf2.minisig", "browser_download_url": "https://x/joycore.uf2.minisig"},
+            {"name": "joycore.uf2", "browser_download_url": "https://x/joycore.uf2"}
+        ]"#).unwrap();
+        assert_eq!(find_signature_asset(&assets, "joycore.uf2").as_deref(), Some("https://x/joycore.uf2.minisig"));
+        assert_eq!(find_signature_asset(&assets[..1], "joycore.uf2").as_deref(), Some("https://x/joycore.uf2.sig"));
+        assert_eq!(find_signature_asset(&assets, "other.uf2"), None);
+        assert_eq!(effective_public_key(Some("  ")), BUNDLED_PUBLIC_KEY.map(str::to_string));
+    }
+}
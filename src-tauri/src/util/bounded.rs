@@ -0,0 +1,194 @@
+//! Memory-bounded buffers with explicit overflow accounting.
+//!
+//! Every buffer reports into a named `OverflowCounter` held in a global registry so
+//! overflow totals survive individual sessions and can be surfaced in metrics.
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+/// Thread-safe overflow counters shared between a buffer and metrics readers
+#[derive(Debug, Default)]
+pub struct OverflowCounter {
+    overflows: AtomicU64,
+    dropped: AtomicU64,
+}
+
+/// Point-in-time copy of an `OverflowCounter`
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct OverflowStats {
+    /// Number of times the bound was hit
+    pub overflows: u64,
+    /// Items (queues) or bytes (text buffers) discarded to stay within the bound
+    pub dropped: u64,
+}
+
+impl OverflowCounter {
+    pub fn record(&self, dropped: u64) {
+        self.overflows.fetch_add(1, Ordering::Relaxed);
+        self.dropped.fetch_add(dropped, Ordering::Relaxed);
+    }
+
+    pub fn stats(&self) -> OverflowStats {
+        OverflowStats {
+            overflows: self.overflows.load(Ordering::Relaxed),
+            dropped: self.dropped.load(Ordering::Relaxed),
+        }
+    }
+}
+
+static COUNTERS: Lazy<Mutex<HashMap<String, Arc<OverflowCounter>>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Get (or create) the global overflow counter registered under `name`
+pub fn overflow_counter(name: &str) -> Arc<OverflowCounter> {
+    let mut counters = COUNTERS.lock().unwrap();
+    counters.entry(name.to_string()).or_default().clone()
+}
+
+/// Snapshot of every registered overflow counter, keyed by buffer name
+pub fn overflow_snapshot() -> HashMap<String, OverflowStats> {
+    let counters = COUNTERS.lock().unwrap();
+    counters.iter().map(|(name, c)| (name.clone(), c.stats())).collect()
+}
+
+/// FIFO queue that evicts the oldest entries once `capacity` is reached
+#[derive(Debug)]
+pub struct BoundedQueue<T> {
+    items: VecDeque<T>,
+    capacity: usize,
+    counter: Arc<OverflowCounter>,
+}
+
+impl<T> BoundedQueue<T> {
+    /// Create a queue reporting overflows under the global counter `name`
+    pub fn new(name: &str, capacity: usize) -> Self {
+        Self::with_counter(capacity, overflow_counter(name))
+    }
+
+    pub fn with_counter(capacity: usize, counter: Arc<OverflowCounter>) -> Self {
+        let capacity = capacity.max(1);
+        Self { items: VecDeque::with_capacity(capacity.min(1024)), capacity, counter }
+    }
+
+    /// Push an item, returning the evicted oldest item if the queue was full
+    pub fn push(&mut self, item: T) -> Option<T> {
+        let evicted = if self.items.len() >= self.capacity {
+            self.counter.record(1);
+            self.items.pop_front()
+        } else { None };
+        self.items.push_back(item);
+        evicted
+    }
+
+    pub fn pop(&mut self) -> Option<T> { self.items.pop_front() }
+    pub fn len(&self) -> usize { self.items.len() }
+    pub fn is_empty(&self) -> bool { self.items.is_empty() }
+    pub fn capacity(&self) -> usize { self.capacity }
+    pub fn iter(&self) -> impl Iterator<Item = &T> { self.items.iter() }
+    pub fn clear(&mut self) { self.items.clear(); }
+    pub fn drain(&mut self) -> impl Iterator<Item = T> + '_ { self.items.drain(..) }
+    pub fn stats(&self) -> OverflowStats { self.counter.stats() }
+
+    /// Change the bound, evicting the oldest entries if it shrank
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity.max(1);
+        let excess = self.items.len().saturating_sub(self.capacity);
+        if excess > 0 {
+            self.items.drain(..excess);
+            self.counter.record(excess as u64);
+        }
+    }
+}
+
+impl<T: Clone> BoundedQueue<T> {
+    pub fn to_vec(&self) -> Vec<T> { self.items.iter().cloned().collect() }
+}
+
+/// Text accumulator for line-oriented streams. When `max_bytes` is exceeded the oldest
+/// data is discarded, keeping the newest `keep_bytes` (cut on a char boundary).
+#[derive(Debug)]
+pub struct BoundedTextBuffer {
+    buf: String,
+    max_bytes: usize,
+    keep_bytes: usize,
+    counter: Arc<OverflowCounter>,
+}
+
+impl BoundedTextBuffer {
+    pub fn new(name: &str, max_bytes: usize, keep_bytes: usize) -> Self {
+        Self::with_counter(max_bytes, keep_bytes, overflow_counter(name))
+    }
+
+    pub fn with_counter(max_bytes: usize, keep_bytes: usize, counter: Arc<OverflowCounter>) -> Self {
+        Self { buf: String::new(), max_bytes, keep_bytes: keep_bytes.min(max_bytes), counter }
+    }
+
+    /// Append data; returns the number of bytes discarded to respect the bound
+    pub fn push_str(&mut self, data: &str) -> usize {
+        self.buf.push_str(data);
+        if self.buf.len() <= self.max_bytes { return 0; }
+        let mut cut = self.buf.len() - self.keep_bytes;
+        while !self.buf.is_char_boundary(cut) { cut += 1; }
+        self.buf.drain(..cut);
+        self.counter.record(cut as u64);
+        cut
+    }
+
+    /// Remove and return the next complete line (terminated by `\n` or `\r`), skipping blank lines
+    pub fn take_line(&mut self) -> Option<String> {
+        loop {
+            let pos = self.buf.find(['\n', '\r'])?;
+            let line = self.buf[..pos].to_string();
+            let mut advance = pos + 1;
+            let bytes = self.buf.as_bytes();
+            while advance < bytes.len() && (bytes[advance] == b'\n' || bytes[advance] == b'\r') { advance += 1; }
+            self.buf.drain(..advance);
+            if !line.trim().is_empty() { return Some(line); }
+        }
+    }
+
+    pub fn as_str(&self) -> &str { &self.buf }
+    pub fn len(&self) -> usize { self.buf.len() }
+    pub fn is_empty(&self) -> bool { self.buf.is_empty() }
+    pub fn clear(&mut self) { self.buf.clear(); }
+    pub fn stats(&self) -> OverflowStats { self.counter.stats() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn queue_evicts_oldest_and_counts() {
+        let mut q = BoundedQueue::with_counter(2, Arc::new(OverflowCounter::default()));
+        assert!(q.push(1).is_none());
+        assert!(q.push(2).is_none());
+        assert_eq!(q.push(3), Some(1));
+        assert_eq!(q.to_vec(), vec![2, 3]);
+        assert_eq!(q.stats(), OverflowStats { overflows: 1, dropped: 1 });
+        q.set_capacity(1);
+        assert_eq!(q.to_vec(), vec![3]);
+        assert_eq!(q.stats().dropped, 2);
+    }
+
+    #[test]
+    fn text_buffer_trims_on_char_boundary() {
+        let mut b = BoundedTextBuffer::with_counter(8, 4, Arc::new(OverflowCounter::default()));
+        assert_eq!(b.push_str("abcd"), 0);
+        let dropped = b.push_str("éééé");
+        assert!(dropped > 0);
+        assert!(b.len() <= 4);
+        assert_eq!(b.stats().overflows, 1);
+    }
+
+    #[test]
+    fn text_buffer_splits_lines() {
+        let mut b = BoundedTextBuffer::with_counter(64, 32, Arc::new(OverflowCounter::default()));
+        b.push_str("A\r\n\r\nB\npartial");
+        assert_eq!(b.take_line().as_deref(), Some("A"));
+        assert_eq!(b.take_line().as_deref(), Some("B"));
+        assert_eq!(b.take_line(), None);
+        assert_eq!(b.as_str(), "partial");
+    }
+}
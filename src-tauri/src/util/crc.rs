@@ -0,0 +1,29 @@
+//! CRC32 (IEEE, reflected polynomial 0xEDB88320) as used by the firmware.
+
+/// Update CRC32 checksum with a single byte using firmware algorithm
+pub fn crc32_update_byte(mut checksum: u32, byte: u8) -> u32 {
+    checksum ^= byte as u32;
+    for _ in 0..8 {
+        if checksum & 1 != 0 {
+            checksum = (checksum >> 1) ^ 0xEDB88320;
+        } else {
+            checksum >>= 1;
+        }
+    }
+    checksum
+}
+
+/// CRC32 of a whole buffer
+pub fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(0xFFFFFFFF, |checksum, &byte| crc32_update_byte(checksum, byte))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_standard_check_value() {
+        assert_eq!(crc32(b"123456789"), 0xCBF43926);
+    }
+}
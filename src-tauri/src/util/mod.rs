@@ -0,0 +1,7 @@
+pub mod bounded;
+pub mod crc;
+pub mod persist;
+#[cfg(test)]
+pub mod test_util;
+
+pub use bounded::{BoundedQueue, BoundedTextBuffer, OverflowCounter, OverflowStats};
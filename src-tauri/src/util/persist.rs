@@ -0,0 +1,33 @@
+//! Small helpers for JSON state files stored under the app data directory.
+use std::path::Path;
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Load `path` as JSON. Missing files yield `T::default()`; corrupt files are logged and ignored.
+pub fn load_json<T: DeserializeOwned + Default>(path: &Path, what: &str) -> T {
+    match std::fs::read_to_string(path) {
+        Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+            log::warn!("Ignoring corrupt {} file {:?}: {}", what, path, e);
+            T::default()
+        }),
+        Err(_) => T::default(),
+    }
+}
+
+/// Write `value` as pretty JSON, creating parent directories. Errors are logged, not returned,
+/// since persistence failures should never break the in-memory state.
+pub fn save_json<T: Serialize + ?Sized>(path: &Path, value: &T, what: &str) {
+    if let Some(parent) = path.parent() {
+        if let Err(e) = std::fs::create_dir_all(parent) {
+            log::error!("Failed to create directory for {}: {}", what, e);
+            return;
+        }
+    }
+    match serde_json::to_string_pretty(value) {
+        Ok(json) => {
+            if let Err(e) = std::fs::write(path, json) {
+                log::error!("Failed to save {}: {}", what, e);
+            }
+        }
+        Err(e) => log::error!("Failed to serialize {}: {}", what, e),
+    }
+}
@@ -0,0 +1,28 @@
+//! Fixtures shared by unit tests.
+use std::path::{Path, PathBuf};
+
+/// Fresh directory under the system temp dir, removed with its contents when dropped (also when
+/// the test panics)
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    pub fn new(prefix: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("joycore_{}_{}", prefix, uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&path).expect("create test temp dir");
+        Self(path)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+
+    pub fn join(&self, name: impl AsRef<Path>) -> PathBuf {
+        self.0.join(name)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
@@ -0,0 +1,205 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use joycore_x_lib::config::BinaryConfig;
+use joycore_x_lib::serial::capabilities::CapabilitySource;
+use joycore_x_lib::serial::mock::{MockSerialPort, SimulatedDevice, MOCK_PORT_NAME};
+use joycore_x_lib::serial::unified::types::{CommandSpec, FinishReason, ParsedEvent, ResponseMatcher, CONSOLE_COMMAND};
+use joycore_x_lib::serial::{ConfigProtocol, SerialDeviceInfo, SerialInterface, UnifiedSerialBuilder};
+
+fn mock_info() -> SerialDeviceInfo {
+    SerialDeviceInfo {
+        port_name: MOCK_PORT_NAME.to_string(),
+        vid: 0,
+        pid: 0,
+        serial_number: Some("SIM0001".to_string()),
+        manufacturer: Some("JoyCore".to_string()),
+        product: Some("Simulated HOTAS".to_string()),
+        firmware_version: Some("1.0.0-sim".to_string()),
+        device_signature: Some("JOYCORE-FW".to_string()),
+        hardware_revision: None,
+    }
+}
+
+fn protocol_for(device: SimulatedDevice) -> (ConfigProtocol, joycore_x_lib::serial::UnifiedSerialHandle) {
+    let iface = SerialInterface::from_port(Box::new(MockSerialPort::new(device)), mock_info());
+    let iface = Arc::new(tokio::sync::Mutex::new(iface));
+    let handle = UnifiedSerialBuilder { interface: iface.clone(), event_capacity: 256, command_capacity: 64 }.build();
+    (ConfigProtocol::new(handle.clone(), iface), handle)
+}
+
+#[tokio::test]
+async fn test_mock_device_status_and_config_read() {
+    let (mut protocol, _handle) = protocol_for(SimulatedDevice::new());
+    let status = protocol.get_device_status().await.expect("STATUS should complete");
+    assert_eq!(status.firmware_version, "1.0.0-sim");
+    assert_eq!((status.config_loaded, status.storage_ok), (Some(true), Some(true)));
+    assert!(status.uptime_ms.is_some() && !status.counts_reported);
+
+    let files = protocol.list_files().await.expect("LIST_FILES should complete");
+    assert_eq!(files, vec!["/config.bin".to_string()]);
+
+    let data = protocol.read_file("/config.bin").await.expect("READ_FILE should complete");
+    BinaryConfig::from_bytes(&data).expect("simulated config should parse");
+}
+
+#[tokio::test]
+async fn test_mock_device_scripted_and_silent_commands() {
+    let device = SimulatedDevice::new()
+        .with_response("STORAGE_INFO", &["STORAGE_USED:10", "STORAGE_TOTAL:20"])
+        .with_silent("SAVE_CONFIG");
+    let (mut protocol, _handle) = protocol_for(device);
+    let info = protocol.get_storage_info().await.expect("scripted STORAGE_INFO");
+    assert!(info.contains("STORAGE_USED:10"));
+    let err = protocol.save_config().await.expect_err("silent command should time out");
+    assert!(matches!(err, joycore_x_lib::serial::SerialError::Timeout));
+}
+
+#[tokio::test]
+async fn test_mock_device_monitor_stream_reaches_unified_reader() {
+    let (_protocol, handle) = protocol_for(SimulatedDevice::new());
+    let mut events = handle.subscribe_events();
+    let spec = CommandSpec { name: "START_RAW_MONITOR", timeout: Duration::from_millis(500), matcher: ResponseMatcher::Contains("OK:RAW_MONITOR_STARTED"), test_min_duration_ms: None };
+    handle.send_command("START_RAW_MONITOR".to_string(), spec).await.expect("monitor start");
+
+    let event = tokio::time::timeout(Duration::from_secs(2), async {
+        loop {
+            if let Ok(ParsedEvent::Gpio { mask, .. }) = events.recv().await { return mask; }
+        }
+    }).await.expect("expected a streamed GPIO event");
+    assert_ne!(event, 0);
+    assert!(handle.snapshot_receiver().borrow().seq > 0);
+}
+
+#[tokio::test]
+async fn test_mock_device_chunked_write_and_delete() {
+    let (mut protocol, _handle) = protocol_for(SimulatedDevice::new().with_chunk_naks(1));
+    let data: Vec<u8> = (0..200u16).map(|i| (i % 251) as u8).collect();
+    protocol.write_raw_file("/test.bin", &data).await.expect("chunked write with one NAK should succeed");
+    assert_eq!(protocol.read_file("/test.bin").await.expect("read back"), data);
+
+    protocol.delete_file("/test.bin").await.expect("DELETE_FILE should succeed");
+    assert!(!protocol.list_files().await.unwrap().contains(&"/test.bin".to_string()));
+    assert!(protocol.delete_file("/test.bin").await.is_err());
+}
+
+#[tokio::test]
+async fn test_mock_device_config_write_transaction() {
+    let (mut protocol, _handle) = protocol_for(SimulatedDevice::new());
+    let original = protocol.read_file("/config.bin").await.expect("read config");
+    let mut config = BinaryConfig::from_bytes(&original).unwrap();
+    config.stored_config.axes[0].deadband = 42;
+    let updated = config.to_bytes().unwrap();
+    protocol.write_config_transaction("/config.bin", &updated).await.expect("verified write should commit");
+    assert_eq!(protocol.read_file("/config.bin").await.unwrap(), updated);
+    assert_eq!(protocol.read_file("/config.bak").await.unwrap(), original, "replaced config is kept as backup");
+}
+
+#[tokio::test]
+async fn test_mock_device_config_write_rolls_back_on_mismatch() {
+    let (mut protocol, _handle) = protocol_for(SimulatedDevice::new().with_corrupt_writes(1));
+    let original = protocol.read_file("/config.bin").await.expect("read config");
+    let mut updated = original.clone();
+    updated[10] ^= 0x01;
+    let err = protocol.write_config_transaction("/config.bin", &updated).await.expect_err("corrupted write must fail");
+    let message = err.to_string();
+    assert!(message.contains("verification failed") && message.contains("first difference"), "{}", message);
+    assert!(message.contains("previous config"), "{}", message);
+    assert_eq!(protocol.read_file("/config.bin").await.unwrap(), original);
+}
+
+#[tokio::test]
+async fn test_mock_device_preview_config_is_not_stored() {
+    let (mut protocol, _handle) = protocol_for(SimulatedDevice::new());
+    protocol.init().await.expect("init");
+    assert!(protocol.capabilities().unwrap().test_config);
+    let original = protocol.read_file("/config.bin").await.expect("read config");
+    let mut config = BinaryConfig::from_bytes(&original).unwrap();
+    config.stored_config.axes[1].curve = 2;
+    protocol.test_config(&config.to_bytes().unwrap()).await.expect("TEST_CONFIG upload should complete");
+    assert_eq!(protocol.read_file("/config.bin").await.unwrap(), original, "preview must not touch storage");
+    protocol.revert_test_config().await.expect("TEST_CONFIG_REVERT should complete");
+
+    let legacy = SimulatedDevice::new().with_response("TEST_CONFIG", &["ERROR:Unknown command: TEST_CONFIG"]);
+    let (mut protocol, _handle) = protocol_for(legacy);
+    let err = protocol.test_config(&original).await.expect_err("firmware without TEST_CONFIG");
+    assert!(matches!(err, joycore_x_lib::serial::SerialError::Unsupported(_)));
+}
+
+#[tokio::test]
+async fn test_mock_device_without_file_write_support() {
+    let device = SimulatedDevice::new()
+        .with_response("WRITE_FILE_BEGIN", &["ERROR:Unknown command: WRITE_FILE_BEGIN"]);
+    let (mut protocol, _handle) = protocol_for(device);
+    protocol.init().await.expect("init");
+    assert_eq!(protocol.file_command_support().0, Some(true));
+    let err = protocol.write_raw_file("/config.bin", &[1, 2, 3]).await.expect_err("unsupported firmware");
+    assert!(matches!(err, joycore_x_lib::serial::SerialError::Unsupported(_)));
+    assert_eq!(protocol.file_command_support().0, Some(false));
+}
+
+#[tokio::test]
+async fn test_mock_device_capability_negotiation() {
+    let (mut protocol, _handle) = protocol_for(SimulatedDevice::new());
+    protocol.init().await.expect("init");
+    let caps = protocol.capabilities().expect("capabilities after init").clone();
+    assert_eq!(caps.source, CapabilitySource::Reported);
+    assert!(caps.write_file && caps.raw_monitor && !caps.hid_mapping);
+
+    // Legacy firmware: no CAPABILITIES, so WRITE_FILE fails fast with the firmware version
+    let legacy = SimulatedDevice::new().with_response("CAPABILITIES", &["ERROR:Unknown command: CAPABILITIES"]);
+    let (mut protocol, _handle) = protocol_for(legacy);
+    protocol.init().await.expect("init");
+    assert_eq!(protocol.capabilities().unwrap().source, CapabilitySource::Heuristic);
+    let err = protocol.write_raw_file("/config.bin", &[1]).await.expect_err("legacy firmware has no WRITE_FILE");
+    assert_eq!(err.to_string(), "WRITE_FILE is not supported by firmware 1.0.0-sim");
+}
+
+#[tokio::test]
+async fn test_mock_device_raw_state_reads_through_unified_reader() {
+    use joycore_x_lib::raw_state::RawStateReader;
+    let (mut protocol, _handle) = protocol_for(SimulatedDevice::new());
+    // Replies look like monitor lines; they must still complete the command
+    let gpio = RawStateReader::read_gpio_states(&mut protocol).await.expect("READ_GPIO_STATES");
+    assert!(gpio.timestamp > 0);
+    let matrix = RawStateReader::read_matrix_state(&mut protocol).await.expect("READ_MATRIX_STATE");
+    assert_eq!(matrix.connections.len(), 1);
+    let shift = RawStateReader::read_shift_reg_state(&mut protocol).await.expect("READ_SHIFT_REG");
+    assert_eq!(shift[0].value, 0xFF);
+    RawStateReader::start_monitoring(&mut protocol).await.expect("START_RAW_MONITOR");
+    RawStateReader::stop_monitoring(&mut protocol).await.expect("STOP_RAW_MONITOR");
+}
+
+#[tokio::test]
+async fn test_mock_device_console_keeps_every_line() {
+    let (_protocol, handle) = protocol_for(SimulatedDevice::new());
+    let console = |quiet_ms, timeout_ms| CommandSpec { name: CONSOLE_COMMAND, timeout: Duration::from_millis(timeout_ms), matcher: ResponseMatcher::Settled(Duration::from_millis(quiet_ms)), test_min_duration_ms: None };
+    // Monitor-shaped replies are the answer, not stream noise
+    let resp = handle.send_command("READ_GPIO_STATES".to_string(), console(50, 1000)).await.expect("console reply");
+    assert!(matches!(resp.finished_reason, FinishReason::MatcherSatisfied));
+    assert!(resp.lines.iter().any(|l| l.starts_with("GPIO_STATES:")), "{:?}", resp.lines);
+
+    // A reply that never settles in time still returns what arrived
+    let resp = handle.send_command("READ_GPIO_STATES".to_string(), console(5000, 300)).await.expect("partial console reply");
+    assert!(matches!(resp.finished_reason, FinishReason::Timeout));
+    assert!(!resp.lines.is_empty());
+}
+
+#[tokio::test]
+async fn test_mock_device_framed_file_transfer_and_fallback() {
+    let (mut protocol, handle) = protocol_for(SimulatedDevice::new());
+    protocol.init().await.expect("init");
+    assert!(protocol.capabilities().unwrap().framed_files);
+    let data: Vec<u8> = (0..300u16).map(|i| (i % 7) as u8).collect();
+    protocol.write_raw_file("/framed.bin", &data).await.expect("framed write");
+    assert_eq!(protocol.read_file("/framed.bin").await.expect("framed read"), data);
+    assert!(handle.metrics_receiver().borrow().frames_received > 0);
+
+    // Firmware advertising frames but failing framed reads falls back to hex text
+    let device = SimulatedDevice::new().with_response("READ_FILE_FRAMED", &["ERROR:Unknown command: READ_FILE_FRAMED"]);
+    let (mut protocol, _handle) = protocol_for(device);
+    protocol.init().await.expect("init");
+    let config = protocol.read_file("/config.bin").await.expect("text fallback");
+    BinaryConfig::from_bytes(&config).expect("config parses");
+    assert!(!protocol.capabilities().unwrap().framed_files);
+}
@@ -40,14 +40,22 @@ interface ParsedButtonInfo {
 }
 
 interface ButtonStates {
-  buttons: number; // Serialized u64; may lose precision >53 bits but current usage <53. Will treat via BigInt.
+  buttons: number[]; // 16-byte LSB-first bitmap: bit (id % 8) of byte (id / 8) is button id (0-127)
   timestamp: string;
 }
 
+const buttonBit = (id: number): bigint => 1n << BigInt(id);
+
+// Pack the backend bitmap into one mask; BigInt keeps all 128 bits exact
+const bitmapToMask = (bytes: number[]): bigint =>
+  bytes.reduce((mask, byte, i) => mask | (BigInt(byte & 0xff) << BigInt(i * 8)), 0n);
+
 interface ButtonEvent {
   button_id: number;
   pressed: boolean;
   timestamp: string;
+  device_time_us?: number; // device clock at `timestamp` while synchronized
+  since_previous_report_us?: number; // interval since the previous HID report, host monotonic clock
 }
 
 // Utility: extract physical mapping segment from a button name e.g. "(Pin 12)" or "(Matrix[1,2])" etc.
@@ -147,9 +155,9 @@ export function ButtonConfiguration({ deviceStatus, isConnected = false, parsedB
     }
   }, [parsedButtons]);
   // hidButtonStates removed (table highlight uses buttonMask only)
-  const [buttonMask, setButtonMask] = useState<number>(0); // UI-rendered bitmask (throttled)
-  const latestMaskRef = useRef<number>(0); // immediate latest from poller
-  const displayedMaskRef = useRef<number>(0); // what's currently displayed in UI
+  const [buttonMask, setButtonMask] = useState<bigint>(0n); // UI-rendered bitmask (throttled)
+  const latestMaskRef = useRef<bigint>(0n); // immediate latest from poller
+  const displayedMaskRef = useRef<bigint>(0n); // what's currently displayed in UI
   const pendingFrameRef = useRef<boolean>(false);
   const pressedHistoryRef = useRef<Map<number, number>>(new Map()); // buttonId -> lastPressedTime
   const lastActivityRef = useRef<number>(0); // track last activity time globally
@@ -283,9 +291,10 @@ export function ButtonConfiguration({ deviceStatus, isConnected = false, parsedB
       // Get initial state
       try {
   const states: ButtonStates = await invoke('read_button_states');
-  latestMaskRef.current = states.buttons;
-  displayedMaskRef.current = states.buttons;
-  setButtonMask(states.buttons);
+  const mask = bitmapToMask(states.buttons);
+  latestMaskRef.current = mask;
+  displayedMaskRef.current = mask;
+  setButtonMask(mask);
       } catch (e) {
         console.warn('Failed to get initial button states:', e);
       }
@@ -298,7 +307,7 @@ export function ButtonConfiguration({ deviceStatus, isConnected = false, parsedB
         // Debug: console.log(`[FRONTEND EVENT] Button ${button_id} ${pressed ? 'pressed' : 'released'} at ${timestamp}`);
         
         // Update button mask
-        const mask = button_id < 32 ? (1 << button_id) : Math.pow(2, button_id);
+        const mask = buttonBit(button_id);
         if (pressed) {
           latestMaskRef.current |= mask;
           pressedHistoryRef.current.set(button_id, now);
@@ -313,20 +322,18 @@ export function ButtonConfiguration({ deviceStatus, isConnected = false, parsedB
         // Clean old entries and apply hold visibility
         for (const [bit, time] of pressedHistoryRef.current) {
           if (time < cutoffTime) {
-            const bitMask = bit < 32 ? (1 << bit) : Math.pow(2, bit);
-            if (!(latestMaskRef.current & bitMask)) {
+            if ((latestMaskRef.current & buttonBit(bit)) === 0n) {
               pressedHistoryRef.current.delete(bit);
             }
           } else {
             // Keep showing recently pressed buttons
-            const bitMask = bit < 32 ? (1 << bit) : Math.pow(2, bit);
-            displayMask |= bitMask;
+            displayMask |= buttonBit(bit);
           }
         }
         
         // Update UI if display mask changed
         if (displayMask !== displayedMaskRef.current) {
-          if (displayMask !== 0) { lastActivityRef.current = now; }
+          if (displayMask !== 0n) { lastActivityRef.current = now; }
           
           // Schedule UI update on next frame
           if (!pendingFrameRef.current) {
@@ -344,7 +351,7 @@ export function ButtonConfiguration({ deviceStatus, isConnected = false, parsedB
       
       // Listen for periodic state sync events
       unlistenSync = await listen<ButtonStates>('button-state-sync', (event) => {
-        const { buttons } = event.payload;
+        const buttons = bitmapToMask(event.payload.buttons);
         // Debug: console.log(`[FRONTEND SYNC] State sync received: 0x${buttons.toString(16)} at ${timestamp}`);
         
         // Update state to match backend
@@ -357,8 +364,7 @@ export function ButtonConfiguration({ deviceStatus, isConnected = false, parsedB
         
         for (const [bit, time] of pressedHistoryRef.current) {
           if (time >= cutoffTime) {
-            const bitMask = bit < 32 ? (1 << bit) : Math.pow(2, bit);
-            displayMask |= bitMask;
+            displayMask |= buttonBit(bit);
           }
         }
         
@@ -384,15 +390,12 @@ export function ButtonConfiguration({ deviceStatus, isConnected = false, parsedB
   // Derived pressed set memoized (avoids repeated bit math in render for many buttons)
   const pressedSet = useMemo(() => {
     const set = new Set<number>();
-    if (buttonMask === 0) return set;
+    if (buttonMask === 0n) return set;
     
-    // Use proper bit manipulation for up to 53 bits
   const source = firmwareButtonsRef.current || parsedButtons;
   const maxId = Math.max(-1, ...source.map(b => b.id));
-    for (let bit = 0; bit <= maxId && bit < 53; bit++) {
-      // Use Math.pow for bits > 31 to avoid JS bitwise operator limitations
-      const bitValue = bit < 32 ? (1 << bit) : Math.pow(2, bit);
-      if ((buttonMask & bitValue) !== 0) {
+    for (let bit = 0; bit <= maxId && bit < 128; bit++) {
+      if ((buttonMask & buttonBit(bit)) !== 0n) {
         set.add(bit);
       }
     }
@@ -20,6 +20,7 @@ import type { StorageInfo } from '@/lib/types';
 import { useRawStateConfig } from '@/contexts/RawStateConfigContext';
 import { Switch } from '@/components/ui/switch';
 import { Label } from '@/components/ui/label';
+import { errorMessage } from '@/lib/utils';
 
 export function DeviceConfigManagement() {
   const { isConnected } = useDeviceContext();
@@ -111,7 +112,7 @@ export function DeviceConfigManagement() {
       toast.success(`Found ${files.length} files: ${files.join(', ')}`);
       console.log('Device files:', files);
     } catch (error) {
-      toast.error(`Failed to list files: ${error}`);
+      toast.error(`Failed to list files: ${errorMessage(error)}`);
       console.error('List files error:', error);
     }
   };
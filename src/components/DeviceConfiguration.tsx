@@ -235,12 +235,16 @@ export function DeviceConfiguration({
             <Badge variant="outline" className="text-xs px-2 py-0.5">
               FW {deviceStatus.firmware_version}
             </Badge>
-            <Badge variant="secondary" className="text-xs px-2 py-0.5">
-              {deviceStatus.axes_count} Axes
-            </Badge>
-            <Badge variant="secondary" className="text-xs px-2 py-0.5">
-              {deviceStatus.buttons_count} Buttons
-            </Badge>
+            {deviceStatus.counts_reported && (
+              <>
+                <Badge variant="secondary" className="text-xs px-2 py-0.5">
+                  {deviceStatus.axes_count} Axes
+                </Badge>
+                <Badge variant="secondary" className="text-xs px-2 py-0.5">
+                  {deviceStatus.buttons_count} Buttons
+                </Badge>
+              </>
+            )}
           </div>
         </div>
       </CardHeader>
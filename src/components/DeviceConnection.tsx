@@ -95,10 +95,12 @@ export function DeviceConnection() {
               {connectedDevice.device_status && (
                 <>
                   <div>Firmware: {connectedDevice.device_status.firmware_version}</div>
-                  <div>
-                    Axes: {connectedDevice.device_status.axes_count} | 
-                    Buttons: {connectedDevice.device_status.buttons_count}
-                  </div>
+                  {connectedDevice.device_status.counts_reported && (
+                    <div>
+                      Axes: {connectedDevice.device_status.axes_count} | 
+                      Buttons: {connectedDevice.device_status.buttons_count}
+                    </div>
+                  )}
                 </>
               )}
             </div>
@@ -167,16 +169,16 @@ export function DeviceConnection() {
                           {getDeviceStatusIcon(device)}
                           <div className="flex-1 min-w-0">
                             <div className="font-medium text-sm truncate">
-                              {device.product || 'JoyCore Device'}
+                              {device.alias || device.product || 'JoyCore Device'}
                             </div>
                             <div className="text-xs text-muted-foreground truncate">
-                              {device.port_name}
+                              {device.present ? device.port_name : `Not plugged in (last on ${device.port_name})`}
                             </div>
                           </div>
                         </div>
                         
                         <div className="flex items-center space-x-2">
-                          {!isDeviceConnected && (
+                          {!isDeviceConnected && device.present && (
                             <Button
                               size="sm"
                               variant="outline"
@@ -209,8 +211,12 @@ export function DeviceConnection() {
                           <Separator className="my-2" />
                           <div className="flex items-center space-x-4 text-xs text-muted-foreground">
                             <span>FW: {device.device_status.firmware_version}</span>
-                            <span>Axes: {device.device_status.axes_count}</span>
-                            <span>Buttons: {device.device_status.buttons_count}</span>
+                            {device.device_status.counts_reported && (
+                              <>
+                                <span>Axes: {device.device_status.axes_count}</span>
+                                <span>Buttons: {device.device_status.buttons_count}</span>
+                              </>
+                            )}
                           </div>
                         </>
                       )}
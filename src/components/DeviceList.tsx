@@ -266,7 +266,7 @@ export function DeviceList({ onCollapse, deviceCount, onRefresh, isLoading: isRe
                       )}
                       
                       {/* Buttons & Axes Row */}
-                      {device.device_status && (
+                      {device.device_status?.counts_reported && (
                         <div className="text-xs text-muted-foreground truncate select-none">
                           <span className="font-medium">Controls:</span> {device.device_status.axes_count}A, {device.device_status.buttons_count}B
                         </div>
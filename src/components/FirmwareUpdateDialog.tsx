@@ -2,6 +2,7 @@ import React, { useState, useEffect } from 'react';
 import { invoke } from '@tauri-apps/api/core';
 import { listen } from '@tauri-apps/api/event';
 import { Button } from './ui/button';
+import { errorMessage } from '@/lib/utils';
 
 interface FirmwareRelease {
   version: string;
@@ -10,6 +11,12 @@ interface FirmwareRelease {
   published_at: string;
   size_bytes: number;
   sha256_hash?: string;
+  prerelease?: boolean;
+  asset_name?: string;
+  checksum_url?: string;
+  signature_url?: string;
+  hardware_revision?: string | null; // board revision the asset targets; null = any board
+  delta_patches?: { from: string; asset_name: string; download_url: string; size_bytes: number }[];
 }
 
 interface VersionCheckResult {
@@ -82,7 +89,7 @@ export const FirmwareUpdateDialog: React.FC<FirmwareUpdateDialogProps> = ({
       
       setCheckResult(result);
     } catch (err) {
-      setError(`Failed to check for updates: ${err}`);
+      setError(`Failed to check for updates: ${errorMessage(err)}`);
     } finally {
       setIsChecking(false);
     }
@@ -99,17 +106,13 @@ export const FirmwareUpdateDialog: React.FC<FirmwareUpdateDialogProps> = ({
       const outputDir = await invoke<string>('path_download_dir') || 'downloads';
       
       const downloadedFilePath = await invoke<string>('download_firmware_update', {
-        downloadUrl: checkResult.release_info.download_url,
-        version: checkResult.release_info.version,
-        changelog: checkResult.release_info.changelog,
-        publishedAt: checkResult.release_info.published_at,
-        sizeBytes: checkResult.release_info.size_bytes,
+        release: checkResult.release_info,
         outputDir,
       });
 
       setDownloadedPath(downloadedFilePath);
     } catch (err) {
-      setError(`Failed to download firmware: ${err}`);
+      setError(`Failed to download firmware: ${errorMessage(err)}`);
     } finally {
       setIsDownloading(false);
       setDownloadProgress(null);
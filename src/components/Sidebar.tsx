@@ -163,7 +163,7 @@ export function Sidebar({ collapsed, onRefresh, isRefreshing, parsedAxes, parsed
                 <span className="font-medium">FW:</span> {device.device_status.firmware_version}
               </div>
             )}
-            {device.device_status && (
+            {device.device_status?.counts_reported && (
               <div className="text-xs text-muted-foreground truncate">
                 <span className="font-medium">Controls:</span> {device.device_status.axes_count}A, {device.device_status.buttons_count}B
               </div>
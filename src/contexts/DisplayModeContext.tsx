@@ -1,8 +1,9 @@
 import React, { createContext, useContext, useEffect, useState, useCallback } from 'react';
 import { invoke } from '@tauri-apps/api/core';
 import { RAW_STATE_CONFIG } from '@/lib/dev-config';
+import type { DisplayMode } from '@/lib/types';
 
-export type DisplayMode = 'hid' | 'raw' | 'both';
+export type { DisplayMode };
 
 interface DisplayModeContextValue {
   displayMode: DisplayMode;
@@ -12,54 +13,70 @@ interface DisplayModeContextValue {
 
 const DisplayModeContext = createContext<DisplayModeContextValue | undefined>(undefined);
 
-const LS_KEY = 'joycore.displayMode.v1';
+// Where the frontend kept the mode before the backend persisted it
+const LEGACY_LS_KEY = 'joycore.displayMode.v1';
 
-function loadDisplayMode(): DisplayMode {
+// A mode saved by an older version, removed once it has been handed to the backend
+function takeLegacyDisplayMode(): DisplayMode | null {
   try {
-    const saved = localStorage.getItem(LS_KEY);
+    const saved = localStorage.getItem(LEGACY_LS_KEY);
     if (saved && ['hid', 'raw', 'both'].includes(saved)) {
       return saved as DisplayMode;
     }
   } catch {
     // Ignore localStorage errors
   }
-  // Default from config or 'hid' if not set
-  return RAW_STATE_CONFIG.displayMode || 'hid';
+  return null;
 }
 
-function saveDisplayMode(mode: DisplayMode) {
-  try {
-    localStorage.setItem(LS_KEY, mode);
-  } catch {
-    // Ignore localStorage errors
-  }
+// Cycle through modes: hid -> raw -> both -> hid
+function nextDisplayMode(current: DisplayMode): DisplayMode {
+  if (current === 'hid') return 'raw';
+  if (current === 'raw') return 'both';
+  return 'hid';
 }
 
 export const DisplayModeProvider: React.FC<{ children: React.ReactNode }> = ({ children }) => {
-  const [displayMode, setDisplayModeState] = useState<DisplayMode>(loadDisplayMode);
+  // The backend persists the mode in AppSettings.display_mode; the config default only shows
+  // until it has answered
+  const [displayMode, setDisplayModeState] = useState<DisplayMode>(RAW_STATE_CONFIG.displayMode || 'hid');
 
-  // Persist + sync to backend whenever it changes
   useEffect(() => {
-    saveDisplayMode(displayMode);
-    // Fire and forget backend sync
-    invoke('set_raw_state_display_mode', { mode: displayMode }).catch(err => {
-      // Non-fatal; log to console for debugging
-      console.warn('Failed to set backend display mode', err);
-    });
-  }, [displayMode]);
+    let cancelled = false;
+    const legacy = takeLegacyDisplayMode();
+    const load = legacy
+      ? invoke<DisplayMode>('set_display_mode', { mode: legacy }).then(mode => {
+          try {
+            localStorage.removeItem(LEGACY_LS_KEY);
+          } catch {
+            // Ignore localStorage errors; importing again is harmless
+          }
+          return mode;
+        })
+      : invoke<DisplayMode>('get_display_mode');
+    load
+      .then(mode => {
+        if (!cancelled) setDisplayModeState(mode);
+      })
+      .catch(err => console.warn('Failed to load display mode', err));
+    return () => {
+      cancelled = true;
+    };
+  }, []);
 
   const setDisplayMode = useCallback((mode: DisplayMode) => {
     setDisplayModeState(mode);
+    invoke<DisplayMode>('set_display_mode', { mode })
+      .then(setDisplayModeState)
+      .catch(err => {
+        // Non-fatal; log to console for debugging
+        console.warn('Failed to set backend display mode', err);
+      });
   }, []);
 
   const toggleDisplayMode = useCallback(() => {
-    setDisplayModeState(current => {
-      // Cycle through modes: hid -> raw -> both -> hid
-      if (current === 'hid') return 'raw';
-      if (current === 'raw') return 'both';
-      return 'hid';
-    });
-  }, []);
+    setDisplayMode(nextDisplayMode(displayMode));
+  }, [displayMode, setDisplayMode]);
 
   return (
     <DisplayModeContext.Provider value={{
@@ -76,4 +93,4 @@ export function useDisplayMode() {
   const ctx = useContext(DisplayModeContext);
   if (!ctx) throw new Error('useDisplayMode must be used within DisplayModeProvider');
   return ctx;
-}
\ No newline at end of file
+}
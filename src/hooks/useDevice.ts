@@ -1,13 +1,18 @@
 import { useState, useEffect, useCallback, useRef } from 'react';
 import { invoke } from '@tauri-apps/api/core';
 import { listen } from '@tauri-apps/api/event';
-import type { Device, DeviceStatus, ConnectionInfo } from '@/lib/types';
+import type { Device, DeviceStatus, ConnectionInfo, DeviceListSnapshot, DeviceChangedEvent, DeviceRemovedEvent } from '@/lib/types';
+
+// Delay before a failed device list snapshot load is retried
+const SNAPSHOT_RETRY_MS = 2000;
 
 export function useDevice() {
   const [devices, setDevices] = useState<Device[]>([]);
   const devicesRef = useRef<Device[]>([]);
   const [connectedDevice, setConnectedDevice] = useState<Device | null>(null);
   const connectedRef = useRef<Device | null>(null);
+  // Sequence number of the last applied device list event; null until the first snapshot
+  const seqRef = useRef<number | null>(null);
   const [connectionInfo, setConnectionInfo] = useState<ConnectionInfo>({
     status: 'disconnected'
   });
@@ -122,24 +127,12 @@ export function useDevice() {
     const setup = async () => {
       try {
         setIsLoading(true);
-        // Initial snapshot
-        const [connected, list] = await Promise.all([
-          invoke<Device | null>('get_connected_device'),
-          invoke<Device[]>('get_devices')
-        ]);
-        if (connected) {
-          connectedRef.current = connected;
-          setConnectedDevice(connected);
-          setConnectionInfo(parseConnectionState(connected));
-        }
-        devicesRef.current = list;
-        setDevices(list);
-        // Event: device list updates
-        const un1 = await listen<Device[]>('device_list_updated', (e) => {
-          const updated = e.payload || [];
+        // Apply a change to the device list and keep the connected device reference in sync
+        const applyDevices = (change: (prev: Device[]) => Device[]) => {
           setDevices(prev => {
-            // Always compare with previous snapshot to avoid stale outer closure
-            if (devicesEqual(prev, updated)) return prev;
+            // Always derive from previous state to avoid stale outer closure
+            const updated = change(prev);
+            if (updated === prev) return prev;
             devicesRef.current = updated;
             // Sync connected device reference if present
             if (connectedRef.current) {
@@ -164,8 +157,79 @@ export function useDevice() {
             }
             return updated;
           });
-        });
-        unlistenList.push(un1);
+        };
+        const loadSnapshot = async () => {
+          const snapshot = await invoke<DeviceListSnapshot>('get_device_list_snapshot');
+          seqRef.current = Math.max(seqRef.current ?? 0, snapshot.seq);
+          applyDevices(prev => devicesEqual(prev, snapshot.devices) ? prev : snapshot.devices);
+        };
+        // A gap in the sequence means an event was missed: one snapshot reload runs at a time and
+        // events arriving meanwhile wait for it, then those newer than the snapshot are applied.
+        // The initial load below counts as a reload, so events from before it are kept too.
+        let reloading = true;
+        let buffered: Array<{ seq: number; apply: () => void }> = [];
+        let retryTimer: ReturnType<typeof setTimeout> | undefined;
+        unlistenList.push(() => clearTimeout(retryTimer));
+        // A failed load keeps the buffered events and tries again
+        const loadAndFlush = async () => {
+          try {
+            await loadSnapshot();
+          } catch (err) {
+            console.warn('Device list reload failed; retrying:', err);
+            retryTimer = setTimeout(() => { loadAndFlush().catch(() => { /* retried */ }); }, SNAPSHOT_RETRY_MS);
+            throw err;
+          }
+          reloading = false;
+          const pending = buffered.sort((a, b) => a.seq - b.seq);
+          buffered = [];
+          pending.forEach(({ seq, apply }) => handleEvent(seq, apply));
+        };
+        const reloadSnapshot = () => {
+          if (reloading) return;
+          reloading = true;
+          loadAndFlush().catch(() => { /* retried */ });
+        };
+        // Events already covered by the snapshot are dropped
+        const handleEvent = (seq: number, apply: () => void) => {
+          if (reloading || seqRef.current === null) {
+            buffered.push({ seq, apply });
+            return;
+          }
+          if (seq <= seqRef.current) return;
+          if (seq !== seqRef.current + 1) {
+            console.warn(`Device event ${seq} after ${seqRef.current}; reloading device list`);
+            buffered.push({ seq, apply });
+            reloadSnapshot();
+            return;
+          }
+          seqRef.current = seq;
+          apply();
+        };
+        // Events: device list deltas (subscribed before the snapshot so none fall in between)
+        const onChanged = (e: { payload: DeviceChangedEvent }) => {
+          const { seq, device } = e.payload;
+          handleEvent(seq, () => applyDevices(prev => prev.some(d => d.id === device.id)
+            ? prev.map(d => d.id === device.id ? device : d)
+            : [...prev, device]));
+        };
+        unlistenList.push(await listen<DeviceChangedEvent>('device_added', onChanged));
+        unlistenList.push(await listen<DeviceChangedEvent>('device_updated', onChanged));
+        unlistenList.push(await listen<DeviceRemovedEvent>('device_removed', (e) => {
+          const { seq, id } = e.payload;
+          handleEvent(seq, () => applyDevices(prev => prev.some(d => d.id === id) ? prev.filter(d => d.id !== id) : prev));
+        }));
+        // Initial snapshot
+        const connected = await invoke<Device | null>('get_connected_device');
+        if (connected) {
+          connectedRef.current = connected;
+          setConnectedDevice(connected);
+          setConnectionInfo(parseConnectionState(connected));
+        }
+        try {
+          await loadAndFlush();
+        } catch (err) {
+          setError(err instanceof Error ? err.message : 'Failed to load devices');
+        }
         // Event: connection state changes
         interface ConnEvt { id: string; state: string; error?: string }
   const un2 = await listen<ConnEvt>('device_connection_changed', (e) => {
@@ -1,5 +1,6 @@
 import { useState, useEffect, useCallback } from 'react';
 import { invoke } from '@tauri-apps/api/core';
+import { errorMessage } from '@/lib/utils';
 
 interface FirmwareRelease {
   version: string;
@@ -58,7 +59,7 @@ export const useFirmwareUpdates = ({
       setLastCheckTime(new Date());
       return result;
     } catch (err) {
-      const errorMsg = `Failed to check for updates: ${err}`;
+      const errorMsg = `Failed to check for updates: ${errorMessage(err)}`;
       setError(errorMsg);
       console.error('Firmware update check failed:', err);
       return null;
@@ -8,6 +8,7 @@ import {
   type ShiftRegisterState 
 } from '@/lib/dev-config';
 import { useDisplayMode } from '@/contexts/DisplayModeContext';
+import { errorMessage } from '@/lib/utils';
 
 /**
  * Hook for reading raw hardware pin states from the connected device
@@ -33,6 +34,7 @@ export function useRawPinState() {
     unsubscribeGpio: () => void;
     unsubscribeMatrix: () => void;
     unsubscribeShift: () => void;
+    subscriptionToken: number | null;
   };
   type WindowWithRawState = Window & {
     __rawStateCleanup?: CleanupFns;
@@ -133,6 +135,14 @@ export function useRawPinState() {
         }
       });
 
+      // The backend only emits raw event kinds that have a subscriber
+      let subscriptionToken: number | null = null;
+      try {
+        subscriptionToken = await invoke<number>('subscribe_raw_events', { kinds: ['gpio', 'matrix', 'shift'] });
+      } catch (subErr) {
+        console.warn('Failed to subscribe to raw events:', subErr);
+      }
+
       // Start firmware monitoring - it might already be running
       try {
         await invoke('start_raw_state_monitoring');
@@ -149,11 +159,12 @@ export function useRawPinState() {
         unsubscribeGpio,
         unsubscribeMatrix,
         unsubscribeShift,
+        subscriptionToken,
       };
 
     } catch (err) {
       console.error('Failed to start monitoring:', err);
-      setError(`Failed to start monitoring: ${err}`);
+      setError(`Failed to start monitoring: ${errorMessage(err)}`);
     }
   }, []);
 
@@ -169,11 +180,14 @@ export function useRawPinState() {
         cleanup.unsubscribeGpio();
         cleanup.unsubscribeMatrix();
         cleanup.unsubscribeShift();
+        if (cleanup.subscriptionToken !== null) {
+          invoke('unsubscribe_raw_events', { token: cleanup.subscriptionToken }).catch(() => {});
+        }
         delete (window as unknown as WindowWithRawState).__rawStateCleanup;
       }
     } catch (err) {
       console.error('Failed to stop monitoring:', err);
-      setError(`Failed to stop monitoring: ${err}`);
+      setError(`Failed to stop monitoring: ${errorMessage(err)}`);
     }
   }, []);
 
@@ -232,7 +246,7 @@ export function useRawPinState() {
       return await invoke<RawGpioStates>('read_raw_gpio_states');
     } catch (err) {
       console.error('Failed to read GPIO states:', err);
-      setError(`Failed to read GPIO states: ${err}`);
+      setError(`Failed to read GPIO states: ${errorMessage(err)}`);
       return null;
     }
   };
@@ -242,7 +256,7 @@ export function useRawPinState() {
       return await invoke<MatrixState>('read_raw_matrix_state');
     } catch (err) {
       console.error('Failed to read matrix state:', err);
-      setError(`Failed to read matrix state: ${err}`);
+      setError(`Failed to read matrix state: ${errorMessage(err)}`);
       return null;
     }
   };
@@ -252,7 +266,7 @@ export function useRawPinState() {
       return await invoke<ShiftRegisterState[]>('read_raw_shift_reg_state');
     } catch (err) {
       console.error('Failed to read shift register state:', err);
-      setError(`Failed to read shift register state: ${err}`);
+      setError(`Failed to read shift register state: ${errorMessage(err)}`);
       return null;
     }
   };
@@ -26,6 +26,7 @@ export const RAW_STATE_CONFIG = {
 export interface RawGpioStates {
   gpio_mask: number;
   timestamp: number;
+  host_time?: string; // ISO timestamp of `timestamp`, set while the device clock is synchronized
 }
 
 export interface MatrixConnection {
@@ -37,16 +38,43 @@ export interface MatrixConnection {
 export interface MatrixState {
   connections: MatrixConnection[];
   timestamp: number;
+  host_time?: string;
 }
 
 export interface ShiftRegisterState {
   register_id: number;
   value: number;
   timestamp: number;
+  host_time?: string;
 }
 
 export interface RawHardwareState {
   gpio?: RawGpioStates;
   matrix?: MatrixState;
   shift_registers: ShiftRegisterState[];
+}
+
+/** Result of `get_raw_state_snapshot` */
+export interface RawStateSnapshotInfo {
+  device_id: string;
+  /** Monitor updates applied so far; 0 until the first monitor line */
+  seq: number;
+  last_update_us: number;
+  state: RawHardwareState;
+}
+
+export type RawTransition =
+  | { type: 'gpio'; timestamp: number; gpio_mask: number }
+  | { type: 'matrix'; timestamp: number; row: number; col: number; is_connected: boolean }
+  | { type: 'shift'; timestamp: number; register_id: number; value: number };
+
+/** Result of `get_raw_state_history`; timestamps are firmware µs since boot */
+export interface RawStateHistory {
+  since_us: number;
+  latest_us: number;
+  /** Transitions before `since_us` were already dropped */
+  truncated: boolean;
+  /** Level of every known signal at `since_us` */
+  initial: RawHardwareState;
+  transitions: RawTransition[];
 }
\ No newline at end of file
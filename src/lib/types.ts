@@ -21,19 +21,80 @@ export interface Device {
   serial_number?: string;
   manufacturer?: string;
   product?: string;
+  alias?: string; // user-chosen name, by serial number
+  present: boolean; // false for a known device that is not plugged in
   connection_state: ConnectionState;
   device_status?: DeviceStatus;
+  capabilities?: FirmwareCapabilities;
   last_seen: string; // ISO timestamp
 }
 
+// get_device_list_snapshot; the next device_added/updated/removed event has seq + 1
+export interface DeviceListSnapshot {
+  seq: number;
+  devices: Device[];
+}
+
+// Payload of device_added and device_updated
+export interface DeviceChangedEvent {
+  seq: number;
+  device: Device;
+}
+
+// Payload of device_removed
+export interface DeviceRemovedEvent {
+  seq: number;
+  id: string;
+}
+
+export interface FirmwareCapabilities {
+  firmware_version: string;
+  source: "reported" | "heuristic";
+  write_file: boolean;
+  delete_file: boolean;
+  raw_monitor: boolean;
+  hid_mapping: boolean;
+  storage_info: boolean;
+  bootloader: boolean;
+  framed_files: boolean;
+  test_config: boolean;
+  shift_layers: boolean;
+  hats: boolean;
+  validate_config: boolean;
+  reboot: boolean;
+  other: string[];
+}
+
+export interface ClockModel {
+  device_ref_us: number;
+  host_ref_us: number;
+  skew_ppm: number;
+  uncertainty_us: number;
+  samples: number;
+}
+
 export interface DeviceStatus {
   firmware_version: string;
   device_name: string;
   axes_count: number;
   buttons_count: number;
   connected: boolean;
+  /** Board revision reported by STATUS or IDENTIFY (`rev2` -> `2`) */
+  hardware_revision?: string | null;
+  /** Counts come from STATUS; otherwise they are the protocol limits (8 axes, 64 buttons) */
+  counts_reported?: boolean;
+  /** Logical inputs in the loaded config */
+  input_count?: number | null;
+  uptime_ms?: number | null;
+  storage_ok?: boolean | null;
+  /** False when the firmware runs its built-in defaults */
+  config_loaded?: boolean | null;
+  config_version?: number | null;
+  usb_state?: UsbState | null;
 }
 
+export type UsbState = 'mounted' | 'suspended' | 'unmounted';
+
 export interface AxisConfig {
   id: number;
   name: string;
@@ -62,9 +123,132 @@ export interface ProfileConfig {
   modified_at: string; // ISO timestamp
 }
 
+export type MacroMode = 'once' | 'while_held' | 'toggle';
+
+export type MacroStep =
+  | { type: 'key_press'; key: string }
+  | { type: 'key_release'; key: string }
+  | { type: 'key_tap'; key: string; hold_ms: number }
+  | { type: 'delay'; ms: number };
+
+export interface MacroDefinition {
+  id: string;
+  name: string;
+  description: string;
+  mode: MacroMode;
+  trigger_button?: number;
+  steps: MacroStep[];
+  created_at: string; // ISO timestamp
+  modified_at: string; // ISO timestamp
+}
+
 export interface ProfileManager {
   profiles: ProfileConfig[];
   active_profile_id?: string;
+  macros: MacroDefinition[];
+}
+
+export interface FirmwareUpdateSettings {
+  auto_check: boolean;
+  check_interval_hours: number;
+  repo_owner: string;
+  repo_name: string;
+  download_directory: string;
+  verify_signatures: boolean;
+  last_check?: string; // ISO timestamp
+  channel: UpdateChannel;
+  proxy_url?: string;
+  // The GitHub token is write-only: set_github_token / has_github_token
+  signature_public_key?: string;
+}
+
+export type UpdateChannel = 'stable' | 'beta' | 'nightly';
+
+export interface CachedFirmware {
+  version: string;
+  path: string;
+  size_bytes: number;
+  cached_at?: string;
+  sha256: string;
+  signature_path?: string;
+}
+
+export type SignatureStatus = 'valid' | 'unsigned' | 'no_public_key' | 'invalid';
+
+export interface SignatureVerification {
+  status: SignatureStatus;
+  signature_path?: string;
+  trusted_comment?: string;
+  error?: string;
+}
+
+export interface DownloadResumed {
+  version: string;
+  resumed_from_bytes: number;
+  total_bytes: number;
+}
+
+export interface DownloadCancelled {
+  version: string;
+  downloaded_bytes: number;
+}
+
+export interface AppSettings {
+  auto_connect: boolean;
+  auto_reconnect: boolean;
+  auto_save: boolean;
+  log_level: string;
+  theme: 'light' | 'dark' | 'system';
+  language: string;
+  update_rate_ms: number;
+  firmware_update: FirmwareUpdateSettings;
+  telemetry: TelemetrySettings;
+  raw_monitor: RawMonitorSettings;
+  discovery: DiscoverySettings;
+  display_mode: DisplayMode; // restored at startup
+}
+
+export type DisplayMode = 'hid' | 'raw' | 'both';
+
+export interface DiscoverySettings {
+  usb_filter: boolean; // only probe USB ports with the JoyCore VID or an extra id
+  extra_usb_ids: string[]; // "VID:PID" or "VID", hex
+  ignored_ports: string[]; // never opened, e.g. "COM5" or "/dev/ttyACM1"
+}
+
+export interface TelemetrySettings {
+  enabled: boolean; // serve ws://127.0.0.1:<port> from startup
+  port: number;
+  allowed_origins: string[]; // browser origins allowed to connect; native clients always are
+}
+
+export interface RawMonitorSettings {
+  max_event_rate_hz: number; // cap on raw-* event batches per second, 0 = unlimited
+  batch_ms: number; // coalescing window, 0 = emit every line
+  debug: boolean;
+}
+
+export type RawEventKind = 'gpio' | 'matrix' | 'shift';
+
+export type RawInputSource =
+  | { type: 'gpio'; pin: number }
+  | { type: 'matrix'; row: number; col: number }
+  | { type: 'shift'; register_id: number; bit: number };
+
+/** Activity of one physical input; GPIO and shift bits count low as active */
+export type InputActivity = RawInputSource & {
+  activations: number;
+  changes: number;
+  active: boolean;
+  last_activity?: string;
+  last_activity_us?: number; // firmware time of the last change
+};
+
+/** Result of `get_input_activity_stats` */
+export interface InputActivityStats {
+  since: string;
+  total_activations: number;
+  inputs: InputActivity[];
 }
 
 // Utility types for connection states
@@ -131,6 +315,232 @@ export interface ParsedButtonConfig {
   enabled: boolean;
 }
 
+// Rotary encoder (ENC_A/ENC_B logical input pair)
+export interface UIEncoderConfig {
+  id: number;
+  name: string;
+  input_type: string; // 'pin' | 'matrix' | 'shift_reg'
+  a_data: [number, number];
+  b_data: [number, number];
+  cw_button: number;
+  ccw_button: number;
+  latch_mode: string; // 'four3' | 'four0' | 'two03' or a numeric code
+  reversed: boolean;
+}
+
+// Hat switch (four hat_up/hat_right/hat_down/hat_left logical inputs)
+export interface UIHatConfig {
+  id: number; // hat number in the HID report
+  name?: string; // read-only
+  mode: 'eight_way' | 'four_way';
+  input_type: 'pin' | 'matrix';
+  up: [number, number]; // pin: [gpio, 0]; matrix: [row, col]
+  right: [number, number];
+  down: [number, number];
+  left: [number, number];
+}
+
+// Axis response curves (normalized: x = axis position, y = output, both 0..1)
+export interface CurvePoint {
+  x: number;
+  y: number;
+}
+
+export interface AxisCurve {
+  points: CurvePoint[]; // ascending x from 0 to 1
+}
+
+// Firmware preset a custom curve is stored as; errors are relative to a model of the presets,
+// not the firmware's actual tables
+export interface PresetMatch {
+  curve: string;
+  code: number;
+  max_error: number;
+  close_match: boolean;
+}
+
+export interface AxisCurvePreview {
+  points: CurvePoint[];
+  preset: PresetMatch;
+}
+
+export interface CurvePreset {
+  name: string;
+  code: number;
+  curve: AxisCurve;
+}
+
+// Button matrix
+export interface MatrixCell {
+  row: number;
+  col: number;
+  joy_button_id: number;
+  behavior: string; // 'normal' | 'momentary' | 'encoder_a' | 'encoder_b'
+  reverse: boolean;
+}
+
+export interface MatrixConfig {
+  row_pins: number[];
+  col_pins: number[];
+  cells: MatrixCell[];
+}
+
+// Config validation diagnostics
+export type ConfigDiagnosticCode =
+  | 'PIN_CONFLICT'
+  | 'DUPLICATE_PIN'
+  | 'PIN_OUT_OF_RANGE'
+  | 'AXIS_PIN_NOT_ANALOG'
+  | 'INVALID_PIN_NAME'
+  | 'INCOMPLETE_MATRIX'
+  | 'MATRIX_CELL_OUT_OF_RANGE'
+  | 'INCOMPLETE_SHIFT_REGISTER'
+  | 'SHIFT_REGISTER_INPUT_OUT_OF_RANGE'
+  | 'UNMAPPED_BUTTON_PIN'
+  | 'DUPLICATE_BUTTON'
+  | 'UNPAIRED_ENCODER'
+  | 'INCOMPLETE_HAT'
+  | 'COUNT_MISMATCH'
+  | 'INVALID_BINARY'
+  | 'UNSUPPORTED_FEATURE'
+  | 'FIRMWARE_CHECK';
+
+export interface ConfigDiagnostic {
+  severity: 'error' | 'warning';
+  code: ConfigDiagnosticCode;
+  message: string;
+  pins: number[];
+  logical_input?: number;
+}
+
+export type ValidationSource = 'firmware' | 'host';
+
+export interface ConfigValidationReport {
+  source: ValidationSource;
+  valid: boolean;
+  diagnostics: ConfigDiagnostic[];
+}
+
+export interface ConfigCheck {
+  name: 'length' | 'magic' | 'version' | 'counts' | 'size' | 'checksum';
+  passed: boolean;
+  detail: string;
+}
+
+// Result of diagnose_config_binary; `recovered` is the config in its JSON export form
+export interface ConfigBinaryDiagnosis {
+  valid: boolean;
+  length: number;
+  checks: ConfigCheck[];
+  notes: string[];
+  recovered?: Record<string, unknown>;
+  diagnostics: ConfigDiagnostic[];
+  repaired?: number[];
+  repair_error?: string;
+}
+
+export interface UsbDescriptorConfig {
+  vid: number;
+  pid: number;
+  manufacturer: string;
+  product: string;
+}
+
+// Config diff (values use the JSON export form, e.g. curve names and hex VID/PID)
+export interface FieldChange {
+  field: string;
+  old: unknown;
+  new: unknown;
+}
+
+export interface ConfigEntryDiff {
+  key: string;
+  changes: FieldChange[];
+}
+
+export interface ConfigListDiff<T> {
+  added: T[];
+  removed: T[];
+  changed: ConfigEntryDiff[];
+}
+
+export interface ConfigDiff {
+  identical: boolean;
+  usb: FieldChange[];
+  general: FieldChange[];
+  axes: { index: number; changes: FieldChange[] }[];
+  pins: ConfigListDiff<{ name: string; pin_type: string }>;
+  inputs: ConfigListDiff<LogicalInputJson>;
+}
+
+// Config entries in their JSON export form (see update_axis_in_config / update_logical_input_in_config)
+export interface AxisJson {
+  index: number;
+  enabled: boolean;
+  pin: number;
+  min_value: number;
+  max_value: number;
+  filter_level: number;
+  ewma_alpha: number;
+  deadband: number;
+  curve: string;
+  inverted: boolean;
+}
+
+export interface LogicalInputJson {
+  input_type: string;
+  behavior: string;
+  joy_button_id: number;
+  reverse: boolean;
+  encoder_latch_mode: number;
+  data: [number, number];
+  shifted?: boolean;
+}
+
+// Payload of the device_config_changed event
+export interface DeviceConfigChangedEvent {
+  device_id: string | null;
+  diff: ConfigDiff;
+}
+
+// list_config_templates; apply_config_template(id) returns a ConfigDiff
+export interface ConfigTemplateInfo {
+  id: string;
+  name: string;
+  description: string;
+  axes: number;
+  buttons: number;
+  encoders: number;
+  config_version: number;
+}
+
+// remap_logical_buttons(mapping: [from, to][], conflict?) result
+export type RemapConflict = 'swap' | 'shift';
+
+export interface RemapSummary {
+  reassigned: { input_index: number; source: string; from: number; to: number }[];
+  layout: { joy_button_id: number; inputs: number[] }[];
+  button_count: number;
+  free_buttons: number[];
+}
+
+// export_mapping: 'gremlin' writes a Joystick Gremlin profile (XML), 'json' a MappingExport
+export type MappingExportFormat = 'gremlin' | 'json';
+
+export interface MappingExport {
+  schema: 'joycore-mapping';
+  schema_version: number;
+  device: { name: string; manufacturer: string; vid: string; pid: string };
+  buttons: { button: number; logical_id: number; source: string; behavior: string; name: string }[];
+  axes: { axis: number; usage: string; pin: number; name: string }[];
+}
+
+export interface ProfileApplyResult {
+  profile_id: string;
+  diff: ConfigDiff;
+  warnings: string[];
+}
+
 // Pin configuration types for RP2040 Pico pinout
 export type PinFunction = 
   | 'PIN_UNUSED'
@@ -190,4 +600,430 @@ export interface PinConfiguration {
 export interface PinoutState {
   pins: Record<number, PinConfiguration>; // keyed by physical pin number
   lastModified?: Date;
-}
\ No newline at end of file
+}
+// Serial protocol trace
+export interface TraceEntry {
+  timestamp: string; // ISO timestamp
+  direction: 'tx' | 'rx';
+  line: string;
+}
+
+export interface ProtocolTrace {
+  enabled: boolean;
+  file_path?: string;
+  total_entries: number;
+  write_error?: string;
+  entries: TraceEntry[];
+}
+
+export interface ConfigBackupInfo {
+  id: string;
+  device_serial: string;
+  reason: 'read' | 'write';
+  created_at: string;
+  size_bytes: number;
+  crc32: number;
+}
+
+export interface KnownDevice {
+  id: string;
+  serial_number: string;
+  manufacturer?: string;
+  product?: string;
+  last_port: string;
+  last_firmware_version?: string;
+  last_backup_id?: string;
+  last_backup_at?: string;
+  first_seen: string;
+  last_seen: string;
+  /** Omitted when all settings are defaults */
+  connection?: ConnectionSettings;
+}
+
+/** Per-device serial overrides; unset fields use the stock firmware values */
+export interface ConnectionSettings {
+  baud_rate?: number;
+  identify_timeout_ms?: number;
+  /** Multiplies every command timeout (0.5-10) */
+  command_timeout_factor?: number;
+}
+
+// Error returned by every Tauri command (see src-tauri/src/error.rs)
+export type AppErrorCode =
+  | "NOT_FOUND"
+  | "NOT_CONNECTED"
+  | "ALREADY_CONNECTED"
+  | "CONNECTION_FAILED"
+  | "TIMEOUT"
+  | "PROTOCOL"
+  | "UNSUPPORTED"
+  | "INVALID_ARGUMENT"
+  | "INVALID_CONFIGURATION"
+  | "PARSE"
+  | "IO"
+  | "SERIAL"
+  | "HID"
+  | "NETWORK"
+  | "RATE_LIMITED"
+  | "NO_UPDATE_AVAILABLE"
+  | "INVALID_SIGNATURE"
+  | "UNSIGNED_FIRMWARE"
+  | "DOWNLOAD_INTERRUPTED"
+  | "CANCELLED"
+  | "UPDATE"
+  | "FLASH"
+  | "BUSY"
+  | "INVALID_STATE"
+  | "INTERNAL";
+
+export interface AppError {
+  code: AppErrorCode;
+  message: string;
+  context?: string;
+}
+
+export interface VirtualBridgeOptions {
+  device_index?: number; // vJoy device 1-16
+  button_map?: Record<number, number>;
+  axis_map?: Record<number, number>;
+  inverted_axes?: number[];
+}
+
+export interface VirtualBridgeStatus {
+  running: boolean;
+  backend?: string;
+  started_at?: string;
+  button_updates: number;
+  axis_updates: number;
+  dropped_updates: number;
+  last_error?: string;
+  shift_layer?: ShiftLayer | null; // emulated layer, see set_shift_layer
+}
+
+// Shift ("pinky") layer: while modifier_button is held, base buttons report their shifted button
+export interface ShiftLayer {
+  modifier_button: number;
+  assignments: { base_button: number; shifted_button: number }[];
+}
+
+// get_shift_layer / set_shift_layer: 'firmware' stores the layer in the config, 'bridge' emulates it
+export interface ShiftLayerState {
+  mode: 'firmware' | 'bridge';
+  layer: ShiftLayer | null;
+  button_count: number;
+  free_buttons: number[];
+}
+
+export interface ChatterOptions {
+  threshold_ms?: number; // transitions closer than this count as bounces
+  duration_ms?: number;
+  buttons?: number[]; // empty = all
+}
+
+export interface ButtonChatterStats {
+  button_id: number;
+  presses: number;
+  releases: number;
+  bounces: number;
+  short_presses: number;
+  quick_represses: number;
+  min_interval_ms?: number;
+  bounce_rate: number;
+}
+
+export interface ChatterReport {
+  running: boolean;
+  started_at?: string;
+  elapsed_ms: number;
+  threshold_ms: number;
+  duration_ms: number;
+  total_bounces: number;
+  buttons: ButtonChatterStats[];
+}
+
+export interface GhostDetectorOptions {
+  gpio_pull_up?: boolean;
+  shift_reg_pull_up?: boolean;
+  grace_ms?: number; // how long raw and HID may disagree before reporting
+}
+
+export type InputSource =
+  | { type: 'pin'; pin: number }
+  | { type: 'matrix'; row: number; col: number }
+  | { type: 'shift_reg'; register: number; bit: number };
+
+// Payload of the `mapping_anomaly` event
+export interface MappingAnomaly {
+  kind: 'logical_without_raw' | 'raw_without_logical';
+  button_id: number;
+  sources: InputSource[];
+  duration_ms: number;
+  detected_at: string;
+}
+
+export interface GhostDetectorStatus {
+  running: boolean;
+  watched_buttons: number;
+  anomaly_count: number;
+  recent: MappingAnomaly[];
+}
+
+export interface EncoderTestOptions {
+  rest_ms?: number; // time without a change after which the knob rests in a detent
+}
+
+export interface EncoderTestReport {
+  running: boolean;
+  encoder_id: number;
+  started_at: string;
+  elapsed_ms: number;
+  direction: 'clockwise' | 'counter_clockwise' | 'none';
+  steps: number; // net quadrature steps, positive is clockwise
+  transitions: number;
+  invalid_transitions: number; // both channels changed at once: a lost step
+  rest_states: [number, number, number, number]; // rests per quadrature state (A | B << 1)
+  detents_cw: number; // emitted with the configured latch mode
+  detents_ccw: number;
+  expected_detents: number;
+  missed_detents: number;
+  configured_latch_mode: string;
+  suggested_latch_mode?: string;
+  latch_mode_mismatch: boolean;
+}
+
+export interface LatencyStats {
+  samples: number;
+  avg_ms: number;
+  p95_ms: number;
+  max_ms: number;
+  jitter_ms: number;
+}
+
+export interface LatencyReport {
+  duration_ms: number;
+  raw_monitor?: LatencyStats; // latency above the fastest raw line of the run
+  hid_frames?: { reports: number; missed_frames: number; interval?: LatencyStats };
+  notes: string[];
+}
+
+export interface AxisFilterSettings {
+  filter_level: number;
+  ewma_alpha: number; // fixed point, 65536 = 1.0
+  deadband: number;
+}
+
+// Decimated samples; `before`/`after` apply the current/recommended settings to `captured`
+export interface AxisTrace {
+  t_ms: number[];
+  captured: number[];
+  before: number[];
+  after: number[];
+}
+
+// Figures are estimates from an approximate model of the firmware filter (see filter_model)
+export interface AxisTuningReport {
+  axis_id: number;
+  filter_model: string;
+  sample_interval_ms: number;
+  rest_noise: { samples: number; mean: number; std_dev: number; peak_to_peak: number };
+  sweep_speed: number; // HID units per second
+  sweep_span: number;
+  current: AxisFilterSettings;
+  recommended: AxisFilterSettings;
+  current_lag_ms: number;
+  recommended_lag_ms: number;
+  rest_trace: AxisTrace;
+  sweep_trace: AxisTrace;
+  notes: string[];
+}
+
+// Payload of the `axis_tuning_phase` event
+export interface AxisTuningPhase {
+  axis_id: number;
+  phase: 'rest' | 'sweep';
+  duration_ms: number;
+}
+
+export interface TelemetryStatus {
+  running: boolean;
+  address?: string;
+  port?: number;
+  allowed_origins: string[];
+  clients: number;
+  events_published: number;
+  started_at?: string;
+}
+
+export interface SupportBundleInfo {
+  path: string;
+  entries: string[];
+  bytes: number;
+  notes: string[];
+}
+
+export type PermissionIssueKind =
+  | 'missing_udev_rules'
+  | 'device_node_access'
+  | 'group_login_required'
+  | 'input_monitoring_denied'
+  | 'input_monitoring_not_determined';
+
+export interface PermissionIssue {
+  kind: PermissionIssueKind;
+  message: string;
+  remediation: string;
+  /** Shell commands that fix the issue, in order */
+  commands: string[];
+  path?: string;
+}
+
+/** Result of `check_system_permissions` */
+export interface PermissionReport {
+  platform: string;
+  ok: boolean;
+  checked: string[];
+  issues: PermissionIssue[];
+}
+
+export interface UdevRulesFile {
+  path: string;
+  install_commands: string[];
+}
+
+export type LogLevel = 'off' | 'error' | 'warn' | 'info' | 'debug' | 'trace';
+
+export interface LogLine {
+  timestamp: string;
+  level: Exclude<LogLevel, 'off'>;
+  target: string;
+  message: string;
+}
+
+/** Options of the `log-line` event, which carries a LogLine */
+export interface LogStreamOptions {
+  enabled: boolean;
+  level: LogLevel;
+  max_lines_per_sec: number;
+}
+
+/** Payload of the `backend_panic` event; the report is a JSON file in the crashes log folder */
+export interface BackendPanic {
+  thread: string;
+  message: string;
+  location?: string;
+  report_path?: string;
+}
+
+/** Payload of the `hid-connection-changed` event */
+export interface HidConnectionEvent {
+  connected: boolean;
+  interface?: number;
+  /** Why the connection ended; absent for a requested disconnect */
+  reason?: string;
+  timestamp: string;
+}
+
+export type HatPosition =
+  | 'centered'
+  | 'up'
+  | 'up_right'
+  | 'right'
+  | 'down_right'
+  | 'down'
+  | 'down_left'
+  | 'left'
+  | 'up_left';
+
+/** Payload of the `hat-changed` event */
+export interface HatEvent {
+  hat_id: number;
+  position: HatPosition;
+  /** Degrees clockwise from up; null when centered */
+  angle: number | null;
+  timestamp: string;
+}
+
+export interface HidLinkStats {
+  /** Reports carrying a frame counter; 0 when the firmware reports none */
+  frames: number;
+  dropped_frames: number;
+  gaps: number;
+  repeated_frames: number;
+  counter_resets: number;
+  stalls: number;
+  longest_stall_ms: number;
+  drop_rate: number;
+  last_counter?: number;
+  since?: string;
+}
+
+export type HidControl = 'identify' | 'test_mode';
+
+/** Controls accepted over the HID control feature report (`get_hid_control_support`) */
+export interface HidControlSupport {
+  /** Control report version; null when the firmware has none */
+  version: number | null;
+  controls: HidControl[];
+}
+
+/** Payload of the `hid-link-warning` event */
+export interface HidLinkWarning {
+  drop_rate: number;
+  threshold: number;
+  dropped_frames: number;
+  frames: number;
+  window_ms: number;
+  timestamp: string;
+}
+
+export type SelfTestStatus = 'skipped' | 'pass' | 'warn' | 'fail';
+
+export interface SelfTestCheck {
+  /** status, config_read, config_crc, hid_mapping, monitor_stream, storage, serial_link, hid_link */
+  name: string;
+  status: SelfTestStatus;
+  detail: string;
+  duration_ms: number;
+}
+
+/** Result of `run_device_self_test` */
+export interface SelfTestReport {
+  /** Worst status of the checks that ran */
+  overall: SelfTestStatus;
+  checks: SelfTestCheck[];
+  started_at: string;
+  duration_ms: number;
+}
+
+/** Why a connected device needs setup */
+export type SetupIssue =
+  | { reason: 'missing_config' }
+  | { reason: 'default_config' }
+  | { reason: 'config_version_mismatch'; device_version: number; expected_version: number }
+  | { reason: 'invalid_config'; error: string }
+  | { reason: 'no_logical_inputs' };
+
+/** Payload of the `device_setup_required` event, emitted after connect */
+export interface DeviceSetupRequired {
+  device_id: string;
+  firmware_version?: string | null;
+  reasons: SetupIssue[];
+}
+
+/** Config previewed from device RAM (`config_preview_changed` carries this or null) */
+export interface ConfigPreview {
+  device_id: string;
+  started_at: string;
+  size_bytes: number;
+}
+
+export type TransferDirection = 'read' | 'write';
+
+/** Payload of the `config_transfer_progress` event during config file reads and uploads */
+export interface ConfigTransferProgress {
+  direction: TransferDirection;
+  file: string;
+  bytes: number;
+  /** Expected size; an estimate while a read is arriving */
+  total: number;
+}
@@ -1,5 +1,6 @@
 import { clsx, type ClassValue } from "clsx"
 import { twMerge } from "tailwind-merge"
+import type { AppError } from "@/lib/types"
 
 export function cn(...inputs: ClassValue[]) {
   return twMerge(clsx(inputs))
@@ -19,3 +20,14 @@ export type ButtonFunction = keyof typeof ButtonFunctionLabels;
 export function getButtonFunctionLabel(func: string): string {
   return ButtonFunctionLabels[func as ButtonFunction] || 'Unknown';
 }
+
+export function isAppError(err: unknown): err is AppError {
+  return typeof err === 'object' && err !== null && 'code' in err && 'message' in err;
+}
+
+// Readable message for anything thrown by invoke()
+export function errorMessage(err: unknown): string {
+  if (isAppError(err)) return err.message;
+  if (err instanceof Error) return err.message;
+  return String(err);
+}